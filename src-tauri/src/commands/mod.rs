@@ -51,6 +51,34 @@ pub async fn add_account(
     Ok(account)
 }
 
+/// [NEW] 从 CSV / JSONL 批量导入账号 (dry_run 只验证不写入)
+#[tauri::command]
+pub async fn bulk_import_accounts(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    content: String,
+    format: Option<modules::account_import::ImportFormat>,
+    dry_run: Option<bool>,
+    concurrency: Option<usize>,
+) -> Result<modules::account_import::BulkImportReport, String> {
+    let service = modules::account_service::AccountService::new(
+        crate::modules::integration::SystemManager::Desktop(app.clone()),
+    );
+    let options = modules::account_import::BulkImportOptions {
+        format,
+        dry_run: dry_run.unwrap_or(false),
+        concurrency,
+        allow_pools: true,
+    };
+    let report = modules::account_import::bulk_import(&service, &content, &options).await?;
+
+    if !report.dry_run && report.created + report.updated > 0 {
+        crate::modules::tray::update_tray_menus(&app);
+        let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+    }
+    Ok(report)
+}
+
 /// 删除账号
 /// 删除账号
 #[tauri::command]
@@ -160,8 +188,105 @@ pub async fn get_current_account() -> Result<Option<Account>, String> {
 use crate::models::AccountExportResponse;
 
 #[tauri::command]
-pub async fn export_accounts(account_ids: Vec<String>) -> Result<AccountExportResponse, String> {
-    modules::account::export_accounts_by_ids(&account_ids)
+pub async fn export_accounts(
+    account_ids: Vec<String>,
+    confirm_plaintext: Option<bool>,
+) -> Result<AccountExportResponse, String> {
+    modules::account::export_accounts_by_ids(&account_ids, confirm_plaintext.unwrap_or(false))
+}
+
+/// 从旧版文件布局 (accounts.json + accounts/*.json) 导入账号
+#[tauri::command]
+pub async fn import_accounts_legacy_layout(
+    path: String,
+) -> Result<modules::account::LegacyLayoutReport, String> {
+    modules::account::import_legacy_layout(std::path::Path::new(&path))
+}
+
+/// 将账号存储导出为旧版文件布局 (`plaintext` 为 true 时写出明文 JSON，需 `confirm_plaintext`)
+#[tauri::command]
+pub async fn export_accounts_legacy_layout(
+    path: String,
+    plaintext: Option<bool>,
+    confirm_plaintext: Option<bool>,
+) -> Result<modules::account::LegacyLayoutReport, String> {
+    modules::account::export_legacy_layout(
+        std::path::Path::new(&path),
+        plaintext.unwrap_or(false),
+        confirm_plaintext.unwrap_or(false),
+    )
+}
+
+/// [NEW] 账号健康状态与转换历史 (反代管理服务未就绪时仅依据账号标记判定)
+#[tauri::command]
+pub async fn get_account_health(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_id: String,
+    limit: Option<usize>,
+) -> Result<modules::account_health::AccountHealthDetail, String> {
+    let limit = limit
+        .unwrap_or(modules::account_health::DEFAULT_HISTORY_LIMIT)
+        .min(500);
+    if let Some(token_manager) = admin_token_manager(&proxy_state).await {
+        return token_manager.account_health(&account_id, limit).await;
+    }
+    let data_dir = modules::account::get_data_dir()?;
+    modules::account_health::account_health_in(
+        &data_dir,
+        &account_id,
+        Default::default(),
+        limit,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+/// [NEW] 账号池健康汇总
+#[tauri::command]
+pub async fn get_pool_health(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+) -> Result<modules::account_health::PoolHealth, String> {
+    if let Some(token_manager) = admin_token_manager(&proxy_state).await {
+        return token_manager.pool_health().await;
+    }
+    let data_dir = modules::account::get_data_dir()?;
+    modules::account_health::pool_health_in(
+        &data_dir,
+        |_| Default::default(),
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+/// [NEW] 账号生命周期事件 (account_id 为空时返回全局事件流)
+#[tauri::command]
+pub async fn list_account_events(
+    account_id: Option<String>,
+    kind: Option<String>,
+    actor: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    before: Option<i64>,
+    limit: Option<usize>,
+) -> Result<modules::account_events::AccountEventPage, String> {
+    modules::account_events::list_events(&modules::account_events::AccountEventQuery {
+        account_id,
+        kind,
+        actor,
+        since,
+        until,
+        before,
+        limit,
+    })
+}
+
+async fn admin_token_manager(
+    proxy_state: &crate::commands::proxy::ProxyServiceState,
+) -> Option<std::sync::Arc<crate::proxy::TokenManager>> {
+    proxy_state
+        .admin_server
+        .read()
+        .await
+        .as_ref()
+        .map(|admin| admin.axum_server.token_manager.clone())
 }
 
 /// 内部辅助功能：在添加或导入账号后自动刷新一次额度
@@ -382,6 +507,10 @@ pub async fn save_config(
         crate::proxy::update_image_thinking_mode(config.proxy.image_thinking_mode.clone());
         // [NEW] 更新 VNPAY DNS Redirect 配置
         crate::proxy::update_vnpay_dns_redirect_config(config.proxy.vnpay_dns_redirect.clone());
+        // [NEW] 更新请求对冲配置
+        crate::proxy::update_hedging_config(config.proxy.hedging.clone());
+        // [NEW] 更新 TLS 配置 (证书热加载，新连接生效)
+        crate::proxy::update_tls_config(config.proxy.tls.clone());
         // 更新代理池配置
         instance
             .axum_server
@@ -392,6 +521,11 @@ pub async fn save_config(
             .token_manager
             .update_circuit_breaker_config(config.circuit_breaker.clone())
             .await;
+        // [NEW] 更新 Token 预刷新配置
+        instance
+            .token_manager
+            .update_token_refresh_config(config.token_refresh.clone())
+            .await;
         tracing::debug!("已同步热更新反代服务配置");
     }
 
@@ -400,6 +534,76 @@ pub async fn save_config(
 
 // --- OAuth 命令 ---
 
+/// [NEW] 密钥保险库状态
+#[tauri::command]
+pub async fn get_vault_status() -> Result<crate::utils::vault::VaultStatus, String> {
+    crate::utils::vault::status()
+}
+
+/// [NEW] 轮换保险库密钥并重新加密账号文件、配置与数据库中的密文
+#[tauri::command]
+pub async fn rotate_vault_key(
+    source: crate::utils::vault::KeySource,
+    passphrase: Option<String>,
+) -> Result<crate::modules::vault_rotation::ReencryptReport, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::vault_rotation::rotate_key(source, passphrase.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// [NEW] 备份数据目录 (`path` 为空时写入配置的备份目录，`components` 为空时备份全部组件)
+#[tauri::command]
+pub async fn create_backup(
+    path: Option<String>,
+    components: Option<Vec<crate::modules::backup::BackupComponent>>,
+    passphrase: Option<String>,
+) -> Result<crate::modules::backup::BackupReport, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::backup::create_backup(
+            path.as_deref().map(std::path::Path::new),
+            &components.unwrap_or_default(),
+            passphrase.as_deref().filter(|p| !p.is_empty()),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// [NEW] 列出备份目录中的归档
+#[tauri::command]
+pub async fn list_backups() -> Result<Vec<crate::modules::backup::BackupInfo>, String> {
+    crate::modules::backup::list_backups(None)
+}
+
+/// [NEW] 从备份恢复选定组件，恢复账号后重载账号池
+#[tauri::command]
+pub async fn restore_backup(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    path: String,
+    components: Option<Vec<crate::modules::backup::BackupComponent>>,
+    passphrase: Option<String>,
+) -> Result<crate::modules::backup::RestoreReport, String> {
+    let report = tokio::task::spawn_blocking(move || {
+        crate::modules::backup::restore_backup(
+            std::path::Path::new(&path),
+            &components.unwrap_or_default(),
+            passphrase.as_deref().filter(|p| !p.is_empty()),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if report
+        .restored
+        .contains(&crate::modules::backup::BackupComponent::Accounts)
+    {
+        let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+    }
+    Ok(report)
+}
+
 #[tauri::command]
 pub async fn start_oauth_login(app_handle: tauri::AppHandle) -> Result<Account, String> {
     modules::logger::log_info("开始 OAuth 授权流程...");
@@ -984,41 +1188,10 @@ pub async fn toggle_proxy_status(
         if enable { "启用" } else { "禁用" }
     ));
 
-    // 1. 读取账号文件
-    let data_dir = modules::account::get_data_dir()?;
-    let account_path = data_dir
-        .join("accounts")
-        .join(format!("{}.json", account_id));
-
-    if !account_path.exists() {
-        return Err(format!("账号文件不存在: {}", account_id));
-    }
-
-    let content =
-        std::fs::read_to_string(&account_path).map_err(|e| format!("读取账号文件失败: {}", e))?;
-
-    let mut account_json: serde_json::Value =
-        serde_json::from_str(&content).map_err(|e| format!("解析账号文件失败: {}", e))?;
-
-    // 2. 更新 proxy_disabled 字段
-    if enable {
-        // 启用反代
-        account_json["proxy_disabled"] = serde_json::Value::Bool(false);
-        account_json["proxy_disabled_reason"] = serde_json::Value::Null;
-        account_json["proxy_disabled_at"] = serde_json::Value::Null;
-    } else {
-        // 禁用反代
-        let now = chrono::Utc::now().timestamp();
-        account_json["proxy_disabled"] = serde_json::Value::Bool(true);
-        account_json["proxy_disabled_at"] = serde_json::Value::Number(now.into());
-        account_json["proxy_disabled_reason"] =
-            serde_json::Value::String(reason.unwrap_or_else(|| "用户手动禁用".to_string()));
-    }
-
-    // 3. 保存到磁盘
-    let json_str = serde_json::to_string_pretty(&account_json)
-        .map_err(|e| format!("序列化账号数据失败: {}", e))?;
-    std::fs::write(&account_path, json_str).map_err(|e| format!("写入账号文件失败: {}", e))?;
+    // 1. 在账号存储中更新 proxy_disabled 字段 (单事务读改写)
+    let reason = reason.unwrap_or_else(|| "用户手动禁用".to_string());
+    modules::account::toggle_proxy_status(&account_id, enable, Some(&reason))
+        .map_err(|e| format!("更新账号失败: {}", e))?;
 
     modules::logger::log_info(&format!(
         "账号反代状态已更新: {} ({})",
@@ -1026,7 +1199,7 @@ pub async fn toggle_proxy_status(
         if enable { "已启用" } else { "已禁用" }
     ));
 
-    // 4. 如果反代服务正在运行,立刻同步到内存池（避免禁用后仍被选中）
+    // 2. 如果反代服务正在运行,立刻同步到内存池（避免禁用后仍被选中）
     {
         let instance_lock = proxy_state.instance.read().await;
         if let Some(instance) = instance_lock.as_ref() {
@@ -1053,7 +1226,7 @@ pub async fn toggle_proxy_status(
         }
     }
 
-    // 5. 更新托盘菜单
+    // 3. 更新托盘菜单
     crate::modules::tray::update_tray_menus(&app);
 
     Ok(())
@@ -1085,33 +1258,13 @@ pub async fn update_account_label(account_id: String, label: String) -> Result<(
         if label.is_empty() { "无" } else { &label }
     ));
 
-    // 1. 读取账号文件
-    let data_dir = modules::account::get_data_dir()?;
-    let account_path = data_dir
-        .join("accounts")
-        .join(format!("{}.json", account_id));
-
-    if !account_path.exists() {
-        return Err(format!("账号文件不存在: {}", account_id));
-    }
-
-    let content =
-        std::fs::read_to_string(&account_path).map_err(|e| format!("读取账号文件失败: {}", e))?;
-
-    let mut account_json: serde_json::Value =
-        serde_json::from_str(&content).map_err(|e| format!("解析账号文件失败: {}", e))?;
-
-    // 2. 更新 custom_label 字段
-    if label.is_empty() {
-        account_json["custom_label"] = serde_json::Value::Null;
-    } else {
-        account_json["custom_label"] = serde_json::Value::String(label.clone());
-    }
-
-    // 3. 保存到磁盘
-    let json_str = serde_json::to_string_pretty(&account_json)
-        .map_err(|e| format!("序列化账号数据失败: {}", e))?;
-    std::fs::write(&account_path, json_str).map_err(|e| format!("写入账号文件失败: {}", e))?;
+    // 1. 在账号存储中更新 custom_label 字段
+    let custom_label = (!label.is_empty()).then(|| label.clone());
+    modules::account::update_account(&account_id, |account| {
+        account.custom_label = custom_label;
+        Ok(())
+    })
+    .map_err(|e| format!("更新账号失败: {}", e))?;
 
     modules::logger::log_info(&format!(
         "账号标签已更新: {} ({})",
@@ -1126,6 +1279,108 @@ pub async fn update_account_label(account_id: String, label: String) -> Result<(
     Ok(())
 }
 
+/// [NEW] 按筛选表达式查询账号 (服务端筛选 / 排序 / 分页)
+#[tauri::command]
+pub async fn query_accounts(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    filter: Option<String>,
+    sort: Option<String>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> Result<modules::account_query::AccountPage<Account>, String> {
+    let plan = modules::account_query::QueryPlan::parse(&modules::account_query::AccountQuery {
+        filter,
+        sort,
+        page,
+        page_size,
+    })?;
+    let accounts = modules::list_accounts()?;
+    let ctx = account_filter_context(&proxy_state, plan.needs_health()).await?;
+    Ok(plan.apply(accounts, &ctx))
+}
+
+/// 账号筛选上下文: 反代管理服务运行时使用 TokenManager 的健康状态 (含运行时信号)，
+/// 否则按账号标记判定
+async fn account_filter_context(
+    proxy_state: &crate::commands::proxy::ProxyServiceState,
+    needs_health: bool,
+) -> Result<modules::account_query::FilterContext, String> {
+    let mut health = std::collections::HashMap::new();
+    if needs_health {
+        if let Some(token_manager) = admin_token_manager(proxy_state).await {
+            health = token_manager
+                .pool_health()
+                .await?
+                .accounts
+                .into_iter()
+                .map(|h| (h.account_id, h.state))
+                .collect();
+        }
+    }
+    Ok(modules::account_query::FilterContext::new(health))
+}
+
+/// [NEW] 替换账号标签，返回规范化后的标签
+#[tauri::command]
+pub async fn update_account_tags(
+    account_id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let tags = modules::account_query::set_account_tags(&account_id, tags)?;
+    modules::logger::log_info(&format!("账号标签已更新: {} -> {:?}", account_id, tags));
+    Ok(tags)
+}
+
+/// [NEW] 所有账号标签及使用次数
+#[tauri::command]
+pub async fn list_account_tags() -> Result<Vec<modules::account_query::TagCount>, String> {
+    Ok(modules::account_query::tag_counts(
+        &modules::list_accounts()?
+    ))
+}
+
+/// [NEW] 按筛选表达式或 ID 列表批量操作账号 (禁用 / 启用 / 预热 / 绑定代理 / 删除 / 标签)
+#[tauri::command]
+pub async fn bulk_account_action(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    request: modules::account_query::BulkActionRequest,
+) -> Result<modules::account_query::BulkActionReport, String> {
+    let filter = request.prepare()?;
+    let accounts = modules::list_accounts()?;
+    let ctx = account_filter_context(&proxy_state, filter.needs_health()).await?;
+    let targets =
+        modules::account_query::select_targets(accounts, &filter, &request.account_ids, &ctx);
+    let report = modules::account_query::run_bulk_action(&request, targets).await?;
+
+    if !report.dry_run && report.succeeded > 0 {
+        crate::modules::tray::update_tray_menus(&app);
+        let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+    }
+    Ok(report)
+}
+
+/// [NEW] 新建通用上游 Provider 账号 (OpenAI / Anthropic 兼容端点)
+#[tauri::command]
+pub async fn add_provider_account(
+    request: modules::provider_account::ProviderAccountRequest,
+) -> Result<Account, String> {
+    let account = modules::provider_account::add_provider_account(request)?;
+    modules::logger::log_info(&format!("Provider 账号已添加: {}", account.email));
+    Ok(account)
+}
+
+/// [NEW] 更新 Provider 账号的端点配置 (api_key 留空沿用原值)
+#[tauri::command]
+pub async fn update_provider_account(
+    account_id: String,
+    request: modules::provider_account::ProviderConfigInput,
+) -> Result<Account, String> {
+    let account = modules::provider_account::update_provider_account(&account_id, request)?;
+    modules::logger::log_info(&format!("Provider 账号已更新: {}", account.email));
+    Ok(account)
+}
+
 // ============================================================================
 // HTTP API 设置命令
 // ============================================================================
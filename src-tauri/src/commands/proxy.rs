@@ -154,6 +154,9 @@ pub async fn internal_start_proxy_service(
     token_manager
         .update_circuit_breaker_config(app_config.circuit_breaker)
         .await;
+    token_manager
+        .update_token_refresh_config(app_config.token_refresh)
+        .await;
 
     // 🆕 [FIX #820] 恢复固定账号模式设置
     if let Some(ref account_id) = config.preferred_account_id {
@@ -239,6 +242,25 @@ pub async fn ensure_admin_server(
     let token_manager = Arc::new(TokenManager::new(app_data_dir));
     // [NEW] 加载账号数据，否则管理界面统计为 0
     let _ = token_manager.load_accounts().await;
+    // [NEW] 订阅账号存储变更，账号增删改后自动同步内存池
+    token_manager.start_account_sync().await;
+    // [NEW] 定期巡检账号健康状态并记录状态转换
+    token_manager.start_health_monitor().await;
+    // [NEW] 后台预刷新即将过期的 Token (invalid_grant 自动禁用账号)
+    if let Ok(app_config) = crate::modules::config::load_app_config() {
+        token_manager
+            .update_token_refresh_config(app_config.token_refresh)
+            .await;
+    }
+    token_manager.start_token_refresher().await;
+    // [NEW] 恢复上次运行的会话绑定、限流状态与签名缓存
+    if let Err(e) = token_manager.restore_runtime_state().await {
+        tracing::warn!("Failed to restore runtime state: {}", e);
+    }
+
+    // [NEW] 加载 TLS 证书 (需在监听前完成，以便首个连接即可使用 HTTPS)
+    let tls_config = config.tls.clone();
+    let _ = tokio::task::spawn_blocking(move || crate::proxy::update_tls_config(tls_config)).await;
 
     let (axum_server, server_handle) = match crate::proxy::AxumServer::start(
         config.get_bind_address().to_string(),
@@ -276,6 +298,8 @@ pub async fn ensure_admin_server(
     crate::proxy::update_image_thinking_mode(config.image_thinking_mode.clone());
     // [NEW] 初始化 VNPAY DNS Redirect 配置
     crate::proxy::update_vnpay_dns_redirect_config(config.vnpay_dns_redirect.clone());
+    // [NEW] 初始化请求对冲配置
+    crate::proxy::update_hedging_config(config.hedging.clone());
 
     Ok(())
 }
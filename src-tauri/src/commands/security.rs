@@ -40,6 +40,8 @@ pub struct IpStatsResponse {
     pub unique_ips: usize,
     pub blocked_requests: usize,
     pub top_ips: Vec<security_db::IpRanking>,
+    pub top_countries: Vec<security_db::GeoRanking>,
+    pub top_asns: Vec<security_db::GeoRanking>,
 }
 
 // ==================== IP 访问日志命令 ====================
@@ -69,12 +71,16 @@ pub async fn get_ip_access_logs(
 pub async fn get_ip_stats() -> Result<IpStatsResponse, String> {
     let stats = security_db::get_ip_stats()?;
     let top_ips = security_db::get_top_ips(10, 24)?; // Top 10 IPs in last 24 hours
+    let top_countries = security_db::get_top_countries(10, 24)?;
+    let top_asns = security_db::get_top_asns(10, 24)?;
     
     Ok(IpStatsResponse {
         total_requests: stats.total_requests as usize,
         unique_ips: stats.unique_ips as usize,
         blocked_requests: stats.blocked_count as usize,
         top_ips,
+        top_countries,
+        top_asns,
     })
 }
 
@@ -99,7 +105,7 @@ pub async fn add_ip_to_blacklist(
 ) -> Result<(), String> {
     // 验证 IP 格式
     if !is_valid_ip_pattern(&request.ip_pattern) {
-        return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)".to_string());
+        return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24 or 2001:db8::/32)".to_string());
     }
     
     security_db::add_to_blacklist(
@@ -157,7 +163,7 @@ pub async fn add_ip_to_whitelist(
 ) -> Result<(), String> {
     // 验证 IP 格式
     if !is_valid_ip_pattern(&request.ip_pattern) {
-        return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)".to_string());
+        return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24 or 2001:db8::/32)".to_string());
     }
     
     security_db::add_to_whitelist(
@@ -265,43 +271,8 @@ pub async fn get_ip_token_stats(
 
 /// 验证 IP 模式格式 (支持单个 IP 和 CIDR)
 fn is_valid_ip_pattern(pattern: &str) -> bool {
-    // 检查是否为 CIDR 格式
-    if pattern.contains('/') {
-        let parts: Vec<&str> = pattern.split('/').collect();
-        if parts.len() != 2 {
-            return false;
-        }
-        
-        // 验证 IP 部分
-        if !is_valid_ip(parts[0]) {
-            return false;
-        }
-        
-        // 验证掩码部分
-        if let Ok(mask) = parts[1].parse::<u8>() {
-            return mask <= 32;
-        }
-        return false;
-    }
-    
-    // 单个 IP 地址
-    is_valid_ip(pattern)
-}
-
-/// 验证 IP 地址格式
-fn is_valid_ip(ip: &str) -> bool {
-    let parts: Vec<&str> = ip.split('.').collect();
-    if parts.len() != 4 {
-        return false;
-    }
-    
-    for part in parts {
-        if part.parse::<u8>().is_err() {
-            return false;
-        }
-    }
-    
-    true
+    // 单个 IP 或 CIDR (支持 IPv4 / IPv6)
+    crate::modules::ip_matcher::parse_pattern(pattern).is_some()
 }
 
 #[cfg(test)]
@@ -315,6 +286,9 @@ mod tests {
         assert!(is_valid_ip_pattern("172.16.0.0/16"));
         assert!(is_valid_ip_pattern("192.168.1.0/24"));
         assert!(is_valid_ip_pattern("8.8.8.8/32"));
+        assert!(is_valid_ip_pattern("2001:db8::/32"));
+        assert!(is_valid_ip_pattern("::1"));
+        assert!(is_valid_ip_pattern("::ffff:10.0.0.0/104"));
     }
 
     #[test]
@@ -324,5 +298,6 @@ mod tests {
         assert!(!is_valid_ip_pattern("192.168.1.1/33"));
         assert!(!is_valid_ip_pattern("192.168.1.1/"));
         assert!(!is_valid_ip_pattern("invalid"));
+        assert!(!is_valid_ip_pattern("2001:db8::/129"));
     }
 }
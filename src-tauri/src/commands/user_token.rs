@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use crate::modules::user_token_db::{self, UserToken, TokenIpBinding};
+use crate::modules::token_schedule::TokenAccessSchedule;
+use crate::modules::user_token_db::{self, UserToken, TokenClientCert, TokenIpBinding, TokenLimits, TokenModelPolicy};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateTokenRequest {
@@ -10,6 +11,13 @@ pub struct CreateTokenRequest {
     pub curfew_start: Option<String>,
     pub curfew_end: Option<String>,
     pub custom_expires_at: Option<i64>,  // 自定义过期时间戳 (秒)
+    #[serde(default)]
+    pub limits: Option<TokenLimits>,
+    #[serde(default)]
+    pub model_policy: Option<TokenModelPolicy>,
+    /// 访问时间表 (设置 enabled=false 可停用)
+    #[serde(default)]
+    pub access_schedule: Option<TokenAccessSchedule>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +28,13 @@ pub struct UpdateTokenRequest {
     pub max_ips: Option<i32>,
     pub curfew_start: Option<Option<String>>,
     pub curfew_end: Option<Option<String>>,
+    #[serde(default)]
+    pub limits: Option<TokenLimits>,
+    #[serde(default)]
+    pub model_policy: Option<TokenModelPolicy>,
+    /// 访问时间表 (设置 enabled=false 可停用)
+    #[serde(default)]
+    pub access_schedule: Option<TokenAccessSchedule>,
 }
 
 // 命令实现
@@ -33,7 +48,11 @@ pub async fn list_user_tokens() -> Result<Vec<UserToken>, String> {
 /// 创建新令牌
 #[tauri::command]
 pub async fn create_user_token(request: CreateTokenRequest) -> Result<UserToken, String> {
-    user_token_db::create_token(
+    if let Some(schedule) = &request.access_schedule {
+        schedule.validate()?;
+    }
+
+    let mut token = user_token_db::create_token(
         request.username,
         request.expires_type,
         request.description,
@@ -41,7 +60,24 @@ pub async fn create_user_token(request: CreateTokenRequest) -> Result<UserToken,
         request.curfew_start,
         request.curfew_end,
         request.custom_expires_at,
-    )
+    )?;
+
+    if let Some(limits) = request.limits {
+        user_token_db::update_token_limits(&token.id, &limits)?;
+        token.limits = limits;
+    }
+
+    if let Some(policy) = request.model_policy {
+        user_token_db::update_token_model_policy(&token.id, &policy)?;
+        token.model_policy = policy;
+    }
+
+    if let Some(schedule) = request.access_schedule {
+        user_token_db::update_token_access_schedule(&token.id, Some(&schedule))?;
+        token.access_schedule = Some(schedule);
+    }
+
+    Ok(token)
 }
 
 /// 更新令牌
@@ -55,7 +91,21 @@ pub async fn update_user_token(id: String, request: UpdateTokenRequest) -> Resul
         request.max_ips,
         request.curfew_start,
         request.curfew_end,
-    )
+    )?;
+
+    if let Some(limits) = request.limits {
+        user_token_db::update_token_limits(&id, &limits)?;
+    }
+
+    if let Some(policy) = request.model_policy {
+        user_token_db::update_token_model_policy(&id, &policy)?;
+    }
+
+    if let Some(schedule) = request.access_schedule {
+        user_token_db::update_token_access_schedule(&id, Some(&schedule))?;
+    }
+
+    Ok(())
 }
 
 /// 删除令牌
@@ -76,6 +126,103 @@ pub async fn get_token_ip_bindings(token_id: String) -> Result<Vec<TokenIpBindin
     user_token_db::get_token_ips(&token_id)
 }
 
+/// 客户端证书默认有效期 (天)
+const DEFAULT_CLIENT_CERT_DAYS: i64 = 365;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IssueClientCertRequest {
+    /// 证书 CN，默认使用令牌用户名
+    #[serde(default)]
+    pub common_name: Option<String>,
+    #[serde(default)]
+    pub valid_days: Option<i64>,
+}
+
+/// 签发结果: 私钥仅在此返回一次，不会保存
+#[derive(Debug, Serialize)]
+pub struct IssuedClientCertResponse {
+    pub cert: TokenClientCert,
+    pub cert_pem: String,
+    pub key_pem: String,
+    /// 客户端校验服务端证书所需的根证书
+    pub ca_pem: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RevokeClientCertRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// 列出令牌的客户端证书 (mTLS)
+#[tauri::command]
+pub async fn list_token_client_certs(token_id: String) -> Result<Vec<TokenClientCert>, String> {
+    user_token_db::list_client_certs(Some(&token_id))
+}
+
+/// 为令牌签发客户端证书 (由本地根证书签发)
+#[tauri::command]
+pub async fn issue_token_client_cert(
+    token_id: String,
+    request: IssueClientCertRequest,
+) -> Result<IssuedClientCertResponse, String> {
+    let token = user_token_db::get_token_by_id(&token_id)?
+        .ok_or_else(|| format!("Token {} not found", token_id))?;
+    let common_name = request
+        .common_name
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| token.username.clone());
+    let valid_days = request.valid_days.unwrap_or(DEFAULT_CLIENT_CERT_DAYS);
+    if !(1..=3650).contains(&valid_days) {
+        return Err("valid_days must be between 1 and 3650".to_string());
+    }
+
+    let issued = tokio::task::spawn_blocking(move || {
+        crate::modules::mitm_ca::issue_client_cert(&common_name, valid_days)
+            .map(|issued| (issued, common_name))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let (issued, common_name) = issued;
+
+    let cert = TokenClientCert {
+        serial: issued.serial,
+        token_id: token.id,
+        fingerprint: issued.fingerprint,
+        common_name,
+        issued_at: chrono::Utc::now().timestamp(),
+        expires_at: issued.not_after,
+        revoked_at: None,
+        revoke_reason: None,
+    };
+    user_token_db::insert_client_cert(&cert)?;
+    tracing::info!("Issued client certificate {} for token {}", cert.serial, token.username);
+
+    Ok(IssuedClientCertResponse {
+        cert,
+        cert_pem: issued.cert_pem,
+        key_pem: issued.key_pem,
+        ca_pem: crate::modules::mitm_ca::read_ca_cert_pem().unwrap_or_default(),
+    })
+}
+
+/// 吊销客户端证书 (立即生效，无需重新加载 TLS)
+#[tauri::command]
+pub async fn revoke_token_client_cert(serial: String, request: RevokeClientCertRequest) -> Result<(), String> {
+    if !user_token_db::revoke_client_cert(&serial, request.reason.as_deref())? {
+        return Err(format!("Client certificate {} not found or already revoked", serial));
+    }
+    tracing::info!("Revoked client certificate {}", serial);
+    Ok(())
+}
+
+/// 客户端证书吊销列表
+#[tauri::command]
+pub async fn list_revoked_client_certs() -> Result<Vec<TokenClientCert>, String> {
+    user_token_db::list_revoked_client_certs()
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserTokenStats {
     pub total_tokens: usize,
@@ -121,6 +121,62 @@ pub fn run() {
     #[cfg(target_os = "linux")]
     configure_linux_gdk_backend();
 
+    // [NEW] 初始化密钥保险库 (密钥不可用时拒绝启动，避免以明文或错误密钥覆盖数据)
+    match utils::vault::init() {
+        Ok(created) => {
+            if created {
+                match modules::vault_rotation::migrate_legacy() {
+                    Ok(report) => info!(
+                        "Migrated legacy secrets to vault key {} ({} file(s), {} DB value(s))",
+                        report.kid, report.files_rewritten, report.db_values_reencrypted
+                    ),
+                    Err(e) => warn!("Legacy secret migration failed: {}", e),
+                }
+            }
+            // 旧版本或反代层写入的明文账号文件统一改写为加密格式
+            if let Err(e) = modules::account::encrypt_plaintext_account_files() {
+                warn!("Failed to encrypt plaintext account files: {}", e);
+            }
+            // 旧版 accounts.json + accounts/*.json 布局迁移到 SQLite 账号存储
+            match modules::account::migrate_legacy_layout() {
+                Ok(Some(report)) => info!(
+                    "Migrated {} account(s) into account store ({} skipped, {} failed)",
+                    report.accounts,
+                    report.skipped.len(),
+                    report.failed.len()
+                ),
+                Ok(None) => {}
+                Err(e) => warn!("Legacy account layout migration failed: {}", e),
+            }
+        }
+        Err(e) => {
+            error!("Failed to unlock secrets vault: {}", e);
+            eprintln!("Failed to unlock secrets vault: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // 密钥轮换: --rotate-vault-key [machine|keyring|passphrase]
+    // 当前密钥已由 ABV_VAULT_PASSPHRASE (旧口令) 解锁，新口令读取 ABV_VAULT_NEW_PASSPHRASE
+    if let Some(pos) = args.iter().position(|arg| arg == "--rotate-vault-key") {
+        let source = match args.get(pos + 1).map(String::as_str) {
+            Some("passphrase") => utils::vault::KeySource::Passphrase,
+            Some("keyring") => utils::vault::KeySource::Keyring,
+            _ => utils::vault::KeySource::Machine,
+        };
+        let passphrase = std::env::var("ABV_VAULT_NEW_PASSPHRASE").ok();
+        match modules::vault_rotation::rotate_key(source, passphrase.as_deref()) {
+            Ok(report) => {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+                std::process::exit(if report.errors.is_empty() { 0 } else { 2 });
+            }
+            Err(e) => {
+                error!("Vault key rotation failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Initialize token stats database
     if let Err(e) = modules::token_stats::init_db() {
         error!("Failed to initialize token stats database: {}", e);
@@ -136,15 +192,97 @@ pub fn run() {
         error!("Failed to initialize user token database: {}", e);
     }
 
+    // Initialize admin identity / audit database
+    if let Err(e) = modules::admin_auth_db::init_db() {
+        error!("Failed to initialize admin auth database: {}", e);
+    }
+
+    // Initialize runtime state database (sticky sessions / rate limits)
+    if let Err(e) = modules::runtime_state_db::init_db() {
+        error!("Failed to initialize runtime state database: {}", e);
+    }
+
+    // Initialize warmup history database
+    if let Err(e) = modules::warmup_db::init_db() {
+        error!("Failed to initialize warmup history database: {}", e);
+    }
+
     // One-shot sync of legacy `~/.antigravity_sw/accounts/*.json` files (used by
-    // older builds, plaintext) into the new encrypted layout under `~/.antisw/`.
-    // Idempotent: skips accounts already present in the new directory.
+    // older builds, plaintext) into the encrypted account store under `~/.antisw/`.
+    // Idempotent: skips accounts already present in the store.
     match modules::migration::sync_legacy_antigravity_sw_accounts() {
         Ok(0) => {}
         Ok(n) => info!("Migrated {} legacy account(s) from ~/.antigravity_sw/", n),
         Err(e) => warn!("Legacy account sync failed: {}", e),
     }
 
+    // 数据备份: --backup [path] / 恢复: --restore <archive> [accounts,config,...] (口令读取 ABV_BACKUP_PASSPHRASE)
+    let backup_passphrase = std::env::var(modules::backup::BACKUP_PASSPHRASE_ENV)
+        .ok()
+        .filter(|p| !p.is_empty());
+    if let Some(pos) = args.iter().position(|arg| arg == "--backup") {
+        let dest = args.get(pos + 1).filter(|a| !a.starts_with("--")).map(std::path::PathBuf::from);
+        match modules::backup::create_backup(dest.as_deref(), &[], backup_passphrase.as_deref()) {
+            Ok(report) => {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                error!("Backup failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(pos) = args.iter().position(|arg| arg == "--restore") {
+        let Some(archive) = args.get(pos + 1).filter(|a| !a.starts_with("--")) else {
+            eprintln!("Usage: --restore <archive> [component,component,...]");
+            std::process::exit(1);
+        };
+        let mut components = Vec::new();
+        if let Some(list) = args.get(pos + 2).filter(|a| !a.starts_with("--")) {
+            for name in list.split(',').filter(|n| !n.trim().is_empty()) {
+                match modules::backup::BackupComponent::parse(name) {
+                    Some(component) => components.push(component),
+                    None => {
+                        eprintln!("Unknown backup component: {}", name);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        match modules::backup::restore_backup(
+            std::path::Path::new(archive),
+            &components,
+            backup_passphrase.as_deref(),
+        ) {
+            Ok(report) => {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                error!("Restore failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // 调度策略模拟: --simulate [config.json]
+    if let Some(pos) = args.iter().position(|arg| arg == "--simulate") {
+        let config_path = args.get(pos + 1).filter(|a| !a.starts_with("--")).cloned();
+        let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+        let result = rt.block_on(proxy::simulator::run_cli(config_path.as_deref()));
+        match result {
+            Ok(report) => {
+                println!("{}", report);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                error!("Simulation failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     if is_headless {
         info!("Starting in HEADLESS mode...");
 
@@ -249,7 +387,11 @@ pub fn run() {
                     info!("📍 Port: {}", config.proxy.port);
                     info!("🔑 Current API Key: {}", config.proxy.api_key);
                     if let Some(ref pwd) = config.proxy.admin_password {
-                        info!("🔐 Web UI Password: {}", pwd);
+                        if crate::utils::crypto::is_password_hash(pwd) {
+                            info!("🔐 Web UI Password: (stored as Argon2 hash)");
+                        } else {
+                            info!("🔐 Web UI Password: {}", pwd);
+                        }
                     } else {
                         info!("🔐 Web UI Password: (Same as API Key)");
                     }
@@ -282,6 +424,15 @@ pub fn run() {
                     // Start smart scheduler
                     modules::scheduler::start_scheduler(None, proxy_state.clone());
                     info!("Smart scheduler started in headless mode.");
+
+                    // Start quota exhaustion forecast
+                    modules::quota_forecast::start_forecast_scheduler();
+
+                    // Start cron warmup schedules
+                    modules::warmup_schedule::start_warmup_schedule_loop();
+
+                    // Start scheduled data backups
+                    modules::backup::start_backup_scheduler();
                 }
                 Err(e) => {
                     error!("Failed to load config for headless mode: {}", e);
@@ -292,6 +443,14 @@ pub fn run() {
             // Wait for Ctrl-C
             tokio::signal::ctrl_c().await.ok();
             info!("Headless mode shutting down");
+
+            // 保存运行时状态 (会话绑定/限流)，以便重启后恢复
+            if let Some(instance) = proxy_state.instance.read().await.as_ref() {
+                instance
+                    .token_manager
+                    .graceful_shutdown(std::time::Duration::from_secs(2))
+                    .await;
+            }
         });
         return;
     }
@@ -368,6 +527,15 @@ pub fn run() {
             let scheduler_state = app.handle().state::<commands::proxy::ProxyServiceState>();
             modules::scheduler::start_scheduler(Some(app.handle().clone()), scheduler_state.inner().clone());
 
+            // Start quota exhaustion forecast
+            modules::quota_forecast::start_forecast_scheduler();
+
+            // Start cron warmup schedules
+            modules::warmup_schedule::start_warmup_schedule_loop();
+
+            // Start scheduled data backups
+            modules::backup::start_backup_scheduler();
+
             // [REMOVED] Port 8045 integration
             info!("Proxy server disabled by default");
 
@@ -400,11 +568,17 @@ pub fn run() {
             // Account management commands
             commands::list_accounts,
             commands::add_account,
+            commands::bulk_import_accounts,
             commands::delete_account,
             commands::delete_accounts,
             commands::reorder_accounts,
             commands::switch_account,
             commands::export_accounts,
+            commands::import_accounts_legacy_layout,
+            commands::export_accounts_legacy_layout,
+            commands::get_account_health,
+            commands::get_pool_health,
+            commands::list_account_events,
             // Device fingerprint
             commands::get_device_profiles,
             commands::bind_device_profile,
@@ -423,6 +597,11 @@ pub fn run() {
             // Config commands
             commands::load_config,
             commands::save_config,
+            commands::get_vault_status,
+            commands::rotate_vault_key,
+            commands::create_backup,
+            commands::list_backups,
+            commands::restore_backup,
             // Additional commands
             commands::prepare_oauth_url,
             commands::start_oauth_login,
@@ -497,6 +676,12 @@ pub fn run() {
             commands::warm_up_all_accounts,
             commands::warm_up_account,
             commands::update_account_label,
+            commands::query_accounts,
+            commands::update_account_tags,
+            commands::list_account_tags,
+            commands::bulk_account_action,
+            commands::add_provider_account,
+            commands::update_provider_account,
             // HTTP API settings commands
             commands::get_http_api_settings,
             commands::save_http_api_settings,
@@ -567,6 +752,10 @@ pub fn run() {
             commands::user_token::renew_user_token,
             commands::user_token::get_token_ip_bindings,
             commands::user_token::get_user_token_summary,
+            commands::user_token::list_token_client_certs,
+            commands::user_token::issue_token_client_cert,
+            commands::user_token::revoke_token_client_cert,
+            commands::user_token::list_revoked_client_certs,
             // Tracking commands
             commands::tracking::track_event,
             commands::tracking::get_tracking_device_id,
@@ -1,6 +1,6 @@
 use super::{quota::QuotaData, token::TokenData};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// 账号数据结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +60,9 @@ pub struct Account {
     /// 用户自定义标签
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub custom_label: Option<String>,
+    /// [NEW] 任意标签 (如 owner:alice / tier:gold / region:eu / batch:2026-10)，用于筛选与批量操作
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     /// Account type, e.g. "anthropic" for Claude accounts
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub account_type: Option<String>,
@@ -69,6 +72,55 @@ pub struct Account {
     /// Anthropic base URL (only for anthropic accounts)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub anthropic_base_url: Option<String>,
+    /// [NEW] 通用上游 Provider 配置 (仅 account_type = "provider")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ProviderConfig>,
+}
+
+/// [NEW] 通用上游 Provider 账号的 account_type
+pub const PROVIDER_ACCOUNT_TYPE: &str = "provider";
+
+/// [NEW] Provider 端点使用的协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderProtocol {
+    /// OpenAI Chat Completions (base_url 含版本前缀，如 https://api.example.com/v1)
+    #[default]
+    OpenAI,
+    /// Anthropic Messages (base_url 不含 /v1，如 https://api.anthropic.com)
+    Anthropic,
+}
+
+/// [NEW] Provider 账号参与号池调度的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderRouting {
+    /// 优先于 Google 号池，失败后回落到 Google
+    Primary,
+    /// 与 Google 账号一起轮询，每个 Provider 占一个槽位
+    Pooled,
+    /// 仅在 Google 号池不可用或全部失败时使用
+    #[default]
+    Fallback,
+}
+
+/// [NEW] 通用 OpenAI / Anthropic 兼容上游 (第三方厂商、自建 vLLM 等)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub base_url: String,
+    /// 为空表示上游无需鉴权 (如本地 vLLM)
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub protocol: ProviderProtocol,
+    /// 该端点可服务的模型，支持通配符 (如 gpt-4o*、*)
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 请求模型 -> 上游模型名，支持通配符；未命中时原样透传
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_mapping: HashMap<String, String>,
+    #[serde(default)]
+    pub routing: ProviderRouting,
 }
 
 impl Account {
@@ -98,12 +150,19 @@ impl Account {
             proxy_id: None,
             proxy_bound_at: None,
             custom_label: None,
+            tags: Vec::new(),
             account_type: None,
             anthropic_auth_token: None,
             anthropic_base_url: None,
+            provider: None,
         }
     }
 
+    /// [NEW] 是否为通用上游 Provider 账号 (无 Google OAuth 凭据)
+    pub fn is_provider(&self) -> bool {
+        self.account_type.as_deref() == Some(PROVIDER_ACCOUNT_TYPE)
+    }
+
     pub fn update_last_used(&mut self) {
         self.last_used = chrono::Utc::now().timestamp();
     }
@@ -155,7 +214,7 @@ impl Default for AccountIndex {
 }
 
 /// 设备指纹（storage.json 中 telemetry 相关字段）
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeviceProfile {
     pub machine_id: String,
     pub mac_machine_id: String,
@@ -1,3 +1,4 @@
+use crate::modules::backup::BackupComponent;
 use crate::modules::cloudflared::CloudflaredConfig;
 use crate::proxy::ProxyConfig;
 use serde::{Deserialize, Serialize};
@@ -23,10 +24,16 @@ pub struct AppConfig {
     #[serde(default)]
     pub quota_protection: QuotaProtectionConfig, // [NEW] Quota protection configuration
     #[serde(default)]
+    pub quota_forecast: QuotaForecastConfig, // [NEW] Quota exhaustion forecast configuration
+    #[serde(default)]
     pub pinned_quota_models: PinnedQuotaModelsConfig, // [NEW] Pinned quota models list
     #[serde(default)]
     pub circuit_breaker: CircuitBreakerConfig, // [NEW] Circuit breaker configuration
     #[serde(default)]
+    pub backup: BackupConfig, // [NEW] Scheduled data directory backup configuration
+    #[serde(default)]
+    pub token_refresh: TokenRefreshConfig, // [NEW] Background access token refresh configuration
+    #[serde(default)]
     pub hidden_menu_items: Vec<String>, // Hidden menu item path list
     #[serde(default)]
     pub antigravity_vnpay_enabled: bool, // [NEW] Antigravity VNPAY mode - redirect Google API to VNPAY
@@ -47,6 +54,67 @@ pub struct ScheduledWarmupConfig {
     /// List of models to warmup
     #[serde(default = "default_warmup_models")]
     pub monitored_models: Vec<String>,
+
+    /// Explicit cron-style warmup schedules
+    #[serde(default)]
+    pub schedules: Vec<WarmupSchedule>,
+
+    /// Default maximum number of concurrent warmup requests per scheduled run
+    #[serde(default = "default_warmup_max_concurrent")]
+    pub max_concurrent: u32,
+}
+
+fn default_warmup_max_concurrent() -> u32 {
+    3
+}
+
+/// Cron-style warmup schedule for a group of models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupSchedule {
+    /// Unique schedule id
+    pub id: String,
+
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// 5-field cron expression: minute hour day-of-month month day-of-week (e.g. "45 8 * * 1-5")
+    pub cron: String,
+
+    /// IANA time zone the cron expression is evaluated in (e.g. "Asia/Shanghai")
+    #[serde(default = "default_warmup_timezone")]
+    pub timezone: String,
+
+    /// Models or standard model groups to warm up (e.g. "claude", "gemini-3-flash")
+    #[serde(default = "default_warmup_models")]
+    pub models: Vec<String>,
+
+    /// Random delay (seconds) added before each run
+    #[serde(default)]
+    pub jitter_seconds: u64,
+
+    /// Overrides the global max_concurrent for this schedule
+    #[serde(default)]
+    pub max_concurrent: Option<u32>,
+
+    /// Only warm models whose quota is still at 100% (i.e. the reset window has not started)
+    #[serde(default = "default_true")]
+    pub only_full_quota: bool,
+
+    /// Only warm these accounts (account IDs or emails); empty means every account
+    #[serde(default)]
+    pub account_ids: Vec<String>,
+
+    /// Only warm accounts carrying at least one of these tags; empty means no tag filter
+    #[serde(default)]
+    pub account_tags: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_warmup_timezone() -> String {
+    "UTC".to_string()
 }
 
 fn default_warmup_models() -> Vec<String> {
@@ -63,6 +131,8 @@ impl ScheduledWarmupConfig {
         Self {
             enabled: false,
             monitored_models: default_warmup_models(),
+            schedules: Vec::new(),
+            max_concurrent: default_warmup_max_concurrent(),
         }
     }
 }
@@ -112,6 +182,123 @@ impl Default for QuotaProtectionConfig {
     }
 }
 
+/// Quota exhaustion forecast configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaForecastConfig {
+    /// Whether automatic rebalancing actions are enabled (the forecast itself is always computed)
+    pub enabled: bool,
+
+    /// Look-back window (hours) used to estimate the burn rate
+    #[serde(default = "default_forecast_window_hours")]
+    pub window_hours: u32,
+
+    /// A model group is considered at risk when it is predicted to run dry within this horizon
+    #[serde(default = "default_forecast_horizon_minutes")]
+    pub horizon_minutes: u32,
+
+    /// Raise the effective quota protection threshold when a monitored group is at risk
+    #[serde(default)]
+    pub auto_tighten_threshold: bool,
+
+    /// Upper bound for the tightened threshold (1-99)
+    #[serde(default = "default_forecast_max_threshold")]
+    pub max_threshold_percentage: u32,
+
+    /// Route requests to fallback models when their group is at risk
+    #[serde(default)]
+    pub auto_fallback: bool,
+
+    /// Fallback targets keyed by standard model group (e.g. "claude" -> "gemini-3-pro-high")
+    #[serde(default)]
+    pub fallback_models: std::collections::HashMap<String, String>,
+}
+
+fn default_forecast_window_hours() -> u32 {
+    3
+}
+
+fn default_forecast_horizon_minutes() -> u32 {
+    120
+}
+
+fn default_forecast_max_threshold() -> u32 {
+    30
+}
+
+impl QuotaForecastConfig {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            window_hours: default_forecast_window_hours(),
+            horizon_minutes: default_forecast_horizon_minutes(),
+            auto_tighten_threshold: false,
+            max_threshold_percentage: default_forecast_max_threshold(),
+            auto_fallback: false,
+            fallback_models: std::collections::HashMap::new(),
+        }
+    }
+}
+
+impl Default for QuotaForecastConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scheduled data directory backup configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Whether scheduled backups are enabled
+    pub enabled: bool,
+
+    /// Minimum interval (hours) between scheduled backups
+    #[serde(default = "default_backup_interval_hours")]
+    pub interval_hours: u32,
+
+    /// Number of scheduled backups to keep; older ones are removed
+    #[serde(default = "default_backup_keep")]
+    pub keep: u32,
+
+    /// Backup directory (relative paths resolve against the data dir), defaults to `<data dir>/backups`
+    #[serde(default)]
+    pub directory: Option<String>,
+
+    /// Components to include (empty = all)
+    #[serde(default)]
+    pub components: Vec<BackupComponent>,
+
+    /// Encrypt scheduled backups with the passphrase from ABV_BACKUP_PASSPHRASE
+    #[serde(default)]
+    pub encrypt: bool,
+}
+
+fn default_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_backup_keep() -> u32 {
+    7
+}
+
+impl BackupConfig {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: default_backup_interval_hours(),
+            keep: default_backup_keep(),
+            directory: None,
+            components: Vec::new(),
+            encrypt: false,
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Pinned quota models configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PinnedQuotaModelsConfig {
@@ -174,6 +361,81 @@ impl Default for CircuitBreakerConfig {
     }
 }
 
+/// Background access token refresh configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenRefreshConfig {
+    /// Whether tokens are refreshed proactively before they expire
+    pub enabled: bool,
+
+    /// Refresh this many seconds before expiry
+    #[serde(default = "default_refresh_margin_secs")]
+    pub margin_secs: u64,
+
+    /// Random extra lead time (seconds, stable per account and token) to spread refreshes out
+    #[serde(default = "default_refresh_jitter_secs")]
+    pub jitter_secs: u64,
+
+    /// How often the scheduler looks for tokens that are due (seconds)
+    #[serde(default = "default_refresh_check_interval_secs")]
+    pub check_interval_secs: u64,
+
+    /// Maximum number of concurrent refresh requests
+    #[serde(default = "default_refresh_max_concurrency")]
+    pub max_concurrency: usize,
+
+    /// Attempts per token before the scheduler gives up and leaves it to on-demand refresh
+    #[serde(default = "default_refresh_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Delay before the first retry (seconds), doubled after each failed attempt
+    #[serde(default = "default_refresh_retry_backoff_secs")]
+    pub retry_backoff_secs: u64,
+}
+
+fn default_refresh_margin_secs() -> u64 {
+    600
+}
+
+fn default_refresh_jitter_secs() -> u64 {
+    120
+}
+
+fn default_refresh_check_interval_secs() -> u64 {
+    30
+}
+
+fn default_refresh_max_concurrency() -> usize {
+    4
+}
+
+fn default_refresh_max_attempts() -> u32 {
+    3
+}
+
+fn default_refresh_retry_backoff_secs() -> u64 {
+    30
+}
+
+impl TokenRefreshConfig {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            margin_secs: default_refresh_margin_secs(),
+            jitter_secs: default_refresh_jitter_secs(),
+            check_interval_secs: default_refresh_check_interval_secs(),
+            max_concurrency: default_refresh_max_concurrency(),
+            max_attempts: default_refresh_max_attempts(),
+            retry_backoff_secs: default_refresh_retry_backoff_secs(),
+        }
+    }
+}
+
+impl Default for TokenRefreshConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AppConfig {
     pub fn new() -> Self {
         Self {
@@ -190,8 +452,11 @@ impl AppConfig {
             auto_launch: false,
             scheduled_warmup: ScheduledWarmupConfig::default(),
             quota_protection: QuotaProtectionConfig::default(),
+            quota_forecast: QuotaForecastConfig::default(),
             pinned_quota_models: PinnedQuotaModelsConfig::default(),
             circuit_breaker: CircuitBreakerConfig::default(),
+            backup: BackupConfig::default(),
+            token_refresh: TokenRefreshConfig::default(),
             hidden_menu_items: Vec::new(),
             antigravity_vnpay_enabled: false,
             tracking_enabled: true,
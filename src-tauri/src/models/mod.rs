@@ -3,8 +3,8 @@ pub mod token;
 pub mod quota;
 pub mod config;
 
-pub use account::{Account, AccountIndex, AccountSummary, DeviceProfile, DeviceProfileVersion, AccountExportItem, AccountExportResponse};
+pub use account::{Account, AccountIndex, AccountSummary, DeviceProfile, DeviceProfileVersion, AccountExportItem, AccountExportResponse, ProviderConfig, ProviderProtocol, ProviderRouting, PROVIDER_ACCOUNT_TYPE};
 pub use token::TokenData;
 pub use quota::QuotaData;
-pub use config::{AppConfig, QuotaProtectionConfig, CircuitBreakerConfig};
+pub use config::{AppConfig, QuotaProtectionConfig, QuotaForecastConfig, CircuitBreakerConfig, BackupConfig, TokenRefreshConfig, WarmupSchedule};
 
@@ -2,7 +2,7 @@ use serde::Serialize;
 use serde_json;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 use std::collections::HashSet;
 
@@ -11,6 +11,8 @@ use crate::models::{
     TokenData,
 };
 use crate::modules;
+use crate::modules::account_db::{self, ChangeOrigin};
+use crate::modules::account_events;
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 
@@ -214,6 +216,108 @@ mod tests {
         println!("Missing index with existing accounts: successfully recovered {} accounts", index.accounts.len());
     }
 
+    #[test]
+    fn test_plaintext_account_files_are_encrypted_at_rest() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let dir = TestDataDir::new();
+
+        create_account_file(dir.path(), "test-id-1", "user1@example.com");
+        let account_path = dir.path().join("accounts").join("test-id-1.json");
+        assert!(fs::read_to_string(&account_path).unwrap().contains("test_refresh_token"));
+
+        assert_eq!(encrypt_plaintext_account_files_in_dir(dir.path()).unwrap(), 1);
+        let raw = fs::read_to_string(&account_path).unwrap();
+        assert!(crate::utils::crypto::is_encrypted_content(&raw));
+        assert!(!raw.contains("test_refresh_token"));
+
+        // 解密后内容不变，且迁移可重复执行
+        let account = load_account_at_path(&account_path).unwrap();
+        assert_eq!(account.token.refresh_token, "test_refresh_token");
+        let mut json = read_account_json(&account_path).unwrap();
+        json["custom_label"] = serde_json::Value::String("vip".to_string());
+        write_account_json(&account_path, &json).unwrap();
+        assert_eq!(
+            read_account_json(&account_path).unwrap()["custom_label"],
+            "vip"
+        );
+        assert_eq!(encrypt_plaintext_account_files_in_dir(dir.path()).unwrap(), 0);
+
+        // 无法解密的密文报错，而不是当作 JSON 解析
+        fs::write(&account_path, format!("{}AAAA", &raw[..raw.len() - 4])).unwrap();
+        assert!(read_account_json(&account_path)
+            .unwrap_err()
+            .starts_with("failed_to_decrypt_account_data"));
+
+        // 明文导出必须显式确认
+        assert_eq!(
+            export_accounts_by_ids(&[], false).unwrap_err(),
+            "plaintext_export_requires_confirmation"
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_layout_into_store() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let dir = TestDataDir::new();
+
+        create_account_file(dir.path(), "test-id-1", "user1@example.com");
+        create_account_file(dir.path(), "test-id-2", "user2@example.com");
+        let index = AccountIndex {
+            version: "2.0".to_string(),
+            accounts: ["test-id-2", "test-id-1"]
+                .iter()
+                .map(|id| AccountSummary {
+                    id: id.to_string(),
+                    email: String::new(),
+                    name: None,
+                    disabled: false,
+                    proxy_disabled: false,
+                    protected_models: Default::default(),
+                    created_at: 0,
+                    last_used: 0,
+                })
+                .collect(),
+            current_account_id: Some("test-id-1".to_string()),
+        };
+        save_account_index_in_dir(dir.path(), &index).unwrap();
+
+        let report = migrate_legacy_layout_in(dir.path()).unwrap().unwrap();
+        assert_eq!(report.accounts, 2);
+        assert!(report.failed.is_empty());
+
+        // 保留索引顺序与当前账号，旧文件重命名备份
+        let ids: Vec<String> = account_db::list_summaries(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, vec!["test-id-2", "test-id-1"]);
+        assert_eq!(
+            account_db::get_current_account_id(dir.path()).unwrap().as_deref(),
+            Some("test-id-1")
+        );
+        assert!(!dir.path().join(ACCOUNTS_DIR).exists());
+        assert!(dir.path().join("accounts.migrated").join("test-id-1.json").exists());
+        assert!(dir.path().join("accounts.json.migrated").exists());
+        assert!(migrate_legacy_layout_in(dir.path()).unwrap().is_none());
+
+        // 导出为旧版布局后可以再导入到新的存储
+        let export_dir = dir.path().join("export");
+        let exported = export_legacy_layout_in(dir.path(), &export_dir, true).unwrap();
+        assert_eq!(exported.accounts, 2);
+        let raw = fs::read_to_string(export_dir.join(ACCOUNTS_DIR).join("test-id-1.json")).unwrap();
+        assert!(raw.contains("test_refresh_token"));
+
+        let other = dir.path().join("other");
+        fs::create_dir_all(&other).unwrap();
+        let imported = import_legacy_layout_in(&other, &export_dir).unwrap();
+        assert_eq!(imported.accounts, 2);
+        let again = import_legacy_layout_in(&other, &export_dir).unwrap();
+        assert_eq!((again.accounts, again.skipped.len()), (0, 2));
+        let account = account_db::get_account(&other, "test-id-2").unwrap().unwrap();
+        assert_eq!(account.email, "user2@example.com");
+    }
+
     #[test]
     fn test_save_account_index_roundtrip() {
         let _guard = TEST_MUTEX.lock().unwrap();
@@ -271,8 +375,6 @@ mod tests {
         assert_eq!(acc2.name, None);
         assert!(acc2.disabled);
         assert!(acc2.proxy_disabled);
-
-        println!("save_account_index roundtrip: successfully saved and loaded index with {} accounts", loaded.accounts.len());
     }
 
     #[test]
@@ -351,19 +453,6 @@ pub fn get_data_dir() -> Result<PathBuf, String> {
     Ok(data_dir)
 }
 
-/// Get accounts directory path
-pub fn get_accounts_dir() -> Result<PathBuf, String> {
-    let data_dir = get_data_dir()?;
-    let accounts_dir = data_dir.join(ACCOUNTS_DIR);
-
-    if !accounts_dir.exists() {
-        fs::create_dir_all(&accounts_dir)
-            .map_err(|e| format!("failed_to_create_accounts_dir: {}", e))?;
-    }
-
-    Ok(accounts_dir)
-}
-
 /// Load account index from a specific directory (internal helper)
 fn load_account_index_in_dir(data_dir: &PathBuf) -> Result<AccountIndex, String> {
     let index_path = data_dir.join(ACCOUNTS_INDEX);
@@ -439,7 +528,7 @@ fn save_account_index_in_dir(data_dir: &PathBuf, index: &AccountIndex) -> Result
 
     let content = serde_json::to_string_pretty(index)
         .map_err(|e| format!("failed_to_serialize_account_index: {}", e))?;
-    let content = crate::utils::crypto::encrypt_file_content(&content);;
+    let content = crate::utils::crypto::encrypt_file_content(&content)?;
 
     // Write to temporary file
     if let Err(e) = fs::write(&temp_path, content) {
@@ -518,16 +607,288 @@ fn rebuild_index_from_accounts_in_dir(data_dir: &PathBuf) -> Result<AccountIndex
 
 /// Load account from a specific path (internal helper)
 fn load_account_at_path(account_path: &PathBuf) -> Result<Account, String> {
+    let content = read_account_file(account_path)?;
+    serde_json::from_str(&content).map_err(|e| format!("failed_to_parse_account_data: {}", e))
+}
+
+/// [NEW] 读取并解密账号文件 (兼容旧版明文文件；密文无法解密时报错而不是当作 JSON 解析)
+fn read_account_file(account_path: &Path) -> Result<String, String> {
     let raw = fs::read_to_string(account_path)
         .map_err(|e| format!("failed_to_read_account_data: {}", e))?;
-    let content = crate::utils::crypto::decrypt_file_content(&raw);
+    crate::utils::crypto::decrypt_file_content_strict(&raw)
+        .map_err(|e| format!("failed_to_decrypt_account_data: {}", e))
+}
+
+/// [NEW] 以 JSON 形式读取账号文件
+#[cfg(test)]
+fn read_account_json(account_path: &Path) -> Result<serde_json::Value, String> {
+    let content = read_account_file(account_path)?;
     serde_json::from_str(&content).map_err(|e| format!("failed_to_parse_account_data: {}", e))
 }
 
-/// Load account index with recovery support
+/// [NEW] 加密写入账号文件 (临时文件 + 原子替换，并发读取方不会读到半截密文)
+pub fn write_account_json<T: Serialize + ?Sized>(account_path: &Path, value: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
+    let content = crate::utils::crypto::encrypt_file_content(&content)?;
+
+    let temp_path = account_path.with_extension(format!("json.tmp.{}", Uuid::new_v4()));
+    if let Err(e) = fs::write(&temp_path, content) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("failed_to_save_account_data: {}", e));
+    }
+    atomic_replace_file(&temp_path, &account_path.to_path_buf()).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("failed_to_save_account_data: {}", e)
+    })
+}
+
+/// [NEW] 一次性迁移: 将仍为明文的账号文件与索引改写为加密格式，返回改写的文件数
+pub fn encrypt_plaintext_account_files() -> Result<usize, String> {
+    let data_dir = get_data_dir()?;
+    encrypt_plaintext_account_files_in_dir(&data_dir)
+}
+
+fn encrypt_plaintext_account_files_in_dir(data_dir: &Path) -> Result<usize, String> {
+    let mut files: Vec<PathBuf> = vec![data_dir.join(ACCOUNTS_INDEX)];
+    if let Ok(entries) = fs::read_dir(data_dir.join(ACCOUNTS_DIR)) {
+        files.extend(
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json")),
+        );
+    }
+
+    let _lock = ACCOUNT_INDEX_LOCK
+        .lock()
+        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
+    let mut migrated = 0;
+    for path in files {
+        let Ok(raw) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if raw.trim().is_empty() || crate::utils::crypto::is_encrypted_content(&raw) {
+            continue;
+        }
+        // 只改写可解析的 JSON，损坏文件留给索引恢复逻辑处理
+        let value: serde_json::Value = match serde_json::from_str(&sanitize_index_content(raw.as_bytes())) {
+            Ok(v) => v,
+            Err(e) => {
+                crate::modules::logger::log_warn(&format!(
+                    "Skipping unparsable plaintext account file {:?}: {}",
+                    path, e
+                ));
+                continue;
+            }
+        };
+        write_account_json(&path, &value)?;
+        migrated += 1;
+    }
+
+    if migrated > 0 {
+        crate::modules::logger::log_info(&format!(
+            "Encrypted {} plaintext account file(s) at rest",
+            migrated
+        ));
+    }
+    Ok(migrated)
+}
+
+/// [NEW] 旧版 (accounts.json + accounts/*.json) 布局导入/导出结果
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct LegacyLayoutReport {
+    pub accounts: usize,
+    /// 已存在于账号存储中的账号
+    pub skipped: Vec<String>,
+    /// 无法读取/解密的账号文件
+    pub failed: Vec<String>,
+}
+
+/// [NEW] 从旧版文件布局导入账号到账号存储 (已存在的 id/邮箱会跳过)
+pub fn import_legacy_layout(source_dir: &Path) -> Result<LegacyLayoutReport, String> {
+    let data_dir = get_data_dir()?;
+    let _lock = ACCOUNT_INDEX_LOCK
+        .lock()
+        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
+    import_legacy_layout_in(&data_dir, source_dir)
+}
+
+fn import_legacy_layout_in(data_dir: &Path, source_dir: &Path) -> Result<LegacyLayoutReport, String> {
+    let source_dir = source_dir.to_path_buf();
+    let accounts_dir = source_dir.join(ACCOUNTS_DIR);
+    let index = load_account_index_in_dir(&source_dir)?;
+
+    // 先按索引顺序，再补充索引中缺失但目录中存在的账号文件
+    let mut ids: Vec<String> = index.accounts.iter().map(|s| s.id.clone()).collect();
+    let mut extra: Vec<String> = fs::read_dir(&accounts_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(String::from))
+                .filter(|id| !ids.contains(id))
+                .collect()
+        })
+        .unwrap_or_default();
+    extra.sort();
+    ids.extend(extra);
+
+    let mut report = LegacyLayoutReport::default();
+    for id in ids {
+        let path = accounts_dir.join(format!("{}.json", id));
+        if !path.exists() {
+            report.skipped.push(format!("{}: account file missing", id));
+            continue;
+        }
+        let account = match load_account_at_path(&path) {
+            Ok(account) => account,
+            Err(e) => {
+                report.failed.push(format!("{}: {}", id, e));
+                continue;
+            }
+        };
+        match account_db::insert_account(data_dir, &account, false, ChangeOrigin::App) {
+            Ok(()) => report.accounts += 1,
+            Err(e) => report.skipped.push(format!("{}: {}", id, e)),
+        }
+    }
+
+    if account_db::get_current_account_id(data_dir)?.is_none() {
+        if let Some(current) = index.current_account_id.as_deref() {
+            if account_db::get_account_flags(data_dir, current)?.is_some() {
+                account_db::set_current_account_id(data_dir, Some(current))?;
+            }
+        }
+    }
+
+    crate::modules::logger::log_info(&format!(
+        "Imported {} account(s) from legacy layout {:?} ({} skipped, {} failed)",
+        report.accounts,
+        source_dir,
+        report.skipped.len(),
+        report.failed.len()
+    ));
+    Ok(report)
+}
+
+/// [NEW] 将账号存储导出为旧版文件布局 (默认加密，`plaintext` 时写出明文 JSON，需显式确认)
+pub fn export_legacy_layout(
+    target_dir: &Path,
+    plaintext: bool,
+    confirm_plaintext: bool,
+) -> Result<LegacyLayoutReport, String> {
+    if plaintext && !confirm_plaintext {
+        return Err("plaintext_export_requires_confirmation".to_string());
+    }
+    if plaintext {
+        crate::modules::logger::log_warn(&format!(
+            "Exporting legacy account layout with plaintext refresh tokens to {}",
+            target_dir.display()
+        ));
+    }
+    let data_dir = get_data_dir()?;
+    export_legacy_layout_in(&data_dir, target_dir, plaintext)
+}
+
+fn export_legacy_layout_in(
+    data_dir: &Path,
+    target_dir: &Path,
+    plaintext: bool,
+) -> Result<LegacyLayoutReport, String> {
+    let target_dir = target_dir.to_path_buf();
+    let accounts_dir = target_dir.join(ACCOUNTS_DIR);
+    fs::create_dir_all(&accounts_dir)
+        .map_err(|e| format!("failed_to_create_accounts_dir: {}", e))?;
+
+    let mut report = LegacyLayoutReport::default();
+    for account in account_db::list_accounts(data_dir)? {
+        let path = accounts_dir.join(format!("{}.json", account.id));
+        if plaintext {
+            let content = serde_json::to_string_pretty(&account)
+                .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
+            fs::write(&path, content).map_err(|e| format!("failed_to_save_account_data: {}", e))?;
+        } else {
+            write_account_json(&path, &account)?;
+        }
+        report.accounts += 1;
+    }
+
+    let index = AccountIndex {
+        version: "2.0".to_string(),
+        accounts: account_db::list_summaries(data_dir)?,
+        current_account_id: account_db::get_current_account_id(data_dir)?,
+    };
+    if plaintext {
+        let content = serde_json::to_string_pretty(&index)
+            .map_err(|e| format!("failed_to_serialize_account_index: {}", e))?;
+        fs::write(target_dir.join(ACCOUNTS_INDEX), content)
+            .map_err(|e| format!("failed_to_write_index_file: {}", e))?;
+    } else {
+        save_account_index_in_dir(&target_dir, &index)?;
+    }
+    Ok(report)
+}
+
+/// [NEW] 启动时将旧版文件布局迁移到账号存储，迁移后旧文件重命名为 *.migrated 保留备份
+pub fn migrate_legacy_layout() -> Result<Option<LegacyLayoutReport>, String> {
+    let data_dir = get_data_dir()?;
+    migrate_legacy_layout_in(&data_dir)
+}
+
+pub fn migrate_legacy_layout_in(data_dir: &Path) -> Result<Option<LegacyLayoutReport>, String> {
+    let index_path = data_dir.join(ACCOUNTS_INDEX);
+    let accounts_dir = data_dir.join(ACCOUNTS_DIR);
+    if !index_path.exists() && !accounts_dir.exists() {
+        return Ok(None);
+    }
+
+    let _lock = ACCOUNT_INDEX_LOCK
+        .lock()
+        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
+    // 持锁后再检查一次，避免并发启动路径重复迁移
+    if !index_path.exists() && !accounts_dir.exists() {
+        return Ok(None);
+    }
+
+    let report = import_legacy_layout_in(data_dir, data_dir)?;
+    if !report.failed.is_empty() {
+        // 有文件无法读取 (例如密钥缺失) 时保留旧布局，下次启动重试
+        crate::modules::logger::log_warn(&format!(
+            "Legacy account migration incomplete, keeping legacy files: {:?}",
+            report.failed
+        ));
+        return Ok(Some(report));
+    }
+    let suffix = chrono::Utc::now().format("%Y%m%d%H%M%S").to_string();
+    for path in [index_path, accounts_dir] {
+        if !path.exists() {
+            continue;
+        }
+        let mut target = PathBuf::from(format!("{}.migrated", path.display()));
+        if target.exists() {
+            target = PathBuf::from(format!("{}.migrated.{}", path.display(), suffix));
+        }
+        fs::rename(&path, &target)
+            .map_err(|e| format!("failed_to_archive_legacy_accounts: {}", e))?;
+    }
+
+    crate::modules::logger::log_info(&format!(
+        "Migrated legacy account files into {:?}",
+        account_db::get_accounts_db_path(data_dir)
+    ));
+    Ok(Some(report))
+}
+
+/// Load account index (summaries and current account from the account store)
 pub fn load_account_index() -> Result<AccountIndex, String> {
     let data_dir = get_data_dir()?;
-    load_account_index_in_dir(&data_dir)
+    Ok(AccountIndex {
+        version: "2.0".to_string(),
+        accounts: account_db::list_summaries(&data_dir)?,
+        current_account_id: account_db::get_current_account_id(&data_dir)?,
+    })
 }
 
 /// Sanitize index file content by stripping BOM and leading NUL bytes
@@ -597,10 +958,13 @@ fn try_save_recovered_index(
     Ok(())
 }
 
-/// Save account index (atomic write)
+/// Save account index: applies list order and current account to the account store.
+/// Summary fields are derived from account data and are not written back.
 pub fn save_account_index(index: &AccountIndex) -> Result<(), String> {
     let data_dir = get_data_dir()?;
-    save_account_index_in_dir(&data_dir, index)
+    let ids: Vec<String> = index.accounts.iter().map(|s| s.id.clone()).collect();
+    account_db::reorder_accounts(&data_dir, &ids)?;
+    account_db::set_current_account_id(&data_dir, index.current_account_id.as_deref())
 }
 
 /// Platform-specific atomic file replacement
@@ -652,45 +1016,31 @@ fn atomic_replace_file(src: &PathBuf, dst: &PathBuf) -> Result<(), String> {
 
 /// Load account data
 pub fn load_account(account_id: &str) -> Result<Account, String> {
-    let accounts_dir = get_accounts_dir()?;
-    let account_path = accounts_dir.join(format!("{}.json", account_id));
-    load_account_at_path(&account_path)
+    let data_dir = get_data_dir()?;
+    account_db::get_account(&data_dir, account_id)?
+        .ok_or_else(|| format!("Account not found: {}", account_id))
 }
 
 /// Save account data
 pub fn save_account(account: &Account) -> Result<(), String> {
-    let accounts_dir = get_accounts_dir()?;
-    let account_path = accounts_dir.join(format!("{}.json", account.id));
-
-    let content = serde_json::to_string_pretty(account)
-        .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
-    let content = crate::utils::crypto::encrypt_file_content(&content);
+    let data_dir = get_data_dir()?;
+    account_db::save_account(&data_dir, account, ChangeOrigin::App)
+}
 
-    fs::write(&account_path, content).map_err(|e| format!("failed_to_save_account_data: {}", e))
+/// [NEW] 在单个事务内读取、修改并写回账号 (避免与调度器/反代层的并发写入互相覆盖)
+pub fn update_account<T>(
+    account_id: &str,
+    f: impl FnOnce(&mut Account) -> Result<T, String>,
+) -> Result<T, String> {
+    let data_dir = get_data_dir()?;
+    account_db::update_account(&data_dir, account_id, ChangeOrigin::App, f)
 }
 
 /// List all accounts
 pub fn list_accounts() -> Result<Vec<Account>, String> {
     crate::modules::logger::log_info("Listing accounts...");
-    let index = load_account_index()?;
-    let mut accounts = Vec::new();
-
-    for summary in &index.accounts {
-        match load_account(&summary.id) {
-            Ok(account) => accounts.push(account),
-            Err(e) => {
-                crate::modules::logger::log_error(&format!(
-                    "Failed to load account {}: {}",
-                    summary.id, e
-                ));
-                // [FIX #929] Removed auto-repair logic.
-                // We no longer silently delete account IDs from the index if the file is missing.
-                // This prevents account loss during version upgrades or temporary FS issues.
-            }
-        }
-    }
-
-    Ok(accounts)
+    let data_dir = get_data_dir()?;
+    account_db::list_accounts(&data_dir)
 }
 
 /// Add account
@@ -699,42 +1049,14 @@ pub fn add_account(
     name: Option<String>,
     token: TokenData,
 ) -> Result<Account, String> {
-    let _lock = ACCOUNT_INDEX_LOCK
-        .lock()
-        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-    let mut index = load_account_index()?;
-
-    // Check if account already exists
-    if index.accounts.iter().any(|s| s.email == email) {
-        return Err(format!("Account already exists: {}", email));
-    }
+    let data_dir = get_data_dir()?;
 
-    // Create new account
-    let account_id = Uuid::new_v4().to_string();
-    let mut account = Account::new(account_id.clone(), email.clone(), token);
-    account.name = name.clone();
-
-    // Save account data
-    save_account(&account)?;
-
-    // Update index
-    index.accounts.push(AccountSummary {
-        id: account.id.clone(),
-        email: account.email.clone(),
-        name: account.name.clone(),
-        disabled: account.disabled,
-        proxy_disabled: account.proxy_disabled,
-        protected_models: account.protected_models.clone(),
-        created_at: account.created_at,
-        last_used: account.last_used,
-    });
+    // Create new account (the store rejects duplicate emails inside the same transaction)
+    let mut account = Account::new(Uuid::new_v4().to_string(), email, token);
+    account.name = name;
 
     // If first account, set as current
-    if index.current_account_id.is_none() {
-        index.current_account_id = Some(account_id);
-    }
-
-    save_account_index(&index)?;
+    account_db::insert_account(&data_dir, &account, true, ChangeOrigin::App)?;
 
     Ok(account)
 }
@@ -745,188 +1067,64 @@ pub fn upsert_account(
     name: Option<String>,
     token: TokenData,
 ) -> Result<Account, String> {
-    let _lock = ACCOUNT_INDEX_LOCK
-        .lock()
-        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-    let mut index = load_account_index()?;
+    let data_dir = get_data_dir()?;
 
     // Find account ID if exists
-    let existing_account_id = index
-        .accounts
-        .iter()
-        .find(|s| s.email == email)
-        .map(|s| s.id.clone());
-
-    if let Some(account_id) = existing_account_id {
-        // Update existing account
-        match load_account(&account_id) {
-            Ok(mut account) => {
-                let old_access_token = account.token.access_token.clone();
-                let old_refresh_token = account.token.refresh_token.clone();
-                account.token = token;
-                account.name = name.clone();
-                // If an account was previously disabled (e.g. invalid_grant), any explicit token upsert
-                // should re-enable it (user manually updated credentials in the UI).
-                if account.disabled
-                    && (account.token.refresh_token != old_refresh_token
-                        || account.token.access_token != old_access_token)
-                {
-                    account.disabled = false;
-                    account.disabled_reason = None;
-                    account.disabled_at = None;
-                }
-                account.update_last_used();
-                save_account(&account)?;
-
-                // Sync name in index
-                if let Some(idx_summary) = index.accounts.iter_mut().find(|s| s.id == account_id) {
-                    idx_summary.name = name;
-                    save_account_index(&index)?;
-                }
-
-                return Ok(account);
-            }
-            Err(e) => {
-                crate::modules::logger::log_warn(&format!(
-                    "Account {} file missing ({}), recreating...",
-                    account_id, e
-                ));
-                // Index exists but file is missing, recreating
-                let mut account = Account::new(account_id.clone(), email.clone(), token);
-                account.name = name.clone();
-                save_account(&account)?;
-
-                // Sync name in index
-                if let Some(idx_summary) = index.accounts.iter_mut().find(|s| s.id == account_id) {
-                    idx_summary.name = name;
-                    save_account_index(&index)?;
-                }
+    let Some(account_id) = account_db::find_account_id_by_email(&data_dir, &email)? else {
+        return add_account(email, name, token);
+    };
 
-                return Ok(account);
-            }
+    // Update existing account
+    account_db::update_account(&data_dir, &account_id, ChangeOrigin::App, |account| {
+        let old_access_token = account.token.access_token.clone();
+        let old_refresh_token = account.token.refresh_token.clone();
+        account.token = token;
+        account.name = name;
+        // If an account was previously disabled (e.g. invalid_grant), any explicit token upsert
+        // should re-enable it (user manually updated credentials in the UI).
+        if account.disabled
+            && (account.token.refresh_token != old_refresh_token
+                || account.token.access_token != old_access_token)
+        {
+            account.disabled = false;
+            account.disabled_reason = None;
+            account.disabled_at = None;
         }
-    }
-
-    // Add if not exists
-    // Note: add_account will attempt to acquire lock, which would deadlock here.
-    // Use an internal version or release lock.
-
-    // Release lock, let add_account handle it
-    drop(_lock);
-    add_account(email, name, token)
+        account.update_last_used();
+        Ok(account.clone())
+    })
 }
 
 /// Delete account
 pub fn delete_account(account_id: &str) -> Result<(), String> {
-    let _lock = ACCOUNT_INDEX_LOCK
-        .lock()
-        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-    let mut index = load_account_index()?;
-
-    // Remove from index
-    let original_len = index.accounts.len();
-    index.accounts.retain(|s| s.id != account_id);
-
-    if index.accounts.len() == original_len {
+    let data_dir = get_data_dir()?;
+    // The store publishes a deletion event so TokenManager purges its caches (Issue #1477)
+    let deleted =
+        account_db::delete_accounts(&data_dir, &[account_id.to_string()], ChangeOrigin::App)?;
+    if deleted == 0 {
         return Err(format!("Account ID not found: {}", account_id));
     }
-
-    // Clear current account if it's being deleted
-    if index.current_account_id.as_deref() == Some(account_id) {
-        index.current_account_id = index.accounts.first().map(|s| s.id.clone());
-    }
-
-    save_account_index(&index)?;
-
-    // Delete account file
-    let accounts_dir = get_accounts_dir()?;
-    let account_path = accounts_dir.join(format!("{}.json", account_id));
-
-    if account_path.exists() {
-        fs::remove_file(&account_path)
-            .map_err(|e| format!("failed_to_delete_account_file: {}", e))?;
-    }
-
-    // [FIX #1477] Trigger TokenManager cache cleanup signal
-    crate::proxy::server::trigger_account_delete(account_id);
-
     Ok(())
 }
 
-/// Batch delete accounts (atomic index operation)
+/// Batch delete accounts (single transaction)
 pub fn delete_accounts(account_ids: &[String]) -> Result<(), String> {
-    let _lock = ACCOUNT_INDEX_LOCK
-        .lock()
-        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-    let mut index = load_account_index()?;
-
-    let accounts_dir = get_accounts_dir()?;
-
-    for account_id in account_ids {
-        // Remove from index
-        index.accounts.retain(|s| &s.id != account_id);
-
-        // Clear current account if it's being deleted
-        if index.current_account_id.as_deref() == Some(account_id) {
-            index.current_account_id = None;
-        }
-
-        // Delete account file
-        let account_path = accounts_dir.join(format!("{}.json", account_id));
-        if account_path.exists() {
-            let _ = fs::remove_file(&account_path);
-        }
-
-        // [FIX #1477] Trigger TokenManager cache cleanup signal
-        crate::proxy::server::trigger_account_delete(account_id);
-    }
-
-    // If current account is empty, use first one as default
-    if index.current_account_id.is_none() {
-        index.current_account_id = index.accounts.first().map(|s| s.id.clone());
-    }
-
-    save_account_index(&index)
+    let data_dir = get_data_dir()?;
+    account_db::delete_accounts(&data_dir, account_ids, ChangeOrigin::App).map(|_| ())
 }
 
 /// Reorder account list
-/// Update account order in index file based on provided IDs
+/// Update account order based on provided IDs (missing accounts keep their order at the end)
 pub fn reorder_accounts(account_ids: &[String]) -> Result<(), String> {
-    let _lock = ACCOUNT_INDEX_LOCK
-        .lock()
-        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-    let mut index = load_account_index()?;
-
-    // Create a map of account ID to summary
-    let id_to_summary: std::collections::HashMap<_, _> = index
-        .accounts
-        .iter()
-        .map(|s| (s.id.clone(), s.clone()))
-        .collect();
-
-    // Rebuild account list with new order
-    let mut new_accounts = Vec::new();
-    for id in account_ids {
-        if let Some(summary) = id_to_summary.get(id) {
-            new_accounts.push(summary.clone());
-        }
-    }
-
-    // Add accounts missing from new order to the end
-    for summary in &index.accounts {
-        if !account_ids.contains(&summary.id) {
-            new_accounts.push(summary.clone());
-        }
-    }
-
-    index.accounts = new_accounts;
+    let data_dir = get_data_dir()?;
+    let total = account_db::reorder_accounts(&data_dir, account_ids)?;
 
     crate::modules::logger::log_info(&format!(
         "Account order updated, {} accounts total",
-        index.accounts.len()
+        total
     ));
 
-    save_account_index(&index)
+    Ok(())
 }
 
 /// Switch current account (Core Logic)
@@ -936,19 +1134,11 @@ pub async fn switch_account(
 ) -> Result<(), String> {
     use crate::modules::oauth;
 
-    let index = {
-        let _lock = ACCOUNT_INDEX_LOCK
-            .lock()
-            .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-        load_account_index()?
-    };
-
     // 1. Verify account exists
-    if !index.accounts.iter().any(|s| s.id == account_id) {
-        return Err(format!("Account not found: {}", account_id));
-    }
-
     let mut account = load_account(account_id)?;
+    if account.is_provider() {
+        return Err("Provider accounts cannot be switched to".to_string());
+    }
     crate::modules::logger::log_info(&format!(
         "Switching to account: {} (ID: {})",
         account.email, account.id
@@ -959,10 +1149,13 @@ pub async fn switch_account(
         .await
         .map_err(|e| format!("Token refresh failed: {}", e))?;
 
-    // If Token updated, save back to account file
+    // If Token updated, save back to account store
     if fresh_token.access_token != account.token.access_token {
         account.token = fresh_token.clone();
-        save_account(&account)?;
+        update_account(account_id, |stored| {
+            stored.token = fresh_token;
+            Ok(())
+        })?;
     }
 
     // [FIX] Ensure account has a device profile for isolation
@@ -984,17 +1177,11 @@ pub async fn switch_account(
     integration.on_account_switch(&account).await?;
 
     // 4. Update tool internal state
-    {
-        let _lock = ACCOUNT_INDEX_LOCK
-            .lock()
-            .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-        let mut index = load_account_index()?;
-        index.current_account_id = Some(account_id.to_string());
-        save_account_index(&index)?;
-    }
-
-    account.update_last_used();
-    save_account(&account)?;
+    set_current_account_id(account_id)?;
+    update_account(account_id, |stored| {
+        stored.update_last_used();
+        Ok(())
+    })?;
 
     crate::modules::logger::log_info(&format!(
         "Account switch core logic completed: {}",
@@ -1078,8 +1265,14 @@ fn apply_profile_to_account(
             is_current: true,
         });
     }
-    save_account(account)?;
-    Ok(())
+    // 只写回设备指纹相关字段，避免覆盖其他并发更新
+    let device_profile = account.device_profile.clone();
+    let device_history = account.device_history.clone();
+    update_account(&account.id, |stored| {
+        stored.device_profile = device_profile;
+        stored.device_history = device_history;
+        Ok(())
+    })
 }
 
 /// List available device profile versions for an account (including baseline)
@@ -1089,8 +1282,10 @@ pub fn list_device_versions(account_id: &str) -> Result<DeviceProfiles, String>
 
 /// Restore device profile by version ID ("baseline" for global original, "current" for current bound)
 pub fn restore_device_version(account_id: &str, version_id: &str) -> Result<DeviceProfile, String> {
-    let mut account = load_account(account_id)?;
+    update_account(account_id, |account| restore_device_version_in(account, version_id))
+}
 
+fn restore_device_version_in(account: &mut Account, version_id: &str) -> Result<DeviceProfile, String> {
     let target_profile = if version_id == "baseline" {
         crate::modules::device::load_global_original().ok_or("Global original profile not found")?
     } else if let Some(v) = account.device_history.iter().find(|v| v.id == version_id) {
@@ -1108,7 +1303,6 @@ pub fn restore_device_version(account_id: &str, version_id: &str) -> Result<Devi
     for h in account.device_history.iter_mut() {
         h.is_current = h.id == version_id;
     }
-    save_account(&account)?;
     Ok(target_profile)
 }
 
@@ -1117,47 +1311,51 @@ pub fn delete_device_version(account_id: &str, version_id: &str) -> Result<(), S
     if version_id == "baseline" {
         return Err("Original profile cannot be deleted".to_string());
     }
-    let mut account = load_account(account_id)?;
-    if account
-        .device_history
-        .iter()
-        .any(|v| v.id == version_id && v.is_current)
-    {
-        return Err("Currently bound profile cannot be deleted".to_string());
-    }
-    let before = account.device_history.len();
-    account.device_history.retain(|v| v.id != version_id);
-    if account.device_history.len() == before {
-        return Err("Historical device profile not found".to_string());
-    }
-    save_account(&account)?;
-    Ok(())
+    update_account(account_id, |account| {
+        if account
+            .device_history
+            .iter()
+            .any(|v| v.id == version_id && v.is_current)
+        {
+            return Err("Currently bound profile cannot be deleted".to_string());
+        }
+        let before = account.device_history.len();
+        account.device_history.retain(|v| v.id != version_id);
+        if account.device_history.len() == before {
+            return Err("Historical device profile not found".to_string());
+        }
+        Ok(())
+    })
 }
 /// Apply account bound device profile to storage.json
 pub fn apply_device_profile(account_id: &str) -> Result<DeviceProfile, String> {
     use crate::modules::device;
-    let mut account = load_account(account_id)?;
+    let account = load_account(account_id)?;
     let profile = account
         .device_profile
         .clone()
         .ok_or("Account has no bound device profile")?;
     let storage_path = device::get_storage_path()?;
     device::write_profile(&storage_path, &profile)?;
-    account.update_last_used();
-    save_account(&account)?;
+    update_account(account_id, |stored| {
+        stored.update_last_used();
+        Ok(())
+    })?;
     Ok(profile)
 }
 
 /// Restore earliest storage.json backup (approximate "original" state)
 pub fn restore_original_device() -> Result<String, String> {
     if let Some(current_id) = get_current_account_id()? {
-        if let Ok(mut account) = load_account(&current_id) {
-            if let Some(original) = crate::modules::device::load_global_original() {
+        if let Some(original) = crate::modules::device::load_global_original() {
+            let reset = update_account(&current_id, |account| {
                 account.device_profile = Some(original);
                 for h in account.device_history.iter_mut() {
                     h.is_current = false;
                 }
-                save_account(&account)?;
+                Ok(())
+            });
+            if reset.is_ok() {
                 return Ok(
                     "Reset current account bound profile to original (not applied to storage)"
                         .to_string(),
@@ -1170,8 +1368,8 @@ pub fn restore_original_device() -> Result<String, String> {
 
 /// Get current account ID
 pub fn get_current_account_id() -> Result<Option<String>, String> {
-    let index = load_account_index()?;
-    Ok(index.current_account_id)
+    let data_dir = get_data_dir()?;
+    account_db::get_current_account_id(&data_dir)
 }
 
 /// Get currently active account details
@@ -1185,101 +1383,97 @@ pub fn get_current_account() -> Result<Option<Account>, String> {
 
 /// Set current active account ID
 pub fn set_current_account_id(account_id: &str) -> Result<(), String> {
-    let _lock = ACCOUNT_INDEX_LOCK
-        .lock()
-        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-    let mut index = load_account_index()?;
-    index.current_account_id = Some(account_id.to_string());
-    save_account_index(&index)
+    let data_dir = get_data_dir()?;
+    let previous = account_db::get_current_account_id(&data_dir)?;
+    account_db::set_current_account_id(&data_dir, Some(account_id))?;
+    // [NEW] 记录切换事件
+    if previous.as_deref() != Some(account_id) {
+        let detail = previous
+            .map(|id| format!("from {}", id))
+            .unwrap_or_default();
+        account_events::record_in(
+            &data_dir,
+            account_id,
+            account_events::AccountEventKind::Switched,
+            &detail,
+        );
+    }
+    Ok(())
 }
 
 /// Update account quota
 pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), String> {
-    let mut account = load_account(account_id)?;
-    account.update_quota(quota);
-
-    // --- Quota protection logic start ---
-    if let Ok(config) = crate::modules::config::load_app_config() {
-        if config.quota_protection.enabled {
-            if let Some(ref q) = account.quota {
-                let threshold = config.quota_protection.threshold_percentage as i32;
-
-                let mut group_min_percentage: HashMap<String, i32> = HashMap::new();
-
-                for model in &q.models {
-                    if let Some(std_id) =
-                        crate::proxy::common::model_mapping::normalize_to_standard_id(&model.name)
-                    {
-                        let entry = group_min_percentage.entry(std_id).or_insert(100);
-                        if model.percentage < *entry {
-                            *entry = model.percentage;
+    // 配置在事务外读取，避免持有写锁时做文件 IO
+    let config = crate::modules::config::load_app_config().ok();
+
+    // 配额与模型保护在同一事务内更新；存储层会通知 TokenManager 刷新内存中的 protected_models
+    update_account(account_id, |account| {
+        account.update_quota(quota);
+
+        // --- Quota protection logic start ---
+        if let Some(config) = config.as_ref() {
+            if config.quota_protection.enabled {
+                if let Some(ref q) = account.quota {
+                    let threshold = crate::modules::quota_forecast::effective_threshold_percentage(
+                        &config.quota_protection,
+                    ) as i32;
+
+                    let mut group_min_percentage: HashMap<String, i32> = HashMap::new();
+
+                    for model in &q.models {
+                        if let Some(std_id) =
+                            crate::proxy::common::model_mapping::normalize_to_standard_id(&model.name)
+                        {
+                            let entry = group_min_percentage.entry(std_id).or_insert(100);
+                            if model.percentage < *entry {
+                                *entry = model.percentage;
+                            }
                         }
                     }
-                }
 
-                for std_id in &config.quota_protection.monitored_models {
-                    let min_pct = group_min_percentage.get(std_id).cloned().unwrap_or(100);
+                    for std_id in &config.quota_protection.monitored_models {
+                        let min_pct = group_min_percentage.get(std_id).cloned().unwrap_or(100);
 
-                    if min_pct <= threshold {
-                        if !account.protected_models.contains(std_id) {
-                            crate::modules::logger::log_info(&format!(
-                                "[Quota] Triggering model protection: {} (Group: {} Min: {}% <= Thres: {}%)",
-                                account.email, std_id, min_pct, threshold
-                            ));
-                            account.protected_models.insert(std_id.clone());
-                        }
-                    } else {
-                        if account.protected_models.contains(std_id) {
-                            crate::modules::logger::log_info(&format!(
-                                "[Quota] Model protection recovered: {} (Group: {} Min: {}% > Thres: {}%)",
-                                account.email, std_id, min_pct, threshold
-                            ));
-                            account.protected_models.remove(std_id);
+                        if min_pct <= threshold {
+                            if !account.protected_models.contains(std_id) {
+                                crate::modules::logger::log_info(&format!(
+                                    "[Quota] Triggering model protection: {} (Group: {} Min: {}% <= Thres: {}%)",
+                                    account.email, std_id, min_pct, threshold
+                                ));
+                                account.protected_models.insert(std_id.clone());
+                            }
+                        } else {
+                            if account.protected_models.contains(std_id) {
+                                crate::modules::logger::log_info(&format!(
+                                    "[Quota] Model protection recovered: {} (Group: {} Min: {}% > Thres: {}%)",
+                                    account.email, std_id, min_pct, threshold
+                                ));
+                                account.protected_models.remove(std_id);
+                            }
                         }
                     }
-                }
 
-                // [Compatibility] Migrate from account-level to model-level protection if previously disabled for quota
-                if account.proxy_disabled
-                    && account
-                        .proxy_disabled_reason
-                        .as_ref()
-                        .map_or(false, |r| r == "quota_protection")
-                {
-                    crate::modules::logger::log_info(&format!(
-                        "[Quota] Migrating account {} from account-level to model-level protection",
-                        account.email
-                    ));
-                    account.proxy_disabled = false;
-                    account.proxy_disabled_reason = None;
-                    account.proxy_disabled_at = None;
+                    // [Compatibility] Migrate from account-level to model-level protection if previously disabled for quota
+                    if account.proxy_disabled
+                        && account
+                            .proxy_disabled_reason
+                            .as_ref()
+                            .map_or(false, |r| r == "quota_protection")
+                    {
+                        crate::modules::logger::log_info(&format!(
+                            "[Quota] Migrating account {} from account-level to model-level protection",
+                            account.email
+                        ));
+                        account.proxy_disabled = false;
+                        account.proxy_disabled_reason = None;
+                        account.proxy_disabled_at = None;
+                    }
                 }
             }
         }
-    }
-    // --- Quota protection logic end ---
-
-    // Save account first
-    save_account(&account)?;
-
-    // [FIX] 同时更新索引文件中的摘要信息，确保列表页图标即时刷新
-    {
-        let _lock = ACCOUNT_INDEX_LOCK
-            .lock()
-            .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-        if let Ok(mut index) = load_account_index() {
-            if let Some(summary) = index.accounts.iter_mut().find(|a| a.id == account_id) {
-                summary.protected_models = account.protected_models.clone();
-                let _ = save_account_index(&index);
-            }
-        }
-    }
-
-    // [FIX] Trigger TokenManager account reload signal
-    // This ensures in-memory protected_models are updated
-    crate::proxy::server::trigger_account_reload(account_id);
-
-    Ok(())
+        // --- Quota protection logic end ---
+        Ok(())
+    })
 }
 
 /// Toggle proxy disabled status for an account
@@ -1288,45 +1482,44 @@ pub fn toggle_proxy_status(
     enable: bool,
     reason: Option<&str>,
 ) -> Result<(), String> {
-    let _lock = ACCOUNT_INDEX_LOCK
-        .lock()
-        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
-
-    let mut account = load_account(account_id)?;
-
-    account.proxy_disabled = !enable;
-    account.proxy_disabled_reason = if !enable {
-        reason.map(|s| s.to_string())
-    } else {
-        None
-    };
-    account.proxy_disabled_at = if !enable {
-        Some(chrono::Utc::now().timestamp())
-    } else {
-        None
-    };
-
-    save_account(&account)?;
-
-    // Also update index summary
-    let mut index = load_account_index()?;
-    if let Some(summary) = index.accounts.iter_mut().find(|a| a.id == account_id) {
-        summary.proxy_disabled = !enable;
-        save_account_index(&index)?;
-    }
-
-    Ok(())
+    update_account(account_id, |account| {
+        account.proxy_disabled = !enable;
+        account.proxy_disabled_reason = if !enable {
+            reason.map(|s| s.to_string())
+        } else {
+            None
+        };
+        account.proxy_disabled_at = if !enable {
+            Some(chrono::Utc::now().timestamp())
+        } else {
+            None
+        };
+        Ok(())
+    })
 }
 
 /// Export accounts by IDs (for backup/migration)
-pub fn export_accounts_by_ids(account_ids: &[String]) -> Result<crate::models::AccountExportResponse, String> {
+///
+/// 导出内容为明文 refresh_token，调用方必须显式确认 (`confirm_plaintext`)
+pub fn export_accounts_by_ids(
+    account_ids: &[String],
+    confirm_plaintext: bool,
+) -> Result<crate::models::AccountExportResponse, String> {
     use crate::models::{AccountExportItem, AccountExportResponse};
-    
+
+    if !confirm_plaintext {
+        return Err("plaintext_export_requires_confirmation".to_string());
+    }
+    crate::modules::logger::log_warn(&format!(
+        "Exporting {} account(s) with plaintext refresh tokens",
+        account_ids.len()
+    ));
+
     let accounts = list_accounts()?;
     
     let export_items: Vec<AccountExportItem> = accounts
         .into_iter()
-        .filter(|acc| account_ids.contains(&acc.id))
+        .filter(|acc| account_ids.contains(&acc.id) && !acc.is_provider())
         .map(|acc| AccountExportItem {
             email: acc.email,
             refresh_token: acc.token.refresh_token,
@@ -1357,6 +1550,13 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
     use crate::modules::oauth;
     use reqwest::StatusCode;
 
+    // [NEW] Provider 账号没有 Google 配额
+    if account.is_provider() {
+        return Err(AppError::Account(
+            "Provider accounts have no Google quota".to_string(),
+        ));
+    }
+
     // 1. Time-based check - ensure Token is valid first
     let token = match oauth::ensure_fresh_token(&account.token, Some(&account.id)).await {
         Ok(t) => t,
@@ -1369,8 +1569,14 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
                 account.disabled = true;
                 account.disabled_at = Some(chrono::Utc::now().timestamp());
                 account.disabled_reason = Some(format!("invalid_grant: {}", e));
-                let _ = save_account(account);
-                crate::proxy::server::trigger_account_reload(&account.id);
+                let (disabled_at, disabled_reason) =
+                    (account.disabled_at, account.disabled_reason.clone());
+                let _ = update_account(&account.id, |stored| {
+                    stored.disabled = true;
+                    stored.disabled_at = disabled_at;
+                    stored.disabled_reason = disabled_reason;
+                    Ok(())
+                });
             }
             return Err(AppError::OAuth(e));
         }
@@ -1469,8 +1675,14 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
                             account.disabled = true;
                             account.disabled_at = Some(chrono::Utc::now().timestamp());
                             account.disabled_reason = Some(format!("invalid_grant: {}", e));
-                            let _ = save_account(account);
-                            crate::proxy::server::trigger_account_reload(&account.id);
+                            let (disabled_at, disabled_reason) =
+                                (account.disabled_at, account.disabled_reason.clone());
+                            let _ = update_account(&account.id, |stored| {
+                                stored.disabled = true;
+                                stored.disabled_at = disabled_at;
+                                stored.disabled_reason = disabled_reason;
+                                Ok(())
+                            });
                         }
                         return Err(AppError::OAuth(e));
                     }
@@ -1568,6 +1780,10 @@ pub async fn refresh_all_quotas_logic() -> Result<RefreshStats, String> {
     let tasks: Vec<_> = accounts
         .into_iter()
         .filter(|account| {
+            // [NEW] Provider 账号没有 Google 配额
+            if account.is_provider() {
+                return false;
+            }
             if account.disabled || account.proxy_disabled {
                 crate::modules::logger::log_info(&format!(
                     "  - Skipping {} ({})",
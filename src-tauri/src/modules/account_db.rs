@@ -0,0 +1,1093 @@
+// 账号存储 (SQLite)
+// 账号、设备指纹与指纹历史统一存入 accounts.db，所有读-改-写操作均在事务中完成，
+// 避免调度器、配额刷新、TokenManager 与管理 API 并发写同一账号文件时互相覆盖。
+// 写入成功后通过广播通道发出变更通知，TokenManager 订阅后即时同步内存池。
+// 账号写入时对比前后状态，在同一事务内追加生命周期事件 (account_events，只追加不删除)。
+
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::sync::broadcast;
+
+use crate::models::{Account, AccountSummary, DeviceProfile, DeviceProfileVersion};
+use crate::modules::account_events::{self, AccountEventKind};
+
+pub const ACCOUNTS_DB_FILE: &str = "accounts.db";
+
+const META_CURRENT_ACCOUNT: &str = "current_account_id";
+
+/// 每个账号每种事件保留的健康历史条数
+const HEALTH_HISTORY_LIMIT: i64 = 500;
+/// 健康历史中状态转换事件的类型
+pub const HEALTH_EVENT_TRANSITION: &str = "transition";
+
+/// 变更来源：反代层 (TokenManager) 自身的写入不需要再回灌给自己
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOrigin {
+    App,
+    Proxy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountChangeKind {
+    Upserted,
+    Deleted,
+}
+
+/// 账号变更通知
+#[derive(Debug, Clone)]
+pub struct AccountChange {
+    /// 所属数据目录 (模拟器等会使用独立目录)
+    pub data_dir: PathBuf,
+    pub account_id: String,
+    pub kind: AccountChangeKind,
+    pub origin: ChangeOrigin,
+}
+
+static CHANGES: Lazy<broadcast::Sender<AccountChange>> = Lazy::new(|| broadcast::channel(256).0);
+
+/// 已完成建表的数据库路径，避免每次连接重复执行 DDL
+static INITIALIZED: Lazy<parking_lot::Mutex<HashSet<PathBuf>>> =
+    Lazy::new(|| parking_lot::Mutex::new(HashSet::new()));
+
+/// 订阅账号变更通知
+pub fn subscribe() -> broadcast::Receiver<AccountChange> {
+    CHANGES.subscribe()
+}
+
+fn publish(data_dir: &Path, account_id: &str, kind: AccountChangeKind, origin: ChangeOrigin) {
+    // 没有订阅者时 send 会返回错误，忽略即可
+    let _ = CHANGES.send(AccountChange {
+        data_dir: data_dir.to_path_buf(),
+        account_id: account_id.to_string(),
+        kind,
+        origin,
+    });
+}
+
+pub fn get_accounts_db_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(ACCOUNTS_DB_FILE)
+}
+
+fn connect_db(data_dir: &Path) -> Result<Connection, String> {
+    let db_path = get_accounts_db_path(data_dir);
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    let mut initialized = INITIALIZED.lock();
+    if !initialized.contains(&db_path) {
+        init_schema(&conn)?;
+        initialized.insert(db_path);
+    }
+
+    Ok(conn)
+}
+
+fn init_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS accounts (
+            id TEXT PRIMARY KEY,
+            email TEXT NOT NULL,
+            name TEXT,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            disabled INTEGER NOT NULL DEFAULT 0,
+            proxy_disabled INTEGER NOT NULL DEFAULT 0,
+            protected_models TEXT NOT NULL DEFAULT '[]',
+            created_at INTEGER NOT NULL,
+            last_used INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            data TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_accounts_email ON accounts (email);
+        CREATE INDEX IF NOT EXISTS idx_accounts_order ON accounts (sort_order);
+
+        CREATE TABLE IF NOT EXISTS device_profiles (
+            account_id TEXT PRIMARY KEY,
+            profile TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS device_history (
+            id TEXT PRIMARY KEY,
+            account_id TEXT NOT NULL,
+            seq INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            profile TEXT NOT NULL,
+            is_current INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_device_history_account ON device_history (account_id, seq);
+
+        CREATE TABLE IF NOT EXISTS account_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS account_health (
+            account_id TEXT PRIMARY KEY,
+            state TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            since INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS account_health_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            account_id TEXT NOT NULL,
+            from_state TEXT,
+            to_state TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'transition'
+        );
+        CREATE INDEX IF NOT EXISTS idx_account_health_history ON account_health_history (account_id, id);
+
+        CREATE TABLE IF NOT EXISTS account_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            account_id TEXT NOT NULL,
+            email TEXT,
+            kind TEXT NOT NULL,
+            detail TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_account_events_account ON account_events (account_id, id);
+        CREATE INDEX IF NOT EXISTS idx_account_events_kind ON account_events (kind, id);",
+    )
+    .map_err(|e| format!("Failed to create accounts schema: {}", e))?;
+    // 旧库补充事件类型列 (已存在时忽略错误)
+    let _ = conn.execute(
+        "ALTER TABLE account_health_history ADD COLUMN kind TEXT NOT NULL DEFAULT 'transition'",
+        [],
+    );
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// 行编解码：账号主体与设备指纹以密文形式存储 (与账号文件一样经过保险库加密)
+// ----------------------------------------------------------------------------
+
+fn encrypt_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, String> {
+    let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    crate::utils::crypto::encrypt_string(&json)
+}
+
+fn decrypt_json<T: serde::de::DeserializeOwned>(raw: &str) -> Result<T, String> {
+    let json = crate::utils::crypto::decrypt_file_content_strict(raw)?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+fn protected_models_json(account: &Account) -> String {
+    let mut models: Vec<&String> = account.protected_models.iter().collect();
+    models.sort();
+    serde_json::to_string(&models).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn read_account(conn: &Connection, account_id: &str) -> Result<Option<Account>, String> {
+    let data: Option<String> = conn
+        .query_row(
+            "SELECT data FROM accounts WHERE id = ?1",
+            params![account_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(data) = data else {
+        return Ok(None);
+    };
+
+    let mut account: Account = decrypt_json(&data)
+        .map_err(|e| format!("failed_to_decode_account {}: {}", account_id, e))?;
+
+    let profile: Option<String> = conn
+        .query_row(
+            "SELECT profile FROM device_profiles WHERE account_id = ?1",
+            params![account_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    account.device_profile = match profile {
+        Some(raw) => Some(decrypt_json::<DeviceProfile>(&raw)?),
+        None => None,
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, created_at, label, profile, is_current FROM device_history
+             WHERE account_id = ?1 ORDER BY seq",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![account_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, bool>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    account.device_history.clear();
+    for row in rows {
+        let (id, created_at, label, profile, is_current) = row.map_err(|e| e.to_string())?;
+        account.device_history.push(DeviceProfileVersion {
+            id,
+            created_at,
+            label,
+            profile: decrypt_json(&profile)?,
+            is_current,
+        });
+    }
+
+    Ok(Some(account))
+}
+
+fn write_account(tx: &Transaction, account: &Account) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+
+    // 设备指纹单独成表，主体中不再重复存储
+    let mut body = account.clone();
+    body.device_profile = None;
+    body.device_history = Vec::new();
+    let data = encrypt_json(&body)?;
+
+    let next_order: i64 = tx
+        .query_row(
+            "SELECT COALESCE(MAX(sort_order), -1) + 1 FROM accounts",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO accounts (id, email, name, sort_order, disabled, proxy_disabled,
+                               protected_models, created_at, last_used, updated_at, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT(id) DO UPDATE SET
+            email = excluded.email,
+            name = excluded.name,
+            disabled = excluded.disabled,
+            proxy_disabled = excluded.proxy_disabled,
+            protected_models = excluded.protected_models,
+            created_at = excluded.created_at,
+            last_used = excluded.last_used,
+            updated_at = excluded.updated_at,
+            data = excluded.data",
+        params![
+            account.id,
+            account.email,
+            account.name,
+            next_order,
+            account.disabled,
+            account.proxy_disabled,
+            protected_models_json(account),
+            account.created_at,
+            account.last_used,
+            now,
+            data,
+        ],
+    )
+    .map_err(|e| format!("failed_to_save_account: {}", e))?;
+
+    match &account.device_profile {
+        Some(profile) => {
+            tx.execute(
+                "INSERT INTO device_profiles (account_id, profile, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(account_id) DO UPDATE SET
+                    profile = excluded.profile, updated_at = excluded.updated_at",
+                params![account.id, encrypt_json(profile)?, now],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        None => {
+            tx.execute(
+                "DELETE FROM device_profiles WHERE account_id = ?1",
+                params![account.id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.execute(
+        "DELETE FROM device_history WHERE account_id = ?1",
+        params![account.id],
+    )
+    .map_err(|e| e.to_string())?;
+    for (seq, version) in account.device_history.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO device_history (id, account_id, seq, created_at, label, profile, is_current)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                version.id,
+                account.id,
+                seq as i64,
+                version.created_at,
+                version.label,
+                encrypt_json(&version.profile)?,
+                version.is_current,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn read_meta(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT value FROM account_meta WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(Option::flatten)
+    .map_err(|e| e.to_string())
+}
+
+fn write_meta(conn: &Connection, key: &str, value: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO account_meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+fn first_account_id(conn: &Connection) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT id FROM accounts ORDER BY sort_order, rowid LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// ----------------------------------------------------------------------------
+// 查询
+// ----------------------------------------------------------------------------
+
+pub fn get_account(data_dir: &Path, account_id: &str) -> Result<Option<Account>, String> {
+    let conn = connect_db(data_dir)?;
+    read_account(&conn, account_id)
+}
+
+/// 按列表顺序返回所有账号；无法解码的账号记录日志后跳过
+pub fn list_accounts(data_dir: &Path) -> Result<Vec<Account>, String> {
+    let conn = connect_db(data_dir)?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM accounts ORDER BY sort_order, rowid")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut accounts = Vec::with_capacity(ids.len());
+    for id in ids {
+        match read_account(&conn, &id) {
+            Ok(Some(account)) => accounts.push(account),
+            Ok(None) => {}
+            Err(e) => tracing::error!("Failed to load account {}: {}", id, e),
+        }
+    }
+    Ok(accounts)
+}
+
+/// 账号摘要 (仅读取明文列，不解密账号主体)
+pub fn list_summaries(data_dir: &Path) -> Result<Vec<AccountSummary>, String> {
+    let conn = connect_db(data_dir)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, email, name, disabled, proxy_disabled, protected_models, created_at, last_used
+             FROM accounts ORDER BY sort_order, rowid",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let protected: String = row.get(5)?;
+            Ok(AccountSummary {
+                id: row.get(0)?,
+                email: row.get(1)?,
+                name: row.get(2)?,
+                disabled: row.get(3)?,
+                proxy_disabled: row.get(4)?,
+                protected_models: serde_json::from_str(&protected).unwrap_or_default(),
+                created_at: row.get(6)?,
+                last_used: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 账号开关状态 (disabled, proxy_disabled)，账号不存在时返回 None
+pub fn get_account_flags(data_dir: &Path, account_id: &str) -> Result<Option<(bool, bool)>, String> {
+    let conn = connect_db(data_dir)?;
+    conn.query_row(
+        "SELECT disabled, proxy_disabled FROM accounts WHERE id = ?1",
+        params![account_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn find_account_id_by_email(data_dir: &Path, email: &str) -> Result<Option<String>, String> {
+    let conn = connect_db(data_dir)?;
+    conn.query_row(
+        "SELECT id FROM accounts WHERE email = ?1 ORDER BY sort_order, rowid LIMIT 1",
+        params![email],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn get_current_account_id(data_dir: &Path) -> Result<Option<String>, String> {
+    let conn = connect_db(data_dir)?;
+    read_meta(&conn, META_CURRENT_ACCOUNT)
+}
+
+// ----------------------------------------------------------------------------
+// 写入 (事务)
+// ----------------------------------------------------------------------------
+
+/// 写入完整账号 (新账号追加到列表末尾，已有账号保留原顺序)
+pub fn save_account(data_dir: &Path, account: &Account, origin: ChangeOrigin) -> Result<(), String> {
+    let mut conn = connect_db(data_dir)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let before = read_account(&tx, &account.id)?;
+    write_account(&tx, account)?;
+    insert_lifecycle_events(&tx, before.as_ref(), account, origin)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    publish(data_dir, &account.id, AccountChangeKind::Upserted, origin);
+    Ok(())
+}
+
+/// 插入新账号；邮箱已存在时报错。`set_current_if_none` 为 true 时若当前无选中账号则设为当前账号
+pub fn insert_account(
+    data_dir: &Path,
+    account: &Account,
+    set_current_if_none: bool,
+    origin: ChangeOrigin,
+) -> Result<(), String> {
+    let mut conn = connect_db(data_dir)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let exists: bool = tx
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM accounts WHERE email = ?1 OR id = ?2)",
+            params![account.email, account.id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if exists {
+        return Err(format!("Account already exists: {}", account.email));
+    }
+    write_account(&tx, account)?;
+    insert_lifecycle_events(&tx, None, account, origin)?;
+    if set_current_if_none && read_meta(&tx, META_CURRENT_ACCOUNT)?.is_none() {
+        write_meta(&tx, META_CURRENT_ACCOUNT, Some(&account.id))?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    publish(data_dir, &account.id, AccountChangeKind::Upserted, origin);
+    Ok(())
+}
+
+/// 在单个事务内读取、修改并写回账号，避免并发写入互相覆盖
+pub fn update_account<T>(
+    data_dir: &Path,
+    account_id: &str,
+    origin: ChangeOrigin,
+    f: impl FnOnce(&mut Account) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut conn = connect_db(data_dir)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let mut account = read_account(&tx, account_id)?
+        .ok_or_else(|| format!("Account not found: {}", account_id))?;
+    let before = account.clone();
+    let result = f(&mut account)?;
+    write_account(&tx, &account)?;
+    insert_lifecycle_events(&tx, Some(&before), &account, origin)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    publish(data_dir, account_id, AccountChangeKind::Upserted, origin);
+    Ok(result)
+}
+
+/// 以 JSON 形式按字段修改账号 (供反代层沿用原有的字段级更新逻辑)
+pub fn update_account_json(
+    data_dir: &Path,
+    account_id: &str,
+    origin: ChangeOrigin,
+    f: impl FnOnce(&mut serde_json::Value),
+) -> Result<(), String> {
+    update_account(data_dir, account_id, origin, |account| {
+        let mut json = serde_json::to_value(&*account).map_err(|e| e.to_string())?;
+        f(&mut json);
+        *account = serde_json::from_value(json)
+            .map_err(|e| format!("failed_to_parse_account_data: {}", e))?;
+        Ok(())
+    })
+}
+
+/// 删除账号及其设备指纹；若删除了当前账号则改选列表中的第一个。返回实际删除的数量
+pub fn delete_accounts(
+    data_dir: &Path,
+    account_ids: &[String],
+    origin: ChangeOrigin,
+) -> Result<usize, String> {
+    let mut conn = connect_db(data_dir)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let mut deleted = Vec::new();
+    let actor = account_events::current_actor(origin);
+    let now = chrono::Utc::now().timestamp();
+    for id in account_ids {
+        let email: Option<String> = tx
+            .query_row(
+                "SELECT email FROM accounts WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let n = tx
+            .execute("DELETE FROM accounts WHERE id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM device_profiles WHERE account_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM device_history WHERE account_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM account_health WHERE account_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM account_health_history WHERE account_id = ?1",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+        if n > 0 {
+            insert_account_event(
+                &tx,
+                id,
+                email.as_deref(),
+                AccountEventKind::Deleted.as_str(),
+                "",
+                &actor,
+                now,
+            )?;
+            deleted.push(id.clone());
+        }
+    }
+    let reselect = match read_meta(&tx, META_CURRENT_ACCOUNT)? {
+        Some(current) => deleted.contains(&current),
+        None => true,
+    };
+    if reselect {
+        let next = first_account_id(&tx)?;
+        write_meta(&tx, META_CURRENT_ACCOUNT, next.as_deref())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for id in &deleted {
+        publish(data_dir, id, AccountChangeKind::Deleted, origin);
+    }
+    Ok(deleted.len())
+}
+
+/// 按给定 ID 顺序重排账号，未列出的账号保持相对顺序排在末尾
+pub fn reorder_accounts(data_dir: &Path, account_ids: &[String]) -> Result<usize, String> {
+    let mut conn = connect_db(data_dir)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let existing: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT id FROM accounts ORDER BY sort_order, rowid")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut seen = HashSet::new();
+    let mut ordered: Vec<&String> = account_ids
+        .iter()
+        .filter(|id| existing.contains(id) && seen.insert(id.as_str()))
+        .collect();
+    ordered.extend(existing.iter().filter(|id| !account_ids.contains(id)));
+
+    for (order, id) in ordered.iter().enumerate() {
+        tx.execute(
+            "UPDATE accounts SET sort_order = ?1 WHERE id = ?2",
+            params![order as i64, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(ordered.len())
+}
+
+pub fn set_current_account_id(data_dir: &Path, account_id: Option<&str>) -> Result<(), String> {
+    let conn = connect_db(data_dir)?;
+    write_meta(&conn, META_CURRENT_ACCOUNT, account_id)
+}
+
+// ----------------------------------------------------------------------------
+// 健康状态 (状态机逻辑见 account_health 模块，这里只负责持久化)
+// ----------------------------------------------------------------------------
+
+/// 账号当前健康状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthRecord {
+    pub state: String,
+    pub reason: String,
+    /// 进入当前状态的时间
+    pub since: i64,
+    /// 最近一次状态或原因变化的时间
+    pub updated_at: i64,
+}
+
+/// 健康历史记录 (状态转换或 Token 刷新等事件)
+#[derive(Debug, Clone)]
+pub struct HealthTransitionRecord {
+    pub id: i64,
+    pub account_id: String,
+    pub kind: String,
+    pub from_state: Option<String>,
+    pub to_state: String,
+    pub reason: String,
+    pub created_at: i64,
+}
+
+fn read_health(conn: &Connection, account_id: &str) -> Result<Option<HealthRecord>, String> {
+    conn.query_row(
+        "SELECT state, reason, since, updated_at FROM account_health WHERE account_id = ?1",
+        params![account_id],
+        |row| {
+            Ok(HealthRecord {
+                state: row.get(0)?,
+                reason: row.get(1)?,
+                since: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 写入账号当前健康状态；状态变化时追加一条转换历史。
+/// 返回写入后的状态及本次是否发生了状态转换 (状态与原因均未变化时不写库)
+pub fn record_health(
+    data_dir: &Path,
+    account_id: &str,
+    state: &str,
+    reason: &str,
+    now: i64,
+) -> Result<(HealthRecord, bool), String> {
+    let mut conn = connect_db(data_dir)?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let previous = read_health(&tx, account_id)?;
+
+    let (record, transitioned) = match previous {
+        Some(prev) if prev.state == state && prev.reason == reason => return Ok((prev, false)),
+        Some(prev) if prev.state == state => (
+            HealthRecord {
+                reason: reason.to_string(),
+                updated_at: now,
+                ..prev
+            },
+            false,
+        ),
+        previous => {
+            insert_health_history(
+                &tx,
+                account_id,
+                HEALTH_EVENT_TRANSITION,
+                previous.map(|p| p.state).as_deref(),
+                state,
+                reason,
+                now,
+            )?;
+            (
+                HealthRecord {
+                    state: state.to_string(),
+                    reason: reason.to_string(),
+                    since: now,
+                    updated_at: now,
+                },
+                true,
+            )
+        }
+    };
+
+    tx.execute(
+        "INSERT INTO account_health (account_id, state, reason, since, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(account_id) DO UPDATE SET
+            state = excluded.state, reason = excluded.reason,
+            since = excluded.since, updated_at = excluded.updated_at",
+        params![account_id, record.state, record.reason, record.since, record.updated_at],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok((record, transitioned))
+}
+
+/// 追加一条健康历史并按 (账号, 事件类型) 裁剪，避免高频事件挤掉状态转换记录
+fn insert_health_history(
+    conn: &Connection,
+    account_id: &str,
+    kind: &str,
+    from_state: Option<&str>,
+    to_state: &str,
+    reason: &str,
+    now: i64,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO account_health_history (account_id, kind, from_state, to_state, reason, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![account_id, kind, from_state, to_state, reason, now],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM account_health_history WHERE account_id = ?1 AND kind = ?2 AND id NOT IN (
+            SELECT id FROM account_health_history WHERE account_id = ?1 AND kind = ?2
+            ORDER BY id DESC LIMIT ?3
+        )",
+        params![account_id, kind, HEALTH_HISTORY_LIMIT],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 记录一条不改变健康状态的事件 (如 Token 刷新结果)，from/to 均为账号当前状态
+pub fn append_health_event(
+    data_dir: &Path,
+    account_id: &str,
+    kind: &str,
+    state: &str,
+    reason: &str,
+    now: i64,
+) -> Result<(), String> {
+    let conn = connect_db(data_dir)?;
+    insert_health_history(&conn, account_id, kind, Some(state), state, reason, now)
+}
+
+/// 健康历史 (新到旧)，`account_id` 为空时返回全部账号，`kind` 为空时返回全部事件类型
+pub fn list_health_history(
+    data_dir: &Path,
+    account_id: Option<&str>,
+    kind: Option<&str>,
+    limit: usize,
+) -> Result<Vec<HealthTransitionRecord>, String> {
+    let conn = connect_db(data_dir)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, account_id, kind, from_state, to_state, reason, created_at
+             FROM account_health_history
+             WHERE (?1 IS NULL OR account_id = ?1) AND (?2 IS NULL OR kind = ?2)
+             ORDER BY id DESC LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![account_id, kind, limit as i64], |row| {
+            Ok(HealthTransitionRecord {
+                id: row.get(0)?,
+                account_id: row.get(1)?,
+                kind: row.get(2)?,
+                from_state: row.get(3)?,
+                to_state: row.get(4)?,
+                reason: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+// ----------------------------------------------------------------------------
+// 生命周期事件 (事件类型与操作者见 account_events 模块，这里只负责持久化)
+// ----------------------------------------------------------------------------
+
+/// 生命周期事件条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountEventRecord {
+    pub id: i64,
+    pub account_id: String,
+    pub email: Option<String>,
+    pub kind: String,
+    pub detail: String,
+    pub actor: String,
+    pub created_at: i64,
+}
+
+/// 事件查询条件 (均为可选)，按 id 倒序返回
+#[derive(Debug, Clone, Default)]
+pub struct AccountEventFilter {
+    pub account_id: Option<String>,
+    /// 事件类型 (任一匹配)
+    pub kinds: Vec<String>,
+    pub actor: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// 游标: 只返回 id 小于该值的事件
+    pub before_id: Option<i64>,
+    pub limit: usize,
+}
+
+/// 追加一条事件；email 为空时取账号当前邮箱
+fn insert_account_event(
+    conn: &Connection,
+    account_id: &str,
+    email: Option<&str>,
+    kind: &str,
+    detail: &str,
+    actor: &str,
+    now: i64,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO account_events (account_id, email, kind, detail, actor, created_at)
+         VALUES (?1, COALESCE(?2, (SELECT email FROM accounts WHERE id = ?1)), ?3, ?4, ?5, ?6)",
+        params![account_id, email, kind, detail, actor, now],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to record account event: {}", e))
+}
+
+/// 对比写入前后的账号，在当前事务内追加字段变化产生的事件
+fn insert_lifecycle_events(
+    tx: &Transaction,
+    before: Option<&Account>,
+    after: &Account,
+    origin: ChangeOrigin,
+) -> Result<(), String> {
+    let events = account_events::lifecycle_changes(before, after);
+    if events.is_empty() {
+        return Ok(());
+    }
+    let actor = account_events::current_actor(origin);
+    let now = chrono::Utc::now().timestamp();
+    for (kind, detail) in events {
+        insert_account_event(
+            tx,
+            &after.id,
+            Some(&after.email),
+            kind.as_str(),
+            &detail,
+            &actor,
+            now,
+        )?;
+    }
+    Ok(())
+}
+
+/// 追加一条由调用方显式记录的事件 (如当前账号切换、代理绑定)
+pub fn append_account_event(
+    data_dir: &Path,
+    account_id: &str,
+    kind: &str,
+    detail: &str,
+    actor: &str,
+    now: i64,
+) -> Result<(), String> {
+    let conn = connect_db(data_dir)?;
+    insert_account_event(&conn, account_id, None, kind, detail, actor, now)
+}
+
+/// 查询生命周期事件 (新到旧)
+pub fn list_account_events(
+    data_dir: &Path,
+    filter: &AccountEventFilter,
+) -> Result<Vec<AccountEventRecord>, String> {
+    let conn = connect_db(data_dir)?;
+    let mut sql = String::from(
+        "SELECT id, account_id, email, kind, detail, actor, created_at FROM account_events
+         WHERE (?1 IS NULL OR account_id = ?1) AND (?2 IS NULL OR actor = ?2)
+           AND (?3 IS NULL OR created_at >= ?3) AND (?4 IS NULL OR created_at <= ?4)
+           AND (?5 IS NULL OR id < ?5)",
+    );
+    let mut values: Vec<rusqlite::types::Value> = vec![
+        filter.account_id.clone().into(),
+        filter.actor.clone().into(),
+        filter.since.into(),
+        filter.until.into(),
+        filter.before_id.into(),
+    ];
+    if !filter.kinds.is_empty() {
+        let placeholders: Vec<String> = (0..filter.kinds.len())
+            .map(|i| format!("?{}", values.len() + i + 1))
+            .collect();
+        sql.push_str(&format!(" AND kind IN ({})", placeholders.join(", ")));
+        values.extend(filter.kinds.iter().cloned().map(Into::into));
+    }
+    sql.push_str(&format!(" ORDER BY id DESC LIMIT ?{}", values.len() + 1));
+    values.push((filter.limit as i64).into());
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| {
+            Ok(AccountEventRecord {
+                id: row.get(0)?,
+                account_id: row.get(1)?,
+                email: row.get(2)?,
+                kind: row.get(3)?,
+                detail: row.get(4)?,
+                actor: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenData;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("account_db_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn account(id: &str, email: &str) -> Account {
+        Account::new(
+            id.to_string(),
+            email.to_string(),
+            TokenData::new("atk".into(), format!("rtk-{}", id), 3600, None, None, None),
+        )
+    }
+
+    fn profile(seed: &str) -> DeviceProfile {
+        DeviceProfile {
+            machine_id: format!("m-{}", seed),
+            mac_machine_id: format!("mac-{}", seed),
+            dev_device_id: format!("dev-{}", seed),
+            sqm_id: format!("sqm-{}", seed),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_with_device_profiles_and_order() {
+        let dir = temp_dir();
+        let mut a = account("a", "a@test.com");
+        a.device_profile = Some(profile("1"));
+        a.device_history = vec![
+            DeviceProfileVersion {
+                id: "v1".into(),
+                created_at: 1,
+                label: "generated".into(),
+                profile: profile("0"),
+                is_current: false,
+            },
+            DeviceProfileVersion {
+                id: "v2".into(),
+                created_at: 2,
+                label: "capture".into(),
+                profile: profile("1"),
+                is_current: true,
+            },
+        ];
+        insert_account(&dir, &a, true, ChangeOrigin::App).unwrap();
+        insert_account(&dir, &account("b", "b@test.com"), true, ChangeOrigin::App).unwrap();
+        assert!(insert_account(&dir, &account("c", "a@test.com"), true, ChangeOrigin::App).is_err());
+
+        let loaded = get_account(&dir, "a").unwrap().unwrap();
+        assert_eq!(loaded.token.refresh_token, "rtk-a");
+        assert_eq!(loaded.device_profile.unwrap().machine_id, "m-1");
+        let history: Vec<_> = loaded.device_history.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(history, vec!["v1", "v2"]);
+        assert_eq!(get_current_account_id(&dir).unwrap().as_deref(), Some("a"));
+
+        // 密文落盘：refresh_token 不以明文出现在数据库中
+        let raw = std::fs::read(get_accounts_db_path(&dir)).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("rtk-a"));
+
+        reorder_accounts(&dir, &["b".to_string()]).unwrap();
+        let ids: Vec<_> = list_summaries(&dir).unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["b", "a"]);
+
+        // 删除当前账号后改选列表首个账号，设备指纹一并删除
+        assert_eq!(delete_accounts(&dir, &["a".to_string()], ChangeOrigin::App).unwrap(), 1);
+        assert_eq!(get_current_account_id(&dir).unwrap().as_deref(), Some("b"));
+        assert!(get_account(&dir, "a").unwrap().is_none());
+        let conn = connect_db(&dir).unwrap();
+        let leftover: i64 = conn
+            .query_row("SELECT COUNT(*) FROM device_history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(leftover, 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_transactional_updates_and_change_notifications() {
+        let dir = temp_dir();
+        let mut rx = subscribe();
+        save_account(&dir, &account("a", "a@test.com"), ChangeOrigin::App).unwrap();
+
+        // 并发的字段级更新不会互相覆盖
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let dir = dir.clone();
+                std::thread::spawn(move || {
+                    update_account(&dir, "a", ChangeOrigin::Proxy, |acc| {
+                        acc.protected_models.insert(format!("model-{}", i));
+                        Ok(())
+                    })
+                    .unwrap();
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let loaded = get_account(&dir, "a").unwrap().unwrap();
+        assert_eq!(loaded.protected_models.len(), 8);
+        assert_eq!(list_summaries(&dir).unwrap()[0].protected_models.len(), 8);
+
+        update_account_json(&dir, "a", ChangeOrigin::Proxy, |json| {
+            json["disabled"] = serde_json::Value::Bool(true);
+        })
+        .unwrap();
+        assert_eq!(get_account_flags(&dir, "a").unwrap(), Some((true, false)));
+        assert!(update_account(&dir, "missing", ChangeOrigin::App, |_| Ok(())).is_err());
+
+        let mut seen = Vec::new();
+        while let Ok(change) = rx.try_recv() {
+            if change.data_dir == dir {
+                seen.push((change.kind, change.origin));
+            }
+        }
+        assert_eq!(seen.len(), 10);
+        assert_eq!(seen[0], (AccountChangeKind::Upserted, ChangeOrigin::App));
+        assert_eq!(seen[9], (AccountChangeKind::Upserted, ChangeOrigin::Proxy));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,541 @@
+// 账号生命周期事件 (审计轨迹)
+// 创建 / 删除 / 切换 / 禁用 / 403 / 验证阻止 / 设备指纹变更 / 代理绑定等事件追加写入 accounts.db 的
+// account_events 表，只追加不修改，账号删除后仍保留。
+// 账号字段类事件由 account_db 在写入事务内对比前后状态生成 (覆盖 modules::account 与
+// TokenManager::set_forbidden / set_validation_block 等所有写入路径)，当前账号切换与代理绑定由调用方显式记录。
+// 操作者: 管理 API 请求内为鉴权后的调用者 (with_actor)，反代层写入为 proxy，其余 (桌面端与后台任务) 为 local。
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+
+use crate::models::Account;
+use crate::modules::account_db::{self, AccountEventFilter, AccountEventRecord, ChangeOrigin};
+
+/// 反代层 (TokenManager) 写入的操作者
+pub const ACTOR_PROXY: &str = "proxy";
+/// 桌面端与后台任务的操作者
+pub const ACTOR_LOCAL: &str = "local";
+
+/// 单次查询返回的默认条数
+const DEFAULT_EVENT_LIMIT: usize = 100;
+/// 单次查询返回的最大条数
+const MAX_EVENT_LIMIT: usize = 1000;
+/// 事件详情的最大字符数 (403 / 验证阻止原因可能是完整的上游响应)
+const MAX_DETAIL_CHARS: usize = 500;
+
+tokio::task_local! {
+    static ACTOR: String;
+}
+
+/// 账号生命周期事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountEventKind {
+    Created,
+    Deleted,
+    /// 切换为当前账号
+    Switched,
+    Disabled,
+    Enabled,
+    ProxyDisabled,
+    ProxyEnabled,
+    /// 上游返回 403
+    Forbidden,
+    ForbiddenCleared,
+    ValidationBlocked,
+    ValidationCleared,
+    /// 设备指纹绑定 / 轮换 / 清除
+    DeviceProfileChanged,
+    ProxyBound,
+    ProxyUnbound,
+    LabelChanged,
+    TagsChanged,
+    /// Provider 账号端点配置变更
+    ProviderChanged,
+}
+
+impl AccountEventKind {
+    pub const ALL: [AccountEventKind; 17] = [
+        AccountEventKind::Created,
+        AccountEventKind::Deleted,
+        AccountEventKind::Switched,
+        AccountEventKind::Disabled,
+        AccountEventKind::Enabled,
+        AccountEventKind::ProxyDisabled,
+        AccountEventKind::ProxyEnabled,
+        AccountEventKind::Forbidden,
+        AccountEventKind::ForbiddenCleared,
+        AccountEventKind::ValidationBlocked,
+        AccountEventKind::ValidationCleared,
+        AccountEventKind::DeviceProfileChanged,
+        AccountEventKind::ProxyBound,
+        AccountEventKind::ProxyUnbound,
+        AccountEventKind::LabelChanged,
+        AccountEventKind::TagsChanged,
+        AccountEventKind::ProviderChanged,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AccountEventKind::Created => "created",
+            AccountEventKind::Deleted => "deleted",
+            AccountEventKind::Switched => "switched",
+            AccountEventKind::Disabled => "disabled",
+            AccountEventKind::Enabled => "enabled",
+            AccountEventKind::ProxyDisabled => "proxy_disabled",
+            AccountEventKind::ProxyEnabled => "proxy_enabled",
+            AccountEventKind::Forbidden => "forbidden",
+            AccountEventKind::ForbiddenCleared => "forbidden_cleared",
+            AccountEventKind::ValidationBlocked => "validation_blocked",
+            AccountEventKind::ValidationCleared => "validation_cleared",
+            AccountEventKind::DeviceProfileChanged => "device_profile_changed",
+            AccountEventKind::ProxyBound => "proxy_bound",
+            AccountEventKind::ProxyUnbound => "proxy_unbound",
+            AccountEventKind::LabelChanged => "label_changed",
+            AccountEventKind::TagsChanged => "tags_changed",
+            AccountEventKind::ProviderChanged => "provider_changed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+}
+
+/// 在指定操作者的作用域内执行 (管理 API 中间件包裹每个请求)
+pub async fn with_actor<F: Future>(actor: String, f: F) -> F::Output {
+    ACTOR.scope(actor, f).await
+}
+
+/// 当前写入的操作者
+pub fn current_actor(origin: ChangeOrigin) -> String {
+    ACTOR
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| match origin {
+            ChangeOrigin::Proxy => ACTOR_PROXY.to_string(),
+            ChangeOrigin::App => ACTOR_LOCAL.to_string(),
+        })
+}
+
+fn truncate_detail(detail: &str) -> String {
+    if detail.chars().count() <= MAX_DETAIL_CHARS {
+        detail.to_string()
+    } else {
+        let mut truncated: String = detail.chars().take(MAX_DETAIL_CHARS).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+/// 对比写入前后的账号，返回需要记录的事件与详情 (before 为空表示新建)
+pub fn lifecycle_changes(
+    before: Option<&Account>,
+    after: &Account,
+) -> Vec<(AccountEventKind, String)> {
+    let Some(before) = before else {
+        let detail = after.account_type.clone().unwrap_or_default();
+        return vec![(AccountEventKind::Created, detail)];
+    };
+
+    let mut events = Vec::new();
+    let reason = |r: &Option<String>| truncate_detail(r.as_deref().unwrap_or(""));
+
+    match (before.disabled, after.disabled) {
+        (false, true) => events.push((AccountEventKind::Disabled, reason(&after.disabled_reason))),
+        (true, false) => events.push((AccountEventKind::Enabled, String::new())),
+        _ => {}
+    }
+    match (before.proxy_disabled, after.proxy_disabled) {
+        (false, true) => events.push((
+            AccountEventKind::ProxyDisabled,
+            reason(&after.proxy_disabled_reason),
+        )),
+        (true, false) => events.push((AccountEventKind::ProxyEnabled, String::new())),
+        _ => {}
+    }
+
+    let forbidden = |a: &Account| a.quota.as_ref().is_some_and(|q| q.is_forbidden);
+    match (forbidden(before), forbidden(after)) {
+        (false, true) => events.push((
+            AccountEventKind::Forbidden,
+            reason(
+                &after
+                    .quota
+                    .as_ref()
+                    .and_then(|q| q.forbidden_reason.clone()),
+            ),
+        )),
+        (true, false) => events.push((AccountEventKind::ForbiddenCleared, String::new())),
+        _ => {}
+    }
+
+    match (before.validation_blocked, after.validation_blocked) {
+        (false, true) => {
+            let mut detail = reason(&after.validation_blocked_reason);
+            if let Some(until) = after.validation_blocked_until {
+                detail = format!("until {}: {}", until, detail);
+            }
+            events.push((AccountEventKind::ValidationBlocked, detail));
+        }
+        (true, false) => events.push((AccountEventKind::ValidationCleared, String::new())),
+        _ => {}
+    }
+
+    if before.device_profile != after.device_profile {
+        let detail = match (&before.device_profile, &after.device_profile) {
+            (None, Some(_)) => "bound",
+            (Some(_), None) => "cleared",
+            _ => "rotated",
+        };
+        events.push((AccountEventKind::DeviceProfileChanged, detail.to_string()));
+    }
+
+    if before.custom_label != after.custom_label {
+        events.push((
+            AccountEventKind::LabelChanged,
+            format!(
+                "{} -> {}",
+                before.custom_label.as_deref().unwrap_or(""),
+                after.custom_label.as_deref().unwrap_or("")
+            ),
+        ));
+    }
+
+    if before.tags != after.tags {
+        let added = after.tags.iter().filter(|t| !before.tags.contains(t));
+        let removed = before.tags.iter().filter(|t| !after.tags.contains(t));
+        let detail: Vec<String> = added
+            .map(|t| format!("+{}", t))
+            .chain(removed.map(|t| format!("-{}", t)))
+            .collect();
+        events.push((AccountEventKind::TagsChanged, detail.join(" ")));
+    }
+
+    if before.provider != after.provider {
+        // 不记录 api_key，只记录是否变更
+        let detail = match (&before.provider, &after.provider) {
+            (Some(b), Some(a)) => {
+                let mut changed = Vec::new();
+                if b.base_url != a.base_url {
+                    changed.push(format!("base_url={}", a.base_url));
+                }
+                if b.api_key != a.api_key {
+                    changed.push("api_key".to_string());
+                }
+                if b.protocol != a.protocol || b.routing != a.routing {
+                    changed.push(format!("{:?}/{:?}", a.protocol, a.routing).to_lowercase());
+                }
+                if b.models != a.models || b.model_mapping != a.model_mapping {
+                    changed.push(format!("models={}", a.models.len()));
+                }
+                changed.join(" ")
+            }
+            (_, Some(a)) => a.base_url.clone(),
+            _ => String::new(),
+        };
+        events.push((AccountEventKind::ProviderChanged, detail));
+    }
+
+    events
+}
+
+/// 显式记录一条事件 (写入失败只记日志，不影响业务操作)
+pub fn record_in(data_dir: &Path, account_id: &str, kind: AccountEventKind, detail: &str) {
+    if let Err(e) = account_db::append_account_event(
+        data_dir,
+        account_id,
+        kind.as_str(),
+        &truncate_detail(detail),
+        &current_actor(ChangeOrigin::App),
+        chrono::Utc::now().timestamp(),
+    ) {
+        tracing::warn!(
+            "[Events] Failed to record {} for {}: {}",
+            kind.as_str(),
+            account_id,
+            e
+        );
+    }
+}
+
+/// 在默认数据目录中显式记录一条事件
+pub fn record(account_id: &str, kind: AccountEventKind, detail: &str) {
+    match crate::modules::account::get_data_dir() {
+        Ok(data_dir) => record_in(&data_dir, account_id, kind, detail),
+        Err(e) => tracing::warn!("[Events] Failed to resolve data dir: {}", e),
+    }
+}
+
+/// 生命周期事件
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountEvent {
+    pub id: i64,
+    pub account_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub kind: AccountEventKind,
+    pub detail: String,
+    pub actor: String,
+    pub created_at: i64,
+}
+
+impl AccountEvent {
+    fn from_record(record: AccountEventRecord) -> Option<Self> {
+        Some(Self {
+            id: record.id,
+            account_id: record.account_id,
+            email: record.email,
+            kind: AccountEventKind::parse(&record.kind)?,
+            detail: record.detail,
+            actor: record.actor,
+            created_at: record.created_at,
+        })
+    }
+}
+
+/// 事件查询参数 (`/accounts/events` 与 `/accounts/:id/events`)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEventQuery {
+    #[serde(default, alias = "account_id")]
+    pub account_id: Option<String>,
+    /// 逗号分隔的事件类型
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub until: Option<i64>,
+    /// 游标: 上一页的 next_before
+    #[serde(default)]
+    pub before: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// 事件分页结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountEventPage {
+    pub events: Vec<AccountEvent>,
+    /// 还有更早的事件时，作为下一页的 before 参数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_before: Option<i64>,
+}
+
+impl AccountEventQuery {
+    fn to_filter(&self) -> Result<AccountEventFilter, String> {
+        let kinds = self
+            .kind
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(|k| {
+                AccountEventKind::parse(k)
+                    .map(|kind| kind.as_str().to_string())
+                    .ok_or_else(|| format!("Unknown event kind: {}", k))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let non_empty = |v: &Option<String>| v.clone().filter(|v| !v.trim().is_empty());
+        Ok(AccountEventFilter {
+            account_id: non_empty(&self.account_id),
+            kinds,
+            actor: non_empty(&self.actor),
+            since: self.since,
+            until: self.until,
+            before_id: self.before,
+            limit: self
+                .limit
+                .unwrap_or(DEFAULT_EVENT_LIMIT)
+                .clamp(1, MAX_EVENT_LIMIT),
+        })
+    }
+}
+
+/// 按条件查询事件 (新到旧)
+pub fn list_events_in(
+    data_dir: &Path,
+    query: &AccountEventQuery,
+) -> Result<AccountEventPage, String> {
+    let filter = query.to_filter()?;
+    let records = account_db::list_account_events(data_dir, &filter)?;
+    let next_before = (records.len() == filter.limit)
+        .then(|| records.last().map(|r| r.id))
+        .flatten();
+    Ok(AccountEventPage {
+        events: records
+            .into_iter()
+            .filter_map(AccountEvent::from_record)
+            .collect(),
+        next_before,
+    })
+}
+
+/// 在默认数据目录中查询事件
+pub fn list_events(query: &AccountEventQuery) -> Result<AccountEventPage, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    list_events_in(&data_dir, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DeviceProfile, QuotaData, TokenData};
+
+    fn temp_dir() -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("account_events_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn account(id: &str) -> Account {
+        Account::new(
+            id.to_string(),
+            format!("{}@test.com", id),
+            TokenData::new("atk".into(), "rtk".into(), 3600, None, None, None),
+        )
+    }
+
+    fn kinds(query: AccountEventQuery, dir: &Path) -> Vec<AccountEventKind> {
+        list_events_in(dir, &query)
+            .unwrap()
+            .events
+            .into_iter()
+            .map(|e| e.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_lifecycle_changes() {
+        let before = account("a");
+        assert_eq!(
+            lifecycle_changes(None, &before)[0].0,
+            AccountEventKind::Created
+        );
+        assert!(lifecycle_changes(Some(&before), &before).is_empty());
+
+        let mut after = before.clone();
+        after.disabled = true;
+        after.disabled_reason = Some("invalid_grant".into());
+        let mut quota = QuotaData::new();
+        quota.is_forbidden = true;
+        quota.forbidden_reason = Some("x".repeat(2000));
+        after.quota = Some(quota);
+        after.device_profile = Some(DeviceProfile {
+            machine_id: "m".into(),
+            mac_machine_id: "mac".into(),
+            dev_device_id: "dev".into(),
+            sqm_id: "sqm".into(),
+        });
+        after.tags = vec!["region:eu".into()];
+
+        let events = lifecycle_changes(Some(&before), &after);
+        let kinds: Vec<_> = events.iter().map(|(k, _)| *k).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AccountEventKind::Disabled,
+                AccountEventKind::Forbidden,
+                AccountEventKind::DeviceProfileChanged,
+                AccountEventKind::TagsChanged,
+            ]
+        );
+        assert_eq!(events[0].1, "invalid_grant");
+        assert_eq!(events[1].1.chars().count(), MAX_DETAIL_CHARS + 1);
+        assert_eq!(events[2].1, "bound");
+        assert_eq!(events[3].1, "+region:eu");
+
+        // 反向变化
+        let kinds: Vec<_> = lifecycle_changes(Some(&after), &before)
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert!(kinds.contains(&AccountEventKind::Enabled));
+        assert!(kinds.contains(&AccountEventKind::ForbiddenCleared));
+    }
+
+    #[tokio::test]
+    async fn test_events_are_recorded_with_actor() {
+        let dir = temp_dir();
+        account_db::save_account(&dir, &account("a"), ChangeOrigin::App).unwrap();
+
+        // 反代层写入
+        account_db::update_account(&dir, "a", ChangeOrigin::Proxy, |acc| {
+            acc.validation_blocked = true;
+            acc.validation_blocked_until = Some(2_000);
+            Ok(())
+        })
+        .unwrap();
+
+        // 管理 API 请求作用域内的写入
+        let admin_dir = dir.clone();
+        with_actor("alice".to_string(), async move {
+            account_db::update_account(&admin_dir, "a", ChangeOrigin::App, |acc| {
+                acc.proxy_disabled = true;
+                Ok(())
+            })
+            .unwrap();
+            record_in(&admin_dir, "a", AccountEventKind::ProxyBound, "px-1");
+        })
+        .await;
+
+        account_db::delete_accounts(&dir, &["a".to_string()], ChangeOrigin::App).unwrap();
+
+        let page = list_events_in(&dir, &AccountEventQuery::default()).unwrap();
+        let summary: Vec<_> = page
+            .events
+            .iter()
+            .map(|e| (e.kind, e.actor.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (AccountEventKind::Deleted, ACTOR_LOCAL),
+                (AccountEventKind::ProxyBound, "alice"),
+                (AccountEventKind::ProxyDisabled, "alice"),
+                (AccountEventKind::ValidationBlocked, ACTOR_PROXY),
+                (AccountEventKind::Created, ACTOR_LOCAL),
+            ]
+        );
+        // 事件在账号删除后保留，且带有邮箱
+        assert!(page
+            .events
+            .iter()
+            .all(|e| e.email.as_deref() == Some("a@test.com")));
+        assert!(page.next_before.is_none());
+
+        // 过滤与游标分页
+        let query =
+            |kind: Option<&str>, actor: Option<&str>, limit: Option<usize>| AccountEventQuery {
+                account_id: Some("a".into()),
+                kind: kind.map(str::to_string),
+                actor: actor.map(str::to_string),
+                limit,
+                ..Default::default()
+            };
+        assert_eq!(
+            kinds(query(Some("deleted,created"), None, None), &dir),
+            vec![AccountEventKind::Deleted, AccountEventKind::Created]
+        );
+        assert_eq!(kinds(query(None, Some("alice"), None), &dir).len(), 2);
+        let first = list_events_in(&dir, &query(None, None, Some(2))).unwrap();
+        assert_eq!(first.events.len(), 2);
+        let rest = list_events_in(
+            &dir,
+            &AccountEventQuery {
+                before: first.next_before,
+                ..query(None, None, Some(10))
+            },
+        )
+        .unwrap();
+        assert_eq!(rest.events.len(), 3);
+        assert!(list_events_in(&dir, &query(Some("exploded"), None, None)).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,550 @@
+// 账号健康状态机
+// 将分散在账号标记 (disabled / proxy_disabled / validation_blocked / protected_models)、
+// 配额 (is_forbidden) 与反代运行时状态 (限流记录、熔断失败计数、健康分) 中的信息
+// 归并为单一健康状态，状态变化及原因持久化到 accounts.db。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::models::Account;
+use crate::modules::account_db::{self, HealthTransitionRecord};
+
+/// 单个账号返回的转换历史条数
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+/// 账号池视图中返回的最近转换条数
+const POOL_RECENT_TRANSITIONS: usize = 50;
+/// 健康分低于该值视为降级
+const DEGRADED_HEALTH_SCORE: f32 = 0.5;
+
+/// 账号健康状态 (按严重程度从低到高)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Healthy,
+    Degraded,
+    QuotaProtected,
+    RateLimited,
+    ValidationRequired,
+    Forbidden,
+    Disabled,
+}
+
+impl HealthState {
+    pub const ALL: [HealthState; 7] = [
+        HealthState::Healthy,
+        HealthState::Degraded,
+        HealthState::QuotaProtected,
+        HealthState::RateLimited,
+        HealthState::ValidationRequired,
+        HealthState::Forbidden,
+        HealthState::Disabled,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HealthState::Healthy => "healthy",
+            HealthState::Degraded => "degraded",
+            HealthState::QuotaProtected => "quota_protected",
+            HealthState::RateLimited => "rate_limited",
+            HealthState::ValidationRequired => "validation_required",
+            HealthState::Forbidden => "forbidden",
+            HealthState::Disabled => "disabled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    /// 该状态下账号是否仍可参与调度 (配额保护只屏蔽部分模型)
+    pub fn is_available(self) -> bool {
+        matches!(
+            self,
+            HealthState::Healthy | HealthState::Degraded | HealthState::QuotaProtected
+        )
+    }
+}
+
+/// 健康历史事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthEventKind {
+    /// 健康状态转换
+    Transition,
+    /// 后台 Token 刷新结果 (不改变状态，from/to 为当时的状态)
+    TokenRefresh,
+}
+
+impl HealthEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HealthEventKind::Transition => account_db::HEALTH_EVENT_TRANSITION,
+            HealthEventKind::TokenRefresh => "token_refresh",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [HealthEventKind::Transition, HealthEventKind::TokenRefresh]
+            .into_iter()
+            .find(|k| k.as_str() == value)
+    }
+}
+
+/// 反代运行时信号 (反代未运行时为空)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuntimeSignals {
+    /// 账号级限流截止时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limited_until: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_reason: Option<String>,
+    /// 仍处于模型级限流中的模型
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub limited_models: Vec<String>,
+    /// 熔断退避的连续失败次数
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_score: Option<f32>,
+}
+
+/// 根据账号标记与运行时信号判定健康状态，按严重程度取最高者
+pub fn evaluate(account: &Account, signals: &RuntimeSignals, now: i64) -> (HealthState, String) {
+    if account.disabled {
+        let reason = account
+            .disabled_reason
+            .clone()
+            .unwrap_or_else(|| "Account disabled".to_string());
+        return (HealthState::Disabled, reason);
+    }
+    if account.proxy_disabled {
+        let reason = match &account.proxy_disabled_reason {
+            Some(reason) => format!("Proxy disabled: {}", reason),
+            None => "Proxy disabled".to_string(),
+        };
+        return (HealthState::Disabled, reason);
+    }
+    if account.quota.as_ref().is_some_and(|q| q.is_forbidden) {
+        return (
+            HealthState::Forbidden,
+            "Upstream returned 403 Forbidden for this account".to_string(),
+        );
+    }
+    let validation_active =
+        !matches!(account.validation_blocked_until, Some(until) if until <= now);
+    if account.validation_blocked && validation_active {
+        let mut reason = account
+            .validation_blocked_reason
+            .clone()
+            .unwrap_or_else(|| "Account verification required".to_string());
+        if let Some(until) = account.validation_blocked_until {
+            reason = format!("{} (blocked until {})", reason, until);
+        }
+        return (HealthState::ValidationRequired, reason);
+    }
+    if let Some(until) = signals.rate_limited_until.filter(|until| *until > now) {
+        let reason = signals.rate_limit_reason.as_deref().unwrap_or("unknown");
+        return (
+            HealthState::RateLimited,
+            format!("Rate limited ({}) until {}", reason, until),
+        );
+    }
+    if !account.protected_models.is_empty() {
+        let mut models: Vec<&str> = account
+            .protected_models
+            .iter()
+            .map(String::as_str)
+            .collect();
+        models.sort_unstable();
+        return (
+            HealthState::QuotaProtected,
+            format!("Quota protection active for: {}", models.join(", ")),
+        );
+    }
+    if !signals.limited_models.is_empty() {
+        return (
+            HealthState::Degraded,
+            format!(
+                "Model-level rate limits: {}",
+                signals.limited_models.join(", ")
+            ),
+        );
+    }
+    if signals.consecutive_failures > 0 {
+        return (
+            HealthState::Degraded,
+            format!(
+                "{} consecutive upstream failure(s)",
+                signals.consecutive_failures
+            ),
+        );
+    }
+    if let Some(score) = signals
+        .health_score
+        .filter(|score| *score < DEGRADED_HEALTH_SCORE)
+    {
+        return (
+            HealthState::Degraded,
+            format!("Low health score ({:.2})", score),
+        );
+    }
+    (HealthState::Healthy, "OK".to_string())
+}
+
+/// 账号当前健康状态
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountHealth {
+    pub account_id: String,
+    pub email: String,
+    pub state: HealthState,
+    pub reason: String,
+    /// 进入当前状态的时间
+    pub since: i64,
+    pub updated_at: i64,
+    pub signals: RuntimeSignals,
+}
+
+/// 健康历史条目 (状态转换或 Token 刷新事件)
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthTransition {
+    pub id: i64,
+    pub account_id: String,
+    pub kind: HealthEventKind,
+    pub from_state: Option<HealthState>,
+    pub to_state: HealthState,
+    pub reason: String,
+    pub created_at: i64,
+}
+
+impl HealthTransition {
+    fn from_record(record: HealthTransitionRecord) -> Option<Self> {
+        Some(Self {
+            id: record.id,
+            account_id: record.account_id,
+            kind: HealthEventKind::parse(&record.kind)?,
+            from_state: record.from_state.as_deref().and_then(HealthState::parse),
+            to_state: HealthState::parse(&record.to_state)?,
+            reason: record.reason,
+            created_at: record.created_at,
+        })
+    }
+}
+
+/// `/accounts/:id/health` 返回的详情
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountHealthDetail {
+    #[serde(flatten)]
+    pub health: AccountHealth,
+    pub history: Vec<HealthTransition>,
+}
+
+/// 账号池健康汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolHealth {
+    pub total: usize,
+    /// 可参与调度的账号数
+    pub available: usize,
+    /// 各状态账号数 (包含数量为 0 的状态)
+    pub counts: BTreeMap<HealthState, usize>,
+    pub accounts: Vec<AccountHealth>,
+    pub recent_transitions: Vec<HealthTransition>,
+}
+
+/// 判定并记录单个账号的健康状态
+pub fn assess_account_in(
+    data_dir: &Path,
+    account: &Account,
+    signals: RuntimeSignals,
+    now: i64,
+) -> Result<AccountHealth, String> {
+    let (state, reason) = evaluate(account, &signals, now);
+    let (record, transitioned) =
+        account_db::record_health(data_dir, &account.id, state.as_str(), &reason, now)?;
+    if transitioned {
+        tracing::info!(
+            "[Health] Account {} -> {} ({})",
+            account.email,
+            state.as_str(),
+            reason
+        );
+    }
+    Ok(AccountHealth {
+        account_id: account.id.clone(),
+        email: account.email.clone(),
+        state,
+        reason: record.reason,
+        since: record.since,
+        updated_at: record.updated_at,
+        signals,
+    })
+}
+
+/// 记录一次 Token 刷新结果：先重新判定状态 (如 invalid_grant 禁用后转为 disabled)，再追加事件
+pub fn record_refresh_event_in(
+    data_dir: &Path,
+    account_id: &str,
+    signals: RuntimeSignals,
+    outcome: &str,
+    now: i64,
+) -> Result<(), String> {
+    let account = account_db::get_account(data_dir, account_id)?
+        .ok_or_else(|| format!("Account not found: {}", account_id))?;
+    let health = assess_account_in(data_dir, &account, signals, now)?;
+    account_db::append_health_event(
+        data_dir,
+        account_id,
+        HealthEventKind::TokenRefresh.as_str(),
+        health.state.as_str(),
+        outcome,
+        now,
+    )
+}
+
+fn history_in(
+    data_dir: &Path,
+    account_id: Option<&str>,
+    kind: Option<HealthEventKind>,
+    limit: usize,
+) -> Result<Vec<HealthTransition>, String> {
+    Ok(
+        account_db::list_health_history(data_dir, account_id, kind.map(HealthEventKind::as_str), limit)?
+            .into_iter()
+            .filter_map(HealthTransition::from_record)
+            .collect(),
+    )
+}
+
+/// 单个账号的当前健康状态与转换历史
+pub fn account_health_in(
+    data_dir: &Path,
+    account_id: &str,
+    signals: RuntimeSignals,
+    history_limit: usize,
+    now: i64,
+) -> Result<AccountHealthDetail, String> {
+    let account = account_db::get_account(data_dir, account_id)?
+        .ok_or_else(|| format!("Account not found: {}", account_id))?;
+    let health = assess_account_in(data_dir, &account, signals, now)?;
+    let history = history_in(data_dir, Some(account_id), None, history_limit)?;
+    Ok(AccountHealthDetail { health, history })
+}
+
+/// 判定并记录所有账号的健康状态，返回账号池汇总
+pub fn pool_health_in(
+    data_dir: &Path,
+    signals_for: impl Fn(&str) -> RuntimeSignals,
+    now: i64,
+) -> Result<PoolHealth, String> {
+    let mut counts: BTreeMap<HealthState, usize> =
+        HealthState::ALL.into_iter().map(|s| (s, 0)).collect();
+    let mut accounts = Vec::new();
+    for account in account_db::list_accounts(data_dir)? {
+        let health = assess_account_in(data_dir, &account, signals_for(&account.id), now)?;
+        *counts.entry(health.state).or_default() += 1;
+        accounts.push(health);
+    }
+    Ok(PoolHealth {
+        total: accounts.len(),
+        available: accounts.iter().filter(|a| a.state.is_available()).count(),
+        counts,
+        accounts,
+        recent_transitions: history_in(
+            data_dir,
+            None,
+            Some(HealthEventKind::Transition),
+            POOL_RECENT_TRANSITIONS,
+        )?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{QuotaData, TokenData};
+    use crate::modules::account_db::ChangeOrigin;
+
+    fn account(id: &str) -> Account {
+        Account::new(
+            id.to_string(),
+            format!("{}@test.com", id),
+            TokenData::new("atk".into(), "rtk".into(), 3600, None, None, None),
+        )
+    }
+
+    #[test]
+    fn test_evaluate_priority() {
+        let now = 1_000;
+        let mut acc = account("a");
+        let idle = RuntimeSignals::default();
+        assert_eq!(evaluate(&acc, &idle, now).0, HealthState::Healthy);
+
+        let failing = RuntimeSignals {
+            consecutive_failures: 2,
+            ..Default::default()
+        };
+        assert_eq!(evaluate(&acc, &failing, now).0, HealthState::Degraded);
+
+        acc.protected_models.insert("claude".to_string());
+        assert_eq!(evaluate(&acc, &failing, now).0, HealthState::QuotaProtected);
+
+        let limited = RuntimeSignals {
+            rate_limited_until: Some(now + 60),
+            rate_limit_reason: Some("quota_exhausted".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(evaluate(&acc, &limited, now).0, HealthState::RateLimited);
+        // 已过期的限流不再生效
+        assert_eq!(
+            evaluate(&acc, &limited, now + 61).0,
+            HealthState::QuotaProtected
+        );
+
+        acc.validation_blocked = true;
+        acc.validation_blocked_until = Some(now + 10);
+        assert_eq!(
+            evaluate(&acc, &limited, now).0,
+            HealthState::ValidationRequired
+        );
+        assert_eq!(
+            evaluate(&acc, &idle, now + 11).0,
+            HealthState::QuotaProtected
+        );
+
+        let mut quota = QuotaData::new();
+        quota.is_forbidden = true;
+        acc.quota = Some(quota);
+        assert_eq!(evaluate(&acc, &limited, now).0, HealthState::Forbidden);
+
+        acc.proxy_disabled = true;
+        let (state, reason) = evaluate(&acc, &limited, now);
+        assert_eq!(state, HealthState::Disabled);
+        assert_eq!(reason, "Proxy disabled");
+    }
+
+    #[test]
+    fn test_transitions_are_persisted() {
+        let dir =
+            std::env::temp_dir().join(format!("account_health_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut acc = account("a");
+        account_db::save_account(&dir, &acc, ChangeOrigin::App).unwrap();
+        account_db::save_account(&dir, &account("b"), ChangeOrigin::App).unwrap();
+
+        let first = assess_account_in(&dir, &acc, RuntimeSignals::default(), 100).unwrap();
+        assert_eq!((first.state, first.since), (HealthState::Healthy, 100));
+        // 状态未变化时不追加历史，since 保持不变
+        let again = assess_account_in(&dir, &acc, RuntimeSignals::default(), 200).unwrap();
+        assert_eq!(again.since, 100);
+
+        acc.disabled = true;
+        acc.disabled_reason = Some("invalid_grant".to_string());
+        account_db::save_account(&dir, &acc, ChangeOrigin::App).unwrap();
+        let detail = account_health_in(
+            &dir,
+            "a",
+            RuntimeSignals::default(),
+            DEFAULT_HISTORY_LIMIT,
+            300,
+        )
+        .unwrap();
+        assert_eq!(detail.health.state, HealthState::Disabled);
+        assert_eq!(detail.health.reason, "invalid_grant");
+        assert_eq!(detail.health.since, 300);
+        let states: Vec<_> = detail
+            .history
+            .iter()
+            .map(|t| (t.from_state, t.to_state))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (Some(HealthState::Healthy), HealthState::Disabled),
+                (None, HealthState::Healthy)
+            ]
+        );
+
+        let pool = pool_health_in(
+            &dir,
+            |id| RuntimeSignals {
+                rate_limited_until: (id == "b").then_some(1_000),
+                ..Default::default()
+            },
+            400,
+        )
+        .unwrap();
+        assert_eq!(pool.total, 2);
+        assert_eq!(pool.available, 0);
+        assert_eq!(pool.counts[&HealthState::Disabled], 1);
+        assert_eq!(pool.counts[&HealthState::RateLimited], 1);
+        assert_eq!(pool.counts[&HealthState::Healthy], 0);
+        assert_eq!(pool.recent_transitions.len(), 3);
+
+        // 删除账号时一并清理健康记录
+        account_db::delete_accounts(&dir, &["a".to_string()], ChangeOrigin::App).unwrap();
+        assert!(account_db::list_health_history(&dir, Some("a"), None, 10)
+            .unwrap()
+            .is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_refresh_events_are_recorded() {
+        let dir =
+            std::env::temp_dir().join(format!("account_health_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut acc = account("a");
+        account_db::save_account(&dir, &acc, ChangeOrigin::App).unwrap();
+
+        record_refresh_event_in(&dir, "a", RuntimeSignals::default(), "Token refreshed", 100)
+            .unwrap();
+        acc.disabled = true;
+        acc.disabled_reason = Some("invalid_grant: revoked".to_string());
+        account_db::save_account(&dir, &acc, ChangeOrigin::App).unwrap();
+        record_refresh_event_in(&dir, "a", RuntimeSignals::default(), "invalid_grant", 200)
+            .unwrap();
+
+        let detail =
+            account_health_in(&dir, "a", RuntimeSignals::default(), DEFAULT_HISTORY_LIMIT, 300)
+                .unwrap();
+        let events: Vec<_> = detail
+            .history
+            .iter()
+            .map(|t| (t.kind, t.from_state, t.to_state, t.reason.as_str()))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    HealthEventKind::TokenRefresh,
+                    Some(HealthState::Disabled),
+                    HealthState::Disabled,
+                    "invalid_grant"
+                ),
+                (
+                    HealthEventKind::Transition,
+                    Some(HealthState::Healthy),
+                    HealthState::Disabled,
+                    "invalid_grant: revoked"
+                ),
+                (
+                    HealthEventKind::TokenRefresh,
+                    Some(HealthState::Healthy),
+                    HealthState::Healthy,
+                    "Token refreshed"
+                ),
+                (HealthEventKind::Transition, None, HealthState::Healthy, "OK"),
+            ]
+        );
+
+        // 账号池视图只展示状态转换
+        let pool = pool_health_in(&dir, |_| RuntimeSignals::default(), 300).unwrap();
+        assert!(pool
+            .recent_transitions
+            .iter()
+            .all(|t| t.kind == HealthEventKind::Transition));
+        assert_eq!(pool.recent_transitions.len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
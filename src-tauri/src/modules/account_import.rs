@@ -0,0 +1,849 @@
+// 批量导入账号 (CSV / JSONL)
+// 每行一个 refresh_token，可附带 email / label / account_type / proxy_id / pool 列。
+// 先在本地校验每一行 (格式、文件内重复、代理与账号池是否存在)，再以有限并发刷新 Token 并调用
+// get_user_info 验证；dry_run 模式只做验证不写入。代理绑定与账号池归属在全部行完成后串行应用，
+// 避免并发读改写配置与令牌策略。
+
+use futures::stream::{self, StreamExt};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::modules::account_service::AccountService;
+use crate::modules::{account, account_db, logger, oauth, user_token_db};
+
+/// 默认并发数 (与批量刷新配额一致)
+const DEFAULT_CONCURRENCY: usize = 5;
+/// 并发上限，避免触发上游限流
+const MAX_CONCURRENCY: usize = 16;
+/// 单次导入的最大行数
+const MAX_ROWS: usize = 1000;
+/// 标签最大字符数 (与 update_account_label 一致)
+const MAX_LABEL_CHARS: usize = 15;
+
+const CSV_COLUMNS: [&str; 6] = [
+    "refresh_token",
+    "email",
+    "label",
+    "account_type",
+    "proxy_id",
+    "pool",
+];
+
+/// 导入文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Jsonl,
+}
+
+impl ImportFormat {
+    /// 首个非空行以 `{` 开头视为 JSONL，否则按 CSV 处理
+    pub fn detect(content: &str) -> Self {
+        let first = content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty());
+        match first {
+            Some(l) if l.starts_with('{') => ImportFormat::Jsonl,
+            _ => ImportFormat::Csv,
+        }
+    }
+}
+
+/// 导入选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkImportOptions {
+    /// 为空时自动识别
+    #[serde(default)]
+    pub format: Option<ImportFormat>,
+    /// 只验证不写入
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 并发数，默认 5，最大 16
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// 是否允许修改令牌账号池 (管理 API 需 tokens:write 作用域)
+    #[serde(skip)]
+    pub allow_pools: bool,
+}
+
+/// 导入文件中的一行
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ImportRow {
+    #[serde(skip)]
+    pub line: usize,
+    #[serde(default)]
+    pub refresh_token: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default, alias = "custom_label")]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub account_type: Option<String>,
+    #[serde(default, alias = "proxy")]
+    pub proxy_id: Option<String>,
+    /// 账号池: 用户令牌 ID 或用户名，导入的账号会加入该令牌的 account_pool
+    #[serde(default)]
+    pub pool: Option<String>,
+}
+
+impl ImportRow {
+    /// 去除首尾空白，空字符串视为未填写
+    fn normalized(mut self) -> Self {
+        fn clean(v: Option<String>) -> Option<String> {
+            v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+        }
+        self.refresh_token = self.refresh_token.trim().to_string();
+        self.email = clean(self.email);
+        self.label = clean(self.label);
+        self.account_type = clean(self.account_type);
+        self.proxy_id = clean(self.proxy_id);
+        self.pool = clean(self.pool);
+        self
+    }
+}
+
+/// 单行导入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Created,
+    Updated,
+    WouldCreate,
+    WouldUpdate,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RowResult {
+    pub line: usize,
+    pub status: RowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 账号已导入但附加操作 (标签 / 代理绑定 / 账号池) 失败
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl RowResult {
+    fn new(line: usize, status: RowStatus) -> Self {
+        Self {
+            line,
+            status,
+            email: None,
+            account_id: None,
+            error: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn failed(line: usize, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(line, RowStatus::Failed)
+        }
+    }
+
+    fn skipped(line: usize, reason: String) -> Self {
+        Self {
+            error: Some(reason),
+            ..Self::new(line, RowStatus::Skipped)
+        }
+    }
+}
+
+/// 导入报告 (rows 按行号排序)
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkImportReport {
+    pub dry_run: bool,
+    pub format: ImportFormat,
+    pub total: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub rows: Vec<RowResult>,
+}
+
+impl BulkImportReport {
+    fn new(dry_run: bool, format: ImportFormat, mut rows: Vec<RowResult>) -> Self {
+        rows.sort_by_key(|r| r.line);
+        let count = |f: &dyn Fn(RowStatus) -> bool| rows.iter().filter(|r| f(r.status)).count();
+        Self {
+            dry_run,
+            format,
+            total: rows.len(),
+            created: count(&|s| matches!(s, RowStatus::Created | RowStatus::WouldCreate)),
+            updated: count(&|s| matches!(s, RowStatus::Updated | RowStatus::WouldUpdate)),
+            skipped: count(&|s| s == RowStatus::Skipped),
+            failed: count(&|s| s == RowStatus::Failed),
+            rows,
+        }
+    }
+}
+
+/// 解析导入内容，格式错误的行以 Err(RowResult) 返回，文件级错误 (如缺少表头) 直接返回 Err
+pub fn parse_rows(
+    content: &str,
+    format: ImportFormat,
+) -> Result<Vec<Result<ImportRow, RowResult>>, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    match format {
+        ImportFormat::Jsonl => Ok(content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| {
+                serde_json::from_str::<ImportRow>(l.trim())
+                    .map(|row| ImportRow { line: i + 1, ..row }.normalized())
+                    .map_err(|e| RowResult::failed(i + 1, format!("Invalid JSON: {}", e)))
+            })
+            .collect()),
+        ImportFormat::Csv => {
+            let mut records = parse_csv_records(content)?.into_iter();
+            let Some((_, header)) = records.next() else {
+                return Ok(Vec::new());
+            };
+            let columns = header
+                .iter()
+                .map(|h| {
+                    let name = h.trim().to_ascii_lowercase();
+                    let name = match name.as_str() {
+                        "proxy" => "proxy_id".to_string(),
+                        "custom_label" => "label".to_string(),
+                        _ => name,
+                    };
+                    if CSV_COLUMNS.contains(&name.as_str()) {
+                        Ok(name)
+                    } else {
+                        Err(format!("Unknown CSV column: {}", h.trim()))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if !columns.iter().any(|c| c == "refresh_token") {
+                return Err("CSV header must contain a refresh_token column".to_string());
+            }
+
+            Ok(records
+                .map(|(line, fields)| {
+                    if fields.len() != columns.len() {
+                        return Err(RowResult::failed(
+                            line,
+                            format!("Expected {} fields, found {}", columns.len(), fields.len()),
+                        ));
+                    }
+                    let mut row = ImportRow {
+                        line,
+                        ..Default::default()
+                    };
+                    for (column, value) in columns.iter().zip(fields) {
+                        match column.as_str() {
+                            "refresh_token" => row.refresh_token = value,
+                            "email" => row.email = Some(value),
+                            "label" => row.label = Some(value),
+                            "account_type" => row.account_type = Some(value),
+                            "proxy_id" => row.proxy_id = Some(value),
+                            _ => row.pool = Some(value),
+                        }
+                    }
+                    Ok(row.normalized())
+                })
+                .collect())
+        }
+    }
+}
+
+/// 解析 CSV (RFC 4180: 双引号包裹字段，`""` 转义，引号内允许换行)，返回 (起始行号, 字段) 并跳过空行
+fn parse_csv_records(content: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                if !(fields.len() == 1 && fields[0].trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!(
+            "Unterminated quoted field starting at line {}",
+            record_line
+        ));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        if !(fields.len() == 1 && fields[0].trim().is_empty()) {
+            records.push((record_line, fields));
+        }
+    }
+    Ok(records)
+}
+
+/// 本地校验所需的上下文
+struct ImportContext {
+    proxies: HashSet<String>,
+    /// (令牌 ID, 用户名)
+    tokens: Vec<(String, String)>,
+    allow_pools: bool,
+}
+
+impl ImportContext {
+    fn load(allow_pools: bool) -> Result<Self, String> {
+        let config = crate::modules::config::load_app_config()?;
+        let tokens = user_token_db::list_tokens()?
+            .into_iter()
+            .map(|t| (t.id, t.username))
+            .collect();
+        Ok(Self {
+            proxies: config
+                .proxy
+                .proxy_pool
+                .proxies
+                .into_iter()
+                .map(|p| p.id)
+                .collect(),
+            tokens,
+            allow_pools,
+        })
+    }
+
+    /// 按令牌 ID 精确匹配，否则按用户名 (忽略大小写) 匹配
+    fn resolve_pool(&self, pool: &str) -> Result<String, String> {
+        if let Some((id, _)) = self.tokens.iter().find(|(id, _)| id == pool) {
+            return Ok(id.clone());
+        }
+        let matches: Vec<_> = self
+            .tokens
+            .iter()
+            .filter(|(_, name)| name.eq_ignore_ascii_case(pool))
+            .collect();
+        match matches.as_slice() {
+            [(id, _)] => Ok(id.clone()),
+            [] => Err(format!("Unknown pool: {}", pool)),
+            _ => Err(format!("Ambiguous pool '{}': use the token id", pool)),
+        }
+    }
+}
+
+/// 通过本地校验、等待验证的行
+struct PendingRow {
+    row: ImportRow,
+    pool_token_id: Option<String>,
+}
+
+/// 本地校验: 必填字段、标签长度、文件内重复、代理与账号池是否存在
+fn check_row(
+    row: ImportRow,
+    ctx: &ImportContext,
+    seen_tokens: &mut HashSet<String>,
+) -> Result<PendingRow, RowResult> {
+    if row.refresh_token.is_empty() {
+        return Err(RowResult::failed(
+            row.line,
+            "Missing refresh_token".to_string(),
+        ));
+    }
+    if !seen_tokens.insert(row.refresh_token.clone()) {
+        return Err(RowResult::skipped(
+            row.line,
+            "Duplicate refresh_token in import".to_string(),
+        ));
+    }
+    if let Some(label) = &row.label {
+        if label.chars().count() > MAX_LABEL_CHARS {
+            return Err(RowResult::failed(
+                row.line,
+                format!("Label exceeds {} characters", MAX_LABEL_CHARS),
+            ));
+        }
+    }
+    if let Some(proxy_id) = &row.proxy_id {
+        if !ctx.proxies.contains(proxy_id) {
+            return Err(RowResult::failed(
+                row.line,
+                format!("Unknown proxy: {}", proxy_id),
+            ));
+        }
+    }
+    let pool_token_id = match &row.pool {
+        Some(_) if !ctx.allow_pools => {
+            return Err(RowResult::failed(
+                row.line,
+                "Pool assignment requires the tokens:write scope".to_string(),
+            ));
+        }
+        Some(pool) => Some(
+            ctx.resolve_pool(pool)
+                .map_err(|e| RowResult::failed(row.line, e))?,
+        ),
+        None => None,
+    };
+    Ok(PendingRow { row, pool_token_id })
+}
+
+/// 批量导入账号，导入完成后由调用方重载账号池
+pub async fn bulk_import(
+    service: &AccountService,
+    content: &str,
+    options: &BulkImportOptions,
+) -> Result<BulkImportReport, String> {
+    let format = options
+        .format
+        .unwrap_or_else(|| ImportFormat::detect(content));
+    let parsed = parse_rows(content, format)?;
+    if parsed.len() > MAX_ROWS {
+        return Err(format!(
+            "Too many rows: {} (max {} per import)",
+            parsed.len(),
+            MAX_ROWS
+        ));
+    }
+    let ctx = ImportContext::load(options.allow_pools)?;
+    let data_dir = account::get_data_dir()?;
+    let concurrency = options
+        .concurrency
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY);
+
+    logger::log_info(&format!(
+        "[Import] Bulk import started: {} rows, format {:?}, concurrency {}, dry_run {}",
+        parsed.len(),
+        format,
+        concurrency,
+        options.dry_run
+    ));
+
+    let mut results = Vec::with_capacity(parsed.len());
+    let mut pending = Vec::new();
+    let mut seen_tokens = HashSet::new();
+    for row in parsed {
+        match row.and_then(|r| check_row(r, &ctx, &mut seen_tokens)) {
+            Ok(p) => pending.push(p),
+            Err(result) => results.push(result),
+        }
+    }
+
+    let claimed_emails = Mutex::new(HashSet::new());
+    let outcomes: Vec<(RowResult, PendingRow)> = stream::iter(pending)
+        .map(|p| {
+            let data_dir = &data_dir;
+            let claimed_emails = &claimed_emails;
+            async move {
+                let result =
+                    import_row(service, data_dir, &p.row, options.dry_run, claimed_emails).await;
+                (result, p)
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let mut imported = Vec::new();
+    for (result, p) in outcomes {
+        if matches!(result.status, RowStatus::Created | RowStatus::Updated) {
+            imported.push((result, p));
+        } else {
+            results.push(result);
+        }
+    }
+    if !imported.is_empty() {
+        apply_proxy_bindings(&mut imported).await;
+        apply_pool_memberships(&mut imported);
+    }
+    results.extend(imported.into_iter().map(|(r, _)| r));
+
+    let report = BulkImportReport::new(options.dry_run, format, results);
+    logger::log_info(&format!(
+        "[Import] Bulk import finished: created {}, updated {}, skipped {}, failed {}",
+        report.created, report.updated, report.skipped, report.failed
+    ));
+    Ok(report)
+}
+
+/// 验证单行 (刷新 Token + get_user_info)，非 dry_run 时写入账号与标签
+async fn import_row(
+    service: &AccountService,
+    data_dir: &Path,
+    row: &ImportRow,
+    dry_run: bool,
+    claimed_emails: &Mutex<HashSet<String>>,
+) -> RowResult {
+    // [FIX #1583] 同 add_account，使用临时 ID 作为代理选择上下文
+    let temp_account_id = uuid::Uuid::new_v4().to_string();
+    let token_res =
+        match oauth::refresh_access_token(&row.refresh_token, Some(&temp_account_id)).await {
+            Ok(t) => t,
+            Err(e) => return RowResult::failed(row.line, format!("Token refresh failed: {}", e)),
+        };
+    let user_info = match oauth::get_user_info(&token_res.access_token, Some(&temp_account_id))
+        .await
+    {
+        Ok(u) => u,
+        Err(e) => return RowResult::failed(row.line, format!("Failed to get user info: {}", e)),
+    };
+
+    let email = user_info.email.clone();
+    if let Some(expected) = &row.email {
+        if !expected.eq_ignore_ascii_case(&email) {
+            return RowResult {
+                email: Some(email.clone()),
+                ..RowResult::failed(
+                    row.line,
+                    format!(
+                        "Email mismatch: row has {}, token belongs to {}",
+                        expected, email
+                    ),
+                )
+            };
+        }
+    }
+    if !claimed_emails.lock().insert(email.to_lowercase()) {
+        return RowResult {
+            email: Some(email),
+            ..RowResult::skipped(row.line, "Duplicate account in import".to_string())
+        };
+    }
+
+    let existing_id = match account_db::find_account_id_by_email(data_dir, &email) {
+        Ok(id) => id,
+        Err(e) => return RowResult::failed(row.line, e),
+    };
+    if dry_run {
+        let status = if existing_id.is_some() {
+            RowStatus::WouldUpdate
+        } else {
+            RowStatus::WouldCreate
+        };
+        return RowResult {
+            email: Some(email),
+            account_id: existing_id,
+            ..RowResult::new(row.line, status)
+        };
+    }
+
+    let account = match service
+        .add_validated_account(
+            &row.refresh_token,
+            token_res,
+            user_info,
+            row.account_type.clone(),
+            None,
+            None,
+        )
+        .await
+    {
+        Ok(a) => a,
+        Err(e) => {
+            return RowResult {
+                email: Some(email),
+                ..RowResult::failed(row.line, e)
+            }
+        }
+    };
+
+    let status = if existing_id.is_some() {
+        RowStatus::Updated
+    } else {
+        RowStatus::Created
+    };
+    let mut result = RowResult {
+        email: Some(account.email.clone()),
+        account_id: Some(account.id.clone()),
+        ..RowResult::new(row.line, status)
+    };
+    if let Some(label) = &row.label {
+        let label = label.clone();
+        if let Err(e) = account::update_account(&account.id, |a| {
+            a.custom_label = Some(label);
+            Ok(())
+        }) {
+            result.warnings.push(format!("Failed to set label: {}", e));
+        }
+    }
+    result
+}
+
+/// 串行应用代理绑定 (经由代理池管理器或配置文件)
+async fn apply_proxy_bindings(imported: &mut [(RowResult, PendingRow)]) {
+    let (rows, changes): (Vec<usize>, Vec<(String, Option<String>)>) = imported
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (r, p))| {
+            let change = (r.account_id.clone()?, Some(p.row.proxy_id.clone()?));
+            Some((i, change))
+        })
+        .unzip();
+    if changes.is_empty() {
+        return;
+    }
+    let results = crate::proxy::proxy_pool::apply_account_bindings(&changes).await;
+    for (i, result) in rows.into_iter().zip(results) {
+        if let Err(e) = result {
+            imported[i]
+                .0
+                .warnings
+                .push(format!("Failed to bind proxy: {}", e));
+        }
+    }
+}
+
+/// 串行把账号加入令牌的 account_pool (每个令牌只读写一次策略)
+/// 注意: 原本 account_pool 为空 (不限制) 的令牌加入账号后将只能使用池内账号
+fn apply_pool_memberships(imported: &mut [(RowResult, PendingRow)]) {
+    let mut by_token: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, (r, p)) in imported.iter().enumerate() {
+        if let (Some(_), Some(token_id)) = (&r.account_id, &p.pool_token_id) {
+            by_token.entry(token_id.clone()).or_default().push(i);
+        }
+    }
+
+    for (token_id, rows) in by_token {
+        let updated = user_token_db::get_token_by_id(&token_id).and_then(|token| {
+            let mut policy = token
+                .ok_or_else(|| format!("Token not found: {}", token_id))?
+                .model_policy;
+            let members: HashMap<String, String> = rows
+                .iter()
+                .filter_map(|&i| {
+                    let r = &imported[i].0;
+                    Some((r.account_id.clone()?, r.email.clone().unwrap_or_default()))
+                })
+                .collect();
+            for (account_id, email) in members {
+                let present = policy.account_pool.iter().any(|a| {
+                    *a == account_id || (!email.is_empty() && a.eq_ignore_ascii_case(&email))
+                });
+                if !present {
+                    policy.account_pool.push(account_id);
+                }
+            }
+            user_token_db::update_token_model_policy(&token_id, &policy)
+        });
+        if let Err(e) = updated {
+            for i in rows {
+                imported[i]
+                    .0
+                    .warnings
+                    .push(format!("Failed to add to pool: {}", e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ImportContext {
+        ImportContext {
+            proxies: ["px-1".to_string()].into_iter().collect(),
+            tokens: vec![
+                ("tok-a".to_string(), "team-a".to_string()),
+                ("tok-b".to_string(), "shared".to_string()),
+                ("tok-c".to_string(), "shared".to_string()),
+            ],
+            allow_pools: true,
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_and_jsonl() {
+        let csv = "\u{feff}Refresh_Token,email,label,proxy\r\n\
+                   1//a,a@x.com,\"vip, \"\"gold\"\"\",px-1\r\n\
+                   \r\n\
+                   1//b,,,\n\
+                   1//c,c@x.com\n";
+        assert_eq!(ImportFormat::detect(csv), ImportFormat::Csv);
+        let rows = parse_rows(csv, ImportFormat::Csv).unwrap();
+        assert_eq!(rows.len(), 3);
+
+        let a = rows[0].as_ref().unwrap();
+        assert_eq!(a.line, 2);
+        assert_eq!(a.refresh_token, "1//a");
+        assert_eq!(a.label.as_deref(), Some("vip, \"gold\""));
+        assert_eq!(a.proxy_id.as_deref(), Some("px-1"));
+
+        let b = rows[1].as_ref().unwrap();
+        assert_eq!(b.line, 4);
+        assert_eq!(b.email, None);
+
+        let c = rows[2].as_ref().unwrap_err();
+        assert_eq!((c.line, c.status), (5, RowStatus::Failed));
+
+        assert!(parse_rows("token,email\n1//a,a@x.com\n", ImportFormat::Csv)
+            .unwrap_err()
+            .contains("Unknown CSV column"));
+        assert!(parse_rows("email\na@x.com\n", ImportFormat::Csv).is_err());
+        assert!(parse_csv_records("refresh_token\n\"1//a\n").is_err());
+
+        let jsonl =
+            "{\"refresh_token\":\" 1//a \",\"pool\":\"team-a\",\"account_type\":\"\"}\n\n{oops}\n";
+        assert_eq!(ImportFormat::detect(jsonl), ImportFormat::Jsonl);
+        let rows = parse_rows(jsonl, ImportFormat::Jsonl).unwrap();
+        let a = rows[0].as_ref().unwrap();
+        assert_eq!((a.line, a.refresh_token.as_str()), (1, "1//a"));
+        assert_eq!(a.pool.as_deref(), Some("team-a"));
+        assert_eq!(a.account_type, None);
+        assert_eq!(rows[1].as_ref().unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn test_check_row() {
+        let ctx = context();
+        let mut seen = HashSet::new();
+        let row = |line: usize, token: &str| ImportRow {
+            line,
+            refresh_token: token.to_string(),
+            ..Default::default()
+        };
+
+        let ok = check_row(
+            ImportRow {
+                pool: Some("TEAM-A".to_string()),
+                proxy_id: Some("px-1".to_string()),
+                ..row(1, "t1")
+            },
+            &ctx,
+            &mut seen,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(ok.pool_token_id.as_deref(), Some("tok-a"));
+
+        let dup = check_row(row(2, "t1"), &ctx, &mut seen).err().unwrap();
+        assert_eq!(dup.status, RowStatus::Skipped);
+
+        let cases = [
+            (row(3, ""), "Missing refresh_token"),
+            (
+                ImportRow {
+                    proxy_id: Some("px-9".to_string()),
+                    ..row(4, "t4")
+                },
+                "Unknown proxy",
+            ),
+            (
+                ImportRow {
+                    pool: Some("nobody".to_string()),
+                    ..row(5, "t5")
+                },
+                "Unknown pool",
+            ),
+            (
+                ImportRow {
+                    pool: Some("shared".to_string()),
+                    ..row(6, "t6")
+                },
+                "Ambiguous pool",
+            ),
+            (
+                ImportRow {
+                    label: Some("x".repeat(16)),
+                    ..row(7, "t7")
+                },
+                "Label exceeds",
+            ),
+        ];
+        for (r, expected) in cases {
+            let err = check_row(r, &ctx, &mut seen).err().unwrap();
+            assert_eq!(err.status, RowStatus::Failed);
+            assert!(err.error.unwrap().contains(expected), "{}", expected);
+        }
+        let locked = ImportContext {
+            allow_pools: false,
+            ..context()
+        };
+        let err = check_row(
+            ImportRow {
+                pool: Some("tok-a".to_string()),
+                ..row(9, "t9")
+            },
+            &locked,
+            &mut seen,
+        )
+        .err()
+        .unwrap();
+        assert!(err.error.unwrap().contains("tokens:write"));
+        assert_eq!(
+            check_row(
+                ImportRow {
+                    pool: Some("tok-c".to_string()),
+                    ..row(8, "t8")
+                },
+                &ctx,
+                &mut seen
+            )
+            .ok()
+            .unwrap()
+            .pool_token_id
+            .as_deref(),
+            Some("tok-c")
+        );
+    }
+
+    #[test]
+    fn test_report_counts() {
+        let rows = vec![
+            RowResult::new(3, RowStatus::WouldUpdate),
+            RowResult::failed(1, "x".to_string()),
+            RowResult::new(2, RowStatus::WouldCreate),
+            RowResult::skipped(4, "dup".to_string()),
+        ];
+        let report = BulkImportReport::new(true, ImportFormat::Csv, rows);
+        assert_eq!(
+            (
+                report.total,
+                report.created,
+                report.updated,
+                report.skipped,
+                report.failed
+            ),
+            (4, 1, 1, 1, 1)
+        );
+        assert_eq!(
+            report.rows.iter().map(|r| r.line).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+    }
+}
@@ -0,0 +1,966 @@
+// 账号查询与批量操作
+// `/accounts` 的服务端筛选、排序与分页，以及按筛选表达式选中账号后执行的批量操作。
+//
+// 筛选表达式由空格分隔的条件组成，条件之间为 AND，同一条件内逗号分隔的值为 OR，前缀 `-` 表示取反:
+//   tag:owner:alice,tier:gold     含任一标签 (大小写不敏感，支持 * 通配)
+//   tier:pro,ultra                订阅等级 (tier:none 表示未知)
+//   health:healthy,degraded       健康状态
+//   quota:claude-*<20             任一匹配模型的剩余配额满足比较 (< <= > >= =)
+//   proxy:none | proxy:any | proxy:<proxy_id>
+//   last_used<7d                  最近使用时间早于 7 天前 (支持 Unix 时间戳、YYYY-MM-DD、Nd/Nh/Nm)
+//   disabled:true                 账号或反代已禁用
+//   其他不含 `:` 的词             匹配邮箱 / 名称 / 自定义标签
+
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::models::Account;
+use crate::modules::account_health::{self, HealthState, RuntimeSignals};
+use crate::modules::{account, logger};
+use crate::proxy::common::model_mapping::wildcard_match;
+
+/// 单个账号的标签上限
+const MAX_TAGS: usize = 32;
+/// 单个标签的最大字符数
+const MAX_TAG_LEN: usize = 64;
+/// 分页大小上限
+const MAX_PAGE_SIZE: usize = 500;
+/// 只传 page 时的默认分页大小
+const DEFAULT_PAGE_SIZE: usize = 50;
+/// 批量预热的并发数
+const WARMUP_CONCURRENCY: usize = 4;
+
+// ============================================================================
+// 标签
+// ============================================================================
+
+/// 校验并规范化标签: 去除首尾空白，忽略空标签，按大小写不敏感去重 (保留首次出现的写法)
+pub fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, String> {
+    let mut seen = HashSet::new();
+    let mut normalized = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(format!("Tag '{}' exceeds {} characters", tag, MAX_TAG_LEN));
+        }
+        if tag.contains(|c: char| c.is_whitespace() || c == ',') {
+            return Err(format!(
+                "Tag '{}' must not contain whitespace or commas",
+                tag
+            ));
+        }
+        if seen.insert(tag.to_lowercase()) {
+            normalized.push(tag.to_string());
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(format!("An account can have at most {} tags", MAX_TAGS));
+    }
+    Ok(normalized)
+}
+
+/// 替换账号的全部标签，返回规范化后的标签
+pub fn set_account_tags(account_id: &str, tags: Vec<String>) -> Result<Vec<String>, String> {
+    let tags = normalize_tags(tags)?;
+    account::update_account(account_id, |account| {
+        account.tags = tags.clone();
+        Ok(())
+    })?;
+    Ok(tags)
+}
+
+/// 标签使用统计
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// 统计所有账号的标签 (大小写不敏感合并)，按使用次数降序
+pub fn tag_counts(accounts: &[Account]) -> Vec<TagCount> {
+    let mut counts: BTreeMap<String, TagCount> = BTreeMap::new();
+    for tag in accounts.iter().flat_map(|a| a.tags.iter()) {
+        counts
+            .entry(tag.to_lowercase())
+            .or_insert_with(|| TagCount {
+                tag: tag.clone(),
+                count: 0,
+            })
+            .count += 1;
+    }
+    let mut result: Vec<TagCount> = counts.into_values().collect();
+    result.sort_by_key(|t| std::cmp::Reverse(t.count));
+    result
+}
+
+// ============================================================================
+// 筛选表达式
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl CmpOp {
+    /// 从字符串开头解析比较运算符，返回运算符与剩余部分
+    fn split(value: &str) -> Option<(Self, &str)> {
+        [
+            ("<=", CmpOp::Le),
+            (">=", CmpOp::Ge),
+            ("<", CmpOp::Lt),
+            (">", CmpOp::Gt),
+            ("=", CmpOp::Eq),
+        ]
+        .into_iter()
+        .find_map(|(token, op)| value.strip_prefix(token).map(|rest| (op, rest)))
+    }
+
+    fn eval<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            CmpOp::Lt => left < right,
+            CmpOp::Le => left <= right,
+            CmpOp::Gt => left > right,
+            CmpOp::Ge => left >= right,
+            CmpOp::Eq => left == right,
+        }
+    }
+}
+
+/// 时间值: 绝对时间戳或相对当前时间的秒数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeValue {
+    At(i64),
+    Ago(i64),
+}
+
+impl TimeValue {
+    fn parse(value: &str) -> Result<Self, String> {
+        if let Ok(ts) = value.parse::<i64>() {
+            return Ok(TimeValue::At(ts));
+        }
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+            return Ok(TimeValue::At(midnight.and_utc().timestamp()));
+        }
+        let unit = match value.chars().last() {
+            Some('d') => 86_400,
+            Some('h') => 3_600,
+            Some('m') => 60,
+            _ => return Err(format!("Invalid time value: {}", value)),
+        };
+        value[..value.len() - 1]
+            .parse::<i64>()
+            .ok()
+            .filter(|n| *n >= 0)
+            .map(|n| TimeValue::Ago(n.saturating_mul(unit)))
+            .ok_or_else(|| format!("Invalid time value: {}", value))
+    }
+
+    fn resolve(self, now: i64) -> i64 {
+        match self {
+            TimeValue::At(ts) => ts,
+            TimeValue::Ago(secs) => now - secs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProxyMatch {
+    Unbound,
+    Bound,
+    Id(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    Tag(Vec<String>),
+    Tier(Vec<String>),
+    Health(Vec<HealthState>),
+    Quota {
+        model: String,
+        op: CmpOp,
+        value: i32,
+    },
+    Proxy(Vec<ProxyMatch>),
+    LastUsed {
+        op: CmpOp,
+        value: TimeValue,
+    },
+    Disabled(bool),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    negate: bool,
+    predicate: Predicate,
+}
+
+/// 解析后的筛选表达式 (空表达式匹配所有账号)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountFilter {
+    terms: Vec<Term>,
+}
+
+fn split_values(key: &str, value: &str) -> Result<Vec<String>, String> {
+    let values: Vec<String> = value
+        .split(',')
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if values.is_empty() {
+        return Err(format!("Filter '{}' requires a value", key));
+    }
+    Ok(values)
+}
+
+fn parse_term(token: &str) -> Result<Predicate, String> {
+    if let Some(rest) = token.strip_prefix("last_used") {
+        let (op, value) =
+            CmpOp::split(rest).ok_or_else(|| format!("Invalid last_used filter: {}", token))?;
+        return Ok(Predicate::LastUsed {
+            op,
+            value: TimeValue::parse(value)?,
+        });
+    }
+
+    let Some((key, value)) = token.split_once(':') else {
+        return Ok(Predicate::Text(token.to_lowercase()));
+    };
+    match key.to_lowercase().as_str() {
+        "tag" => Ok(Predicate::Tag(split_values(key, value)?)),
+        "tier" => Ok(Predicate::Tier(split_values(key, value)?)),
+        "health" => split_values(key, value)?
+            .iter()
+            .map(|v| HealthState::parse(v).ok_or_else(|| format!("Unknown health state: {}", v)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Predicate::Health),
+        "quota" => {
+            let pos = value
+                .find(['<', '>', '='])
+                .ok_or_else(|| format!("Invalid quota filter: {}", token))?;
+            let (model, rest) = value.split_at(pos);
+            let (op, number) = CmpOp::split(rest).unwrap_or((CmpOp::Eq, rest));
+            let value = number
+                .parse::<i32>()
+                .map_err(|_| format!("Invalid quota percentage: {}", number))?;
+            let model = match model.trim() {
+                "" => "*".to_string(),
+                model => model.to_lowercase(),
+            };
+            Ok(Predicate::Quota { model, op, value })
+        }
+        "proxy" => Ok(Predicate::Proxy(
+            split_values(key, value)?
+                .into_iter()
+                .map(|v| match v.as_str() {
+                    "none" => ProxyMatch::Unbound,
+                    "any" => ProxyMatch::Bound,
+                    _ => ProxyMatch::Id(v),
+                })
+                .collect(),
+        )),
+        "disabled" => match value.to_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok(Predicate::Disabled(true)),
+            "false" | "no" | "0" => Ok(Predicate::Disabled(false)),
+            _ => Err(format!("Invalid disabled filter: {}", value)),
+        },
+        other => Err(format!("Unknown filter key: {}", other)),
+    }
+}
+
+/// 解析筛选表达式
+pub fn parse_filter(expr: &str) -> Result<AccountFilter, String> {
+    let terms = expr
+        .split_whitespace()
+        .map(|token| {
+            let (negate, body) = match token.strip_prefix('-') {
+                Some(body) if !body.is_empty() => (true, body),
+                _ => (false, token),
+            };
+            Ok(Term {
+                negate,
+                predicate: parse_term(body)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(AccountFilter { terms })
+}
+
+/// 订阅等级排序权重 (未知 < free < pro < ultra)
+fn tier_rank(account: &Account) -> u8 {
+    let tier = account
+        .quota
+        .as_ref()
+        .and_then(|q| q.subscription_tier.as_deref())
+        .unwrap_or("")
+        .to_lowercase();
+    if tier.contains("ultra") {
+        3
+    } else if tier.contains("pro") {
+        2
+    } else if tier.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+/// 匹配模型的剩余配额百分比
+fn model_quotas<'a>(account: &'a Account, pattern: &'a str) -> impl Iterator<Item = i32> + 'a {
+    account
+        .quota
+        .iter()
+        .flat_map(|q| q.models.iter())
+        .filter(move |m| wildcard_match(pattern, &m.name.to_lowercase()))
+        .map(|m| m.percentage)
+}
+
+/// 筛选所需的运行时上下文
+#[derive(Debug, Clone, Default)]
+pub struct FilterContext {
+    pub now: i64,
+    /// 账号健康状态 (缺失时按账号标记判定，不含反代运行时信号)
+    pub health: HashMap<String, HealthState>,
+    /// 代理绑定 (account_id -> proxy_id)
+    pub bindings: HashMap<String, String>,
+}
+
+impl FilterContext {
+    /// 以当前时间与代理绑定快照构建上下文
+    pub fn new(health: HashMap<String, HealthState>) -> Self {
+        Self {
+            now: chrono::Utc::now().timestamp(),
+            health,
+            bindings: crate::proxy::proxy_pool::account_bindings_snapshot(),
+        }
+    }
+
+    fn health_of(&self, account: &Account) -> HealthState {
+        self.health.get(&account.id).copied().unwrap_or_else(|| {
+            account_health::evaluate(account, &RuntimeSignals::default(), self.now).0
+        })
+    }
+}
+
+impl Predicate {
+    fn matches(&self, account: &Account, ctx: &FilterContext) -> bool {
+        match self {
+            Predicate::Tag(patterns) => account.tags.iter().any(|tag| {
+                let tag = tag.to_lowercase();
+                patterns.iter().any(|p| wildcard_match(p, &tag))
+            }),
+            Predicate::Tier(tiers) => {
+                let tier = account
+                    .quota
+                    .as_ref()
+                    .and_then(|q| q.subscription_tier.as_deref())
+                    .map(str::to_lowercase);
+                tiers.iter().any(|t| match &tier {
+                    None => t == "none",
+                    Some(tier) => tier.contains(t.as_str()),
+                })
+            }
+            Predicate::Health(states) => states.contains(&ctx.health_of(account)),
+            Predicate::Quota { model, op, value } => {
+                model_quotas(account, model).any(|pct| op.eval(pct, *value))
+            }
+            Predicate::Proxy(values) => {
+                let bound = ctx.bindings.get(&account.id);
+                values.iter().any(|v| match v {
+                    ProxyMatch::Unbound => bound.is_none(),
+                    ProxyMatch::Bound => bound.is_some(),
+                    ProxyMatch::Id(id) => bound.is_some_and(|b| b.eq_ignore_ascii_case(id)),
+                })
+            }
+            Predicate::LastUsed { op, value } => op.eval(account.last_used, value.resolve(ctx.now)),
+            Predicate::Disabled(expected) => {
+                (account.disabled || account.proxy_disabled) == *expected
+            }
+            Predicate::Text(text) => {
+                account.email.to_lowercase().contains(text.as_str())
+                    || account
+                        .name
+                        .as_deref()
+                        .is_some_and(|n| n.to_lowercase().contains(text.as_str()))
+                    || account
+                        .custom_label
+                        .as_deref()
+                        .is_some_and(|l| l.to_lowercase().contains(text.as_str()))
+            }
+        }
+    }
+}
+
+impl AccountFilter {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// 是否需要健康状态 (由调用方决定是否采集反代运行时信号)
+    pub fn needs_health(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|t| matches!(t.predicate, Predicate::Health(_)))
+    }
+
+    pub fn matches(&self, account: &Account, ctx: &FilterContext) -> bool {
+        self.terms
+            .iter()
+            .all(|t| t.predicate.matches(account, ctx) != t.negate)
+    }
+}
+
+// ============================================================================
+// 排序与分页
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SortKey {
+    Email,
+    LastUsed,
+    CreatedAt,
+    Tier,
+    Health,
+    /// 匹配模型中最低的剩余配额 (无配额数据的账号排在最后)
+    Quota(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SortSpec {
+    key: SortKey,
+    descending: bool,
+}
+
+fn parse_sort(value: &str) -> Result<SortSpec, String> {
+    let (descending, key) = match value.trim().strip_prefix('-') {
+        Some(key) => (true, key),
+        None => (false, value.trim()),
+    };
+    let key = match key {
+        "email" => SortKey::Email,
+        "last_used" => SortKey::LastUsed,
+        "created_at" => SortKey::CreatedAt,
+        "tier" => SortKey::Tier,
+        "health" => SortKey::Health,
+        other => match other.strip_prefix("quota:") {
+            Some(model) if !model.is_empty() => SortKey::Quota(model.to_lowercase()),
+            _ => return Err(format!("Unknown sort key: {}", other)),
+        },
+    };
+    Ok(SortSpec { key, descending })
+}
+
+/// `/accounts` 查询参数 (均为可选，不传时返回全部账号)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountQuery {
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// 页码 (从 1 开始)
+    #[serde(default)]
+    pub page: Option<usize>,
+    #[serde(default, alias = "page_size")]
+    pub page_size: Option<usize>,
+}
+
+/// 解析后的查询
+#[derive(Debug, Clone)]
+pub struct QueryPlan {
+    filter: AccountFilter,
+    sort: Option<SortSpec>,
+    page: Option<(usize, usize)>,
+}
+
+/// 查询结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountPage<T> {
+    pub accounts: Vec<T>,
+    /// 筛选后 (分页前) 的账号数
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+}
+
+impl<T> AccountPage<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> AccountPage<U> {
+        AccountPage {
+            accounts: self.accounts.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            page_size: self.page_size,
+        }
+    }
+}
+
+impl QueryPlan {
+    pub fn parse(query: &AccountQuery) -> Result<Self, String> {
+        let filter = parse_filter(query.filter.as_deref().unwrap_or(""))?;
+        let sort = match query.sort.as_deref().map(str::trim) {
+            Some(sort) if !sort.is_empty() => Some(parse_sort(sort)?),
+            _ => None,
+        };
+        let page = match (query.page, query.page_size) {
+            (None, None) => None,
+            (page, size) => Some((
+                page.unwrap_or(1).max(1),
+                size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+            )),
+        };
+        Ok(Self { filter, sort, page })
+    }
+
+    pub fn needs_health(&self) -> bool {
+        self.filter.needs_health()
+            || matches!(
+                self.sort,
+                Some(SortSpec {
+                    key: SortKey::Health,
+                    ..
+                })
+            )
+    }
+
+    fn compare(&self, a: &Account, b: &Account, ctx: &FilterContext) -> Ordering {
+        let Some(spec) = &self.sort else {
+            return Ordering::Equal;
+        };
+        let ordering = match &spec.key {
+            SortKey::Email => a.email.to_lowercase().cmp(&b.email.to_lowercase()),
+            SortKey::LastUsed => a.last_used.cmp(&b.last_used),
+            SortKey::CreatedAt => a.created_at.cmp(&b.created_at),
+            SortKey::Tier => tier_rank(a).cmp(&tier_rank(b)),
+            SortKey::Health => ctx.health_of(a).cmp(&ctx.health_of(b)),
+            SortKey::Quota(model) => {
+                let (qa, qb) = (model_quotas(a, model).min(), model_quotas(b, model).min());
+                // 无配额数据的账号无论升降序都排在最后
+                return match (qa, qb) {
+                    (Some(x), Some(y)) if spec.descending => y.cmp(&x),
+                    (Some(x), Some(y)) => x.cmp(&y),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+            }
+        };
+        if spec.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// 筛选、排序 (稳定排序，相同键保持存储顺序) 并分页
+    pub fn apply(&self, accounts: Vec<Account>, ctx: &FilterContext) -> AccountPage<Account> {
+        let mut matched: Vec<Account> = accounts
+            .into_iter()
+            .filter(|a| self.filter.matches(a, ctx))
+            .collect();
+        if self.sort.is_some() {
+            matched.sort_by(|a, b| self.compare(a, b, ctx));
+        }
+        let total = matched.len();
+        let Some((page, page_size)) = self.page else {
+            return AccountPage {
+                accounts: matched,
+                total,
+                page: None,
+                page_size: None,
+            };
+        };
+        let accounts = matched
+            .into_iter()
+            .skip((page - 1).saturating_mul(page_size))
+            .take(page_size)
+            .collect();
+        AccountPage {
+            accounts,
+            total,
+            page: Some(page),
+            page_size: Some(page_size),
+        }
+    }
+}
+
+// ============================================================================
+// 批量操作
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAction {
+    /// 禁用反代 (proxy_disabled)
+    Disable,
+    Enable,
+    Warmup,
+    BindProxy,
+    UnbindProxy,
+    Delete,
+    AddTags,
+    RemoveTags,
+}
+
+/// 批量操作请求: 通过筛选表达式和/或显式 ID 列表选择账号 (同时提供时取交集)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkActionRequest {
+    pub action: BulkAction,
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub account_ids: Vec<String>,
+    /// bind_proxy 的目标代理
+    #[serde(default)]
+    pub proxy_id: Option<String>,
+    /// add_tags / remove_tags 的标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// disable 的原因
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// 只返回匹配的账号，不执行操作
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl BulkActionRequest {
+    /// 校验参数并解析筛选表达式；筛选与 ID 列表都为空时拒绝，避免误操作全部账号
+    pub fn prepare(&self) -> Result<AccountFilter, String> {
+        let filter = parse_filter(self.filter.as_deref().unwrap_or(""))?;
+        if filter.is_empty() && self.account_ids.is_empty() {
+            return Err("A filter expression or explicit account IDs are required".to_string());
+        }
+        match self.action {
+            BulkAction::BindProxy if self.proxy_id.as_deref().map_or("", str::trim).is_empty() => {
+                Err("proxyId is required for bind_proxy".to_string())
+            }
+            BulkAction::AddTags | BulkAction::RemoveTags
+                if normalize_tags(self.tags.clone())?.is_empty() =>
+            {
+                Err("tags are required for add_tags / remove_tags".to_string())
+            }
+            _ => Ok(filter),
+        }
+    }
+}
+
+/// 选出批量操作的目标账号 (保持存储顺序)
+pub fn select_targets(
+    accounts: Vec<Account>,
+    filter: &AccountFilter,
+    account_ids: &[String],
+    ctx: &FilterContext,
+) -> Vec<Account> {
+    let ids: HashSet<&str> = account_ids.iter().map(String::as_str).collect();
+    accounts
+        .into_iter()
+        .filter(|a| ids.is_empty() || ids.contains(a.id.as_str()))
+        .filter(|a| filter.matches(a, ctx))
+        .collect()
+}
+
+/// 单个账号的批量操作结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkItemResult {
+    pub account_id: String,
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 批量操作报告
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkActionReport {
+    pub action: BulkAction,
+    pub dry_run: bool,
+    pub matched: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+/// 对目标账号执行批量操作，逐个账号记录结果
+pub async fn run_bulk_action(
+    request: &BulkActionRequest,
+    targets: Vec<Account>,
+) -> Result<BulkActionReport, String> {
+    let ids: Vec<String> = targets.iter().map(|a| a.id.clone()).collect();
+    let outcomes: Vec<Result<(), String>> = if request.dry_run {
+        ids.iter().map(|_| Ok(())).collect()
+    } else {
+        match request.action {
+            BulkAction::Disable | BulkAction::Enable => {
+                let enable = request.action == BulkAction::Enable;
+                let reason = request.reason.as_deref().unwrap_or("Bulk disable");
+                ids.iter()
+                    .map(|id| account::toggle_proxy_status(id, enable, Some(reason)))
+                    .collect()
+            }
+            BulkAction::Warmup => {
+                stream::iter(ids.iter())
+                    .map(|id| async move {
+                        crate::modules::quota::warm_up_account(id).await.map(|_| ())
+                    })
+                    .buffered(WARMUP_CONCURRENCY)
+                    .collect()
+                    .await
+            }
+            BulkAction::BindProxy | BulkAction::UnbindProxy => {
+                let proxy_id = match request.action {
+                    BulkAction::BindProxy => request.proxy_id.as_deref().map(str::trim),
+                    _ => None,
+                };
+                let changes: Vec<(String, Option<String>)> = ids
+                    .iter()
+                    .map(|id| (id.clone(), proxy_id.map(str::to_string)))
+                    .collect();
+                crate::proxy::proxy_pool::apply_account_bindings(&changes).await
+            }
+            BulkAction::Delete => {
+                // 单事务删除，结果对所有账号一致
+                let result = account::delete_accounts(&ids);
+                ids.iter().map(|_| result.clone()).collect()
+            }
+            BulkAction::AddTags | BulkAction::RemoveTags => {
+                let tags = normalize_tags(request.tags.clone())?;
+                let adding = request.action == BulkAction::AddTags;
+                ids.iter()
+                    .map(|id| {
+                        account::update_account(id, |account| {
+                            let mut merged = std::mem::take(&mut account.tags);
+                            if adding {
+                                merged.extend(tags.iter().cloned());
+                            } else {
+                                let removed: HashSet<String> =
+                                    tags.iter().map(|t| t.to_lowercase()).collect();
+                                merged.retain(|t| !removed.contains(&t.to_lowercase()));
+                            }
+                            account.tags = normalize_tags(merged)?;
+                            Ok(())
+                        })
+                    })
+                    .collect()
+            }
+        }
+    };
+
+    let results: Vec<BulkItemResult> = targets
+        .into_iter()
+        .zip(outcomes)
+        .map(|(account, outcome)| BulkItemResult {
+            account_id: account.id,
+            email: account.email,
+            error: outcome.err(),
+        })
+        .collect();
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    let report = BulkActionReport {
+        action: request.action,
+        dry_run: request.dry_run,
+        matched: results.len(),
+        succeeded: if request.dry_run {
+            0
+        } else {
+            results.len() - failed
+        },
+        failed,
+        results,
+    };
+    if !report.dry_run {
+        logger::log_info(&format!(
+            "[Accounts] Bulk {:?}: {} matched, {} succeeded, {} failed",
+            report.action, report.matched, report.succeeded, report.failed
+        ));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{QuotaData, TokenData};
+
+    fn account(id: &str, tags: &[&str], tier: Option<&str>, quota: &[(&str, i32)]) -> Account {
+        let mut acc = Account::new(
+            id.to_string(),
+            format!("{}@test.com", id),
+            TokenData::new("atk".into(), "rtk".into(), 3600, None, None, None),
+        );
+        acc.tags = tags.iter().map(|t| t.to_string()).collect();
+        if tier.is_some() || !quota.is_empty() {
+            let mut q = QuotaData::new();
+            q.subscription_tier = tier.map(str::to_string);
+            for (model, pct) in quota {
+                q.add_model(model.to_string(), *pct, String::new());
+            }
+            acc.quota = Some(q);
+        }
+        acc
+    }
+
+    fn fixtures() -> (Vec<Account>, FilterContext) {
+        let mut a = account(
+            "a",
+            &["owner:alice", "region:eu"],
+            Some("PRO"),
+            &[("claude-sonnet-4-5", 10), ("gemini-3-pro", 80)],
+        );
+        a.last_used = 1_000;
+        let mut b = account(
+            "b",
+            &["owner:bob"],
+            Some("ULTRA"),
+            &[("claude-sonnet-4-5", 90)],
+        );
+        b.last_used = 900_000;
+        let mut c = account("c", &[], None, &[]);
+        c.last_used = 300_000;
+        c.proxy_disabled = true;
+        let ctx = FilterContext {
+            now: 1_000_000,
+            health: HashMap::from([("b".to_string(), HealthState::RateLimited)]),
+            bindings: HashMap::from([("a".to_string(), "px-1".to_string())]),
+        };
+        (vec![a, b, c], ctx)
+    }
+
+    fn ids(filter: &str) -> Vec<String> {
+        let (accounts, ctx) = fixtures();
+        let filter = parse_filter(filter).unwrap();
+        accounts
+            .into_iter()
+            .filter(|a| filter.matches(a, &ctx))
+            .map(|a| a.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = normalize_tags(vec![
+            " tier:gold ".into(),
+            "".into(),
+            "TIER:GOLD".into(),
+            "eu".into(),
+        ])
+        .unwrap();
+        assert_eq!(tags, vec!["tier:gold", "eu"]);
+        assert!(normalize_tags(vec!["a b".into()]).is_err());
+        assert!(normalize_tags(vec!["a,b".into()]).is_err());
+        assert!(normalize_tags((0..=MAX_TAGS).map(|i| format!("t{}", i)).collect()).is_err());
+    }
+
+    #[test]
+    fn test_filter_expressions() {
+        assert_eq!(ids(""), vec!["a", "b", "c"]);
+        assert_eq!(ids("tag:owner:*"), vec!["a", "b"]);
+        assert_eq!(ids("tag:OWNER:ALICE,owner:bob -tag:region:eu"), vec!["b"]);
+        assert_eq!(ids("tier:ultra,none"), vec!["b", "c"]);
+        assert_eq!(ids("health:healthy"), vec!["a"]);
+        assert_eq!(ids("health:rate_limited,disabled"), vec!["b", "c"]);
+        assert_eq!(ids("quota:claude-*<20"), vec!["a"]);
+        assert_eq!(ids("quota:gemini-*>=80"), vec!["a"]);
+        assert_eq!(ids("proxy:none"), vec!["b", "c"]);
+        assert_eq!(ids("proxy:px-1"), vec!["a"]);
+        assert_eq!(ids("last_used<7d"), vec!["a", "c"]);
+        assert_eq!(ids("last_used>=1970-01-02"), vec!["b", "c"]);
+        assert_eq!(ids("disabled:false b@"), vec!["b"]);
+
+        assert!(parse_filter("color:red").is_err());
+        assert!(parse_filter("health:sleepy").is_err());
+        assert!(parse_filter("quota:claude").is_err());
+        assert!(parse_filter("last_used<soon").is_err());
+        assert!(parse_filter("tag:").is_err());
+    }
+
+    #[test]
+    fn test_sort_and_paginate() {
+        let (accounts, ctx) = fixtures();
+        let query = |sort: &str, page: Option<usize>, page_size: Option<usize>| {
+            let plan = QueryPlan::parse(&AccountQuery {
+                filter: None,
+                sort: Some(sort.to_string()),
+                page,
+                page_size,
+            })
+            .unwrap();
+            let result = plan.apply(accounts.clone(), &ctx);
+            (
+                result.total,
+                result
+                    .accounts
+                    .into_iter()
+                    .map(|a| a.id)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            query("-last_used", None, None),
+            (3, vec!["b".into(), "c".into(), "a".into()])
+        );
+        assert_eq!(query("-tier", None, None).1, vec!["b", "a", "c"]);
+        assert_eq!(query("health", None, None).1, vec!["a", "b", "c"]);
+        // 无配额数据的账号始终排在最后
+        assert_eq!(query("quota:claude-*", None, None).1, vec!["a", "b", "c"]);
+        assert_eq!(query("-quota:claude-*", None, None).1, vec!["b", "a", "c"]);
+        assert_eq!(query("email", Some(2), Some(2)), (3, vec!["c".into()]));
+        assert_eq!(query("email", Some(5), Some(2)), (3, vec![]));
+        assert!(QueryPlan::parse(&AccountQuery {
+            sort: Some("color".into()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_bulk_request_selection() {
+        let (accounts, ctx) = fixtures();
+        let request = |action: BulkAction, filter: Option<&str>, ids: &[&str]| BulkActionRequest {
+            action,
+            filter: filter.map(str::to_string),
+            account_ids: ids.iter().map(|s| s.to_string()).collect(),
+            proxy_id: None,
+            tags: Vec::new(),
+            reason: None,
+            dry_run: true,
+        };
+
+        // 空筛选且无 ID 列表时拒绝
+        assert!(request(BulkAction::Disable, Some("  "), &[])
+            .prepare()
+            .is_err());
+        assert!(request(BulkAction::BindProxy, Some("tier:pro"), &[])
+            .prepare()
+            .is_err());
+        assert!(request(BulkAction::AddTags, Some("tier:pro"), &[])
+            .prepare()
+            .is_err());
+
+        let req = request(BulkAction::Disable, Some("tag:owner:*"), &["b", "c"]);
+        let filter = req.prepare().unwrap();
+        let targets = select_targets(accounts.clone(), &filter, &req.account_ids, &ctx);
+        assert_eq!(
+            targets.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(),
+            vec!["b"]
+        );
+
+        let req = request(BulkAction::Delete, None, &["a", "c"]);
+        let filter = req.prepare().unwrap();
+        let targets = select_targets(accounts, &filter, &req.account_ids, &ctx);
+        assert_eq!(targets.len(), 2);
+    }
+}
@@ -22,6 +22,20 @@ impl AccountService {
         // 2. 获取用户信息
         let user_info = modules::oauth::get_user_info(&token_res.access_token, Some(&temp_account_id)).await?;
 
+        self.add_validated_account(refresh_token, token_res, user_info, account_type, anthropic_auth_token, anthropic_base_url)
+            .await
+    }
+
+    /// [NEW] 持久化已通过 get_user_info 验证的账号 (供批量导入复用，避免重复刷新 Token)
+    pub async fn add_validated_account(
+        &self,
+        refresh_token: &str,
+        token_res: modules::oauth::TokenResponse,
+        user_info: modules::oauth::UserInfo,
+        account_type: Option<String>,
+        anthropic_auth_token: Option<String>,
+        anthropic_base_url: Option<String>,
+    ) -> Result<Account, String> {
         // 3. 获取项目 ID (尝试)
         let project_id = crate::proxy::project_resolver::fetch_project_id(&token_res.access_token)
             .await
@@ -60,21 +74,28 @@ impl AccountService {
         let access_token = token_res.access_token.clone();
         match modules::quota::fetch_quota(&access_token, &email_for_log, Some(&account.id)).await {
             Ok((quota_data, new_project_id)) => {
-                account.quota = Some(quota_data);
-                if let Some(pid) = new_project_id {
-                    account.token.project_id = Some(pid);
-                }
-                // 保存更新后的账号信息
-                if let Err(e) = modules::account::save_account(&account) {
-                    modules::logger::log_warn(&format!(
-                        "[Service] Failed to save quota for {}: {}",
-                        email_for_log, e
-                    ));
-                } else {
-                    modules::logger::log_info(&format!(
-                        "[Service] Fetched quota for new account: {}",
-                        email_for_log
-                    ));
+                // [FIX] 在存储事务内只更新配额与 project_id，避免覆盖查询期间的其他修改
+                let updated = modules::account::update_account(&account.id, |a| {
+                    a.quota = Some(quota_data);
+                    if let Some(pid) = new_project_id {
+                        a.token.project_id = Some(pid);
+                    }
+                    Ok(a.clone())
+                });
+                match updated {
+                    Ok(updated) => {
+                        account = updated;
+                        modules::logger::log_info(&format!(
+                            "[Service] Fetched quota for new account: {}",
+                            email_for_log
+                        ));
+                    }
+                    Err(e) => {
+                        modules::logger::log_warn(&format!(
+                            "[Service] Failed to save quota for {}: {}",
+                            email_for_log, e
+                        ));
+                    }
                 }
             }
             Err(e) => {
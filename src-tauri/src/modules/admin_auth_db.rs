@@ -0,0 +1,669 @@
+//! Admin Auth Database Module
+//! 管理员身份、作用域 API Key 与审计日志
+
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// 作用域 API Key 前缀，用于与旧版 admin_password / api_key 区分
+pub const ADMIN_KEY_PREFIX: &str = "agk_";
+
+/// 管理员角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    /// 只读
+    Viewer,
+    /// 日常运维 (账号、代理、令牌等写操作，不含导出与配置)
+    Operator,
+    /// 全部权限
+    Owner,
+}
+
+impl AdminRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::Viewer => "viewer",
+            AdminRole::Operator => "operator",
+            AdminRole::Owner => "owner",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "viewer" => Ok(AdminRole::Viewer),
+            "operator" => Ok(AdminRole::Operator),
+            "owner" => Ok(AdminRole::Owner),
+            other => Err(format!("Unknown admin role '{}'", other)),
+        }
+    }
+}
+
+/// 管理员身份
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminIdentity {
+    pub id: String,
+    pub name: String,
+    pub role: AdminRole,
+    pub disabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 作用域 API Key (不含明文)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminApiKey {
+    pub id: String,
+    pub identity_id: String,
+    pub name: String,
+    /// 明文前缀，便于识别
+    pub key_prefix: String,
+    /// 授予的作用域，为空表示继承角色的全部作用域
+    pub scopes: Vec<String>,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub revoked: bool,
+}
+
+/// 新建 API Key 的返回值 (明文仅返回这一次)
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedAdminApiKey {
+    #[serde(flatten)]
+    pub key: AdminApiKey,
+    pub secret: String,
+}
+
+/// 通过 API Key 解析出的身份
+#[derive(Debug, Clone)]
+pub struct ResolvedAdminKey {
+    pub identity: AdminIdentity,
+    pub key: AdminApiKey,
+}
+
+/// 审计日志条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminAuditEntry {
+    #[serde(default)]
+    pub id: i64,
+    pub timestamp: i64,
+    /// 操作者名称 ("legacy-admin" / "anonymous" / 身份名)
+    pub actor: String,
+    pub identity_id: Option<String>,
+    pub key_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub scope: Option<String>,
+    pub status: u16,
+    pub client_ip: Option<String>,
+    pub detail: Option<String>,
+}
+
+pub fn get_admin_auth_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("admin_auth.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_admin_auth_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "foreign_keys", "ON")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS admin_identities (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            role TEXT NOT NULL,
+            disabled INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS admin_api_keys (
+            id TEXT PRIMARY KEY,
+            identity_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            key_prefix TEXT NOT NULL,
+            scopes TEXT NOT NULL DEFAULT '[]',
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER,
+            last_used_at INTEGER,
+            revoked INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY(identity_id) REFERENCES admin_identities(id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS admin_audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            actor TEXT NOT NULL,
+            identity_id TEXT,
+            key_id TEXT,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            scope TEXT,
+            status INTEGER NOT NULL,
+            client_ip TEXT,
+            detail TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_admin_audit_timestamp ON admin_audit_log (timestamp DESC);
+        CREATE INDEX IF NOT EXISTS idx_admin_audit_actor ON admin_audit_log (actor);",
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_tables(&conn)
+}
+
+fn hash_key(secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(secret.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let body: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", ADMIN_KEY_PREFIX, body)
+}
+
+fn row_to_identity(row: &rusqlite::Row) -> rusqlite::Result<AdminIdentity> {
+    let role: String = row.get("role")?;
+    Ok(AdminIdentity {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        role: AdminRole::parse(&role).unwrap_or(AdminRole::Viewer),
+        disabled: row.get("disabled")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+fn row_to_key(row: &rusqlite::Row) -> rusqlite::Result<AdminApiKey> {
+    let scopes: String = row.get("scopes")?;
+    Ok(AdminApiKey {
+        id: row.get("id")?,
+        identity_id: row.get("identity_id")?,
+        name: row.get("name")?,
+        key_prefix: row.get("key_prefix")?,
+        scopes: serde_json::from_str(&scopes).unwrap_or_default(),
+        created_at: row.get("created_at")?,
+        expires_at: row.get("expires_at")?,
+        last_used_at: row.get("last_used_at")?,
+        revoked: row.get("revoked")?,
+    })
+}
+
+// ============================================================================
+// 身份
+// ============================================================================
+
+pub fn create_identity(name: &str, role: AdminRole) -> Result<AdminIdentity, String> {
+    let conn = connect_db()?;
+    create_identity_with_conn(&conn, name, role)
+}
+
+fn create_identity_with_conn(conn: &Connection, name: &str, role: AdminRole) -> Result<AdminIdentity, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Identity name must not be empty".to_string());
+    }
+    let now = chrono::Utc::now().timestamp();
+    let identity = AdminIdentity {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        role,
+        disabled: false,
+        created_at: now,
+        updated_at: now,
+    };
+    conn.execute(
+        "INSERT INTO admin_identities (id, name, role, disabled, created_at, updated_at)
+         VALUES (?1, ?2, ?3, 0, ?4, ?5)",
+        params![identity.id, identity.name, role.as_str(), now, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(identity)
+}
+
+pub fn list_identities() -> Result<Vec<AdminIdentity>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare("SELECT * FROM admin_identities ORDER BY created_at ASC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_identity)
+        .map_err(|e| e.to_string())?;
+
+    let mut identities = Vec::new();
+    for row in rows {
+        identities.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(identities)
+}
+
+pub fn update_identity(id: &str, role: Option<AdminRole>, disabled: Option<bool>) -> Result<(), String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    let updated = conn
+        .execute(
+            "UPDATE admin_identities SET
+                role = COALESCE(?1, role),
+                disabled = COALESCE(?2, disabled),
+                updated_at = ?3
+             WHERE id = ?4",
+            params![role.map(|r| r.as_str()), disabled, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Admin identity {} not found", id));
+    }
+    Ok(())
+}
+
+/// 删除身份 (其 API Key 随之级联删除)
+pub fn delete_identity(id: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    delete_identity_with_conn(&conn, id)
+}
+
+fn delete_identity_with_conn(conn: &Connection, id: &str) -> Result<(), String> {
+    let deleted = conn
+        .execute("DELETE FROM admin_identities WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Admin identity {} not found", id));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// API Key
+// ============================================================================
+
+pub fn create_api_key(
+    identity_id: &str,
+    name: &str,
+    scopes: Vec<String>,
+    expires_at: Option<i64>,
+) -> Result<CreatedAdminApiKey, String> {
+    let conn = connect_db()?;
+    create_api_key_with_conn(&conn, identity_id, name, scopes, expires_at)
+}
+
+fn create_api_key_with_conn(
+    conn: &Connection,
+    identity_id: &str,
+    name: &str,
+    scopes: Vec<String>,
+    expires_at: Option<i64>,
+) -> Result<CreatedAdminApiKey, String> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM admin_identities WHERE id = ?1)",
+            params![identity_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Admin identity {} not found", identity_id));
+    }
+
+    let secret = generate_secret();
+    let key = AdminApiKey {
+        id: Uuid::new_v4().to_string(),
+        identity_id: identity_id.to_string(),
+        name: name.to_string(),
+        key_prefix: secret.chars().take(12).collect(),
+        scopes,
+        created_at: chrono::Utc::now().timestamp(),
+        expires_at,
+        last_used_at: None,
+        revoked: false,
+    };
+
+    conn.execute(
+        "INSERT INTO admin_api_keys (id, identity_id, name, key_hash, key_prefix, scopes, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            key.id,
+            key.identity_id,
+            key.name,
+            hash_key(&secret),
+            key.key_prefix,
+            serde_json::to_string(&key.scopes).map_err(|e| e.to_string())?,
+            key.created_at,
+            key.expires_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(CreatedAdminApiKey { key, secret })
+}
+
+pub fn list_api_keys(identity_id: Option<&str>) -> Result<Vec<AdminApiKey>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT * FROM admin_api_keys
+             WHERE (?1 IS NULL OR identity_id = ?1)
+             ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![identity_id], row_to_key)
+        .map_err(|e| e.to_string())?;
+
+    let mut keys = Vec::new();
+    for row in rows {
+        keys.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(keys)
+}
+
+pub fn revoke_api_key(id: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    let updated = conn
+        .execute("UPDATE admin_api_keys SET revoked = 1 WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Admin API key {} not found", id));
+    }
+    Ok(())
+}
+
+/// 校验明文 API Key，返回有效 (未撤销/未过期/身份未禁用) 的身份
+pub fn resolve_api_key(secret: &str) -> Result<Option<ResolvedAdminKey>, String> {
+    let conn = connect_db()?;
+    resolve_api_key_with_conn(&conn, secret, chrono::Utc::now().timestamp())
+}
+
+fn resolve_api_key_with_conn(
+    conn: &Connection,
+    secret: &str,
+    now: i64,
+) -> Result<Option<ResolvedAdminKey>, String> {
+    let key = conn
+        .query_row(
+            "SELECT * FROM admin_api_keys WHERE key_hash = ?1",
+            params![hash_key(secret)],
+            row_to_key,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let Some(key) = key else {
+        return Ok(None);
+    };
+    let resolved = validate_key_with_conn(conn, key, now)?;
+    if let Some(resolved) = &resolved {
+        let _ = conn.execute(
+            "UPDATE admin_api_keys SET last_used_at = ?1 WHERE id = ?2",
+            params![now, resolved.key.id],
+        );
+    }
+    Ok(resolved)
+}
+
+/// 按 ID 重新解析 API Key (会话每次请求时确认 Key 未撤销、身份未禁用，并取得最新角色)
+pub fn resolve_api_key_by_id(key_id: &str) -> Result<Option<ResolvedAdminKey>, String> {
+    let conn = connect_db()?;
+    resolve_api_key_by_id_with_conn(&conn, key_id, chrono::Utc::now().timestamp())
+}
+
+fn resolve_api_key_by_id_with_conn(
+    conn: &Connection,
+    key_id: &str,
+    now: i64,
+) -> Result<Option<ResolvedAdminKey>, String> {
+    let key = conn
+        .query_row(
+            "SELECT * FROM admin_api_keys WHERE id = ?1",
+            params![key_id],
+            row_to_key,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match key {
+        Some(key) => validate_key_with_conn(conn, key, now),
+        None => Ok(None),
+    }
+}
+
+/// Key 未撤销/未过期且所属身份未禁用时返回其身份
+fn validate_key_with_conn(
+    conn: &Connection,
+    key: AdminApiKey,
+    now: i64,
+) -> Result<Option<ResolvedAdminKey>, String> {
+    if key.revoked || key.expires_at.is_some_and(|exp| exp <= now) {
+        return Ok(None);
+    }
+
+    let identity = conn
+        .query_row(
+            "SELECT * FROM admin_identities WHERE id = ?1",
+            params![key.identity_id],
+            row_to_identity,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let Some(identity) = identity.filter(|i| !i.disabled) else {
+        return Ok(None);
+    };
+
+    Ok(Some(ResolvedAdminKey { identity, key }))
+}
+
+// ============================================================================
+// 审计日志
+// ============================================================================
+
+pub fn insert_audit(entry: &AdminAuditEntry) -> Result<(), String> {
+    let conn = connect_db()?;
+    insert_audit_with_conn(&conn, entry)
+}
+
+fn insert_audit_with_conn(conn: &Connection, entry: &AdminAuditEntry) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO admin_audit_log
+            (timestamp, actor, identity_id, key_id, method, path, scope, status, client_ip, detail)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            entry.timestamp,
+            entry.actor,
+            entry.identity_id,
+            entry.key_id,
+            entry.method,
+            entry.path,
+            entry.scope,
+            entry.status,
+            entry.client_ip,
+            entry.detail
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 查询审计日志 (按时间倒序)
+pub fn list_audit(limit: usize, actor: Option<&str>, since: Option<i64>) -> Result<Vec<AdminAuditEntry>, String> {
+    let conn = connect_db()?;
+    list_audit_with_conn(&conn, limit, actor, since)
+}
+
+fn list_audit_with_conn(
+    conn: &Connection,
+    limit: usize,
+    actor: Option<&str>,
+    since: Option<i64>,
+) -> Result<Vec<AdminAuditEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, actor, identity_id, key_id, method, path, scope, status, client_ip, detail
+             FROM admin_audit_log
+             WHERE (?1 IS NULL OR actor = ?1) AND (?2 IS NULL OR timestamp >= ?2)
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![actor, since, limit as i64], |row| {
+            Ok(AdminAuditEntry {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                actor: row.get(2)?,
+                identity_id: row.get(3)?,
+                key_id: row.get(4)?,
+                method: row.get(5)?,
+                path: row.get(6)?,
+                scope: row.get(7)?,
+                status: row.get(8)?,
+                client_ip: row.get(9)?,
+                detail: row.get(10)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_api_key_resolution_respects_revocation_and_expiry() {
+        let conn = memory_db();
+        let identity = create_identity_with_conn(&conn, "ci-bot", AdminRole::Operator).unwrap();
+
+        let created = create_api_key_with_conn(
+            &conn,
+            &identity.id,
+            "deploy",
+            vec!["accounts:write".to_string()],
+            Some(1_000),
+        )
+        .unwrap();
+        assert!(created.secret.starts_with(ADMIN_KEY_PREFIX));
+        assert!(created.secret.starts_with(&created.key.key_prefix));
+
+        let resolved = resolve_api_key_with_conn(&conn, &created.secret, 500)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.identity.name, "ci-bot");
+        assert_eq!(resolved.key.scopes, vec!["accounts:write".to_string()]);
+
+        // 过期
+        assert!(resolve_api_key_with_conn(&conn, &created.secret, 1_000).unwrap().is_none());
+        // 错误明文
+        assert!(resolve_api_key_with_conn(&conn, "agk_wrong", 500).unwrap().is_none());
+
+        // 禁用身份后失效
+        conn.execute("UPDATE admin_identities SET disabled = 1", []).unwrap();
+        assert!(resolve_api_key_with_conn(&conn, &created.secret, 500).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resolve_by_id_reflects_revocation_and_role_changes() {
+        let conn = memory_db();
+        let identity = create_identity_with_conn(&conn, "ci-bot", AdminRole::Owner).unwrap();
+        let created =
+            create_api_key_with_conn(&conn, &identity.id, "deploy", Vec::new(), None).unwrap();
+
+        let resolved = resolve_api_key_by_id_with_conn(&conn, &created.key.id, 500)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.identity.role, AdminRole::Owner);
+
+        // 角色降级后立即生效
+        conn.execute("UPDATE admin_identities SET role = 'viewer'", []).unwrap();
+        let resolved = resolve_api_key_by_id_with_conn(&conn, &created.key.id, 500)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.identity.role, AdminRole::Viewer);
+
+        conn.execute("UPDATE admin_api_keys SET revoked = 1", []).unwrap();
+        assert!(resolve_api_key_by_id_with_conn(&conn, &created.key.id, 500).unwrap().is_none());
+        assert!(resolve_api_key_by_id_with_conn(&conn, "missing", 500).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_delete_identity_reports_missing() {
+        let conn = memory_db();
+        let identity = create_identity_with_conn(&conn, "ci-bot", AdminRole::Operator).unwrap();
+        create_api_key_with_conn(&conn, &identity.id, "deploy", Vec::new(), None).unwrap();
+
+        delete_identity_with_conn(&conn, &identity.id).unwrap();
+        let keys: i64 = conn
+            .query_row("SELECT COUNT(*) FROM admin_api_keys", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(keys, 0);
+
+        let err = delete_identity_with_conn(&conn, &identity.id).unwrap_err();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_audit_log_filters() {
+        let conn = memory_db();
+        for (ts, actor) in [(100, "alice"), (200, "bob"), (300, "alice")] {
+            insert_audit_with_conn(
+                &conn,
+                &AdminAuditEntry {
+                    id: 0,
+                    timestamp: ts,
+                    actor: actor.to_string(),
+                    identity_id: None,
+                    key_id: None,
+                    method: "POST".to_string(),
+                    path: "/accounts/export".to_string(),
+                    scope: Some("accounts:export".to_string()),
+                    status: 200,
+                    client_ip: Some("10.0.0.2".to_string()),
+                    detail: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let all = list_audit_with_conn(&conn, 10, None, None).unwrap();
+        assert_eq!(all.iter().map(|e| e.timestamp).collect::<Vec<_>>(), vec![300, 200, 100]);
+
+        let alice = list_audit_with_conn(&conn, 10, Some("alice"), Some(150)).unwrap();
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].timestamp, 300);
+    }
+}
@@ -0,0 +1,1190 @@
+//! Backup Module
+//! 数据目录备份与恢复: 使用 SQLite 在线备份 API 生成一致性快照，与账号文件、配置及 MITM CA 一起打包为
+//! 带清单与校验和的 tar.gz 归档 (可选口令加密)，支持按组件选择性恢复与定时轮换
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use argon2::Argon2;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::models::BackupConfig;
+use crate::modules::{config, logger};
+
+/// 定时备份使用的加密口令环境变量 (口令不写入配置文件)
+pub const BACKUP_PASSPHRASE_ENV: &str = "ABV_BACKUP_PASSPHRASE";
+
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+/// 归档内数据文件的前缀目录
+const DATA_PREFIX: &str = "data";
+const DEFAULT_BACKUP_DIR: &str = "backups";
+
+const MANUAL_PREFIX: &str = "abv-backup-";
+const SCHEDULED_PREFIX: &str = "abv-scheduled-";
+const PRE_RESTORE_PREFIX: &str = "abv-pre-restore-";
+const ARCHIVE_EXT: &str = ".tar.gz";
+const ENCRYPTED_EXT: &str = ".tar.gz.enc";
+
+/// 加密归档: `MAGIC || salt(16) || nonce_prefix(7) || chunk...`，每个分块独立 AES-256-GCM 认证，
+/// nonce = prefix || 分块序号 (u32 BE) || 末块标记，防止分块重排与截断
+const ENC_MAGIC: &[u8; 8] = b"ABVBAK01";
+const SALT_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 7;
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+
+/// 定时备份检查间隔
+const TICK_INTERVAL_SECS: u64 = 600;
+
+/// 串行化备份与恢复，避免恢复过程中生成半新半旧的快照
+static BACKUP_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+
+/// 可备份的数据组件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupComponent {
+    Accounts,
+    ProxyLogs,
+    TokenStats,
+    Security,
+    UserTokens,
+    AdminAuth,
+    RuntimeState,
+    Warmup,
+    Config,
+    Vault,
+    MitmCa,
+}
+
+impl BackupComponent {
+    pub const ALL: [BackupComponent; 11] = [
+        BackupComponent::Accounts,
+        BackupComponent::ProxyLogs,
+        BackupComponent::TokenStats,
+        BackupComponent::Security,
+        BackupComponent::UserTokens,
+        BackupComponent::AdminAuth,
+        BackupComponent::RuntimeState,
+        BackupComponent::Warmup,
+        BackupComponent::Config,
+        BackupComponent::Vault,
+        BackupComponent::MitmCa,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BackupComponent::Accounts => "accounts",
+            BackupComponent::ProxyLogs => "proxy_logs",
+            BackupComponent::TokenStats => "token_stats",
+            BackupComponent::Security => "security",
+            BackupComponent::UserTokens => "user_tokens",
+            BackupComponent::AdminAuth => "admin_auth",
+            BackupComponent::RuntimeState => "runtime_state",
+            BackupComponent::Warmup => "warmup",
+            BackupComponent::Config => "config",
+            BackupComponent::Vault => "vault",
+            BackupComponent::MitmCa => "mitm_ca",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+
+    /// 组件包含的 SQLite 数据库 (相对数据目录)
+    fn databases(self) -> &'static [&'static str] {
+        match self {
+            BackupComponent::Accounts => &["accounts.db"],
+            BackupComponent::ProxyLogs => &["proxy_logs.db"],
+            BackupComponent::TokenStats => &["token_stats.db"],
+            BackupComponent::Security => &["security.db"],
+            BackupComponent::UserTokens => &["user_tokens.db"],
+            BackupComponent::AdminAuth => &["admin_auth.db"],
+            BackupComponent::RuntimeState => &["runtime_state.db"],
+            BackupComponent::Warmup => &["warmup_history.db"],
+            BackupComponent::Config | BackupComponent::Vault | BackupComponent::MitmCa => &[],
+        }
+    }
+
+    /// 组件包含的普通文件或目录 (相对数据目录)
+    fn paths(self) -> &'static [&'static str] {
+        match self {
+            // 旧版账号文件布局及迁移后保留的副本
+            BackupComponent::Accounts => &[
+                "accounts.json",
+                "accounts",
+                "accounts.json.migrated",
+                "accounts.migrated",
+            ],
+            BackupComponent::Warmup => &["warmup_history.json"],
+            BackupComponent::Config => &[
+                "gui_config.json",
+                "http_api_settings.json",
+                "update_settings.json",
+                "device_original.json",
+            ],
+            BackupComponent::Vault => &[crate::utils::vault::VAULT_FILE],
+            BackupComponent::MitmCa => &["mitm"],
+            _ => &[],
+        }
+    }
+
+    /// 恢复后需重启才能完全生效 (启动时加载到内存的数据)
+    fn needs_restart(self) -> bool {
+        matches!(
+            self,
+            BackupComponent::Security
+                | BackupComponent::UserTokens
+                | BackupComponent::AdminAuth
+                | BackupComponent::RuntimeState
+                | BackupComponent::Config
+                | BackupComponent::Vault
+                | BackupComponent::MitmCa
+        )
+    }
+}
+
+/// 清单中的单个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// 相对数据目录的路径 (`/` 分隔)
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+    #[serde(default)]
+    pub sqlite: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestComponent {
+    pub component: BackupComponent,
+    pub files: Vec<ManifestFile>,
+}
+
+/// 归档清单 (`manifest.json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: i64,
+    pub components: Vec<ManifestComponent>,
+}
+
+/// 备份结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupReport {
+    pub path: String,
+    pub size: u64,
+    pub encrypted: bool,
+    pub created_at: i64,
+    pub components: Vec<BackupComponent>,
+    pub files: usize,
+}
+
+/// 备份目录中的归档
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub file_name: String,
+    pub size: u64,
+    pub modified: i64,
+    pub encrypted: bool,
+    pub scheduled: bool,
+}
+
+/// 恢复结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub archive_created_at: i64,
+    pub restored: Vec<BackupComponent>,
+    pub files_restored: usize,
+    /// 恢复前自动生成的安全备份
+    pub pre_restore_backup: Option<String>,
+    pub restart_recommended: bool,
+}
+
+/// 解析备份目录: 未配置时为 `<数据目录>/backups`，相对路径按数据目录解析
+fn resolve_backup_dir(data_dir: &Path, directory: Option<&str>) -> PathBuf {
+    match directory.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(dir) if Path::new(dir).is_absolute() => PathBuf::from(dir),
+        Some(dir) => data_dir.join(dir),
+        None => data_dir.join(DEFAULT_BACKUP_DIR),
+    }
+}
+
+fn configured_backup_dir(data_dir: &Path) -> PathBuf {
+    let directory = config::load_app_config()
+        .ok()
+        .and_then(|c| c.backup.directory);
+    resolve_backup_dir(data_dir, directory.as_deref())
+}
+
+/// 空列表表示全部组件，去重并保持固定顺序
+fn normalize_components(components: &[BackupComponent]) -> Vec<BackupComponent> {
+    BackupComponent::ALL
+        .into_iter()
+        .filter(|c| components.is_empty() || components.contains(c))
+        .collect()
+}
+
+/// 在目录中生成不冲突的归档文件名
+fn unique_archive_path(dir: &Path, prefix: &str, encrypted: bool) -> PathBuf {
+    // 毫秒精度保证文件名顺序与生成顺序一致
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    let ext = if encrypted {
+        ENCRYPTED_EXT
+    } else {
+        ARCHIVE_EXT
+    };
+    let mut candidate = dir.join(format!("{}{}{}", prefix, stamp, ext));
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{}{}-{}{}", prefix, stamp, n, ext));
+        n += 1;
+    }
+    candidate
+}
+
+/// 创建备份: `dest` 为空时写入配置的备份目录，为目录时在其中生成文件名
+pub fn create_backup(
+    dest: Option<&Path>,
+    components: &[BackupComponent],
+    passphrase: Option<&str>,
+) -> Result<BackupReport, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let encrypted = passphrase.is_some();
+    let dest = match dest {
+        Some(path) if path.is_dir() => unique_archive_path(path, MANUAL_PREFIX, encrypted),
+        Some(path) => path.to_path_buf(),
+        None => unique_archive_path(&configured_backup_dir(&data_dir), MANUAL_PREFIX, encrypted),
+    };
+    let _guard = BACKUP_LOCK.lock();
+    create_backup_in(&data_dir, &dest, components, passphrase)
+}
+
+/// 将 `data_dir` 中选定组件打包到 `dest`
+pub fn create_backup_in(
+    data_dir: &Path,
+    dest: &Path,
+    components: &[BackupComponent],
+    passphrase: Option<&str>,
+) -> Result<BackupReport, String> {
+    let parent = dest
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let file_name = dest
+        .file_name()
+        .ok_or_else(|| "Invalid backup path".to_string())?
+        .to_string_lossy()
+        .to_string();
+    // 临时目录与目标同盘，最终 rename 保证不会留下半成品归档
+    let staging = parent.join(format!(".{}.{}.staging", file_name, uuid::Uuid::new_v4()));
+    fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create staging directory: {}", e))?;
+
+    let result = write_archive(data_dir, dest, &staging, components, passphrase);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn write_archive(
+    data_dir: &Path,
+    dest: &Path,
+    staging: &Path,
+    components: &[BackupComponent],
+    passphrase: Option<&str>,
+) -> Result<BackupReport, String> {
+    let components = normalize_components(components);
+    let archive_path = staging.join("archive.tar.gz");
+    let file =
+        File::create(&archive_path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut builder =
+        tar::Builder::new(GzEncoder::new(BufWriter::new(file), Compression::default()));
+    let created_at = chrono::Utc::now().timestamp();
+
+    let mut manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at,
+        components: Vec::new(),
+    };
+
+    for component in &components {
+        let mut files = Vec::new();
+
+        for db in component.databases() {
+            let live = data_dir.join(db);
+            if !live.is_file() {
+                continue;
+            }
+            let snapshot = staging.join(db);
+            snapshot_db(&live, &snapshot)?;
+            let (sha256, size) = hash_file(&snapshot)?;
+            builder
+                .append_path_with_name(&snapshot, format!("{}/{}", DATA_PREFIX, db))
+                .map_err(|e| format!("Failed to add {} to archive: {}", db, e))?;
+            files.push(ManifestFile {
+                path: db.to_string(),
+                sha256,
+                size,
+                sqlite: true,
+            });
+        }
+
+        for rel in component.paths() {
+            for path in collect_files(data_dir, rel)? {
+                // 读入内存后再计算校验和与写入，避免两次读取之间文件被修改
+                let bytes = fs::read(data_dir.join(&path))
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                append_bytes(
+                    &mut builder,
+                    &format!("{}/{}", DATA_PREFIX, path),
+                    &bytes,
+                    created_at,
+                )?;
+                files.push(ManifestFile {
+                    sha256: hex_digest(&bytes),
+                    size: bytes.len() as u64,
+                    path,
+                    sqlite: false,
+                });
+            }
+        }
+
+        if !files.is_empty() {
+            manifest.components.push(ManifestComponent {
+                component: *component,
+                files,
+            });
+        }
+    }
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    append_bytes(&mut builder, MANIFEST_NAME, &manifest_json, created_at)?;
+    builder
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .and_then(|mut w| w.flush())
+        .map_err(|e| format!("Failed to finalize archive: {}", e))?;
+
+    let final_path = match passphrase {
+        Some(passphrase) => {
+            let encrypted = staging.join("archive.tar.gz.enc");
+            encrypt_file(&archive_path, &encrypted, passphrase)?;
+            encrypted
+        }
+        None => archive_path,
+    };
+    fs::rename(&final_path, dest).map_err(|e| format!("Failed to write backup: {}", e))?;
+
+    let size = fs::metadata(dest).map(|m| m.len()).unwrap_or(0);
+    Ok(BackupReport {
+        path: dest.to_string_lossy().to_string(),
+        size,
+        encrypted: passphrase.is_some(),
+        created_at,
+        components: manifest.components.iter().map(|c| c.component).collect(),
+        files: manifest.components.iter().map(|c| c.files.len()).sum(),
+    })
+}
+
+/// 在线备份 API 生成一致性快照 (不阻塞其他连接的写入)
+fn snapshot_db(src: &Path, dst: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open {}: {}", src.display(), e))?;
+    conn.busy_timeout(Duration::from_secs(10))
+        .map_err(|e| e.to_string())?;
+    conn.backup(DatabaseName::Main, dst, None)
+        .map_err(|e| format!("Failed to snapshot {}: {}", src.display(), e))
+}
+
+/// 将快照写回在用数据库 (在线恢复，现有连接看到的是完整的新内容)
+fn restore_db(snapshot: &Path, live: &Path) -> Result<(), String> {
+    let mut conn =
+        Connection::open(live).map_err(|e| format!("Failed to open {}: {}", live.display(), e))?;
+    conn.busy_timeout(Duration::from_secs(10))
+        .map_err(|e| e.to_string())?;
+    conn.restore(
+        DatabaseName::Main,
+        snapshot,
+        None::<fn(rusqlite::backup::Progress)>,
+    )
+    .map_err(|e| format!("Failed to restore {}: {}", live.display(), e))
+}
+
+/// 列出 `rel` (文件或目录) 下的全部普通文件，返回相对数据目录的路径
+fn collect_files(data_dir: &Path, rel: &str) -> Result<Vec<String>, String> {
+    let root = data_dir.join(rel);
+    let Ok(meta) = fs::symlink_metadata(&root) else {
+        return Ok(Vec::new());
+    };
+    if meta.is_file() {
+        return Ok(vec![rel.to_string()]);
+    }
+    if !meta.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![(root, rel.to_string())];
+    while let Some((dir, prefix)) = pending.pop() {
+        let entries =
+            fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", prefix, e))?;
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().to_string();
+            let child = format!("{}/{}", prefix, name);
+            if file_type.is_dir() {
+                pending.push((entry.path(), child));
+            } else if file_type.is_file() {
+                files.push(child);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn append_bytes<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    bytes: &[u8],
+    mtime: i64,
+) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(mtime.max(0) as u64);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, name, bytes)
+        .map_err(|e| format!("Failed to add {} to archive: {}", name, e))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn hash_file(path: &Path) -> Result<(String, u64), String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+fn archive_cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())
+}
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+fn encrypt_file(src: &Path, dst: &Path, passphrase: &str) -> Result<(), String> {
+    let salt: [u8; SALT_LEN] = rand::random();
+    let prefix: [u8; NONCE_PREFIX_LEN] = rand::random();
+    let cipher = archive_cipher(passphrase, &salt)?;
+
+    let input = File::open(src).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut remaining = input.metadata().map_err(|e| e.to_string())?.len();
+    let mut reader = BufReader::new(input);
+    let mut writer = BufWriter::new(
+        File::create(dst).map_err(|e| format!("Failed to create encrypted archive: {}", e))?,
+    );
+    let write_err = |e: std::io::Error| format!("Failed to write encrypted archive: {}", e);
+    writer.write_all(ENC_MAGIC).map_err(write_err)?;
+    writer.write_all(&salt).map_err(write_err)?;
+    writer.write_all(&prefix).map_err(write_err)?;
+
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut counter = 0u32;
+    loop {
+        let n = remaining.min(CHUNK_SIZE as u64) as usize;
+        reader
+            .read_exact(&mut buf[..n])
+            .map_err(|e| format!("Failed to read archive: {}", e))?;
+        remaining -= n as u64;
+        let last = remaining == 0;
+        let nonce = chunk_nonce(&prefix, counter, last);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), &buf[..n])
+            .map_err(|e| format!("Encryption failed: {}", e))?;
+        writer.write_all(&ciphertext).map_err(write_err)?;
+        if last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| "Archive too large to encrypt".to_string())?;
+    }
+    writer.flush().map_err(write_err)
+}
+
+fn decrypt_file(src: &Path, dst: &Path, passphrase: &str) -> Result<(), String> {
+    let input = File::open(src).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let total = input.metadata().map_err(|e| e.to_string())?.len();
+    let mut reader = BufReader::new(input);
+
+    let mut magic = [0u8; 8];
+    let mut salt = [0u8; SALT_LEN];
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    reader
+        .read_exact(&mut magic)
+        .and_then(|_| reader.read_exact(&mut salt))
+        .and_then(|_| reader.read_exact(&mut prefix))
+        .map_err(|_| "Encrypted backup header is truncated".to_string())?;
+    if &magic != ENC_MAGIC {
+        return Err("Not an encrypted backup archive".to_string());
+    }
+    let cipher = archive_cipher(passphrase, &salt)?;
+
+    let mut remaining = total - (ENC_MAGIC.len() + SALT_LEN + NONCE_PREFIX_LEN) as u64;
+    let mut writer =
+        BufWriter::new(File::create(dst).map_err(|e| format!("Failed to create archive: {}", e))?);
+    let mut buf = vec![0u8; CHUNK_SIZE + TAG_LEN];
+    let mut counter = 0u32;
+    loop {
+        let n = remaining.min((CHUNK_SIZE + TAG_LEN) as u64) as usize;
+        if n < TAG_LEN {
+            return Err("Encrypted backup is truncated".to_string());
+        }
+        reader
+            .read_exact(&mut buf[..n])
+            .map_err(|e| format!("Failed to read backup: {}", e))?;
+        remaining -= n as u64;
+        let last = remaining == 0;
+        let nonce = chunk_nonce(&prefix, counter, last);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), &buf[..n])
+            .map_err(|_| "Wrong passphrase or corrupted backup".to_string())?;
+        writer
+            .write_all(&plaintext)
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        if last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| "Encrypted backup is corrupted".to_string())?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write archive: {}", e))
+}
+
+fn is_encrypted_archive(path: &Path) -> Result<bool, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut magic = [0u8; 8];
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == ENC_MAGIC),
+        Err(_) => Ok(false),
+    }
+}
+
+/// 清单路径必须为普通相对路径，且属于声明的组件，防止恶意归档写出数据目录
+fn validate_entry(component: BackupComponent, file: &ManifestFile) -> Result<(), String> {
+    let path = Path::new(&file.path);
+    let is_plain =
+        !file.path.is_empty() && path.components().all(|c| matches!(c, Component::Normal(_)));
+    let allowed = if file.sqlite {
+        component.databases().contains(&file.path.as_str())
+    } else {
+        component
+            .paths()
+            .iter()
+            .any(|p| file.path == *p || file.path.starts_with(&format!("{}/", p)))
+    };
+    if is_plain && allowed {
+        Ok(())
+    } else {
+        Err(format!(
+            "Backup manifest lists unexpected path '{}' for component {}",
+            file.path,
+            component.as_str()
+        ))
+    }
+}
+
+/// 原子替换文件 (先写临时文件再 rename)
+fn replace_file(src: &Path, dest: &Path) -> Result<(), String> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let tmp = dest.with_file_name(format!(
+        ".{}.restore-tmp",
+        dest.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    fs::copy(src, &tmp).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    fs::rename(&tmp, dest).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("Failed to replace {}: {}", dest.display(), e)
+    })
+}
+
+/// 恢复备份: 先校验全部校验和，再为受影响组件生成恢复前备份，最后写回数据目录
+pub fn restore_backup(
+    archive: &Path,
+    components: &[BackupComponent],
+    passphrase: Option<&str>,
+) -> Result<RestoreReport, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let pre_restore_dir = configured_backup_dir(&data_dir);
+    let _guard = BACKUP_LOCK.lock();
+    restore_backup_in(
+        &data_dir,
+        archive,
+        components,
+        passphrase,
+        Some(&pre_restore_dir),
+    )
+}
+
+pub fn restore_backup_in(
+    data_dir: &Path,
+    archive: &Path,
+    components: &[BackupComponent],
+    passphrase: Option<&str>,
+    pre_restore_dir: Option<&Path>,
+) -> Result<RestoreReport, String> {
+    let staging = data_dir.join(format!(".restore-staging-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create staging directory: {}", e))?;
+    let result = restore_from_staging(
+        data_dir,
+        archive,
+        &staging,
+        components,
+        passphrase,
+        pre_restore_dir,
+    );
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn restore_from_staging(
+    data_dir: &Path,
+    archive: &Path,
+    staging: &Path,
+    components: &[BackupComponent],
+    passphrase: Option<&str>,
+    pre_restore_dir: Option<&Path>,
+) -> Result<RestoreReport, String> {
+    let tar_gz = if is_encrypted_archive(archive)? {
+        let passphrase = passphrase.ok_or_else(|| {
+            format!(
+                "Backup is encrypted; a passphrase is required (or set {})",
+                BACKUP_PASSPHRASE_ENV
+            )
+        })?;
+        let decrypted = staging.join("archive.tar.gz");
+        decrypt_file(archive, &decrypted, passphrase)?;
+        decrypted
+    } else {
+        archive.to_path_buf()
+    };
+
+    // tar 解包会拒绝绝对路径与 `..`
+    let unpacked = staging.join("unpacked");
+    let file = File::open(&tar_gz).map_err(|e| format!("Failed to open backup: {}", e))?;
+    tar::Archive::new(GzDecoder::new(BufReader::new(file)))
+        .unpack(&unpacked)
+        .map_err(|e| format!("Failed to read backup archive: {}", e))?;
+
+    let manifest: BackupManifest = fs::read(unpacked.join(MANIFEST_NAME))
+        .map_err(|_| "Backup archive has no manifest".to_string())
+        .and_then(|raw| {
+            serde_json::from_slice(&raw).map_err(|e| format!("Invalid backup manifest: {}", e))
+        })?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "Backup format version {} is newer than supported ({})",
+            manifest.format_version, FORMAT_VERSION
+        ));
+    }
+
+    for requested in components {
+        if !manifest
+            .components
+            .iter()
+            .any(|c| c.component == *requested)
+        {
+            return Err(format!(
+                "Component {} is not present in this backup",
+                requested.as_str()
+            ));
+        }
+    }
+    let selected: Vec<&ManifestComponent> = manifest
+        .components
+        .iter()
+        .filter(|c| components.is_empty() || components.contains(&c.component))
+        .collect();
+
+    // 写入任何数据前校验全部选定文件
+    let data_root = unpacked.join(DATA_PREFIX);
+    for component in &selected {
+        for file in &component.files {
+            validate_entry(component.component, file)?;
+            let (sha256, size) = hash_file(&data_root.join(&file.path))
+                .map_err(|_| format!("Backup is missing {}", file.path))?;
+            if sha256 != file.sha256 || size != file.size {
+                return Err(format!("Checksum mismatch for {}", file.path));
+            }
+        }
+    }
+
+    let selected_components: Vec<BackupComponent> = selected.iter().map(|c| c.component).collect();
+    let pre_restore_backup = match pre_restore_dir {
+        Some(dir) => {
+            let dest = unique_archive_path(dir, PRE_RESTORE_PREFIX, false);
+            let report = create_backup_in(data_dir, &dest, &selected_components, None)?;
+            Some(report.path)
+        }
+        None => None,
+    };
+
+    let mut files_restored = 0;
+    for component in &selected {
+        for file in &component.files {
+            let staged = data_root.join(&file.path);
+            let live = data_dir.join(&file.path);
+            if file.sqlite {
+                restore_db(&staged, &live)?;
+            } else {
+                replace_file(&staged, &live)?;
+            }
+            files_restored += 1;
+        }
+        logger::log_info(&format!(
+            "[Backup] Restored component {} ({} file(s))",
+            component.component.as_str(),
+            component.files.len()
+        ));
+    }
+
+    Ok(RestoreReport {
+        archive_created_at: manifest.created_at,
+        restart_recommended: selected_components.iter().any(|c| c.needs_restart()),
+        restored: selected_components,
+        files_restored,
+        pre_restore_backup,
+    })
+}
+
+/// 列出备份目录中的归档 (`dir` 为空时使用配置的备份目录)，按时间倒序
+pub fn list_backups(dir: Option<&Path>) -> Result<Vec<BackupInfo>, String> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => configured_backup_dir(&crate::modules::account::get_data_dir()?),
+    };
+    list_backups_in(&dir)
+}
+
+pub fn list_backups_in(dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut backups: Vec<BackupInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let known_prefix = [MANUAL_PREFIX, SCHEDULED_PREFIX, PRE_RESTORE_PREFIX]
+                .iter()
+                .any(|p| file_name.starts_with(p));
+            let encrypted = file_name.ends_with(ENCRYPTED_EXT);
+            if !known_prefix || !(encrypted || file_name.ends_with(ARCHIVE_EXT)) {
+                return None;
+            }
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            Some(BackupInfo {
+                path: entry.path().to_string_lossy().to_string(),
+                scheduled: file_name.starts_with(SCHEDULED_PREFIX),
+                file_name,
+                size: meta.len(),
+                modified,
+                encrypted,
+            })
+        })
+        .collect();
+    // 同一秒内生成的归档按文件名 (含序号) 排序
+    backups.sort_by(|a, b| {
+        b.modified
+            .cmp(&a.modified)
+            .then_with(|| b.file_name.cmp(&a.file_name))
+    });
+    Ok(backups)
+}
+
+/// 只保留最新的 `keep` 个定时备份，返回删除的文件
+pub fn prune_scheduled_backups(dir: &Path, keep: usize) -> Result<Vec<String>, String> {
+    let mut removed = Vec::new();
+    for info in list_backups_in(dir)?
+        .into_iter()
+        .filter(|b| b.scheduled)
+        .skip(keep)
+    {
+        fs::remove_file(&info.path)
+            .map_err(|e| format!("Failed to remove old backup {}: {}", info.file_name, e))?;
+        removed.push(info.path);
+    }
+    Ok(removed)
+}
+
+/// 到期时执行一次定时备份并轮换旧备份，未到期返回 None
+pub fn run_scheduled_backup_in(
+    data_dir: &Path,
+    backup_config: &BackupConfig,
+    passphrase: Option<&str>,
+    now: i64,
+) -> Result<Option<BackupReport>, String> {
+    let dir = resolve_backup_dir(data_dir, backup_config.directory.as_deref());
+    let last = list_backups_in(&dir)?
+        .into_iter()
+        .find(|b| b.scheduled)
+        .map(|b| b.modified);
+    let interval_secs = i64::from(backup_config.interval_hours.max(1)) * 3600;
+    if last.is_some_and(|ts| now - ts < interval_secs) {
+        return Ok(None);
+    }
+
+    if backup_config.encrypt && passphrase.is_none() {
+        return Err(format!(
+            "Scheduled backup encryption is enabled but {} is not set",
+            BACKUP_PASSPHRASE_ENV
+        ));
+    }
+    let passphrase = passphrase.filter(|_| backup_config.encrypt);
+    let dest = unique_archive_path(&dir, SCHEDULED_PREFIX, passphrase.is_some());
+    let report = create_backup_in(data_dir, &dest, &backup_config.components, passphrase)?;
+    prune_scheduled_backups(&dir, backup_config.keep.max(1) as usize)?;
+    Ok(Some(report))
+}
+
+/// 启动定时备份循环 (每次检查时重新读取配置)
+pub fn start_backup_scheduler() {
+    tauri::async_runtime::spawn(async move {
+        logger::log_info("[Backup] Scheduled backup loop started");
+        let mut interval = tokio::time::interval(Duration::from_secs(TICK_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let Ok(app_config) = config::load_app_config() else {
+                continue;
+            };
+            if !app_config.backup.enabled {
+                continue;
+            }
+
+            let result = tokio::task::spawn_blocking(move || {
+                let data_dir = crate::modules::account::get_data_dir()?;
+                let passphrase = std::env::var(BACKUP_PASSPHRASE_ENV)
+                    .ok()
+                    .filter(|p| !p.is_empty());
+                let _guard = BACKUP_LOCK.lock();
+                run_scheduled_backup_in(
+                    &data_dir,
+                    &app_config.backup,
+                    passphrase.as_deref(),
+                    chrono::Utc::now().timestamp(),
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+
+            match result {
+                Ok(Some(report)) => logger::log_info(&format!(
+                    "[Backup] Scheduled backup written to {} ({} file(s), {} bytes)",
+                    report.path, report.files, report.size
+                )),
+                Ok(None) => {}
+                Err(e) => logger::log_warn(&format!("[Backup] Scheduled backup failed: {}", e)),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "abv-backup-test-{}-{}",
+            label,
+            uuid::Uuid::new_v4()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn seed_data_dir(dir: &Path, marker: &str) {
+        let conn = Connection::open(dir.join("security.db")).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode=WAL; CREATE TABLE IF NOT EXISTS kv (k TEXT PRIMARY KEY, v TEXT);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO kv (k, v) VALUES ('marker', ?1)",
+            [marker],
+        )
+        .unwrap();
+        fs::write(
+            dir.join("gui_config.json"),
+            format!("{{\"marker\":\"{}\"}}", marker),
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("mitm")).unwrap();
+        fs::write(dir.join("mitm").join("rootCA.pem"), marker).unwrap();
+    }
+
+    fn read_marker(dir: &Path) -> String {
+        let conn = Connection::open(dir.join("security.db")).unwrap();
+        conn.query_row("SELECT v FROM kv WHERE k = 'marker'", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_backup_restore_roundtrip() {
+        let data_dir = temp_dir("roundtrip");
+        let backups = temp_dir("roundtrip-out");
+        seed_data_dir(&data_dir, "before");
+
+        let dest = backups.join("full.tar.gz");
+        let report = create_backup_in(&data_dir, &dest, &[], None).unwrap();
+        assert!(!report.encrypted);
+        assert_eq!(
+            report.components,
+            vec![
+                BackupComponent::Security,
+                BackupComponent::Config,
+                BackupComponent::MitmCa
+            ]
+        );
+        assert_eq!(report.files, 3);
+
+        seed_data_dir(&data_dir, "after");
+        let restored = restore_backup_in(&data_dir, &dest, &[], None, Some(&backups)).unwrap();
+        assert_eq!(restored.files_restored, 3);
+        assert!(restored.restart_recommended);
+        assert_eq!(read_marker(&data_dir), "before");
+        assert_eq!(
+            fs::read_to_string(data_dir.join("mitm").join("rootCA.pem")).unwrap(),
+            "before"
+        );
+
+        // 恢复前备份保存了被覆盖的数据
+        let pre = PathBuf::from(restored.pre_restore_backup.unwrap());
+        assert!(pre
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(PRE_RESTORE_PREFIX));
+        restore_backup_in(&data_dir, &pre, &[], None, None).unwrap();
+        assert_eq!(read_marker(&data_dir), "after");
+
+        let _ = fs::remove_dir_all(&data_dir);
+        let _ = fs::remove_dir_all(&backups);
+    }
+
+    #[test]
+    fn test_selective_restore() {
+        let data_dir = temp_dir("selective");
+        seed_data_dir(&data_dir, "before");
+        let dest = data_dir.join("backups").join("sel.tar.gz");
+        create_backup_in(&data_dir, &dest, &[], None).unwrap();
+
+        seed_data_dir(&data_dir, "after");
+        let report =
+            restore_backup_in(&data_dir, &dest, &[BackupComponent::Config], None, None).unwrap();
+        assert_eq!(report.restored, vec![BackupComponent::Config]);
+        assert!(fs::read_to_string(data_dir.join("gui_config.json"))
+            .unwrap()
+            .contains("before"));
+        assert_eq!(read_marker(&data_dir), "after");
+
+        let missing = restore_backup_in(&data_dir, &dest, &[BackupComponent::Accounts], None, None);
+        assert!(missing.unwrap_err().contains("not present"));
+
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn test_encrypted_backup_requires_passphrase() {
+        let data_dir = temp_dir("encrypted");
+        seed_data_dir(&data_dir, "secret");
+        let dest = data_dir.join("enc.tar.gz.enc");
+        let report = create_backup_in(&data_dir, &dest, &[], Some("hunter2")).unwrap();
+        assert!(report.encrypted);
+        assert!(is_encrypted_archive(&dest).unwrap());
+
+        assert!(restore_backup_in(&data_dir, &dest, &[], None, None)
+            .unwrap_err()
+            .contains("passphrase"));
+        assert!(
+            restore_backup_in(&data_dir, &dest, &[], Some("wrong"), None)
+                .unwrap_err()
+                .contains("Wrong passphrase")
+        );
+
+        seed_data_dir(&data_dir, "changed");
+        restore_backup_in(&data_dir, &dest, &[], Some("hunter2"), None).unwrap();
+        assert_eq!(read_marker(&data_dir), "secret");
+
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip_multi_chunk_and_truncation() {
+        let dir = temp_dir("chunks");
+        let plain = dir.join("plain.bin");
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2).map(|i| (i % 251) as u8).collect();
+        fs::write(&plain, &data).unwrap();
+
+        let enc = dir.join("plain.enc");
+        encrypt_file(&plain, &enc, "pw").unwrap();
+        let out = dir.join("plain.out");
+        decrypt_file(&enc, &out, "pw").unwrap();
+        assert_eq!(fs::read(&out).unwrap(), data);
+
+        // 在分块边界截断也必须被识别
+        let raw = fs::read(&enc).unwrap();
+        let header = ENC_MAGIC.len() + SALT_LEN + NONCE_PREFIX_LEN;
+        fs::write(&enc, &raw[..header + CHUNK_SIZE + TAG_LEN]).unwrap();
+        assert!(decrypt_file(&enc, &out, "pw").is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_aborts_before_writing() {
+        let data_dir = temp_dir("tamper");
+        seed_data_dir(&data_dir, "original");
+        let dest = data_dir.join("tampered.tar.gz");
+
+        // 构造一个清单校验和与内容不符的归档
+        let manifest = BackupManifest {
+            format_version: FORMAT_VERSION,
+            app_version: "test".to_string(),
+            created_at: 0,
+            components: vec![ManifestComponent {
+                component: BackupComponent::Config,
+                files: vec![ManifestFile {
+                    path: "gui_config.json".to_string(),
+                    sha256: hex_digest(b"expected"),
+                    size: 8,
+                    sqlite: false,
+                }],
+            }],
+        };
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&dest).unwrap(),
+            Compression::default(),
+        ));
+        append_bytes(&mut builder, "data/gui_config.json", b"tampered", 0).unwrap();
+        let manifest_json = serde_json::to_vec(&manifest).unwrap();
+        append_bytes(&mut builder, MANIFEST_NAME, &manifest_json, 0).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let err = restore_backup_in(&data_dir, &dest, &[], None, None).unwrap_err();
+        assert!(err.contains("Checksum mismatch"));
+        assert!(fs::read_to_string(data_dir.join("gui_config.json"))
+            .unwrap()
+            .contains("original"));
+
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn test_validate_entry_rejects_foreign_paths() {
+        let file = |path: &str, sqlite: bool| ManifestFile {
+            path: path.to_string(),
+            sha256: String::new(),
+            size: 0,
+            sqlite,
+        };
+        assert!(validate_entry(BackupComponent::MitmCa, &file("mitm/rootCA.key", false)).is_ok());
+        assert!(
+            validate_entry(BackupComponent::MitmCa, &file("mitm/../vault.json", false)).is_err()
+        );
+        assert!(validate_entry(BackupComponent::Config, &file("vault.json", false)).is_err());
+        assert!(validate_entry(BackupComponent::Accounts, &file("accounts.db", true)).is_ok());
+        assert!(validate_entry(BackupComponent::Accounts, &file("accounts.db", false)).is_err());
+    }
+
+    #[test]
+    fn test_scheduled_backup_rotation() {
+        let data_dir = temp_dir("rotation");
+        seed_data_dir(&data_dir, "rotate");
+        let backup_config = BackupConfig {
+            enabled: true,
+            interval_hours: 1,
+            keep: 2,
+            directory: Some("rotated".to_string()),
+            components: vec![BackupComponent::Config],
+            encrypt: false,
+        };
+        let dir = data_dir.join("rotated");
+
+        for _ in 0..3 {
+            let report = run_scheduled_backup_in(&data_dir, &backup_config, None, i64::MAX)
+                .unwrap()
+                .unwrap();
+            assert_eq!(report.components, vec![BackupComponent::Config]);
+        }
+        let remaining = list_backups_in(&dir).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().all(|b| b.scheduled));
+
+        // 未到期不生成新备份
+        let now = chrono::Utc::now().timestamp();
+        assert!(
+            run_scheduled_backup_in(&data_dir, &backup_config, None, now)
+                .unwrap()
+                .is_none()
+        );
+
+        // 开启加密但未提供口令时报错
+        let encrypted = BackupConfig {
+            encrypt: true,
+            ..backup_config
+        };
+        assert!(run_scheduled_backup_in(&data_dir, &encrypted, None, i64::MAX).is_err());
+
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+}
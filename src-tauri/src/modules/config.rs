@@ -88,9 +88,14 @@ pub fn load_app_config() -> Result<AppConfig, String> {
         }
     }
 
-    let config: AppConfig = serde_json::from_value(v)
+    let mut config: AppConfig = serde_json::from_value(v)
         .map_err(|e| format!("failed_to_convert_config_after_migration: {}", e))?;
 
+    // [NEW] 旧版明文管理密码迁移为 Argon2 哈希
+    if hash_plain_admin_password(&mut config) {
+        modified = true;
+    }
+
     // If migration occurred, auto-save once to clean up the file
     if modified {
         let _ = save_app_config(&config);
@@ -104,13 +109,37 @@ pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
     let data_dir = get_data_dir()?;
     let config_path = data_dir.join(CONFIG_FILE);
 
-    let content = serde_json::to_string_pretty(config)
+    // 管理密码永不以明文落盘
+    let mut config = config.clone();
+    hash_plain_admin_password(&mut config);
+
+    let content = serde_json::to_string_pretty(&config)
         .map_err(|e| format!("failed_to_serialize_config: {}", e))?;
-    let content = crate::utils::crypto::encrypt_file_content(&content);
+    let content = crate::utils::crypto::encrypt_file_content(&content)?;
 
     fs::write(&config_path, content).map_err(|e| format!("failed_to_save_config: {}", e))
 }
 
+/// 将明文管理密码替换为 Argon2 哈希，返回是否发生了替换
+fn hash_plain_admin_password(config: &mut AppConfig) -> bool {
+    let Some(pwd) = config.proxy.admin_password.as_deref() else {
+        return false;
+    };
+    if pwd.is_empty() || crate::utils::crypto::is_password_hash(pwd) {
+        return false;
+    }
+    match crate::utils::crypto::hash_password(pwd) {
+        Ok(hash) => {
+            config.proxy.admin_password = Some(hash);
+            true
+        }
+        Err(e) => {
+            warn!("Failed to hash admin password: {}", e);
+            false
+        }
+    }
+}
+
 /// Return the passphrase used by VNPAY SSO server to AES-256-GCM encrypt the callback body.
 /// Must match the passphrase used in the Go server's deriveKey() function.
 pub fn get_vnpay_sso_passphrase() -> String {
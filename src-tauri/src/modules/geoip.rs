@@ -0,0 +1,266 @@
+//! GeoIP Module
+//! 基于本地 MaxMind 格式 (.mmdb) 数据库解析 IP 的国家与 ASN，并评估国家 / ASN 访问规则
+
+use maxminddb::{geoip2, Reader};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::modules::ip_matcher::parse_ip;
+use crate::proxy::config::GeoIpConfig;
+
+/// 数据库文件变更检查间隔 (替换 .mmdb 文件后自动重新加载)
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// IP 的地理 / 网络归属信息
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeoInfo {
+    /// 国家代码 (ISO 3166-1 alpha-2)
+    pub country: Option<String>,
+    pub asn: Option<u32>,
+    pub asn_org: Option<String>,
+}
+
+struct LoadedDb {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    reader: Reader<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct GeoIpState {
+    country: Option<LoadedDb>,
+    asn: Option<LoadedDb>,
+    configured: (Option<PathBuf>, Option<PathBuf>),
+    last_checked: Option<Instant>,
+}
+
+static STATE: Lazy<RwLock<GeoIpState>> = Lazy::new(|| RwLock::new(GeoIpState::default()));
+
+/// 相对路径按数据目录解析
+fn resolve_path(path: Option<&String>) -> Option<PathBuf> {
+    let path = path.map(|p| p.trim()).filter(|p| !p.is_empty())?;
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        return Some(path);
+    }
+    crate::modules::account::get_data_dir()
+        .map(|dir| dir.join(&path))
+        .ok()
+        .or(Some(path))
+}
+
+fn file_modified(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 按需 (重新) 加载数据库
+fn sync_db(slot: &mut Option<LoadedDb>, path: Option<&PathBuf>) {
+    let Some(path) = path else {
+        *slot = None;
+        return;
+    };
+    let modified = file_modified(path);
+    if let Some(loaded) = slot.as_ref() {
+        if &loaded.path == path && loaded.modified == modified {
+            return;
+        }
+    }
+    match Reader::open_readfile(path) {
+        Ok(reader) => {
+            tracing::info!(
+                "[GeoIP] Loaded {} ({})",
+                path.display(),
+                reader.metadata.database_type
+            );
+            *slot = Some(LoadedDb {
+                path: path.clone(),
+                modified,
+                reader,
+            });
+        }
+        Err(e) => {
+            tracing::warn!("[GeoIP] Failed to load {}: {}", path.display(), e);
+            *slot = None;
+        }
+    }
+}
+
+/// 根据配置加载 / 卸载数据库，配置未变更时按间隔检查文件是否被替换
+pub fn ensure_loaded(config: &GeoIpConfig) {
+    let configured = if config.enabled {
+        (
+            resolve_path(config.database_path.as_ref()),
+            resolve_path(config.asn_database_path.as_ref()),
+        )
+    } else {
+        (None, None)
+    };
+
+    {
+        let state = STATE.read();
+        let fresh = state
+            .last_checked
+            .is_some_and(|t| t.elapsed() < RELOAD_CHECK_INTERVAL);
+        if state.configured == configured && (fresh || configured == (None, None)) {
+            return;
+        }
+    }
+
+    let mut state = STATE.write();
+    sync_db(&mut state.country, configured.0.as_ref());
+    sync_db(&mut state.asn, configured.1.as_ref());
+    state.configured = configured;
+    state.last_checked = Some(Instant::now());
+}
+
+/// 是否已加载任一数据库
+pub fn is_loaded() -> bool {
+    let state = STATE.read();
+    state.country.is_some() || state.asn.is_some()
+}
+
+/// 查询 IP 的国家与 ASN (数据库未加载或无记录时返回 None)
+pub fn lookup(ip: &str) -> Option<GeoInfo> {
+    let addr = parse_ip(ip)?;
+    let state = STATE.read();
+
+    let country = state.country.as_ref().and_then(|db| {
+        let record = db.reader.lookup::<geoip2::Country>(addr).ok()?;
+        record
+            .country
+            .and_then(|c| c.iso_code)
+            .or_else(|| record.registered_country.and_then(|c| c.iso_code))
+            .map(|code| code.to_ascii_uppercase())
+    });
+
+    // 未单独配置 ASN 数据库时尝试从国家数据库读取 (部分合并格式的数据库同时包含两者)
+    let (asn, asn_org) = state
+        .asn
+        .as_ref()
+        .or(state.country.as_ref())
+        .and_then(|db| db.reader.lookup::<geoip2::Asn>(addr).ok())
+        .map(|record| {
+            (
+                record.autonomous_system_number,
+                record.autonomous_system_organization.map(str::to_string),
+            )
+        })
+        .unwrap_or_default();
+
+    let info = GeoInfo {
+        country,
+        asn,
+        asn_org,
+    };
+    (info != GeoInfo::default()).then_some(info)
+}
+
+/// 评估国家 / ASN 规则，返回拒绝原因 (None 表示放行)
+/// 拒绝列表优先；允许列表非空时，未命中 (或无法解析且不允许未知) 的地址被拒绝
+pub fn evaluate(info: Option<&GeoInfo>, config: &GeoIpConfig) -> Option<String> {
+    let country = info.and_then(|i| i.country.as_deref());
+    let asn = info.and_then(|i| i.asn);
+
+    if let Some(country) = country {
+        if config
+            .denied_countries
+            .iter()
+            .any(|c| c.trim().eq_ignore_ascii_case(country))
+        {
+            return Some(format!("Country {} is denied", country));
+        }
+    }
+    if let Some(asn) = asn {
+        if config.denied_asns.contains(&asn) {
+            return Some(format!("AS{} is denied", asn));
+        }
+    }
+
+    if !config.allowed_countries.is_empty() {
+        match country {
+            Some(country)
+                if config
+                    .allowed_countries
+                    .iter()
+                    .any(|c| c.trim().eq_ignore_ascii_case(country)) => {}
+            Some(country) => return Some(format!("Country {} is not allowed", country)),
+            None if !config.allow_unknown => return Some("Unknown country".to_string()),
+            None => {}
+        }
+    }
+
+    if !config.allowed_asns.is_empty() {
+        match asn {
+            Some(asn) if config.allowed_asns.contains(&asn) => {}
+            Some(asn) => return Some(format!("AS{} is not allowed", asn)),
+            None if !config.allow_unknown => return Some("Unknown ASN".to_string()),
+            None => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(country: Option<&str>, asn: Option<u32>) -> GeoInfo {
+        GeoInfo {
+            country: country.map(str::to_string),
+            asn,
+            asn_org: None,
+        }
+    }
+
+    #[test]
+    fn test_deny_rules() {
+        let config = GeoIpConfig {
+            enabled: true,
+            denied_countries: vec!["kp".to_string()],
+            denied_asns: vec![14061],
+            ..GeoIpConfig::default()
+        };
+        assert!(evaluate(Some(&info(Some("KP"), None)), &config).is_some());
+        assert!(evaluate(Some(&info(Some("US"), Some(14061))), &config).is_some());
+        assert!(evaluate(Some(&info(Some("US"), Some(7922))), &config).is_none());
+        assert!(evaluate(None, &config).is_none());
+    }
+
+    #[test]
+    fn test_allow_rules_and_unknown() {
+        let mut config = GeoIpConfig {
+            enabled: true,
+            allowed_countries: vec!["CN".to_string(), "HK".to_string()],
+            ..GeoIpConfig::default()
+        };
+        assert!(evaluate(Some(&info(Some("HK"), None)), &config).is_none());
+        assert_eq!(
+            evaluate(Some(&info(Some("DE"), None)), &config).as_deref(),
+            Some("Country DE is not allowed")
+        );
+        // 内网 / 回环地址无法解析，默认放行
+        assert!(evaluate(None, &config).is_none());
+        config.allow_unknown = false;
+        assert!(evaluate(None, &config).is_some());
+
+        let config = GeoIpConfig {
+            enabled: true,
+            allowed_asns: vec![4134],
+            ..GeoIpConfig::default()
+        };
+        assert!(evaluate(Some(&info(None, Some(4134))), &config).is_none());
+        assert!(evaluate(Some(&info(None, Some(16509))), &config).is_some());
+    }
+
+    #[test]
+    fn test_lookup_without_database() {
+        ensure_loaded(&GeoIpConfig::default());
+        assert!(!is_loaded());
+        assert!(lookup("8.8.8.8").is_none());
+        assert!(lookup("not-an-ip").is_none());
+    }
+}
@@ -0,0 +1,314 @@
+//! IP Matcher Module
+//! IPv4 / IPv6 地址与 CIDR 解析、匹配，以及黑白名单使用的前缀树
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// 解析 IP 地址，IPv4 映射的 IPv6 地址 (::ffff:a.b.c.d) 统一为 IPv4
+pub fn parse_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    // 去掉 IPv6 zone id (fe80::1%eth0)
+    let value = value.split('%').next().unwrap_or(value);
+    value.parse::<IpAddr>().ok().map(normalize_ip)
+}
+
+/// IPv4 映射的 IPv6 地址转换为 IPv4
+pub fn normalize_ip(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+/// 解析 IP 或 CIDR 规则，返回 (网络地址, 前缀长度)
+/// 单个 IP 视为 /32 或 /128；IPv4 映射的 IPv6 CIDR (::ffff:0:0/96 及更长) 转换为 IPv4 CIDR
+pub fn parse_pattern(pattern: &str) -> Option<(IpAddr, u8)> {
+    let pattern = pattern.trim();
+    let (addr_part, prefix_part) = match pattern.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (pattern, None),
+    };
+
+    let raw_addr = addr_part
+        .trim()
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(addr_part.trim())
+        .parse::<IpAddr>()
+        .ok()?;
+    let max_prefix = if raw_addr.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix_part {
+        Some(p) => p.trim().parse::<u8>().ok().filter(|p| *p <= max_prefix)?,
+        None => max_prefix,
+    };
+
+    match (raw_addr, normalize_ip(raw_addr)) {
+        (IpAddr::V6(_), IpAddr::V4(v4)) => {
+            // ::ffff:0:0/96 以下的前缀同时覆盖非映射地址，保持 IPv6 语义
+            if prefix >= 96 {
+                Some((IpAddr::V4(v4), prefix - 96))
+            } else {
+                Some((raw_addr, prefix))
+            }
+        }
+        (_, addr) => Some((addr, prefix)),
+    }
+}
+
+fn addr_bits(addr: IpAddr) -> (u128, u8) {
+    match addr {
+        IpAddr::V4(v4) => (u32::from(v4) as u128, 32),
+        IpAddr::V6(v6) => (u128::from(v6), 128),
+    }
+}
+
+fn bit_at(bits: u128, width: u8, index: u8) -> usize {
+    ((bits >> (width - 1 - index)) & 1) as usize
+}
+
+/// IP 是否命中规则 (单个 IP 或 CIDR，支持 IPv4 / IPv6 / IPv4 映射 IPv6)
+pub fn cidr_match(ip: &str, pattern: &str) -> bool {
+    parse_ip(ip).is_some_and(|addr| pattern_contains(pattern, addr))
+}
+
+/// 规则是否包含指定地址
+pub fn pattern_contains(pattern: &str, addr: IpAddr) -> bool {
+    let Some((network, prefix)) = parse_pattern(pattern) else {
+        return false;
+    };
+    let addr = normalize_ip(addr);
+    if addr.is_ipv4() != network.is_ipv4() {
+        return false;
+    }
+    let (ip_bits, width) = addr_bits(addr);
+    let (net_bits, _) = addr_bits(network);
+    if prefix == 0 {
+        return true;
+    }
+    let shift = width - prefix;
+    (ip_bits >> shift) == (net_bits >> shift)
+}
+
+#[derive(Debug, Clone)]
+struct TrieNode<T> {
+    children: [Option<usize>; 2],
+    value: Option<T>,
+}
+
+impl<T> TrieNode<T> {
+    fn new() -> Self {
+        Self {
+            children: [None, None],
+            value: None,
+        }
+    }
+}
+
+/// 二进制前缀树 (IPv4 / IPv6 各一棵)，查询为最长前缀匹配
+#[derive(Debug, Clone)]
+pub struct IpPrefixTrie<T> {
+    nodes: Vec<TrieNode<T>>,
+    len: usize,
+}
+
+const V4_ROOT: usize = 0;
+const V6_ROOT: usize = 1;
+
+impl<T> Default for IpPrefixTrie<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IpPrefixTrie<T> {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![TrieNode::new(), TrieNode::new()],
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 插入规则，无法解析时返回 false；同一前缀已存在时保留先插入的值
+    pub fn insert(&mut self, pattern: &str, value: T) -> bool {
+        let Some((network, prefix)) = parse_pattern(pattern) else {
+            return false;
+        };
+        let (bits, width) = addr_bits(network);
+        let mut node = if network.is_ipv4() { V4_ROOT } else { V6_ROOT };
+        for i in 0..prefix {
+            let bit = bit_at(bits, width, i);
+            node = match self.nodes[node].children[bit] {
+                Some(next) => next,
+                None => {
+                    self.nodes.push(TrieNode::new());
+                    let next = self.nodes.len() - 1;
+                    self.nodes[node].children[bit] = Some(next);
+                    next
+                }
+            };
+        }
+        if self.nodes[node].value.is_none() {
+            self.nodes[node].value = Some(value);
+            self.len += 1;
+        }
+        true
+    }
+
+    /// 最长前缀匹配
+    pub fn longest_match(&self, addr: IpAddr) -> Option<&T> {
+        let addr = normalize_ip(addr);
+        let (bits, width) = addr_bits(addr);
+        let mut node = if addr.is_ipv4() { V4_ROOT } else { V6_ROOT };
+        let mut best = self.nodes[node].value.as_ref();
+        for i in 0..width {
+            match self.nodes[node].children[bit_at(bits, width, i)] {
+                Some(next) => {
+                    node = next;
+                    if let Some(value) = self.nodes[node].value.as_ref() {
+                        best = Some(value);
+                    }
+                }
+                None => break,
+            }
+        }
+        best
+    }
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        self.longest_match(addr).is_some()
+    }
+}
+
+/// 黑白名单规则索引: 可解析的 IP / CIDR 进入前缀树，其余规则按原始字符串精确匹配
+#[derive(Debug, Clone)]
+pub struct IpRuleIndex<T> {
+    trie: IpPrefixTrie<T>,
+    literals: HashMap<String, T>,
+}
+
+impl<T> Default for IpRuleIndex<T> {
+    fn default() -> Self {
+        Self {
+            trie: IpPrefixTrie::new(),
+            literals: HashMap::new(),
+        }
+    }
+}
+
+impl<T> IpRuleIndex<T> {
+    pub fn insert(&mut self, pattern: &str, value: T) {
+        let pattern = pattern.trim();
+        if parse_pattern(pattern).is_some() {
+            self.trie.insert(pattern, value);
+        } else {
+            self.literals.entry(pattern.to_string()).or_insert(value);
+        }
+    }
+
+    /// 查询 IP 命中的规则 (最长前缀优先)
+    pub fn lookup(&self, ip: &str) -> Option<&T> {
+        match parse_ip(ip) {
+            Some(addr) => self.trie.longest_match(addr),
+            None => self.literals.get(ip.trim()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.trie.len() + self.literals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cidr_match_ipv4_and_ipv6() {
+        assert!(cidr_match("192.168.1.77", "192.168.1.0/24"));
+        assert!(!cidr_match("192.168.2.1", "192.168.1.0/24"));
+        assert!(cidr_match("8.8.8.8", "8.8.8.8"));
+        assert!(cidr_match("1.2.3.4", "0.0.0.0/0"));
+
+        assert!(cidr_match("2001:db8::1", "2001:db8::/32"));
+        assert!(cidr_match("2001:DB8:0:0:ffff::1", "2001:db8::/48"));
+        assert!(!cidr_match("2001:db9::1", "2001:db8::/32"));
+        assert!(cidr_match("::1", "::1/128"));
+
+        // 不同协议族不匹配
+        assert!(!cidr_match("::1", "0.0.0.0/0"));
+        assert!(!cidr_match("10.0.0.1", "::/0"));
+
+        // 非法规则
+        assert!(!cidr_match("10.0.0.1", "10.0.0.0/33"));
+        assert!(!cidr_match("not-an-ip", "10.0.0.0/8"));
+    }
+
+    #[test]
+    fn test_ipv4_mapped_ipv6() {
+        assert!(cidr_match("::ffff:10.1.2.3", "10.0.0.0/8"));
+        assert!(cidr_match("10.1.2.3", "::ffff:10.0.0.0/104"));
+        assert!(cidr_match("[::ffff:192.168.0.9]", "192.168.0.9"));
+        assert_eq!(
+            parse_pattern("::ffff:10.0.0.0/104"),
+            Some(("10.0.0.0".parse().unwrap(), 8))
+        );
+    }
+
+    #[test]
+    fn test_trie_longest_prefix_match() {
+        let mut trie = IpPrefixTrie::new();
+        assert!(trie.insert("10.0.0.0/8", "wide"));
+        assert!(trie.insert("10.1.0.0/16", "narrow"));
+        assert!(trie.insert("2001:db8::/32", "v6"));
+        assert!(trie.insert("::ffff:172.16.0.1", "mapped"));
+        assert!(!trie.insert("garbage", "x"));
+        assert_eq!(trie.len(), 4);
+
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(trie.longest_match(ip("10.1.2.3")), Some(&"narrow"));
+        assert_eq!(trie.longest_match(ip("10.2.2.3")), Some(&"wide"));
+        assert_eq!(trie.longest_match(ip("::ffff:10.2.2.3")), Some(&"wide"));
+        assert_eq!(trie.longest_match(ip("2001:db8:1::5")), Some(&"v6"));
+        assert_eq!(trie.longest_match(ip("172.16.0.1")), Some(&"mapped"));
+        assert!(trie.longest_match(ip("11.0.0.1")).is_none());
+        assert!(trie.longest_match(ip("2001:db9::1")).is_none());
+
+        let mut all = IpPrefixTrie::new();
+        all.insert("0.0.0.0/0", ());
+        assert!(all.contains(ip("203.0.113.1")));
+        assert!(!all.contains(ip("::1")));
+        assert!(!all.is_empty());
+    }
+
+    #[test]
+    fn test_rule_index_literal_fallback() {
+        let mut index = IpRuleIndex::default();
+        index.insert("10.0.0.0/8", 1);
+        index.insert("10.0.0.5", 2);
+        index.insert("legacy.host.tag", 3);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.lookup("10.0.0.5"), Some(&2));
+        assert_eq!(index.lookup("::ffff:10.9.9.9"), Some(&1));
+        assert_eq!(index.lookup(" legacy.host.tag "), Some(&3));
+        assert_eq!(index.lookup("11.0.0.1"), None);
+    }
+}
@@ -3,14 +3,13 @@ use std::path::PathBuf;
 use serde_json::Value;
 use base64::{Engine as _, engine::general_purpose};
 use crate::models::{TokenData, Account, AccountIndex, AccountSummary};
-use crate::modules::{account, db};
+use crate::modules::{account, account_db, db};
 use crate::utils::protobuf;
 
 /// Sync legacy data from `~/.antigravity_sw/accounts/*.json` (plaintext, used by
-/// older builds) into the new `~/.antisw/accounts/` layout that stores each
-/// account file encrypted. Idempotent: only copies accounts whose target file
-/// does not already exist in the new directory, so it is safe to call on every
-/// startup.
+/// older builds) into the encrypted account store under `~/.antisw/`.
+/// Idempotent: only copies accounts whose ID is not already in the store, so it
+/// is safe to call on every startup.
 pub fn sync_legacy_antigravity_sw_accounts() -> Result<usize, String> {
     let home = dirs::home_dir().ok_or("Failed to get home directory")?;
     let legacy_dir = home.join(".antigravity_sw");
@@ -20,10 +19,10 @@ pub fn sync_legacy_antigravity_sw_accounts() -> Result<usize, String> {
         return Ok(0);
     }
 
-    let new_accounts_dir = account::get_accounts_dir()?;
+    let data_dir = account::get_data_dir()?;
 
     crate::modules::logger::log_info(&format!(
-        "[Migration] Detected legacy data directory {:?}, syncing accounts to new encrypted layout",
+        "[Migration] Detected legacy data directory {:?}, syncing accounts to encrypted account store",
         legacy_accounts_dir
     ));
 
@@ -61,8 +60,7 @@ pub fn sync_legacy_antigravity_sw_accounts() -> Result<usize, String> {
             None => continue,
         };
 
-        let new_account_path = new_accounts_dir.join(format!("{}.json", account_id));
-        if new_account_path.exists() {
+        if account_db::get_account_flags(&data_dir, &account_id)?.is_some() {
             skipped += 1;
             continue;
         }
@@ -129,8 +127,8 @@ pub fn sync_legacy_antigravity_sw_accounts() -> Result<usize, String> {
         return Ok(0);
     }
 
-    // Merge migrated summaries into the new index (build a fresh index if the
-    // new directory had none previously).
+    // Merge migrated summaries into the account order (build a fresh index if the
+    // store had none previously).
     let mut new_index = account::load_account_index().unwrap_or_else(|_| AccountIndex::new());
 
     for acc in &migrated {
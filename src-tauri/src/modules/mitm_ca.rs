@@ -3,9 +3,11 @@
 use std::fs;
 use std::path::PathBuf;
 
+use chrono::Datelike;
+
 use rcgen::{
     BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, Ia5String, IsCa,
-    KeyPair, KeyUsagePurpose, SanType,
+    KeyPair, KeyUsagePurpose, SanType, SerialNumber,
 };
 
 const MITM_DOMAINS: &[&str] = &[
@@ -95,6 +97,186 @@ pub fn ensure_all_certs() -> Result<(), String> {
     Ok(())
 }
 
+/// Gateway (built-in HTTPS listener) leaf cert validity — stays under the 398-day browser limit.
+const GATEWAY_CERT_VALID_DAYS: i64 = 397;
+/// Re-issue the gateway cert when it expires within this many days.
+const GATEWAY_CERT_RENEW_DAYS: i64 = 30;
+
+fn gateway_key_path() -> PathBuf {
+    get_mitm_dir().join("gateway.key")
+}
+
+fn gateway_cert_path() -> PathBuf {
+    get_mitm_dir().join("gateway.crt")
+}
+
+fn gateway_meta_path() -> PathBuf {
+    get_mitm_dir().join("gateway.json")
+}
+
+pub fn ca_cert_file() -> PathBuf {
+    ca_cert_path()
+}
+
+/// Ensure the Root CA exists and return its certificate path (trust anchor for mTLS client certs).
+pub fn ensure_ca_cert_file() -> Result<PathBuf, String> {
+    fs::create_dir_all(get_mitm_dir())
+        .map_err(|e| format!("Failed to create mitm dir: {}", e))?;
+    ensure_root_ca()?;
+    Ok(ca_cert_path())
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq)]
+struct GatewayCertMeta {
+    sans: Vec<String>,
+    not_after: i64,
+}
+
+/// Ensure a leaf cert for the built-in HTTPS listener, signed by the local Root CA and covering `sans`
+/// (DNS names or IP addresses). Re-issued when the SAN set changes or expiry is near.
+/// Returns (cert chain path, key path); the chain file contains the leaf followed by the Root CA.
+pub fn ensure_gateway_cert(sans: &[String]) -> Result<(PathBuf, PathBuf), String> {
+    let mitm_dir = get_mitm_dir();
+    fs::create_dir_all(&mitm_dir)
+        .map_err(|e| format!("Failed to create mitm dir: {}", e))?;
+
+    let key_path = gateway_key_path();
+    let cert_path = gateway_cert_path();
+    let now = chrono::Utc::now();
+
+    let existing: Option<GatewayCertMeta> = fs::read_to_string(gateway_meta_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok());
+    if let Some(meta) = existing {
+        let fresh = meta.not_after - now.timestamp() > GATEWAY_CERT_RENEW_DAYS * 86400;
+        if meta.sans == sans && fresh && key_path.exists() && cert_path.exists() {
+            return Ok((cert_path, key_path));
+        }
+    }
+
+    tracing::info!("[MITM-CA] Issuing gateway cert for {:?}", sans);
+    let (ca_key, ca_cert) = ensure_root_ca()?;
+
+    let leaf_key = KeyPair::generate()
+        .map_err(|e| format!("Failed to generate gateway key: {}", e))?;
+
+    let mut params = CertificateParams::default();
+    let common_name = sans
+        .iter()
+        .find(|s| s.parse::<std::net::IpAddr>().is_err())
+        .map(String::as_str)
+        .unwrap_or("localhost");
+    params.distinguished_name.push(DnType::CommonName, common_name);
+    params.distinguished_name.push(DnType::OrganizationName, "Antisw");
+    params.subject_alt_names = sans
+        .iter()
+        .map(|san| match san.parse::<std::net::IpAddr>() {
+            Ok(ip) => Ok(SanType::IpAddress(ip)),
+            Err(_) => Ia5String::try_from(san.clone())
+                .map(SanType::DnsName)
+                .map_err(|e| format!("Invalid SAN {}: {}", san, e)),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    params.key_usages = vec![
+        KeyUsagePurpose::DigitalSignature,
+        KeyUsagePurpose::KeyEncipherment,
+    ];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+
+    let (not_after, _) = set_validity_and_serial(&mut params, GATEWAY_CERT_VALID_DAYS);
+
+    let leaf_cert = params
+        .signed_by(&leaf_key, &ca_cert, &ca_key)
+        .map_err(|e| format!("Failed to sign gateway cert: {}", e))?;
+
+    let chain = format!("{}{}", leaf_cert.pem(), read_ca_cert_pem().unwrap_or_default());
+    fs::write(&key_path, leaf_key.serialize_pem())
+        .map_err(|e| format!("Failed to write gateway key: {}", e))?;
+    fs::write(&cert_path, chain)
+        .map_err(|e| format!("Failed to write gateway cert: {}", e))?;
+
+    let meta = GatewayCertMeta {
+        sans: sans.to_vec(),
+        not_after,
+    };
+    let meta_json = serde_json::to_string_pretty(&meta).map_err(|e| e.to_string())?;
+    fs::write(gateway_meta_path(), meta_json)
+        .map_err(|e| format!("Failed to write gateway cert metadata: {}", e))?;
+
+    Ok((cert_path, key_path))
+}
+
+/// A client certificate issued for mutual TLS. The private key is only returned once and never stored.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IssuedClientCert {
+    pub cert_pem: String,
+    pub key_pem: String,
+    /// Serial number (hex)
+    pub serial: String,
+    /// SHA-256 fingerprint of the DER certificate (hex)
+    pub fingerprint: String,
+    pub not_after: i64,
+}
+
+/// Issue a client-auth certificate signed by the local Root CA.
+pub fn issue_client_cert(common_name: &str, valid_days: i64) -> Result<IssuedClientCert, String> {
+    ensure_ca_cert_file()?;
+    let (ca_key, ca_cert) = ensure_root_ca()?;
+
+    let leaf_key = KeyPair::generate()
+        .map_err(|e| format!("Failed to generate client key: {}", e))?;
+
+    let mut params = CertificateParams::default();
+    params.distinguished_name.push(DnType::CommonName, common_name);
+    params.distinguished_name.push(DnType::OrganizationName, "Antisw Clients");
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let (not_after, serial) = set_validity_and_serial(&mut params, valid_days.max(1));
+
+    let leaf_cert = params
+        .signed_by(&leaf_key, &ca_cert, &ca_key)
+        .map_err(|e| format!("Failed to sign client cert: {}", e))?;
+
+    Ok(IssuedClientCert {
+        cert_pem: leaf_cert.pem(),
+        key_pem: leaf_key.serialize_pem(),
+        serial,
+        fingerprint: cert_fingerprint(leaf_cert.der()),
+        not_after,
+    })
+}
+
+/// SHA-256 fingerprint (lowercase hex) of a DER-encoded certificate.
+pub fn cert_fingerprint(der: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(der).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Set validity (backdated by one day to tolerate clock skew) and a random positive serial.
+/// Returns (not_after timestamp, serial hex).
+fn set_validity_and_serial(params: &mut CertificateParams, valid_days: i64) -> (i64, String) {
+    let now = chrono::Utc::now();
+    let not_before = now - chrono::Duration::days(1);
+    let not_after = now + chrono::Duration::days(valid_days);
+    params.not_before = rcgen::date_time_ymd(
+        not_before.year(),
+        not_before.month() as u8,
+        not_before.day() as u8,
+    );
+    params.not_after = rcgen::date_time_ymd(
+        not_after.year(),
+        not_after.month() as u8,
+        not_after.day() as u8,
+    );
+    let mut serial: [u8; 16] = rand::random();
+    serial[0] &= 0x7f;
+    params.serial_number = Some(SerialNumber::from_slice(&serial));
+    (
+        not_after.timestamp(),
+        serial.iter().map(|b| format!("{:02x}", b)).collect(),
+    )
+}
+
 /// Generate or load Root CA. Returns (KeyPair, Certificate) kept in memory for signing.
 fn ensure_root_ca() -> Result<(KeyPair, rcgen::Certificate), String> {
     let key_path = ca_key_path();
@@ -1,4 +1,11 @@
 pub mod account;
+pub mod account_db;
+pub mod account_events;
+pub mod account_health;
+pub mod account_import;
+pub mod account_query;
+pub mod provider_account;
+pub mod backup;
 pub mod quota;
 pub mod config;
 pub mod logger;
@@ -22,7 +29,16 @@ pub mod http_api;
 pub mod cache;
 pub mod log_bridge;
 pub mod security_db;
+pub mod ip_matcher;
+pub mod geoip;
+pub mod admin_auth_db;
 pub mod user_token_db;
+pub mod vault_rotation;
+pub mod token_schedule;
+pub mod runtime_state_db;
+pub mod quota_forecast;
+pub mod warmup_db;
+pub mod warmup_schedule;
 pub mod version;
 pub mod tracking;
 pub mod claude_settings;
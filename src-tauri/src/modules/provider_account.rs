@@ -0,0 +1,229 @@
+// 通用上游 Provider 账号 (第三方 OpenAI / Anthropic 兼容端点、自建 vLLM 等)
+// 以 account_type = "provider" 的账号保存，复用标签 / 禁用 / 事件等账号能力；
+// 账号写入后由 TokenManager 的存储订阅同步进号池，调度与协议转换见 proxy::providers::generic。
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::{
+    Account, ProviderConfig, ProviderProtocol, ProviderRouting, TokenData, PROVIDER_ACCOUNT_TYPE,
+};
+use crate::modules::account::{self, get_data_dir};
+use crate::modules::account_db::{self, ChangeOrigin};
+
+/// 账号名 (即存储中的 email 字段) 的最大字符数
+const MAX_NAME_CHARS: usize = 64;
+/// 单个端点声明的最大模型数
+const MAX_MODELS: usize = 256;
+
+/// Provider 端点配置 (更新时 api_key 为空表示沿用原值)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConfigInput {
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub protocol: ProviderProtocol,
+    #[serde(default)]
+    pub models: Vec<String>,
+    #[serde(default)]
+    pub model_mapping: HashMap<String, String>,
+    #[serde(default)]
+    pub routing: ProviderRouting,
+}
+
+/// 新建 Provider 账号
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderAccountRequest {
+    /// 账号名，在账号列表中唯一
+    pub name: String,
+    #[serde(flatten)]
+    pub config: ProviderConfigInput,
+}
+
+impl ProviderConfigInput {
+    /// 校验并构建配置
+    pub fn into_config(self, existing: Option<&ProviderConfig>) -> Result<ProviderConfig, String> {
+        let base_url = self.base_url.trim().trim_end_matches('/').to_string();
+        let parsed = url::Url::parse(&base_url).map_err(|e| format!("invalid_base_url: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+            return Err(format!("invalid_base_url: {}", base_url));
+        }
+
+        let mut models: Vec<String> = Vec::new();
+        for model in self.models {
+            let model = model.trim();
+            if !model.is_empty() && !models.iter().any(|m| m == model) {
+                models.push(model.to_string());
+            }
+        }
+        if models.is_empty() {
+            return Err("provider_models_required".to_string());
+        }
+        if models.len() > MAX_MODELS {
+            return Err(format!("too_many_models: max {}", MAX_MODELS));
+        }
+
+        let model_mapping = self
+            .model_mapping
+            .into_iter()
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+            .collect();
+
+        let api_key = match self.api_key.map(|k| k.trim().to_string()) {
+            Some(key) if !key.is_empty() => key,
+            _ => existing.map(|c| c.api_key.clone()).unwrap_or_default(),
+        };
+
+        Ok(ProviderConfig {
+            base_url,
+            api_key,
+            protocol: self.protocol,
+            models,
+            model_mapping,
+            routing: self.routing,
+        })
+    }
+}
+
+/// 隐藏 api_key，仅保留末 4 位 (用于管理 API 响应)
+pub fn redacted(config: &ProviderConfig) -> ProviderConfig {
+    let chars: Vec<char> = config.api_key.chars().collect();
+    let api_key = match chars.len() {
+        0 => String::new(),
+        n if n <= 8 => "****".to_string(),
+        n => format!("****{}", chars[n - 4..].iter().collect::<String>()),
+    };
+    ProviderConfig {
+        api_key,
+        ..config.clone()
+    }
+}
+
+/// 新建 Provider 账号
+pub fn add_provider_account(request: ProviderAccountRequest) -> Result<Account, String> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err("provider_name_required".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("provider_name_too_long: max {}", MAX_NAME_CHARS));
+    }
+    let config = request.config.into_config(None)?;
+
+    // Provider 账号没有 OAuth 凭据，token 仅为占位
+    let token = TokenData::new(String::new(), String::new(), 0, None, None, None);
+    let mut account = Account::new(Uuid::new_v4().to_string(), name.clone(), token);
+    account.name = Some(name);
+    account.account_type = Some(PROVIDER_ACCOUNT_TYPE.to_string());
+    account.provider = Some(config);
+
+    let data_dir = get_data_dir()?;
+    // Provider 账号不能作为当前 (IDE) 账号
+    account_db::insert_account(&data_dir, &account, false, ChangeOrigin::App)?;
+    Ok(account)
+}
+
+/// 更新 Provider 账号的端点配置
+pub fn update_provider_account(
+    account_id: &str,
+    input: ProviderConfigInput,
+) -> Result<Account, String> {
+    account::update_account(account_id, |account| {
+        if !account.is_provider() {
+            return Err("not_a_provider_account".to_string());
+        }
+        account.provider = Some(input.into_config(account.provider.as_ref())?);
+        Ok(account.clone())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(base_url: &str, models: &[&str]) -> ProviderConfigInput {
+        ProviderConfigInput {
+            base_url: base_url.to_string(),
+            api_key: None,
+            protocol: ProviderProtocol::OpenAI,
+            models: models.iter().map(|m| m.to_string()).collect(),
+            model_mapping: HashMap::from([(" gpt-4o ".to_string(), "".to_string())]),
+            routing: ProviderRouting::Fallback,
+        }
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = input(
+            " http://10.0.0.5:8000/v1/ ",
+            &["llama-3", " llama-3", "", "qwen*"],
+        )
+        .into_config(None)
+        .unwrap();
+        assert_eq!(config.base_url, "http://10.0.0.5:8000/v1");
+        assert_eq!(config.models, ["llama-3", "qwen*"]);
+        assert!(config.model_mapping.is_empty());
+        assert_eq!(config.api_key, "");
+
+        assert!(input("ftp://host", &["m"]).into_config(None).is_err());
+        assert!(input("not a url", &["m"]).into_config(None).is_err());
+        assert_eq!(
+            input("https://api.example.com/v1", &[" "]).into_config(None),
+            Err("provider_models_required".to_string())
+        );
+
+        // 更新时未提供 api_key 则沿用原值
+        let existing = ProviderConfig {
+            api_key: "sk-secret-1234".to_string(),
+            ..config
+        };
+        let updated = input("https://api.example.com/v1", &["m"])
+            .into_config(Some(&existing))
+            .unwrap();
+        assert_eq!(updated.api_key, "sk-secret-1234");
+        assert_eq!(redacted(&updated).api_key, "****1234");
+        assert_eq!(
+            redacted(&ProviderConfig {
+                api_key: "short".to_string(),
+                ..updated
+            })
+            .api_key,
+            "****"
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_base_url_and_protocol() {
+        for base_url in [
+            "",
+            "   ",
+            "http://",
+            "file:///etc/passwd",
+            "javascript:alert(1)",
+            "api.example.com/v1",
+        ] {
+            let err = input(base_url, &["m"]).into_config(None).unwrap_err();
+            assert!(err.starts_with("invalid_base_url"), "{}: {}", base_url, err);
+        }
+
+        let request = |protocol: &str| {
+            serde_json::from_value::<ProviderAccountRequest>(serde_json::json!({
+                "name": "vllm",
+                "baseUrl": "https://api.example.com/v1",
+                "protocol": protocol,
+                "models": ["m"]
+            }))
+        };
+        assert_eq!(
+            request("anthropic").unwrap().config.protocol,
+            ProviderProtocol::Anthropic
+        );
+        assert!(request("grpc").is_err());
+        assert!(request("OpenAI").is_err());
+    }
+}
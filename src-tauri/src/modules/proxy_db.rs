@@ -437,6 +437,49 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
     Ok(logs)
 }
 
+/// 用于调度模拟回放的精简流量记录
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReplayRecord {
+    pub timestamp: i64,
+    pub model: String,
+    pub mapped_model: Option<String>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub client_ip: Option<String>,
+    pub username: Option<String>,
+}
+
+/// 按时间正序读取指定时间 (毫秒) 之后的模型请求，用于调度模拟回放
+pub fn get_replay_traffic(since_ms: i64, limit: usize) -> Result<Vec<ReplayRecord>, String> {
+    let conn = connect_db()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, model, mapped_model, input_tokens, output_tokens, client_ip, username
+         FROM request_logs
+         WHERE timestamp >= ?1 AND method = 'POST' AND model IS NOT NULL AND model != ''
+         ORDER BY timestamp ASC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![since_ms, limit as i64], |row| {
+        Ok(ReplayRecord {
+            timestamp: row.get(0)?,
+            model: row.get(1)?,
+            mapped_model: row.get(2).unwrap_or(None),
+            input_tokens: row.get::<_, Option<u32>>(3).unwrap_or(None).unwrap_or(0),
+            output_tokens: row.get::<_, Option<u32>>(4).unwrap_or(None).unwrap_or(0),
+            client_ip: row.get(5).unwrap_or(None),
+            username: row.get(6).unwrap_or(None),
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        records.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(records)
+}
+
 // ... existing code ...
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
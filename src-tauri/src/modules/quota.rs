@@ -332,15 +332,23 @@ pub async fn fetch_all_quotas(accounts: Vec<(String, String, String)>) -> Vec<(S
 
 /// Get valid token (auto-refresh if expired)
 pub async fn get_valid_token_for_warmup(account: &crate::models::account::Account) -> Result<(String, String), String> {
+    // [NEW] Provider 账号没有 Google OAuth 凭据，不参与预热
+    if account.is_provider() {
+        return Err("Provider accounts do not support warmup".to_string());
+    }
     let mut account = account.clone();
     
     // Check and auto-refresh token
     let new_token = crate::modules::oauth::ensure_fresh_token(&account.token, Some(&account.id)).await?;
     
     // If token changed (meant refreshed), save it
+    // [FIX] 只写回 token 字段: OAuth 刷新期间账号可能已被其他流程修改，不能用旧快照整体覆盖
     if new_token.access_token != account.token.access_token {
-        account.token = new_token;
-        if let Err(e) = crate::modules::account::save_account(&account) {
+        account.token = new_token.clone();
+        if let Err(e) = crate::modules::account::update_account(&account.id, |a| {
+            a.token = new_token;
+            Ok(())
+        }) {
             crate::modules::logger::log_warn(&format!("[Warmup] Failed to save refreshed token: {}", e));
         } else {
             crate::modules::logger::log_info(&format!("[Warmup] Successfully refreshed and saved new token for {}", account.email));
@@ -418,7 +426,7 @@ pub async fn warm_up_all_accounts() -> Result<String, String> {
         // [FIX] 过滤掉禁用反代的账号
         let target_accounts: Vec<_> = all_accounts
             .into_iter()
-            .filter(|a| !a.disabled && !a.proxy_disabled)
+            .filter(|a| !a.disabled && !a.proxy_disabled && !a.is_provider())
             .collect();
 
         if target_accounts.is_empty() {
@@ -0,0 +1,578 @@
+// 配额耗尽预测
+// 结合账号配额快照 (quota.rs 拉取的剩余百分比与重置时间) 与 token_stats 的小时级消耗，
+// 按标准模型组估算整个号池的耗尽时间，并可在硬性断供前自动收紧配额保护阈值或切换到备用模型。
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use tokio::time::{self, Duration};
+
+use crate::models::{Account, QuotaForecastConfig, QuotaProtectionConfig};
+use crate::modules::{account, config, logger, token_stats};
+use crate::proxy::common::model_mapping::normalize_to_standard_id;
+
+/// 采样间隔 (秒)
+const SAMPLE_INTERVAL_SECS: u64 = 300;
+/// 每个模型组保留的采样点数量 (5 分钟一次，覆盖 24 小时)
+const MAX_SAMPLES_PER_GROUP: usize = 288;
+/// 计算燃烧速率所需的最短观测跨度 (秒)
+const MIN_OBSERVATION_SECS: i64 = 600;
+/// 最近一小时消耗相对窗口均值的修正系数上下限
+const MIN_TREND_FACTOR: f64 = 0.25;
+const MAX_TREND_FACTOR: f64 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PoolSample {
+    timestamp: i64,
+    remaining_units: f64,
+}
+
+/// 号池在某个标准模型组上的配额汇总
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoolQuota {
+    pub accounts: usize,
+    /// 剩余额度之和，以"单账号满额"为单位 (两个账号各剩 50% = 1.0)
+    pub remaining_units: f64,
+    /// 组内最早的配额重置时间 (Unix 秒)
+    pub next_reset_at: Option<i64>,
+}
+
+/// 单个模型组的预测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelForecast {
+    pub model_group: String,
+    pub accounts: usize,
+    pub remaining_units: f64,
+    pub avg_remaining_percentage: f64,
+    /// 观测窗口内的平均消耗 (单账号满额/小时)，已按最近一小时的 token 消耗趋势修正
+    pub burn_units_per_hour: Option<f64>,
+    pub tokens_last_hour: u64,
+    pub avg_tokens_per_hour: f64,
+    /// 距离触达配额保护阈值的预计小时数
+    pub hours_to_exhaustion: Option<f64>,
+    pub exhaustion_at: Option<i64>,
+    pub next_reset_at: Option<i64>,
+    pub at_risk: bool,
+}
+
+/// 完整的预测报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuotaForecastReport {
+    pub generated_at: i64,
+    pub window_hours: u32,
+    pub horizon_minutes: u32,
+    pub models: Vec<ModelForecast>,
+    /// 当前生效的配额保护阈值 (可能已被预测收紧)
+    pub effective_threshold_percentage: u32,
+    /// 当前生效的备用模型切换 (模型组 -> 备用模型)
+    pub active_fallbacks: HashMap<String, String>,
+}
+
+static POOL_SAMPLES: Lazy<RwLock<HashMap<String, VecDeque<PoolSample>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+static LATEST_REPORT: Lazy<RwLock<Option<QuotaForecastReport>>> = Lazy::new(|| RwLock::new(None));
+static THRESHOLD_OVERRIDE: Lazy<RwLock<Option<u32>>> = Lazy::new(|| RwLock::new(None));
+static ACTIVE_FALLBACKS: Lazy<RwLock<HashMap<String, String>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 获取生效的配额保护阈值 (配置值与预测收紧值取较大者)
+pub fn effective_threshold_percentage(config: &QuotaProtectionConfig) -> u32 {
+    match *THRESHOLD_OVERRIDE.read() {
+        Some(v) if v > config.threshold_percentage => v.min(99),
+        _ => config.threshold_percentage,
+    }
+}
+
+/// 若目标模型所在的模型组即将耗尽且配置了备用模型，则返回备用模型
+pub fn apply_fallback(model: String) -> String {
+    let fallbacks = ACTIVE_FALLBACKS.read();
+    if fallbacks.is_empty() {
+        return model;
+    }
+    let Some(group) = normalize_to_standard_id(&model) else {
+        return model;
+    };
+    match fallbacks.get(&group) {
+        Some(target) if *target != model => {
+            logger::log_info(&format!(
+                "[Forecast] Pool for {} is close to exhaustion, routing {} -> {}",
+                group, model, target
+            ));
+            target.clone()
+        }
+        _ => model,
+    }
+}
+
+/// 最近一次的预测报告
+pub fn get_latest_report() -> Option<QuotaForecastReport> {
+    LATEST_REPORT.read().clone()
+}
+
+/// 按标准模型组汇总号池剩余配额 (跳过禁用/被禁止的账号；组内取最低百分比，与配额保护口径一致)
+pub fn aggregate_pool(accounts: &[Account]) -> HashMap<String, PoolQuota> {
+    let mut pool: HashMap<String, PoolQuota> = HashMap::new();
+
+    for acc in accounts {
+        if acc.disabled || acc.proxy_disabled {
+            continue;
+        }
+        let Some(ref quota) = acc.quota else {
+            continue;
+        };
+        if quota.is_forbidden {
+            continue;
+        }
+
+        let mut group_min: HashMap<String, (i32, Option<i64>)> = HashMap::new();
+        for model in &quota.models {
+            let Some(group) = normalize_to_standard_id(&model.name) else {
+                continue;
+            };
+            let reset = chrono::DateTime::parse_from_rfc3339(&model.reset_time)
+                .ok()
+                .map(|dt| dt.timestamp());
+            let entry = group_min.entry(group).or_insert((100, None));
+            entry.0 = entry.0.min(model.percentage);
+            entry.1 = match (entry.1, reset) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+
+        for (group, (pct, reset)) in group_min {
+            let entry = pool.entry(group).or_default();
+            entry.accounts += 1;
+            entry.remaining_units += pct.clamp(0, 100) as f64 / 100.0;
+            entry.next_reset_at = match (entry.next_reset_at, reset) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+
+    pool
+}
+
+fn record_sample(group: &str, timestamp: i64, remaining_units: f64) {
+    let mut samples = POOL_SAMPLES.write();
+    let series = samples.entry(group.to_string()).or_default();
+    series.push_back(PoolSample {
+        timestamp,
+        remaining_units,
+    });
+    while series.len() > MAX_SAMPLES_PER_GROUP {
+        series.pop_front();
+    }
+}
+
+/// 根据采样序列计算窗口内的消耗速率 (单位/小时)
+/// 剩余额度上升 (配额重置、新增账号) 的区间不计入消耗
+fn burn_rate_per_hour(samples: &[PoolSample], since: i64) -> Option<f64> {
+    let window: Vec<&PoolSample> = samples.iter().filter(|s| s.timestamp >= since).collect();
+    let (first, last) = (window.first()?, window.last()?);
+    let span = last.timestamp - first.timestamp;
+    if span < MIN_OBSERVATION_SECS {
+        return None;
+    }
+
+    let consumed: f64 = window
+        .windows(2)
+        .map(|w| (w[0].remaining_units - w[1].remaining_units).max(0.0))
+        .sum();
+    Some(consumed / (span as f64 / 3600.0))
+}
+
+/// 最近一小时 token 消耗相对窗口均值的比例，用于放大/收缩燃烧速率
+fn trend_factor(tokens_last_hour: u64, avg_tokens_per_hour: f64) -> f64 {
+    if avg_tokens_per_hour <= 0.0 {
+        return 1.0;
+    }
+    (tokens_last_hour as f64 / avg_tokens_per_hour).clamp(MIN_TREND_FACTOR, MAX_TREND_FACTOR)
+}
+
+/// 按模型组汇总 token 消耗
+fn tokens_by_group(hours: i64) -> HashMap<String, u64> {
+    let mut result = HashMap::new();
+    match token_stats::get_model_stats(hours) {
+        Ok(stats) => {
+            for s in stats {
+                if let Some(group) = normalize_to_standard_id(&s.model) {
+                    *result.entry(group).or_insert(0) += s.total_tokens;
+                }
+            }
+        }
+        Err(e) => tracing::debug!("[Forecast] Failed to load token stats: {}", e),
+    }
+    result
+}
+
+fn forecast_group(
+    group: &str,
+    pool: &PoolQuota,
+    burn_units_per_hour: Option<f64>,
+    tokens: (u64, f64),
+    reserve_percentage: u32,
+    horizon_minutes: u32,
+    now: i64,
+) -> ModelForecast {
+    let (tokens_last_hour, avg_tokens_per_hour) = tokens;
+    let burn = burn_units_per_hour.map(|b| b * trend_factor(tokens_last_hour, avg_tokens_per_hour));
+
+    // 配额保护会在阈值处锁定账号，因此"耗尽"指的是触达保留额度
+    let reserve_units = pool.accounts as f64 * reserve_percentage as f64 / 100.0;
+    let usable_units = (pool.remaining_units - reserve_units).max(0.0);
+
+    let hours_to_exhaustion = if pool.accounts > 0 && usable_units <= f64::EPSILON {
+        Some(0.0)
+    } else {
+        match burn {
+            Some(b) if b > 1e-6 => Some(usable_units / b),
+            _ => None,
+        }
+    };
+    let exhaustion_at = hours_to_exhaustion.map(|h| now + (h * 3600.0) as i64);
+
+    // 在重置之前耗尽，且落在预测视野内，才视为有风险
+    let at_risk = match exhaustion_at {
+        Some(ts) => {
+            let within_horizon = ts - now <= horizon_minutes as i64 * 60;
+            let before_reset = pool.next_reset_at.map_or(true, |reset| ts < reset);
+            within_horizon && before_reset
+        }
+        None => false,
+    };
+
+    ModelForecast {
+        model_group: group.to_string(),
+        accounts: pool.accounts,
+        remaining_units: pool.remaining_units,
+        avg_remaining_percentage: if pool.accounts > 0 {
+            pool.remaining_units * 100.0 / pool.accounts as f64
+        } else {
+            0.0
+        },
+        burn_units_per_hour: burn,
+        tokens_last_hour,
+        avg_tokens_per_hour,
+        hours_to_exhaustion,
+        exhaustion_at,
+        next_reset_at: pool.next_reset_at,
+        at_risk,
+    }
+}
+
+/// 计算收紧后的阈值：越接近耗尽，阈值越接近上限
+fn tightened_threshold(
+    base: u32,
+    max: u32,
+    horizon_minutes: u32,
+    forecasts: &[ModelForecast],
+    monitored_models: &[String],
+) -> Option<u32> {
+    if max <= base || horizon_minutes == 0 {
+        return None;
+    }
+    let horizon_hours = horizon_minutes as f64 / 60.0;
+    let urgency = forecasts
+        .iter()
+        .filter(|f| f.at_risk && monitored_models.contains(&f.model_group))
+        .filter_map(|f| f.hours_to_exhaustion)
+        .map(|h| (1.0 - h / horizon_hours).clamp(0.0, 1.0))
+        .fold(None, |acc: Option<f64>, u| Some(acc.map_or(u, |a| a.max(u))))?;
+
+    Some(base + ((max - base) as f64 * urgency).round() as u32)
+}
+
+/// 选出需要切换的模型组：本组有风险、配置了备用模型，且备用模型所在组自身没有风险
+fn select_fallbacks(
+    forecasts: &[ModelForecast],
+    fallback_models: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let at_risk = |group: &str| forecasts.iter().any(|f| f.model_group == group && f.at_risk);
+
+    fallback_models
+        .iter()
+        .filter(|(group, _)| at_risk(group))
+        .filter(|(_, target)| normalize_to_standard_id(target).map_or(true, |g| !at_risk(&g)))
+        .map(|(group, target)| (group.clone(), target.clone()))
+        .collect()
+}
+
+/// 根据预测结果更新阈值收紧与备用模型切换状态
+fn apply_actions(
+    forecast_config: &QuotaForecastConfig,
+    protection: &QuotaProtectionConfig,
+    forecasts: &[ModelForecast],
+) {
+    let threshold = if forecast_config.enabled && forecast_config.auto_tighten_threshold && protection.enabled {
+        tightened_threshold(
+            protection.threshold_percentage,
+            forecast_config.max_threshold_percentage.min(99),
+            forecast_config.horizon_minutes,
+            forecasts,
+            &protection.monitored_models,
+        )
+    } else {
+        None
+    };
+    {
+        let mut current = THRESHOLD_OVERRIDE.write();
+        if *current != threshold {
+            logger::log_info(&format!(
+                "[Forecast] Quota protection threshold override: {:?} -> {:?}",
+                *current, threshold
+            ));
+            *current = threshold;
+        }
+    }
+
+    let fallbacks = if forecast_config.enabled && forecast_config.auto_fallback {
+        select_fallbacks(forecasts, &forecast_config.fallback_models)
+    } else {
+        HashMap::new()
+    };
+    {
+        let mut current = ACTIVE_FALLBACKS.write();
+        if *current != fallbacks {
+            logger::log_info(&format!("[Forecast] Active model fallbacks: {:?}", fallbacks));
+            *current = fallbacks;
+        }
+    }
+}
+
+/// 采样号池配额、重新计算预测并执行自动调整 (阻塞操作，需在 spawn_blocking 中调用)
+pub fn refresh_forecast() -> Result<QuotaForecastReport, String> {
+    let app_config = config::load_app_config()?;
+    let forecast_config = &app_config.quota_forecast;
+    let accounts = account::list_accounts()?;
+    let now = chrono::Utc::now().timestamp();
+
+    let pool = aggregate_pool(&accounts);
+    for (group, quota) in &pool {
+        record_sample(group, now, quota.remaining_units);
+    }
+
+    let window_hours = forecast_config.window_hours.max(1);
+    let since = now - window_hours as i64 * 3600;
+    let tokens_last_hour = tokens_by_group(1);
+    let tokens_window = tokens_by_group(window_hours as i64);
+
+    let mut models: Vec<ModelForecast> = {
+        let samples = POOL_SAMPLES.read();
+        pool.iter()
+            .map(|(group, quota)| {
+                let series: Vec<PoolSample> = samples
+                    .get(group)
+                    .map(|s| s.iter().copied().collect())
+                    .unwrap_or_default();
+                let tokens = (
+                    tokens_last_hour.get(group).copied().unwrap_or(0),
+                    tokens_window.get(group).copied().unwrap_or(0) as f64 / window_hours as f64,
+                );
+                forecast_group(
+                    group,
+                    quota,
+                    burn_rate_per_hour(&series, since),
+                    tokens,
+                    app_config.quota_protection.threshold_percentage,
+                    forecast_config.horizon_minutes,
+                    now,
+                )
+            })
+            .collect()
+    };
+    models.sort_by(|a, b| a.model_group.cmp(&b.model_group));
+
+    apply_actions(forecast_config, &app_config.quota_protection, &models);
+
+    let report = QuotaForecastReport {
+        generated_at: now,
+        window_hours,
+        horizon_minutes: forecast_config.horizon_minutes,
+        models,
+        effective_threshold_percentage: effective_threshold_percentage(&app_config.quota_protection),
+        active_fallbacks: ACTIVE_FALLBACKS.read().clone(),
+    };
+    *LATEST_REPORT.write() = Some(report.clone());
+    Ok(report)
+}
+
+/// 启动预测后台任务 (每 5 分钟采样并重新计算)
+pub fn start_forecast_scheduler() {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(SAMPLE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match tokio::task::spawn_blocking(refresh_forecast).await {
+                Ok(Ok(report)) => {
+                    for m in report.models.iter().filter(|m| m.at_risk) {
+                        logger::log_warn(&format!(
+                            "[Forecast] Pool for {} predicted to exhaust in {:.1}h ({} accounts, {:.1}% avg remaining)",
+                            m.model_group,
+                            m.hours_to_exhaustion.unwrap_or(0.0),
+                            m.accounts,
+                            m.avg_remaining_percentage
+                        ));
+                    }
+                }
+                Ok(Err(e)) => tracing::debug!("[Forecast] Refresh skipped: {}", e),
+                Err(e) => tracing::warn!("[Forecast] Refresh task failed: {}", e),
+            }
+        }
+    });
+}
+
+/// 将预测结果渲染为 Prometheus 文本格式
+pub fn render_prometheus(report: &QuotaForecastReport) -> String {
+    let mut out = String::new();
+
+    let gauges: [(&str, &str, fn(&ModelForecast) -> Option<f64>); 6] = [
+        (
+            "antigravity_quota_pool_accounts",
+            "Active accounts contributing quota to the model group",
+            |m| Some(m.accounts as f64),
+        ),
+        (
+            "antigravity_quota_pool_remaining_units",
+            "Remaining quota in full-account units",
+            |m| Some(m.remaining_units),
+        ),
+        (
+            "antigravity_quota_pool_burn_units_per_hour",
+            "Estimated quota consumption in full-account units per hour",
+            |m| m.burn_units_per_hour,
+        ),
+        (
+            "antigravity_quota_pool_hours_to_exhaustion",
+            "Predicted hours until the pool reaches the protection threshold",
+            |m| m.hours_to_exhaustion,
+        ),
+        (
+            "antigravity_quota_pool_next_reset_timestamp_seconds",
+            "Earliest quota reset time in the model group",
+            |m| m.next_reset_at.map(|v| v as f64),
+        ),
+        (
+            "antigravity_quota_pool_at_risk",
+            "Whether the pool is predicted to run dry within the forecast horizon",
+            |m| Some(if m.at_risk { 1.0 } else { 0.0 }),
+        ),
+    ];
+
+    for (name, help, value) in gauges {
+        out.push_str(&format!("# HELP {} {}\n# TYPE {} gauge\n", name, help, name));
+        for m in &report.models {
+            if let Some(v) = value(m) {
+                out.push_str(&format!("{}{{model_group=\"{}\"}} {}\n", name, m.model_group, v));
+            }
+        }
+    }
+
+    out.push_str("# HELP antigravity_quota_protection_threshold_percentage Effective quota protection threshold\n");
+    out.push_str("# TYPE antigravity_quota_protection_threshold_percentage gauge\n");
+    out.push_str(&format!(
+        "antigravity_quota_protection_threshold_percentage {}\n",
+        report.effective_threshold_percentage
+    ));
+
+    out.push_str("# HELP antigravity_quota_fallback_active Model groups currently routed to a fallback model\n");
+    out.push_str("# TYPE antigravity_quota_fallback_active gauge\n");
+    for (group, target) in &report.active_fallbacks {
+        out.push_str(&format!(
+            "antigravity_quota_fallback_active{{model_group=\"{}\",fallback=\"{}\"}} 1\n",
+            group, target
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, remaining_units: f64) -> PoolSample {
+        PoolSample {
+            timestamp,
+            remaining_units,
+        }
+    }
+
+    #[test]
+    fn test_burn_rate_ignores_resets_and_short_windows() {
+        let samples = vec![
+            sample(0, 2.0),
+            sample(1800, 1.5),
+            sample(2700, 1.9), // 配额重置，不计入消耗
+            sample(3600, 1.4),
+        ];
+        let rate = burn_rate_per_hour(&samples, 0).unwrap();
+        assert!((rate - 1.0).abs() < 1e-9);
+
+        // 观测跨度不足
+        assert!(burn_rate_per_hour(&samples[..1], 0).is_none());
+        assert!(burn_rate_per_hour(&[sample(0, 1.0), sample(300, 0.9)], 0).is_none());
+    }
+
+    #[test]
+    fn test_forecast_respects_reserve_horizon_and_reset() {
+        let pool = PoolQuota {
+            accounts: 4,
+            remaining_units: 1.4, // 4 个账号平均剩 35%
+            next_reset_at: None,
+        };
+        // 保留 10% => 可用 1.0 单位，每小时消耗 1.0 单位 => 1 小时后触达阈值
+        let f = forecast_group("claude", &pool, Some(1.0), (0, 0.0), 10, 120, 0);
+        assert!((f.hours_to_exhaustion.unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(f.exhaustion_at, Some(3600));
+        assert!(f.at_risk);
+
+        // 超出预测视野
+        let f = forecast_group("claude", &pool, Some(1.0), (0, 0.0), 10, 30, 0);
+        assert!(!f.at_risk);
+
+        // 重置早于耗尽
+        let pool_with_reset = PoolQuota {
+            next_reset_at: Some(1800),
+            ..pool.clone()
+        };
+        let f = forecast_group("claude", &pool_with_reset, Some(1.0), (0, 0.0), 10, 120, 0);
+        assert!(!f.at_risk);
+
+        // 最近一小时消耗翻倍 => 速率翻倍
+        let f = forecast_group("claude", &pool, Some(1.0), (2000, 1000.0), 10, 120, 0);
+        assert!((f.hours_to_exhaustion.unwrap() - 0.5).abs() < 1e-9);
+
+        // 无燃烧数据时不预测
+        let f = forecast_group("claude", &pool, None, (0, 0.0), 10, 120, 0);
+        assert!(f.hours_to_exhaustion.is_none());
+        assert!(!f.at_risk);
+    }
+
+    #[test]
+    fn test_actions_tighten_threshold_and_select_fallbacks() {
+        let pool = PoolQuota {
+            accounts: 2,
+            remaining_units: 0.7,
+            next_reset_at: None,
+        };
+        let claude = forecast_group("claude", &pool, Some(1.0), (0, 0.0), 10, 120, 0);
+        let flash = forecast_group("gemini-3-flash", &pool, None, (0, 0.0), 10, 120, 0);
+        let forecasts = vec![claude, flash];
+
+        // 可用 0.5 单位 => 0.5 小时，视野 2 小时 => 紧迫度 0.75
+        let monitored = vec!["claude".to_string()];
+        assert_eq!(tightened_threshold(10, 30, 120, &forecasts, &monitored), Some(25));
+        assert_eq!(tightened_threshold(10, 30, 120, &forecasts, &[]), None);
+        assert_eq!(tightened_threshold(30, 10, 120, &forecasts, &monitored), None);
+
+        let mut fallback_models = HashMap::new();
+        fallback_models.insert("claude".to_string(), "gemini-3-flash".to_string());
+        fallback_models.insert("gemini-3-flash".to_string(), "claude-sonnet-4-6".to_string());
+        let selected = select_fallbacks(&forecasts, &fallback_models);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected.get("claude"), Some(&"gemini-3-flash".to_string()));
+    }
+}
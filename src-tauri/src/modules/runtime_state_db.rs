@@ -0,0 +1,335 @@
+// 调度运行时状态持久化
+// 将会话绑定、限流记录、失败计数与签名缓存快照到 SQLite，
+// 以便应用更新或崩溃重启后恢复 Prompt Cache 亲和性并避免重复冲击已耗尽的账号。
+
+use rusqlite::{params, Connection};
+use std::path::PathBuf;
+
+/// 会话绑定记录 (SessionID -> AccountID)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionBindingRecord {
+    pub session_id: String,
+    pub account_id: String,
+    /// 快照时间 (Unix 秒)
+    pub updated_at: i64,
+}
+
+/// 限流记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitRecord {
+    /// 限流 Key ("account_id" 或 "account_id:model")
+    pub key: String,
+    /// 限流解除时间 (Unix 秒)
+    pub reset_time: i64,
+    pub retry_after_sec: u64,
+    /// 检测时间 (Unix 秒)
+    pub detected_at: i64,
+    pub reason: String,
+    pub model: Option<String>,
+}
+
+/// 连续失败计数记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailureCountRecord {
+    pub account_id: String,
+    pub count: u32,
+    /// 最近一次失败时间 (Unix 秒)
+    pub last_failure: i64,
+}
+
+/// 签名缓存记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureRecord {
+    /// 缓存层: "tool" | "family" | "session"
+    pub layer: String,
+    pub key: String,
+    pub data: String,
+    /// 仅 session 层使用
+    pub message_count: i64,
+    /// 写入缓存的时间 (Unix 秒)
+    pub timestamp: i64,
+}
+
+/// 完整的运行时状态快照
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeStateSnapshot {
+    pub sessions: Vec<SessionBindingRecord>,
+    pub rate_limits: Vec<RateLimitRecord>,
+    pub failure_counts: Vec<FailureCountRecord>,
+    pub signatures: Vec<SignatureRecord>,
+}
+
+pub fn get_runtime_state_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("runtime_state.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_runtime_state_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS session_bindings (
+            session_id TEXT PRIMARY KEY,
+            account_id TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS rate_limits (
+            limit_key TEXT PRIMARY KEY,
+            reset_time INTEGER NOT NULL,
+            retry_after_sec INTEGER NOT NULL,
+            detected_at INTEGER NOT NULL,
+            reason TEXT NOT NULL,
+            model TEXT
+        );
+        CREATE TABLE IF NOT EXISTS failure_counts (
+            account_id TEXT PRIMARY KEY,
+            count INTEGER NOT NULL,
+            last_failure INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS signature_cache (
+            layer TEXT NOT NULL,
+            cache_key TEXT NOT NULL,
+            data TEXT NOT NULL,
+            message_count INTEGER NOT NULL DEFAULT 0,
+            timestamp INTEGER NOT NULL,
+            PRIMARY KEY (layer, cache_key)
+        );",
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_tables(&conn)
+}
+
+/// 以快照整体替换已持久化的状态 (单事务)
+pub fn save_snapshot(snapshot: &RuntimeStateSnapshot) -> Result<(), String> {
+    let mut conn = connect_db()?;
+    create_tables(&conn)?;
+    write_snapshot(&mut conn, snapshot)
+}
+
+/// 读取已持久化的状态快照 (不做过期过滤，由调用方处理)
+pub fn load_snapshot() -> Result<RuntimeStateSnapshot, String> {
+    let conn = connect_db()?;
+    create_tables(&conn)?;
+    read_snapshot(&conn)
+}
+
+fn write_snapshot(conn: &mut Connection, snapshot: &RuntimeStateSnapshot) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute("DELETE FROM session_bindings", [])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM rate_limits", [])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM failure_counts", [])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM signature_cache", [])
+        .map_err(|e| e.to_string())?;
+
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO session_bindings (session_id, account_id, updated_at)
+                 VALUES (?1, ?2, ?3)",
+            )
+            .map_err(|e| e.to_string())?;
+        for s in &snapshot.sessions {
+            stmt.execute(params![s.session_id, s.account_id, s.updated_at])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO rate_limits (limit_key, reset_time, retry_after_sec, detected_at, reason, model)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(|e| e.to_string())?;
+        for r in &snapshot.rate_limits {
+            stmt.execute(params![
+                r.key,
+                r.reset_time,
+                r.retry_after_sec as i64,
+                r.detected_at,
+                r.reason,
+                r.model
+            ])
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO failure_counts (account_id, count, last_failure)
+                 VALUES (?1, ?2, ?3)",
+            )
+            .map_err(|e| e.to_string())?;
+        for f in &snapshot.failure_counts {
+            stmt.execute(params![f.account_id, f.count, f.last_failure])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO signature_cache (layer, cache_key, data, message_count, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .map_err(|e| e.to_string())?;
+        for s in &snapshot.signatures {
+            stmt.execute(params![s.layer, s.key, s.data, s.message_count, s.timestamp])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn read_snapshot(conn: &Connection) -> Result<RuntimeStateSnapshot, String> {
+    let mut snapshot = RuntimeStateSnapshot::default();
+
+    let mut stmt = conn
+        .prepare("SELECT session_id, account_id, updated_at FROM session_bindings")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(SessionBindingRecord {
+                session_id: row.get(0)?,
+                account_id: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        snapshot.sessions.push(row.map_err(|e| e.to_string())?);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT limit_key, reset_time, retry_after_sec, detected_at, reason, model FROM rate_limits",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(RateLimitRecord {
+                key: row.get(0)?,
+                reset_time: row.get(1)?,
+                retry_after_sec: row.get::<_, i64>(2)?.max(0) as u64,
+                detected_at: row.get(3)?,
+                reason: row.get(4)?,
+                model: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        snapshot.rate_limits.push(row.map_err(|e| e.to_string())?);
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT account_id, count, last_failure FROM failure_counts")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(FailureCountRecord {
+                account_id: row.get(0)?,
+                count: row.get(1)?,
+                last_failure: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        snapshot.failure_counts.push(row.map_err(|e| e.to_string())?);
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT layer, cache_key, data, message_count, timestamp FROM signature_cache")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(SignatureRecord {
+                layer: row.get(0)?,
+                key: row.get(1)?,
+                data: row.get(2)?,
+                message_count: row.get(3)?,
+                timestamp: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        snapshot.signatures.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip_replaces_previous_state() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        let first = RuntimeStateSnapshot {
+            sessions: vec![SessionBindingRecord {
+                session_id: "sid-1".to_string(),
+                account_id: "acc-1".to_string(),
+                updated_at: 100,
+            }],
+            rate_limits: vec![RateLimitRecord {
+                key: "acc-1:claude-sonnet-4-5".to_string(),
+                reset_time: 2_000,
+                retry_after_sec: 60,
+                detected_at: 1_940,
+                reason: "quota_exhausted".to_string(),
+                model: Some("claude-sonnet-4-5".to_string()),
+            }],
+            failure_counts: vec![FailureCountRecord {
+                account_id: "acc-1".to_string(),
+                count: 2,
+                last_failure: 1_940,
+            }],
+            signatures: vec![SignatureRecord {
+                layer: "session".to_string(),
+                key: "sid-1".to_string(),
+                data: "sig".to_string(),
+                message_count: 4,
+                timestamp: 1_900,
+            }],
+        };
+        write_snapshot(&mut conn, &first).unwrap();
+
+        let loaded = read_snapshot(&conn).unwrap();
+        assert_eq!(loaded.sessions, first.sessions);
+        assert_eq!(loaded.rate_limits, first.rate_limits);
+        assert_eq!(loaded.failure_counts, first.failure_counts);
+        assert_eq!(loaded.signatures, first.signatures);
+
+        // 新快照整体替换旧状态
+        write_snapshot(&mut conn, &RuntimeStateSnapshot::default()).unwrap();
+        let loaded = read_snapshot(&conn).unwrap();
+        assert!(loaded.sessions.is_empty());
+        assert!(loaded.rate_limits.is_empty());
+        assert!(loaded.failure_counts.is_empty());
+        assert!(loaded.signatures.is_empty());
+    }
+}
@@ -1,9 +1,14 @@
 //! Security Database Module
 //! 安全监控相关的数据库操作
 
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::modules::ip_matcher::IpRuleIndex;
 
 /// IP 访问日志
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +26,15 @@ pub struct IpAccessLog {
     pub block_reason: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
+    /// 国家代码 (ISO 3166-1 alpha-2，来自 GeoIP 数据库)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// 自治系统号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<u32>,
+    /// 自治系统所属组织
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn_org: Option<String>,
 }
 
 /// IP 黑名单条目
@@ -33,6 +47,23 @@ pub struct IpBlacklistEntry {
     pub expires_at: Option<i64>,
     pub created_by: String,
     pub hit_count: i64,
+    /// 自动封禁的触发证据 (JSON)
+    #[serde(default)]
+    pub evidence: Option<String>,
+}
+
+/// 自动封禁历史 (黑名单条目过期删除后仍保留，用于递增封禁时长)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpBanRecord {
+    pub id: String,
+    pub client_ip: String,
+    pub signal: String,
+    pub reason: String,
+    pub evidence: Option<String>,
+    /// 第几次违规 (从 1 开始)
+    pub offence: i64,
+    pub created_at: i64,
+    pub expires_at: i64,
 }
 
 /// IP 白名单条目
@@ -64,6 +95,18 @@ pub struct IpRanking {
     pub is_blocked: bool,
 }
 
+/// 国家 / ASN 访问排行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoRanking {
+    /// 国家代码或 ASN 编号 ("AS13335")
+    pub key: String,
+    /// ASN 组织名称 (国家排行为空)
+    pub label: Option<String>,
+    pub request_count: u64,
+    pub unique_ips: u64,
+    pub blocked_count: u64,
+}
+
 /// 获取安全数据库路径
 pub fn get_security_db_path() -> Result<PathBuf, String> {
     let data_dir = crate::modules::account::get_data_dir()?;
@@ -167,6 +210,36 @@ pub fn init_db() -> Result<(), String> {
     // Migration: Add username column to ip_access_logs
     let _ = conn.execute("ALTER TABLE ip_access_logs ADD COLUMN username TEXT", []);
 
+    // Migration: Add GeoIP columns to ip_access_logs
+    let _ = conn.execute("ALTER TABLE ip_access_logs ADD COLUMN country TEXT", []);
+    let _ = conn.execute("ALTER TABLE ip_access_logs ADD COLUMN asn INTEGER", []);
+    let _ = conn.execute("ALTER TABLE ip_access_logs ADD COLUMN asn_org TEXT", []);
+
+    // Migration: Add evidence column to ip_blacklist
+    let _ = conn.execute("ALTER TABLE ip_blacklist ADD COLUMN evidence TEXT", []);
+
+    // 自动封禁历史表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ip_ban_history (
+            id TEXT PRIMARY KEY,
+            client_ip TEXT NOT NULL,
+            signal TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            evidence TEXT,
+            offence INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_ban_history_ip ON ip_ban_history (client_ip, created_at DESC)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -179,8 +252,8 @@ pub fn save_ip_access_log(log: &IpAccessLog) -> Result<(), String> {
     let conn = connect_db()?;
 
     conn.execute(
-        "INSERT INTO ip_access_logs (id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        "INSERT INTO ip_access_logs (id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username, country, asn, asn_org)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
         params![
             log.id,
             log.client_ip,
@@ -194,6 +267,9 @@ pub fn save_ip_access_log(log: &IpAccessLog) -> Result<(), String> {
             log.blocked,
             log.block_reason,
             log.username,
+            log.country,
+            log.asn,
+            log.asn_org,
         ],
     )
     .map_err(|e| e.to_string())?;
@@ -213,7 +289,7 @@ pub fn get_ip_access_logs(
     let sql = if blocked_only {
         if let Some(ip) = ip_filter {
             format!(
-                "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username
+                "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username, country, asn, asn_org
                  FROM ip_access_logs
                  WHERE blocked = 1 AND client_ip LIKE '%{}%'
                  ORDER BY timestamp DESC
@@ -222,7 +298,7 @@ pub fn get_ip_access_logs(
             )
         } else {
             format!(
-                "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username
+                "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username, country, asn, asn_org
                  FROM ip_access_logs
                  WHERE blocked = 1
                  ORDER BY timestamp DESC
@@ -232,7 +308,7 @@ pub fn get_ip_access_logs(
         }
     } else if let Some(ip) = ip_filter {
         format!(
-            "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username
+            "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username, country, asn, asn_org
              FROM ip_access_logs
              WHERE client_ip LIKE '%{}%'
              ORDER BY timestamp DESC
@@ -241,7 +317,7 @@ pub fn get_ip_access_logs(
         )
     } else {
         format!(
-            "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username
+            "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username, country, asn, asn_org
              FROM ip_access_logs
              ORDER BY timestamp DESC
              LIMIT {} OFFSET {}",
@@ -266,6 +342,9 @@ pub fn get_ip_access_logs(
                 blocked: row.get::<_, i32>(9)? != 0,
                 block_reason: row.get(10)?,
                 username: row.get(11).unwrap_or(None),
+                country: row.get(12).unwrap_or(None),
+                asn: row.get(13).unwrap_or(None),
+                asn_org: row.get(14).unwrap_or(None),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -358,6 +437,72 @@ pub fn get_top_ips(limit: usize, hours: i64) -> Result<Vec<IpRanking>, String> {
     Ok(rankings)
 }
 
+/// 获取 TOP N 国家访问排行 (仅统计已解析出国家的记录)
+pub fn get_top_countries(limit: usize, hours: i64) -> Result<Vec<GeoRanking>, String> {
+    let conn = connect_db()?;
+    let since = chrono::Utc::now().timestamp() - (hours * 3600);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT country, COUNT(*) as cnt, COUNT(DISTINCT client_ip),
+                    SUM(CASE WHEN blocked = 1 THEN 1 ELSE 0 END)
+             FROM ip_access_logs
+             WHERE timestamp >= ?1 AND country IS NOT NULL
+             GROUP BY country
+             ORDER BY cnt DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([since, limit as i64], |row| {
+            Ok(GeoRanking {
+                key: row.get(0)?,
+                label: None,
+                request_count: row.get(1)?,
+                unique_ips: row.get(2)?,
+                blocked_count: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 获取 TOP N ASN 访问排行
+pub fn get_top_asns(limit: usize, hours: i64) -> Result<Vec<GeoRanking>, String> {
+    let conn = connect_db()?;
+    let since = chrono::Utc::now().timestamp() - (hours * 3600);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT asn, MAX(asn_org), COUNT(*) as cnt, COUNT(DISTINCT client_ip),
+                    SUM(CASE WHEN blocked = 1 THEN 1 ELSE 0 END)
+             FROM ip_access_logs
+             WHERE timestamp >= ?1 AND asn IS NOT NULL
+             GROUP BY asn
+             ORDER BY cnt DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([since, limit as i64], |row| {
+            Ok(GeoRanking {
+                key: format!("AS{}", row.get::<_, u32>(0)?),
+                label: row.get(1)?,
+                request_count: row.get(2)?,
+                unique_ips: row.get(3)?,
+                blocked_count: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
 /// 清理旧的 IP 访问日志
 pub fn cleanup_old_ip_logs(days: i64) -> Result<usize, String> {
     let conn = connect_db()?;
@@ -399,6 +544,7 @@ pub fn add_to_blacklist(
         params![id, ip_pattern, reason, now, expires_at, created_by],
     )
     .map_err(|e| e.to_string())?;
+    invalidate_ip_list_cache();
 
     Ok(IpBlacklistEntry {
         id,
@@ -408,6 +554,7 @@ pub fn add_to_blacklist(
         expires_at,
         created_by: created_by.to_string(),
         hit_count: 0,
+        evidence: None,
     })
 }
 
@@ -417,6 +564,7 @@ pub fn remove_from_blacklist(id: &str) -> Result<(), String> {
 
     conn.execute("DELETE FROM ip_blacklist WHERE id = ?1", [id])
         .map_err(|e| e.to_string())?;
+    invalidate_ip_list_cache();
 
     Ok(())
 }
@@ -427,7 +575,7 @@ pub fn get_blacklist() -> Result<Vec<IpBlacklistEntry>, String> {
 
     let mut stmt = conn
         .prepare(
-            "SELECT id, ip_pattern, reason, created_at, expires_at, created_by, hit_count
+            "SELECT id, ip_pattern, reason, created_at, expires_at, created_by, hit_count, evidence
              FROM ip_blacklist
              ORDER BY created_at DESC",
         )
@@ -443,6 +591,7 @@ pub fn get_blacklist() -> Result<Vec<IpBlacklistEntry>, String> {
                 expires_at: row.get(4)?,
                 created_by: row.get(5)?,
                 hit_count: row.get(6)?,
+                evidence: row.get(7)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -460,97 +609,136 @@ pub fn is_ip_in_blacklist(ip: &str) -> Result<bool, String> {
 }
 
 /// 获取 IP 对应的黑名单条目（如果存在）
+/// 基于内存前缀树做最长前缀匹配 (支持 IPv4 / IPv6)，列表变更时自动重建
 pub fn get_blacklist_entry_for_ip(ip: &str) -> Result<Option<IpBlacklistEntry>, String> {
-    let conn = connect_db()?;
     let now = chrono::Utc::now().timestamp();
 
-    // 清理过期的黑名单条目
-    let _ = conn.execute(
-        "DELETE FROM ip_blacklist WHERE expires_at IS NOT NULL AND expires_at < ?1",
-        [now],
-    );
-
-    // 精确匹配
-    let entry_result = conn.query_row(
-        "SELECT id, ip_pattern, reason, created_at, expires_at, created_by, hit_count
-         FROM ip_blacklist WHERE ip_pattern = ?1",
-        [ip],
-        |row| {
-            Ok(IpBlacklistEntry {
-                id: row.get(0)?,
-                ip_pattern: row.get(1)?,
-                reason: row.get(2)?,
-                created_at: row.get(3)?,
-                expires_at: row.get(4)?,
-                created_by: row.get(5)?,
-                hit_count: row.get(6)?,
-            })
-        },
-    );
+    // 命中已过期条目时清理后重试一次 (可能存在更短的有效前缀)
+    for _ in 0..2 {
+        let matched = {
+            let index = blacklist_index()?;
+            index.lookup(ip).cloned()
+        };
+        let Some(entry) = matched else {
+            return Ok(None);
+        };
+
+        let conn = connect_db()?;
+        if entry.expires_at.is_some_and(|expires_at| expires_at < now) {
+            // 清理过期的黑名单条目
+            let _ = conn.execute(
+                "DELETE FROM ip_blacklist WHERE expires_at IS NOT NULL AND expires_at < ?1",
+                [now],
+            );
+            invalidate_ip_list_cache();
+            continue;
+        }
 
-    if let Ok(entry) = entry_result {
         // 增加命中计数
         let _ = conn.execute(
-            "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE ip_pattern = ?1",
-            [ip],
+            "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE id = ?1",
+            [&entry.id],
         );
         return Ok(Some(entry));
     }
 
-    // CIDR 匹配
-    let entries = get_blacklist()?;
-    for entry in entries {
-        if entry.ip_pattern.contains('/') {
-            if cidr_match(ip, &entry.ip_pattern) {
-                // 增加命中计数
-                let _ = conn.execute(
-                    "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE id = ?1",
-                    [&entry.id],
-                );
-                return Ok(Some(entry));
-            }
-        }
-    }
-
     Ok(None)
 }
 
-/// 简单的 CIDR 匹配
-fn cidr_match(ip: &str, cidr: &str) -> bool {
-    let parts: Vec<&str> = cidr.split('/').collect();
-    if parts.len() != 2 {
-        return false;
-    }
+// ============================================================================
+// 自动封禁
+// ============================================================================
 
-    let network = parts[0];
-    let prefix_len: u8 = match parts[1].parse() {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
+/// 回溯期内某 IP 的自动封禁次数
+pub fn count_auto_bans_since(ip: &str, since: i64) -> Result<i64, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT COUNT(*) FROM ip_ban_history WHERE client_ip = ?1 AND created_at >= ?2",
+        params![ip, since],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
 
-    let ip_parts: Vec<u8> = ip
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    let net_parts: Vec<u8> = network
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    if ip_parts.len() != 4 || net_parts.len() != 4 {
-        return false;
-    }
+/// 写入自动封禁: 记录历史并创建/延长 created_by = "auto" 的黑名单条目
+/// 已存在的手动条目不会被覆盖
+pub fn add_auto_ban(record: &IpBanRecord) -> Result<(), String> {
+    let mut conn = connect_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    let ip_u32 = u32::from_be_bytes([ip_parts[0], ip_parts[1], ip_parts[2], ip_parts[3]]);
-    let net_u32 = u32::from_be_bytes([net_parts[0], net_parts[1], net_parts[2], net_parts[3]]);
+    tx.execute(
+        "INSERT INTO ip_ban_history (id, client_ip, signal, reason, evidence, offence, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            record.id,
+            record.client_ip,
+            record.signal,
+            record.reason,
+            record.evidence,
+            record.offence,
+            record.created_at,
+            record.expires_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
 
-    let mask = if prefix_len == 0 {
-        0
-    } else {
-        !0u32 << (32 - prefix_len)
-    };
+    tx.execute(
+        "INSERT INTO ip_blacklist (id, ip_pattern, reason, created_at, expires_at, created_by, hit_count, evidence)
+         VALUES (?1, ?2, ?3, ?4, ?5, 'auto', 0, ?6)
+         ON CONFLICT(ip_pattern) DO UPDATE SET
+            reason = excluded.reason,
+            expires_at = MAX(COALESCE(ip_blacklist.expires_at, 0), excluded.expires_at),
+            evidence = excluded.evidence
+         WHERE ip_blacklist.created_by = 'auto'",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            record.client_ip,
+            record.reason,
+            record.created_at,
+            record.expires_at,
+            record.evidence
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    invalidate_ip_list_cache();
+    Ok(())
+}
 
-    (ip_u32 & mask) == (net_u32 & mask)
+/// 查询自动封禁历史 (按时间倒序)
+pub fn get_ban_history(ip: Option<&str>, limit: usize) -> Result<Vec<IpBanRecord>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, client_ip, signal, reason, evidence, offence, created_at, expires_at
+             FROM ip_ban_history
+             WHERE (?1 IS NULL OR client_ip = ?1)
+             ORDER BY created_at DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![ip, limit as i64], |row| {
+            Ok(IpBanRecord {
+                id: row.get(0)?,
+                client_ip: row.get(1)?,
+                signal: row.get(2)?,
+                reason: row.get(3)?,
+                evidence: row.get(4)?,
+                offence: row.get(5)?,
+                created_at: row.get(6)?,
+                expires_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut records = Vec::new();
+    for r in rows {
+        records.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(records)
 }
 
 // ============================================================================
@@ -570,6 +758,7 @@ pub fn add_to_whitelist(ip_pattern: &str, description: Option<&str>) -> Result<I
         params![id, ip_pattern, description, now],
     )
     .map_err(|e| e.to_string())?;
+    invalidate_ip_list_cache();
 
     Ok(IpWhitelistEntry {
         id,
@@ -585,6 +774,7 @@ pub fn remove_from_whitelist(id: &str) -> Result<(), String> {
 
     conn.execute("DELETE FROM ip_whitelist WHERE id = ?1", [id])
         .map_err(|e| e.to_string())?;
+    invalidate_ip_list_cache();
 
     Ok(())
 }
@@ -621,32 +811,71 @@ pub fn get_whitelist() -> Result<Vec<IpWhitelistEntry>, String> {
 
 /// 检查 IP 是否在白名单中
 pub fn is_ip_in_whitelist(ip: &str) -> Result<bool, String> {
-    let conn = connect_db()?;
+    Ok(whitelist_index()?.lookup(ip).is_some())
+}
 
-    // 精确匹配
-    let count: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM ip_whitelist WHERE ip_pattern = ?1",
-            [ip],
-            |row| row.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+// ============================================================================
+// 黑白名单内存索引
+// ============================================================================
 
-    if count > 0 {
-        return Ok(true);
-    }
+#[derive(Default)]
+struct IpListCache {
+    /// 每次失效递增，重建期间发生失效时丢弃重建结果
+    generation: u64,
+    blacklist: Option<Arc<IpRuleIndex<IpBlacklistEntry>>>,
+    whitelist: Option<Arc<IpRuleIndex<()>>>,
+}
 
-    // CIDR 匹配
-    let entries = get_whitelist()?;
-    for entry in entries {
-        if entry.ip_pattern.contains('/') {
-            if cidr_match(ip, &entry.ip_pattern) {
-                return Ok(true);
-            }
+static IP_LIST_CACHE: Lazy<RwLock<IpListCache>> =
+    Lazy::new(|| RwLock::new(IpListCache::default()));
+
+/// 黑白名单变更后使内存索引失效 (下次查询时从数据库重建)
+pub fn invalidate_ip_list_cache() {
+    let mut cache = IP_LIST_CACHE.write();
+    cache.generation = cache.generation.wrapping_add(1);
+    cache.blacklist = None;
+    cache.whitelist = None;
+}
+
+fn blacklist_index() -> Result<Arc<IpRuleIndex<IpBlacklistEntry>>, String> {
+    let generation = {
+        let cache = IP_LIST_CACHE.read();
+        if let Some(index) = cache.blacklist.clone() {
+            return Ok(index);
         }
+        cache.generation
+    };
+    let mut index = IpRuleIndex::default();
+    for entry in get_blacklist()? {
+        let pattern = entry.ip_pattern.clone();
+        index.insert(&pattern, entry);
     }
+    let index = Arc::new(index);
+    let mut cache = IP_LIST_CACHE.write();
+    if cache.generation == generation {
+        cache.blacklist = Some(index.clone());
+    }
+    Ok(index)
+}
 
-    Ok(false)
+fn whitelist_index() -> Result<Arc<IpRuleIndex<()>>, String> {
+    let generation = {
+        let cache = IP_LIST_CACHE.read();
+        if let Some(index) = cache.whitelist.clone() {
+            return Ok(index);
+        }
+        cache.generation
+    };
+    let mut index = IpRuleIndex::default();
+    for entry in get_whitelist()? {
+        index.insert(&entry.ip_pattern, ());
+    }
+    let index = Arc::new(index);
+    let mut cache = IP_LIST_CACHE.write();
+    if cache.generation == generation {
+        cache.whitelist = Some(index.clone());
+    }
+    Ok(index)
 }
 
 /// 清空所有 IP 访问日志
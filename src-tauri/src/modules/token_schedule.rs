@@ -0,0 +1,331 @@
+// 用户令牌访问时间表
+// 替代固定 UTC+8 的单一宵禁窗口: 支持 IANA 时区、按星期配置多个允许窗口、
+// 日期区间例外 (节假日)，以及在允许时段之外 "降级到低成本模型" 而非直接拒绝。
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// 旧版宵禁固定使用的时区
+const LEGACY_CURFEW_TIMEZONE: &str = "Asia/Shanghai";
+
+/// 允许时段之外的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleMode {
+    /// 拒绝请求 (403)
+    #[default]
+    Deny,
+    /// 放行但强制路由到 `downgrade_model`
+    Downgrade,
+}
+
+/// 允许访问的时间窗口 (本地时间, [start, end))
+/// start > end 表示跨午夜 (例如 22:00-06:00，属于 start 所在的那一天)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleWindow {
+    /// 适用的星期 ("mon", "tue", ...)，为空表示每天
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"
+    pub end: String,
+}
+
+/// 日期区间例外 (本地日期，闭区间)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleException {
+    /// "YYYY-MM-DD"
+    pub start_date: String,
+    /// "YYYY-MM-DD"，为空表示与 start_date 相同
+    #[serde(default)]
+    pub end_date: Option<String>,
+    /// true = 全天允许 (加班日)，false = 全天受限 (节假日)
+    #[serde(default)]
+    pub allow: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// 令牌访问时间表
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenAccessSchedule {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// IANA 时区 (例如 "Asia/Ho_Chi_Minh", "Europe/Berlin")
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// 允许访问的窗口，为空表示全天允许 (仅受例外影响)
+    #[serde(default)]
+    pub windows: Vec<ScheduleWindow>,
+    #[serde(default)]
+    pub exceptions: Vec<ScheduleException>,
+    #[serde(default)]
+    pub mode: ScheduleMode,
+    /// Downgrade 模式下使用的模型
+    #[serde(default)]
+    pub downgrade_model: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+/// 时间表判定结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleDecision {
+    Allow,
+    Deny(String),
+    Downgrade { model: String, reason: String },
+}
+
+impl TokenAccessSchedule {
+    /// 将旧版宵禁 (北京时间禁止 start..end) 转换为等价的允许窗口 end..start
+    pub fn from_legacy_curfew(curfew_start: &str, curfew_end: &str) -> Self {
+        Self {
+            enabled: true,
+            timezone: LEGACY_CURFEW_TIMEZONE.to_string(),
+            windows: vec![ScheduleWindow {
+                days: Vec::new(),
+                start: curfew_end.to_string(),
+                end: curfew_start.to_string(),
+            }],
+            exceptions: Vec::new(),
+            mode: ScheduleMode::Deny,
+            downgrade_model: None,
+        }
+    }
+
+    /// 校验时间表配置
+    pub fn validate(&self) -> Result<(), String> {
+        parse_timezone(&self.timezone)?;
+        for window in &self.windows {
+            parse_time(&window.start)?;
+            parse_time(&window.end)?;
+            for day in &window.days {
+                parse_weekday(day)?;
+            }
+        }
+        for exception in &self.exceptions {
+            let (start, end) = exception_range(exception)?;
+            if start > end {
+                return Err(format!(
+                    "Exception start date {} is after end date {}",
+                    start, end
+                ));
+            }
+        }
+        if self.mode == ScheduleMode::Downgrade
+            && self.downgrade_model.as_deref().unwrap_or("").is_empty()
+        {
+            return Err("Downgrade mode requires downgrade_model".to_string());
+        }
+        Ok(())
+    }
+
+    /// 按当前时间判定是否允许访问
+    pub fn evaluate(&self, now: DateTime<Utc>) -> ScheduleDecision {
+        if !self.enabled {
+            return ScheduleDecision::Allow;
+        }
+
+        // 无效时区回退到 UTC (保存时已校验，这里只做防御)
+        let tz = parse_timezone(&self.timezone).unwrap_or(Tz::UTC);
+        let local = now.with_timezone(&tz);
+        let date = local.date_naive();
+        let time = local.time();
+        let local_desc = format!(
+            "{} {} ({})",
+            date.weekday(),
+            local.format("%H:%M"),
+            self.timezone
+        );
+
+        let allowed = match self.exception_for(date) {
+            Some(exception) => exception.allow,
+            None => self.windows.is_empty() || self.in_any_window(date, time),
+        };
+        if allowed {
+            return ScheduleDecision::Allow;
+        }
+
+        let reason = format!(
+            "Service is not available for this token at the current time: {}.",
+            local_desc
+        );
+        match (self.mode, self.downgrade_model.as_deref()) {
+            (ScheduleMode::Downgrade, Some(model)) if !model.is_empty() => {
+                ScheduleDecision::Downgrade {
+                    model: model.to_string(),
+                    reason,
+                }
+            }
+            _ => ScheduleDecision::Deny(reason),
+        }
+    }
+
+    fn exception_for(&self, date: NaiveDate) -> Option<&ScheduleException> {
+        self.exceptions.iter().find(|e| {
+            exception_range(e)
+                .map(|(start, end)| date >= start && date <= end)
+                .unwrap_or(false)
+        })
+    }
+
+    fn in_any_window(&self, date: NaiveDate, time: NaiveTime) -> bool {
+        let yesterday = date - Duration::days(1);
+        self.windows.iter().any(|w| {
+            let (Ok(start), Ok(end)) = (parse_time(&w.start), parse_time(&w.end)) else {
+                return false;
+            };
+            if start <= end {
+                window_applies(w, date.weekday()) && time >= start && time < end
+            } else {
+                // 跨午夜: 当天 start 之后，或前一天窗口延续到今天 end 之前
+                (window_applies(w, date.weekday()) && time >= start)
+                    || (window_applies(w, yesterday.weekday()) && time < end)
+            }
+        })
+    }
+}
+
+fn window_applies(window: &ScheduleWindow, weekday: Weekday) -> bool {
+    window.days.is_empty()
+        || window
+            .days
+            .iter()
+            .any(|d| parse_weekday(d).map(|w| w == weekday).unwrap_or(false))
+}
+
+fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("Unknown time zone '{}'", name))
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", value))
+}
+
+fn parse_weekday(value: &str) -> Result<Weekday, String> {
+    value
+        .trim()
+        .parse::<Weekday>()
+        .map_err(|_| format!("Invalid weekday '{}'", value))
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+fn exception_range(exception: &ScheduleException) -> Result<(NaiveDate, NaiveDate), String> {
+    let start = parse_date(&exception.start_date)?;
+    let end = match exception.end_date.as_deref() {
+        Some(end) if !end.is_empty() => parse_date(end)?,
+        _ => start,
+    };
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn weekday_schedule(mode: ScheduleMode) -> TokenAccessSchedule {
+        TokenAccessSchedule {
+            enabled: true,
+            timezone: "Asia/Ho_Chi_Minh".to_string(),
+            windows: vec![
+                ScheduleWindow {
+                    days: vec!["mon".into(), "tue".into(), "wed".into(), "thu".into(), "fri".into()],
+                    start: "08:00".into(),
+                    end: "12:00".into(),
+                },
+                ScheduleWindow {
+                    days: vec!["mon".into(), "tue".into(), "wed".into(), "thu".into(), "fri".into()],
+                    start: "13:30".into(),
+                    end: "18:00".into(),
+                },
+            ],
+            exceptions: vec![ScheduleException {
+                start_date: "2025-04-30".into(),
+                end_date: Some("2025-05-01".into()),
+                allow: false,
+                note: Some("Reunification Day".into()),
+            }],
+            mode,
+            downgrade_model: Some("gemini-3-flash".into()),
+        }
+    }
+
+    /// Asia/Ho_Chi_Minh = UTC+7
+    fn vn(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap() - Duration::hours(7)
+    }
+
+    #[test]
+    fn test_multiple_windows_per_weekday() {
+        let schedule = weekday_schedule(ScheduleMode::Deny);
+        // 2025-04-28 周一
+        assert_eq!(schedule.evaluate(vn(2025, 4, 28, 9, 0)), ScheduleDecision::Allow);
+        assert!(matches!(schedule.evaluate(vn(2025, 4, 28, 12, 30)), ScheduleDecision::Deny(_)));
+        assert_eq!(schedule.evaluate(vn(2025, 4, 28, 17, 59)), ScheduleDecision::Allow);
+        assert!(matches!(schedule.evaluate(vn(2025, 4, 28, 18, 0)), ScheduleDecision::Deny(_)));
+        // 2025-04-27 周日
+        assert!(matches!(schedule.evaluate(vn(2025, 4, 27, 9, 0)), ScheduleDecision::Deny(_)));
+    }
+
+    #[test]
+    fn test_exception_and_downgrade_mode() {
+        let schedule = weekday_schedule(ScheduleMode::Downgrade);
+        // 2025-04-30 周三为节假日: 工作时间内也降级
+        match schedule.evaluate(vn(2025, 4, 30, 9, 0)) {
+            ScheduleDecision::Downgrade { model, reason } => {
+                assert_eq!(model, "gemini-3-flash");
+                assert!(reason.contains("Asia/Ho_Chi_Minh"));
+            }
+            other => panic!("unexpected decision: {:?}", other),
+        }
+        assert_eq!(schedule.evaluate(vn(2025, 5, 2, 9, 0)), ScheduleDecision::Allow);
+    }
+
+    #[test]
+    fn test_legacy_curfew_conversion() {
+        // 旧版: 北京时间 23:00-06:00 禁止访问
+        let schedule = TokenAccessSchedule::from_legacy_curfew("23:00", "06:00");
+        let bj = |h: u32| Utc.with_ymd_and_hms(2025, 1, 6, h, 0, 0).unwrap() - Duration::hours(8);
+        assert_eq!(schedule.evaluate(bj(10)), ScheduleDecision::Allow);
+        assert!(matches!(schedule.evaluate(bj(23)), ScheduleDecision::Deny(_)));
+        assert!(matches!(schedule.evaluate(bj(3)), ScheduleDecision::Deny(_)));
+
+        // 旧版: 09:00-18:00 禁止 -> 允许窗口 18:00-09:00 跨午夜
+        let schedule = TokenAccessSchedule::from_legacy_curfew("09:00", "18:00");
+        assert!(matches!(schedule.evaluate(bj(12)), ScheduleDecision::Deny(_)));
+        assert_eq!(schedule.evaluate(bj(20)), ScheduleDecision::Allow);
+        assert_eq!(schedule.evaluate(bj(2)), ScheduleDecision::Allow);
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_config() {
+        let mut schedule = weekday_schedule(ScheduleMode::Deny);
+        assert!(schedule.validate().is_ok());
+
+        schedule.timezone = "Mars/Olympus".into();
+        assert!(schedule.validate().is_err());
+
+        let mut schedule = weekday_schedule(ScheduleMode::Downgrade);
+        schedule.downgrade_model = None;
+        assert!(schedule.validate().is_err());
+
+        let mut schedule = weekday_schedule(ScheduleMode::Deny);
+        schedule.windows[0].start = "25:00".into();
+        assert!(schedule.validate().is_err());
+    }
+}
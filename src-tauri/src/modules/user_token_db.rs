@@ -8,7 +8,9 @@ use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use uuid::Uuid;
-use chrono::{Utc, Local, Timelike, FixedOffset};
+use chrono::{Utc, Local};
+
+use crate::modules::token_schedule::{ScheduleDecision, TokenAccessSchedule};
 
 /// 用户令牌结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,13 +23,104 @@ pub struct UserToken {
     pub expires_type: String,      // "day", "week", "month", "never"
     pub expires_at: Option<i64>,
     pub max_ips: i32,              // 0 = unlimited
-    pub curfew_start: Option<String>, // "HH:MM" 宵禁开始时间
-    pub curfew_end: Option<String>,   // "HH:MM" 宵禁结束时间
+    pub curfew_start: Option<String>, // "HH:MM" 宵禁开始时间 (旧版，北京时间)
+    pub curfew_end: Option<String>,   // "HH:MM" 宵禁结束时间 (旧版，北京时间)
     pub created_at: i64,
     pub updated_at: i64,
     pub last_used_at: Option<i64>,
     pub total_requests: i64,
     pub total_tokens_used: i64,
+    /// 用量限制 (预算 / RPM / 并发)
+    #[serde(default)]
+    pub limits: TokenLimits,
+    /// 模型访问策略与路由覆盖
+    #[serde(default)]
+    pub model_policy: TokenModelPolicy,
+    /// 访问时间表 (优先于旧版宵禁字段)
+    #[serde(default)]
+    pub access_schedule: Option<TokenAccessSchedule>,
+}
+
+impl UserToken {
+    /// 生效的访问时间表: 优先使用新版时间表，否则由旧版宵禁字段转换
+    pub fn effective_access_schedule(&self) -> Option<TokenAccessSchedule> {
+        if let Some(schedule) = &self.access_schedule {
+            return Some(schedule.clone());
+        }
+        match (&self.curfew_start, &self.curfew_end) {
+            (Some(start), Some(end)) if !start.is_empty() && !end.is_empty() => {
+                Some(TokenAccessSchedule::from_legacy_curfew(start, end))
+            }
+            _ => None,
+        }
+    }
+
+    /// 当前时间处于受限时段且时间表为降级模式时，返回降级目标模型
+    pub fn scheduled_downgrade_model(&self) -> Option<String> {
+        match self.effective_access_schedule()?.evaluate(Utc::now()) {
+            ScheduleDecision::Downgrade { model, .. } => Some(model),
+            _ => None,
+        }
+    }
+}
+
+/// 令牌级模型策略
+/// 模式支持 `*` 通配符 (与模型映射规则一致)，拒绝列表优先于允许列表
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenModelPolicy {
+    /// 允许的模型模式，为空表示不限制
+    pub allowed_models: Vec<String>,
+    /// 拒绝的模型模式
+    pub denied_models: Vec<String>,
+    /// 强制映射的目标模型 (该令牌的所有请求都路由到此模型)
+    pub forced_model: Option<String>,
+    /// 限定可用的账号池 (账号 ID 或邮箱)，为空表示使用全部账号
+    pub account_pool: Vec<String>,
+    /// 优先使用的账号 ID (覆盖全局固定账号)
+    pub preferred_account_id: Option<String>,
+}
+
+impl TokenModelPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.allowed_models.is_empty()
+            && self.denied_models.is_empty()
+            && self.forced_model.as_deref().unwrap_or("").is_empty()
+            && self.account_pool.is_empty()
+            && self.preferred_account_id.as_deref().unwrap_or("").is_empty()
+    }
+}
+
+/// 令牌用量限制，所有字段 0 = 不限制
+/// Token 预算按 UTC 自然日 / 自然周 (周一开始) / 自然月统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenLimits {
+    pub daily_token_limit: i64,
+    pub weekly_token_limit: i64,
+    pub monthly_token_limit: i64,
+    /// 每分钟请求数上限
+    pub rpm_limit: i32,
+    /// 最大并发请求数
+    pub max_concurrency: i32,
+}
+
+impl TokenLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.daily_token_limit <= 0
+            && self.weekly_token_limit <= 0
+            && self.monthly_token_limit <= 0
+            && self.rpm_limit <= 0
+            && self.max_concurrency <= 0
+    }
+}
+
+/// 各统计周期内已使用的 Token 数
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsageWindow {
+    pub daily: i64,
+    pub weekly: i64,
+    pub monthly: i64,
 }
 
 /// 令牌 IP 绑定结构体
@@ -42,6 +135,27 @@ pub struct TokenIpBinding {
     pub user_agent: Option<String>,
 }
 
+/// 令牌客户端证书 (mTLS)，私钥不落库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenClientCert {
+    /// 证书序列号 (hex)
+    pub serial: String,
+    pub token_id: String,
+    /// DER 证书的 SHA-256 指纹 (hex)
+    pub fingerprint: String,
+    pub common_name: String,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    pub revoked_at: Option<i64>,
+    pub revoke_reason: Option<String>,
+}
+
+impl TokenClientCert {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
 /// 令牌使用日志结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenUsageLog {
@@ -105,6 +219,13 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN last_used_at INTEGER", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_start TEXT", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_end TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN daily_token_limit INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN weekly_token_limit INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN monthly_token_limit INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN rpm_limit INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN max_concurrency INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN model_policy TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN access_schedule TEXT", []);
 
     // 创建 token_ip_bindings 表
     conn.execute(
@@ -137,10 +258,28 @@ pub fn init_db() -> Result<(), String> {
         )",
         [],
     ).map_err(|e| format!("Failed to create token_usage_logs table: {}", e))?;
+
+    // [NEW] 创建 token_client_certs 表 (mTLS 客户端证书与吊销列表)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS token_client_certs (
+            serial TEXT PRIMARY KEY,
+            token_id TEXT NOT NULL,
+            fingerprint TEXT UNIQUE NOT NULL,
+            common_name TEXT NOT NULL,
+            issued_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            revoked_at INTEGER,
+            revoke_reason TEXT,
+            FOREIGN KEY(token_id) REFERENCES user_tokens(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create token_client_certs table: {}", e))?;
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_client_certs_token_id ON token_client_certs(token_id)", []);
     
     // 创建索引
     let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_usage_logs_token_id ON token_usage_logs(token_id)", []);
     let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_usage_logs_request_time ON token_usage_logs(request_time)", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_usage_logs_token_time ON token_usage_logs(token_id, request_time)", []);
 
     // [FIX Issue #1719] 数据清洗：修复旧版本升级导致的 NULL 字段
     // 这些字段在旧版本中可能不存在，ALTER TABLE 添加后默认为 NULL，导致反序列化失败
@@ -192,6 +331,9 @@ pub fn create_token(
         last_used_at: None,
         total_requests: 0,
         total_tokens_used: 0,
+        limits: TokenLimits::default(),
+        model_policy: TokenModelPolicy::default(),
+        access_schedule: None,
     };
 
     conn.execute(
@@ -244,6 +386,9 @@ pub fn list_tokens() -> Result<Vec<UserToken>, String> {
             last_used_at: row.get("last_used_at").unwrap_or(None),
             total_requests: row.get("total_requests").unwrap_or(0),
             total_tokens_used: row.get("total_tokens_used").unwrap_or(0),
+            limits: limits_from_row(row),
+            model_policy: model_policy_from_row(row),
+            access_schedule: access_schedule_from_row(row),
         })
     }).map_err(|e| format!("Failed to query tokens: {}", e))?;
 
@@ -278,6 +423,9 @@ pub fn get_token_by_id(id: &str) -> Result<Option<UserToken>, String> {
             last_used_at: row.get("last_used_at")?,
             total_requests: row.get("total_requests")?,
             total_tokens_used: row.get("total_tokens_used")?,
+            limits: limits_from_row(row),
+            model_policy: model_policy_from_row(row),
+            access_schedule: access_schedule_from_row(row),
         })
     }).optional().map_err(|e| format!("Failed to query token: {}", e))?;
     
@@ -307,6 +455,9 @@ pub fn get_token_by_value(token: &str) -> Result<Option<UserToken>, String> {
             last_used_at: row.get("last_used_at")?,
             total_requests: row.get("total_requests")?,
             total_tokens_used: row.get("total_tokens_used")?,
+            limits: limits_from_row(row),
+            model_policy: model_policy_from_row(row),
+            access_schedule: access_schedule_from_row(row),
         })
     }).optional().map_err(|e| format!("Failed to query token: {}", e))?;
     
@@ -378,6 +529,122 @@ pub fn update_token(
     Ok(())
 }
 
+/// 读取限制字段 (旧数据库中可能为 NULL，按不限制处理)
+fn limits_from_row(row: &rusqlite::Row) -> TokenLimits {
+    TokenLimits {
+        daily_token_limit: row.get::<_, Option<i64>>("daily_token_limit").ok().flatten().unwrap_or(0),
+        weekly_token_limit: row.get::<_, Option<i64>>("weekly_token_limit").ok().flatten().unwrap_or(0),
+        monthly_token_limit: row.get::<_, Option<i64>>("monthly_token_limit").ok().flatten().unwrap_or(0),
+        rpm_limit: row.get::<_, Option<i32>>("rpm_limit").ok().flatten().unwrap_or(0),
+        max_concurrency: row.get::<_, Option<i32>>("max_concurrency").ok().flatten().unwrap_or(0),
+    }
+}
+
+/// 读取模型策略 (JSON 文本，缺失或解析失败时视为无策略)
+fn model_policy_from_row(row: &rusqlite::Row) -> TokenModelPolicy {
+    row.get::<_, Option<String>>("model_policy")
+        .ok()
+        .flatten()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// 读取访问时间表 (JSON 文本)
+fn access_schedule_from_row(row: &rusqlite::Row) -> Option<TokenAccessSchedule> {
+    row.get::<_, Option<String>>("access_schedule")
+        .ok()
+        .flatten()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// 更新令牌访问时间表 (None 表示清除，回退到旧版宵禁字段)
+pub fn update_token_access_schedule(id: &str, schedule: Option<&TokenAccessSchedule>) -> Result<(), String> {
+    let json = match schedule {
+        Some(schedule) => {
+            schedule.validate()?;
+            Some(serde_json::to_string(schedule).map_err(|e| e.to_string())?)
+        }
+        None => None,
+    };
+
+    let conn = connect_db()?;
+    let now = Utc::now().timestamp();
+    conn.execute(
+        "UPDATE user_tokens SET access_schedule = ?1, updated_at = ?2 WHERE id = ?3",
+        params![json, now, id],
+    ).map_err(|e| format!("Failed to update token access schedule: {}", e))?;
+    Ok(())
+}
+
+/// 更新令牌模型策略
+pub fn update_token_model_policy(id: &str, policy: &TokenModelPolicy) -> Result<(), String> {
+    let conn = connect_db()?;
+    let now = Utc::now().timestamp();
+    let json = if policy.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(policy).map_err(|e| e.to_string())?)
+    };
+    conn.execute(
+        "UPDATE user_tokens SET model_policy = ?1, updated_at = ?2 WHERE id = ?3",
+        params![json, now, id],
+    ).map_err(|e| format!("Failed to update token model policy: {}", e))?;
+    Ok(())
+}
+
+/// 更新令牌用量限制
+pub fn update_token_limits(id: &str, limits: &TokenLimits) -> Result<(), String> {
+    let conn = connect_db()?;
+    let now = Utc::now().timestamp();
+    conn.execute(
+        "UPDATE user_tokens SET
+            daily_token_limit = ?1,
+            weekly_token_limit = ?2,
+            monthly_token_limit = ?3,
+            rpm_limit = ?4,
+            max_concurrency = ?5,
+            updated_at = ?6
+        WHERE id = ?7",
+        params![
+            limits.daily_token_limit.max(0),
+            limits.weekly_token_limit.max(0),
+            limits.monthly_token_limit.max(0),
+            limits.rpm_limit.max(0),
+            limits.max_concurrency.max(0),
+            now,
+            id
+        ],
+    ).map_err(|e| format!("Failed to update token limits: {}", e))?;
+    Ok(())
+}
+
+/// 统计令牌在各周期起点 (Unix 秒) 之后消耗的 Token 数
+pub fn get_token_usage_since(
+    token_id: &str,
+    day_start: i64,
+    week_start: i64,
+    month_start: i64,
+) -> Result<TokenUsageWindow, String> {
+    let conn = connect_db()?;
+    let earliest = day_start.min(week_start).min(month_start);
+    conn.query_row(
+        "SELECT
+            COALESCE(SUM(CASE WHEN request_time >= ?2 THEN COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0) ELSE 0 END), 0),
+            COALESCE(SUM(CASE WHEN request_time >= ?3 THEN COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0) ELSE 0 END), 0),
+            COALESCE(SUM(CASE WHEN request_time >= ?4 THEN COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0) ELSE 0 END), 0)
+        FROM token_usage_logs
+        WHERE token_id = ?1 AND request_time >= ?5",
+        params![token_id, day_start, week_start, month_start, earliest],
+        |row| {
+            Ok(TokenUsageWindow {
+                daily: row.get(0)?,
+                weekly: row.get(1)?,
+                monthly: row.get(2)?,
+            })
+        },
+    ).map_err(|e| format!("Failed to query token usage: {}", e))
+}
+
 /// 续期令牌
 pub fn renew_token(id: &str, expires_type: &str) -> Result<(), String> {
     let conn = connect_db()?;
@@ -403,6 +670,8 @@ pub fn delete_token(id: &str) -> Result<(), String> {
     let conn = connect_db()?;
     conn.execute("DELETE FROM user_tokens WHERE id = ?1", params![id])
         .map_err(|e| format!("Failed to delete token: {}", e))?;
+    // [NEW] 令牌删除后其客户端证书一并失效
+    let _ = conn.execute("DELETE FROM token_client_certs WHERE token_id = ?1", params![id]);
     Ok(())
 }
 
@@ -536,27 +805,11 @@ pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>
             }
         }
 
-        // 3. 检查宵禁时间 (Curfew)
-        // 逻辑：如果当前北京时间在 start 和 end 之间，则拒绝
-        // 格式：HH:MM
-        // 使用固定 UTC+8 (北京时间)，不依赖服务器本地时区
-        if let (Some(start_str), Some(end_str)) = (&token.curfew_start, &token.curfew_end) {
-            if !start_str.is_empty() && !end_str.is_empty() {
-                let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap();
-                let now_beijing = Utc::now().with_timezone(&beijing_offset);
-                let current_time_str = format!("{:02}:{:02}", now_beijing.hour(), now_beijing.minute());
-
-                // 跨午夜处理: start > end (e.g. 23:00 to 06:00)
-                // 正常: start < end (e.g. 09:00 to 18:00)
-                let is_curfew = if start_str > end_str {
-                    current_time_str >= *start_str || current_time_str < *end_str
-                } else {
-                    current_time_str >= *start_str && current_time_str < *end_str
-                };
-
-                if is_curfew {
-                     return Ok((false, Some(format!("Service is not available between {} and {} Beijing Time (Curfew enabled). Current Beijing time: {}", start_str, end_str, current_time_str))));
-                }
+        // 3. 检查访问时间表 (旧版宵禁字段自动转换为北京时间时间表)
+        // Downgrade 模式不在此拒绝，由鉴权中间件改写为强制映射
+        if let Some(schedule) = token.effective_access_schedule() {
+            if let ScheduleDecision::Deny(reason) = schedule.evaluate(Utc::now()) {
+                return Ok((false, Some(reason)));
             }
         }
 
@@ -567,6 +820,90 @@ pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>
     }
 }
 
+// ===== 客户端证书 (mTLS) =====
+
+fn client_cert_from_row(row: &rusqlite::Row) -> rusqlite::Result<TokenClientCert> {
+    Ok(TokenClientCert {
+        serial: row.get("serial")?,
+        token_id: row.get("token_id")?,
+        fingerprint: row.get("fingerprint")?,
+        common_name: row.get("common_name")?,
+        issued_at: row.get("issued_at")?,
+        expires_at: row.get("expires_at")?,
+        revoked_at: row.get("revoked_at")?,
+        revoke_reason: row.get("revoke_reason")?,
+    })
+}
+
+/// 记录新签发的客户端证书
+pub fn insert_client_cert(cert: &TokenClientCert) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO token_client_certs (
+            serial, token_id, fingerprint, common_name, issued_at, expires_at, revoked_at, revoke_reason
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            cert.serial,
+            cert.token_id,
+            cert.fingerprint,
+            cert.common_name,
+            cert.issued_at,
+            cert.expires_at,
+            cert.revoked_at,
+            cert.revoke_reason,
+        ],
+    ).map_err(|e| format!("Failed to insert client cert: {}", e))?;
+    Ok(())
+}
+
+/// 列出客户端证书 (token_id 为空时列出全部)
+pub fn list_client_certs(token_id: Option<&str>) -> Result<Vec<TokenClientCert>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn.prepare(
+        "SELECT * FROM token_client_certs WHERE (?1 IS NULL OR token_id = ?1) ORDER BY issued_at DESC",
+    ).map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let iter = stmt.query_map(params![token_id], client_cert_from_row)
+        .map_err(|e| format!("Failed to query client certs: {}", e))?;
+
+    let mut certs = Vec::new();
+    for c in iter {
+        certs.push(c.map_err(|e| format!("Failed to parse client cert row: {}", e))?);
+    }
+    Ok(certs)
+}
+
+/// 吊销列表 (按吊销时间倒序)
+pub fn list_revoked_client_certs() -> Result<Vec<TokenClientCert>, String> {
+    let mut certs: Vec<TokenClientCert> = list_client_certs(None)?
+        .into_iter()
+        .filter(|c| c.is_revoked())
+        .collect();
+    certs.sort_by_key(|c| std::cmp::Reverse(c.revoked_at));
+    Ok(certs)
+}
+
+/// 按指纹查询客户端证书
+pub fn get_client_cert_by_fingerprint(fingerprint: &str) -> Result<Option<TokenClientCert>, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT * FROM token_client_certs WHERE fingerprint = ?1",
+        params![fingerprint.to_ascii_lowercase()],
+        client_cert_from_row,
+    ).optional().map_err(|e| format!("Failed to query client cert: {}", e))
+}
+
+/// 吊销客户端证书，返回是否有记录被更新 (已吊销的证书保留原吊销时间)
+pub fn revoke_client_cert(serial: &str, reason: Option<&str>) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let updated = conn.execute(
+        "UPDATE token_client_certs SET revoked_at = ?1, revoke_reason = ?2
+         WHERE serial = ?3 AND revoked_at IS NULL",
+        params![Utc::now().timestamp(), reason, serial.to_ascii_lowercase()],
+    ).map_err(|e| format!("Failed to revoke client cert: {}", e))?;
+    Ok(updated > 0)
+}
+
 /// 获取 IP 关联的用户名 (用于 IP 管理页面)
 /// 返回最近一次使用该 IP 的 Token 所属的用户名
 pub fn get_username_for_ip(ip: &str) -> Result<Option<String>, String> {
@@ -606,4 +943,68 @@ mod tests {
         assert!(fetched.is_ok());
         assert_eq!(fetched.unwrap().unwrap().username, username);
     }
+
+    #[test]
+    fn test_token_limits_and_usage_window() {
+        let _ = init_db();
+
+        let username = format!("BudgetUser_{}", Uuid::new_v4());
+        let token = create_token(username, "never".to_string(), None, 0, None, None, None).unwrap();
+        assert!(token.limits.is_unlimited());
+
+        let limits = TokenLimits {
+            daily_token_limit: 1_000,
+            rpm_limit: 10,
+            max_concurrency: 2,
+            ..Default::default()
+        };
+        update_token_limits(&token.id, &limits).unwrap();
+        assert_eq!(get_token_by_id(&token.id).unwrap().unwrap().limits, limits);
+
+        record_token_usage_and_ip(&token.id, "10.0.0.1", "gemini-3-flash", 100, 50, 200, None).unwrap();
+        record_token_usage_and_ip(&token.id, "10.0.0.1", "gemini-3-flash", 30, 20, 200, None).unwrap();
+
+        let now = Utc::now().timestamp();
+        let usage = get_token_usage_since(&token.id, now - 60, now - 3600, now + 60).unwrap();
+        assert_eq!(usage.daily, 200);
+        assert_eq!(usage.weekly, 200);
+        assert_eq!(usage.monthly, 0);
+
+        let _ = delete_token(&token.id);
+    }
+
+    #[test]
+    fn test_client_cert_revocation() {
+        let _ = init_db();
+
+        let token = create_token(format!("CertUser_{}", Uuid::new_v4()), "never".to_string(), None, 0, None, None, None).unwrap();
+        let serial = Uuid::new_v4().simple().to_string();
+        let fingerprint = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let now = Utc::now().timestamp();
+        insert_client_cert(&TokenClientCert {
+            serial: serial.clone(),
+            token_id: token.id.clone(),
+            fingerprint: fingerprint.clone(),
+            common_name: token.username.clone(),
+            issued_at: now,
+            expires_at: now + 86400,
+            revoked_at: None,
+            revoke_reason: None,
+        }).unwrap();
+
+        let cert = get_client_cert_by_fingerprint(&fingerprint.to_uppercase()).unwrap().unwrap();
+        assert_eq!(cert.token_id, token.id);
+        assert!(!cert.is_revoked());
+        assert_eq!(list_client_certs(Some(&token.id)).unwrap().len(), 1);
+
+        assert!(revoke_client_cert(&serial, Some("lost laptop")).unwrap());
+        assert!(!revoke_client_cert(&serial, None).unwrap());
+        let cert = get_client_cert_by_fingerprint(&fingerprint).unwrap().unwrap();
+        assert!(cert.is_revoked());
+        assert_eq!(cert.revoke_reason.as_deref(), Some("lost laptop"));
+        assert!(list_revoked_client_certs().unwrap().iter().any(|c| c.serial == serial));
+
+        delete_token(&token.id).unwrap();
+        assert!(get_client_cert_by_fingerprint(&fingerprint).unwrap().is_none());
+    }
 }
@@ -0,0 +1,356 @@
+//! Vault Rotation Module
+//! 密钥轮换与旧版密文迁移: 将数据目录中所有保险库密文 (账号文件、配置文件及其内嵌字段、SQLite 文本列)
+//! 重新加密为当前密钥的 v2 格式
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::crypto::{self, ENCRYPTED_PREFIX};
+use crate::utils::vault::{self, KeySource, VAULT_FILE};
+
+/// 串行化轮换，避免并发轮换交错写入
+static ROTATION_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+
+/// 重新加密结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReencryptReport {
+    /// 当前密钥 ID
+    pub kid: String,
+    pub files_rewritten: usize,
+    pub values_reencrypted: usize,
+    pub db_values_reencrypted: usize,
+    /// 无法处理的条目 (如旧密钥不可用)，非空时保留旧密钥以便重试
+    pub errors: Vec<String>,
+    /// 需要运维处理的提示 (如重启前更新口令)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// 轮换到新密钥并重新加密全部数据；全部成功后丢弃旧密钥
+pub fn rotate_key(source: KeySource, passphrase: Option<&str>) -> Result<ReencryptReport, String> {
+    let _guard = ROTATION_LOCK.lock();
+    vault::begin_rotation(source, passphrase)?;
+    let mut report = reencrypt_data_dir(&crate::modules::account::get_data_dir()?)?;
+    if source == KeySource::Passphrase {
+        // 启动时只从环境变量取得口令，重启前须将其更新为新口令
+        let warning = format!(
+            "Set {} (or {}) to the new passphrase before the next start",
+            vault::PASSPHRASE_ENV,
+            vault::PASSPHRASE_FILE_ENV
+        );
+        tracing::warn!("[Vault] {}", warning);
+        report.warnings.push(warning);
+    }
+    if report.errors.is_empty() {
+        vault::finish_rotation()?;
+    } else {
+        if let Some(old) = vault::retired_passphrase_kid() {
+            report.warnings.push(format!(
+                "Retired passphrase key {} is still needed; provide its passphrase via {} on restart",
+                old,
+                vault::OLD_PASSPHRASE_ENV
+            ));
+        }
+        tracing::warn!(
+            "[Vault] Rotation finished with {} error(s); previous key retained",
+            report.errors.len()
+        );
+    }
+    Ok(report)
+}
+
+/// 迁移旧版 (`ag_enc_` 固定 nonce) 密文到当前密钥，新建保险库后调用一次
+pub fn migrate_legacy() -> Result<ReencryptReport, String> {
+    let _guard = ROTATION_LOCK.lock();
+    reencrypt_data_dir(&crate::modules::account::get_data_dir()?)
+}
+
+fn reencrypt_data_dir(data_dir: &Path) -> Result<ReencryptReport, String> {
+    let mut report = ReencryptReport {
+        kid: vault::current_kid().unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let mut files = list_files(data_dir, "json");
+    files.extend(list_files(&data_dir.join("accounts"), "json"));
+    // 迁移到账号存储后保留的旧版账号文件
+    files.extend(list_files(&data_dir.join("accounts.migrated"), "json"));
+    for path in files {
+        if path.file_name().is_some_and(|n| n == VAULT_FILE) {
+            continue;
+        }
+        match reencrypt_file(&path, &mut report.values_reencrypted) {
+            Ok(true) => report.files_rewritten += 1,
+            Ok(false) => {}
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    for path in list_files(data_dir, "db") {
+        match reencrypt_db(&path) {
+            Ok(count) => report.db_values_reencrypted += count,
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    tracing::info!(
+        "[Vault] Re-encrypted {} file(s), {} value(s), {} DB value(s) under key {}",
+        report.files_rewritten,
+        report.values_reencrypted,
+        report.db_values_reencrypted,
+        report.kid
+    );
+    Ok(report)
+}
+
+fn list_files(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == ext))
+        .collect();
+    files.sort();
+    files
+}
+
+/// 重新加密单个值 (已是当前密钥时返回 None)
+fn reencrypt_value(value: &str) -> Result<Option<String>, String> {
+    if !value.starts_with(ENCRYPTED_PREFIX) || crypto::is_current_ciphertext(value) {
+        return Ok(None);
+    }
+    let plaintext = crypto::decrypt_string(value)?;
+    crypto::encrypt_string(&plaintext).map(Some)
+}
+
+/// 递归重新加密 JSON 中的密文字段
+fn reencrypt_json(value: &mut serde_json::Value, count: &mut usize) -> Result<bool, String> {
+    let mut changed = false;
+    match value {
+        serde_json::Value::String(s) => {
+            if let Some(new) = reencrypt_value(s)? {
+                *s = new;
+                *count += 1;
+                changed = true;
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                changed |= reencrypt_json(item, count)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (_, item) in map.iter_mut() {
+                changed |= reencrypt_json(item, count)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(changed)
+}
+
+/// 重新加密文件内容: 整体加密的文件先解密，再处理内嵌密文字段，最后按原格式写回
+/// 返回是否写回了文件
+fn reencrypt_text(raw: &str, count: &mut usize) -> Result<Option<String>, String> {
+    let trimmed = raw.trim();
+    let whole_file = trimmed.starts_with(ENCRYPTED_PREFIX);
+    let mut whole_changed = false;
+    let plaintext = if whole_file {
+        whole_changed = !crypto::is_current_ciphertext(trimmed);
+        crypto::decrypt_string(trimmed)?
+    } else {
+        raw.to_string()
+    };
+
+    let (inner, inner_changed) = match serde_json::from_str::<serde_json::Value>(&plaintext) {
+        Ok(mut json) => {
+            if reencrypt_json(&mut json, count)? {
+                let pretty = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+                (pretty, true)
+            } else {
+                (plaintext, false)
+            }
+        }
+        Err(_) => (plaintext, false),
+    };
+
+    if whole_file && (whole_changed || inner_changed) {
+        if whole_changed {
+            *count += 1;
+        }
+        return crypto::encrypt_file_content(&inner).map(Some);
+    }
+    Ok(inner_changed.then_some(inner))
+}
+
+fn reencrypt_file(path: &Path, count: &mut usize) -> Result<bool, String> {
+    let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if !raw.contains(ENCRYPTED_PREFIX) {
+        return Ok(false);
+    }
+    let Some(content) = reencrypt_text(&raw, count)? else {
+        return Ok(false);
+    };
+    let tmp = path.with_extension(format!("vault.{}.tmp", uuid::Uuid::new_v4().simple()));
+    fs::write(&tmp, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })?;
+    Ok(true)
+}
+
+/// 扫描数据库所有文本列，重新加密以密文前缀开头的值
+fn reencrypt_db(path: &Path) -> Result<usize, String> {
+    let mut conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let _ = conn.busy_timeout(std::time::Duration::from_secs(5));
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut count = 0;
+
+    let tables: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.flatten().collect()
+    };
+
+    for table in tables {
+        let columns: Vec<String> = {
+            let mut stmt = tx
+                .prepare(&format!("PRAGMA table_info(\"{}\")", table.replace('"', "\"\"")))
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+                })
+                .map_err(|e| e.to_string())?;
+            rows.flatten()
+                .filter(|(_, ty)| ty.is_empty() || ty.to_ascii_uppercase().contains("TEXT"))
+                .map(|(name, _)| name)
+                .collect()
+        };
+
+        for column in columns {
+            let table_q = format!("\"{}\"", table.replace('"', "\"\""));
+            let column_q = format!("\"{}\"", column.replace('"', "\"\""));
+            let select = format!(
+                "SELECT rowid, {col} FROM {tbl} WHERE substr({col}, 1, {len}) = ?1",
+                col = column_q,
+                tbl = table_q,
+                len = ENCRYPTED_PREFIX.len()
+            );
+            // WITHOUT ROWID 表无法按 rowid 更新，跳过
+            let Ok(mut stmt) = tx.prepare(&select) else {
+                continue;
+            };
+            let rows: Vec<(i64, String)> = stmt
+                .query_map(params![ENCRYPTED_PREFIX], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?
+                .flatten()
+                .collect();
+            drop(stmt);
+
+            let update = format!("UPDATE {} SET {} = ?1 WHERE rowid = ?2", table_q, column_q);
+            for (rowid, value) in rows {
+                if let Some(new) = reencrypt_value(&value)
+                    .map_err(|e| format!("{}.{} row {}: {}", table, column, rowid, e))?
+                {
+                    tx.execute(&update, params![new, rowid])
+                        .map_err(|e| e.to_string())?;
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reencrypt_text_nested_and_whole_file() {
+        let current = crypto::encrypt_string("fresh").unwrap();
+        let nested = serde_json::json!({
+            "proxy": { "auth": { "password": current.clone() } },
+            "name": "plain"
+        })
+        .to_string();
+
+        let mut count = 0;
+        // 内嵌字段均为当前密钥时无需改写
+        assert!(reencrypt_text(&nested, &mut count).unwrap().is_none());
+        let whole = crypto::encrypt_file_content(&nested).unwrap();
+        assert!(reencrypt_text(&whole, &mut count).unwrap().is_none());
+        assert_eq!(count, 0);
+        assert!(reencrypt_value("plain text").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_migrate_legacy_values() {
+        let legacy_inner = crypto::encrypt_legacy("proxy-pass");
+        let config = serde_json::to_string_pretty(&serde_json::json!({
+            "proxy": { "auth": { "username": "u", "password": legacy_inner } }
+        }))
+        .unwrap();
+        let legacy_file = crypto::encrypt_legacy(&config);
+
+        let mut count = 0;
+        let migrated = reencrypt_text(&legacy_file, &mut count).unwrap().unwrap();
+        assert_eq!(count, 2);
+        assert!(crypto::is_current_ciphertext(&migrated));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&crypto::decrypt_file_content(&migrated)).unwrap();
+        let password = json["proxy"]["auth"]["password"].as_str().unwrap();
+        assert!(crypto::is_current_ciphertext(password));
+        assert_eq!(crypto::decrypt_string(password).unwrap(), "proxy-pass");
+        assert_eq!(json["proxy"]["auth"]["username"], "u");
+    }
+
+    #[test]
+    fn test_reencrypt_db_columns() {
+        let dir = std::env::temp_dir().join(format!("vault_rot_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("test.db");
+        let conn = Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE secrets (id TEXT PRIMARY KEY, value TEXT, n INTEGER);
+             CREATE TABLE other (k TEXT);",
+        )
+        .unwrap();
+        let current = crypto::encrypt_string("already current").unwrap();
+        conn.execute("INSERT INTO secrets VALUES ('a', ?1, 1)", params![current]).unwrap();
+        conn.execute("INSERT INTO secrets VALUES ('b', 'plain', 2)", []).unwrap();
+        conn.execute(
+            "INSERT INTO secrets VALUES ('c', ?1, 3)",
+            params![crypto::encrypt_legacy("old secret")],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_eq!(reencrypt_db(&db).unwrap(), 1);
+        assert_eq!(reencrypt_db(&db).unwrap(), 0);
+        let conn = Connection::open(&db).unwrap();
+        let value = |id: &str| -> String {
+            conn.query_row("SELECT value FROM secrets WHERE id = ?1", params![id], |r| r.get(0))
+                .unwrap()
+        };
+        assert_eq!(value("a"), current);
+        assert_eq!(value("b"), "plain");
+        assert!(crypto::is_current_ciphertext(&value("c")));
+        assert_eq!(crypto::decrypt_string(&value("c")).unwrap(), "old secret");
+        drop(conn);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,220 @@
+// 预热执行历史
+// 记录每次计划/手动预热中每个 (账号, 模型) 的执行结果，供管理 API 查询。
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 单条预热执行记录
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WarmupRunRecord {
+    /// 同一次计划执行共享的 run_id
+    pub run_id: String,
+    pub schedule_id: String,
+    /// 触发方式: "cron" | "manual"
+    pub trigger: String,
+    pub account_email: String,
+    pub model: String,
+    /// 预热前的剩余配额百分比
+    pub percentage: i32,
+    pub success: bool,
+    pub error: Option<String>,
+    /// 开始/结束时间 (Unix 秒)
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+pub fn get_warmup_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("warmup_history.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_warmup_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS warmup_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id TEXT NOT NULL,
+            schedule_id TEXT NOT NULL,
+            trigger TEXT NOT NULL,
+            account_email TEXT NOT NULL,
+            model TEXT NOT NULL,
+            percentage INTEGER NOT NULL,
+            success INTEGER NOT NULL,
+            error TEXT,
+            started_at INTEGER NOT NULL,
+            finished_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_warmup_history_started ON warmup_history (started_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_warmup_history_schedule ON warmup_history (schedule_id);",
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_tables(&conn)
+}
+
+pub fn insert_record(record: &WarmupRunRecord) -> Result<(), String> {
+    let conn = connect_db()?;
+    insert_record_with_conn(&conn, record)
+}
+
+/// 查询最近的预热记录 (按开始时间倒序)，可按计划 ID 过滤
+pub fn list_history(limit: usize, schedule_id: Option<&str>) -> Result<Vec<WarmupRunRecord>, String> {
+    let conn = connect_db()?;
+    list_history_with_conn(&conn, limit, schedule_id)
+}
+
+/// 清理早于指定时间的记录，返回删除条数
+pub fn prune_before(cutoff_ts: i64) -> Result<usize, String> {
+    let conn = connect_db()?;
+    prune_before_with_conn(&conn, cutoff_ts)
+}
+
+fn prune_before_with_conn(conn: &Connection, cutoff_ts: i64) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM warmup_history WHERE started_at < ?1",
+        params![cutoff_ts],
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn insert_record_with_conn(conn: &Connection, record: &WarmupRunRecord) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO warmup_history
+            (run_id, schedule_id, trigger, account_email, model, percentage, success, error, started_at, finished_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            record.run_id,
+            record.schedule_id,
+            record.trigger,
+            record.account_email,
+            record.model,
+            record.percentage,
+            record.success,
+            record.error,
+            record.started_at,
+            record.finished_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn list_history_with_conn(
+    conn: &Connection,
+    limit: usize,
+    schedule_id: Option<&str>,
+) -> Result<Vec<WarmupRunRecord>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT run_id, schedule_id, trigger, account_email, model, percentage, success, error, started_at, finished_at
+             FROM warmup_history
+             WHERE (?1 IS NULL OR schedule_id = ?1)
+             ORDER BY started_at DESC, id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![schedule_id, limit as i64], |row| {
+            Ok(WarmupRunRecord {
+                run_id: row.get(0)?,
+                schedule_id: row.get(1)?,
+                trigger: row.get(2)?,
+                account_email: row.get(3)?,
+                model: row.get(4)?,
+                percentage: row.get(5)?,
+                success: row.get(6)?,
+                error: row.get(7)?,
+                started_at: row.get(8)?,
+                finished_at: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        records.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(schedule_id: &str, model: &str, started_at: i64, success: bool) -> WarmupRunRecord {
+        WarmupRunRecord {
+            run_id: format!("run-{}", started_at),
+            schedule_id: schedule_id.to_string(),
+            trigger: "cron".to_string(),
+            account_email: "a@example.com".to_string(),
+            model: model.to_string(),
+            percentage: 100,
+            success,
+            error: if success { None } else { Some("upstream 503".to_string()) },
+            started_at,
+            finished_at: started_at + 2,
+        }
+    }
+
+    #[test]
+    fn test_list_history_orders_and_filters() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        insert_record_with_conn(&conn, &record("morning", "claude-sonnet-4-5", 100, true)).unwrap();
+        insert_record_with_conn(&conn, &record("evening", "gemini-3-flash", 200, false)).unwrap();
+        insert_record_with_conn(&conn, &record("morning", "gemini-3-flash", 300, true)).unwrap();
+
+        let all = list_history_with_conn(&conn, 10, None).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].started_at, 300);
+        assert_eq!(all[1].error.as_deref(), Some("upstream 503"));
+
+        let morning = list_history_with_conn(&conn, 10, Some("morning")).unwrap();
+        assert_eq!(morning.len(), 2);
+        assert!(morning.iter().all(|r| r.schedule_id == "morning"));
+
+        let limited = list_history_with_conn(&conn, 1, None).unwrap();
+        assert_eq!(limited, vec![record("morning", "gemini-3-flash", 300, true)]);
+    }
+
+    #[test]
+    fn test_prune_keeps_recent_history_in_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        for started_at in [100, 200, 300, 400] {
+            insert_record_with_conn(&conn, &record("morning", "gemini-3-flash", started_at, true)).unwrap();
+        }
+        // 同一秒内的多次运行按插入顺序倒序
+        let mut retry = record("morning", "claude-sonnet-4-5", 400, false);
+        retry.run_id = "run-400-retry".to_string();
+        insert_record_with_conn(&conn, &retry).unwrap();
+
+        // 截止时间本身保留
+        assert_eq!(prune_before_with_conn(&conn, 300).unwrap(), 2);
+        assert_eq!(prune_before_with_conn(&conn, 300).unwrap(), 0);
+
+        let remaining = list_history_with_conn(&conn, 10, None).unwrap();
+        let runs: Vec<&str> = remaining.iter().map(|r| r.run_id.as_str()).collect();
+        assert_eq!(runs, vec!["run-400-retry", "run-400", "run-300"]);
+    }
+}
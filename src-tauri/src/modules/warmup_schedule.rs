@@ -0,0 +1,651 @@
+// Cron 预热计划
+// 按模型组配置 5 段 cron 表达式 (分 时 日 月 周)，在指定时区触发预热，
+// 例如 "45 8 * * 1-5" + "Asia/Shanghai" 表示工作日 08:45 (北京时间) 在团队上班前预热。
+// 每次执行支持随机抖动与最大并发限制，结果写入 warmup_db 供管理 API 查询。
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::time::{self, Duration};
+
+use crate::models::{Account, WarmupSchedule};
+use crate::modules::{account, config, logger, quota, scheduler, warmup_db};
+use crate::proxy::common::model_mapping::normalize_to_standard_id;
+
+/// 计划检查间隔
+const TICK_INTERVAL_SECS: u64 = 30;
+/// 与智能预热共用的冷却时间 (4 小时)
+const WARMUP_COOLDOWN_SECS: i64 = 14400;
+/// 历史记录保留天数
+const HISTORY_RETENTION_DAYS: i64 = 30;
+/// 向后搜索下一次触发时间的最大天数 (覆盖闰年 2 月 29 日)
+const MAX_SEARCH_DAYS: u32 = 366 * 4 + 1;
+
+/// 计划 ID -> (表达式签名, 下一次触发时间戳)
+static NEXT_RUNS: Lazy<RwLock<HashMap<String, (String, i64)>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+/// 正在执行中的计划，防止同一计划重叠执行
+static RUNNING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 解析后的 cron 表达式，每个字段以位图表示
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// 日/周字段是否为 "*"，用于实现标准 cron 的 "日 或 周" 语义
+    dom_any: bool,
+    dow_any: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday)",
+                expr
+            ));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7)?;
+        // 7 与 0 都表示周日
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days_of_month: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            days_of_week,
+            dom_any: fields[2] == "*",
+            dow_any: fields[4] == "*",
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+        let dom = bit(self.days_of_month, date.day());
+        let dow = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        match (self.dom_any, self.dow_any) {
+            (true, true) => true,
+            (true, false) => dow,
+            (false, true) => dom,
+            (false, false) => dom || dow,
+        }
+    }
+
+    /// 计算严格晚于 `after` 的下一次触发时间 (按 `after` 所在时区的本地时间匹配)
+    /// DST 跳过的本地时间不会触发；重复的本地时间取较早的一次。
+    pub fn next_after(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let local = after.naive_local();
+        let start = local.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+
+        let mut date = start.date();
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.matches_date(date) {
+                for hour in 0..24u32 {
+                    if !bit(self.hours, hour) {
+                        continue;
+                    }
+                    for minute in 0..60u32 {
+                        if !bit(self.minutes, minute) {
+                            continue;
+                        }
+                        let candidate = date.and_hms_opt(hour, minute, 0)?;
+                        if candidate < start {
+                            continue;
+                        }
+                        if let Some(dt) = tz.from_local_datetime(&candidate).earliest() {
+                            if dt > *after {
+                                return Some(dt);
+                            }
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1u64 << value) != 0
+}
+
+/// 解析单个 cron 字段: "*", "5", "1-5", "*/15", "0-30/10", "1,3,5"
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => {
+                let step: u32 = s
+                    .parse()
+                    .map_err(|_| format!("Invalid cron step '{}'", part))?;
+                if step == 0 {
+                    return Err(format!("Invalid cron step '{}'", part));
+                }
+                (r, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let a: u32 = a.parse().map_err(|_| format!("Invalid cron range '{}'", part))?;
+            let b: u32 = b.parse().map_err(|_| format!("Invalid cron range '{}'", part))?;
+            (a, b)
+        } else {
+            let v: u32 = range
+                .parse()
+                .map_err(|_| format!("Invalid cron value '{}'", part))?;
+            // "5/10" 表示从 5 开始到最大值
+            if step > 1 {
+                (v, max)
+            } else {
+                (v, v)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "Cron value '{}' out of range {}-{}",
+                part, min, max
+            ));
+        }
+
+        let mut v = start;
+        while v <= end {
+            mask |= 1u64 << v;
+            v += step;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("Unknown time zone '{}'", name))
+}
+
+fn schedule_signature(schedule: &WarmupSchedule) -> String {
+    format!("{}|{}", schedule.cron, schedule.timezone)
+}
+
+/// 计算计划在 `now` 之后的下一次触发时间
+pub fn next_run_for(schedule: &WarmupSchedule, now: DateTime<Utc>) -> Result<Option<DateTime<Tz>>, String> {
+    let expr = CronExpr::parse(&schedule.cron)?;
+    let tz = parse_timezone(&schedule.timezone)?;
+    Ok(expr.next_after(&now.with_timezone(&tz)))
+}
+
+/// 判断配额中的模型是否属于计划配置的模型 (精确名称或标准模型组)
+fn schedule_matches_model(patterns: &[String], model_name: &str) -> bool {
+    let group = normalize_to_standard_id(model_name);
+    patterns
+        .iter()
+        .any(|p| p == model_name || group.as_deref() == Some(p.as_str()))
+}
+
+/// 判断账号是否在计划的账号范围内 (账号 ID/邮箱列表与标签同时满足，为空表示不限制)
+fn schedule_matches_account(schedule: &WarmupSchedule, account: &Account) -> bool {
+    let id_allowed = schedule.account_ids.is_empty()
+        || schedule
+            .account_ids
+            .iter()
+            .any(|a| a == &account.id || a.eq_ignore_ascii_case(&account.email));
+    let tag_allowed = schedule.account_tags.is_empty()
+        || account
+            .tags
+            .iter()
+            .any(|tag| schedule.account_tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+    id_allowed && tag_allowed
+}
+
+/// 计划状态 (管理 API)
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupScheduleStatus {
+    pub schedule: WarmupSchedule,
+    /// 下一次触发时间 (Unix 秒)
+    pub next_run: Option<i64>,
+    /// 下一次触发的本地时间 (RFC3339，计划时区)
+    pub next_run_local: Option<String>,
+    pub running: bool,
+    pub error: Option<String>,
+}
+
+/// 单次计划执行结果汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupRunSummary {
+    pub run_id: String,
+    pub schedule_id: String,
+    pub trigger: String,
+    pub scheduled: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped_cooldown: usize,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+pub fn list_schedule_status() -> Result<Vec<WarmupScheduleStatus>, String> {
+    let app_config = config::load_app_config()?;
+    let now = Utc::now();
+    let running = RUNNING.lock().clone();
+
+    Ok(app_config
+        .scheduled_warmup
+        .schedules
+        .into_iter()
+        .map(|schedule| {
+            let (next_run, next_run_local, error) = match next_run_for(&schedule, now) {
+                Ok(Some(dt)) => (Some(dt.timestamp()), Some(dt.to_rfc3339()), None),
+                Ok(None) => (None, None, None),
+                Err(e) => (None, None, Some(e)),
+            };
+            WarmupScheduleStatus {
+                running: running.contains(&schedule.id),
+                schedule,
+                next_run,
+                next_run_local,
+                error,
+            }
+        })
+        .collect())
+}
+
+/// 立即执行指定计划 (忽略 cron 时间与抖动)
+pub async fn trigger_schedule(schedule_id: &str) -> Result<WarmupRunSummary, String> {
+    let app_config = config::load_app_config()?;
+    let schedule = app_config
+        .scheduled_warmup
+        .schedules
+        .iter()
+        .find(|s| s.id == schedule_id)
+        .cloned()
+        .ok_or_else(|| format!("Warmup schedule '{}' not found", schedule_id))?;
+
+    run_schedule(schedule, "manual", app_config.scheduled_warmup.max_concurrent).await
+}
+
+/// 启动 cron 预热计划循环
+pub fn start_warmup_schedule_loop() {
+    tauri::async_runtime::spawn(async move {
+        logger::log_info("[WarmupSchedule] Cron warmup scheduler started");
+        let mut interval = time::interval(Duration::from_secs(TICK_INTERVAL_SECS));
+        let mut last_prune = 0i64;
+
+        loop {
+            interval.tick().await;
+
+            let Ok(app_config) = config::load_app_config() else {
+                continue;
+            };
+            let warmup_config = app_config.scheduled_warmup;
+            let now = Utc::now();
+
+            // 移除已删除计划的状态
+            {
+                let ids: HashSet<&str> = warmup_config.schedules.iter().map(|s| s.id.as_str()).collect();
+                NEXT_RUNS.write().retain(|id, _| ids.contains(id.as_str()));
+            }
+
+            if !warmup_config.enabled {
+                continue;
+            }
+
+            for schedule in warmup_config.schedules.iter().filter(|s| s.enabled) {
+                let signature = schedule_signature(schedule);
+                let due = {
+                    let mut next_runs = NEXT_RUNS.write();
+                    match next_runs.get(&schedule.id) {
+                        Some((sig, next_ts)) if *sig == signature => {
+                            if now.timestamp() < *next_ts {
+                                false
+                            } else {
+                                schedule_next(&mut next_runs, schedule, signature, now);
+                                true
+                            }
+                        }
+                        // 首次加载或表达式变更: 只计算下一次时间，不补跑错过的触发
+                        _ => {
+                            schedule_next(&mut next_runs, schedule, signature, now);
+                            false
+                        }
+                    }
+                };
+
+                if !due {
+                    continue;
+                }
+
+                let schedule = schedule.clone();
+                let default_concurrency = warmup_config.max_concurrent;
+                tokio::spawn(async move {
+                    if schedule.jitter_seconds > 0 {
+                        let delay = rand::thread_rng().gen_range(0..=schedule.jitter_seconds);
+                        logger::log_info(&format!(
+                            "[WarmupSchedule] '{}' due, applying {}s jitter",
+                            schedule.id, delay
+                        ));
+                        time::sleep(Duration::from_secs(delay)).await;
+                    }
+                    if let Err(e) = run_schedule(schedule.clone(), "cron", default_concurrency).await {
+                        logger::log_warn(&format!(
+                            "[WarmupSchedule] '{}' run failed: {}",
+                            schedule.id, e
+                        ));
+                    }
+                });
+            }
+
+            // 每小时清理一次过期历史
+            if now.timestamp() - last_prune >= 3600 {
+                last_prune = now.timestamp();
+                let cutoff = now.timestamp() - HISTORY_RETENTION_DAYS * 86400;
+                let _ = tokio::task::spawn_blocking(move || warmup_db::prune_before(cutoff)).await;
+            }
+        }
+    });
+}
+
+fn schedule_next(
+    next_runs: &mut HashMap<String, (String, i64)>,
+    schedule: &WarmupSchedule,
+    signature: String,
+    now: DateTime<Utc>,
+) {
+    match next_run_for(schedule, now) {
+        Ok(Some(next)) => {
+            next_runs.insert(schedule.id.clone(), (signature, next.timestamp()));
+        }
+        Ok(None) => {
+            next_runs.insert(schedule.id.clone(), (signature, i64::MAX));
+        }
+        Err(e) => {
+            logger::log_warn(&format!(
+                "[WarmupSchedule] Invalid schedule '{}': {}",
+                schedule.id, e
+            ));
+            next_runs.insert(schedule.id.clone(), (signature, i64::MAX));
+        }
+    }
+}
+
+struct WarmupTask {
+    account_id: String,
+    email: String,
+    model: String,
+    token: String,
+    project_id: String,
+    percentage: i32,
+}
+
+/// 执行一次计划预热: 扫描所有账号，对匹配模型调用 warmup_model_directly，并记录结果
+async fn run_schedule(
+    schedule: WarmupSchedule,
+    trigger: &str,
+    default_concurrency: u32,
+) -> Result<WarmupRunSummary, String> {
+    // 防止同一计划重叠执行
+    if !RUNNING.lock().insert(schedule.id.clone()) {
+        return Err(format!("Warmup schedule '{}' is already running", schedule.id));
+    }
+    let result = execute_schedule(&schedule, trigger, default_concurrency).await;
+    RUNNING.lock().remove(&schedule.id);
+    result
+}
+
+async fn execute_schedule(
+    schedule: &WarmupSchedule,
+    trigger: &str,
+    default_concurrency: u32,
+) -> Result<WarmupRunSummary, String> {
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let started_at = Utc::now().timestamp();
+    let accounts = account::list_accounts()?;
+
+    let mut tasks = Vec::new();
+    let mut skipped_cooldown = 0;
+
+    for acc in &accounts {
+        if acc.disabled || acc.proxy_disabled || !schedule_matches_account(schedule, acc) {
+            continue;
+        }
+        let Ok((token, pid)) = quota::get_valid_token_for_warmup(acc).await else {
+            continue;
+        };
+        let Ok((fresh_quota, _)) =
+            quota::fetch_quota_with_cache(&token, &acc.email, Some(&pid), Some(&acc.id)).await
+        else {
+            continue;
+        };
+
+        if fresh_quota.is_forbidden {
+            logger::log_warn(&format!(
+                "[WarmupSchedule] Account {} returned 403 Forbidden during quota fetch, persisting forbidden status",
+                acc.email
+            ));
+            let _ = account::update_account_quota(&acc.id, fresh_quota);
+            continue;
+        }
+
+        for model in fresh_quota.models {
+            if !schedule_matches_model(&schedule.models, &model.name) {
+                continue;
+            }
+            if schedule.only_full_quota && model.percentage < 100 {
+                continue;
+            }
+            let history_key = format!("{}:{}:100", acc.email, model.name);
+            if scheduler::check_cooldown(&history_key, WARMUP_COOLDOWN_SECS) {
+                skipped_cooldown += 1;
+                continue;
+            }
+            tasks.push(WarmupTask {
+                account_id: acc.id.clone(),
+                email: acc.email.clone(),
+                model: model.name,
+                token: token.clone(),
+                project_id: pid.clone(),
+                percentage: model.percentage,
+            });
+        }
+    }
+
+    let scheduled = tasks.len();
+    let concurrency = schedule.max_concurrent.unwrap_or(default_concurrency).max(1) as usize;
+    logger::log_info(&format!(
+        "[WarmupSchedule] '{}' ({}) triggering {} warmups (concurrency {}, skipped {} in cooldown)",
+        schedule.id, trigger, scheduled, concurrency, skipped_cooldown
+    ));
+
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut handles = Vec::with_capacity(scheduled);
+
+    for task in tasks {
+        let semaphore = semaphore.clone();
+        let run_id = run_id.clone();
+        let schedule_id = schedule.id.clone();
+        let trigger = trigger.to_string();
+
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok()?;
+            let task_started = Utc::now().timestamp();
+            let success = quota::warmup_model_directly(
+                &task.token,
+                &task.model,
+                &task.project_id,
+                &task.email,
+                task.percentage,
+                Some(&task.account_id),
+            )
+            .await;
+            let finished_at = Utc::now().timestamp();
+
+            if success {
+                scheduler::record_warmup_history(
+                    &format!("{}:{}:100", task.email, task.model),
+                    finished_at,
+                );
+            }
+
+            let record = warmup_db::WarmupRunRecord {
+                run_id,
+                schedule_id,
+                trigger,
+                account_email: task.email,
+                model: task.model,
+                percentage: task.percentage,
+                success,
+                error: if success { None } else { Some("warmup request failed".to_string()) },
+                started_at: task_started,
+                finished_at,
+            };
+            if let Err(e) = tokio::task::spawn_blocking(move || warmup_db::insert_record(&record))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r)
+            {
+                logger::log_warn(&format!("[WarmupSchedule] Failed to record history: {}", e));
+            }
+            Some(success)
+        }));
+    }
+
+    let mut succeeded = 0;
+    for handle in handles {
+        if let Ok(Some(true)) = handle.await {
+            succeeded += 1;
+        }
+    }
+
+    let summary = WarmupRunSummary {
+        run_id,
+        schedule_id: schedule.id.clone(),
+        trigger: trigger.to_string(),
+        scheduled,
+        succeeded,
+        failed: scheduled - succeeded,
+        skipped_cooldown,
+        started_at,
+        finished_at: Utc::now().timestamp(),
+    };
+
+    logger::log_info(&format!(
+        "[WarmupSchedule] '{}' completed: {}/{} successful",
+        summary.schedule_id, summary.succeeded, summary.scheduled
+    ));
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cron_fields() {
+        let expr = CronExpr::parse("*/15 8-10 * * 1-5").unwrap();
+        assert!(bit(expr.minutes, 0) && bit(expr.minutes, 45) && !bit(expr.minutes, 10));
+        assert!(bit(expr.hours, 8) && bit(expr.hours, 10) && !bit(expr.hours, 11));
+        assert!(bit(expr.days_of_week, 1) && !bit(expr.days_of_week, 0));
+
+        // 7 表示周日
+        let sunday = CronExpr::parse("0 0 * * 7").unwrap();
+        assert_eq!(sunday.days_of_week, 1);
+
+        assert!(CronExpr::parse("0 8 * *").is_err());
+        assert!(CronExpr::parse("60 8 * * *").is_err());
+        assert!(CronExpr::parse("*/0 8 * * *").is_err());
+        assert!(CronExpr::parse("5-1 8 * * *").is_err());
+    }
+
+    #[test]
+    fn test_next_after_respects_timezone_and_weekdays() {
+        let expr = CronExpr::parse("45 8 * * 1-5").unwrap();
+        let tz: Tz = "Asia/Shanghai".parse().unwrap();
+
+        // 2025-01-03 是周五，09:00 已过 08:45 -> 下一次为周一 2025-01-06 08:45 (北京时间)
+        let after = tz.with_ymd_and_hms(2025, 1, 3, 9, 0, 0).unwrap();
+        let next = expr.next_after(&after).unwrap();
+        assert_eq!(next, tz.with_ymd_and_hms(2025, 1, 6, 8, 45, 0).unwrap());
+        assert_eq!(next.with_timezone(&Utc).hour(), 0);
+
+        // 恰好处于触发时刻时返回下一次，而不是当前时刻
+        let at = tz.with_ymd_and_hms(2025, 1, 6, 8, 45, 0).unwrap();
+        assert_eq!(
+            expr.next_after(&at).unwrap(),
+            tz.with_ymd_and_hms(2025, 1, 7, 8, 45, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_next_after_skips_dst_gap() {
+        // 2025-03-09 America/New_York 02:00-03:00 不存在
+        let expr = CronExpr::parse("30 2 * * *").unwrap();
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let after = tz.with_ymd_and_hms(2025, 3, 8, 12, 0, 0).unwrap();
+        let next = expr.next_after(&after).unwrap();
+        assert_eq!(next, tz.with_ymd_and_hms(2025, 3, 10, 2, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn test_schedule_matches_model_by_group() {
+        let patterns = vec!["gemini-3-flash".to_string(), "claude-opus-4-5-thinking".to_string()];
+        assert!(schedule_matches_model(&patterns, "gemini-3-flash"));
+        assert!(schedule_matches_model(&patterns, "gemini-2.5-flash"));
+        assert!(schedule_matches_model(&patterns, "claude-opus-4-5-thinking"));
+        assert!(!schedule_matches_model(&patterns, "gemini-3-pro-high"));
+    }
+
+    #[test]
+    fn test_schedule_matches_account_by_id_and_tag() {
+        let mut schedule: WarmupSchedule =
+            serde_json::from_value(serde_json::json!({ "id": "s1", "cron": "0 8 * * *" }))
+                .unwrap();
+        let mut account = Account::new(
+            "acc1".to_string(),
+            "a@test.com".to_string(),
+            crate::models::TokenData::new(
+                "atk".to_string(),
+                "rtk".to_string(),
+                3600,
+                None,
+                None,
+                None,
+            ),
+        );
+        account.tags = vec!["tier:gold".to_string()];
+        assert!(schedule_matches_account(&schedule, &account));
+
+        schedule.account_ids = vec!["A@test.com".to_string()];
+        assert!(schedule_matches_account(&schedule, &account));
+        schedule.account_ids = vec!["acc2".to_string()];
+        assert!(!schedule_matches_account(&schedule, &account));
+
+        schedule.account_ids.clear();
+        schedule.account_tags = vec!["tier:free".to_string()];
+        assert!(!schedule_matches_account(&schedule, &account));
+        schedule.account_tags.push("tier:gold".to_string());
+        assert!(schedule_matches_account(&schedule, &account));
+
+        // 两种条件同时配置时须同时满足
+        schedule.account_ids = vec!["acc2".to_string()];
+        assert!(!schedule_matches_account(&schedule, &account));
+    }
+}
@@ -0,0 +1,436 @@
+// 滥用行为检测与自动封禁
+// ip_filter_middleware 在每个代理请求完成后上报 (IP, 路径, 状态码)，按 IP 维护滑动窗口:
+// - 无效令牌: 窗口内 401 次数
+// - 请求洪泛: 每分钟请求数
+// - 扫描探测: 窗口内不同 404 路径数
+// - 令牌 IP 超限: auth 中间件在响应 extensions 中标记 AbuseSignal::TokenIpLimit
+// 触发后写入 created_by = "auto" 的黑名单条目 (附带证据)，重复违规逐级延长封禁时长。
+
+use axum::response::Response;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use crate::modules::security_db::{self, IpBanRecord};
+use crate::proxy::config::AutoBanConfig;
+
+/// 每类证据保留的样本路径数
+const MAX_EVIDENCE_SAMPLES: usize = 10;
+
+/// 跟踪的 IP 数超过该值时清理无活动窗口
+const MAX_TRACKED_IPS: usize = 10_000;
+
+static WINDOWS: Lazy<DashMap<String, IpWindow>> = Lazy::new(DashMap::new);
+
+/// 已触发 (封禁写入中或生效中) 的 IP -> 封禁到期时间
+/// 封禁异步写入数据库，期间到达的请求不再重复计为违规，避免一次洪泛直接升级到最长封禁
+static BANNED_UNTIL: Lazy<DashMap<String, i64>> = Lazy::new(DashMap::new);
+
+/// 由中间件写入响应 extensions 的滥用信号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbuseSignal {
+    /// 用户令牌的 IP 数超过限制
+    TokenIpLimit,
+}
+
+/// 触发封禁的违规类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    InvalidToken,
+    RequestFlood,
+    Scanning,
+    TokenIpLimit,
+}
+
+impl ViolationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViolationKind::InvalidToken => "invalid_token",
+            ViolationKind::RequestFlood => "request_flood",
+            ViolationKind::Scanning => "scanning",
+            ViolationKind::TokenIpLimit => "token_ip_limit",
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            ViolationKind::InvalidToken => "Repeated invalid API tokens",
+            ViolationKind::RequestFlood => "Request flood",
+            ViolationKind::Scanning => "Path scanning",
+            ViolationKind::TokenIpLimit => "Repeated token IP limit violations",
+        }
+    }
+}
+
+/// 违规证据
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub count: usize,
+    pub threshold: u32,
+    pub window_secs: i64,
+    /// 样本路径
+    pub samples: Vec<String>,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+#[derive(Debug, Default)]
+struct IpWindow {
+    requests: VecDeque<i64>,
+    invalid_tokens: VecDeque<(i64, String)>,
+    not_found: VecDeque<(i64, String)>,
+    ip_limit: VecDeque<(i64, String)>,
+}
+
+fn prune<T>(events: &mut VecDeque<(i64, T)>, cutoff: i64) {
+    while events.front().is_some_and(|(t, _)| *t <= cutoff) {
+        events.pop_front();
+    }
+}
+
+fn violation_from(
+    kind: ViolationKind,
+    events: &VecDeque<(i64, String)>,
+    count: usize,
+    threshold: u32,
+    window_secs: i64,
+) -> Violation {
+    let mut samples: Vec<String> = Vec::new();
+    for (_, path) in events.iter().rev() {
+        if samples.len() >= MAX_EVIDENCE_SAMPLES {
+            break;
+        }
+        if !samples.contains(path) {
+            samples.push(path.clone());
+        }
+    }
+    Violation {
+        kind,
+        count,
+        threshold,
+        window_secs,
+        samples,
+        first_seen: events.front().map(|(t, _)| *t).unwrap_or_default(),
+        last_seen: events.back().map(|(t, _)| *t).unwrap_or_default(),
+    }
+}
+
+impl IpWindow {
+    fn observe(
+        &mut self,
+        path: &str,
+        status: u16,
+        signal: Option<AbuseSignal>,
+        config: &AutoBanConfig,
+        now: i64,
+    ) -> Option<Violation> {
+        // 1. 请求洪泛
+        if config.rpm_limit > 0 {
+            while self.requests.front().is_some_and(|t| *t <= now - 60) {
+                self.requests.pop_front();
+            }
+            self.requests.push_back(now);
+            if self.requests.len() > config.rpm_limit as usize {
+                return Some(Violation {
+                    kind: ViolationKind::RequestFlood,
+                    count: self.requests.len(),
+                    threshold: config.rpm_limit,
+                    window_secs: 60,
+                    samples: vec![path.to_string()],
+                    first_seen: self.requests.front().copied().unwrap_or(now),
+                    last_seen: now,
+                });
+            }
+        }
+
+        // 2. 令牌 IP 超限
+        if signal == Some(AbuseSignal::TokenIpLimit) && config.token_ip_violation_threshold > 0 {
+            prune(&mut self.ip_limit, now - config.token_ip_violation_window_secs);
+            self.ip_limit.push_back((now, path.to_string()));
+            if self.ip_limit.len() >= config.token_ip_violation_threshold as usize {
+                return Some(violation_from(
+                    ViolationKind::TokenIpLimit,
+                    &self.ip_limit,
+                    self.ip_limit.len(),
+                    config.token_ip_violation_threshold,
+                    config.token_ip_violation_window_secs,
+                ));
+            }
+        }
+
+        // 3. 无效令牌
+        if status == 401 && config.invalid_token_threshold > 0 {
+            prune(&mut self.invalid_tokens, now - config.invalid_token_window_secs);
+            self.invalid_tokens.push_back((now, path.to_string()));
+            if self.invalid_tokens.len() >= config.invalid_token_threshold as usize {
+                return Some(violation_from(
+                    ViolationKind::InvalidToken,
+                    &self.invalid_tokens,
+                    self.invalid_tokens.len(),
+                    config.invalid_token_threshold,
+                    config.invalid_token_window_secs,
+                ));
+            }
+        }
+
+        // 4. 扫描探测 (按不同路径计数)
+        if status == 404 && config.not_found_threshold > 0 {
+            prune(&mut self.not_found, now - config.not_found_window_secs);
+            self.not_found.push_back((now, path.to_string()));
+            let mut distinct: Vec<&str> = self.not_found.iter().map(|(_, p)| p.as_str()).collect();
+            distinct.sort_unstable();
+            distinct.dedup();
+            if distinct.len() >= config.not_found_threshold as usize {
+                return Some(violation_from(
+                    ViolationKind::Scanning,
+                    &self.not_found,
+                    distinct.len(),
+                    config.not_found_threshold,
+                    config.not_found_window_secs,
+                ));
+            }
+        }
+
+        None
+    }
+
+    fn is_idle(&self) -> bool {
+        self.requests.is_empty()
+            && self.invalid_tokens.is_empty()
+            && self.not_found.is_empty()
+            && self.ip_limit.is_empty()
+    }
+}
+
+fn observe_at(
+    ip: &str,
+    path: &str,
+    status: u16,
+    signal: Option<AbuseSignal>,
+    config: &AutoBanConfig,
+    now: i64,
+) -> Option<Violation> {
+    if BANNED_UNTIL.get(ip).is_some_and(|until| *until > now) {
+        return None;
+    }
+    let violation = WINDOWS
+        .entry(ip.to_string())
+        .or_default()
+        .observe(path, status, signal, config, now);
+    if violation.is_some() {
+        // 已触发封禁，重置窗口并在封禁写入前先标记，避免重复封禁
+        WINDOWS.remove(ip);
+        BANNED_UNTIL.insert(ip.to_string(), now + ban_duration_secs(config, 1));
+    }
+    violation
+}
+
+fn is_loopback(ip: &str) -> bool {
+    ip.parse::<std::net::IpAddr>()
+        .map(|addr| addr.is_loopback())
+        .unwrap_or(false)
+}
+
+/// 上报一次已完成的请求，触发阈值时异步写入自动封禁
+pub fn observe_response(ip: &str, path: &str, response: &Response, config: &AutoBanConfig) {
+    if !config.enabled || (config.ignore_loopback && is_loopback(ip)) {
+        return;
+    }
+    let signal = response.extensions().get::<AbuseSignal>().copied();
+    observe_status(ip, path, response.status().as_u16(), signal, config);
+}
+
+/// 上报一次无效的管理凭据 (管理接口不经过 ip_filter_middleware)，按无效令牌计数
+pub fn observe_auth_failure(ip: &str, path: &str, config: &AutoBanConfig) {
+    if !config.enabled || (config.ignore_loopback && is_loopback(ip)) {
+        return;
+    }
+    observe_status(ip, path, 401, None, config);
+}
+
+fn observe_status(
+    ip: &str,
+    path: &str,
+    status: u16,
+    signal: Option<AbuseSignal>,
+    config: &AutoBanConfig,
+) {
+    let now = chrono::Utc::now().timestamp();
+    if WINDOWS.len() > MAX_TRACKED_IPS {
+        cleanup_idle_windows(now);
+    }
+    if let Some(violation) = observe_at(ip, path, status, signal, config, now) {
+        let ip = ip.to_string();
+        let config = config.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = apply_ban(&ip, &violation, &config, now) {
+                tracing::error!("[AutoBan] Failed to ban {}: {}", ip, e);
+            }
+        });
+    }
+}
+
+/// 第 offence 次违规 (从 1 开始) 对应的封禁时长 (秒)
+pub fn ban_duration_secs(config: &AutoBanConfig, offence: i64) -> i64 {
+    let durations = &config.ban_durations_minutes;
+    if durations.is_empty() {
+        return 15 * 60;
+    }
+    let index = ((offence.max(1) - 1) as usize).min(durations.len() - 1);
+    durations[index] as i64 * 60
+}
+
+fn apply_ban(ip: &str, violation: &Violation, config: &AutoBanConfig, now: i64) -> Result<(), String> {
+    let since = now - config.offence_memory_days * 86400;
+    let offence = security_db::count_auto_bans_since(ip, since)? + 1;
+    let duration = ban_duration_secs(config, offence);
+    BANNED_UNTIL.insert(ip.to_string(), now + duration);
+    let reason = format!(
+        "{} ({} in {}s, offence #{})",
+        violation.kind.describe(),
+        violation.count,
+        violation.window_secs,
+        offence
+    );
+
+    tracing::warn!(
+        "[AutoBan] Banning {} for {} minute(s): {}",
+        ip,
+        duration / 60,
+        reason
+    );
+
+    security_db::add_auto_ban(&IpBanRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        client_ip: ip.to_string(),
+        signal: violation.kind.as_str().to_string(),
+        reason,
+        evidence: serde_json::to_string(violation).ok(),
+        offence,
+        created_at: now,
+        expires_at: now + duration,
+    })
+}
+
+/// 清理长期无活动的 IP 窗口与已到期的封禁标记
+fn cleanup_idle_windows(now: i64) {
+    BANNED_UNTIL.retain(|_, until| *until > now);
+    WINDOWS.retain(|_, w| {
+        while w.requests.front().is_some_and(|t| *t <= now - 60) {
+            w.requests.pop_front();
+        }
+        prune(&mut w.invalid_tokens, now - 3600);
+        prune(&mut w.not_found, now - 3600);
+        prune(&mut w.ip_limit, now - 3600);
+        !w.is_idle()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AutoBanConfig {
+        AutoBanConfig {
+            enabled: true,
+            invalid_token_threshold: 3,
+            rpm_limit: 5,
+            not_found_threshold: 3,
+            token_ip_violation_threshold: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_invalid_token_threshold() {
+        let cfg = config();
+        let ip = "198.51.100.1";
+        assert!(observe_at(ip, "/v1/messages", 401, None, &cfg, 100).is_none());
+        assert!(observe_at(ip, "/v1/messages", 401, None, &cfg, 110).is_none());
+        let v = observe_at(ip, "/v1/models", 401, None, &cfg, 120).unwrap();
+        assert_eq!(v.kind, ViolationKind::InvalidToken);
+        assert_eq!(v.count, 3);
+        assert_eq!(v.samples, vec!["/v1/models".to_string(), "/v1/messages".to_string()]);
+        assert_eq!((v.first_seen, v.last_seen), (100, 120));
+
+        // 触发后窗口重置
+        assert!(observe_at(ip, "/v1/messages", 401, None, &cfg, 130).is_none());
+    }
+
+    #[test]
+    fn test_request_flood() {
+        let cfg = config();
+        let ip = "198.51.100.2";
+        for t in 0..5 {
+            assert!(observe_at(ip, "/v1/messages", 200, None, &cfg, 1_000 + t).is_none());
+        }
+        let v = observe_at(ip, "/v1/messages", 200, None, &cfg, 1_010).unwrap();
+        assert_eq!(v.kind, ViolationKind::RequestFlood);
+
+        // 超过 60 秒后不再累计
+        let ip = "198.51.100.3";
+        for t in 0..10 {
+            assert!(observe_at(ip, "/v1/messages", 200, None, &cfg, 2_000 + t * 20).is_none());
+        }
+    }
+
+    #[test]
+    fn test_scanning_counts_distinct_paths() {
+        let cfg = AutoBanConfig {
+            rpm_limit: 0,
+            ..config()
+        };
+        let ip = "198.51.100.4";
+        for _ in 0..5 {
+            assert!(observe_at(ip, "/.env", 404, None, &cfg, 3_000).is_none());
+        }
+        assert!(observe_at(ip, "/wp-login.php", 404, None, &cfg, 3_001).is_none());
+        let v = observe_at(ip, "/.git/config", 404, None, &cfg, 3_002).unwrap();
+        assert_eq!(v.kind, ViolationKind::Scanning);
+        assert_eq!(v.count, 3);
+    }
+
+    #[test]
+    fn test_token_ip_limit_signal() {
+        let cfg = config();
+        let ip = "198.51.100.5";
+        let signal = Some(AbuseSignal::TokenIpLimit);
+        assert!(observe_at(ip, "/v1/messages", 403, signal, &cfg, 4_000).is_none());
+        let v = observe_at(ip, "/v1/messages", 403, signal, &cfg, 4_001).unwrap();
+        assert_eq!(v.kind, ViolationKind::TokenIpLimit);
+    }
+
+    #[test]
+    fn test_pending_ban_suppresses_repeat_violations() {
+        let cfg = config();
+        let ip = "198.51.100.6";
+        for t in 0..5 {
+            assert!(observe_at(ip, "/v1/messages", 200, None, &cfg, 5_000 + t).is_none());
+        }
+        assert!(observe_at(ip, "/v1/messages", 200, None, &cfg, 5_005).is_some());
+
+        // 封禁写入前后继续洪泛: 封禁期内不再产生新的违规
+        for t in 0..20 {
+            assert!(observe_at(ip, "/v1/messages", 200, None, &cfg, 5_006 + t).is_none());
+        }
+
+        // 封禁到期后重新计数
+        let expired = 5_005 + ban_duration_secs(&cfg, 1);
+        for t in 0..5 {
+            assert!(observe_at(ip, "/v1/messages", 200, None, &cfg, expired + t).is_none());
+        }
+        assert!(observe_at(ip, "/v1/messages", 200, None, &cfg, expired + 5).is_some());
+    }
+
+    #[test]
+    fn test_ban_duration_escalates() {
+        let cfg = AutoBanConfig::default();
+        assert_eq!(ban_duration_secs(&cfg, 1), 15 * 60);
+        assert_eq!(ban_duration_secs(&cfg, 2), 60 * 60);
+        assert_eq!(ban_duration_secs(&cfg, 5), 10080 * 60);
+        assert_eq!(ban_duration_secs(&cfg, 42), 10080 * 60);
+    }
+}
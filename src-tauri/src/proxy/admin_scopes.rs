@@ -0,0 +1,269 @@
+// 管理接口作用域
+// 每个管理路由映射到一个作用域 (如 logs:read / accounts:write / accounts:export / config:write)，
+// 调用者 (旧版管理密码、身份 API Key) 的权限由角色与 Key 自身的作用域共同决定。
+
+use axum::http::Method;
+
+use crate::modules::admin_auth_db::{AdminRole, ResolvedAdminKey};
+
+/// 旧版 admin_password / api_key 调用者在审计日志中的名称
+pub const LEGACY_ACTOR: &str = "legacy-admin";
+
+/// 鉴权关闭时的调用者名称
+pub const ANONYMOUS_ACTOR: &str = "anonymous";
+
+/// 通过管理鉴权的调用者 (注入到请求 extensions 中)
+#[derive(Debug, Clone)]
+pub struct AdminPrincipal {
+    pub actor: String,
+    pub identity_id: Option<String>,
+    pub key_id: Option<String>,
+    pub role: AdminRole,
+    /// API Key 额外限定的作用域，为空表示继承角色全部作用域
+    pub key_scopes: Vec<String>,
+}
+
+impl AdminPrincipal {
+    /// 旧版管理密码视为 owner，保持升级前的行为
+    pub fn legacy_owner() -> Self {
+        Self {
+            actor: LEGACY_ACTOR.to_string(),
+            identity_id: None,
+            key_id: None,
+            role: AdminRole::Owner,
+            key_scopes: Vec::new(),
+        }
+    }
+
+    /// 鉴权关闭 (auth_mode=off) 时的调用者
+    pub fn anonymous() -> Self {
+        Self {
+            actor: ANONYMOUS_ACTOR.to_string(),
+            ..Self::legacy_owner()
+        }
+    }
+
+    pub fn from_key(resolved: ResolvedAdminKey) -> Self {
+        Self {
+            actor: resolved.identity.name,
+            identity_id: Some(resolved.identity.id),
+            key_id: Some(resolved.key.id),
+            role: resolved.identity.role,
+            key_scopes: resolved.key.scopes,
+        }
+    }
+
+    /// 是否具备指定作用域 (角色与 Key 作用域同时满足)
+    pub fn has_scope(&self, required: &str) -> bool {
+        let role_allows = role_scopes(self.role)
+            .iter()
+            .any(|granted| scope_matches(granted, required));
+        let key_allows = self.key_scopes.is_empty()
+            || self
+                .key_scopes
+                .iter()
+                .any(|granted| scope_matches(granted, required));
+        role_allows && key_allows
+    }
+}
+
+/// 角色默认授予的作用域
+pub fn role_scopes(role: AdminRole) -> &'static [&'static str] {
+    match role {
+        AdminRole::Viewer => &["*:read"],
+        AdminRole::Operator => &[
+            "*:read",
+            "accounts:write",
+            "proxy:write",
+            "logs:write",
+            "stats:write",
+            "tokens:write",
+            "warmup:write",
+            "security:write",
+        ],
+        AdminRole::Owner => &["*"],
+    }
+}
+
+/// 作用域匹配: 支持 "*"、"area:*" 与 "*:action"
+pub fn scope_matches(granted: &str, required: &str) -> bool {
+    if granted == "*" || granted == required {
+        return true;
+    }
+    let (Some((g_area, g_action)), Some((r_area, r_action))) =
+        (granted.split_once(':'), required.split_once(':'))
+    else {
+        return false;
+    };
+    (g_area == "*" || g_area == r_area) && (g_action == "*" || g_action == r_action)
+}
+
+/// 路由所需的作用域 (path 为去掉 /api 前缀后的路径)，None 表示无需作用域
+pub fn required_scope(method: &Method, path: &str) -> Option<String> {
+    let path = path.trim_end_matches('/');
+    if path == "/health" || path == "/healthz" {
+        return None;
+    }
+
+    // 特殊敏感操作
+    if path == "/accounts/export" || path == "/accounts/legacy/export" {
+        return Some("accounts:export".to_string());
+    }
+    let is_read = method == Method::GET || method == Method::HEAD;
+    // 备份归档包含全部账号凭据，恢复会覆盖管理员身份与配置
+    if path == "/system/backups" && !is_read {
+        return Some("accounts:export".to_string());
+    }
+    if path == "/system/backups/restore" {
+        return Some("admin:manage".to_string());
+    }
+    if path == "/auth/logout" {
+        return None;
+    }
+    if path == "/admin"
+        || path.starts_with("/admin/")
+        || path == "/auth/sessions"
+        || path.starts_with("/auth/sessions/")
+    {
+        return Some("admin:manage".to_string());
+    }
+    // 配置中含 api_key 等凭据 (未设置管理密码时 api_key 即 owner 凭据)，读取同样需要写权限
+    if matches!(
+        path,
+        "/config" | "/security/config" | "/proxy/api-key/generate" | "/system/http-api/settings"
+    ) {
+        return Some("config:write".to_string());
+    }
+    // 令牌列表包含令牌明文
+    if path == "/user-tokens" {
+        return Some("tokens:write".to_string());
+    }
+
+    let first = path.trim_start_matches('/').split('/').next().unwrap_or("");
+    let area = match first {
+        "user-tokens" => "tokens",
+        "debug" => "logs",
+        "zai" => "proxy",
+        "auth" => "accounts",
+        "" => "system",
+        other => other,
+    };
+
+    // 使用 POST 的只读查询接口
+    let post_read = path == "/accounts/device-preview"
+        || (path.starts_with("/proxy/")
+            && (path.ends_with("/status") || path.ends_with("/config"))
+            && ["/proxy/cli/", "/proxy/opencode/", "/proxy/droid/"]
+                .iter()
+                .any(|p| path.starts_with(p)));
+
+    let action = if is_read || post_read { "read" } else { "write" };
+    Some(format!("{}:{}", area, action))
+}
+
+/// 是否为需要写入审计日志的变更请求
+pub fn is_mutating(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(role: AdminRole, key_scopes: &[&str]) -> AdminPrincipal {
+        AdminPrincipal {
+            actor: "tester".to_string(),
+            identity_id: Some("id-1".to_string()),
+            key_id: Some("key-1".to_string()),
+            role,
+            key_scopes: key_scopes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_required_scope_mapping() {
+        let cases = [
+            (Method::GET, "/logs", Some("logs:read")),
+            (Method::POST, "/logs/clear", Some("logs:write")),
+            (Method::POST, "/accounts/export", Some("accounts:export")),
+            (Method::POST, "/accounts/legacy/export", Some("accounts:export")),
+            (Method::POST, "/accounts/legacy/import", Some("accounts:write")),
+            (Method::POST, "/accounts/switch", Some("accounts:write")),
+            (Method::POST, "/accounts/bulk", Some("accounts:write")),
+            (Method::POST, "/accounts/providers", Some("accounts:write")),
+            (Method::GET, "/accounts/tags", Some("accounts:read")),
+            (Method::GET, "/config", Some("config:write")),
+            (Method::GET, "/security/config", Some("config:write")),
+            (Method::GET, "/user-tokens", Some("tokens:write")),
+            (Method::GET, "/user-tokens/summary", Some("tokens:read")),
+            (Method::POST, "/config", Some("config:write")),
+            (Method::POST, "/proxy/api-key/generate", Some("config:write")),
+            (Method::POST, "/proxy/cli/status", Some("proxy:read")),
+            (Method::POST, "/proxy/cli/sync", Some("proxy:write")),
+            (Method::PATCH, "/user-tokens/abc", Some("tokens:write")),
+            (Method::GET, "/debug/logs", Some("logs:read")),
+            (Method::DELETE, "/admin/keys/k1", Some("admin:manage")),
+            (Method::GET, "/health", None),
+            (Method::POST, "/auth/logout", None),
+            (Method::DELETE, "/auth/sessions/s1", Some("admin:manage")),
+            (Method::GET, "/auth/url", Some("accounts:read")),
+            (Method::GET, "/system/backups", Some("system:read")),
+            (Method::POST, "/system/backups", Some("accounts:export")),
+            (Method::POST, "/system/backups/restore", Some("admin:manage")),
+        ];
+        for (method, path, expected) in cases {
+            assert_eq!(
+                required_scope(&method, path).as_deref(),
+                expected,
+                "{} {}",
+                method,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_role_scopes() {
+        let viewer = principal(AdminRole::Viewer, &[]);
+        assert!(viewer.has_scope("logs:read"));
+        assert!(!viewer.has_scope("accounts:write"));
+
+        let operator = principal(AdminRole::Operator, &[]);
+        assert!(operator.has_scope("accounts:write"));
+        assert!(!operator.has_scope("accounts:export"));
+        assert!(!operator.has_scope("config:write"));
+        assert!(!operator.has_scope("admin:manage"));
+
+        assert!(AdminPrincipal::legacy_owner().has_scope("admin:manage"));
+    }
+
+    #[test]
+    fn test_viewer_cannot_read_credentials() {
+        let viewer = principal(AdminRole::Viewer, &[]);
+        let read_only_key = principal(AdminRole::Owner, &["*:read"]);
+        let owner = principal(AdminRole::Owner, &[]);
+        for path in [
+            "/config",
+            "/security/config",
+            "/system/http-api/settings",
+            "/user-tokens",
+        ] {
+            let scope = required_scope(&Method::GET, path).unwrap();
+            assert!(!viewer.has_scope(&scope), "{}", path);
+            assert!(!read_only_key.has_scope(&scope), "{}", path);
+            assert!(owner.has_scope(&scope), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_key_scopes_narrow_role() {
+        let owner_key = principal(AdminRole::Owner, &["logs:read", "accounts:*"]);
+        assert!(owner_key.has_scope("logs:read"));
+        assert!(owner_key.has_scope("accounts:export"));
+        assert!(!owner_key.has_scope("config:write"));
+
+        // Key 作用域不能超出角色
+        let viewer_key = principal(AdminRole::Viewer, &["accounts:write"]);
+        assert!(!viewer_key.has_scope("accounts:write"));
+    }
+}
@@ -0,0 +1,380 @@
+// 管理后台会话登录
+// /api/auth/login 校验管理密码 (或身份 API Key) 后签发短期会话 Cookie (HMAC 签名) 与 CSRF Token，
+// 会话仅保存在内存中，进程重启后全部失效；登录 (或请求头凭据) 失败过多的 IP 会被临时加入黑名单。
+
+use axum::http::{header, HeaderMap};
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::VecDeque;
+
+use crate::proxy::admin_scopes::AdminPrincipal;
+
+pub const SESSION_COOKIE: &str = "abv_session";
+pub const CSRF_COOKIE: &str = "abv_csrf";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// 空闲超时 (每次使用后顺延)
+const SESSION_IDLE_TTL_SECS: i64 = 30 * 60;
+/// 绝对有效期
+const SESSION_MAX_TTL_SECS: i64 = 12 * 3600;
+
+/// 登录失败统计窗口
+const LOGIN_FAILURE_WINDOW_SECS: i64 = 15 * 60;
+/// 窗口内允许的失败次数，超过后临时封禁
+const LOGIN_MAX_FAILURES: usize = 5;
+/// 临时封禁时长
+pub const LOGIN_BAN_SECS: i64 = 30 * 60;
+/// 每分钟最多登录尝试次数 (无论成功与否)
+const LOGIN_MAX_ATTEMPTS_PER_MINUTE: usize = 10;
+
+/// 进程级签名密钥
+static SIGNING_KEY: Lazy<[u8; 32]> = Lazy::new(|| {
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    key
+});
+
+static SESSIONS: Lazy<DashMap<String, AdminSession>> = Lazy::new(DashMap::new);
+
+static LOGIN_ATTEMPTS: Lazy<DashMap<String, LoginAttempts>> = Lazy::new(DashMap::new);
+
+/// 管理会话
+#[derive(Debug, Clone)]
+pub struct AdminSession {
+    pub id: String,
+    pub principal: AdminPrincipal,
+    pub csrf_token: String,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub expires_at: i64,
+}
+
+/// 会话列表条目 (不含 CSRF Token)
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminSessionInfo {
+    pub id: String,
+    pub actor: String,
+    pub identity_id: Option<String>,
+    pub role: crate::modules::admin_auth_db::AdminRole,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub expires_at: i64,
+}
+
+impl AdminSession {
+    pub fn info(&self) -> AdminSessionInfo {
+        AdminSessionInfo {
+            id: self.id.clone(),
+            actor: self.principal.actor.clone(),
+            identity_id: self.principal.identity_id.clone(),
+            role: self.principal.role,
+            client_ip: self.client_ip.clone(),
+            user_agent: self.user_agent.clone(),
+            created_at: self.created_at,
+            last_seen_at: self.last_seen_at,
+            expires_at: self.expires_at,
+        }
+    }
+
+    /// 校验请求头中的 CSRF Token
+    pub fn csrf_matches(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get(CSRF_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| {
+                crate::utils::crypto::constant_time_eq(v.as_bytes(), self.csrf_token.as_bytes())
+            })
+    }
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sign(session_id: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SIGNING_KEY.as_slice())
+        .expect("HMAC accepts keys of any length");
+    mac.update(session_id.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 校验 Cookie 签名，返回会话 ID
+fn verify_cookie_value(value: &str) -> Option<&str> {
+    let (id, signature) = value.split_once('.')?;
+    crate::utils::crypto::constant_time_eq(sign(id).as_bytes(), signature.as_bytes()).then_some(id)
+}
+
+/// 创建会话，返回会话与签名后的 Cookie 值
+pub fn create_session(
+    principal: AdminPrincipal,
+    client_ip: Option<String>,
+    user_agent: Option<String>,
+) -> (AdminSession, String) {
+    let now = chrono::Utc::now().timestamp();
+    let session = AdminSession {
+        id: random_hex(16),
+        principal,
+        csrf_token: random_hex(24),
+        client_ip,
+        user_agent,
+        created_at: now,
+        last_seen_at: now,
+        expires_at: now + SESSION_IDLE_TTL_SECS,
+    };
+    let cookie_value = format!("{}.{}", session.id, sign(&session.id));
+    SESSIONS.insert(session.id.clone(), session.clone());
+    (session, cookie_value)
+}
+
+/// 通过 Cookie 值认证会话 (并顺延空闲超时)
+pub fn authenticate(cookie_value: &str) -> Option<AdminSession> {
+    authenticate_at(cookie_value, chrono::Utc::now().timestamp())
+}
+
+fn authenticate_at(cookie_value: &str, now: i64) -> Option<AdminSession> {
+    let id = verify_cookie_value(cookie_value)?;
+    let mut entry = SESSIONS.get_mut(id)?;
+    if entry.expires_at <= now {
+        drop(entry);
+        SESSIONS.remove(id);
+        return None;
+    }
+    entry.last_seen_at = now;
+    entry.expires_at = (now + SESSION_IDLE_TTL_SECS).min(entry.created_at + SESSION_MAX_TTL_SECS);
+    Some(entry.clone())
+}
+
+/// 当前请求携带的会话 ID (仅校验签名)
+pub fn session_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    read_cookie(headers, SESSION_COOKIE)
+        .as_deref()
+        .and_then(verify_cookie_value)
+        .map(|id| id.to_string())
+}
+
+/// 撤销会话
+pub fn revoke(id: &str) -> bool {
+    SESSIONS.remove(id).is_some()
+}
+
+/// 更新会话的调用者 (重新解析出的最新角色与 Key 作用域)
+pub fn update_principal(id: &str, principal: AdminPrincipal) {
+    if let Some(mut entry) = SESSIONS.get_mut(id) {
+        entry.principal = principal;
+    }
+}
+
+/// 列出未过期会话 (顺带清理过期会话)
+pub fn list_sessions() -> Vec<AdminSessionInfo> {
+    let now = chrono::Utc::now().timestamp();
+    SESSIONS.retain(|_, s| s.expires_at > now);
+    let mut sessions: Vec<_> = SESSIONS.iter().map(|s| s.info()).collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last_seen_at));
+    sessions
+}
+
+/// 从 Cookie 头中读取指定 Cookie
+pub fn read_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| v.to_string())
+}
+
+/// 登录成功后下发的 Set-Cookie 值 (会话 Cookie 为 HttpOnly，CSRF Cookie 供前端读取)
+pub fn session_cookies(session: &AdminSession, cookie_value: &str, secure: bool) -> [String; 2] {
+    let max_age = SESSION_MAX_TTL_SECS;
+    let secure = if secure { "; Secure" } else { "" };
+    [
+        format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+            SESSION_COOKIE, cookie_value, max_age, secure
+        ),
+        format!(
+            "{}={}; Path=/; SameSite=Strict; Max-Age={}{}",
+            CSRF_COOKIE, session.csrf_token, max_age, secure
+        ),
+    ]
+}
+
+/// 登出时清除 Cookie
+pub fn clear_cookies() -> [String; 2] {
+    [
+        format!("{}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0", SESSION_COOKIE),
+        format!("{}=; Path=/; SameSite=Strict; Max-Age=0", CSRF_COOKIE),
+    ]
+}
+
+// ============================================================================
+// 登录限流
+// ============================================================================
+
+#[derive(Debug, Default)]
+struct LoginAttempts {
+    attempts: VecDeque<i64>,
+    failures: VecDeque<i64>,
+}
+
+impl LoginAttempts {
+    fn prune(&mut self, now: i64) {
+        while self.attempts.front().is_some_and(|t| *t <= now - 60) {
+            self.attempts.pop_front();
+        }
+        while self
+            .failures
+            .front()
+            .is_some_and(|t| *t <= now - LOGIN_FAILURE_WINDOW_SECS)
+        {
+            self.failures.pop_front();
+        }
+    }
+}
+
+/// 记录一次登录尝试；超过每分钟上限时返回需等待的秒数
+pub fn check_login_rate(ip: &str) -> Result<(), u64> {
+    check_login_rate_at(ip, chrono::Utc::now().timestamp())
+}
+
+fn check_login_rate_at(ip: &str, now: i64) -> Result<(), u64> {
+    let mut entry = LOGIN_ATTEMPTS.entry(ip.to_string()).or_default();
+    entry.prune(now);
+    if entry.attempts.len() >= LOGIN_MAX_ATTEMPTS_PER_MINUTE {
+        let oldest = entry.attempts.front().copied().unwrap_or(now);
+        return Err((oldest + 60 - now).max(1) as u64);
+    }
+    entry.attempts.push_back(now);
+    Ok(())
+}
+
+/// 记录登录失败；返回窗口内失败次数达到上限时应封禁
+pub fn record_login_failure(ip: &str) -> Option<usize> {
+    record_login_failure_at(ip, chrono::Utc::now().timestamp())
+}
+
+fn record_login_failure_at(ip: &str, now: i64) -> Option<usize> {
+    let mut entry = LOGIN_ATTEMPTS.entry(ip.to_string()).or_default();
+    entry.prune(now);
+    entry.failures.push_back(now);
+    let failures = entry.failures.len();
+    if failures >= LOGIN_MAX_FAILURES {
+        entry.failures.clear();
+        Some(failures)
+    } else {
+        None
+    }
+}
+
+/// 记录一次管理鉴权失败 (登录或请求头凭据)；窗口内失败过多时临时封禁该 IP
+pub async fn register_auth_failure(ip: &str) {
+    let Some(failures) = record_login_failure(ip) else {
+        return;
+    };
+    let reason = format!("Too many failed admin logins ({} attempts)", failures);
+    let expires_at = chrono::Utc::now().timestamp() + LOGIN_BAN_SECS;
+    tracing::warn!("Temporarily banning {}: {}", ip, reason);
+    let ip = ip.to_string();
+    let _ = tokio::task::spawn_blocking(move || {
+        crate::modules::security_db::add_to_blacklist(&ip, Some(&reason), Some(expires_at), "auto")
+    })
+    .await;
+}
+
+/// 登录成功后清空失败计数
+pub fn record_login_success(ip: &str) {
+    if let Some(mut entry) = LOGIN_ATTEMPTS.get_mut(ip) {
+        entry.failures.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_session_cookie_signature_and_expiry() {
+        let (session, cookie) = create_session(AdminPrincipal::legacy_owner(), None, None);
+        let now = session.created_at;
+
+        assert!(authenticate_at(&cookie, now + 10).is_some());
+        // 篡改签名
+        let forged = format!("{}.{}", session.id, "0".repeat(64));
+        assert!(authenticate_at(&forged, now + 10).is_none());
+
+        // 空闲超时
+        assert!(authenticate_at(&cookie, now + 10 + SESSION_IDLE_TTL_SECS).is_none());
+        assert!(!revoke(&session.id));
+    }
+
+    #[test]
+    fn test_session_sliding_expiry_capped() {
+        let (session, cookie) = create_session(AdminPrincipal::legacy_owner(), None, None);
+        let start = session.created_at;
+        let mut now = start;
+        while now < start + SESSION_MAX_TTL_SECS - SESSION_IDLE_TTL_SECS {
+            now += SESSION_IDLE_TTL_SECS - 60;
+            assert!(authenticate_at(&cookie, now).is_some());
+        }
+        assert!(authenticate_at(&cookie, start + SESSION_MAX_TTL_SECS).is_none());
+    }
+
+    #[test]
+    fn test_csrf_and_cookie_parsing() {
+        let (session, cookie) = create_session(AdminPrincipal::legacy_owner(), None, None);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&format!("theme=dark; {}={}", SESSION_COOKIE, cookie)).unwrap(),
+        );
+        assert_eq!(session_id_from_headers(&headers).as_deref(), Some(session.id.as_str()));
+        assert!(!session.csrf_matches(&headers));
+
+        headers.insert(CSRF_HEADER, HeaderValue::from_str(&session.csrf_token).unwrap());
+        assert!(session.csrf_matches(&headers));
+        assert!(revoke(&session.id));
+    }
+
+    #[test]
+    fn test_login_failures_trigger_ban() {
+        let ip = "203.0.113.77";
+        for i in 0..LOGIN_MAX_FAILURES - 1 {
+            assert!(record_login_failure_at(ip, 1_000 + i as i64).is_none());
+        }
+        assert_eq!(record_login_failure_at(ip, 1_010), Some(LOGIN_MAX_FAILURES));
+
+        // 失败记录超出窗口后重新计数
+        let ip = "203.0.113.78";
+        assert!(record_login_failure_at(ip, 0).is_none());
+        for i in 0..LOGIN_MAX_FAILURES - 1 {
+            assert!(record_login_failure_at(ip, LOGIN_FAILURE_WINDOW_SECS + i as i64).is_none());
+        }
+    }
+
+    #[test]
+    fn test_login_rate_limit() {
+        let ip = "203.0.113.79";
+        for _ in 0..LOGIN_MAX_ATTEMPTS_PER_MINUTE {
+            assert!(check_login_rate_at(ip, 5_000).is_ok());
+        }
+        assert_eq!(check_login_rate_at(ip, 5_030), Err(30));
+        assert!(check_login_rate_at(ip, 5_060).is_ok());
+    }
+}
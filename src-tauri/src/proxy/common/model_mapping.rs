@@ -184,7 +184,7 @@ pub async fn get_all_dynamic_models(
 /// - `claude-*-sonnet-*` matches `claude-3-5-sonnet-20241022` ✓
 /// - `*-thinking` matches `claude-opus-4-5-thinking` ✓
 /// - `a*b*c` matches `a123b456c` ✓
-fn wildcard_match(pattern: &str, text: &str) -> bool {
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
     let parts: Vec<&str> = pattern.split('*').collect();
 
     // No wildcard - exact match
@@ -222,7 +222,7 @@ fn wildcard_match(pattern: &str, text: &str) -> bool {
 }
 
 /// 核心模型路由解析引擎
-/// 优先级：精确匹配 > 通配符匹配 > 系统默认映射
+/// 优先级：令牌强制映射 > 精确匹配 > 通配符匹配 > 系统默认映射
 /// 
 /// # 参数
 /// - `original_model`: 原始模型名称
@@ -234,6 +234,12 @@ pub fn resolve_model_route(
     original_model: &str,
     custom_mapping: &std::collections::HashMap<String, String>,
 ) -> String {
+    // 0. [NEW] 令牌级强制映射 (优先于所有规则)
+    if let Some(forced) = crate::proxy::token_policy::current_forced_model() {
+        crate::modules::logger::log_info(&format!("[Router] 令牌强制映射: {} -> {}", original_model, forced));
+        return forced;
+    }
+
     // 1. 精确匹配 (最高优先级)
     if let Some(target) = custom_mapping.get(original_model) {
         crate::modules::logger::log_info(&format!("[Router] 精确映射: {} -> {}", original_model, target));
@@ -241,21 +247,7 @@ pub fn resolve_model_route(
     }
     
     // 2. Wildcard match - most specific (highest non-wildcard chars) wins
-    // Note: When multiple patterns have the SAME specificity, HashMap iteration order
-    // determines the result (non-deterministic). Users can avoid this by making patterns
-    // more specific. Future improvement: use IndexMap + frontend sorting for full control.
-    let mut best_match: Option<(&str, &str, usize)> = None;
-
-    for (pattern, target) in custom_mapping.iter() {
-        if pattern.contains('*') && wildcard_match(pattern, original_model) {
-            let specificity = pattern.chars().count() - pattern.matches('*').count();
-            if best_match.is_none() || specificity > best_match.unwrap().2 {
-                best_match = Some((pattern.as_str(), target.as_str(), specificity));
-            }
-        }
-    }
-
-    if let Some((pattern, target, _)) = best_match {
+    if let Some((pattern, target)) = best_wildcard_match(original_model, custom_mapping) {
         crate::modules::logger::log_info(&format!(
             "[Router] Wildcard match: {} -> {} (rule: {})",
             original_model, target, pattern
@@ -268,7 +260,44 @@ pub fn resolve_model_route(
     if result != original_model {
         crate::modules::logger::log_info(&format!("[Router] 系统默认映射: {} -> {}", original_model, result));
     }
-    result
+    // 4. [NEW] 号池即将耗尽时切换到备用模型 (由配额预测自动启用)
+    crate::modules::quota_forecast::apply_fallback(result)
+}
+
+/// 计算路由目标但不输出日志、不应用令牌强制映射与备用模型 (用于策略预检查)
+pub fn preview_model_route(
+    original_model: &str,
+    custom_mapping: &std::collections::HashMap<String, String>,
+) -> String {
+    if let Some(target) = custom_mapping.get(original_model) {
+        return target.clone();
+    }
+    if let Some((_, target)) = best_wildcard_match(original_model, custom_mapping) {
+        return target.to_string();
+    }
+    map_claude_model_to_gemini(original_model)
+}
+
+/// Wildcard match - most specific (highest non-wildcard chars) wins
+/// Note: When multiple patterns have the SAME specificity, HashMap iteration order
+/// determines the result (non-deterministic). Users can avoid this by making patterns
+/// more specific. Future improvement: use IndexMap + frontend sorting for full control.
+fn best_wildcard_match<'a>(
+    original_model: &str,
+    custom_mapping: &'a std::collections::HashMap<String, String>,
+) -> Option<(&'a str, &'a str)> {
+    let mut best_match: Option<(&str, &str, usize)> = None;
+
+    for (pattern, target) in custom_mapping.iter() {
+        if pattern.contains('*') && wildcard_match(pattern, original_model) {
+            let specificity = pattern.chars().count() - pattern.matches('*').count();
+            if best_match.is_none() || specificity > best_match.unwrap().2 {
+                best_match = Some((pattern.as_str(), target.as_str(), specificity));
+            }
+        }
+    }
+
+    best_match.map(|(pattern, target, _)| (pattern, target))
 }
 
 /// Normalize any physical model name to one of the 3 standard protection IDs.
@@ -195,6 +195,44 @@ pub fn transform_url_for_vnpay(url: &str) -> Option<String> {
     }
 }
 
+// ============================================================================
+// 全局请求对冲 (Hedged Requests) 配置存储
+// 对冲判定发生在 handler 的上游调用处，使用全局存储避免修改 AppState
+// ============================================================================
+static GLOBAL_HEDGING_CONFIG: OnceLock<RwLock<HedgingConfig>> = OnceLock::new();
+
+/// 获取当前请求对冲配置
+pub fn get_hedging_config() -> HedgingConfig {
+    GLOBAL_HEDGING_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局请求对冲配置
+pub fn update_hedging_config(config: HedgingConfig) {
+    if let Some(lock) = GLOBAL_HEDGING_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[Hedging] Global config updated: enabled={}, budget={}%, rules={}",
+                config.enabled,
+                config.budget_percent,
+                config.rules.len()
+            );
+        }
+    } else {
+        let _ = GLOBAL_HEDGING_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[Hedging] Global config initialized: enabled={}, budget={}%, rules={}",
+            config.enabled,
+            config.budget_percent,
+            config.rules.len()
+        );
+    }
+}
+
 /// 全局系统提示词配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalSystemPromptConfig {
@@ -387,6 +425,73 @@ fn default_threshold_l3() -> f32 {
     0.7
 }
 
+/// 请求对冲规则 (按模型匹配)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HedgeRule {
+    /// 模型匹配模式，支持 `*` 通配符，匹配路由后的模型名 (如 `claude-*`)
+    pub model: String,
+
+    /// 是否对该规则匹配的模型启用对冲
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// 触发对冲的首字节延迟分位数 (如 95 表示 P95)
+    #[serde(default = "default_hedge_percentile")]
+    pub percentile: f64,
+
+    /// 对冲延迟下限 (毫秒)，避免在样本偏小时过早对冲
+    #[serde(default = "default_hedge_min_delay_ms")]
+    pub min_delay_ms: u64,
+
+    /// 对冲延迟上限 (毫秒)，样本不足时也使用该值
+    #[serde(default = "default_hedge_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+/// 请求对冲配置
+/// 当首字节迟迟未到达时，在另一个账号上发起重复请求，取先响应者
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HedgingConfig {
+    /// 总开关
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 对冲预算：对冲请求数占符合规则请求数的最大百分比 (1-100)
+    /// 用于限制额外的配额消耗
+    #[serde(default = "default_hedge_budget_percent")]
+    pub budget_percent: u32,
+
+    /// 按模型的对冲规则，第一个匹配的规则生效
+    #[serde(default)]
+    pub rules: Vec<HedgeRule>,
+}
+
+impl Default for HedgingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            budget_percent: default_hedge_budget_percent(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+fn default_hedge_percentile() -> f64 {
+    95.0
+}
+
+fn default_hedge_min_delay_ms() -> u64 {
+    1500
+}
+
+fn default_hedge_max_delay_ms() -> u64 {
+    8000
+}
+
+fn default_hedge_budget_percent() -> u32 {
+    10
+}
+
 /// Thinking Budget 模式
 /// 控制如何处理调用方传入的 thinking_budget 参数
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -517,6 +622,18 @@ pub struct SecurityMonitorConfig {
     /// IP 白名单配置
     #[serde(default)]
     pub whitelist: IpWhitelistConfig,
+
+    /// 滥用行为自动封禁配置
+    #[serde(default)]
+    pub auto_ban: AutoBanConfig,
+
+    /// 可信反向代理配置 (决定是否采信转发头中的客户端 IP)
+    #[serde(default)]
+    pub trusted_proxies: TrustedProxyConfig,
+
+    /// GeoIP / ASN 访问规则
+    #[serde(default)]
+    pub geoip: GeoIpConfig,
 }
 
 impl Default for SecurityMonitorConfig {
@@ -524,10 +641,194 @@ impl Default for SecurityMonitorConfig {
         Self {
             blacklist: IpBlacklistConfig::default(),
             whitelist: IpWhitelistConfig::default(),
+            auto_ban: AutoBanConfig::default(),
+            trusted_proxies: TrustedProxyConfig::default(),
+            geoip: GeoIpConfig::default(),
         }
     }
 }
 
+/// GeoIP / ASN 访问规则配置
+/// 使用本地 MaxMind 格式 (.mmdb) 数据库，如 GeoLite2-Country / GeoLite2-ASN
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoIpConfig {
+    /// 是否启用 GeoIP 解析与规则
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 国家 (或城市) 数据库路径
+    #[serde(default)]
+    pub database_path: Option<String>,
+
+    /// ASN 数据库路径 (为空时尝试从国家数据库读取 ASN 字段)
+    #[serde(default)]
+    pub asn_database_path: Option<String>,
+
+    /// 允许的国家代码 (ISO 3166-1 alpha-2)，为空表示不限制
+    #[serde(default)]
+    pub allowed_countries: Vec<String>,
+
+    /// 拒绝的国家代码
+    #[serde(default)]
+    pub denied_countries: Vec<String>,
+
+    /// 允许的 ASN，为空表示不限制
+    #[serde(default)]
+    pub allowed_asns: Vec<u32>,
+
+    /// 拒绝的 ASN (如云厂商 / 数据中心)
+    #[serde(default)]
+    pub denied_asns: Vec<u32>,
+
+    /// 无法解析国家 / ASN 的地址 (内网、回环等) 是否放行允许列表检查
+    #[serde(default = "default_true")]
+    pub allow_unknown: bool,
+}
+
+impl Default for GeoIpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            database_path: None,
+            asn_database_path: None,
+            allowed_countries: Vec::new(),
+            denied_countries: Vec::new(),
+            allowed_asns: Vec::new(),
+            denied_asns: Vec::new(),
+            allow_unknown: true,
+        }
+    }
+}
+
+/// 可信反向代理配置
+/// 仅当 TCP 对端属于可信代理时才读取转发头，否则一律使用连接 IP，防止伪造 X-Forwarded-For 绕过黑名单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedProxyConfig {
+    /// 可信代理地址 (IP 或 CIDR，支持 IPv6)
+    #[serde(default = "default_trusted_proxies")]
+    pub proxies: Vec<String>,
+
+    /// 按顺序尝试的客户端 IP 头 (如 cf-connecting-ip / x-forwarded-for / x-real-ip)
+    #[serde(default = "default_client_ip_headers")]
+    pub headers: Vec<String>,
+}
+
+impl Default for TrustedProxyConfig {
+    fn default() -> Self {
+        Self {
+            proxies: default_trusted_proxies(),
+            headers: default_client_ip_headers(),
+        }
+    }
+}
+
+fn default_trusted_proxies() -> Vec<String> {
+    vec!["127.0.0.0/8".to_string(), "::1/128".to_string()]
+}
+
+fn default_client_ip_headers() -> Vec<String> {
+    vec![
+        "cf-connecting-ip".to_string(),
+        "x-forwarded-for".to_string(),
+        "x-real-ip".to_string(),
+    ]
+}
+
+/// 滥用行为自动封禁配置
+/// 封禁写入 IP 黑名单 (created_by = "auto")，需同时启用黑名单才会生效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoBanConfig {
+    /// 是否启用自动封禁
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 忽略本机回环地址
+    #[serde(default = "default_true")]
+    pub ignore_loopback: bool,
+
+    /// 窗口内无效令牌 (401) 次数阈值
+    #[serde(default = "default_invalid_token_threshold")]
+    pub invalid_token_threshold: u32,
+
+    /// 无效令牌统计窗口 (秒)
+    #[serde(default = "default_abuse_window_secs")]
+    pub invalid_token_window_secs: i64,
+
+    /// 单 IP 每分钟请求数上限 (0 = 不限制)
+    #[serde(default = "default_auto_ban_rpm_limit")]
+    pub rpm_limit: u32,
+
+    /// 窗口内不同 404 路径数阈值 (扫描探测)
+    #[serde(default = "default_not_found_threshold")]
+    pub not_found_threshold: u32,
+
+    /// 404 统计窗口 (秒)
+    #[serde(default = "default_abuse_window_secs")]
+    pub not_found_window_secs: i64,
+
+    /// 窗口内令牌 IP 数超限次数阈值
+    #[serde(default = "default_token_ip_violation_threshold")]
+    pub token_ip_violation_threshold: u32,
+
+    /// 令牌 IP 超限统计窗口 (秒)
+    #[serde(default = "default_abuse_window_secs")]
+    pub token_ip_violation_window_secs: i64,
+
+    /// 逐级递增的封禁时长 (分钟)，重复违规时依次使用，超出后沿用最后一级
+    #[serde(default = "default_ban_durations_minutes")]
+    pub ban_durations_minutes: Vec<u64>,
+
+    /// 统计历史违规次数的回溯天数
+    #[serde(default = "default_offence_memory_days")]
+    pub offence_memory_days: i64,
+}
+
+impl Default for AutoBanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ignore_loopback: true,
+            invalid_token_threshold: default_invalid_token_threshold(),
+            invalid_token_window_secs: default_abuse_window_secs(),
+            rpm_limit: default_auto_ban_rpm_limit(),
+            not_found_threshold: default_not_found_threshold(),
+            not_found_window_secs: default_abuse_window_secs(),
+            token_ip_violation_threshold: default_token_ip_violation_threshold(),
+            token_ip_violation_window_secs: default_abuse_window_secs(),
+            ban_durations_minutes: default_ban_durations_minutes(),
+            offence_memory_days: default_offence_memory_days(),
+        }
+    }
+}
+
+fn default_invalid_token_threshold() -> u32 {
+    20
+}
+
+fn default_abuse_window_secs() -> i64 {
+    300
+}
+
+fn default_auto_ban_rpm_limit() -> u32 {
+    600
+}
+
+fn default_not_found_threshold() -> u32 {
+    30
+}
+
+fn default_token_ip_violation_threshold() -> u32 {
+    5
+}
+
+fn default_ban_durations_minutes() -> Vec<u64> {
+    vec![15, 60, 360, 1440, 10080]
+}
+
+fn default_offence_memory_days() -> i64 {
+    30
+}
+
 /// 反代服务配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyConfig {
@@ -634,6 +935,74 @@ pub struct ProxyConfig {
     /// Chuyển hướng request từ Google API sang VNPAY endpoint
     #[serde(default)]
     pub vnpay_dns_redirect: VnpayDnsRedirectConfig,
+
+    /// 请求对冲配置 (降低交互式请求的尾延迟)
+    #[serde(default)]
+    pub hedging: HedgingConfig,
+
+    /// 内置 TLS (HTTPS 监听) 配置
+    #[serde(default)]
+    pub tls: TlsConfig,
+}
+
+/// 内置 TLS 配置
+/// 启用后监听端口同时接受 HTTPS；未提供证书时使用本地根证书 (mitm_ca) 自动签发
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// 是否启用 HTTPS
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 用户提供的证书链 (PEM)，与 key_path 同时设置时生效
+    #[serde(default)]
+    pub cert_path: Option<String>,
+
+    /// 用户提供的私钥 (PEM)
+    #[serde(default)]
+    pub key_path: Option<String>,
+
+    /// 自动签发证书时额外加入的 SAN (域名或 IP)
+    #[serde(default)]
+    pub extra_sans: Vec<String>,
+
+    /// 非回环地址的明文 HTTP 请求重定向到 HTTPS (关闭时直接拒绝)
+    #[serde(default = "default_true")]
+    pub redirect_http: bool,
+
+    /// 本机回环连接仍允许明文 HTTP (本地 CLI 集成使用 http://127.0.0.1)；client_auth=required 时不生效
+    #[serde(default = "default_true")]
+    pub allow_plain_loopback: bool,
+
+    /// [NEW] 客户端证书 (mTLS) 校验模式，证书由本地根证书签发并绑定用户令牌
+    #[serde(default)]
+    pub client_auth: TlsClientAuthMode,
+}
+
+/// mTLS 客户端证书校验模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsClientAuthMode {
+    /// 不请求客户端证书
+    #[default]
+    Off,
+    /// 请求但不强制；提供的证书仍需有效且未吊销
+    Optional,
+    /// TLS 连接必须提供有效客户端证书 (仅 API Key 不足以访问)
+    Required,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: None,
+            key_path: None,
+            extra_sans: Vec::new(),
+            redirect_http: true,
+            allow_plain_loopback: true,
+            client_auth: TlsClientAuthMode::Off,
+        }
+    }
 }
 
 /// VNPAY DNS Redirect 配置
@@ -704,6 +1073,8 @@ impl Default for ProxyConfig {
             proxy_pool: ProxyPoolConfig::default(),
             image_thinking_mode: None,
             vnpay_dns_redirect: VnpayDnsRedirectConfig::default(),
+            hedging: HedgingConfig::default(),
+            tls: TlsConfig::default(),
         }
     }
 }
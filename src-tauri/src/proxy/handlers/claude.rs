@@ -22,6 +22,7 @@ use crate::proxy::server::AppState;
 use crate::proxy::mappers::context_manager::ContextManager;
 use crate::proxy::mappers::estimation_calibrator::get_calibrator;
 use crate::proxy::debug_logger;
+use crate::proxy::hedging;
 use crate::proxy::upstream::client::mask_email;
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS; // [NEW] Import Adapter Registry
 use axum::http::HeaderMap;
@@ -400,6 +401,28 @@ pub async fn handle_messages(
         )
         .await;
     }
+
+    // [NEW] 通用上游 Provider 账号: primary / 命中槽位的 pooled / Google 不可用时的 fallback 先于 Google 号池尝试
+    let mut provider_attempted = std::collections::HashSet::new();
+    let provider_body = if state.token_manager.provider_count() > 0 {
+        serde_json::to_value(&request).ok()
+    } else {
+        None
+    };
+    if let Some(body) = provider_body.as_ref() {
+        if let Some(resp) = crate::proxy::providers::generic::dispatch(
+            &state,
+            crate::models::ProviderProtocol::Anthropic,
+            &headers,
+            body,
+            crate::proxy::providers::generic::ProviderStage::BeforePool,
+            &mut provider_attempted,
+        )
+        .await
+        {
+            return resp;
+        }
+    }
     
     // Google Flow 继续使用 request 对象
     // (后续代码不需要再次 filter_invalid_thinking_blocks)
@@ -511,7 +534,7 @@ pub async fn handle_messages(
     
     // 3. 准备闭包
     let mut request_for_body = request.clone();
-    let token_manager = state.token_manager;
+    let token_manager = state.token_manager.clone();
     
     let pool_size = token_manager.len();
     // [FIX] Ensure max_attempts is at least 2 to allow for internal retries (e.g. stripping signatures)
@@ -863,9 +886,82 @@ pub async fn handle_messages(
 
         // Upstream call configuration continued...
 
-        let call_result = match upstream
-            .call_v1_internal_with_headers(method, &access_token, gemini_body, query, extra_headers.clone(), Some(account_id.as_str()))
-            .await {
+        // [NEW] 请求对冲：首字节超过分位数延迟时，在另一个账号上发起重复请求
+        let hedging_cfg = crate::proxy::config::get_hedging_config();
+        let hedge_rule = hedging::match_rule(&hedging_cfg, &mapped_model).cloned();
+        let call_started = std::time::Instant::now();
+        let primary_call = upstream.call_v1_internal_with_headers(
+            method,
+            &access_token,
+            gemini_body,
+            query,
+            extra_headers.clone(),
+            Some(account_id.as_str()),
+        );
+
+        let outcome = if let Some(rule) = hedge_rule {
+            hedging::register_eligible_request(hedging_cfg.budget_percent);
+            let delay = hedging::hedge_delay(&rule, &mapped_model);
+            let prepare_hedge = async {
+                if !hedging::try_acquire_hedge() {
+                    return None;
+                }
+                let (h_token, h_project, h_email, h_account_id) = token_manager
+                    .get_hedge_token(&config.final_model, &account_id)
+                    .await?;
+                let h_body = transform_claude_request_in(&request_with_mapped, &h_project, retried_without_thinking).ok()?;
+                info!(
+                    "[{}] [Hedging] No response after {}ms, hedging on {}",
+                    trace_id,
+                    delay.as_millis(),
+                    mask_email(&h_email)
+                );
+                let h_upstream = upstream.clone();
+                let h_headers = extra_headers.clone();
+                let h_account_for_call = h_account_id.clone();
+                Some(((h_email, h_account_id), async move {
+                    h_upstream
+                        .call_v1_internal_with_headers(method, &h_token, h_body, query, h_headers, Some(h_account_for_call.as_str()))
+                        .await
+                }))
+            };
+            hedging::race(primary_call, delay, prepare_hedge, |r: &Result<_, String>| {
+                matches!(r, Ok(c) if c.response.status().is_success())
+            })
+            .await
+        } else {
+            let value = primary_call.await;
+            hedging::HedgeOutcome::unhedged(value, call_started.elapsed())
+        };
+        // 只记录采用方成功响应的首字节延迟，失败与落后方不计入分位数样本
+        if matches!(&outcome.value, Ok(c) if c.response.status().is_success()) {
+            hedging::record_first_byte_latency(&mapped_model, outcome.first_byte.as_millis() as u64);
+        }
+
+        let hedge_log_ctx = hedging::HedgeLogContext {
+            url: "/v1/messages",
+            protocol: "anthropic",
+            model: &request.model,
+            mapped_model: &mapped_model,
+        };
+        let (email, account_id) = match (outcome.winner, outcome.hedge_account) {
+            (hedging::HedgeSide::Hedge, Some((h_email, h_account_id))) => {
+                if let Some((_, elapsed)) = outcome.cancelled {
+                    hedging::log_cancelled_attempt(&state.monitor, &hedge_log_ctx, &email, hedging::HedgeSide::Primary, elapsed).await;
+                }
+                info!("[{}] [Hedging] Hedge on {} won the race", trace_id, mask_email(&h_email));
+                (h_email, h_account_id)
+            }
+            (_, hedge_account) => {
+                if let (Some((_, elapsed)), Some((h_email, _))) = (outcome.cancelled, hedge_account) {
+                    hedging::log_cancelled_attempt(&state.monitor, &hedge_log_ctx, &h_email, hedging::HedgeSide::Hedge, elapsed).await;
+                }
+                (email, account_id)
+            }
+        };
+        last_email = Some(email.clone());
+
+        let call_result = match outcome.value {
             Ok(r) => r,
             Err(e) => {
                 last_error = e.clone();
@@ -1329,8 +1425,23 @@ pub async fn handle_messages(
             ], error_text).into_response();
         }
     }
-    
-    
+
+    // [NEW] Google 号池全部失败后尝试剩余的 Provider 账号
+    if let Some(body) = provider_body.as_ref() {
+        if let Some(resp) = crate::proxy::providers::generic::dispatch(
+            &state,
+            crate::models::ProviderProtocol::Anthropic,
+            &headers,
+            body,
+            crate::proxy::providers::generic::ProviderStage::AfterPool,
+            &mut provider_attempted,
+        )
+        .await
+        {
+            return resp;
+        }
+    }
+
     if let Some(email) = last_email {
         // [FIX] Include X-Mapped-Model in exhaustion error
         let mut headers = HeaderMap::new();
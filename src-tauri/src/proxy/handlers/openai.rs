@@ -83,6 +83,9 @@ pub async fn handle_chat_completions(
         }
     }
 
+    // [NEW] 通用上游 Provider 账号按原始请求体转发 (保留 OpenAIRequest 未建模的字段)
+    let provider_body = (state.token_manager.provider_count() > 0).then(|| body.clone());
+
     let mut openai_req: OpenAIRequest = serde_json::from_value(body)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
 
@@ -139,9 +142,26 @@ pub async fn handle_chat_completions(
         debug!("[{}] Client Adapter detected", trace_id);
     }
 
+    // [NEW] primary / 命中槽位的 pooled / Google 不可用时的 fallback 先于 Google 号池尝试
+    let mut provider_attempted = std::collections::HashSet::new();
+    if let Some(body) = provider_body.as_ref() {
+        if let Some(resp) = crate::proxy::providers::generic::dispatch(
+            &state,
+            crate::models::ProviderProtocol::OpenAI,
+            &headers,
+            body,
+            crate::proxy::providers::generic::ProviderStage::BeforePool,
+            &mut provider_attempted,
+        )
+        .await
+        {
+            return Ok(resp);
+        }
+    }
+
     // 1. 获取 UpstreamClient (Clone handle)
     let upstream = state.upstream.clone();
-    let token_manager = state.token_manager;
+    let token_manager = state.token_manager.clone();
     let pool_size = token_manager.len();
     // [FIX] Ensure max_attempts is at least 2 to allow for internal retries
     let max_attempts = MAX_RETRY_ATTEMPTS.min(pool_size.saturating_add(1)).max(2);
@@ -721,6 +741,22 @@ pub async fn handle_chat_completions(
             .into_response());
     }
 
+    // [NEW] Google 号池全部失败后尝试剩余的 Provider 账号
+    if let Some(body) = provider_body.as_ref() {
+        if let Some(resp) = crate::proxy::providers::generic::dispatch(
+            &state,
+            crate::models::ProviderProtocol::OpenAI,
+            &headers,
+            body,
+            crate::proxy::providers::generic::ProviderStage::AfterPool,
+            &mut provider_attempted,
+        )
+        .await
+        {
+            return Ok(resp);
+        }
+    }
+
     // 所有尝试均失败
     if let Some(email) = last_email {
         Ok((
@@ -1567,8 +1603,8 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
 
     let model_ids = get_all_dynamic_models(&state.custom_mapping).await;
 
-    let data: Vec<_> = model_ids
-        .into_iter()
+    let mut data: Vec<_> = model_ids
+        .iter()
         .map(|id| {
             json!({
                 "id": id,
@@ -1578,6 +1614,17 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
             })
         })
         .collect();
+    // [NEW] 通用上游 Provider 账号声明的模型 (通配模式不列出)
+    for (id, owner) in state.token_manager.provider_models() {
+        if !model_ids.contains(&id) {
+            data.push(json!({
+                "id": id,
+                "object": "model",
+                "created": 1706745600,
+                "owned_by": owner
+            }));
+        }
+    }
 
     Json(json!({
         "object": "list",
@@ -1678,7 +1725,8 @@ pub async fn handle_images_generations(
 
         let model_to_use = "gemini-3-pro-image".to_string();
 
-        tasks.push(tokio::spawn(async move {
+        // 图像任务在独立任务中获取账号，需延续令牌的账号池限制
+        tasks.push(crate::proxy::token_policy::spawn_scoped(async move {
             let mut last_error = String::new();
 
             for attempt in 0..max_attempts {
@@ -2081,7 +2129,7 @@ pub async fn handle_images_edits(
         let response_format = response_format.clone();
         let model = model.clone();
 
-        tasks.push(tokio::spawn(async move {
+        tasks.push(crate::proxy::token_policy::spawn_scoped(async move {
             let mut last_error = String::new();
 
             for attempt in 0..max_attempts {
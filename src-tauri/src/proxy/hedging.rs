@@ -0,0 +1,440 @@
+// 请求对冲 (Hedged Requests)
+// 主请求在分位数延迟内未拿到首字节时，在另一个账号上发起重复请求，
+// 取先成功响应的一方并取消落后者，以降低交互式请求的尾延迟。
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::{HedgeRule, HedgingConfig};
+use crate::proxy::monitor::{ProxyMonitor, ProxyRequestLog};
+
+/// 每个模型保留的首字节延迟样本数
+const MAX_LATENCY_SAMPLES: usize = 200;
+/// 样本数少于该值时不计算分位数，直接使用规则的 max_delay_ms
+const MIN_SAMPLES_FOR_PERCENTILE: usize = 20;
+/// 对冲预算最多可累积的令牌数 (允许短时突发)
+const MAX_BUDGET_BURST: f64 = 5.0;
+
+/// 首字节延迟样本 (mapped_model -> 最近 N 次延迟，毫秒)
+static FIRST_BYTE_LATENCY: Lazy<DashMap<String, VecDeque<u64>>> = Lazy::new(DashMap::new);
+
+/// 全局对冲预算
+static HEDGE_BUDGET: Lazy<Mutex<HedgeBudget>> = Lazy::new(|| Mutex::new(HedgeBudget::new()));
+
+/// 查找第一个匹配模型的已启用对冲规则
+pub fn match_rule<'a>(config: &'a HedgingConfig, model: &str) -> Option<&'a HedgeRule> {
+    if !config.enabled {
+        return None;
+    }
+    config.rules.iter().find(|rule| {
+        rule.enabled && crate::proxy::common::model_mapping::wildcard_match(&rule.model, model)
+    })
+}
+
+/// 记录一次首字节延迟样本
+pub fn record_first_byte_latency(model: &str, latency_ms: u64) {
+    let mut samples = FIRST_BYTE_LATENCY.entry(model.to_string()).or_default();
+    if samples.len() >= MAX_LATENCY_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(latency_ms);
+}
+
+/// 计算分位数 (nearest-rank)，样本为空时返回 None
+pub fn percentile(samples: &[u64], p: f64) -> Option<u64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let p = p.clamp(0.0, 100.0);
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+}
+
+/// 根据规则与历史样本计算对冲延迟
+pub fn hedge_delay(rule: &HedgeRule, model: &str) -> Duration {
+    let min_ms = rule.min_delay_ms.min(rule.max_delay_ms);
+    let observed = FIRST_BYTE_LATENCY.get(model).and_then(|samples| {
+        if samples.len() < MIN_SAMPLES_FOR_PERCENTILE {
+            return None;
+        }
+        let samples: Vec<u64> = samples.iter().copied().collect();
+        percentile(&samples, rule.percentile)
+    });
+    let delay_ms = observed
+        .unwrap_or(rule.max_delay_ms)
+        .clamp(min_ms, rule.max_delay_ms);
+    Duration::from_millis(delay_ms)
+}
+
+/// 对冲预算 (令牌桶)
+/// 每个符合规则的请求存入 budget_percent / 100 个令牌，每次对冲消耗 1 个，
+/// 从而保证对冲请求数不超过符合规则请求数的 budget_percent%。
+#[derive(Debug, Default)]
+pub struct HedgeBudget {
+    tokens: f64,
+}
+
+impl HedgeBudget {
+    pub fn new() -> Self {
+        Self { tokens: 0.0 }
+    }
+
+    /// 为一个符合对冲规则的请求存入预算
+    pub fn deposit(&mut self, budget_percent: u32) {
+        let ratio = budget_percent.min(100) as f64 / 100.0;
+        self.tokens = (self.tokens + ratio).min(MAX_BUDGET_BURST);
+    }
+
+    /// 尝试消耗一次对冲预算
+    pub fn try_withdraw(&mut self) -> bool {
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 登记一个符合对冲规则的请求
+pub fn register_eligible_request(budget_percent: u32) {
+    HEDGE_BUDGET.lock().deposit(budget_percent);
+}
+
+/// 尝试获取一次对冲预算
+pub fn try_acquire_hedge() -> bool {
+    HEDGE_BUDGET.lock().try_withdraw()
+}
+
+/// 对冲竞速中的一方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HedgeSide {
+    Primary,
+    Hedge,
+}
+
+/// 对冲竞速结果
+pub struct HedgeOutcome<T, A> {
+    /// 最终采用的结果
+    pub value: T,
+    /// 结果来自哪一方
+    pub winner: HedgeSide,
+    /// 对冲请求使用的账号 (仅在实际发起对冲时存在)
+    pub hedge_account: Option<A>,
+    /// 被取消的一方及其已运行时长
+    pub cancelled: Option<(HedgeSide, Duration)>,
+    /// 采用的一方从自身发起到返回的耗时 (首字节延迟)
+    pub first_byte: Duration,
+}
+
+impl<T, A> HedgeOutcome<T, A> {
+    /// 未发起对冲时的结果
+    pub fn unhedged(value: T, first_byte: Duration) -> Self {
+        Self {
+            value,
+            winner: HedgeSide::Primary,
+            hedge_account: None,
+            cancelled: None,
+            first_byte,
+        }
+    }
+}
+
+/// 主请求与对冲请求竞速
+///
+/// # 参数
+/// * `primary` - 主请求 future
+/// * `delay` - 主请求超过该时长仍未返回时才准备对冲
+/// * `prepare_hedge` - 准备对冲请求 (选账号、构建请求体)，返回 None 表示放弃对冲
+/// * `is_acceptable` - 判断结果是否可直接采用 (如 2xx)；不可采用时会等待另一方
+///
+/// 落后的一方在返回时被 drop，底层 HTTP 请求随之取消。
+pub async fn race<T, A, P, Prep, HF, F>(
+    primary: P,
+    delay: Duration,
+    prepare_hedge: Prep,
+    is_acceptable: F,
+) -> HedgeOutcome<T, A>
+where
+    P: Future<Output = T>,
+    Prep: Future<Output = Option<(A, HF)>>,
+    HF: Future<Output = T>,
+    F: Fn(&T) -> bool,
+{
+    let primary_started = Instant::now();
+    tokio::pin!(primary);
+
+    // 阶段 1: 等待主请求或对冲延迟到期
+    tokio::select! {
+        biased;
+        value = &mut primary => return HedgeOutcome::unhedged(value, primary_started.elapsed()),
+        _ = tokio::time::sleep(delay) => {}
+    }
+
+    // 阶段 2: 准备对冲请求，期间主请求继续推进
+    tokio::pin!(prepare_hedge);
+    let prepared = tokio::select! {
+        biased;
+        value = &mut primary => return HedgeOutcome::unhedged(value, primary_started.elapsed()),
+        prepared = &mut prepare_hedge => prepared,
+    };
+    let Some((hedge_account, hedge)) = prepared else {
+        let value = primary.await;
+        return HedgeOutcome::unhedged(value, primary_started.elapsed());
+    };
+
+    // 阶段 3: 竞速，取先可采用的一方
+    let hedge_started = Instant::now();
+    tokio::pin!(hedge);
+    let mut primary_result: Option<(T, Duration)> = None;
+    let mut hedge_result: Option<T> = None;
+
+    loop {
+        tokio::select! {
+            value = &mut primary, if primary_result.is_none() => {
+                if is_acceptable(&value) || hedge_result.is_some() {
+                    let cancelled = hedge_result
+                        .is_none()
+                        .then(|| (HedgeSide::Hedge, hedge_started.elapsed()));
+                    return HedgeOutcome {
+                        value,
+                        winner: HedgeSide::Primary,
+                        hedge_account: Some(hedge_account),
+                        cancelled,
+                        first_byte: primary_started.elapsed(),
+                    };
+                }
+                primary_result = Some((value, primary_started.elapsed()));
+            }
+            value = &mut hedge, if hedge_result.is_none() => {
+                if is_acceptable(&value) {
+                    let cancelled = primary_result
+                        .is_none()
+                        .then(|| (HedgeSide::Primary, delay + hedge_started.elapsed()));
+                    return HedgeOutcome {
+                        value,
+                        winner: HedgeSide::Hedge,
+                        hedge_account: Some(hedge_account),
+                        cancelled,
+                        first_byte: hedge_started.elapsed(),
+                    };
+                }
+                if let Some((primary_value, first_byte)) = primary_result.take() {
+                    // 双方均不可采用，以主请求结果为准，交由上层重试逻辑处理
+                    return HedgeOutcome {
+                        value: primary_value,
+                        winner: HedgeSide::Primary,
+                        hedge_account: Some(hedge_account),
+                        cancelled: None,
+                        first_byte,
+                    };
+                }
+                hedge_result = Some(value);
+            }
+        }
+    }
+}
+
+/// 被取消请求的日志上下文
+pub struct HedgeLogContext<'a> {
+    pub url: &'a str,
+    pub protocol: &'a str,
+    pub model: &'a str,
+    pub mapped_model: &'a str,
+}
+
+/// 将被取消的对冲/主请求写入监控日志，便于排查与计费核对
+pub async fn log_cancelled_attempt(
+    monitor: &ProxyMonitor,
+    ctx: &HedgeLogContext<'_>,
+    account_email: &str,
+    side: HedgeSide,
+    elapsed: Duration,
+) {
+    let label = match side {
+        HedgeSide::Primary => "primary",
+        HedgeSide::Hedge => "hedge",
+    };
+    tracing::info!(
+        "[Hedging] Cancelled {} attempt on {} after {}ms ({})",
+        label,
+        crate::proxy::upstream::client::mask_email(account_email),
+        elapsed.as_millis(),
+        ctx.mapped_model
+    );
+
+    monitor
+        .log_request(ProxyRequestLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            method: "POST".to_string(),
+            url: ctx.url.to_string(),
+            // 499: 请求在响应前被主动取消 (沿用 nginx 约定)
+            status: 499,
+            duration: elapsed.as_millis() as u64,
+            model: Some(ctx.model.to_string()),
+            mapped_model: Some(ctx.mapped_model.to_string()),
+            account_email: Some(account_email.to_string()),
+            client_ip: None,
+            error: Some(format!("Hedged request cancelled ({} attempt lost the race)", label)),
+            request_body: None,
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            protocol: Some(ctx.protocol.to_string()),
+            username: None,
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(model: &str) -> HedgeRule {
+        HedgeRule {
+            model: model.to_string(),
+            enabled: true,
+            percentile: 95.0,
+            min_delay_ms: 100,
+            max_delay_ms: 2000,
+        }
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 95.0), Some(95));
+        assert_eq!(percentile(&samples, 50.0), Some(50));
+        assert_eq!(percentile(&samples, 100.0), Some(100));
+        assert_eq!(percentile(&samples, 0.0), Some(1));
+        assert_eq!(percentile(&[], 95.0), None);
+    }
+
+    #[test]
+    fn test_match_rule_respects_switches() {
+        let mut config = HedgingConfig {
+            enabled: true,
+            budget_percent: 10,
+            rules: vec![rule("claude-*"), rule("gemini-3-flash")],
+        };
+        assert!(match_rule(&config, "claude-sonnet-4-6").is_some());
+        assert!(match_rule(&config, "gemini-3-flash").is_some());
+        assert!(match_rule(&config, "gemini-3-pro-high").is_none());
+
+        config.rules[0].enabled = false;
+        assert!(match_rule(&config, "claude-sonnet-4-6").is_none());
+
+        config.enabled = false;
+        assert!(match_rule(&config, "gemini-3-flash").is_none());
+    }
+
+    #[test]
+    fn test_hedge_delay_uses_percentile_within_bounds() {
+        let model = "test-hedge-delay-model";
+        let r = rule(model);
+        // 样本不足时使用上限
+        assert_eq!(hedge_delay(&r, model), Duration::from_millis(2000));
+
+        for ms in 1..=100u64 {
+            record_first_byte_latency(model, ms * 10);
+        }
+        assert_eq!(hedge_delay(&r, model), Duration::from_millis(950));
+
+        let mut tight = r.clone();
+        tight.max_delay_ms = 500;
+        assert_eq!(hedge_delay(&tight, model), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_budget_caps_hedge_ratio() {
+        let mut budget = HedgeBudget::new();
+        let mut hedges = 0;
+        for _ in 0..100 {
+            budget.deposit(10);
+            if budget.try_withdraw() {
+                hedges += 1;
+            }
+        }
+        assert!(hedges <= 10, "hedges={}", hedges);
+        assert!(hedges >= 9, "hedges={}", hedges);
+    }
+
+    #[tokio::test]
+    async fn test_race_primary_fast_skips_hedge() {
+        let outcome: HedgeOutcome<u32, &str> = race(
+            async { 1 },
+            Duration::from_millis(50),
+            async { Some(("hedge", async { 2 })) },
+            |_| true,
+        )
+        .await;
+        assert_eq!(outcome.value, 1);
+        assert_eq!(outcome.winner, HedgeSide::Primary);
+        assert!(outcome.hedge_account.is_none());
+        assert!(outcome.cancelled.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_race_hedge_wins_and_cancels_primary() {
+        let outcome = race(
+            async {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                1
+            },
+            Duration::from_millis(20),
+            async { Some(("hedge-account", async { 2 })) },
+            |_| true,
+        )
+        .await;
+        assert_eq!(outcome.value, 2);
+        assert_eq!(outcome.winner, HedgeSide::Hedge);
+        assert_eq!(outcome.hedge_account, Some("hedge-account"));
+        assert_eq!(outcome.cancelled.map(|c| c.0), Some(HedgeSide::Primary));
+        // 首字节延迟从对冲请求自身发起时计算，不含对冲等待时间
+        assert!(
+            outcome.first_byte < Duration::from_millis(20),
+            "{:?}",
+            outcome.first_byte
+        );
+    }
+
+    #[tokio::test]
+    async fn test_race_waits_for_primary_when_hedge_unacceptable() {
+        let outcome = race(
+            async {
+                tokio::time::sleep(Duration::from_millis(80)).await;
+                200u16
+            },
+            Duration::from_millis(10),
+            async { Some(("hedge-account", async { 429u16 })) },
+            |status: &u16| *status == 200,
+        )
+        .await;
+        assert_eq!(outcome.value, 200);
+        assert_eq!(outcome.winner, HedgeSide::Primary);
+        assert!(outcome.cancelled.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_race_without_prepared_hedge_awaits_primary() {
+        let outcome = race(
+            async {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                7
+            },
+            Duration::from_millis(5),
+            async { None::<(&str, std::future::Ready<i32>)> },
+            |_| true,
+        )
+        .await;
+        assert_eq!(outcome.value, 7);
+        assert!(outcome.hedge_account.is_none());
+    }
+}
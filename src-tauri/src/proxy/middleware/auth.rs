@@ -1,14 +1,21 @@
 // API Key 认证中间件
 use axum::{
+    extract::ConnectInfo,
     extract::State,
     extract::Request,
     http::{header, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::modules::admin_auth_db::{AdminAuditEntry, ADMIN_KEY_PREFIX};
+use crate::proxy::admin_scopes::{self, AdminPrincipal};
+use crate::proxy::admin_session;
+use crate::proxy::middleware::ip_filter::{extract_client_ip, ClientIp};
+use crate::proxy::tls::TlsConnection;
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
 
 /// API Key 认证中间件 (代理接口使用，遵循 auth_mode)
@@ -32,7 +39,7 @@ pub async fn admin_auth_middleware(
 /// 内部认证逻辑
 async fn auth_middleware_internal(
     State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
-    request: Request,
+    mut request: Request,
     next: Next,
     force_strict: bool,
 ) -> Result<Response, StatusCode> {
@@ -56,6 +63,26 @@ async fn auth_middleware_internal(
     let security = security.read().await.clone();
     let effective_mode = security.effective_auth_mode();
 
+    // 解析客户端 IP (管理接口未经过 ip_filter，此处补充注入)
+    if request.extensions().get::<ClientIp>().is_none() {
+        if let Some(ip) =
+            extract_client_ip(&request, &security.security_monitor.trusted_proxies)
+        {
+            request.extensions_mut().insert(ClientIp(ip));
+        }
+    }
+
+    // [NEW] mTLS: 已通过握手校验的客户端证书映射为用户令牌身份 (代理接口)
+    if !force_strict {
+        let fingerprint = request
+            .extensions()
+            .get::<TlsConnection>()
+            .and_then(|tls| tls.client_cert_fingerprint.clone());
+        if let Some(fingerprint) = fingerprint {
+            return Ok(run_client_cert_request(&fingerprint, &security.api_key, request, next).await);
+        }
+    }
+
     // 权限检查逻辑
     if !force_strict {
         // AI 代理接口 (v1/chat/completions 等)
@@ -78,15 +105,18 @@ async fn auth_middleware_internal(
                 // 尝试验证是否为 User Token（不阻止请求，只记录）
                 if let Ok(Some(user_token)) = crate::modules::user_token_db::get_token_by_value(token) {
                     let identity = UserTokenIdentity {
-                        token_id: user_token.id,
-                        token: user_token.token,
-                        username: user_token.username,
+                        token_id: user_token.id.clone(),
+                        token: user_token.token.clone(),
+                        username: user_token.username.clone(),
+                        model_policy: user_token.model_policy.clone(),
+                        client_cert_serial: None,
                     };
                     // 注入 identity 到请求
                     let (mut parts, body) = request.into_parts();
                     parts.extensions.insert(identity);
                     let request = Request::from_parts(parts, body);
-                    return Ok(next.run(request).await);
+                    // [NEW] 鉴权关闭时同样执行令牌预算与请求配额
+                    return Ok(run_with_token_budget(&user_token, request, next).await);
                 }
             }
             
@@ -106,13 +136,21 @@ async fn auth_middleware_internal(
         // 管理接口 (/api/*)
         // 1. 如果全局鉴权关闭，则管理接口也放行 (除非是强制局域网模式)
         if matches!(effective_mode, ProxyAuthMode::Off) {
-            return Ok(next.run(request).await);
+            return Ok(run_admin_request(AdminPrincipal::anonymous(), request, next).await);
         }
 
         // 2. 健康检查在所有模式下对管理接口放行
         if is_health_check {
             return Ok(next.run(request).await);
         }
+
+        // 3. [SECURITY] 管理接口未经过 ip_filter，登录失败等触发的临时封禁在此生效
+        let banned = request.extensions().get::<ClientIp>().is_some_and(|ip| {
+            matches!(crate::modules::security_db::is_ip_in_blacklist(&ip.0), Ok(true))
+        });
+        if banned {
+            return Err(StatusCode::FORBIDDEN);
+        }
     }
     
     // 从 header 中提取 API key
@@ -134,6 +172,82 @@ async fn auth_middleware_internal(
                 .and_then(|h| h.to_str().ok())
         });
 
+    // [NEW] 管理接口: 未携带 API Key 时使用登录会话 Cookie (变更请求需校验 CSRF)
+    if force_strict && api_key.is_none() {
+        if let Some(cookie) = admin_session::read_cookie(request.headers(), admin_session::SESSION_COOKIE) {
+            let Some(mut session) = admin_session::authenticate(&cookie) else {
+                return Err(StatusCode::UNAUTHORIZED);
+            };
+            // [SECURITY] 以 API Key 登录的会话每次请求重新解析: Key 撤销、身份禁用后立即失效，角色变更立即生效
+            if let Some(key_id) = session.principal.key_id.clone() {
+                let resolved = tokio::task::spawn_blocking(move || {
+                    crate::modules::admin_auth_db::resolve_api_key_by_id(&key_id)
+                })
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                match resolved {
+                    Ok(Some(resolved)) => {
+                        session.principal = AdminPrincipal::from_key(resolved);
+                        admin_session::update_principal(&session.id, session.principal.clone());
+                    }
+                    Ok(None) => {
+                        tracing::warn!(
+                            "Admin session {} revoked: key or identity no longer valid",
+                            session.principal.actor
+                        );
+                        admin_session::revoke(&session.id);
+                        return Err(StatusCode::UNAUTHORIZED);
+                    }
+                    Err(e) => {
+                        tracing::error!("Admin API key lookup failed: {}", e);
+                        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                    }
+                }
+            }
+            if admin_scopes::is_mutating(&method) && !session.csrf_matches(request.headers()) {
+                tracing::warn!("Admin session {} rejected: CSRF token mismatch", session.principal.actor);
+                let body = serde_json::json!({
+                    "error": {
+                        "message": "Missing or invalid CSRF token",
+                        "type": "permission_error",
+                        "code": "csrf_failed"
+                    }
+                });
+                return Ok(Response::builder()
+                    .status(StatusCode::FORBIDDEN)
+                    .header("Content-Type", "application/json")
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap());
+            }
+            return Ok(run_admin_request(session.principal, request, next).await);
+        }
+    }
+
+    // [NEW] 管理接口: 身份 API Key (agk_*) 优先于旧版管理密码
+    if force_strict {
+        if let Some(key) = api_key.filter(|k| k.starts_with(ADMIN_KEY_PREFIX)) {
+            let key = key.to_string();
+            let resolved = tokio::task::spawn_blocking(move || {
+                crate::modules::admin_auth_db::resolve_api_key(&key)
+            })
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            return match resolved {
+                Ok(Some(resolved)) => {
+                    Ok(run_admin_request(AdminPrincipal::from_key(resolved), request, next).await)
+                }
+                Ok(None) => {
+                    record_admin_auth_failure(&request, &security, &path).await;
+                    Err(StatusCode::UNAUTHORIZED)
+                }
+                Err(e) => {
+                    tracing::error!("Admin API key lookup failed: {}", e);
+                    Err(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            };
+        }
+    }
+
     if security.api_key.is_empty() && (security.admin_password.is_none() || security.admin_password.as_ref().unwrap().is_empty()) {
         if force_strict {
              tracing::error!("Admin auth is required but both api_key and admin_password are empty; denying request");
@@ -147,9 +261,18 @@ async fn auth_middleware_internal(
     let authorized = if force_strict {
         // 管理接口：优先使用独立的 admin_password，如果没有则回退使用 api_key
         match &security.admin_password {
-            Some(pwd) if !pwd.is_empty() => {
-                api_key.map(|k| k == pwd).unwrap_or(false)
-            }
+            Some(pwd) if !pwd.is_empty() => match api_key {
+                // [NEW] 管理密码以 Argon2 哈希存储 (兼容旧版明文)，校验放到阻塞线程池
+                Some(key) => {
+                    let (candidate, stored) = (key.to_string(), pwd.clone());
+                    tokio::task::spawn_blocking(move || {
+                        crate::utils::crypto::verify_password(&candidate, &stored)
+                    })
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                }
+                None => false,
+            },
             _ => {
                 // 回退使用 api_key
                 api_key.map(|k| k == security.api_key).unwrap_or(false)
@@ -160,25 +283,22 @@ async fn auth_middleware_internal(
         api_key.map(|k| k == security.api_key).unwrap_or(false)
     };
 
-    if authorized {
+    if authorized && force_strict {
+        Ok(run_admin_request(AdminPrincipal::legacy_owner(), request, next).await)
+    } else if force_strict && api_key.is_some() {
+        record_admin_auth_failure(&request, &security, &path).await;
+        Err(StatusCode::UNAUTHORIZED)
+    } else if authorized {
         Ok(next.run(request).await)
     } else if !force_strict && api_key.is_some() {
         // 尝试验证 UserToken
         let token = api_key.unwrap();
         
-        // 提取 IP (复用逻辑)
+        // 提取 IP (复用 ip_filter 解析结果)
         let client_ip = request
-            .headers()
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-            .or_else(|| {
-                request
-                    .headers()
-                    .get("x-real-ip")
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string())
-            })
+            .extensions()
+            .get::<ClientIp>()
+            .map(|ip| ip.0.clone())
             .unwrap_or_else(|| "127.0.0.1".to_string()); // Default fallback
 
         // 验证 Token
@@ -186,11 +306,22 @@ async fn auth_middleware_internal(
             Ok((true, _)) => {
                 // Token 有效，查询信息以便传递
                 if let Ok(Some(user_token)) = crate::modules::user_token_db::get_token_by_value(token) {
-                     let identity = UserTokenIdentity {
-                        token_id: user_token.id,
-                        token: user_token.token,
-                        username: user_token.username,
+                     let mut identity = UserTokenIdentity {
+                        token_id: user_token.id.clone(),
+                        token: user_token.token.clone(),
+                        username: user_token.username.clone(),
+                        model_policy: user_token.model_policy.clone(),
+                        client_cert_serial: None,
                     };
+
+                    // [NEW] 访问时间表降级模式: 受限时段内强制路由到低成本模型
+                    if let Some(model) = user_token.scheduled_downgrade_model() {
+                        tracing::info!(
+                            "UserToken {} outside access schedule, downgrading to {}",
+                            user_token.username, model
+                        );
+                        identity.model_policy.forced_model = Some(model);
+                    }
                     
                     // [FIX] 将身份信息注入到请求 extensions 中，而不是响应
                     // 这样 monitor_middleware 在处理请求时就能获取到 identity
@@ -201,8 +332,8 @@ async fn auth_middleware_internal(
                     parts.extensions.insert(identity);
                     let request = Request::from_parts(parts, body);
                     
-                    // 执行请求
-                    let response = next.run(request).await;
+                    // 执行请求 ([NEW] 先检查令牌预算 / RPM / 并发)
+                    let response = run_with_token_budget(&user_token, request, next).await;
                     
                     Ok(response)
                 } else {
@@ -212,6 +343,7 @@ async fn auth_middleware_internal(
             Ok((false, reason)) => {
                 let reason_str = reason.unwrap_or_else(|| "Access denied".to_string());
                 tracing::warn!("UserToken rejected: {}", reason_str);
+                let ip_limit_exceeded = reason_str.starts_with("IP limit reached");
                 let body = serde_json::json!({
                     "error": {
                         "message": reason_str,
@@ -219,11 +351,17 @@ async fn auth_middleware_internal(
                         "code": "token_rejected"
                     }
                 });
-                let response = axum::response::Response::builder()
+                let mut response = axum::response::Response::builder()
                     .status(StatusCode::FORBIDDEN)
                     .header("Content-Type", "application/json")
                     .body(axum::body::Body::from(serde_json::to_string(&body).unwrap()))
                     .unwrap();
+                // [NEW] 标记令牌 IP 超限，供自动封禁统计
+                if ip_limit_exceeded {
+                    response
+                        .extensions_mut()
+                        .insert(crate::proxy::abuse_detector::AbuseSignal::TokenIpLimit);
+                }
                 Ok(response)
             }
             Err(e) => {
@@ -236,6 +374,228 @@ async fn auth_middleware_internal(
     }
 }
 
+/// [SECURITY] 请求头携带的管理凭据无效: 与登录接口共用失败计数与临时封禁，并上报滥用检测
+async fn record_admin_auth_failure(request: &Request, security: &ProxySecurityConfig, path: &str) {
+    let Some(ClientIp(ip)) = request.extensions().get::<ClientIp>() else {
+        return;
+    };
+    tracing::warn!("Admin credential rejected from {} for {}", ip, path);
+    admin_session::register_auth_failure(ip).await;
+    crate::proxy::abuse_detector::observe_auth_failure(ip, path, &security.security_monitor.auto_ban);
+}
+
+fn json_error(status: StatusCode, message: &str, error_type: &str, code: &str) -> Response {
+    let body = serde_json::json!({
+        "error": {
+            "message": message,
+            "type": error_type,
+            "code": code
+        }
+    });
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(axum::body::Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// [NEW] 以客户端证书绑定的用户令牌身份处理请求
+/// 证书须已登记且未吊销；同时携带 API Key 时，Key 必须是该令牌或全局 API Key
+async fn run_client_cert_request(
+    fingerprint: &str,
+    global_api_key: &str,
+    request: Request,
+    next: Next,
+) -> Response {
+    use crate::modules::user_token_db;
+
+    let cert = match user_token_db::get_client_cert_by_fingerprint(fingerprint) {
+        Ok(Some(cert)) => cert,
+        Ok(None) => {
+            tracing::warn!("mTLS client certificate {} is not registered", fingerprint);
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "Unknown client certificate",
+                "authentication_error",
+                "client_cert_unknown",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Client certificate lookup failed: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if cert.is_revoked() {
+        tracing::warn!("mTLS client certificate {} is revoked", cert.serial);
+        return json_error(
+            StatusCode::UNAUTHORIZED,
+            "Client certificate has been revoked",
+            "authentication_error",
+            "client_cert_revoked",
+        );
+    }
+    let user_token = match user_token_db::get_token_by_id(&cert.token_id) {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "Unknown client certificate",
+                "authentication_error",
+                "client_cert_unknown",
+            )
+        }
+        Err(e) => {
+            tracing::error!("UserToken lookup for client certificate failed: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let api_key = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
+        .or_else(|| request.headers().get("x-api-key").and_then(|h| h.to_str().ok()))
+        .or_else(|| request.headers().get("x-goog-api-key").and_then(|h| h.to_str().ok()));
+    if let Some(key) = api_key {
+        if key != user_token.token && (global_api_key.is_empty() || key != global_api_key) {
+            tracing::warn!(
+                "mTLS client certificate {} presented with a mismatched API key",
+                cert.serial
+            );
+            return json_error(
+                StatusCode::FORBIDDEN,
+                "API key does not match the client certificate",
+                "permission_error",
+                "client_cert_mismatch",
+            );
+        }
+    }
+
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ip| ip.0.clone())
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    match user_token_db::validate_token(&user_token.token, &client_ip) {
+        Ok((true, _)) => {}
+        Ok((false, reason)) => {
+            let reason = reason.unwrap_or_else(|| "Access denied".to_string());
+            tracing::warn!("UserToken {} (mTLS) rejected: {}", user_token.username, reason);
+            return json_error(StatusCode::FORBIDDEN, &reason, "token_rejected", "token_rejected");
+        }
+        Err(e) => {
+            tracing::error!("UserToken validation error: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let mut identity = UserTokenIdentity {
+        token_id: user_token.id.clone(),
+        token: user_token.token.clone(),
+        username: user_token.username.clone(),
+        model_policy: user_token.model_policy.clone(),
+        client_cert_serial: Some(cert.serial),
+    };
+    if let Some(model) = user_token.scheduled_downgrade_model() {
+        identity.model_policy.forced_model = Some(model);
+    }
+
+    let (mut parts, body) = request.into_parts();
+    parts.extensions.insert(identity);
+    run_with_token_budget(&user_token, Request::from_parts(parts, body), next).await
+}
+
+/// 检查令牌预算、RPM 与并发限制后转发请求，超限时返回 429
+async fn run_with_token_budget(
+    user_token: &crate::modules::user_token_db::UserToken,
+    request: Request,
+    next: Next,
+) -> Response {
+    match crate::proxy::token_budget::check_and_acquire(user_token) {
+        Ok(permit) => permit.attach(next.run(request).await),
+        Err(rejection) => {
+            tracing::warn!(
+                "UserToken {} rejected by limits: {}",
+                user_token.username,
+                rejection.message
+            );
+            rejection.into_response()
+        }
+    }
+}
+
+/// 校验管理路由作用域，注入调用者身份，并将变更请求与越权请求写入审计日志
+async fn run_admin_request(principal: AdminPrincipal, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let scope = admin_scopes::required_scope(&method, &path);
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ip| ip.0.clone())
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ci| ci.0.ip().to_string())
+        });
+
+    let missing_scope = scope.as_deref().filter(|s| !principal.has_scope(s));
+    let response = if let Some(required) = missing_scope {
+        tracing::warn!(
+            "Admin {} denied {} {}: missing scope {}",
+            principal.actor, method, path, required
+        );
+        let body = serde_json::json!({
+            "error": {
+                "message": format!("Missing required scope '{}'", required),
+                "type": "permission_error",
+                "code": "insufficient_scope",
+                "required_scope": required
+            }
+        });
+        Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header("Content-Type", "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap()
+    } else {
+        let (mut parts, body) = request.into_parts();
+        parts.extensions.insert(principal.clone());
+        // [NEW] 请求内产生的账号生命周期事件记录为该调用者
+        crate::modules::account_events::with_actor(
+            principal.actor.clone(),
+            next.run(Request::from_parts(parts, body)),
+        )
+        .await
+    };
+
+    let status = response.status();
+    if admin_scopes::is_mutating(&method) || status == StatusCode::FORBIDDEN {
+        let entry = AdminAuditEntry {
+            id: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+            actor: principal.actor,
+            identity_id: principal.identity_id,
+            key_id: principal.key_id,
+            method: method.to_string(),
+            path,
+            scope,
+            status: status.as_u16(),
+            client_ip,
+            detail: None,
+        };
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = crate::modules::admin_auth_db::insert_audit(&entry) {
+                tracing::error!("Failed to write admin audit log: {}", e);
+            }
+        });
+    }
+
+    response
+}
+
 /// 用户令牌身份信息 (传递给 Monitor 使用)
 #[derive(Clone, Debug)]
 pub struct UserTokenIdentity {
@@ -243,6 +603,10 @@ pub struct UserTokenIdentity {
     #[allow(dead_code)] // 保留原始 token 便于审计/调试
     pub token: String,
     pub username: String,
+    /// 模型访问策略与路由覆盖
+    pub model_policy: crate::modules::user_token_db::TokenModelPolicy,
+    /// [NEW] 通过 mTLS 客户端证书认证时的证书序列号
+    pub client_cert_serial: Option<String>,
 }
 
 #[cfg(test)]
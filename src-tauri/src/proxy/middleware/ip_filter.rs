@@ -2,23 +2,37 @@ use axum::{
     extract::{Request, State},
     middleware::Next,
     response::{IntoResponse, Response},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
 };
+use std::net::{IpAddr, SocketAddr};
+use crate::proxy::config::TrustedProxyConfig;
 use crate::proxy::server::AppState;
-use crate::modules::security_db;
+use crate::modules::{geoip, ip_matcher, security_db};
+
+/// 已解析的客户端 IP (由 ip_filter / auth 注入请求 extensions，供后续中间件复用)
+#[derive(Debug, Clone)]
+pub struct ClientIp(pub String);
 
 /// IP 黑白名单过滤中间件
 pub async fn ip_filter_middleware(
     State(state): State<AppState>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
-    // 提取客户端 IP
-    let client_ip = extract_client_ip(&request);
+    // 提取客户端 IP (仅信任来自可信代理的转发头)
+    let trusted = state.security.read().await.security_monitor.trusted_proxies.clone();
+    let client_ip = extract_client_ip(&request, &trusted);
+    if let Some(ip) = &client_ip {
+        request.extensions_mut().insert(ClientIp(ip.clone()));
+    }
     
     if let Some(ip) = &client_ip {
         // 读取安全配置
         let security_config = state.security.read().await;
+
+        // GeoIP 数据库按配置加载 (同时用于访问日志的国家 / ASN 标注)
+        let geoip_config = &security_config.security_monitor.geoip;
+        geoip::ensure_loaded(geoip_config);
         
         // 1. 检查白名单 (如果启用白名单模式,只允许白名单 IP)
         if security_config.security_monitor.whitelist.enabled {
@@ -96,31 +110,13 @@ pub async fn ip_filter_middleware(
                     );
                     
                     // 记录被封禁的访问日志
-                    let log = security_db::IpAccessLog {
-                        id: uuid::Uuid::new_v4().to_string(),
-                        client_ip: ip.clone(),
-                        timestamp: chrono::Utc::now().timestamp(),
-                        method: Some(request.method().to_string()),
-                        path: Some(request.uri().to_string()),
-                        user_agent: request
-                            .headers()
-                            .get("user-agent")
-                            .and_then(|v| v.to_str().ok())
-                            .map(|s| s.to_string()),
-                        status: Some(403),
-                        duration: Some(0),
-                        api_key_hash: None,
-                        blocked: true,
-                        block_reason: Some(format!("IP in blacklist: {}", reason)),
-                        username: None,
-                    };
-                    
-                    tokio::spawn(async move {
-                        if let Err(e) = security_db::save_ip_access_log(&log) {
-                            tracing::error!("[IP Filter] Failed to save blocked access log: {}", e);
-                        }
-                    });
-                    
+                    save_blocked_log(
+                        &request,
+                        ip,
+                        geoip::lookup(ip),
+                        format!("IP in blacklist: {}", reason),
+                    );
+
                     return create_blocked_response(
                         ip,
                         &detailed_message,
@@ -135,38 +131,115 @@ pub async fn ip_filter_middleware(
                 }
             }
         }
+
+        // 3. [NEW] GeoIP / ASN 规则
+        if geoip_config.enabled {
+            let geo = geoip::lookup(ip);
+            if let Some(reason) = geoip::evaluate(geo.as_ref(), geoip_config) {
+                tracing::warn!("[IP Filter] IP {} blocked by GeoIP rule: {}", ip, reason);
+                save_blocked_log(&request, ip, geo, format!("GeoIP rule: {}", reason));
+                return create_blocked_response(
+                    ip,
+                    &format!("Access denied. Reason: {}.", reason),
+                );
+            }
+        }
     } else {
         tracing::warn!("[IP Filter] Unable to extract client IP from request");
     }
 
-    // 放行请求
-    next.run(request).await
+    // 放行请求 ([NEW] 完成后上报滥用检测)
+    let auto_ban = state.security.read().await.security_monitor.auto_ban.clone();
+    let path = request.uri().path().to_string();
+    let response = next.run(request).await;
+    if let Some(ip) = &client_ip {
+        crate::proxy::abuse_detector::observe_response(ip, &path, &response, &auto_ban);
+    }
+    response
 }
 
-/// 从请求中提取客户端 IP
-fn extract_client_ip(request: &Request) -> Option<String> {
-    // 1. 优先从 X-Forwarded-For 提取 (取第一个 IP)
-    request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            // 2. 备选从 X-Real-IP 提取
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        })
-        .or_else(|| {
-            // 3. 最后尝试从 ConnectInfo 获取 (TCP 连接 IP)
-            // 这可以解决本地开发/测试时没有代理头导致 IP 获取失败的问题
-            request
-                .extensions()
-                .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-                .map(|info| info.0.ip().to_string())
-        })
+/// 从请求中提取客户端 IP (优先复用已解析结果)
+pub fn extract_client_ip(request: &Request, trusted: &TrustedProxyConfig) -> Option<String> {
+    if let Some(ClientIp(ip)) = request.extensions().get::<ClientIp>() {
+        return Some(ip.clone());
+    }
+    let peer = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    resolve_client_ip(request.headers(), peer, trusted)
+}
+
+/// 根据 TCP 对端与转发头解析客户端 IP
+/// 对端不是可信代理时忽略所有转发头；X-Forwarded-For 从右向左跳过可信代理，取第一个非可信地址。
+/// 缺少对端地址 (未注入 ConnectInfo) 时沿用旧行为直接读取转发头
+pub fn resolve_client_ip(
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    trusted: &TrustedProxyConfig,
+) -> Option<String> {
+    let is_trusted = |addr: IpAddr| {
+        trusted
+            .proxies
+            .iter()
+            .any(|pattern| ip_matcher::pattern_contains(pattern, addr))
+    };
+
+    let peer = peer.map(ip_matcher::normalize_ip);
+    if let Some(peer) = peer {
+        if !is_trusted(peer) {
+            return Some(peer.to_string());
+        }
+    }
+
+    for name in &trusted.headers {
+        let Some(value) = headers.get(name.as_str()).and_then(|v| v.to_str().ok()) else {
+            continue;
+        };
+        let hops: Vec<IpAddr> = value.split(',').filter_map(ip_matcher::parse_ip).collect();
+        let client = hops
+            .iter()
+            .rev()
+            .find(|hop| !is_trusted(**hop))
+            .or_else(|| hops.first());
+        if let Some(client) = client {
+            return Some(client.to_string());
+        }
+    }
+
+    peer.map(|p| p.to_string())
+}
+
+/// 异步记录被拦截的访问日志
+fn save_blocked_log(request: &Request, ip: &str, geo: Option<geoip::GeoInfo>, reason: String) {
+    let geo = geo.unwrap_or_default();
+    let log = security_db::IpAccessLog {
+        id: uuid::Uuid::new_v4().to_string(),
+        client_ip: ip.to_string(),
+        timestamp: chrono::Utc::now().timestamp(),
+        method: Some(request.method().to_string()),
+        path: Some(request.uri().to_string()),
+        user_agent: request
+            .headers()
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string()),
+        status: Some(403),
+        duration: Some(0),
+        api_key_hash: None,
+        blocked: true,
+        block_reason: Some(reason),
+        username: None,
+        country: geo.country,
+        asn: geo.asn,
+        asn_org: geo.asn_org,
+    };
+
+    tokio::spawn(async move {
+        if let Err(e) = security_db::save_ip_access_log(&log) {
+            tracing::error!("[IP Filter] Failed to save blocked access log: {}", e);
+        }
+    });
 }
 
 /// 创建被封禁的响应
@@ -49,21 +49,11 @@ pub async fn monitor_middleware(
     
     let start = Instant::now();
     
-    // Extract client IP from headers (X-Forwarded-For or X-Real-IP)
-    // IMPORTANT: Extract from Request headers, not Response headers (since we want the client's IP)
-    // Note: We need to do this BEFORE consuming the request body if possible, or extract it from the original request
+    // Client IP resolved by ip_filter (honours trusted proxy settings)
     let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        });
+        .extensions()
+        .get::<crate::proxy::middleware::ip_filter::ClientIp>()
+        .map(|ip| ip.0.clone());
         
     let user_agent = request
         .headers()
@@ -112,7 +102,37 @@ pub async fn monitor_middleware(
         request
     };
     
-    let response = next.run(request).await;
+    // [NEW] 令牌级模型策略: 拒绝不允许的模型，并在作用域内应用强制映射与账号池
+    let token_policy = user_token_identity
+        .as_ref()
+        .map(|identity| identity.model_policy.clone())
+        .filter(|policy| !policy.is_empty());
+
+    let response = match token_policy {
+        Some(policy) => {
+            let username = user_token_identity.as_ref().map(|i| i.username.as_str()).unwrap_or("-");
+            match model.as_deref() {
+                Some(requested) => {
+                    let check = {
+                        let mapping = state.custom_mapping.read().await;
+                        crate::proxy::token_policy::check_model(&policy, requested, &mapping)
+                    };
+                    if let Err(blocked) = check {
+                        tracing::warn!("UserToken {} blocked from model {}", username, blocked);
+                        return crate::proxy::token_policy::model_forbidden_response(&blocked);
+                    }
+                }
+                // 请求体无法解析、缺少 model 或超出读取上限时无法校验，按拒绝处理
+                None if method == "POST" && crate::proxy::token_policy::restricts_models(&policy) => {
+                    tracing::warn!("UserToken {} blocked: request model could not be determined", username);
+                    return crate::proxy::token_policy::model_required_response();
+                }
+                None => {}
+            }
+            crate::proxy::token_policy::scope(policy, next.run(request)).await
+        }
+        None => next.run(request).await,
+    };
     
     // user_token_identity 已在上面从请求 extensions 中提取
     
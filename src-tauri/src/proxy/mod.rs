@@ -8,12 +8,16 @@ pub mod server;
 pub mod token_manager;
 
 // 新架构模块
+pub mod abuse_detector; // 滥用行为自动封禁
+pub mod admin_scopes; // 管理接口作用域
+pub mod admin_session; // 管理后台会话登录
 pub mod audio; // 音频处理模块
 pub mod cli_sync; // CLI 配置同步 (v3.3.35)
 pub mod droid_sync; // Droid (Factory CLI) 配置同步
 pub mod common; // 公共工具
 pub mod debug_logger;
 pub mod handlers; // API 端点处理器
+pub mod hedging; // 请求对冲 (降低尾延迟)
 pub mod mappers; // 协议转换器
 pub mod middleware; // Axum 中间件
 pub mod monitor; // 监控
@@ -23,7 +27,12 @@ pub mod proxy_pool; // 代理池管理器
 pub mod rate_limit; // 限流跟踪
 pub mod session_manager; // 会话指纹管理
 pub mod signature_cache; // Signature Cache (v3.3.16)
+pub mod simulator; // 调度策略模拟器
 pub mod sticky_config; // 粘性调度配置
+pub mod tls; // 内置 TLS 终止 (HTTPS 监听)
+pub mod token_budget; // 用户令牌预算与请求配额
+pub mod token_policy; // 用户令牌模型策略
+pub mod token_refresh; // 后台 Token 预刷新调度
 pub mod upstream; // 上游客户端
 pub mod vnpay_mitm; // VNPAY Transparent MITM Proxy
 pub mod zai_vision_mcp; // Built-in Vision MCP server state
@@ -33,6 +42,8 @@ pub use config::update_global_system_prompt_config;
 pub use config::update_thinking_budget_config;
 pub use config::update_image_thinking_mode;
 pub use config::update_vnpay_dns_redirect_config;
+pub use config::update_hedging_config;
+pub use tls::update_tls_config;
 pub use config::ProxyAuthMode;
 pub use config::ProxyConfig;
 pub use config::ProxyPoolConfig;
@@ -126,6 +126,7 @@ impl ProxyMonitor {
 
             // Sync to Security DB (IpAccessLogs) so it appears in Security Monitor
             if let Some(ip) = &log_to_save.client_ip {
+                let geo = crate::modules::geoip::lookup(ip).unwrap_or_default();
                 let security_log = crate::modules::security_db::IpAccessLog {
                     id: uuid::Uuid::new_v4().to_string(),
                     client_ip: ip.clone(),
@@ -139,6 +140,9 @@ impl ProxyMonitor {
                     blocked: false, // This comes from monitor, so it wasn't blocked by IP filter
                     block_reason: None,
                     username: log_to_save.username.clone(),
+                    country: geo.country,
+                    asn: geo.asn,
+                    asn_org: geo.asn_org,
                 };
 
                 if let Err(e) = crate::modules::security_db::save_ip_access_log(&security_log) {
@@ -1018,8 +1018,8 @@ fn sync_accounts_file(accounts_path: &PathBuf) -> Result<(), String> {
     let mut new_accounts: Vec<PluginAccount> = Vec::new();
 
     for acc in app_accounts {
-        // Skip disabled accounts (preserve existing logic)
-        if acc.disabled || acc.proxy_disabled {
+        // Skip disabled accounts (preserve existing logic) and provider accounts (no refresh_token)
+        if acc.disabled || acc.proxy_disabled || acc.is_provider() {
             continue;
         }
 
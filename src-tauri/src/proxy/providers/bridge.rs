@@ -0,0 +1,897 @@
+// OpenAI Chat Completions <-> Anthropic Messages 协议互转
+// 供通用 Provider 账号在客户端协议与上游协议不一致时使用 (例如 Claude Code 请求回落到 vLLM)。
+// 覆盖文本、图片、工具调用与推理内容；流式响应按 SSE 事件逐条转换。
+
+use serde_json::{json, Map, Value};
+
+/// Anthropic 要求 max_tokens 必填，OpenAI 请求未指定时使用的默认值
+const DEFAULT_ANTHROPIC_MAX_TOKENS: u64 = 4096;
+
+fn text_of(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn parse_arguments(arguments: Option<&Value>) -> Value {
+    match arguments {
+        Some(Value::String(s)) => serde_json::from_str(s).unwrap_or_else(|_| json!({})),
+        Some(v @ Value::Object(_)) => v.clone(),
+        _ => json!({}),
+    }
+}
+
+fn copy_fields(from: &Value, to: &mut Map<String, Value>, fields: &[(&str, &str)]) {
+    for (src, dst) in fields {
+        if let Some(v) = from.get(*src).filter(|v| !v.is_null()) {
+            to.insert(dst.to_string(), v.clone());
+        }
+    }
+}
+
+// ===== 请求转换 =====
+
+fn openai_part_to_anthropic(part: &Value) -> Option<Value> {
+    match part.get("type").and_then(|t| t.as_str()) {
+        Some("text") => Some(json!({"type": "text", "text": part.get("text")?.as_str()?})),
+        Some("image_url") => {
+            let url = part
+                .get("image_url")
+                .and_then(|i| i.get("url").or(Some(i)))
+                .and_then(|u| u.as_str())?;
+            let source = match url
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+            {
+                Some((media_type, data)) => {
+                    json!({"type": "base64", "media_type": media_type, "data": data})
+                }
+                None => json!({"type": "url", "url": url}),
+            };
+            Some(json!({"type": "image", "source": source}))
+        }
+        _ => None,
+    }
+}
+
+fn openai_content_to_blocks(content: Option<&Value>) -> Vec<Value> {
+    match content {
+        Some(Value::String(s)) if !s.is_empty() => vec![json!({"type": "text", "text": s})],
+        Some(Value::Array(parts)) => parts.iter().filter_map(openai_part_to_anthropic).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 追加消息，连续的同角色消息合并 (Anthropic 要求 user / assistant 交替)
+fn push_anthropic_message(messages: &mut Vec<Value>, role: &str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut() {
+        if last["role"] == role {
+            if let Some(content) = last["content"].as_array_mut() {
+                content.extend(blocks);
+                return;
+            }
+        }
+    }
+    messages.push(json!({"role": role, "content": blocks}));
+}
+
+/// OpenAI Chat Completions 请求 -> Anthropic Messages 请求
+pub fn openai_to_anthropic_request(body: &Value, model: &str) -> Value {
+    let mut system = Vec::new();
+    let mut messages = Vec::new();
+
+    for msg in body["messages"].as_array().into_iter().flatten() {
+        let role = msg["role"].as_str().unwrap_or("user");
+        match role {
+            "system" | "developer" => {
+                let text = text_of(&msg["content"]);
+                if !text.is_empty() {
+                    system.push(text);
+                }
+            }
+            "assistant" => {
+                let mut blocks = openai_content_to_blocks(msg.get("content"));
+                for call in msg["tool_calls"].as_array().into_iter().flatten() {
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": call["id"].as_str().unwrap_or_default(),
+                        "name": call["function"]["name"].as_str().unwrap_or_default(),
+                        "input": parse_arguments(call["function"].get("arguments")),
+                    }));
+                }
+                push_anthropic_message(&mut messages, "assistant", blocks);
+            }
+            "tool" | "function" => {
+                let block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": msg["tool_call_id"].as_str().unwrap_or_default(),
+                    "content": text_of(&msg["content"]),
+                });
+                push_anthropic_message(&mut messages, "user", vec![block]);
+            }
+            _ => {
+                let blocks = openai_content_to_blocks(msg.get("content"));
+                push_anthropic_message(&mut messages, "user", blocks);
+            }
+        }
+    }
+
+    let mut out = Map::new();
+    out.insert("model".into(), json!(model));
+    out.insert("messages".into(), Value::Array(messages));
+    if !system.is_empty() {
+        out.insert("system".into(), json!(system.join("\n\n")));
+    }
+    let max_tokens = body["max_completion_tokens"]
+        .as_u64()
+        .or_else(|| body["max_tokens"].as_u64())
+        .unwrap_or(DEFAULT_ANTHROPIC_MAX_TOKENS);
+    out.insert("max_tokens".into(), json!(max_tokens));
+    copy_fields(
+        body,
+        &mut out,
+        &[
+            ("temperature", "temperature"),
+            ("top_p", "top_p"),
+            ("stream", "stream"),
+        ],
+    );
+    match &body["stop"] {
+        Value::String(s) => {
+            out.insert("stop_sequences".into(), json!([s]));
+        }
+        stop @ Value::Array(_) => {
+            out.insert("stop_sequences".into(), stop.clone());
+        }
+        _ => {}
+    }
+
+    let tools: Vec<Value> = body["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| {
+            let f = t.get("function")?;
+            Some(json!({
+                "name": f["name"].as_str()?,
+                "description": f["description"].as_str().unwrap_or_default(),
+                "input_schema": f.get("parameters").cloned().unwrap_or_else(|| json!({"type": "object"})),
+            }))
+        })
+        .collect();
+    if !tools.is_empty() {
+        out.insert("tools".into(), Value::Array(tools));
+        let choice = match &body["tool_choice"] {
+            Value::String(s) if s == "required" => Some(json!({"type": "any"})),
+            Value::String(s) if s == "none" => Some(json!({"type": "none"})),
+            Value::Object(o) => o
+                .get("function")
+                .and_then(|f| f["name"].as_str())
+                .map(|name| json!({"type": "tool", "name": name})),
+            _ => None,
+        };
+        if let Some(choice) = choice {
+            out.insert("tool_choice".into(), choice);
+        }
+    }
+
+    Value::Object(out)
+}
+
+fn anthropic_blocks_to_openai(role: &str, content: &Value, out: &mut Vec<Value>) {
+    let blocks = match content {
+        Value::String(s) => {
+            out.push(json!({"role": role, "content": s}));
+            return;
+        }
+        Value::Array(blocks) => blocks,
+        _ => return,
+    };
+
+    let mut parts = Vec::new();
+    let mut tool_calls = Vec::new();
+    for block in blocks {
+        match block["type"].as_str() {
+            Some("text") => parts.push(json!({"type": "text", "text": block["text"]})),
+            Some("image") => {
+                let source = &block["source"];
+                let url = match source["type"].as_str() {
+                    Some("base64") => format!(
+                        "data:{};base64,{}",
+                        source["media_type"].as_str().unwrap_or("image/png"),
+                        source["data"].as_str().unwrap_or_default()
+                    ),
+                    _ => source["url"].as_str().unwrap_or_default().to_string(),
+                };
+                parts.push(json!({"type": "image_url", "image_url": {"url": url}}));
+            }
+            Some("tool_use") => tool_calls.push(json!({
+                "id": block["id"],
+                "type": "function",
+                "function": {
+                    "name": block["name"],
+                    "arguments": block.get("input").unwrap_or(&json!({})).to_string(),
+                },
+            })),
+            Some("tool_result") => out.push(json!({
+                "role": "tool",
+                "tool_call_id": block["tool_use_id"],
+                "content": text_of(&block["content"]),
+            })),
+            // thinking / redacted_thinking 等签名内容无法被其他厂商校验，直接丢弃
+            _ => {}
+        }
+    }
+
+    if parts.is_empty() && tool_calls.is_empty() {
+        return;
+    }
+    let only_text = parts.iter().all(|p| p["type"] == "text");
+    let content = if parts.is_empty() {
+        Value::Null
+    } else if only_text {
+        json!(parts
+            .iter()
+            .filter_map(|p| p["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    } else {
+        Value::Array(parts)
+    };
+    let mut msg = json!({"role": role, "content": content});
+    if !tool_calls.is_empty() {
+        msg["tool_calls"] = Value::Array(tool_calls);
+    }
+    out.push(msg);
+}
+
+/// Anthropic Messages 请求 -> OpenAI Chat Completions 请求
+pub fn anthropic_to_openai_request(body: &Value, model: &str) -> Value {
+    let mut messages = Vec::new();
+    let system = text_of(&body["system"]);
+    if !system.is_empty() {
+        messages.push(json!({"role": "system", "content": system}));
+    }
+    for msg in body["messages"].as_array().into_iter().flatten() {
+        let role = msg["role"].as_str().unwrap_or("user");
+        anthropic_blocks_to_openai(role, &msg["content"], &mut messages);
+    }
+
+    let mut out = Map::new();
+    out.insert("model".into(), json!(model));
+    out.insert("messages".into(), Value::Array(messages));
+    copy_fields(
+        body,
+        &mut out,
+        &[
+            ("max_tokens", "max_tokens"),
+            ("temperature", "temperature"),
+            ("top_p", "top_p"),
+            ("stop_sequences", "stop"),
+            ("stream", "stream"),
+        ],
+    );
+    if body["stream"].as_bool() == Some(true) {
+        out.insert("stream_options".into(), json!({"include_usage": true}));
+    }
+
+    let tools: Vec<Value> = body["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| {
+            Some(json!({
+                "type": "function",
+                "function": {
+                    "name": t["name"].as_str()?,
+                    "description": t["description"].as_str().unwrap_or_default(),
+                    "parameters": t.get("input_schema").cloned().unwrap_or_else(|| json!({"type": "object"})),
+                },
+            }))
+        })
+        .collect();
+    if !tools.is_empty() {
+        out.insert("tools".into(), Value::Array(tools));
+        let choice = match body["tool_choice"]["type"].as_str() {
+            Some("any") => Some(json!("required")),
+            Some("none") => Some(json!("none")),
+            Some("tool") => Some(json!({
+                "type": "function",
+                "function": {"name": body["tool_choice"]["name"]},
+            })),
+            _ => None,
+        };
+        if let Some(choice) = choice {
+            out.insert("tool_choice".into(), choice);
+        }
+    }
+
+    Value::Object(out)
+}
+
+// ===== 非流式响应转换 =====
+
+fn anthropic_stop_to_openai(reason: Option<&str>) -> &'static str {
+    match reason {
+        Some("max_tokens") => "length",
+        Some("tool_use") => "tool_calls",
+        _ => "stop",
+    }
+}
+
+fn openai_finish_to_anthropic(reason: Option<&str>) -> &'static str {
+    match reason {
+        Some("length") => "max_tokens",
+        Some("tool_calls") | Some("function_call") => "tool_use",
+        _ => "end_turn",
+    }
+}
+
+/// Anthropic Messages 响应 -> OpenAI Chat Completions 响应
+pub fn anthropic_to_openai_response(resp: &Value, model: &str) -> Value {
+    let mut text = String::new();
+    let mut reasoning = String::new();
+    let mut tool_calls = Vec::new();
+    for block in resp["content"].as_array().into_iter().flatten() {
+        match block["type"].as_str() {
+            Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+            Some("thinking") => reasoning.push_str(block["thinking"].as_str().unwrap_or_default()),
+            Some("tool_use") => tool_calls.push(json!({
+                "id": block["id"],
+                "type": "function",
+                "function": {
+                    "name": block["name"],
+                    "arguments": block.get("input").unwrap_or(&json!({})).to_string(),
+                },
+            })),
+            _ => {}
+        }
+    }
+
+    let mut message = json!({"role": "assistant", "content": text});
+    if !reasoning.is_empty() {
+        message["reasoning_content"] = json!(reasoning);
+    }
+    if !tool_calls.is_empty() {
+        message["tool_calls"] = Value::Array(tool_calls);
+    }
+    let input = resp["usage"]["input_tokens"].as_u64().unwrap_or(0);
+    let output = resp["usage"]["output_tokens"].as_u64().unwrap_or(0);
+
+    json!({
+        "id": resp["id"].as_str().map(|id| format!("chatcmpl-{}", id)).unwrap_or_default(),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": anthropic_stop_to_openai(resp["stop_reason"].as_str()),
+        }],
+        "usage": {
+            "prompt_tokens": input,
+            "completion_tokens": output,
+            "total_tokens": input + output,
+        },
+    })
+}
+
+/// OpenAI Chat Completions 响应 -> Anthropic Messages 响应
+pub fn openai_to_anthropic_response(resp: &Value, model: &str) -> Value {
+    let choice = &resp["choices"][0];
+    let message = &choice["message"];
+    let mut content = Vec::new();
+    let text = text_of(&message["content"]);
+    if !text.is_empty() {
+        content.push(json!({"type": "text", "text": text}));
+    }
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        content.push(json!({
+            "type": "tool_use",
+            "id": call["id"],
+            "name": call["function"]["name"],
+            "input": parse_arguments(call["function"].get("arguments")),
+        }));
+    }
+
+    json!({
+        "id": resp["id"].as_str().map(|id| format!("msg_{}", id)).unwrap_or_default(),
+        "type": "message",
+        "role": "assistant",
+        "model": model,
+        "content": content,
+        "stop_reason": openai_finish_to_anthropic(choice["finish_reason"].as_str()),
+        "stop_sequence": null,
+        "usage": {
+            "input_tokens": resp["usage"]["prompt_tokens"].as_u64().unwrap_or(0),
+            "output_tokens": resp["usage"]["completion_tokens"].as_u64().unwrap_or(0),
+        },
+    })
+}
+
+// ===== 流式响应转换 =====
+
+/// 按行切分 SSE 字节流 (上游分片可能在任意位置截断)
+#[derive(Default)]
+pub struct SseLineBuffer {
+    pending: Vec<u8>,
+}
+
+impl SseLineBuffer {
+    /// 追加一个分片，返回其中完整的 data 负载
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut out = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim_end().strip_prefix("data:") {
+                out.push(data.trim_start().to_string());
+            }
+        }
+        out
+    }
+}
+
+fn openai_chunk(id: &str, model: &str, created: i64, delta: Value, finish: Option<&str>) -> String {
+    let chunk = json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": created,
+        "model": model,
+        "choices": [{"index": 0, "delta": delta, "finish_reason": finish}],
+    });
+    format!("data: {}\n\n", chunk)
+}
+
+/// Anthropic SSE 事件 -> OpenAI chat.completion.chunk
+pub struct AnthropicToOpenAIStream {
+    id: String,
+    model: String,
+    created: i64,
+    /// Anthropic content block 索引 -> OpenAI tool_calls 索引
+    tool_indexes: Vec<(u64, usize)>,
+    input_tokens: u64,
+    output_tokens: u64,
+    finished: bool,
+}
+
+impl AnthropicToOpenAIStream {
+    pub fn new(model: &str) -> Self {
+        Self {
+            id: format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+            model: model.to_string(),
+            created: chrono::Utc::now().timestamp(),
+            tool_indexes: Vec::new(),
+            input_tokens: 0,
+            output_tokens: 0,
+            finished: false,
+        }
+    }
+
+    pub fn convert(&mut self, data: &str) -> Vec<String> {
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return Vec::new();
+        };
+        let chunk = |s: &Self, delta: Value, finish: Option<&str>| {
+            openai_chunk(&s.id, &s.model, s.created, delta, finish)
+        };
+        match event["type"].as_str() {
+            Some("message_start") => {
+                self.input_tokens = event["message"]["usage"]["input_tokens"]
+                    .as_u64()
+                    .unwrap_or(0);
+                vec![chunk(
+                    self,
+                    json!({"role": "assistant", "content": ""}),
+                    None,
+                )]
+            }
+            Some("content_block_start") if event["content_block"]["type"] == "tool_use" => {
+                let index = self.tool_indexes.len();
+                self.tool_indexes
+                    .push((event["index"].as_u64().unwrap_or(0), index));
+                let block = &event["content_block"];
+                vec![chunk(
+                    self,
+                    json!({"tool_calls": [{
+                        "index": index,
+                        "id": block["id"],
+                        "type": "function",
+                        "function": {"name": block["name"], "arguments": ""},
+                    }]}),
+                    None,
+                )]
+            }
+            Some("content_block_delta") => {
+                let delta = &event["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => {
+                        vec![chunk(self, json!({"content": delta["text"]}), None)]
+                    }
+                    Some("thinking_delta") => vec![chunk(
+                        self,
+                        json!({"reasoning_content": delta["thinking"]}),
+                        None,
+                    )],
+                    Some("input_json_delta") => {
+                        let block = event["index"].as_u64().unwrap_or(0);
+                        let Some((_, index)) = self.tool_indexes.iter().find(|(b, _)| *b == block)
+                        else {
+                            return Vec::new();
+                        };
+                        vec![chunk(
+                            self,
+                            json!({"tool_calls": [{
+                                "index": index,
+                                "function": {"arguments": delta["partial_json"]},
+                            }]}),
+                            None,
+                        )]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            Some("message_delta") => {
+                self.output_tokens = event["usage"]["output_tokens"]
+                    .as_u64()
+                    .unwrap_or(self.output_tokens);
+                let finish = anthropic_stop_to_openai(event["delta"]["stop_reason"].as_str());
+                vec![chunk(self, json!({}), Some(finish))]
+            }
+            Some("message_stop") => self.finish(),
+            Some("error") => {
+                self.finished = true;
+                vec![
+                    format!("data: {}\n\n", json!({"error": event["error"]})),
+                    "data: [DONE]\n\n".to_string(),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// 输出 usage 与结束标记 (上游未发送 message_stop 时由流结束触发)
+    pub fn finish(&mut self) -> Vec<String> {
+        if self.finished {
+            return Vec::new();
+        }
+        self.finished = true;
+        let usage = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [],
+            "usage": {
+                "prompt_tokens": self.input_tokens,
+                "completion_tokens": self.output_tokens,
+                "total_tokens": self.input_tokens + self.output_tokens,
+            },
+        });
+        vec![
+            format!("data: {}\n\n", usage),
+            "data: [DONE]\n\n".to_string(),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum OpenBlock {
+    None,
+    Text,
+    /// OpenAI tool_calls 索引
+    Tool(u64),
+}
+
+fn anthropic_event(kind: &str, data: Value) -> String {
+    format!("event: {}\ndata: {}\n\n", kind, data)
+}
+
+/// OpenAI chat.completion.chunk -> Anthropic SSE 事件
+pub struct OpenAIToAnthropicStream {
+    model: String,
+    started: bool,
+    block: OpenBlock,
+    block_index: u64,
+    stop_reason: &'static str,
+    input_tokens: u64,
+    output_tokens: u64,
+    finished: bool,
+}
+
+impl OpenAIToAnthropicStream {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            started: false,
+            block: OpenBlock::None,
+            block_index: 0,
+            stop_reason: "end_turn",
+            input_tokens: 0,
+            output_tokens: 0,
+            finished: false,
+        }
+    }
+
+    fn start(&mut self, out: &mut Vec<String>) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        out.push(anthropic_event(
+            "message_start",
+            json!({
+                "type": "message_start",
+                "message": {
+                    "id": format!("msg_{}", uuid::Uuid::new_v4().simple()),
+                    "type": "message",
+                    "role": "assistant",
+                    "model": self.model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0},
+                },
+            }),
+        ));
+    }
+
+    fn close_block(&mut self, out: &mut Vec<String>) {
+        if self.block == OpenBlock::None {
+            return;
+        }
+        out.push(anthropic_event(
+            "content_block_stop",
+            json!({"type": "content_block_stop", "index": self.block_index}),
+        ));
+        self.block = OpenBlock::None;
+        self.block_index += 1;
+    }
+
+    fn open_block(&mut self, block: OpenBlock, content_block: Value, out: &mut Vec<String>) {
+        self.close_block(out);
+        self.block = block;
+        out.push(anthropic_event(
+            "content_block_start",
+            json!({
+                "type": "content_block_start",
+                "index": self.block_index,
+                "content_block": content_block,
+            }),
+        ));
+    }
+
+    fn delta(&self, delta: Value) -> String {
+        anthropic_event(
+            "content_block_delta",
+            json!({"type": "content_block_delta", "index": self.block_index, "delta": delta}),
+        )
+    }
+
+    pub fn convert(&mut self, data: &str) -> Vec<String> {
+        if data == "[DONE]" {
+            return self.finish();
+        }
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        self.start(&mut out);
+
+        if let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) {
+            self.input_tokens = usage["prompt_tokens"].as_u64().unwrap_or(self.input_tokens);
+            self.output_tokens = usage["completion_tokens"]
+                .as_u64()
+                .unwrap_or(self.output_tokens);
+        }
+
+        let choice = &chunk["choices"][0];
+        let delta = &choice["delta"];
+        if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) {
+            if self.block != OpenBlock::Text {
+                self.open_block(
+                    OpenBlock::Text,
+                    json!({"type": "text", "text": ""}),
+                    &mut out,
+                );
+            }
+            out.push(self.delta(json!({"type": "text_delta", "text": text})));
+        }
+        for call in delta["tool_calls"].as_array().into_iter().flatten() {
+            let index = call["index"].as_u64().unwrap_or(0);
+            if self.block != OpenBlock::Tool(index) {
+                self.open_block(
+                    OpenBlock::Tool(index),
+                    json!({
+                        "type": "tool_use",
+                        "id": call["id"].as_str().map(str::to_string)
+                            .unwrap_or_else(|| format!("toolu_{}", uuid::Uuid::new_v4().simple())),
+                        "name": call["function"]["name"].as_str().unwrap_or_default(),
+                        "input": {},
+                    }),
+                    &mut out,
+                );
+            }
+            if let Some(args) = call["function"]["arguments"]
+                .as_str()
+                .filter(|a| !a.is_empty())
+            {
+                out.push(self.delta(json!({"type": "input_json_delta", "partial_json": args})));
+            }
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            self.stop_reason = openai_finish_to_anthropic(Some(reason));
+        }
+        out
+    }
+
+    /// 关闭未结束的内容块并输出 message_delta / message_stop
+    pub fn finish(&mut self) -> Vec<String> {
+        if self.finished {
+            return Vec::new();
+        }
+        self.finished = true;
+        let mut out = Vec::new();
+        self.start(&mut out);
+        self.close_block(&mut out);
+        out.push(anthropic_event(
+            "message_delta",
+            json!({
+                "type": "message_delta",
+                "delta": {"stop_reason": self.stop_reason, "stop_sequence": null},
+                "usage": {"input_tokens": self.input_tokens, "output_tokens": self.output_tokens},
+            }),
+        ));
+        out.push(anthropic_event(
+            "message_stop",
+            json!({"type": "message_stop"}),
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_round_trip_with_tools() {
+        let openai = json!({
+            "model": "gpt-4o",
+            "stream": true,
+            "stop": "END",
+            "messages": [
+                {"role": "system", "content": "be brief"},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "look"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+                ]},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "ls", "arguments": "{\"dir\":\"/\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "a.txt"},
+                {"role": "user", "content": "thanks"}
+            ],
+            "tools": [{"type": "function", "function": {"name": "ls", "parameters": {"type": "object"}}}],
+            "tool_choice": "required"
+        });
+
+        let claude = openai_to_anthropic_request(&openai, "claude-x");
+        assert_eq!(claude["system"], "be brief");
+        assert_eq!(claude["max_tokens"], DEFAULT_ANTHROPIC_MAX_TOKENS);
+        assert_eq!(claude["stop_sequences"], json!(["END"]));
+        assert_eq!(claude["tool_choice"], json!({"type": "any"}));
+        let messages = claude["messages"].as_array().unwrap();
+        // tool_result 与后续 user 文本合并为同一条 user 消息
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[0]["content"][1]["source"]["media_type"],
+            "image/png"
+        );
+        assert_eq!(messages[1]["content"][0]["input"], json!({"dir": "/"}));
+        assert_eq!(messages[2]["content"][0]["type"], "tool_result");
+        assert_eq!(messages[2]["content"][1]["text"], "thanks");
+
+        let back = anthropic_to_openai_request(&claude, "gpt-4o");
+        assert_eq!(back["stream_options"]["include_usage"], true);
+        assert_eq!(back["tool_choice"], "required");
+        let messages = back["messages"].as_array().unwrap();
+        let roles: Vec<&str> = messages
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["system", "user", "assistant", "tool", "user"]);
+        assert_eq!(
+            messages[2]["tool_calls"][0]["function"]["arguments"],
+            "{\"dir\":\"/\"}"
+        );
+        assert_eq!(messages[3]["tool_call_id"], "call_1");
+    }
+
+    #[test]
+    fn test_response_mapping() {
+        let claude = json!({
+            "id": "abc",
+            "content": [
+                {"type": "text", "text": "hi"},
+                {"type": "tool_use", "id": "t1", "name": "ls", "input": {"a": 1}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 3, "output_tokens": 4}
+        });
+        let openai = anthropic_to_openai_response(&claude, "m");
+        assert_eq!(openai["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(openai["choices"][0]["message"]["content"], "hi");
+        assert_eq!(openai["usage"]["total_tokens"], 7);
+
+        let back = openai_to_anthropic_response(&openai, "m");
+        assert_eq!(back["stop_reason"], "tool_use");
+        assert_eq!(back["content"][1]["input"], json!({"a": 1}));
+        assert_eq!(back["usage"]["output_tokens"], 4);
+    }
+
+    #[test]
+    fn test_stream_conversion() {
+        let mut lines = SseLineBuffer::default();
+        assert!(lines
+            .push(b"event: message_start\ndata: {\"type\":\"mess")
+            .is_empty());
+        let data = lines.push(b"age_start\",\"message\":{\"usage\":{\"input_tokens\":5}}}\n\n");
+        assert_eq!(data.len(), 1);
+
+        let mut to_openai = AnthropicToOpenAIStream::new("m");
+        let mut out = to_openai.convert(&data[0]);
+        for event in [
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "hi"}}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "t1", "name": "ls"}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{}"}}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 2}}),
+            json!({"type": "message_stop"}),
+        ] {
+            out.extend(to_openai.convert(&event.to_string()));
+        }
+        assert!(to_openai.finish().is_empty());
+        assert_eq!(out.last().unwrap(), "data: [DONE]\n\n");
+        assert!(out[4].contains("\"finish_reason\":\"tool_calls\""));
+        assert!(out[5].contains("\"total_tokens\":7"));
+
+        // 再转换回 Anthropic 事件
+        let mut to_claude = OpenAIToAnthropicStream::new("m");
+        let mut events = Vec::new();
+        for chunk in &out {
+            for data in SseLineBuffer::default().push(chunk.as_bytes()) {
+                events.extend(to_claude.convert(&data));
+            }
+        }
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|e| e.lines().next().unwrap().trim_start_matches("event: "))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert!(events[7].contains("\"stop_reason\":\"tool_use\""));
+        assert!(events[7].contains("\"output_tokens\":2"));
+    }
+}
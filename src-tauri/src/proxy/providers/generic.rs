@@ -0,0 +1,529 @@
+// 通用上游 Provider 账号 (account_type = "provider")
+// 第三方 OpenAI / Anthropic 兼容端点与自建 vLLM 等以账号形式加入 TokenManager 号池：
+// 按模型列表参与路由，按 routing 决定排在 Google 号池之前、与之轮询或仅作兜底，
+// 请求在客户端协议与端点协议不一致时经 bridge 互转。429 / 5xx / 鉴权失败 / 网络错误切换到下一个候选，
+// 全部失败时交回调用方继续走 Google 号池。出口沿用全局上游代理 (与 z.ai 一致)。
+
+use std::collections::HashSet;
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use futures::StreamExt;
+use serde_json::Value;
+
+use super::bridge;
+use crate::models::{Account, ProviderConfig, ProviderProtocol, ProviderRouting};
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::server::AppState;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Provider 账号在号池中的运行时视图
+#[derive(Debug, Clone)]
+pub struct ProviderEndpoint {
+    pub account_id: String,
+    pub name: String,
+    pub config: ProviderConfig,
+    pub health_score: f32,
+}
+
+impl ProviderEndpoint {
+    /// 从账号构建；非 Provider 账号或配置缺失时返回 None
+    pub fn from_account(account: &Account) -> Option<Self> {
+        if !account.is_provider() {
+            return None;
+        }
+        let config = account.provider.clone()?;
+        Some(Self {
+            account_id: account.id.clone(),
+            name: account.email.clone(),
+            config,
+            health_score: 1.0,
+        })
+    }
+
+    /// 端点是否可服务该模型
+    pub fn serves(&self, model: &str) -> bool {
+        self.config
+            .models
+            .iter()
+            .any(|pattern| wildcard_match(pattern, model))
+    }
+
+    /// 上游模型名：精确映射 > 通配映射 (非通配字符越多越优先) > 原样透传
+    pub fn upstream_model(&self, model: &str) -> String {
+        let mapping = &self.config.model_mapping;
+        if let Some(target) = mapping.get(model) {
+            return target.clone();
+        }
+        mapping
+            .iter()
+            .filter(|(pattern, _)| pattern.contains('*') && wildcard_match(pattern, model))
+            .max_by_key(|(pattern, _)| pattern.len() - pattern.matches('*').count())
+            .map(|(_, target)| target.clone())
+            .unwrap_or_else(|| model.to_string())
+    }
+
+    fn url(&self) -> String {
+        let base = self.config.base_url.trim_end_matches('/');
+        match self.config.protocol {
+            ProviderProtocol::OpenAI => format!("{}/chat/completions", base),
+            ProviderProtocol::Anthropic => format!("{}/v1/messages", base),
+        }
+    }
+}
+
+/// 调度阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderStage {
+    /// 进入 Google 号池之前 (primary / 命中槽位的 pooled / Google 不可用时的 fallback)
+    BeforePool,
+    /// Google 号池已全部失败，剩余所有候选
+    AfterPool,
+}
+
+/// 排列本次请求需要依次尝试的 Provider
+///
+/// 候选先按轮询位置旋转、再按健康分稳定排序；pooled 端点与 Google 账号共享槽位，
+/// 槽位落在 Google 之外时才排入本轮。
+pub fn plan_attempts(
+    mut candidates: Vec<ProviderEndpoint>,
+    stage: ProviderStage,
+    google_accounts: usize,
+    google_available: bool,
+    rotation: usize,
+) -> Vec<ProviderEndpoint> {
+    if candidates.is_empty() {
+        return candidates;
+    }
+    candidates.sort_by(|a, b| a.account_id.cmp(&b.account_id));
+    let len = candidates.len();
+    candidates.rotate_left(rotation % len);
+    candidates.sort_by(|a, b| {
+        b.health_score
+            .partial_cmp(&a.health_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    if stage == ProviderStage::AfterPool {
+        return candidates;
+    }
+
+    let of = |routing: ProviderRouting| {
+        candidates
+            .iter()
+            .filter(move |p| p.config.routing == routing)
+            .cloned()
+    };
+    let pooled = of(ProviderRouting::Pooled).count();
+    let pooled_hit =
+        !google_available || rotation % (google_accounts + pooled).max(1) >= google_accounts;
+
+    let mut plan: Vec<ProviderEndpoint> = of(ProviderRouting::Primary).collect();
+    if pooled_hit {
+        plan.extend(of(ProviderRouting::Pooled));
+    }
+    if !google_available {
+        plan.extend(of(ProviderRouting::Fallback));
+    }
+    plan
+}
+
+/// 单次转发失败
+struct Failure {
+    status: u16,
+    retry_after: Option<String>,
+    message: String,
+}
+
+impl Failure {
+    /// 可切换到下一个候选的失败 (其余 4xx 视为请求本身的问题，直接返回客户端)
+    fn retryable(&self) -> bool {
+        matches!(self.status, 401 | 403 | 404 | 408 | 429) || self.status >= 500
+    }
+}
+
+fn build_headers(endpoint: &ProviderEndpoint, incoming: &HeaderMap) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let api_key = endpoint.config.api_key.trim();
+    match endpoint.config.protocol {
+        ProviderProtocol::OpenAI => {
+            if !api_key.is_empty() {
+                if let Ok(v) = HeaderValue::from_str(&format!("Bearer {}", api_key)) {
+                    headers.insert(header::AUTHORIZATION, v);
+                }
+            }
+        }
+        ProviderProtocol::Anthropic => {
+            if let Ok(v) = HeaderValue::from_str(api_key) {
+                headers.insert("x-api-key", v);
+            }
+            let version = incoming
+                .get("anthropic-version")
+                .cloned()
+                .unwrap_or(HeaderValue::from_static(ANTHROPIC_VERSION));
+            headers.insert("anthropic-version", version);
+            if let Some(beta) = incoming.get("anthropic-beta") {
+                headers.insert("anthropic-beta", beta.clone());
+            }
+        }
+    }
+    headers
+}
+
+fn json_response(
+    status: StatusCode,
+    endpoint: &ProviderEndpoint,
+    model: &str,
+    body: Value,
+) -> Response {
+    let mut resp = (status, axum::Json(body)).into_response();
+    set_route_headers(&mut resp, endpoint, model);
+    resp
+}
+
+fn set_route_headers(resp: &mut Response, endpoint: &ProviderEndpoint, model: &str) {
+    let headers = resp.headers_mut();
+    if let Ok(v) = HeaderValue::from_str(&endpoint.name) {
+        headers.insert("X-Account-Email", v);
+    }
+    if let Ok(v) = HeaderValue::from_str(model) {
+        headers.insert("X-Mapped-Model", v);
+    }
+}
+
+async fn forward_once(
+    state: &AppState,
+    endpoint: &ProviderEndpoint,
+    client_protocol: ProviderProtocol,
+    incoming: &HeaderMap,
+    body: &Value,
+    model: &str,
+) -> Result<Response, Failure> {
+    let upstream_model = endpoint.upstream_model(model);
+    let stream = body["stream"].as_bool().unwrap_or(false);
+    let request = match (client_protocol, endpoint.config.protocol) {
+        (ProviderProtocol::OpenAI, ProviderProtocol::Anthropic) => {
+            bridge::openai_to_anthropic_request(body, &upstream_model)
+        }
+        (ProviderProtocol::Anthropic, ProviderProtocol::OpenAI) => {
+            bridge::anthropic_to_openai_request(body, &upstream_model)
+        }
+        _ => {
+            let mut same = body.clone();
+            same["model"] = Value::String(upstream_model.clone());
+            same
+        }
+    };
+
+    let upstream_proxy = state.upstream_proxy.read().await.clone();
+    let client = super::zai_anthropic::build_client(Some(upstream_proxy), state.request_timeout)
+        .map_err(|e| Failure {
+            status: 500,
+            retry_after: None,
+            message: e,
+        })?;
+
+    tracing::debug!(
+        "[Provider] {} -> {} ({:?}, model {})",
+        endpoint.name,
+        endpoint.url(),
+        endpoint.config.protocol,
+        upstream_model
+    );
+    let resp = client
+        .post(endpoint.url())
+        .headers(build_headers(endpoint, incoming))
+        .body(serde_json::to_vec(&request).unwrap_or_default())
+        .send()
+        .await
+        .map_err(|e| Failure {
+            // 网络错误按 503 处理，触发短时避让
+            status: 503,
+            retry_after: None,
+            message: format!("Upstream request failed: {}", e),
+        })?;
+
+    let status = resp.status().as_u16();
+    if !resp.status().is_success() {
+        let retry_after = resp
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let message = resp.text().await.unwrap_or_default();
+        return Err(Failure {
+            status,
+            retry_after,
+            message,
+        });
+    }
+
+    let same_protocol = client_protocol == endpoint.config.protocol;
+    if !stream {
+        let bytes = resp.bytes().await.map_err(|e| Failure {
+            status: 502,
+            retry_after: None,
+            message: format!("Failed to read upstream response: {}", e),
+        })?;
+        let upstream: Value = serde_json::from_slice(&bytes).map_err(|e| Failure {
+            status: 502,
+            retry_after: None,
+            message: format!("Invalid upstream response: {}", e),
+        })?;
+        let body = match (same_protocol, client_protocol) {
+            (true, _) => upstream,
+            (false, ProviderProtocol::OpenAI) => {
+                bridge::anthropic_to_openai_response(&upstream, model)
+            }
+            (false, ProviderProtocol::Anthropic) => {
+                bridge::openai_to_anthropic_response(&upstream, model)
+            }
+        };
+        return Ok(json_response(
+            StatusCode::OK,
+            endpoint,
+            &upstream_model,
+            body,
+        ));
+    }
+
+    let upstream = resp.bytes_stream();
+    let body = if same_protocol {
+        Body::from_stream(upstream.map(|chunk| {
+            Ok::<Bytes, std::io::Error>(
+                chunk.unwrap_or_else(|e| Bytes::from(format!("Upstream stream error: {}", e))),
+            )
+        }))
+    } else {
+        let mut converter = StreamConverter::new(client_protocol, model);
+        let mut lines = bridge::SseLineBuffer::default();
+        let converted = async_stream::stream! {
+            futures::pin_mut!(upstream);
+            while let Some(chunk) = upstream.next().await {
+                match chunk {
+                    Ok(bytes) => {
+                        for data in lines.push(&bytes) {
+                            for event in converter.convert(&data) {
+                                yield Ok::<Bytes, std::io::Error>(Bytes::from(event));
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("[Provider] Upstream stream error: {}", e);
+                        break;
+                    }
+                }
+            }
+            for event in converter.finish() {
+                yield Ok(Bytes::from(event));
+            }
+        };
+        Body::from_stream(converted)
+    };
+
+    let mut resp = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response());
+    set_route_headers(&mut resp, endpoint, &upstream_model);
+    Ok(resp)
+}
+
+/// 跨协议流式转换器
+enum StreamConverter {
+    ToOpenAI(bridge::AnthropicToOpenAIStream),
+    ToAnthropic(bridge::OpenAIToAnthropicStream),
+}
+
+impl StreamConverter {
+    fn new(client_protocol: ProviderProtocol, model: &str) -> Self {
+        match client_protocol {
+            ProviderProtocol::OpenAI => Self::ToOpenAI(bridge::AnthropicToOpenAIStream::new(model)),
+            ProviderProtocol::Anthropic => {
+                Self::ToAnthropic(bridge::OpenAIToAnthropicStream::new(model))
+            }
+        }
+    }
+
+    fn convert(&mut self, data: &str) -> Vec<String> {
+        match self {
+            Self::ToOpenAI(c) => c.convert(data),
+            Self::ToAnthropic(c) => c.convert(data),
+        }
+    }
+
+    fn finish(&mut self) -> Vec<String> {
+        match self {
+            Self::ToOpenAI(c) => c.finish(),
+            Self::ToAnthropic(c) => c.finish(),
+        }
+    }
+}
+
+fn error_body(client_protocol: ProviderProtocol, message: &str) -> Value {
+    match client_protocol {
+        ProviderProtocol::OpenAI => serde_json::json!({
+            "error": {"message": message, "type": "upstream_error"}
+        }),
+        ProviderProtocol::Anthropic => serde_json::json!({
+            "type": "error",
+            "error": {"type": "api_error", "message": message}
+        }),
+    }
+}
+
+/// 按调度阶段尝试可服务该请求的 Provider 账号
+///
+/// 返回 None 表示没有候选或候选全部失败，调用方继续走 Google 号池 (或返回自身的错误)；
+/// 已尝试的账号记入 `attempted`，号池耗尽后的第二轮不会重复尝试。
+pub async fn dispatch(
+    state: &AppState,
+    client_protocol: ProviderProtocol,
+    incoming: &HeaderMap,
+    body: &Value,
+    stage: ProviderStage,
+    attempted: &mut HashSet<String>,
+) -> Option<Response> {
+    let model = body["model"].as_str().unwrap_or_default().to_string();
+    let candidates = state
+        .token_manager
+        .select_providers(&model, stage, attempted)
+        .await;
+
+    for endpoint in candidates {
+        attempted.insert(endpoint.account_id.clone());
+        match forward_once(state, &endpoint, client_protocol, incoming, body, &model).await {
+            Ok(resp) => {
+                state
+                    .token_manager
+                    .mark_provider_success(&endpoint.account_id);
+                return Some(resp);
+            }
+            Err(failure) => {
+                tracing::warn!(
+                    "[Provider] {} failed for {} (HTTP {}): {}",
+                    endpoint.name,
+                    model,
+                    failure.status,
+                    failure.message
+                );
+                state
+                    .token_manager
+                    .mark_provider_failure(
+                        &endpoint.account_id,
+                        failure.status,
+                        failure.retry_after.as_deref(),
+                        &failure.message,
+                    )
+                    .await;
+                if !failure.retryable() {
+                    let status =
+                        StatusCode::from_u16(failure.status).unwrap_or(StatusCode::BAD_GATEWAY);
+                    let body = error_body(client_protocol, &failure.message);
+                    return Some(json_response(status, &endpoint, &model, body));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn endpoint(id: &str, routing: ProviderRouting, health: f32) -> ProviderEndpoint {
+        ProviderEndpoint {
+            account_id: id.to_string(),
+            name: id.to_string(),
+            config: ProviderConfig {
+                base_url: "http://localhost:8000/v1/".to_string(),
+                api_key: String::new(),
+                protocol: ProviderProtocol::OpenAI,
+                models: vec!["gpt-4o*".to_string(), "llama-3".to_string()],
+                model_mapping: HashMap::from([
+                    ("gpt-4o*".to_string(), "meta/llama".to_string()),
+                    ("gpt-4o-mini*".to_string(), "qwen".to_string()),
+                ]),
+                routing,
+            },
+            health_score: health,
+        }
+    }
+
+    fn ids(plan: &[ProviderEndpoint]) -> Vec<&str> {
+        plan.iter().map(|p| p.account_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_model_matching_and_mapping() {
+        let p = endpoint("a", ProviderRouting::Fallback, 1.0);
+        assert!(p.serves("gpt-4o-2024"));
+        assert!(p.serves("llama-3"));
+        assert!(!p.serves("claude-sonnet-4-5"));
+        assert_eq!(p.upstream_model("gpt-4o"), "meta/llama");
+        assert_eq!(p.upstream_model("gpt-4o-mini-1"), "qwen");
+        assert_eq!(p.upstream_model("llama-3"), "llama-3");
+        assert_eq!(p.url(), "http://localhost:8000/v1/chat/completions");
+    }
+
+    #[test]
+    fn test_plan_attempts() {
+        let candidates = vec![
+            endpoint("fallback", ProviderRouting::Fallback, 1.0),
+            endpoint("pooled", ProviderRouting::Pooled, 1.0),
+            endpoint("primary-b", ProviderRouting::Primary, 1.0),
+            endpoint("primary-a", ProviderRouting::Primary, 0.5),
+        ];
+        let plan = |stage, google_available, rotation| {
+            plan_attempts(candidates.clone(), stage, 2, google_available, rotation)
+        };
+
+        // 健康分优先，Google 可用时 fallback 不参与；槽位 0/1 属于 Google 账号
+        assert_eq!(
+            ids(&plan(ProviderStage::BeforePool, true, 0)),
+            ["primary-b", "primary-a"]
+        );
+        assert_eq!(
+            ids(&plan(ProviderStage::BeforePool, true, 2)),
+            ["primary-b", "primary-a", "pooled"]
+        );
+        // Google 不可用时 pooled 与 fallback 依次排在 primary 之后
+        assert_eq!(
+            ids(&plan(ProviderStage::BeforePool, false, 0)),
+            ["primary-b", "primary-a", "pooled", "fallback"]
+        );
+        assert_eq!(plan(ProviderStage::AfterPool, true, 0).len(), 4);
+
+        // 同等健康分时按轮询位置旋转
+        let equal = vec![
+            endpoint("x", ProviderRouting::Primary, 1.0),
+            endpoint("y", ProviderRouting::Primary, 1.0),
+        ];
+        assert_eq!(
+            ids(&plan_attempts(
+                equal.clone(),
+                ProviderStage::BeforePool,
+                0,
+                true,
+                0
+            )),
+            ["x", "y"]
+        );
+        assert_eq!(
+            ids(&plan_attempts(equal, ProviderStage::BeforePool, 0, true, 1)),
+            ["y", "x"]
+        );
+    }
+}
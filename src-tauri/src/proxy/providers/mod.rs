@@ -1,2 +1,3 @@
+pub mod bridge;
+pub mod generic;
 pub mod zai_anthropic;
-
@@ -25,6 +25,92 @@ pub fn init_global_proxy_pool(config: Arc<RwLock<ProxyPoolConfig>>) -> Arc<Proxy
     manager
 }
 
+/// [NEW] 批量设置账号代理绑定 (proxy_id 为 None 表示解绑)，按顺序返回每条的结果
+/// 代理池已初始化时经由管理器 (校验代理存在与 max_accounts)，否则直接写入配置文件
+pub async fn apply_account_bindings(
+    changes: &[(String, Option<String>)],
+) -> Vec<Result<(), String>> {
+    if let Some(pool) = get_global_proxy_pool() {
+        let mut results = Vec::with_capacity(changes.len());
+        for (account_id, proxy_id) in changes {
+            results.push(match proxy_id {
+                Some(proxy_id) => {
+                    pool.bind_account_to_proxy(account_id.clone(), proxy_id.clone())
+                        .await
+                }
+                None => {
+                    pool.unbind_account_proxy(account_id.clone()).await;
+                    Ok(())
+                }
+            });
+        }
+        return results;
+    }
+
+    let saved = crate::modules::config::load_app_config().and_then(|mut config| {
+        let pool = &mut config.proxy.proxy_pool;
+        let mut events = Vec::new();
+        let results: Vec<Result<(), String>> = changes
+            .iter()
+            .map(|(account_id, proxy_id)| match proxy_id {
+                Some(proxy_id) if !pool.proxies.iter().any(|p| &p.id == proxy_id) => {
+                    Err(format!("Proxy {} not found", proxy_id))
+                }
+                Some(proxy_id) => {
+                    let previous = pool
+                        .account_bindings
+                        .insert(account_id.clone(), proxy_id.clone());
+                    events.push((account_id, previous, Some(proxy_id)));
+                    Ok(())
+                }
+                None => {
+                    let previous = pool.account_bindings.remove(account_id);
+                    events.push((account_id, previous, None));
+                    Ok(())
+                }
+            })
+            .collect();
+        crate::modules::config::save_app_config(&config)?;
+        for (account_id, previous, proxy_id) in events {
+            record_binding_event(
+                account_id,
+                previous.as_deref(),
+                proxy_id.map(String::as_str),
+            );
+        }
+        Ok(results)
+    });
+    saved.unwrap_or_else(|e| changes.iter().map(|_| Err(e.clone())).collect())
+}
+
+/// [NEW] 记录账号代理绑定变化 (绑定关系未变化时不记录)
+fn record_binding_event(account_id: &str, previous: Option<&str>, current: Option<&str>) {
+    use crate::modules::account_events::{self, AccountEventKind};
+    match (previous, current) {
+        (previous, Some(proxy_id)) if previous != Some(proxy_id) => {
+            let detail = match previous {
+                Some(old) => format!("{} (was {})", proxy_id, old),
+                None => proxy_id.to_string(),
+            };
+            account_events::record(account_id, AccountEventKind::ProxyBound, &detail);
+        }
+        (Some(old), None) => {
+            account_events::record(account_id, AccountEventKind::ProxyUnbound, old)
+        }
+        _ => {}
+    }
+}
+
+/// [NEW] 账号代理绑定快照 (account_id -> proxy_id)，代理池未初始化时读取配置文件
+pub fn account_bindings_snapshot() -> std::collections::HashMap<String, String> {
+    match get_global_proxy_pool() {
+        Some(pool) => pool.get_all_bindings_snapshot(),
+        None => crate::modules::config::load_app_config()
+            .map(|config| config.proxy.proxy_pool.account_bindings)
+            .unwrap_or_default(),
+    }
+}
+
 /// 代理配置 (用于构建 reqwest Client)
 /// 注意：重命名为 PoolProxyConfig 以避免与 config::ProxyConfig 冲突
 #[derive(Debug, Clone)]
@@ -298,23 +384,25 @@ impl ProxyPoolManager {
         }
 
         // 更新内存中的绑定
-        self.account_bindings.insert(account_id.clone(), proxy_id.clone());
+        let previous = self.account_bindings.insert(account_id.clone(), proxy_id.clone());
 
         // 持久化到配置文件
         self.persist_bindings().await;
 
         tracing::info!("[ProxyPool] Bound account {} to proxy {}", account_id, proxy_id);
+        record_binding_event(&account_id, previous.as_deref(), Some(&proxy_id));
         Ok(())
     }
 
     /// 解绑账号代理
     pub async fn unbind_account_proxy(&self, account_id: String) {
-        self.account_bindings.remove(&account_id);
+        let previous = self.account_bindings.remove(&account_id).map(|(_, v)| v);
 
         // 持久化到配置文件
         self.persist_bindings().await;
 
         tracing::info!("[ProxyPool] Unbound account {}", account_id);
+        record_binding_event(&account_id, previous.as_deref(), None);
     }
 
     /// 获取账号当前绑定的代理ID
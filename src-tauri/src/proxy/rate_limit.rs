@@ -2,6 +2,8 @@ use dashmap::DashMap;
 use std::time::{SystemTime, Duration};
 use regex::Regex;
 
+use crate::modules::runtime_state_db::{FailureCountRecord, RateLimitRecord};
+
 /// 限流原因类型
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RateLimitReason {
@@ -17,6 +19,30 @@ pub enum RateLimitReason {
     Unknown,
 }
 
+impl RateLimitReason {
+    /// 持久化用的稳定字符串表示
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::QuotaExhausted => "quota_exhausted",
+            Self::RateLimitExceeded => "rate_limit_exceeded",
+            Self::ModelCapacityExhausted => "model_capacity_exhausted",
+            Self::ServerError => "server_error",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// 从持久化字符串还原，未知值映射为 Unknown
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "quota_exhausted" => Self::QuotaExhausted,
+            "rate_limit_exceeded" => Self::RateLimitExceeded,
+            "model_capacity_exhausted" => Self::ModelCapacityExhausted,
+            "server_error" => Self::ServerError,
+            _ => Self::Unknown,
+        }
+    }
+}
+
 /// 限流信息
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -504,6 +530,39 @@ impl RateLimitTracker {
         self.limits.get(account_id).map(|r| r.clone())
     }
     
+    /// 仍有效的账号级限流 (重置时间戳, 原因)，用于健康状态判定
+    pub fn active_account_limit(&self, account_id: &str) -> Option<(i64, RateLimitReason)> {
+        self.limits
+            .get(account_id)
+            .filter(|info| info.reset_time > SystemTime::now())
+            .map(|info| (to_unix_secs(info.reset_time), info.reason))
+    }
+
+    /// 仍处于模型级限流中的模型 (已排序)
+    pub fn limited_models(&self, account_id: &str) -> Vec<String> {
+        let now = SystemTime::now();
+        let prefix = format!("{}:", account_id);
+        let mut models: Vec<String> = self
+            .limits
+            .iter()
+            .filter(|e| e.key().starts_with(&prefix) && e.value().reset_time > now)
+            .map(|e| e.key()[prefix.len()..].to_string())
+            .collect();
+        models.sort();
+        models
+    }
+
+    /// 连续失败次数 (超过过期时间的计数视为 0)
+    pub fn failure_count(&self, account_id: &str) -> u32 {
+        self.failure_counts
+            .get(account_id)
+            .filter(|entry| {
+                let elapsed = SystemTime::now().duration_since(entry.1).unwrap_or_default();
+                elapsed.as_secs() <= FAILURE_COUNT_EXPIRY_SECONDS
+            })
+            .map_or(0, |entry| entry.0)
+    }
+
     /// 检查账号是否仍在限流中
     /// 检查账号是否仍在限流中 (支持模型级)
     pub fn is_rate_limited(&self, account_id: &str, model: Option<&str>) -> bool {
@@ -550,6 +609,12 @@ impl RateLimitTracker {
         self.limits.remove(account_id).is_some()
     }
     
+    /// 清除指定账号在某个模型上的模型级限流记录
+    pub fn clear_model(&self, account_id: &str, model: &str) -> bool {
+        let key = self.get_limit_key(account_id, Some(model));
+        self.limits.remove(&key).is_some()
+    }
+
     /// 清除所有限流记录 (乐观重置策略)
     /// 
     /// 用于乐观重置机制,当所有账号都被限流但等待时间很短时,
@@ -559,6 +624,96 @@ impl RateLimitTracker {
         self.limits.clear();
         tracing::warn!("🔄 Optimistic reset: Cleared all {} rate limit record(s)", count);
     }
+
+    /// 导出当前限流记录与失败计数 (用于持久化快照，已过期的记录不导出)
+    pub fn export_state(&self) -> (Vec<RateLimitRecord>, Vec<FailureCountRecord>) {
+        let now = SystemTime::now();
+        let limits = self
+            .limits
+            .iter()
+            .filter(|e| e.value().reset_time > now)
+            .map(|e| {
+                let info = e.value();
+                RateLimitRecord {
+                    key: e.key().clone(),
+                    reset_time: to_unix_secs(info.reset_time),
+                    retry_after_sec: info.retry_after_sec,
+                    detected_at: to_unix_secs(info.detected_at),
+                    reason: info.reason.as_str().to_string(),
+                    model: info.model.clone(),
+                }
+            })
+            .collect();
+
+        let failures = self
+            .failure_counts
+            .iter()
+            .map(|e| FailureCountRecord {
+                account_id: e.key().clone(),
+                count: e.value().0,
+                last_failure: to_unix_secs(e.value().1),
+            })
+            .collect();
+
+        (limits, failures)
+    }
+
+    /// 从持久化快照恢复状态，跳过已过期的记录；内存中已有的记录优先
+    /// 返回恢复的 (限流记录数, 失败计数数)
+    pub fn import_state(
+        &self,
+        limits: Vec<RateLimitRecord>,
+        failures: Vec<FailureCountRecord>,
+    ) -> (usize, usize) {
+        let now = SystemTime::now();
+        let mut restored_limits = 0;
+        for record in limits {
+            let reset_time = from_unix_secs(record.reset_time);
+            if reset_time <= now || self.limits.contains_key(&record.key) {
+                continue;
+            }
+            self.limits.insert(
+                record.key,
+                RateLimitInfo {
+                    reset_time,
+                    retry_after_sec: record.retry_after_sec,
+                    detected_at: from_unix_secs(record.detected_at),
+                    reason: RateLimitReason::from_str_lossy(&record.reason),
+                    model: record.model,
+                },
+            );
+            restored_limits += 1;
+        }
+
+        let mut restored_failures = 0;
+        for record in failures {
+            let last_failure = from_unix_secs(record.last_failure);
+            let elapsed = now
+                .duration_since(last_failure)
+                .unwrap_or(Duration::from_secs(0))
+                .as_secs();
+            if elapsed > FAILURE_COUNT_EXPIRY_SECONDS
+                || self.failure_counts.contains_key(&record.account_id)
+            {
+                continue;
+            }
+            self.failure_counts
+                .insert(record.account_id, (record.count, last_failure));
+            restored_failures += 1;
+        }
+
+        (restored_limits, restored_failures)
+    }
+}
+
+fn to_unix_secs(t: SystemTime) -> i64 {
+    t.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn from_unix_secs(secs: i64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
 }
 
 impl Default for RateLimitTracker {
@@ -613,6 +768,22 @@ mod tests {
         assert!(wait > 25 && wait <= 30);
     }
 
+    #[test]
+    fn test_health_signal_accessors() {
+        let tracker = RateLimitTracker::new();
+        tracker.parse_from_error("acc1", 429, Some("30"), "quota exhausted", Some("gemini-3-pro".to_string()), &[]);
+        assert!(tracker.active_account_limit("acc1").is_none());
+        assert_eq!(tracker.limited_models("acc1"), vec!["gemini-3-pro".to_string()]);
+        assert!(tracker.limited_models("acc").is_empty());
+
+        tracker.parse_from_error("acc1", 429, None, "quota exhausted", None, &[60, 300]);
+        let (_, reason) = tracker.active_account_limit("acc1").unwrap();
+        assert_eq!(reason, RateLimitReason::QuotaExhausted);
+        assert_eq!(tracker.failure_count("acc1"), 1);
+        tracker.mark_success("acc1");
+        assert_eq!(tracker.failure_count("acc1"), 0);
+    }
+
     #[test]
     fn test_safety_buffer() {
         let tracker = RateLimitTracker::new();
@@ -657,6 +828,37 @@ mod tests {
         assert_eq!(info.retry_after_sec, 60, "429 应该从第 1 次退避开始(60秒),而不是被 5xx 污染");
     }
 
+    #[test]
+    fn test_export_import_state_drops_expired() {
+        let tracker = RateLimitTracker::new();
+        let quota_body = r#"{"error":{"details":[{"reason":"QUOTA_EXHAUSTED"}]}}"#;
+        tracker.parse_from_error("acc1", 429, None, quota_body, Some("claude-sonnet-4-5".to_string()), &[600]);
+        let (mut limits, failures) = tracker.export_state();
+        assert_eq!(limits.len(), 1);
+        assert_eq!(failures.len(), 1);
+
+        // 追加一条已过期的记录，恢复时应被丢弃
+        limits.push(RateLimitRecord {
+            key: "acc2".to_string(),
+            reset_time: to_unix_secs(SystemTime::now()) - 10,
+            retry_after_sec: 60,
+            detected_at: to_unix_secs(SystemTime::now()) - 70,
+            reason: "rate_limit_exceeded".to_string(),
+            model: None,
+        });
+
+        let restored = RateLimitTracker::new();
+        let (restored_limits, restored_failures) = restored.import_state(limits, failures);
+        assert_eq!(restored_limits, 1);
+        assert_eq!(restored_failures, 1);
+        assert!(restored.is_rate_limited("acc1", Some("claude-sonnet-4-5")));
+        assert!(!restored.is_rate_limited("acc2", None));
+
+        // 恢复的失败计数继续参与退避阶梯
+        let info = restored.parse_from_error("acc1", 429, None, quota_body, None, &[60, 300]);
+        assert_eq!(info.unwrap().retry_after_sec, 300);
+    }
+
     #[test]
     fn test_quota_exhausted_does_accumulate_failure_count() {
         let tracker = RateLimitTracker::new();
@@ -5,89 +5,16 @@ use axum::{
     extract::{DefaultBodyLimit, Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::{Html, IntoResponse, Json, Response},
-    routing::{any, delete, get, post},
+    routing::{any, delete, get, patch, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
-use std::sync::OnceLock;
 use tokio::sync::oneshot;
 use tokio::sync::RwLock;
 use tracing::{debug, error};
 
-// [FIX] 全局待重新加载账号队列
-// 当 update_account_quota 更新 protected_models 后，将账号 ID 加入此队列
-// TokenManager 在 get_token 时会检查并处理这些账号
-static PENDING_RELOAD_ACCOUNTS: OnceLock<std::sync::RwLock<HashSet<String>>> = OnceLock::new();
-
-// [NEW] 全局待删除账号队列 (Issue #1477)
-// 当账号被删除后，将账号 ID 加入此队列，TokenManager 在 get_token 时会检查并清理内存缓存
-static PENDING_DELETE_ACCOUNTS: OnceLock<std::sync::RwLock<HashSet<String>>> = OnceLock::new();
-
-fn get_pending_reload_accounts() -> &'static std::sync::RwLock<HashSet<String>> {
-    PENDING_RELOAD_ACCOUNTS.get_or_init(|| std::sync::RwLock::new(HashSet::new()))
-}
-
-fn get_pending_delete_accounts() -> &'static std::sync::RwLock<HashSet<String>> {
-    PENDING_DELETE_ACCOUNTS.get_or_init(|| std::sync::RwLock::new(HashSet::new()))
-}
-
-/// 触发账号重新加载信号（供 update_account_quota 调用）
-pub fn trigger_account_reload(account_id: &str) {
-    if let Ok(mut pending) = get_pending_reload_accounts().write() {
-        pending.insert(account_id.to_string());
-        tracing::debug!(
-            "[Quota] Queued account {} for TokenManager reload",
-            account_id
-        );
-    }
-}
-
-/// 触发账号删除信号 (Issue #1477)
-pub fn trigger_account_delete(account_id: &str) {
-    if let Ok(mut pending) = get_pending_delete_accounts().write() {
-        pending.insert(account_id.to_string());
-        tracing::debug!(
-            "[Proxy] Queued account {} for cache removal",
-            account_id
-        );
-    }
-}
-
-/// 获取并清空待重新加载的账号列表（供 TokenManager 调用）
-pub fn take_pending_reload_accounts() -> Vec<String> {
-    if let Ok(mut pending) = get_pending_reload_accounts().write() {
-        let accounts: Vec<String> = pending.drain().collect();
-        if !accounts.is_empty() {
-            tracing::debug!(
-                "[Quota] Taking {} pending accounts for reload",
-                accounts.len()
-            );
-        }
-        accounts
-    } else {
-        Vec::new()
-    }
-}
-
-/// 获取并清空待删除的账号列表 (Issue #1477)
-pub fn take_pending_delete_accounts() -> Vec<String> {
-    if let Ok(mut pending) = get_pending_delete_accounts().write() {
-        let accounts: Vec<String> = pending.drain().collect();
-        if !accounts.is_empty() {
-            tracing::debug!(
-                "[Proxy] Taking {} pending accounts for cache removal",
-                accounts.len()
-            );
-        }
-        accounts
-    } else {
-        Vec::new()
-    }
-}
-
 /// Axum 应用状态
 #[derive(Clone)]
 pub struct AppState {
@@ -149,6 +76,15 @@ struct AccountResponse {
     quota: Option<QuotaResponse>,
     device_bound: bool,
     last_used: i64,
+    custom_label: Option<String>,
+    /// [NEW] 账号标签
+    tags: Vec<String>,
+    /// [NEW] 账号类型 ("provider" 为通用上游端点)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_type: Option<String>,
+    /// [NEW] Provider 端点配置 (api_key 已脱敏)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider: Option<crate::models::ProviderConfig>,
 }
 
 #[derive(Serialize)]
@@ -168,7 +104,9 @@ struct ModelQuota {
 
 #[derive(Serialize)]
 struct AccountListResponse {
-    accounts: Vec<AccountResponse>,
+    /// [NEW] accounts / total / page / page_size
+    #[serde(flatten)]
+    page: crate::modules::account_query::AccountPage<AccountResponse>,
     current_account_id: Option<String>,
 }
 
@@ -207,6 +145,13 @@ fn to_account_response(
         validation_blocked: account.validation_blocked,
         validation_blocked_until: account.validation_blocked_until,
         validation_blocked_reason: account.validation_blocked_reason.clone(),
+        custom_label: account.custom_label.clone(),
+        tags: account.tags.clone(),
+        account_type: account.account_type.clone(),
+        provider: account
+            .provider
+            .as_ref()
+            .map(crate::modules::provider_account::redacted),
     }
 }
 
@@ -372,6 +317,7 @@ impl AxumServer {
         let proxy_routes = Router::new()
             .route("/health", get(health_check_handler))
             .route("/healthz", get(health_check_handler))
+            .route("/metrics", get(metrics_handler))
             // OpenAI Protocol
             .route("/v1/models", get(handlers::openai::handle_list_models))
             .route(
@@ -494,6 +440,7 @@ impl AxumServer {
             .route("/accounts/import/v1", post(admin_import_v1_accounts))
             .route("/accounts/import/db", post(admin_import_from_db))
             .route("/accounts/import/db-custom", post(admin_import_custom_db))
+            .route("/accounts/import/bulk", post(admin_bulk_import_accounts))
             .route("/accounts/sync/db", post(admin_sync_account_from_db))
             .route("/stats/summary", get(admin_get_token_stats_summary))
             .route("/stats/hourly", get(admin_get_token_stats_hourly))
@@ -501,6 +448,10 @@ impl AxumServer {
             .route("/stats/weekly", get(admin_get_token_stats_weekly))
             .route("/stats/accounts", get(admin_get_token_stats_by_account))
             .route("/stats/models", get(admin_get_token_stats_by_model))
+            .route("/stats/quota-forecast", get(admin_get_quota_forecast))
+            .route("/warmup/schedules", get(admin_list_warmup_schedules))
+            .route("/warmup/schedules/:id/run", post(admin_run_warmup_schedule))
+            .route("/warmup/history", get(admin_get_warmup_history))
             .route("/config", get(admin_get_config).post(admin_save_config))
             .route("/proxy/cli/status", post(admin_get_cli_sync_status))
             .route("/proxy/cli/sync", post(admin_execute_cli_sync))
@@ -561,6 +512,9 @@ impl AxumServer {
             .route("/proxy/cloudflared/stop", post(admin_cloudflared_stop))
             .route("/system/open-folder", post(admin_open_folder))
             .route("/proxy/stats", get(admin_get_proxy_stats))
+            .route("/proxy/tls/status", get(admin_get_tls_status))
+            .route("/proxy/tls/reload", post(admin_reload_tls))
+            .route("/proxy/tls/ca", get(admin_get_tls_ca_cert))
             .route("/logs", get(admin_get_proxy_logs_filtered))
             .route("/logs/count", get(admin_get_proxy_logs_count_filtered))
             .route("/logs/clear", post(admin_clear_proxy_logs))
@@ -599,15 +553,40 @@ impl AxumServer {
             )
             .route("/accounts/bulk-delete", post(admin_delete_accounts))
             .route("/accounts/export", post(admin_export_accounts))
+            .route("/accounts/legacy/import", post(admin_import_legacy_layout))
+            .route("/accounts/legacy/export", post(admin_export_legacy_layout))
             .route("/accounts/reorder", post(admin_reorder_accounts))
             .route("/accounts/:accountId/quota", get(admin_fetch_account_quota))
+            .route("/accounts/health", get(admin_get_pool_health))
+            .route("/accounts/:accountId/health", get(admin_get_account_health))
+            .route("/accounts/events", get(admin_list_account_events))
+            .route("/accounts/:accountId/events", get(admin_get_account_events))
             .route(
                 "/accounts/:accountId/toggle-proxy",
                 post(admin_toggle_proxy_status),
             )
             .route("/accounts/warmup", post(admin_warm_up_all_accounts))
             .route("/accounts/:accountId/warmup", post(admin_warm_up_account))
+            .route(
+                "/accounts/:accountId/label",
+                post(admin_update_account_label),
+            )
+            .route("/accounts/:accountId/tags", post(admin_update_account_tags))
+            .route("/accounts/tags", get(admin_list_account_tags))
+            .route("/accounts/bulk", post(admin_bulk_account_action))
+            .route("/accounts/providers", post(admin_add_provider_account))
+            .route(
+                "/accounts/:accountId/provider",
+                post(admin_update_provider_account),
+            )
             .route("/system/data-dir", get(admin_get_data_dir_path))
+            .route("/system/vault", get(admin_get_vault_status))
+            .route("/system/vault/rotate", post(admin_rotate_vault_key))
+            .route(
+                "/system/backups",
+                get(admin_list_backups).post(admin_create_backup),
+            )
+            .route("/system/backups/restore", post(admin_restore_backup))
             .route("/system/updates/settings", get(admin_get_update_settings))
             .route(
                 "/system/updates/check-status",
@@ -640,6 +619,7 @@ impl AxumServer {
             .route("/security/token-stats", get(admin_get_ip_token_stats)) // For IP Token usage
             .route("/security/blacklist", get(admin_get_ip_blacklist).post(admin_add_ip_to_blacklist).delete(admin_remove_ip_from_blacklist))
             .route("/security/blacklist/clear", post(admin_clear_ip_blacklist))
+            .route("/security/auto-bans", get(admin_get_auto_ban_history))
             .route("/security/blacklist/check", get(admin_check_ip_in_blacklist))
             .route("/security/whitelist", get(admin_get_ip_whitelist).post(admin_add_ip_to_whitelist).delete(admin_remove_ip_from_whitelist))
             .route("/security/whitelist/clear", post(admin_clear_ip_whitelist))
@@ -649,9 +629,37 @@ impl AxumServer {
             .route("/user-tokens", get(admin_list_user_tokens).post(admin_create_user_token))
             .route("/user-tokens/summary", get(admin_get_user_token_summary))
             .route("/user-tokens/:id/renew", post(admin_renew_user_token))
+            .route(
+                "/user-tokens/:id/client-certs",
+                get(admin_list_token_client_certs).post(admin_issue_token_client_cert),
+            )
+            .route("/user-tokens/client-certs/revoked", get(admin_list_revoked_client_certs))
+            .route(
+                "/user-tokens/client-certs/:serial/revoke",
+                post(admin_revoke_token_client_cert),
+            )
             .route("/user-tokens/:id", delete(admin_delete_user_token).patch(admin_update_user_token))
             // OAuth (Web) - Admin 接口
             .route("/auth/url", get(admin_prepare_oauth_url_web))
+            // Admin Identities / Scoped Keys / Audit
+            .route(
+                "/admin/identities",
+                get(admin_list_admin_identities).post(admin_create_admin_identity),
+            )
+            .route(
+                "/admin/identities/:id",
+                patch(admin_update_admin_identity).delete(admin_delete_admin_identity),
+            )
+            .route(
+                "/admin/keys",
+                get(admin_list_admin_keys).post(admin_create_admin_key),
+            )
+            .route("/admin/keys/:id", delete(admin_revoke_admin_key))
+            .route("/admin/audit", get(admin_get_admin_audit_log))
+            // Admin Sessions
+            .route("/auth/logout", post(admin_logout))
+            .route("/auth/sessions", get(admin_list_sessions))
+            .route("/auth/sessions/:id", delete(admin_revoke_session))
             // 应用管理特定鉴权层 (强制校验)
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
@@ -671,6 +679,8 @@ impl AxumServer {
             .merge(proxy_routes)
             // 公开路由 (无需鉴权)
             .route("/auth/callback", get(handle_oauth_callback))
+            // [NEW] 管理后台会话登录 (自身限流，无需鉴权)
+            .route("/api/auth/login", post(admin_login))
             // 应用全局监控与状态层 (外层)
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
@@ -697,7 +707,12 @@ impl AxumServer {
             .await
             .map_err(|e| format!("Failed to bind address {}: {}", addr, e))?;
 
-        tracing::info!("Proxy server started at http://{}", addr);
+        let scheme = if crate::proxy::tls::status().enabled {
+            "https"
+        } else {
+            "http"
+        };
+        tracing::info!("Proxy server started at {}://{}", scheme, addr);
 
         // 创建关闭通道
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
@@ -720,36 +735,12 @@ impl AxumServer {
 
         // 在新任务中启动服务器
         let handle = tokio::spawn(async move {
-            use hyper::server::conn::http1;
-            use hyper_util::rt::TokioIo;
-            use hyper_util::service::TowerToHyperService;
-
             loop {
                 tokio::select! {
                     res = listener.accept() => {
                         match res {
                             Ok((stream, remote_addr)) => {
-                                let io = TokioIo::new(stream);
-                                
-                                // 注入 ConnectInfo (用于获取真实 IP)
-                                use tower::ServiceExt;
-                                use hyper::body::Incoming;
-                                let app_with_info = app.clone().map_request(move |mut req: axum::http::Request<Incoming>| {
-                                    req.extensions_mut().insert(axum::extract::ConnectInfo(remote_addr));
-                                    req
-                                });
-
-                                let service = TowerToHyperService::new(app_with_info);
-
-                                tokio::task::spawn(async move {
-                                    if let Err(err) = http1::Builder::new()
-                                        .serve_connection(io, service)
-                                        .with_upgrades() // 支持 WebSocket (如果以后需要)
-                                        .await
-                                    {
-                                        debug!("Connection closed or error occurred: {:?}", err);
-                                    }
-                                });
+                                tokio::task::spawn(handle_connection(stream, remote_addr, app.clone()));
                             }
                             Err(e) => {
                                 error!("Failed to accept connection: {:?}", e);
@@ -780,6 +771,69 @@ impl AxumServer {
     }
 }
 
+/// [NEW] 处理单个入站连接: 按 TLS 配置选择 HTTPS / 明文 / 重定向 / 拒绝
+async fn handle_connection(
+    stream: tokio::net::TcpStream,
+    remote_addr: std::net::SocketAddr,
+    app: Router,
+) {
+    use crate::proxy::tls::{self, ConnectionMode};
+
+    let Some(mode) = tls::classify(&stream, remote_addr).await else {
+        return;
+    };
+    match mode {
+        ConnectionMode::Plain => serve_connection(stream, remote_addr, app, None).await,
+        ConnectionMode::Tls(acceptor) => match acceptor.accept(stream).await {
+            Ok(tls_stream) => {
+                let conn = tls::TlsConnection::from_session(tls_stream.get_ref().1);
+                serve_connection(tls_stream, remote_addr, app, Some(conn)).await
+            }
+            Err(e) => debug!("TLS handshake with {} failed: {}", remote_addr, e),
+        },
+        ConnectionMode::Redirect => {
+            serve_connection(stream, remote_addr, tls::redirect_router(), None).await
+        }
+        ConnectionMode::Reject => {
+            serve_connection(stream, remote_addr, tls::reject_router(), None).await
+        }
+    }
+}
+
+async fn serve_connection<I>(
+    io: I,
+    remote_addr: std::net::SocketAddr,
+    app: Router,
+    tls: Option<crate::proxy::tls::TlsConnection>,
+) where
+    I: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    use hyper::body::Incoming;
+    use hyper::server::conn::http1;
+    use hyper_util::rt::TokioIo;
+    use hyper_util::service::TowerToHyperService;
+    use tower::ServiceExt;
+
+    // 注入 ConnectInfo (用于获取真实 IP) 与 TLS 连接信息 (含 mTLS 客户端证书指纹)
+    let app_with_info = app.map_request(move |mut req: axum::http::Request<Incoming>| {
+        req.extensions_mut()
+            .insert(axum::extract::ConnectInfo(remote_addr));
+        if let Some(tls) = &tls {
+            req.extensions_mut().insert(tls.clone());
+        }
+        req
+    });
+    let service = TowerToHyperService::new(app_with_info);
+
+    if let Err(err) = http1::Builder::new()
+        .serve_connection(TokioIo::new(io), service)
+        .with_upgrades() // 支持 WebSocket (如果以后需要)
+        .await
+    {
+        debug!("Connection closed or error occurred: {:?}", err);
+    }
+}
+
 // ===== API 处理器 (旧代码已移除，由 src/proxy/handlers/* 接管) =====
 
 /// 健康检查处理器
@@ -791,6 +845,26 @@ async fn health_check_handler() -> Response {
     .into_response()
 }
 
+/// Prometheus 指标 (配额耗尽预测)
+async fn metrics_handler() -> Response {
+    let report = match crate::modules::quota_forecast::get_latest_report() {
+        Some(r) => r,
+        None => tokio::task::spawn_blocking(crate::modules::quota_forecast::refresh_forecast)
+            .await
+            .ok()
+            .and_then(|r| r.ok())
+            .unwrap_or_default(),
+    };
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        crate::modules::quota_forecast::render_prometheus(&report),
+    )
+        .into_response()
+}
+
 /// 静默成功处理器 (用于拦截遥测日志等)
 async fn silent_ok_handler() -> Response {
     StatusCode::OK.into_response()
@@ -804,75 +878,80 @@ async fn silent_ok_handler() -> Response {
 
 async fn admin_list_accounts(
     State(state): State<AppState>,
+    Query(query): Query<crate::modules::account_query::AccountQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // [NEW] 服务端筛选 / 排序 / 分页 (不带参数时返回全部账号)
+    let plan = crate::modules::account_query::QueryPlan::parse(&query)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
     let accounts = state.account_service.list_accounts().map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: e }),
         )
     })?;
+    let ctx = account_filter_context(&state, plan.needs_health()).await?;
 
     let current_id = state.account_service.get_current_id().ok().flatten();
-
-    let account_responses: Vec<AccountResponse> = accounts
-        .into_iter()
-        .map(|acc| {
-            let is_current = current_id.as_ref().map(|id| id == &acc.id).unwrap_or(false);
-            let quota = acc.quota.map(|q| QuotaResponse {
-                models: q
-                    .models
-                    .into_iter()
-                    .map(|m| ModelQuota {
-                        name: m.name,
-                        percentage: m.percentage,
-                        reset_time: m.reset_time,
-                    })
-                    .collect(),
-                last_updated: q.last_updated,
-                subscription_tier: q.subscription_tier,
-                is_forbidden: q.is_forbidden,
-            });
-
-            AccountResponse {
-                id: acc.id,
-                email: acc.email,
-                name: acc.name,
-                is_current,
-                disabled: acc.disabled,
-                disabled_reason: acc.disabled_reason,
-                disabled_at: acc.disabled_at,
-                proxy_disabled: acc.proxy_disabled,
-                proxy_disabled_reason: acc.proxy_disabled_reason,
-                proxy_disabled_at: acc.proxy_disabled_at,
-                protected_models: acc.protected_models.into_iter().collect(),
-                validation_blocked: acc.validation_blocked,
-                validation_blocked_until: acc.validation_blocked_until,
-                validation_blocked_reason: acc.validation_blocked_reason,
-                quota,
-                device_bound: acc.device_profile.is_some(),
-                last_used: acc.last_used,
-            }
-        })
-        .collect();
+    let page = plan
+        .apply(accounts, &ctx)
+        .map(|acc| to_account_response(&acc, &current_id));
 
     Ok(Json(AccountListResponse {
+        page,
         current_account_id: current_id,
-        accounts: account_responses,
     }))
 }
 
+/// [NEW] 构建账号筛选上下文，需要时从 TokenManager 采集健康状态 (含限流 / 熔断等运行时信号)
+async fn account_filter_context(
+    state: &AppState,
+    needs_health: bool,
+) -> Result<crate::modules::account_query::FilterContext, (StatusCode, Json<ErrorResponse>)> {
+    let health = if needs_health {
+        state
+            .token_manager
+            .pool_health()
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse { error: e }),
+                )
+            })?
+            .accounts
+            .into_iter()
+            .map(|h| (h.account_id, h.state))
+            .collect()
+    } else {
+        Default::default()
+    };
+    Ok(crate::modules::account_query::FilterContext::new(health))
+}
+
 /// Export accounts with refresh tokens (for backup/migration)
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ExportAccountsRequest {
     account_ids: Vec<String>,
+    // [NEW] 明文导出需显式确认
+    #[serde(default)]
+    confirm_plaintext: bool,
 }
 
 async fn admin_export_accounts(
     State(_state): State<AppState>,
     Json(payload): Json<ExportAccountsRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let response = account::export_accounts_by_ids(&payload.account_ids).map_err(|e| {
+    if !payload.confirm_plaintext {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "plaintext_export_requires_confirmation".to_string(),
+            }),
+        ));
+    }
+    let response = account::export_accounts_by_ids(&payload.account_ids, true).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: e }),
@@ -882,6 +961,95 @@ async fn admin_export_accounts(
     Ok(Json(response))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyLayoutRequest {
+    path: String,
+    #[serde(default)]
+    plaintext: bool,
+    // [NEW] 明文导出需显式确认
+    #[serde(default)]
+    confirm_plaintext: bool,
+}
+
+/// 管理 API 导出目录的根 (数据目录下)，远程调用方只能写入该目录内
+const ADMIN_EXPORTS_DIR: &str = "exports";
+/// 管理 API 备份归档的根 (数据目录下)，创建与恢复都限定在该目录内
+const ADMIN_BACKUPS_DIR: &str = "backups";
+
+/// [SECURITY] 将管理 API 传入的路径限定在 `<data_dir>/<root>/` 之下
+fn resolve_admin_data_path(
+    root: &str,
+    path: &str,
+) -> Result<std::path::PathBuf, (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }));
+    let relative = std::path::Path::new(path.trim());
+    let is_plain_relative = !relative.as_os_str().is_empty()
+        && relative
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if !is_plain_relative {
+        return Err(bad_request(format!("非法路径: 仅允许 {}/ 下的相对路径", root)));
+    }
+    let data_dir = account::get_data_dir().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    let root_dir = data_dir.join(root);
+    let resolved = root_dir.join(relative);
+    // 已存在的路径可能是指向外部的符号链接，按真实路径再校验一次
+    if let (Ok(real_root), Ok(real_path)) = (root_dir.canonicalize(), resolved.canonicalize()) {
+        if !real_path.starts_with(&real_root) {
+            return Err(bad_request(format!("非法路径: 仅允许 {}/ 下的相对路径", root)));
+        }
+    }
+    Ok(resolved)
+}
+
+fn resolve_admin_export_dir(
+    path: &str,
+) -> Result<std::path::PathBuf, (StatusCode, Json<ErrorResponse>)> {
+    resolve_admin_data_path(ADMIN_EXPORTS_DIR, path)
+}
+
+async fn admin_import_legacy_layout(
+    Json(payload): Json<LegacyLayoutRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // 与导出共用 `<data_dir>/exports/`，远程调用方无法读取该目录外的文件
+    let source_dir = resolve_admin_export_dir(&payload.path)?;
+    // 导入的账号由账号存储的变更通知同步到 TokenManager
+    let report = account::import_legacy_layout(&source_dir).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(report))
+}
+
+async fn admin_export_legacy_layout(
+    Json(payload): Json<LegacyLayoutRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if payload.plaintext && !payload.confirm_plaintext {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "plaintext_export_requires_confirmation".to_string(),
+            }),
+        ));
+    }
+    let target_dir = resolve_admin_export_dir(&payload.path)?;
+    let report = account::export_legacy_layout(&target_dir, payload.plaintext, true).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(report))
+}
+
 async fn admin_get_current_account(
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -892,47 +1060,11 @@ async fn admin_get_current_account(
         )
     })?;
 
-    let response = if let Some(id) = current_id {
-        let acc = account::load_account(&id).ok();
-        acc.map(|acc| {
-            let quota = acc.quota.map(|q| QuotaResponse {
-                models: q
-                    .models
-                    .into_iter()
-                    .map(|m| ModelQuota {
-                        name: m.name,
-                        percentage: m.percentage,
-                        reset_time: m.reset_time,
-                    })
-                    .collect(),
-                last_updated: q.last_updated,
-                subscription_tier: q.subscription_tier,
-                is_forbidden: q.is_forbidden,
-            });
-
-            AccountResponse {
-                id: acc.id,
-                email: acc.email,
-                name: acc.name,
-                is_current: true,
-                disabled: acc.disabled,
-                disabled_reason: acc.disabled_reason,
-                disabled_at: acc.disabled_at,
-                proxy_disabled: acc.proxy_disabled,
-                proxy_disabled_reason: acc.proxy_disabled_reason,
-                proxy_disabled_at: acc.proxy_disabled_at,
-                protected_models: acc.protected_models.into_iter().collect(),
-                validation_blocked: acc.validation_blocked,
-                validation_blocked_until: acc.validation_blocked_until,
-                validation_blocked_reason: acc.validation_blocked_reason,
-                quota,
-                device_bound: acc.device_profile.is_some(),
-                last_used: acc.last_used,
-            }
-        })
-    } else {
-        None
-    };
+    // 与账号列表共用 to_account_response，保证 Provider api_key 同样被隐藏
+    let response = current_id
+        .as_deref()
+        .and_then(|id| account::load_account(id).ok())
+        .map(|acc| to_account_response(&acc, &current_id));
 
     Ok(Json(response))
 }
@@ -1309,6 +1441,12 @@ async fn admin_save_config(
         *pool = new_config.clone().proxy.proxy_pool;
     }
 
+    // 更新请求对冲配置
+    crate::proxy::update_hedging_config(new_config.proxy.hedging.clone());
+    // [NEW] 更新 TLS 配置
+    let tls_config = new_config.proxy.tls.clone();
+    let _ = tokio::task::spawn_blocking(move || crate::proxy::update_tls_config(tls_config)).await;
+
     Ok(StatusCode::OK)
 }
 
@@ -1718,6 +1856,145 @@ async fn admin_get_proxy_stats(
     Ok(Json(stats))
 }
 
+// [NEW] 内置 TLS 状态 / 热加载 / 根证书下载
+async fn admin_get_tls_status() -> impl IntoResponse {
+    Json(crate::proxy::tls::status())
+}
+
+async fn admin_reload_tls() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let status = tokio::task::spawn_blocking(crate::proxy::tls::reload)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+    Ok(Json(status))
+}
+
+async fn admin_get_tls_ca_cert() -> Response {
+    match crate::modules::mitm_ca::read_ca_cert_pem() {
+        Some(pem) => (
+            [
+                (axum::http::header::CONTENT_TYPE, "application/x-pem-file"),
+                (
+                    axum::http::header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"antigravity-ca.crt\"",
+                ),
+            ],
+            pem,
+        )
+            .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "CA certificate not generated".to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+// [NEW] 密钥保险库
+#[derive(Deserialize)]
+struct RotateVaultKeyRequest {
+    source: crate::utils::vault::KeySource,
+    #[serde(default)]
+    passphrase: Option<String>,
+}
+
+async fn admin_get_vault_status() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::get_vault_status()
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))
+}
+
+async fn admin_rotate_vault_key(
+    Json(payload): Json<RotateVaultKeyRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::rotate_vault_key(payload.source, payload.passphrase)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))
+}
+
+// [NEW] 数据目录备份与恢复
+#[derive(Deserialize)]
+struct CreateBackupRequest {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    components: Vec<crate::modules::backup::BackupComponent>,
+    #[serde(default)]
+    passphrase: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RestoreBackupRequest {
+    path: String,
+    #[serde(default)]
+    components: Vec<crate::modules::backup::BackupComponent>,
+    #[serde(default)]
+    passphrase: Option<String>,
+}
+
+async fn admin_list_backups() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::list_backups()
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))
+}
+
+async fn admin_create_backup(
+    Json(payload): Json<CreateBackupRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let path = match payload.path.as_deref() {
+        Some(path) => Some(
+            resolve_admin_data_path(ADMIN_BACKUPS_DIR, path)?
+                .to_string_lossy()
+                .to_string(),
+        ),
+        None => None,
+    };
+    crate::commands::create_backup(path, Some(payload.components), payload.passphrase)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))
+}
+
+async fn admin_restore_backup(
+    State(state): State<AppState>,
+    Json(payload): Json<RestoreBackupRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let archive = resolve_admin_data_path(ADMIN_BACKUPS_DIR, &payload.path)?;
+    let report = tokio::task::spawn_blocking(move || {
+        crate::modules::backup::restore_backup(
+            &archive,
+            &payload.components,
+            payload.passphrase.as_deref().filter(|p| !p.is_empty()),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r)
+    .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    // 整库恢复不会触发账号存储的变更通知，需要显式重载
+    if report
+        .restored
+        .contains(&crate::modules::backup::BackupComponent::Accounts)
+    {
+        if let Err(e) = state.token_manager.reload_all_accounts().await {
+            tracing::warn!("Failed to reload accounts after restore: {}", e);
+        }
+    }
+    Ok(Json(report))
+}
+
 async fn admin_get_data_dir_path() -> impl IntoResponse {
     match crate::modules::account::get_data_dir() {
         Ok(p) => Json(p.to_string_lossy().to_string()),
@@ -1778,6 +2055,45 @@ async fn admin_renew_user_token(
     Ok(StatusCode::OK)
 }
 
+// [NEW] mTLS 客户端证书
+async fn admin_list_token_client_certs(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let certs = crate::commands::user_token::list_token_client_certs(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(certs))
+}
+
+async fn admin_issue_token_client_cert(
+    Path(id): Path<String>,
+    payload: Option<Json<crate::commands::user_token::IssueClientCertRequest>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let request = payload.map(|Json(p)| p).unwrap_or_default();
+    let issued = crate::commands::user_token::issue_token_client_cert(id, request)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(issued))
+}
+
+async fn admin_revoke_token_client_cert(
+    Path(serial): Path<String>,
+    payload: Option<Json<crate::commands::user_token::RevokeClientCertRequest>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let request = payload.map(|Json(p)| p).unwrap_or_default();
+    crate::commands::user_token::revoke_token_client_cert(serial, request)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, Json(ErrorResponse { error: e })))?;
+    Ok(StatusCode::OK)
+}
+
+async fn admin_list_revoked_client_certs() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let certs = crate::commands::user_token::list_revoked_client_certs()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(certs))
+}
+
 async fn admin_delete_user_token(
     Path(id): Path<String>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -1987,14 +2303,23 @@ async fn admin_get_token_stats_summary(
     }
 }
 
-async fn admin_get_token_stats_by_model(
-    Query(p): Query<StatsPeriodQuery>,
+#[derive(Deserialize)]
+struct QuotaForecastQuery {
+    refresh: Option<bool>,
+}
+
+async fn admin_get_quota_forecast(
+    Query(q): Query<QuotaForecastQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let hours = p.hours.unwrap_or(168);
-    let res = tokio::task::spawn_blocking(move || token_stats::get_model_stats(hours)).await;
+    if !q.refresh.unwrap_or(false) {
+        if let Some(report) = crate::modules::quota_forecast::get_latest_report() {
+            return Ok(Json(report));
+        }
+    }
 
+    let res = tokio::task::spawn_blocking(crate::modules::quota_forecast::refresh_forecast).await;
     match res {
-        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Ok(report)) => Ok(Json(report)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: e }),
@@ -2008,15 +2333,10 @@ async fn admin_get_token_stats_by_model(
     }
 }
 
-async fn admin_get_token_stats_model_trend_hourly(
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let res = tokio::task::spawn_blocking(|| {
-        token_stats::get_model_trend_hourly(24) // Default 24 hours
-    })
-    .await;
-
+async fn admin_list_warmup_schedules() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res = tokio::task::spawn_blocking(crate::modules::warmup_schedule::list_schedule_status).await;
     match res {
-        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Ok(schedules)) => Ok(Json(schedules)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: e }),
@@ -2030,30 +2350,430 @@ async fn admin_get_token_stats_model_trend_hourly(
     }
 }
 
-async fn admin_get_token_stats_model_trend_daily(
+async fn admin_run_warmup_schedule(
+    Path(id): Path<String>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let res = tokio::task::spawn_blocking(|| {
-        token_stats::get_model_trend_daily(7) // Default 7 days
-    })
-    .await;
-
-    match res {
-        Ok(Ok(stats)) => Ok(Json(stats)),
-        Ok(Err(e)) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
+    match crate::modules::warmup_schedule::trigger_schedule(&id).await {
+        Ok(summary) => Ok(Json(summary)),
+        Err(e) if e.contains("not found") => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) if e.contains("already running") => Err((
+            StatusCode::CONFLICT,
             Json(ErrorResponse { error: e }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
+            Json(ErrorResponse { error: e }),
         )),
     }
 }
 
-async fn admin_get_token_stats_account_trend_hourly(
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+#[derive(Deserialize)]
+struct WarmupHistoryQuery {
+    limit: Option<usize>,
+    schedule_id: Option<String>,
+}
+
+async fn admin_get_warmup_history(
+    Query(q): Query<WarmupHistoryQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let limit = q.limit.unwrap_or(100).min(1000);
+    let res = tokio::task::spawn_blocking(move || {
+        crate::modules::warmup_db::list_history(limit, q.schedule_id.as_deref())
+    })
+    .await;
+
+    match res {
+        Ok(Ok(records)) => Ok(Json(records)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+// --- Admin Identity Handlers ---
+
+/// 在阻塞线程中执行管理员身份数据库操作，"not found" 映射为 404
+async fn run_admin_auth_db<T, F>(f: F) -> Result<Json<T>, (StatusCode, Json<ErrorResponse>)>
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(value)) => Ok(Json(value)),
+        Ok(Err(e)) if e.contains("not found") => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: e }),
+        )),
+        Ok(Err(e)) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+// --- Admin Session Handlers ---
+
+#[derive(Deserialize)]
+struct AdminLoginRequest {
+    /// 管理密码或身份 API Key (agk_*)
+    password: String,
+}
+
+fn admin_session_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: message.to_string(),
+        }),
+    )
+        .into_response()
+}
+
+fn admin_login_audit(actor: &str, status: StatusCode, client_ip: &str, detail: Option<String>) {
+    let entry = crate::modules::admin_auth_db::AdminAuditEntry {
+        id: 0,
+        timestamp: chrono::Utc::now().timestamp(),
+        actor: actor.to_string(),
+        identity_id: None,
+        key_id: None,
+        method: "POST".to_string(),
+        path: "/auth/login".to_string(),
+        scope: None,
+        status: status.as_u16(),
+        client_ip: Some(client_ip.to_string()),
+        detail,
+    };
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = crate::modules::admin_auth_db::insert_audit(&entry) {
+            tracing::error!("Failed to write admin audit log: {}", e);
+        }
+    });
+}
+
+/// 校验登录凭据: 身份 API Key 或管理密码 (未设置时回退 api_key)
+async fn resolve_login_principal(
+    state: &AppState,
+    password: &str,
+) -> Result<Option<crate::proxy::admin_scopes::AdminPrincipal>, String> {
+    use crate::proxy::admin_scopes::AdminPrincipal;
+
+    if password.starts_with(crate::modules::admin_auth_db::ADMIN_KEY_PREFIX) {
+        let key = password.to_string();
+        let resolved = tokio::task::spawn_blocking(move || {
+            crate::modules::admin_auth_db::resolve_api_key(&key)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        return Ok(resolved.map(AdminPrincipal::from_key));
+    }
+
+    let security = state.security.read().await.clone();
+    let stored = security
+        .admin_password
+        .filter(|p| !p.is_empty())
+        .unwrap_or(security.api_key);
+    if stored.is_empty() {
+        return Err("Admin password is not configured".to_string());
+    }
+
+    let candidate = password.to_string();
+    let ok = tokio::task::spawn_blocking(move || {
+        crate::utils::crypto::verify_password(&candidate, &stored)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(ok.then(AdminPrincipal::legacy_owner))
+}
+
+async fn admin_login(
+    State(state): State<AppState>,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    tls: Option<axum::Extension<crate::proxy::tls::TlsConnection>>,
+    headers: HeaderMap,
+    Json(payload): Json<AdminLoginRequest>,
+) -> Response {
+    use crate::proxy::admin_session;
+
+    let trusted = state.security.read().await.security_monitor.trusted_proxies.clone();
+    let client_ip = crate::proxy::middleware::ip_filter::resolve_client_ip(
+        &headers,
+        connect_info.map(|ci| ci.0.ip()),
+        &trusted,
+    )
+    .unwrap_or_else(|| "127.0.0.1".to_string());
+
+    if matches!(security_db::is_ip_in_blacklist(&client_ip), Ok(true)) {
+        return admin_session_error(StatusCode::FORBIDDEN, "Access denied");
+    }
+    if let Err(retry_after) = admin_session::check_login_rate(&client_ip) {
+        let mut response =
+            admin_session_error(StatusCode::TOO_MANY_REQUESTS, "Too many login attempts");
+        if let Ok(value) = retry_after.to_string().parse() {
+            response.headers_mut().insert(axum::http::header::RETRY_AFTER, value);
+        }
+        return response;
+    }
+
+    let principal = match resolve_login_principal(&state, &payload.password).await {
+        Ok(Some(principal)) => principal,
+        Ok(None) => {
+            tracing::warn!("Admin login failed from {}", client_ip);
+            admin_login_audit("unknown", StatusCode::UNAUTHORIZED, &client_ip, None);
+            admin_session::register_auth_failure(&client_ip).await;
+            return admin_session_error(StatusCode::UNAUTHORIZED, "Invalid credentials");
+        }
+        Err(e) => return admin_session_error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+
+    admin_session::record_login_success(&client_ip);
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let actor = principal.actor.clone();
+    let (session, cookie_value) =
+        admin_session::create_session(principal, Some(client_ip.clone()), user_agent);
+    admin_login_audit(&actor, StatusCode::OK, &client_ip, Some(format!("session {}", session.id)));
+
+    let secure = tls.is_some()
+        || headers
+            .get("x-forwarded-proto")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|p| p.eq_ignore_ascii_case("https"));
+    let mut response = Json(serde_json::json!({
+        "session_id": session.id,
+        "csrf_token": session.csrf_token,
+        "actor": actor,
+        "role": session.principal.role,
+        "expires_at": session.expires_at,
+    }))
+    .into_response();
+    for cookie in admin_session::session_cookies(&session, &cookie_value, secure) {
+        if let Ok(value) = cookie.parse() {
+            response.headers_mut().append(axum::http::header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+async fn admin_logout(headers: HeaderMap) -> Response {
+    use crate::proxy::admin_session;
+
+    if let Some(id) = admin_session::session_id_from_headers(&headers) {
+        admin_session::revoke(&id);
+    }
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    for cookie in admin_session::clear_cookies() {
+        if let Ok(value) = cookie.parse() {
+            response.headers_mut().append(axum::http::header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+async fn admin_list_sessions() -> impl IntoResponse {
+    Json(crate::proxy::admin_session::list_sessions())
+}
+
+async fn admin_revoke_session(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if crate::proxy::admin_session::revoke(&id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Session {} not found", id),
+            }),
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateAdminIdentityRequest {
+    name: String,
+    role: crate::modules::admin_auth_db::AdminRole,
+}
+
+#[derive(Deserialize)]
+struct UpdateAdminIdentityRequest {
+    role: Option<crate::modules::admin_auth_db::AdminRole>,
+    disabled: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct CreateAdminKeyRequest {
+    identity_id: String,
+    name: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+    expires_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct AdminKeysQuery {
+    identity_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AdminAuditQuery {
+    limit: Option<usize>,
+    actor: Option<String>,
+    since: Option<i64>,
+}
+
+async fn admin_list_admin_identities() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_admin_auth_db(crate::modules::admin_auth_db::list_identities).await
+}
+
+async fn admin_create_admin_identity(
+    Json(payload): Json<CreateAdminIdentityRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_admin_auth_db(move || {
+        crate::modules::admin_auth_db::create_identity(&payload.name, payload.role)
+    })
+    .await
+}
+
+async fn admin_update_admin_identity(
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateAdminIdentityRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_admin_auth_db(move || {
+        crate::modules::admin_auth_db::update_identity(&id, payload.role, payload.disabled)
+    })
+    .await
+}
+
+async fn admin_delete_admin_identity(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_admin_auth_db(move || crate::modules::admin_auth_db::delete_identity(&id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_list_admin_keys(
+    Query(q): Query<AdminKeysQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_admin_auth_db(move || crate::modules::admin_auth_db::list_api_keys(q.identity_id.as_deref()))
+        .await
+}
+
+async fn admin_create_admin_key(
+    Json(payload): Json<CreateAdminKeyRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_admin_auth_db(move || {
+        crate::modules::admin_auth_db::create_api_key(
+            &payload.identity_id,
+            &payload.name,
+            payload.scopes,
+            payload.expires_at,
+        )
+    })
+    .await
+}
+
+async fn admin_revoke_admin_key(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    run_admin_auth_db(move || crate::modules::admin_auth_db::revoke_api_key(&id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_get_admin_audit_log(
+    Query(q): Query<AdminAuditQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let limit = q.limit.unwrap_or(200).min(2000);
+    run_admin_auth_db(move || {
+        crate::modules::admin_auth_db::list_audit(limit, q.actor.as_deref(), q.since)
+    })
+    .await
+}
+
+async fn admin_get_token_stats_by_model(
+    Query(p): Query<StatsPeriodQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let hours = p.hours.unwrap_or(168);
+    let res = tokio::task::spawn_blocking(move || token_stats::get_model_stats(hours)).await;
+
+    match res {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_token_stats_model_trend_hourly(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res = tokio::task::spawn_blocking(|| {
+        token_stats::get_model_trend_hourly(24) // Default 24 hours
+    })
+    .await;
+
+    match res {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_token_stats_model_trend_daily(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res = tokio::task::spawn_blocking(|| {
+        token_stats::get_model_trend_daily(7) // Default 7 days
+    })
+    .await;
+
+    match res {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_token_stats_account_trend_hourly(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let res = tokio::task::spawn_blocking(|| {
         token_stats::get_account_trend_hourly(24) // Default 24 hours
     })
@@ -2227,6 +2947,68 @@ async fn admin_reorder_accounts(
     Ok(StatusCode::OK)
 }
 
+// [NEW] 账号健康状态
+#[derive(Deserialize)]
+struct AccountHealthQuery {
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+async fn admin_get_account_health(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+    Query(params): Query<AccountHealthQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let limit = params
+        .limit
+        .unwrap_or(crate::modules::account_health::DEFAULT_HISTORY_LIMIT)
+        .min(500);
+    let detail = state
+        .token_manager
+        .account_health(&account_id, limit)
+        .await
+        .map_err(|e| {
+            let status = if e.starts_with("Account not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            (status, Json(ErrorResponse { error: e }))
+        })?;
+    Ok(Json(detail))
+}
+
+async fn admin_get_pool_health(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let pool = state.token_manager.pool_health().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(pool))
+}
+
+// [NEW] 账号生命周期事件
+async fn admin_list_account_events(
+    Query(query): Query<crate::modules::account_events::AccountEventQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let page = crate::modules::account_events::list_events(&query)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(page))
+}
+
+async fn admin_get_account_events(
+    Path(account_id): Path<String>,
+    Query(mut query): Query<crate::modules::account_events::AccountEventQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    query.account_id = Some(account_id);
+    let page = crate::modules::account_events::list_events(&query)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(page))
+}
+
 async fn admin_fetch_account_quota(
     Path(account_id): Path<String>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -2313,6 +3095,115 @@ async fn admin_warm_up_account(
     Ok(Json(result))
 }
 
+#[derive(Deserialize)]
+struct UpdateLabelRequest {
+    label: String,
+}
+
+async fn admin_update_account_label(
+    Path(account_id): Path<String>,
+    Json(payload): Json<UpdateLabelRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::update_account_label(account_id, payload.label)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(StatusCode::OK)
+}
+
+// [NEW] 账号标签与批量操作
+#[derive(Deserialize)]
+struct UpdateTagsRequest {
+    tags: Vec<String>,
+}
+
+async fn admin_update_account_tags(
+    Path(account_id): Path<String>,
+    Json(payload): Json<UpdateTagsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let tags = crate::modules::account_query::set_account_tags(&account_id, payload.tags)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(tags))
+}
+
+async fn admin_add_provider_account(
+    Json(payload): Json<crate::modules::provider_account::ProviderAccountRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let account = crate::modules::provider_account::add_provider_account(payload)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(to_account_response(&account, &None)))
+}
+
+async fn admin_update_provider_account(
+    Path(account_id): Path<String>,
+    Json(payload): Json<crate::modules::provider_account::ProviderConfigInput>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let account = crate::modules::provider_account::update_provider_account(&account_id, payload)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(to_account_response(&account, &None)))
+}
+
+async fn admin_list_account_tags(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let accounts = state.account_service.list_accounts().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(crate::modules::account_query::tag_counts(&accounts)))
+}
+
+async fn admin_bulk_account_action(
+    State(state): State<AppState>,
+    principal: Option<axum::Extension<crate::proxy::admin_scopes::AdminPrincipal>>,
+    Json(payload): Json<crate::modules::account_query::BulkActionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    use crate::modules::account_query::{self, BulkAction};
+
+    // 路由只要求 accounts:write，绑定代理与预热额外需要对应作用域
+    let extra_scope = match payload.action {
+        BulkAction::BindProxy | BulkAction::UnbindProxy => Some("proxy:write"),
+        BulkAction::Warmup => Some("warmup:write"),
+        _ => None,
+    };
+    if let Some(scope) = extra_scope {
+        if !principal.is_some_and(|p| p.has_scope(scope)) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse {
+                    error: format!("Missing required scope '{}'", scope),
+                }),
+            ));
+        }
+    }
+
+    let filter = payload
+        .prepare()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    let accounts = state.account_service.list_accounts().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    let ctx = account_filter_context(&state, filter.needs_health()).await?;
+    let targets = account_query::select_targets(accounts, &filter, &payload.account_ids, &ctx);
+    let report = account_query::run_bulk_action(&payload, targets)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    if !report.dry_run && report.succeeded > 0 {
+        if let Err(e) = state.token_manager.load_accounts().await {
+            logger::log_error(&format!(
+                "[API] Failed to reload accounts after bulk action: {}",
+                e
+            ));
+        }
+    }
+    Ok(Json(report))
+}
+
 
 async fn admin_save_http_api_settings(
     Json(payload): Json<crate::modules::http_api::HttpApiSettings>,
@@ -2633,6 +3524,41 @@ async fn admin_import_v1_accounts(
     Ok(Json(responses))
 }
 
+// [NEW] CSV / JSONL 批量导入
+#[derive(Deserialize)]
+struct BulkImportRequest {
+    content: String,
+    #[serde(flatten)]
+    options: crate::modules::account_import::BulkImportOptions,
+}
+
+async fn admin_bulk_import_accounts(
+    State(state): State<AppState>,
+    principal: Option<axum::Extension<crate::proxy::admin_scopes::AdminPrincipal>>,
+    Json(payload): Json<BulkImportRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // 路由只要求 accounts:write，写入令牌账号池额外需要 tokens:write
+    let mut options = payload.options;
+    options.allow_pools = principal.is_some_and(|p| p.has_scope("tokens:write"));
+    let report = crate::modules::account_import::bulk_import(
+        &state.account_service,
+        &payload.content,
+        &options,
+    )
+    .await
+    .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    if !report.dry_run && report.created + report.updated > 0 {
+        if let Err(e) = state.token_manager.load_accounts().await {
+            logger::log_error(&format!(
+                "[API] Failed to reload accounts after bulk import: {}",
+                e
+            ));
+        }
+    }
+    Ok(Json(report))
+}
+
 async fn admin_import_from_db(
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -3106,6 +4032,8 @@ struct IpStatsResponse {
     unique_ips: usize,
     blocked_requests: usize,
     top_ips: Vec<crate::modules::security_db::IpRanking>,
+    top_countries: Vec<crate::modules::security_db::GeoRanking>,
+    top_asns: Vec<crate::modules::security_db::GeoRanking>,
 }
 
 async fn admin_get_ip_stats() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -3113,12 +4041,18 @@ async fn admin_get_ip_stats() -> Result<impl IntoResponse, (StatusCode, Json<Err
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
     let top_ips = security_db::get_top_ips(10, 24)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    let top_countries = security_db::get_top_countries(10, 24)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    let top_asns = security_db::get_top_asns(10, 24)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
 
     let response = IpStatsResponse {
         total_requests: stats.total_requests as usize,
         unique_ips: stats.unique_ips as usize,
         blocked_requests: stats.blocked_count as usize,
         top_ips,
+        top_countries,
+        top_asns,
     };
     Ok(Json(response))
 }
@@ -3145,6 +4079,21 @@ async fn admin_get_ip_blacklist() -> Result<impl IntoResponse, (StatusCode, Json
     Ok(Json(list))
 }
 
+#[derive(Deserialize)]
+struct AutoBanHistoryQuery {
+    ip: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn admin_get_auto_ban_history(
+    Query(q): Query<AutoBanHistoryQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let limit = q.limit.unwrap_or(100).min(1000);
+    let history = security_db::get_ban_history(q.ip.as_deref(), limit)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(history))
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct AddBlacklistRequest {
@@ -3495,3 +4444,32 @@ async fn admin_get_droid_config_content(
             Json(ErrorResponse { error: e }),
         ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, ProviderConfig, TokenData, PROVIDER_ACCOUNT_TYPE};
+
+    #[test]
+    fn test_account_response_redacts_provider_api_key() {
+        let token = TokenData::new(String::new(), String::new(), 0, None, None, None);
+        let mut account = Account::new("p1".to_string(), "vllm".to_string(), token);
+        account.account_type = Some(PROVIDER_ACCOUNT_TYPE.to_string());
+        account.provider = Some(ProviderConfig {
+            base_url: "http://10.0.0.5:8000/v1".to_string(),
+            api_key: "sk-very-secret-9876".to_string(),
+            protocol: Default::default(),
+            models: vec!["llama-3".to_string()],
+            model_mapping: Default::default(),
+            routing: Default::default(),
+        });
+
+        // 列表、当前账号、新建/更新 Provider 等管理接口均经由 to_account_response 输出
+        for current_id in [None, Some("p1".to_string())] {
+            let response = to_account_response(&account, &current_id);
+            assert_eq!(response.provider.as_ref().unwrap().api_key, "****9876");
+            let json = serde_json::to_string(&response).unwrap();
+            assert!(!json.contains("sk-very-secret"));
+        }
+    }
+}
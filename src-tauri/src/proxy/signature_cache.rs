@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, SystemTime};
 
+use crate::modules::runtime_state_db::SignatureRecord;
+
 // Node.js proxy uses 2 hours TTL
 const SIGNATURE_TTL: Duration = Duration::from_secs(2 * 60 * 60);
 const MIN_SIGNATURE_LENGTH: usize = 50;
@@ -249,6 +251,80 @@ impl SignatureCache {
         }
     }
 
+    /// Export all non-expired entries for persistence across restarts
+    pub fn export_entries(&self) -> Vec<SignatureRecord> {
+        let mut records = Vec::new();
+
+        if let Ok(cache) = self.tool_signatures.lock() {
+            for (key, entry) in cache.iter().filter(|(_, e)| !e.is_expired()) {
+                records.push(SignatureRecord {
+                    layer: "tool".to_string(),
+                    key: key.clone(),
+                    data: entry.data.clone(),
+                    message_count: 0,
+                    timestamp: to_unix_secs(entry.timestamp),
+                });
+            }
+        }
+        if let Ok(cache) = self.thinking_families.lock() {
+            for (key, entry) in cache.iter().filter(|(_, e)| !e.is_expired()) {
+                records.push(SignatureRecord {
+                    layer: "family".to_string(),
+                    key: key.clone(),
+                    data: entry.data.clone(),
+                    message_count: 0,
+                    timestamp: to_unix_secs(entry.timestamp),
+                });
+            }
+        }
+        if let Ok(cache) = self.session_signatures.lock() {
+            for (key, entry) in cache.iter().filter(|(_, e)| !e.is_expired()) {
+                records.push(SignatureRecord {
+                    layer: "session".to_string(),
+                    key: key.clone(),
+                    data: entry.data.signature.clone(),
+                    message_count: entry.data.message_count as i64,
+                    timestamp: to_unix_secs(entry.timestamp),
+                });
+            }
+        }
+
+        records
+    }
+
+    /// Restore persisted entries, keeping their original timestamps so TTL still applies.
+    /// Expired entries and keys already present in memory are skipped.
+    /// Returns the number of restored entries.
+    pub fn import_entries(&self, records: Vec<SignatureRecord>) -> usize {
+        let mut restored = 0;
+        for record in records {
+            let entry_time = SystemTime::UNIX_EPOCH + Duration::from_secs(record.timestamp.max(0) as u64);
+            if entry_time.elapsed().unwrap_or(Duration::ZERO) > SIGNATURE_TTL {
+                continue;
+            }
+            let inserted = match record.layer.as_str() {
+                "tool" => self.tool_signatures.lock().ok().map(|mut cache| {
+                    insert_if_absent(&mut cache, record.key, CacheEntry { data: record.data, timestamp: entry_time })
+                }),
+                "family" => self.thinking_families.lock().ok().map(|mut cache| {
+                    insert_if_absent(&mut cache, record.key, CacheEntry { data: record.data, timestamp: entry_time })
+                }),
+                "session" => self.session_signatures.lock().ok().map(|mut cache| {
+                    let data = SessionSignatureEntry {
+                        signature: record.data,
+                        message_count: record.message_count.max(0) as usize,
+                    };
+                    insert_if_absent(&mut cache, record.key, CacheEntry { data, timestamp: entry_time })
+                }),
+                _ => None,
+            };
+            if inserted == Some(true) {
+                restored += 1;
+            }
+        }
+        restored
+    }
+
     /// Clear all caches (for testing or manual reset)
     #[allow(dead_code)] // Used in tests
     pub fn clear(&self) {
@@ -264,6 +340,20 @@ impl SignatureCache {
     }
 }
 
+fn to_unix_secs(t: SystemTime) -> i64 {
+    t.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn insert_if_absent<T>(cache: &mut HashMap<String, CacheEntry<T>>, key: String, entry: CacheEntry<T>) -> bool {
+    if cache.contains_key(&key) {
+        return false;
+    }
+    cache.insert(key, entry);
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -329,6 +419,32 @@ mod tests {
         assert!(cache.get_session_signature("sid-other").is_none());
     }
 
+    #[test]
+    fn test_export_import_entries_skips_expired() {
+        let cache = SignatureCache::new();
+        let sig = "s".repeat(60);
+        cache.cache_tool_signature("tool_1", sig.clone());
+        cache.cache_thinking_family(sig.clone(), "claude".to_string());
+        cache.cache_session_signature("sid-1", sig.clone(), 7);
+
+        let mut records = cache.export_entries();
+        assert_eq!(records.len(), 3);
+        records.push(SignatureRecord {
+            layer: "tool".to_string(),
+            key: "tool_old".to_string(),
+            data: sig.clone(),
+            message_count: 0,
+            timestamp: to_unix_secs(SystemTime::now()) - SIGNATURE_TTL.as_secs() as i64 - 10,
+        });
+
+        let restored = SignatureCache::new();
+        assert_eq!(restored.import_entries(records), 3);
+        assert_eq!(restored.get_tool_signature("tool_1"), Some(sig.clone()));
+        assert_eq!(restored.get_signature_family(&sig), Some("claude".to_string()));
+        assert_eq!(restored.get_session_signature("sid-1"), Some(sig));
+        assert!(restored.get_tool_signature("tool_old").is_none());
+    }
+
     #[test]
     fn test_clear_all_caches() {
         let cache = SignatureCache::new();
@@ -0,0 +1,728 @@
+// 调度策略模拟器
+// 回放 proxy_db 中记录的流量 (时间戳 / 模型 / token 数)，在合成账号上驱动真实的 TokenManager 选号逻辑，
+// 对比不同 SchedulingMode、熔断退避阶梯与配额保护阈值下的成功率、等待时间、缓存亲和性与账号负载。
+//
+// 模拟使用虚拟时钟：TokenManager 内部的限流记录基于真实时间，回放过程中几乎不会自然过期，
+// 因此由模拟器在虚拟时间越过锁定期时主动解除，从而保证退避时长与真实逻辑计算的一致。
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::models::CircuitBreakerConfig;
+use crate::modules::proxy_db::ReplayRecord;
+use crate::proxy::common::model_mapping::normalize_to_standard_id;
+use crate::proxy::rate_limit::RateLimitReason;
+use crate::proxy::sticky_config::{SchedulingMode, StickySessionConfig};
+use crate::proxy::TokenManager;
+
+const QUOTA_EXHAUSTED_BODY: &str =
+    r#"{"error":{"code":429,"message":"Resource has been exhausted","details":[{"reason":"QUOTA_EXHAUSTED"}]}}"#;
+const RATE_LIMIT_BODY: &str =
+    r#"{"error":{"code":429,"message":"Too many requests","details":[{"reason":"RATE_LIMIT_EXCEEDED"}]}}"#;
+
+/// 合成账号定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyntheticAccount {
+    pub email: String,
+    /// 订阅等级 (FREE / PRO / ULTRA)
+    pub tier: String,
+    /// 单个模型组 100% 配额对应的 token 数
+    pub quota_tokens: u64,
+    /// 初始剩余百分比
+    pub initial_percentage: i32,
+    /// 配额重置周期 (小时)
+    pub reset_hours: u32,
+    /// 每分钟请求上限，超过则返回 429 RATE_LIMIT_EXCEEDED
+    pub rpm_limit: Option<u32>,
+    /// 随机 429 概率 (0.0 - 1.0)
+    pub error_rate: f64,
+    /// 支持的模型组 (标准 ID)
+    pub models: Vec<String>,
+}
+
+impl Default for SyntheticAccount {
+    fn default() -> Self {
+        Self {
+            email: "sim@example.com".to_string(),
+            tier: "PRO".to_string(),
+            quota_tokens: 2_000_000,
+            initial_percentage: 100,
+            reset_hours: 5,
+            rpm_limit: None,
+            error_rate: 0.0,
+            models: vec![
+                "claude".to_string(),
+                "gemini-3-pro-high".to_string(),
+                "gemini-3-flash".to_string(),
+            ],
+        }
+    }
+}
+
+/// 待评估的调度策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationPolicy {
+    pub name: String,
+    pub scheduling: StickySessionConfig,
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// 配额保护阈值 (百分比)，None 表示关闭
+    pub quota_protection_threshold: Option<u32>,
+}
+
+impl Default for SimulationPolicy {
+    fn default() -> Self {
+        Self {
+            name: "balance".to_string(),
+            scheduling: StickySessionConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            quota_protection_threshold: None,
+        }
+    }
+}
+
+/// 模拟配置 (headless 子命令可从 JSON 文件加载)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    pub accounts: Vec<SyntheticAccount>,
+    pub policies: Vec<SimulationPolicy>,
+    /// 回放最近多少小时的流量
+    pub since_hours: i64,
+    /// 最多回放的请求数
+    pub limit: usize,
+    /// 单个请求的最大尝试次数 (含轮换重试)
+    pub max_attempts: usize,
+    /// 随机种子，保证结果可复现
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        let accounts = (1..=4)
+            .map(|i| SyntheticAccount {
+                email: format!("sim{}@example.com", i),
+                ..SyntheticAccount::default()
+            })
+            .collect();
+        let policies = [
+            ("cache_first", SchedulingMode::CacheFirst),
+            ("balance", SchedulingMode::Balance),
+            ("performance_first", SchedulingMode::PerformanceFirst),
+        ]
+        .into_iter()
+        .map(|(name, mode)| SimulationPolicy {
+            name: name.to_string(),
+            scheduling: StickySessionConfig {
+                mode,
+                ..StickySessionConfig::default()
+            },
+            ..SimulationPolicy::default()
+        })
+        .collect();
+
+        Self {
+            accounts,
+            policies,
+            since_hours: 24,
+            limit: 2000,
+            max_attempts: 3,
+            seed: 42,
+        }
+    }
+}
+
+/// 单个回放请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimRequest {
+    /// 相对首个请求的虚拟时间 (秒)
+    pub offset_secs: i64,
+    pub model: String,
+    pub tokens: u64,
+    pub session_id: Option<String>,
+}
+
+/// 单账号负载统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccountLoad {
+    pub email: String,
+    pub requests: u64,
+    pub tokens: u64,
+    pub share: f64,
+    pub rate_limited: u64,
+    pub quota_protected: u64,
+}
+
+/// 单策略模拟结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PolicyReport {
+    pub policy: String,
+    pub requests: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub success_rate: f64,
+    pub retries: u64,
+    pub total_wait_secs: f64,
+    pub avg_wait_secs: f64,
+    /// 同一会话再次请求时仍命中上次账号的比例 (Prompt Cache 亲和性)
+    pub cache_locality: f64,
+    pub rate_limit_events: u64,
+    pub accounts: Vec<AccountLoad>,
+}
+
+/// 完整模拟报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SimulationReport {
+    pub requests: usize,
+    /// 回放流量覆盖的虚拟时长 (秒)
+    pub span_secs: i64,
+    pub policies: Vec<PolicyReport>,
+}
+
+/// 将 proxy_db 记录转换为回放请求
+/// 日志中没有原始会话 ID，这里以 (用户令牌, 客户端 IP) 近似代表一个会话
+pub fn requests_from_records(records: &[ReplayRecord]) -> Vec<SimRequest> {
+    let Some(first_ts) = records.iter().map(|r| r.timestamp).min() else {
+        return Vec::new();
+    };
+
+    records
+        .iter()
+        .map(|r| {
+            let session_id = match (&r.username, &r.client_ip) {
+                (None, None) => None,
+                (user, ip) => Some(format!(
+                    "{}|{}",
+                    user.as_deref().unwrap_or(""),
+                    ip.as_deref().unwrap_or("")
+                )),
+            };
+            SimRequest {
+                offset_secs: (r.timestamp - first_ts) / 1000,
+                model: r.mapped_model.clone().unwrap_or_else(|| r.model.clone()),
+                tokens: r.input_tokens as u64 + r.output_tokens as u64,
+                session_id,
+            }
+        })
+        .collect()
+}
+
+/// 从 TokenManager 的 "All accounts limited. Wait Ns." 错误中解析等待秒数
+fn parse_wait_secs(error: &str) -> Option<u64> {
+    error
+        .strip_prefix("All accounts limited. Wait ")?
+        .strip_suffix("s.")?
+        .parse()
+        .ok()
+}
+
+enum ServeOutcome {
+    Success,
+    RateLimited,
+    QuotaExhausted,
+}
+
+struct SimAccount {
+    account_id: String,
+    spec: SyntheticAccount,
+    /// 模型组 -> 剩余百分比
+    quota: HashMap<String, f64>,
+    next_reset: i64,
+    recent: VecDeque<i64>,
+    /// 配额保护生效中的模型组
+    protected: Vec<String>,
+    load: AccountLoad,
+}
+
+impl SimAccount {
+    fn serve(&mut self, now: i64, group: &str, rng: &mut StdRng) -> ServeOutcome {
+        while self.recent.front().map_or(false, |t| now - *t >= 60) {
+            self.recent.pop_front();
+        }
+        if let Some(limit) = self.spec.rpm_limit {
+            if self.recent.len() >= limit as usize {
+                return ServeOutcome::RateLimited;
+            }
+        }
+        if self.quota.get(group).copied().unwrap_or(0.0) <= 0.0 {
+            return ServeOutcome::QuotaExhausted;
+        }
+        if self.spec.error_rate > 0.0 && rng.gen_bool(self.spec.error_rate.min(1.0)) {
+            return ServeOutcome::RateLimited;
+        }
+        ServeOutcome::Success
+    }
+
+    /// 扣减配额，返回扣减后的剩余百分比
+    fn consume(&mut self, now: i64, group: &str, tokens: u64) -> f64 {
+        self.recent.push_back(now);
+        let cost = tokens as f64 * 100.0 / self.spec.quota_tokens.max(1) as f64;
+        let remaining = self.quota.entry(group.to_string()).or_insert(0.0);
+        *remaining = (*remaining - cost).max(0.0);
+        *remaining
+    }
+}
+
+struct VirtualLock {
+    account_id: String,
+    until: i64,
+}
+
+fn seed_accounts(dir: &Path, accounts: &[SyntheticAccount]) -> Result<Vec<String>, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建模拟目录失败: {}", e))?;
+
+    let now = chrono::Utc::now().timestamp();
+    let mut ids = Vec::new();
+    for (i, acc) in accounts.iter().enumerate() {
+        let id = format!("sim-{}", i + 1);
+        let reset_time = chrono::DateTime::from_timestamp(now + acc.reset_hours as i64 * 3600, 0)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default();
+        let models: Vec<serde_json::Value> = acc
+            .models
+            .iter()
+            .map(|m| {
+                serde_json::json!({
+                    "name": m,
+                    "percentage": acc.initial_percentage,
+                    "reset_time": reset_time,
+                })
+            })
+            .collect();
+        // 令牌设置为远期过期并预置 project_id，避免模拟过程中触发 OAuth 刷新或网络请求
+        let json = serde_json::json!({
+            "id": id,
+            "email": acc.email,
+            "token": {
+                "access_token": format!("sim-atk-{}", i + 1),
+                "refresh_token": format!("sim-rtk-{}", i + 1),
+                "expires_in": 3600,
+                "expiry_timestamp": now + 365 * 86400,
+                "token_type": "Bearer",
+                "project_id": "sim-project",
+            },
+            "quota": {
+                "models": models,
+                "last_updated": now,
+                "subscription_tier": acc.tier,
+            },
+            "disabled": false,
+            "proxy_disabled": false,
+            "created_at": now,
+            "last_used": now,
+        });
+        let account: crate::models::Account = serde_json::from_value(json)
+            .map_err(|e| format!("构造模拟账号失败: {}", e))?;
+        crate::modules::account_db::save_account(
+            dir,
+            &account,
+            crate::modules::account_db::ChangeOrigin::Proxy,
+        )
+        .map_err(|e| format!("写入模拟账号失败: {}", e))?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+struct PolicyRun<'a> {
+    config: &'a SimulationConfig,
+    policy: &'a SimulationPolicy,
+    manager: TokenManager,
+    accounts: HashMap<String, SimAccount>,
+    locks: Vec<VirtualLock>,
+    last_selection: Option<i64>,
+    session_accounts: HashMap<String, String>,
+    rng: StdRng,
+    report: PolicyReport,
+    cache_candidates: u64,
+    cache_hits: u64,
+}
+
+impl PolicyRun<'_> {
+    /// 推进虚拟时钟：处理配额重置、解除到期锁定、恢复被乐观重置清掉的保护锁
+    async fn advance(&mut self, now: i64) {
+        let tracker = self.manager.rate_limit_tracker();
+
+        for acc in self.accounts.values_mut() {
+            if now >= acc.next_reset {
+                while now >= acc.next_reset {
+                    acc.next_reset += acc.spec.reset_hours.max(1) as i64 * 3600;
+                }
+                for (group, pct) in acc.quota.iter_mut() {
+                    *pct = 100.0;
+                    self.manager.set_model_quota(&acc.account_id, group, 100);
+                }
+                for group in acc.protected.drain(..) {
+                    tracker.clear_model(&acc.account_id, &group);
+                }
+            }
+            for group in &acc.protected {
+                if !tracker.is_rate_limited(&acc.account_id, Some(group)) {
+                    let secs = (acc.next_reset - now).max(1) as u64;
+                    tracker.set_lockout_until(
+                        &acc.account_id,
+                        SystemTime::now() + Duration::from_secs(secs),
+                        RateLimitReason::QuotaExhausted,
+                        Some(group.clone()),
+                    );
+                }
+            }
+        }
+
+        self.locks.retain(|lock| {
+            if lock.until <= now {
+                tracker.clear(&lock.account_id);
+                false
+            } else {
+                true
+            }
+        });
+
+        // 60s 全局锁定窗口依赖真实时间，按虚拟时间手动过期
+        if self.last_selection.map_or(false, |t| now - t >= 60) {
+            self.last_selection = None;
+            self.manager.clear_last_used_account().await;
+        }
+    }
+
+    async fn replay(&mut self, req: &SimRequest) {
+        self.report.requests += 1;
+        let group = normalize_to_standard_id(&req.model).unwrap_or_else(|| req.model.clone());
+        let quota_group = if group == "claude" { "claude" } else { "gemini" };
+        let mut now = req.offset_secs;
+        let mut waited = 0.0;
+        self.advance(now).await;
+
+        for attempt in 0..self.config.max_attempts.max(1) {
+            if attempt > 0 {
+                self.report.retries += 1;
+            }
+            let selected = self
+                .manager
+                .get_token(quota_group, attempt > 0, req.session_id.as_deref(), &req.model)
+                .await;
+
+            let (email, account_id, wait_ms) = match selected {
+                Ok((_, _, email, account_id, wait_ms)) => (email, account_id, wait_ms),
+                Err(e) => match parse_wait_secs(&e) {
+                    Some(wait) if wait <= self.policy.scheduling.max_wait_seconds => {
+                        waited += wait as f64;
+                        now += wait as i64;
+                        self.advance(now).await;
+                        continue;
+                    }
+                    _ => break,
+                },
+            };
+            waited += wait_ms as f64 / 1000.0;
+            self.last_selection = Some(now);
+
+            let Some(acc) = self.accounts.get_mut(&account_id) else {
+                break;
+            };
+            match acc.serve(now, &group, &mut self.rng) {
+                ServeOutcome::Success => {
+                    let remaining = acc.consume(now, &group, req.tokens);
+                    acc.load.requests += 1;
+                    acc.load.tokens += req.tokens;
+                    self.manager
+                        .set_model_quota(&account_id, &group, remaining.ceil() as i32);
+                    // 与 handler 保持一致：成功后以 email 调用
+                    self.manager.mark_account_success(&email);
+
+                    if let Some(threshold) = self.policy.quota_protection_threshold {
+                        if remaining <= threshold as f64 && !acc.protected.contains(&group) {
+                            acc.protected.push(group.clone());
+                            acc.load.quota_protected += 1;
+                        }
+                    }
+
+                    if let Some(ref sid) = req.session_id {
+                        if let Some(prev) = self.session_accounts.insert(sid.clone(), account_id.clone()) {
+                            self.cache_candidates += 1;
+                            if prev == account_id {
+                                self.cache_hits += 1;
+                            }
+                        }
+                    }
+
+                    self.report.succeeded += 1;
+                    self.report.total_wait_secs += waited;
+                    return;
+                }
+                outcome => {
+                    let body = match outcome {
+                        ServeOutcome::QuotaExhausted => QUOTA_EXHAUSTED_BODY,
+                        _ => RATE_LIMIT_BODY,
+                    };
+                    acc.load.rate_limited += 1;
+                    self.report.rate_limit_events += 1;
+                    self.manager.mark_rate_limited(&email, 429, None, body).await;
+                    if let Some(secs) = self.manager.get_rate_limit_reset_seconds(&account_id) {
+                        self.locks.push(VirtualLock {
+                            account_id: account_id.clone(),
+                            until: now + secs as i64,
+                        });
+                    }
+                }
+            }
+        }
+
+        self.report.failed += 1;
+        self.report.total_wait_secs += waited;
+    }
+
+    fn finish(mut self) -> PolicyReport {
+        let total = self.report.requests.max(1) as f64;
+        self.report.success_rate = self.report.succeeded as f64 / total;
+        self.report.avg_wait_secs = self.report.total_wait_secs / total;
+        self.report.cache_locality = if self.cache_candidates > 0 {
+            self.cache_hits as f64 / self.cache_candidates as f64
+        } else {
+            0.0
+        };
+
+        let succeeded = self.report.succeeded.max(1) as f64;
+        let mut accounts: Vec<AccountLoad> = self
+            .accounts
+            .into_values()
+            .map(|mut acc| {
+                acc.load.share = acc.load.requests as f64 / succeeded;
+                acc.load
+            })
+            .collect();
+        accounts.sort_by(|a, b| a.email.cmp(&b.email));
+        self.report.accounts = accounts;
+        self.report
+    }
+}
+
+async fn run_policy(
+    config: &SimulationConfig,
+    policy: &SimulationPolicy,
+    requests: &[SimRequest],
+) -> Result<PolicyReport, String> {
+    let dir = std::env::temp_dir().join(format!("antigravity-simulation-{}", uuid::Uuid::new_v4()));
+    let ids = seed_accounts(&dir, &config.accounts)?;
+
+    let manager = TokenManager::new(dir.clone());
+    let loaded = manager.load_accounts().await;
+    manager.update_sticky_config(policy.scheduling.clone()).await;
+    manager
+        .update_circuit_breaker_config(policy.circuit_breaker.clone())
+        .await;
+
+    let reset_base = requests.first().map(|r| r.offset_secs).unwrap_or(0);
+    let accounts = ids
+        .into_iter()
+        .zip(config.accounts.iter().cloned())
+        .map(|(id, spec)| {
+            let quota = spec
+                .models
+                .iter()
+                .map(|m| normalize_to_standard_id(m).unwrap_or_else(|| m.clone()))
+                .map(|g| (g, spec.initial_percentage as f64))
+                .collect();
+            let sim = SimAccount {
+                account_id: id.clone(),
+                next_reset: reset_base + spec.reset_hours.max(1) as i64 * 3600,
+                quota,
+                recent: VecDeque::new(),
+                protected: Vec::new(),
+                load: AccountLoad {
+                    email: spec.email.clone(),
+                    ..AccountLoad::default()
+                },
+                spec,
+            };
+            (id, sim)
+        })
+        .collect();
+
+    let mut run = PolicyRun {
+        config,
+        policy,
+        manager,
+        accounts,
+        locks: Vec::new(),
+        last_selection: None,
+        session_accounts: HashMap::new(),
+        rng: StdRng::seed_from_u64(config.seed),
+        report: PolicyReport {
+            policy: policy.name.clone(),
+            ..PolicyReport::default()
+        },
+        cache_candidates: 0,
+        cache_hits: 0,
+    };
+
+    if let Err(e) = loaded {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    for req in requests {
+        run.replay(req).await;
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(run.finish())
+}
+
+/// 对给定请求序列运行所有策略
+pub async fn simulate(config: &SimulationConfig, requests: &[SimRequest]) -> Result<SimulationReport, String> {
+    if config.accounts.is_empty() {
+        return Err("模拟至少需要一个合成账号".to_string());
+    }
+
+    let mut policies = Vec::new();
+    for policy in &config.policies {
+        policies.push(run_policy(config, policy, requests).await?);
+    }
+
+    Ok(SimulationReport {
+        requests: requests.len(),
+        span_secs: requests.last().map(|r| r.offset_secs).unwrap_or(0),
+        policies,
+    })
+}
+
+/// 从 proxy_db 读取最近的流量并运行模拟
+pub async fn simulate_from_proxy_db(config: &SimulationConfig) -> Result<SimulationReport, String> {
+    let since_ms = (chrono::Utc::now().timestamp() - config.since_hours * 3600) * 1000;
+    let limit = config.limit;
+    let records = tokio::task::spawn_blocking(move || {
+        crate::modules::proxy_db::init_db()?;
+        crate::modules::proxy_db::get_replay_traffic(since_ms, limit)
+    })
+    .await
+    .map_err(|e| format!("读取流量失败: {}", e))??;
+
+    if records.is_empty() {
+        return Err("proxy_db 中没有可回放的流量".to_string());
+    }
+    simulate(config, &requests_from_records(&records)).await
+}
+
+/// headless 子命令入口: `--simulate [config.json]`
+pub async fn run_cli(config_path: Option<&str>) -> Result<String, String> {
+    let config = match config_path {
+        Some(path) => {
+            let content =
+                std::fs::read_to_string(path).map_err(|e| format!("读取模拟配置失败: {}", e))?;
+            serde_json::from_str(&content).map_err(|e| format!("解析模拟配置失败: {}", e))?
+        }
+        None => SimulationConfig::default(),
+    };
+    let report = simulate_from_proxy_db(&config).await?;
+    serde_json::to_string_pretty(&report).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic_traffic(sessions: usize, per_session: usize, tokens: u64) -> Vec<SimRequest> {
+        let mut requests = Vec::new();
+        for i in 0..per_session {
+            for s in 0..sessions {
+                requests.push(SimRequest {
+                    offset_secs: (i * sessions + s) as i64 * 20,
+                    model: "claude-sonnet-4-6".to_string(),
+                    tokens,
+                    session_id: Some(format!("session-{}", s)),
+                });
+            }
+        }
+        requests
+    }
+
+    #[test]
+    fn test_requests_from_records() {
+        let record = |ts: i64, user: Option<&str>| ReplayRecord {
+            timestamp: ts,
+            model: "claude-sonnet-4-5".to_string(),
+            mapped_model: Some("claude-sonnet-4-6".to_string()),
+            input_tokens: 100,
+            output_tokens: 50,
+            client_ip: None,
+            username: user.map(|s| s.to_string()),
+        };
+        let requests = requests_from_records(&[record(10_000, Some("alice")), record(70_500, None)]);
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].offset_secs, 0);
+        assert_eq!(requests[1].offset_secs, 60);
+        assert_eq!(requests[0].model, "claude-sonnet-4-6");
+        assert_eq!(requests[0].tokens, 150);
+        assert_eq!(requests[0].session_id.as_deref(), Some("alice|"));
+        assert!(requests[1].session_id.is_none());
+
+        assert_eq!(parse_wait_secs("All accounts limited. Wait 42s."), Some(42));
+        assert_eq!(parse_wait_secs("Token pool is empty"), None);
+    }
+
+    #[tokio::test]
+    async fn test_sticky_policies_keep_cache_locality() {
+        let config = SimulationConfig::default();
+        let requests = synthetic_traffic(3, 5, 1_000);
+
+        let report = simulate(&config, &requests).await.unwrap();
+        assert_eq!(report.policies.len(), 3);
+        for p in &report.policies {
+            assert_eq!(p.requests, requests.len() as u64);
+            assert_eq!(p.succeeded + p.failed, p.requests);
+            assert_eq!(p.succeeded, p.requests, "policy {} should serve all requests", p.policy);
+        }
+
+        let locality = |name: &str| {
+            report
+                .policies
+                .iter()
+                .find(|p| p.policy == name)
+                .map(|p| p.cache_locality)
+                .unwrap()
+        };
+        assert_eq!(locality("cache_first"), 1.0);
+        assert_eq!(locality("balance"), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_account_is_rotated_away() {
+        let config = SimulationConfig {
+            accounts: vec![
+                SyntheticAccount {
+                    email: "small@example.com".to_string(),
+                    quota_tokens: 1_000,
+                    ..SyntheticAccount::default()
+                },
+                SyntheticAccount {
+                    email: "large@example.com".to_string(),
+                    ..SyntheticAccount::default()
+                },
+            ],
+            policies: vec![SimulationPolicy {
+                name: "performance_first".to_string(),
+                scheduling: StickySessionConfig {
+                    mode: SchedulingMode::PerformanceFirst,
+                    ..StickySessionConfig::default()
+                },
+                ..SimulationPolicy::default()
+            }],
+            ..SimulationConfig::default()
+        };
+        let requests = synthetic_traffic(2, 10, 800);
+
+        let report = simulate(&config, &requests).await.unwrap();
+        let p = &report.policies[0];
+        assert_eq!(p.succeeded, p.requests);
+        let small = p.accounts.iter().find(|a| a.email == "small@example.com").unwrap();
+        let large = p.accounts.iter().find(|a| a.email == "large@example.com").unwrap();
+        // 小额度账号耗尽后只能由大额度账号承接
+        assert!(small.requests <= 2);
+        assert!(large.requests >= p.succeeded - 2);
+    }
+}
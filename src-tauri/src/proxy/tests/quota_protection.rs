@@ -5,9 +5,10 @@
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::Path;
 
-    use crate::models::QuotaProtectionConfig;
+    use crate::models::{Account, QuotaData, QuotaProtectionConfig, TokenData};
+    use crate::modules::account_db::{self, ChangeOrigin};
     use crate::proxy::common::model_mapping::normalize_to_standard_id;
     use crate::proxy::token_manager::ProxyToken;
 
@@ -28,7 +29,6 @@ mod tests {
             expires_in: 3600,
             timestamp: chrono::Utc::now().timestamp() + 3600,
             email: email.to_string(),
-            account_path: PathBuf::from(format!("/tmp/test_accounts/{}.json", account_id)),
             project_id: Some("test-project".to_string()),
             subscription_tier: Some("PRO".to_string()),
             remaining_quota,
@@ -37,6 +37,7 @@ mod tests {
             reset_time: None,
             validation_blocked: false,
             validation_blocked_until: 0,
+            validation_url: None,
             model_quotas: std::collections::HashMap::new(),
         }
     }
@@ -920,34 +921,26 @@ mod tests {
     }
 
     // ==================================================================================
-    // 测试 17: get_model_quota_from_json 函数正确性
-    // 验证从磁盘读取特定模型 quota 而非 max(所有模型)
+    // 测试 17: get_model_quota_from_store 函数正确性
+    // 验证从账号存储读取特定模型 quota 而非 max(所有模型)
     // ==================================================================================
 
     #[test]
-    fn test_get_model_quota_from_json_reads_correct_model() {
-        // 创建模拟账号 JSON 文件，包含多个模型的 quota
-        let account_json = serde_json::json!({
-            "email": "test@example.com",
-            "quota": {
-                "models": [
-                    { "name": "claude", "percentage": 60 },
-                    { "name": "claude-opus-4-5-thinking", "percentage": 40 },
-                    { "name": "gemini-3-flash", "percentage": 100 }
-                ]
-            }
-        });
-
-        // 使用 std::env::temp_dir() 创建临时文件
-        let temp_dir = std::env::temp_dir();
-        let account_path = temp_dir.join(format!("test_quota_{}.json", uuid::Uuid::new_v4()));
-        std::fs::write(&account_path, account_json.to_string()).expect("Failed to write temp file");
+    fn test_get_model_quota_from_store_reads_correct_model() {
+        // 在临时账号存储中创建包含多个模型 quota 的账号
+        let temp_dir = std::env::temp_dir().join(format!("test_quota_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).expect("Failed to create temp dir");
+        seed_quota_account(
+            &temp_dir,
+            "acc",
+            "test@example.com",
+            &[("claude", 60), ("claude-opus-4-5-thinking", 40), ("gemini-3-flash", 100)],
+        );
 
         // 测试读取 claude 的 quota
         let sonnet_quota =
-            crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-                &account_path,
-                "claude",
+            crate::proxy::token_manager::TokenManager::get_model_quota_from_store_for_test(
+                &temp_dir, "acc", "claude",
             );
         assert_eq!(
             sonnet_quota,
@@ -957,22 +950,24 @@ mod tests {
 
         // 测试读取 gemini-3-flash 的 quota
         let gemini_quota =
-            crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-                &account_path,
+            crate::proxy::token_manager::TokenManager::get_model_quota_from_store_for_test(
+                &temp_dir,
+                "acc",
                 "gemini-3-flash",
             );
         assert_eq!(gemini_quota, Some(100), "gemini-3-flash 应该返回 100%");
 
         // 测试读取不存在的模型
         let unknown_quota =
-            crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-                &account_path,
+            crate::proxy::token_manager::TokenManager::get_model_quota_from_store_for_test(
+                &temp_dir,
+                "acc",
                 "unknown-model",
             );
         assert_eq!(unknown_quota, None, "不存在的模型应该返回 None");
 
-        // 清理临时文件
-        let _ = std::fs::remove_file(&account_path);
+        // 清理临时目录
+        let _ = std::fs::remove_dir_all(&temp_dir);
     }
 
     // ==================================================================================
@@ -987,52 +982,32 @@ mod tests {
         std::fs::create_dir_all(&temp_dir).expect("Failed to create temp dir");
 
         // 账号 A: max=100 (gemini), sonnet=40
-        let account_a_json = serde_json::json!({
-            "email": "carmelioventori@example.com",
-            "quota": {
-                "models": [
-                    { "name": "claude", "percentage": 40 },
-                    { "name": "gemini-3-flash", "percentage": 100 }
-                ]
-            }
-        });
-
+        seed_quota_account(
+            &temp_dir,
+            "a",
+            "carmelioventori@example.com",
+            &[("claude", 40), ("gemini-3-flash", 100)],
+        );
         // 账号 B: max=100 (gemini), sonnet=100
-        let account_b_json = serde_json::json!({
-            "email": "kiriyamaleo@example.com",
-            "quota": {
-                "models": [
-                    { "name": "claude", "percentage": 100 },
-                    { "name": "gemini-3-flash", "percentage": 100 }
-                ]
-            }
-        });
-
+        seed_quota_account(
+            &temp_dir,
+            "b",
+            "kiriyamaleo@example.com",
+            &[("claude", 100), ("gemini-3-flash", 100)],
+        );
         // 账号 C: max=100 (gemini), sonnet=60
-        let account_c_json = serde_json::json!({
-            "email": "mizusawakai9@example.com",
-            "quota": {
-                "models": [
-                    { "name": "claude", "percentage": 60 },
-                    { "name": "gemini-3-flash", "percentage": 100 }
-                ]
-            }
-        });
-
-        // 写入临时文件
-        let path_a = temp_dir.join("account_a.json");
-        let path_b = temp_dir.join("account_b.json");
-        let path_c = temp_dir.join("account_c.json");
-
-        std::fs::write(&path_a, account_a_json.to_string()).unwrap();
-        std::fs::write(&path_b, account_b_json.to_string()).unwrap();
-        std::fs::write(&path_c, account_c_json.to_string()).unwrap();
+        seed_quota_account(
+            &temp_dir,
+            "c",
+            "mizusawakai9@example.com",
+            &[("claude", 60), ("gemini-3-flash", 100)],
+        );
 
         // 创建 tokens，remaining_quota 使用 max 值（模拟旧逻辑）
         let mut tokens = vec![
-            create_mock_token_with_path("a", "carmelioventori@example.com", vec![], Some(100), path_a.clone()),
-            create_mock_token_with_path("b", "kiriyamaleo@example.com", vec![], Some(100), path_b.clone()),
-            create_mock_token_with_path("c", "mizusawakai9@example.com", vec![], Some(100), path_c.clone()),
+            create_mock_token("a", "carmelioventori@example.com", vec![], Some(100)),
+            create_mock_token("b", "kiriyamaleo@example.com", vec![], Some(100)),
+            create_mock_token("c", "mizusawakai9@example.com", vec![], Some(100)),
         ];
 
         // 目标模型: claude
@@ -1040,13 +1015,15 @@ mod tests {
 
         // 使用修复后的排序逻辑：读取目标模型的 quota
         tokens.sort_by(|a, b| {
-            let quota_a = crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-                &a.account_path,
+            let quota_a = crate::proxy::token_manager::TokenManager::get_model_quota_from_store_for_test(
+                &temp_dir,
+                &a.account_id,
                 target_model,
             )
             .unwrap_or(0);
-            let quota_b = crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-                &b.account_path,
+            let quota_b = crate::proxy::token_manager::TokenManager::get_model_quota_from_store_for_test(
+                &temp_dir,
+                &b.account_id,
                 target_model,
             )
             .unwrap_or(0);
@@ -1078,20 +1055,15 @@ mod tests {
 
     #[test]
     fn test_quota_matching_with_normalized_model_name() {
-        // 账号 JSON：只记录标准化后的模型名
-        let account_json = serde_json::json!({
-            "email": "test@example.com",
-            "quota": {
-                "models": [
-                    { "name": "claude", "percentage": 75 },
-                    { "name": "gemini-3-flash", "percentage": 90 }
-                ]
-            }
-        });
-
-        let temp_dir = std::env::temp_dir();
-        let account_path = temp_dir.join(format!("test_normalized_{}.json", uuid::Uuid::new_v4()));
-        std::fs::write(&account_path, account_json.to_string()).expect("Failed to write temp file");
+        // 账号只记录标准化后的模型名
+        let temp_dir = std::env::temp_dir().join(format!("test_normalized_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).expect("Failed to create temp dir");
+        seed_quota_account(
+            &temp_dir,
+            "acc",
+            "test@example.com",
+            &[("claude", 75), ("gemini-3-flash", 90)],
+        );
 
         // 请求 claude-opus-4-5-thinking，应该归一化为 claude
         let request_model = "claude-opus-4-5-thinking";
@@ -1101,8 +1073,9 @@ mod tests {
         assert_eq!(normalized, "claude", "应该归一化为 claude");
 
         // 读取归一化后模型的 quota
-        let quota = crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-            &account_path,
+        let quota = crate::proxy::token_manager::TokenManager::get_model_quota_from_store_for_test(
+            &temp_dir,
+            "acc",
             &normalized,
         );
 
@@ -1112,35 +1085,27 @@ mod tests {
             "claude-opus-4-5-thinking 归一化后应该读取 claude 的 quota (75%)"
         );
 
-        // 清理临时文件
-        let _ = std::fs::remove_file(&account_path);
+        // 清理临时目录
+        let _ = std::fs::remove_dir_all(&temp_dir);
     }
 
-    /// 辅助函数：创建带有自定义 account_path 的 mock token
-    fn create_mock_token_with_path(
-        account_id: &str,
-        email: &str,
-        protected_models: Vec<&str>,
-        remaining_quota: Option<i32>,
-        account_path: PathBuf,
-    ) -> ProxyToken {
-        ProxyToken {
-            account_id: account_id.to_string(),
-            access_token: format!("mock_access_token_{}", account_id),
-            refresh_token: format!("mock_refresh_token_{}", account_id),
-            expires_in: 3600,
-            timestamp: chrono::Utc::now().timestamp() + 3600,
-            email: email.to_string(),
-            account_path,
-            project_id: Some("test-project".to_string()),
-            subscription_tier: Some("PRO".to_string()),
-            remaining_quota,
-            protected_models: protected_models.iter().map(|s| s.to_string()).collect(),
-            health_score: 1.0,
-            reset_time: None,
-            validation_blocked: false,
-            validation_blocked_until: 0,
-            model_quotas: std::collections::HashMap::new(),
+    /// 辅助函数：在临时账号存储中写入带有指定模型配额的账号
+    fn seed_quota_account(data_dir: &Path, account_id: &str, email: &str, models: &[(&str, i32)]) {
+        let mut quota = QuotaData::new();
+        for (name, percentage) in models {
+            quota.add_model(name.to_string(), *percentage, String::new());
         }
+        let token = TokenData::new(
+            format!("mock_access_token_{}", account_id),
+            format!("mock_refresh_token_{}", account_id),
+            3600,
+            Some(email.to_string()),
+            None,
+            None,
+        );
+        let mut account = Account::new(account_id.to_string(), email.to_string(), token);
+        account.quota = Some(quota);
+        account_db::save_account(data_dir, &account, ChangeOrigin::App)
+            .expect("Failed to seed account");
     }
 }
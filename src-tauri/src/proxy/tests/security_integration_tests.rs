@@ -254,6 +254,9 @@ mod integration_tests {
             blocked: true,
             block_reason: Some("IP in blacklist".to_string()),
             username: None,
+            country: None,
+            asn: None,
+            asn_org: None,
         };
 
         let save_result = security_db::save_ip_access_log(&log);
@@ -429,6 +432,9 @@ mod stress_tests {
                 blocked: false,
                 block_reason: None,
                 username: None,
+                country: None,
+                asn: None,
+                asn_org: None,
             };
             let _ = save_ip_access_log(&log);
         }
@@ -313,6 +313,47 @@ mod security_db_tests {
         cleanup_test_data();
     }
 
+    #[test]
+    fn test_ipv6_blacklist_and_whitelist() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        // IPv6 CIDR 与 IPv4 映射地址
+        let _ = add_to_blacklist("2001:db8::/32", Some("Block v6 range"), None, "test");
+        let _ = add_to_blacklist("203.0.113.0/24", Some("Block v4 range"), None, "test");
+        let _ = add_to_whitelist("fd00::/8", Some("ULA"));
+
+        assert!(is_ip_in_blacklist("2001:db8:abcd::1").unwrap());
+        assert!(is_ip_in_blacklist("[2001:DB8::5]").unwrap());
+        assert!(!is_ip_in_blacklist("2001:db9::1").unwrap());
+        assert!(is_ip_in_blacklist("::ffff:203.0.113.7").unwrap(), "mapped v4 should match v4 CIDR");
+        assert!(is_ip_in_whitelist("fd12:3456::1").unwrap());
+        assert!(!is_ip_in_whitelist("fe80::1").unwrap());
+
+        // 删除后内存索引应失效
+        let entry = get_blacklist_entry_for_ip("2001:db8::1").unwrap().unwrap();
+        let _ = remove_from_blacklist(&entry.id);
+        assert!(!is_ip_in_blacklist("2001:db8::1").unwrap());
+
+        cleanup_test_data();
+    }
+
+    #[test]
+    fn test_longest_prefix_entry_wins() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        let _ = add_to_blacklist("10.0.0.0/8", Some("wide"), None, "test");
+        let _ = add_to_blacklist("10.1.0.0/16", Some("narrow"), None, "test");
+
+        let entry = get_blacklist_entry_for_ip("10.1.2.3").unwrap().unwrap();
+        assert_eq!(entry.reason.as_deref(), Some("narrow"));
+        let entry = get_blacklist_entry_for_ip("10.2.2.3").unwrap().unwrap();
+        assert_eq!(entry.reason.as_deref(), Some("wide"));
+
+        cleanup_test_data();
+    }
+
     // ============================================================================
     // 测试类别 6: IP 访问日志
     // ============================================================================
@@ -336,6 +377,9 @@ mod security_db_tests {
             blocked: false,
             block_reason: None,
             username: None,
+            country: None,
+            asn: None,
+            asn_org: None,
         };
 
         let save_result = save_ip_access_log(&log);
@@ -371,6 +415,9 @@ mod security_db_tests {
             blocked: false,
             block_reason: None,
             username: None,
+            country: None,
+            asn: None,
+            asn_org: None,
         };
         let _ = save_ip_access_log(&normal_log);
 
@@ -388,6 +435,9 @@ mod security_db_tests {
             blocked: true,
             block_reason: Some("IP in blacklist".to_string()),
             username: None,
+            country: None,
+            asn: None,
+            asn_org: None,
         };
         let _ = save_ip_access_log(&blocked_log);
 
@@ -424,6 +474,9 @@ mod security_db_tests {
                 blocked: i == 4, // 最后一个被阻止
                 block_reason: if i == 4 { Some("Test".to_string()) } else { None },
                 username: None,
+                country: None,
+                asn: None,
+                asn_org: None,
             };
             let _ = save_ip_access_log(&log);
         }
@@ -447,6 +500,46 @@ mod security_db_tests {
         cleanup_test_data();
     }
 
+    #[test]
+    fn test_top_countries_and_asns() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        for i in 0..4 {
+            let log = IpAccessLog {
+                id: uuid::Uuid::new_v4().to_string(),
+                client_ip: format!("geo.test.{}", i % 2),
+                timestamp: now_timestamp(),
+                method: Some("POST".to_string()),
+                path: Some("/v1/messages".to_string()),
+                user_agent: None,
+                status: Some(200),
+                duration: Some(100),
+                api_key_hash: None,
+                blocked: i == 3,
+                block_reason: None,
+                username: None,
+                country: Some("ZZ".to_string()),
+                asn: Some(4_200_000_001),
+                asn_org: Some("Test Cloud".to_string()),
+            };
+            let _ = save_ip_access_log(&log);
+        }
+
+        let countries = security_db::get_top_countries(50, 1).unwrap();
+        let zz = countries.iter().find(|r| r.key == "ZZ").expect("country ranking");
+        assert_eq!(zz.request_count, 4);
+        assert_eq!(zz.unique_ips, 2);
+        assert_eq!(zz.blocked_count, 1);
+
+        let asns = security_db::get_top_asns(50, 1).unwrap();
+        let asn = asns.iter().find(|r| r.key == "AS4200000001").expect("asn ranking");
+        assert_eq!(asn.label.as_deref(), Some("Test Cloud"));
+        assert_eq!(asn.request_count, 4);
+
+        cleanup_test_data();
+    }
+
     // ============================================================================
     // 测试类别 8: 清理功能
     // ============================================================================
@@ -470,6 +563,9 @@ mod security_db_tests {
             blocked: false,
             block_reason: None,
             username: None,
+            country: None,
+            asn: None,
+            asn_org: None,
         };
         let _ = save_ip_access_log(&old_log);
 
@@ -487,6 +583,9 @@ mod security_db_tests {
             blocked: false,
             block_reason: None,
             username: None,
+            country: None,
+            asn: None,
+            asn_org: None,
         };
         let _ = save_ip_access_log(&new_log);
 
@@ -617,25 +716,87 @@ mod security_db_tests {
 
 #[cfg(test)]
 mod ip_filter_middleware_tests {
-    // 注意：中间件测试需要模拟 HTTP 请求，这里提供测试框架
-    // 实际的集成测试应该在启动完整服务后进行
+    use crate::proxy::config::TrustedProxyConfig;
+    use crate::proxy::middleware::ip_filter::resolve_client_ip;
+    use axum::http::HeaderMap;
+    use std::net::IpAddr;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    fn peer(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
 
-    /// 验证 IP 提取逻辑的正确性
+    /// 非可信对端的转发头会被忽略
     #[test]
-    fn test_ip_extraction_priority() {
-        // X-Forwarded-For 应该优先于 X-Real-IP
-        // X-Real-IP 应该优先于 ConnectInfo
-        // 这里只验证逻辑概念，实际测试需要构造 HTTP 请求
-        
-        // 场景 1: X-Forwarded-For 有多个 IP，取第一个
-        let xff_header = "203.0.113.1, 198.51.100.2, 192.0.2.3";
-        let first_ip = xff_header.split(',').next().unwrap().trim();
-        assert_eq!(first_ip, "203.0.113.1");
-
-        // 场景 2: 单个 IP
-        let single_ip = "10.0.0.1";
-        let parsed = single_ip.split(',').next().unwrap().trim();
-        assert_eq!(parsed, "10.0.0.1");
+    fn test_untrusted_peer_ignores_forwarded_headers() {
+        let cfg = TrustedProxyConfig::default();
+        let h = headers(&[("x-forwarded-for", "1.1.1.1")]);
+        assert_eq!(
+            resolve_client_ip(&h, peer("198.51.100.9"), &cfg).as_deref(),
+            Some("198.51.100.9")
+        );
+        assert_eq!(
+            resolve_client_ip(&h, peer("2001:db8::9"), &cfg).as_deref(),
+            Some("2001:db8::9")
+        );
+    }
+
+    /// 可信代理: X-Forwarded-For 从右向左跳过可信跳点
+    #[test]
+    fn test_trusted_proxy_walks_forwarded_for() {
+        let cfg = TrustedProxyConfig {
+            proxies: vec!["127.0.0.1".to_string(), "10.0.0.0/8".to_string()],
+            ..TrustedProxyConfig::default()
+        };
+        // 最左侧地址可被客户端伪造，应取最右侧的非可信地址
+        let h = headers(&[("x-forwarded-for", "6.6.6.6, 203.0.113.1, 10.0.0.2")]);
+        assert_eq!(
+            resolve_client_ip(&h, peer("::ffff:127.0.0.1"), &cfg).as_deref(),
+            Some("203.0.113.1")
+        );
+
+        // 全部为可信跳点时取最左侧
+        let h = headers(&[("x-forwarded-for", "10.0.0.3, 10.0.0.2")]);
+        assert_eq!(
+            resolve_client_ip(&h, peer("127.0.0.1"), &cfg).as_deref(),
+            Some("10.0.0.3")
+        );
+
+        // 无转发头时使用对端地址
+        assert_eq!(
+            resolve_client_ip(&HeaderMap::new(), peer("127.0.0.1"), &cfg).as_deref(),
+            Some("127.0.0.1")
+        );
+    }
+
+    /// 按配置顺序优先使用 CF-Connecting-IP
+    #[test]
+    fn test_header_priority_and_ipv6() {
+        let cfg = TrustedProxyConfig::default();
+        let h = headers(&[
+            ("cf-connecting-ip", "2001:db8::42"),
+            ("x-forwarded-for", "203.0.113.1"),
+        ]);
+        assert_eq!(
+            resolve_client_ip(&h, peer("::1"), &cfg).as_deref(),
+            Some("2001:db8::42")
+        );
+
+        let cfg = TrustedProxyConfig {
+            headers: vec!["x-forwarded-for".to_string()],
+            ..TrustedProxyConfig::default()
+        };
+        assert_eq!(
+            resolve_client_ip(&h, peer("::1"), &cfg).as_deref(),
+            Some("203.0.113.1")
+        );
     }
 }
 
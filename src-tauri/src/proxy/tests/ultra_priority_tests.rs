@@ -17,7 +17,6 @@
 
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
 
 use crate::proxy::token_manager::ProxyToken;
 
@@ -43,7 +42,6 @@ fn create_test_token(
         expires_in: 3600,
         timestamp: chrono::Utc::now().timestamp() + 3600,
         email: email.to_string(),
-        account_path: PathBuf::from("/tmp/test"),
         project_id: None,
         subscription_tier: tier.map(|s| s.to_string()),
         remaining_quota,
@@ -52,6 +50,7 @@ fn create_test_token(
         reset_time,
         validation_blocked: false,
         validation_blocked_until: 0,
+        validation_url: None,
         model_quotas,
     }
 }
@@ -0,0 +1,479 @@
+// 内置 TLS 终止
+// 反代监听端口同时接受 HTTPS 与明文 HTTP: 按首字节识别 TLS 握手，非回环地址的明文请求按配置重定向到 HTTPS 或拒绝。
+// 证书来源为用户提供的 cert/key，或由 mitm_ca 本地根证书签发 (SAN 覆盖局域网 IP 与主机名)；
+// 可选 mTLS: 客户端证书由同一根证书签发，握手时校验签名与有效期，证书与令牌的绑定及吊销由 auth 中间件检查；
+// 后台任务定期检查证书文件与局域网地址变化并热加载，新连接立即使用新证书。
+
+use axum::{
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{self, RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::modules::{ip_matcher, mitm_ca};
+use crate::proxy::config::{TlsClientAuthMode, TlsConfig};
+
+/// 证书文件 / 局域网地址变更检查间隔
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// 等待客户端首字节的超时
+const PEEK_TIMEOUT: Duration = Duration::from_secs(10);
+/// TLS 记录层 handshake 类型
+const TLS_HANDSHAKE_RECORD: u8 = 0x16;
+
+/// 标记经 TLS 建立的连接 (注入请求 extensions)
+#[derive(Debug, Clone, Default)]
+pub struct TlsConnection {
+    /// 已通过握手校验的客户端证书 SHA-256 指纹 (mTLS)
+    pub client_cert_fingerprint: Option<String>,
+}
+
+impl TlsConnection {
+    pub fn from_session(conn: &rustls::ServerConnection) -> Self {
+        Self {
+            client_cert_fingerprint: conn
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| mitm_ca::cert_fingerprint(cert.as_ref())),
+        }
+    }
+}
+
+/// 当前 TLS 状态 (管理接口展示)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TlsStatus {
+    pub enabled: bool,
+    /// 证书来源: "user" / "auto"
+    pub source: Option<String>,
+    pub cert_path: Option<String>,
+    /// 自动签发证书覆盖的 SAN
+    pub sans: Vec<String>,
+    pub loaded_at: Option<i64>,
+    /// 自动签发时需在客户端信任的根证书
+    pub ca_cert_path: Option<String>,
+    /// 客户端证书 (mTLS) 校验模式
+    pub client_auth: TlsClientAuthMode,
+    pub error: Option<String>,
+}
+
+/// 新连接的处理方式
+pub enum ConnectionMode {
+    /// 明文 HTTP (TLS 未启用或本机回环)
+    Plain,
+    /// TLS 握手后处理
+    Tls(TlsAcceptor),
+    /// 明文请求重定向到 HTTPS
+    Redirect,
+    /// 明文请求被拒绝
+    Reject,
+}
+
+#[derive(Default)]
+struct TlsState {
+    settings: TlsConfig,
+    acceptor: Option<TlsAcceptor>,
+    /// 已加载的证书文件及其修改时间
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    sans: Vec<String>,
+    status: TlsStatus,
+}
+
+static STATE: Lazy<RwLock<TlsState>> = Lazy::new(|| RwLock::new(TlsState::default()));
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// 更新 TLS 配置并立即 (重新) 加载证书
+pub fn update_tls_config(config: TlsConfig) {
+    let changed = {
+        let mut state = STATE.write();
+        let changed = state.settings != config;
+        state.settings = config;
+        changed
+    };
+    refresh(changed);
+    ensure_watcher();
+}
+
+/// 强制重新加载证书
+pub fn reload() -> TlsStatus {
+    refresh(true);
+    status()
+}
+
+pub fn status() -> TlsStatus {
+    STATE.read().status.clone()
+}
+
+/// 根据首字节判断连接类型；客户端超时未发送数据或已断开时返回 None
+pub async fn classify(stream: &TcpStream, remote_addr: SocketAddr) -> Option<ConnectionMode> {
+    let (acceptor, settings) = {
+        let state = STATE.read();
+        match &state.acceptor {
+            Some(acceptor) => (acceptor.clone(), state.settings.clone()),
+            None => return Some(ConnectionMode::Plain),
+        }
+    };
+
+    let mut first = [0u8; 1];
+    match tokio::time::timeout(PEEK_TIMEOUT, stream.peek(&mut first)).await {
+        Ok(Ok(n)) if n > 0 => {}
+        _ => return None,
+    }
+
+    if first[0] == TLS_HANDSHAKE_RECORD {
+        return Some(ConnectionMode::Tls(acceptor));
+    }
+    Some(plain_mode(&settings, remote_addr.ip()))
+}
+
+/// 明文连接的处理方式
+/// 强制客户端证书时回环明文同样拒绝: cloudflared 等本机转发的外部请求也来自回环地址，放行会绕过 mTLS
+fn plain_mode(settings: &TlsConfig, ip: IpAddr) -> ConnectionMode {
+    if settings.allow_plain_loopback
+        && settings.client_auth != TlsClientAuthMode::Required
+        && ip_matcher::normalize_ip(ip).is_loopback()
+    {
+        return ConnectionMode::Plain;
+    }
+    if settings.redirect_http {
+        ConnectionMode::Redirect
+    } else {
+        ConnectionMode::Reject
+    }
+}
+
+/// 明文请求重定向到同端口的 HTTPS
+pub fn redirect_router() -> Router {
+    Router::new().fallback(redirect_handler)
+}
+
+/// 明文请求直接拒绝
+pub fn reject_router() -> Router {
+    Router::new().fallback(reject_handler)
+}
+
+async fn redirect_handler(headers: HeaderMap, uri: Uri) -> Response {
+    let Some(host) = headers.get(header::HOST).and_then(|h| h.to_str().ok()) else {
+        return reject_handler().await;
+    };
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    (
+        StatusCode::PERMANENT_REDIRECT,
+        [(header::LOCATION, format!("https://{}{}", host, path))],
+    )
+        .into_response()
+}
+
+async fn reject_handler() -> Response {
+    (
+        StatusCode::UPGRADE_REQUIRED,
+        [(header::UPGRADE, "TLS/1.2, HTTP/1.1")],
+        "HTTPS is required on this port",
+    )
+        .into_response()
+}
+
+/// 自动签发证书的 SAN: 回环地址、主机名、局域网 IP 与额外配置
+pub fn gateway_sans(extra: &[String]) -> Vec<String> {
+    let mut sans = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    if let Some(name) = hostname::get().ok().and_then(|h| h.into_string().ok()) {
+        let name = name.trim().to_ascii_lowercase();
+        if !name.is_empty() && name.is_ascii() && !name.contains(' ') {
+            if !name.contains('.') {
+                sans.push(format!("{}.local", name));
+            }
+            sans.push(name);
+        }
+    }
+    for target in ["8.8.8.8:80", "[2001:4860:4860::8888]:80"] {
+        if let Some(ip) = local_ip_for(target) {
+            sans.push(ip.to_string());
+        }
+    }
+    sans.extend(
+        extra
+            .iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty()),
+    );
+    sans.sort();
+    sans.dedup();
+    sans
+}
+
+/// 通过未发送数据的 UDP connect 获取访问外网时使用的本机地址
+fn local_ip_for(target: &str) -> Option<IpAddr> {
+    let bind = if target.starts_with('[') {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    };
+    let socket = UdpSocket::bind(bind).ok()?;
+    socket.connect(target).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_unspecified() && !ip.is_loopback()).then_some(ip)
+}
+
+/// 相对路径按数据目录解析
+fn resolve_path(path: &str) -> PathBuf {
+    let path = PathBuf::from(path.trim());
+    if path.is_absolute() {
+        return path;
+    }
+    crate::modules::account::get_data_dir()
+        .map(|dir| dir.join(&path))
+        .unwrap_or(path)
+}
+
+fn file_stamps(paths: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    paths
+        .iter()
+        .map(|p| {
+            let modified = std::fs::metadata(p).and_then(|m| m.modified()).ok();
+            (p.clone(), modified)
+        })
+        .collect()
+}
+
+/// 按配置加载证书；force=false 时仅在文件或 SAN 变化时重新加载
+fn refresh(force: bool) {
+    let (settings, current_files, current_sans, loaded) = {
+        let state = STATE.read();
+        (
+            state.settings.clone(),
+            state.files.clone(),
+            state.sans.clone(),
+            state.acceptor.is_some(),
+        )
+    };
+
+    if !settings.enabled {
+        if loaded {
+            tracing::info!("[TLS] HTTPS listener disabled");
+        }
+        let mut state = STATE.write();
+        state.acceptor = None;
+        state.files.clear();
+        state.sans.clear();
+        state.status = TlsStatus::default();
+        return;
+    }
+
+    let user_paths = match (settings.cert_path.as_deref(), settings.key_path.as_deref()) {
+        (Some(cert), Some(key)) if !cert.trim().is_empty() && !key.trim().is_empty() => {
+            Some((resolve_path(cert), resolve_path(key)))
+        }
+        _ => None,
+    };
+
+    // 文件与 SAN 均未变化时跳过 (加载失败的证书也不会被反复重试，直到文件被替换)
+    let attempted = !current_files.is_empty();
+    let (source, sans, paths) = match user_paths {
+        Some((cert, key)) => {
+            if !force && attempted && file_stamps_unchanged(&current_files) {
+                return;
+            }
+            ("user", Vec::new(), Ok((cert, key)))
+        }
+        None => {
+            let sans = gateway_sans(&settings.extra_sans);
+            if !force && attempted && sans == current_sans && file_stamps_unchanged(&current_files) {
+                return;
+            }
+            ("auto", sans.clone(), mitm_ca::ensure_gateway_cert(&sans))
+        }
+    };
+
+    // mTLS 信任锚为本地根证书，根证书被替换时同样触发重新加载
+    let client_ca = match settings.client_auth {
+        TlsClientAuthMode::Off => Ok(None),
+        _ => mitm_ca::ensure_ca_cert_file().map(Some),
+    };
+    let paths = paths.and_then(|(cert, key)| client_ca.map(|ca| (cert, key, ca)));
+
+    let result = match paths {
+        Ok((cert, key, client_ca)) => {
+            let mut watched = vec![cert.clone(), key.clone()];
+            watched.extend(client_ca.clone());
+            let files = file_stamps(&watched);
+            match build_acceptor(&cert, &key, client_ca.as_ref(), settings.client_auth) {
+                Ok(acceptor) => Ok((acceptor, cert, files)),
+                Err(e) => Err((e, files)),
+            }
+        }
+        Err(e) => Err((e, Vec::new())),
+    };
+
+    let mut state = STATE.write();
+    state.sans = sans.clone();
+    match result {
+        Ok((acceptor, cert, files)) => {
+            tracing::info!("[TLS] Certificate loaded from {} ({})", cert.display(), source);
+            state.acceptor = Some(acceptor);
+            state.files = files;
+            state.status = TlsStatus {
+                enabled: true,
+                source: Some(source.to_string()),
+                cert_path: Some(cert.to_string_lossy().to_string()),
+                sans,
+                loaded_at: Some(chrono::Utc::now().timestamp()),
+                ca_cert_path: (source == "auto")
+                    .then(|| mitm_ca::ca_cert_file().to_string_lossy().to_string()),
+                client_auth: settings.client_auth,
+                error: None,
+            };
+        }
+        Err((e, files)) => {
+            // 保留之前可用的证书，避免轮换失败导致 HTTPS 中断
+            tracing::error!("[TLS] Failed to load certificate: {}", e);
+            state.files = files;
+            state.status.enabled = true;
+            state.status.error = Some(e);
+        }
+    }
+}
+
+fn file_stamps_unchanged(files: &[(PathBuf, Option<SystemTime>)]) -> bool {
+    let paths: Vec<PathBuf> = files.iter().map(|(p, _)| p.clone()).collect();
+    file_stamps(&paths) == files
+}
+
+fn build_acceptor(
+    cert_path: &PathBuf,
+    key_path: &PathBuf,
+    client_ca: Option<&PathBuf>,
+    client_auth: TlsClientAuthMode,
+) -> Result<TlsAcceptor, String> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Invalid certificate {}: {}", cert_path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", cert_path.display()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| format!("Invalid private key {}: {}", key_path.display(), e))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+    let builder = match (client_auth, client_ca) {
+        (TlsClientAuthMode::Off, _) | (_, None) => builder.with_no_client_auth(),
+        (mode, Some(ca_path)) => {
+            let mut roots = RootCertStore::empty();
+            for ca in CertificateDer::pem_file_iter(ca_path)
+                .map_err(|e| format!("Invalid CA certificate {}: {}", ca_path.display(), e))?
+            {
+                let ca = ca.map_err(|e| format!("Invalid CA certificate: {}", e))?;
+                roots.add(ca).map_err(|e| format!("Invalid CA certificate: {}", e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if mode == TlsClientAuthMode::Optional {
+                verifier.allow_unauthenticated()
+            } else {
+                verifier
+            };
+            let verifier = verifier
+                .build()
+                .map_err(|e| format!("Failed to build client verifier: {}", e))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+    };
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Certificate and key do not match: {}", e))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// 启动证书热加载后台任务 (仅一次)
+fn ensure_watcher() {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    if WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    handle.spawn(async {
+        let mut interval = tokio::time::interval(RELOAD_CHECK_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if !STATE.read().settings.enabled {
+                continue;
+            }
+            let _ = tokio::task::spawn_blocking(|| refresh(false)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gateway_sans_include_loopback_and_extra() {
+        let sans = gateway_sans(&["gw.example.lan".to_string(), " 10.9.8.7 ".to_string()]);
+        assert!(sans.contains(&"localhost".to_string()));
+        assert!(sans.contains(&"127.0.0.1".to_string()));
+        assert!(sans.contains(&"::1".to_string()));
+        assert!(sans.contains(&"gw.example.lan".to_string()));
+        assert!(sans.contains(&"10.9.8.7".to_string()));
+        let mut sorted = sans.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sans, sorted);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_keeps_host_and_path() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "192.168.1.10:8045".parse().unwrap());
+        let uri: Uri = "/v1/models?x=1".parse().unwrap();
+        let response = redirect_handler(headers, uri).await;
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers().get(header::LOCATION).unwrap(),
+            "https://192.168.1.10:8045/v1/models?x=1"
+        );
+
+        let response = redirect_handler(HeaderMap::new(), "/".parse().unwrap()).await;
+        assert_eq!(response.status(), StatusCode::UPGRADE_REQUIRED);
+    }
+
+    #[test]
+    fn test_required_client_auth_rejects_plain_loopback() {
+        let loopback: IpAddr = "127.0.0.1".parse().unwrap();
+        let mapped: IpAddr = "::ffff:127.0.0.1".parse().unwrap();
+        let lan: IpAddr = "192.168.1.20".parse().unwrap();
+
+        let mut settings = TlsConfig::default();
+        assert!(matches!(plain_mode(&settings, loopback), ConnectionMode::Plain));
+        assert!(matches!(plain_mode(&settings, lan), ConnectionMode::Redirect));
+
+        settings.client_auth = TlsClientAuthMode::Optional;
+        assert!(matches!(plain_mode(&settings, loopback), ConnectionMode::Plain));
+
+        settings.client_auth = TlsClientAuthMode::Required;
+        assert!(matches!(plain_mode(&settings, loopback), ConnectionMode::Redirect));
+        assert!(matches!(plain_mode(&settings, mapped), ConnectionMode::Redirect));
+        settings.redirect_http = false;
+        assert!(matches!(plain_mode(&settings, loopback), ConnectionMode::Reject));
+    }
+}
@@ -0,0 +1,355 @@
+// 用户令牌预算与请求配额
+// 对 UserToken 执行日/周/月 Token 预算、每分钟请求数 (RPM) 与最大并发限制。
+// 超限返回 429 + Retry-After，正常请求在响应头中返回剩余额度。
+
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue, StatusCode},
+    response::Response,
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use dashmap::DashMap;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+
+use crate::modules::user_token_db::{self, TokenLimits, TokenUsageWindow, UserToken};
+
+const RPM_WINDOW_MS: i64 = 60_000;
+
+/// token_id -> 最近 60 秒内的请求时间戳 (毫秒)
+static RPM_WINDOWS: Lazy<DashMap<String, VecDeque<i64>>> = Lazy::new(DashMap::new);
+/// token_id -> 当前进行中的请求数
+static ACTIVE_REQUESTS: Lazy<DashMap<String, usize>> = Lazy::new(DashMap::new);
+
+/// 超限拒绝信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetRejection {
+    pub code: &'static str,
+    pub message: String,
+    pub retry_after_secs: u64,
+}
+
+impl BudgetRejection {
+    pub fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "error": {
+                "message": self.message,
+                "type": "rate_limit_error",
+                "code": self.code
+            }
+        });
+        Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header("Content-Type", "application/json")
+            .header("Retry-After", self.retry_after_secs.max(1).to_string())
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+}
+
+/// 请求许可: 持有并发名额 (Drop 时释放) 并携带剩余额度响应头
+#[derive(Debug)]
+pub struct BudgetPermit {
+    token_id: Option<String>,
+    headers: Vec<(&'static str, String)>,
+}
+
+impl Drop for BudgetPermit {
+    fn drop(&mut self) {
+        if let Some(token_id) = self.token_id.take() {
+            release_slot(&token_id);
+        }
+    }
+}
+
+impl BudgetPermit {
+    /// 写入剩余额度响应头，并将许可绑定到响应体上，
+    /// 使并发名额在流式响应真正结束时才释放
+    pub fn attach(self, response: Response) -> Response {
+        let (mut parts, body) = response.into_parts();
+        for (name, value) in &self.headers {
+            if let Ok(v) = HeaderValue::from_str(value) {
+                parts.headers.insert(HeaderName::from_static(name), v);
+            }
+        }
+
+        if self.token_id.is_none() {
+            return Response::from_parts(parts, body);
+        }
+
+        let permit = self;
+        let stream = body.into_data_stream().map(move |chunk| {
+            let _hold = &permit;
+            chunk
+        });
+        Response::from_parts(parts, Body::from_stream(stream))
+    }
+}
+
+/// 各统计周期的起点与下一次重置时间 (Unix 秒, UTC 自然周期)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BudgetPeriods {
+    day_start: i64,
+    week_start: i64,
+    month_start: i64,
+    day_reset: i64,
+    week_reset: i64,
+    month_reset: i64,
+}
+
+fn budget_periods(now: DateTime<Utc>) -> BudgetPeriods {
+    let today = now.date_naive();
+    let day_start = midnight(today);
+    let week_start_date = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let month_start_date = today.with_day(1).unwrap_or(today);
+    let next_month_date = if month_start_date.month() == 12 {
+        NaiveDate::from_ymd_opt(month_start_date.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(month_start_date.year(), month_start_date.month() + 1, 1)
+    }
+    .unwrap_or(month_start_date + Duration::days(31));
+
+    BudgetPeriods {
+        day_start,
+        week_start: midnight(week_start_date),
+        month_start: midnight(month_start_date),
+        day_reset: day_start + 86_400,
+        week_reset: midnight(week_start_date) + 7 * 86_400,
+        month_reset: midnight(next_month_date),
+    }
+}
+
+fn midnight(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .map(|dt| Utc.from_utc_datetime(&dt).timestamp())
+        .unwrap_or(0)
+}
+
+/// 检查 Token 预算，返回剩余额度响应头或拒绝原因
+fn evaluate_token_budget(
+    limits: &TokenLimits,
+    usage: &TokenUsageWindow,
+    periods: &BudgetPeriods,
+    now_ts: i64,
+) -> Result<Vec<(&'static str, String)>, BudgetRejection> {
+    let checks = [
+        ("daily", limits.daily_token_limit, usage.daily, periods.day_reset,
+         "x-token-budget-limit-daily", "x-token-budget-remaining-daily"),
+        ("weekly", limits.weekly_token_limit, usage.weekly, periods.week_reset,
+         "x-token-budget-limit-weekly", "x-token-budget-remaining-weekly"),
+        ("monthly", limits.monthly_token_limit, usage.monthly, periods.month_reset,
+         "x-token-budget-limit-monthly", "x-token-budget-remaining-monthly"),
+    ];
+
+    let mut headers = Vec::new();
+    let mut exceeded: Vec<&str> = Vec::new();
+    let mut retry_at = 0i64;
+
+    for (name, limit, used, reset_at, limit_header, remaining_header) in checks {
+        if limit <= 0 {
+            continue;
+        }
+        if used >= limit {
+            exceeded.push(name);
+            // 需要等待所有超限周期都重置
+            retry_at = retry_at.max(reset_at);
+        }
+        headers.push((limit_header, limit.to_string()));
+        headers.push((remaining_header, (limit - used).max(0).to_string()));
+    }
+
+    if exceeded.is_empty() {
+        Ok(headers)
+    } else {
+        Err(BudgetRejection {
+            code: "token_budget_exceeded",
+            message: format!(
+                "Token budget exceeded ({}). Please wait for the budget to reset or contact the administrator.",
+                exceeded.join(", ")
+            ),
+            retry_after_secs: (retry_at - now_ts).max(1) as u64,
+        })
+    }
+}
+
+/// 滑动窗口 RPM 检查；通过时记录本次请求，返回剩余请求数
+fn check_rpm(window: &mut VecDeque<i64>, limit: i32, now_ms: i64) -> Result<i64, BudgetRejection> {
+    while window.front().is_some_and(|&ts| now_ms - ts >= RPM_WINDOW_MS) {
+        window.pop_front();
+    }
+
+    if window.len() as i64 >= limit as i64 {
+        let oldest = window.front().copied().unwrap_or(now_ms);
+        let wait_ms = (oldest + RPM_WINDOW_MS - now_ms).max(0);
+        return Err(BudgetRejection {
+            code: "token_rpm_exceeded",
+            message: format!("Request rate limit exceeded ({} requests per minute).", limit),
+            retry_after_secs: ((wait_ms + 999) / 1000).max(1) as u64,
+        });
+    }
+
+    window.push_back(now_ms);
+    Ok(limit as i64 - window.len() as i64)
+}
+
+fn try_acquire_slot(token_id: &str, max_concurrency: i32) -> bool {
+    let mut active = ACTIVE_REQUESTS.entry(token_id.to_string()).or_insert(0);
+    if *active >= max_concurrency as usize {
+        return false;
+    }
+    *active += 1;
+    true
+}
+
+fn release_slot(token_id: &str) {
+    let remove = match ACTIVE_REQUESTS.get_mut(token_id) {
+        Some(mut active) => {
+            *active = active.saturating_sub(1);
+            *active == 0
+        }
+        None => false,
+    };
+    if remove {
+        ACTIVE_REQUESTS.remove_if(token_id, |_, v| *v == 0);
+    }
+}
+
+/// 检查令牌的预算、RPM 与并发限制
+/// 通过时返回许可 (需通过 `BudgetPermit::attach` 绑定到响应)，超限返回拒绝信息
+pub fn check_and_acquire(token: &UserToken) -> Result<BudgetPermit, BudgetRejection> {
+    let limits = &token.limits;
+    if limits.is_unlimited() {
+        return Ok(BudgetPermit { token_id: None, headers: Vec::new() });
+    }
+
+    let now = Utc::now();
+    let mut headers = Vec::new();
+
+    // 1. Token 预算 (数据库查询失败时放行，避免统计故障阻断服务)
+    if limits.daily_token_limit > 0 || limits.weekly_token_limit > 0 || limits.monthly_token_limit > 0 {
+        let periods = budget_periods(now);
+        match user_token_db::get_token_usage_since(
+            &token.id,
+            periods.day_start,
+            periods.week_start,
+            periods.month_start,
+        ) {
+            Ok(usage) => headers.extend(evaluate_token_budget(limits, &usage, &periods, now.timestamp())?),
+            Err(e) => tracing::warn!("[TokenBudget] Failed to load usage for {}: {}", token.username, e),
+        }
+    }
+
+    // 2. 并发
+    let holds_slot = limits.max_concurrency > 0;
+    if holds_slot && !try_acquire_slot(&token.id, limits.max_concurrency) {
+        return Err(BudgetRejection {
+            code: "token_concurrency_exceeded",
+            message: format!(
+                "Too many concurrent requests (max {}). Please retry after in-flight requests complete.",
+                limits.max_concurrency
+            ),
+            retry_after_secs: 1,
+        });
+    }
+    let permit_token_id = holds_slot.then(|| token.id.clone());
+
+    // 3. RPM (并发检查通过后才计入窗口)
+    if limits.rpm_limit > 0 {
+        let mut window = RPM_WINDOWS.entry(token.id.clone()).or_default();
+        match check_rpm(&mut window, limits.rpm_limit, now.timestamp_millis()) {
+            Ok(remaining) => {
+                headers.push(("x-ratelimit-limit-requests", limits.rpm_limit.to_string()));
+                headers.push(("x-ratelimit-remaining-requests", remaining.to_string()));
+            }
+            Err(rejection) => {
+                drop(window);
+                if let Some(id) = permit_token_id {
+                    release_slot(&id);
+                }
+                return Err(rejection);
+            }
+        }
+    }
+
+    Ok(BudgetPermit { token_id: permit_token_id, headers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_periods_utc_calendar() {
+        // 2025-12-31 (周三) 15:30 UTC
+        let now = Utc.with_ymd_and_hms(2025, 12, 31, 15, 30, 0).unwrap();
+        let p = budget_periods(now);
+        assert_eq!(p.day_start, Utc.with_ymd_and_hms(2025, 12, 31, 0, 0, 0).unwrap().timestamp());
+        assert_eq!(p.week_start, Utc.with_ymd_and_hms(2025, 12, 29, 0, 0, 0).unwrap().timestamp());
+        assert_eq!(p.month_start, Utc.with_ymd_and_hms(2025, 12, 1, 0, 0, 0).unwrap().timestamp());
+        assert_eq!(p.week_reset, Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap().timestamp());
+        assert_eq!(p.month_reset, Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap().timestamp());
+    }
+
+    #[test]
+    fn test_evaluate_token_budget_reports_remaining_and_longest_reset() {
+        let now = Utc.with_ymd_and_hms(2025, 12, 31, 15, 30, 0).unwrap();
+        let periods = budget_periods(now);
+        let limits = TokenLimits {
+            daily_token_limit: 1_000,
+            weekly_token_limit: 5_000,
+            ..Default::default()
+        };
+
+        let headers = evaluate_token_budget(
+            &limits,
+            &TokenUsageWindow { daily: 400, weekly: 400, monthly: 400 },
+            &periods,
+            now.timestamp(),
+        )
+        .unwrap();
+        assert!(headers.contains(&("x-token-budget-remaining-daily", "600".to_string())));
+        assert!(headers.contains(&("x-token-budget-remaining-weekly", "4600".to_string())));
+        assert!(!headers.iter().any(|(k, _)| k.ends_with("monthly")));
+
+        // 日、周预算同时耗尽 -> 等到周预算重置
+        let rejection = evaluate_token_budget(
+            &limits,
+            &TokenUsageWindow { daily: 1_200, weekly: 5_000, monthly: 5_000 },
+            &periods,
+            now.timestamp(),
+        )
+        .unwrap_err();
+        assert_eq!(rejection.code, "token_budget_exceeded");
+        assert_eq!(
+            rejection.retry_after_secs as i64,
+            periods.week_reset - now.timestamp()
+        );
+    }
+
+    #[test]
+    fn test_check_rpm_sliding_window() {
+        let mut window = VecDeque::new();
+        assert_eq!(check_rpm(&mut window, 2, 0).unwrap(), 1);
+        assert_eq!(check_rpm(&mut window, 2, 10_000).unwrap(), 0);
+
+        let rejection = check_rpm(&mut window, 2, 20_000).unwrap_err();
+        assert_eq!(rejection.retry_after_secs, 40);
+
+        // 第一条请求滑出窗口后恢复
+        assert_eq!(check_rpm(&mut window, 2, 60_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_concurrency_slot_released_on_permit_drop() {
+        let token_id = format!("test-{}", uuid::Uuid::new_v4());
+        assert!(try_acquire_slot(&token_id, 1));
+        assert!(!try_acquire_slot(&token_id, 1));
+
+        let permit = BudgetPermit { token_id: Some(token_id.clone()), headers: Vec::new() };
+        drop(permit);
+        assert!(ACTIVE_REQUESTS.get(&token_id).is_none());
+        assert!(try_acquire_slot(&token_id, 1));
+        release_slot(&token_id);
+    }
+}
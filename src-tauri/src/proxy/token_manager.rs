@@ -6,11 +6,26 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 
+use crate::modules::account_db::{self, AccountChangeKind, ChangeOrigin};
+use crate::modules::account_health::{self, AccountHealthDetail, PoolHealth, RuntimeSignals};
+use crate::modules::runtime_state_db::{RuntimeStateSnapshot, SessionBindingRecord};
+use crate::proxy::providers::generic::{self as provider, ProviderEndpoint, ProviderStage};
 use crate::proxy::rate_limit::RateLimitTracker;
+use crate::proxy::signature_cache::SignatureCache;
 use crate::proxy::sticky_config::StickySessionConfig;
+use crate::proxy::token_refresh::{self, RefreshFailure, RetryState};
+
+/// 运行时状态 (会话绑定/限流/签名缓存) 的持久化间隔
+const RUNTIME_STATE_PERSIST_INTERVAL_SECS: u64 = 60;
+/// 重启后恢复会话绑定的最长时间窗口，超过则视为过期
+const SESSION_BINDING_TTL_SECS: i64 = 3600;
+/// 账号健康状态巡检间隔
+const HEALTH_MONITOR_INTERVAL_SECS: u64 = 30;
+/// Token 预刷新巡检的最短间隔
+const MIN_TOKEN_REFRESH_CHECK_SECS: u64 = 5;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum OnDiskAccountState {
+enum StoredAccountState {
     Enabled,
     Disabled,
     Unknown,
@@ -24,7 +39,6 @@ pub struct ProxyToken {
     pub expires_in: i64,
     pub timestamp: i64,
     pub email: String,
-    pub account_path: PathBuf, // 账号文件路径，用于更新
     pub project_id: Option<String>,
     pub subscription_tier: Option<String>, // "FREE" | "PRO" | "ULTRA"
     pub remaining_quota: Option<i32>,      // [FIX #563] Remaining quota for priority sorting
@@ -50,6 +64,13 @@ pub struct TokenManager {
     circuit_breaker_config: Arc<tokio::sync::RwLock<crate::models::CircuitBreakerConfig>>, // [NEW] 熔断配置缓存
     /// 支持优雅关闭时主动 abort 后台任务
     auto_cleanup_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    account_sync_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // [NEW] 账号存储变更订阅
+    health_monitor_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // [NEW] 健康状态巡检
+    token_refresh_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // [NEW] Token 预刷新
+    token_refresh_config: Arc<tokio::sync::RwLock<crate::models::TokenRefreshConfig>>, // [NEW] 预刷新配置
+    refresh_retries: Arc<DashMap<String, RetryState>>, // [NEW] account_id -> 预刷新重试状态
+    providers: Arc<DashMap<String, ProviderEndpoint>>, // [NEW] 通用上游 Provider 账号 (不进入 Google 号池)
+    provider_index: Arc<AtomicUsize>,                  // [NEW] Provider 轮询位置
     cancel_token: CancellationToken,
 }
 
@@ -70,17 +91,33 @@ impl TokenManager {
                 crate::models::CircuitBreakerConfig::default(),
             )),
             auto_cleanup_handle: Arc::new(tokio::sync::Mutex::new(None)),
+            account_sync_handle: Arc::new(tokio::sync::Mutex::new(None)),
+            health_monitor_handle: Arc::new(tokio::sync::Mutex::new(None)),
+            token_refresh_handle: Arc::new(tokio::sync::Mutex::new(None)),
+            token_refresh_config: Arc::new(tokio::sync::RwLock::new(
+                crate::models::TokenRefreshConfig::default(),
+            )),
+            refresh_retries: Arc::new(DashMap::new()),
+            providers: Arc::new(DashMap::new()),
+            provider_index: Arc::new(AtomicUsize::new(0)),
             cancel_token: CancellationToken::new(),
         }
     }
 
     /// 启动限流记录自动清理后台任务（每15秒检查并清除过期记录）
+    /// 同时定期将运行时状态快照持久化到 SQLite
     pub async fn start_auto_cleanup(&self) {
         let tracker = self.rate_limit_tracker.clone();
+        let sessions = self.session_accounts.clone();
         let cancel = self.cancel_token.child_token();
 
         let handle = tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+            let mut persist_interval = tokio::time::interval(std::time::Duration::from_secs(
+                RUNTIME_STATE_PERSIST_INTERVAL_SECS,
+            ));
+            // 跳过立即触发的首个 tick，避免在恢复前覆盖磁盘上的快照
+            persist_interval.tick().await;
             loop {
                 tokio::select! {
                     _ = cancel.cancelled() => {
@@ -96,6 +133,12 @@ impl TokenManager {
                             );
                         }
                     }
+                    _ = persist_interval.tick() => {
+                        let snapshot = Self::build_runtime_snapshot(&sessions, &tracker);
+                        if let Err(e) = Self::save_runtime_snapshot(snapshot).await {
+                            tracing::warn!("Failed to persist runtime state: {}", e);
+                        }
+                    }
                 }
             }
         });
@@ -111,39 +154,36 @@ impl TokenManager {
         tracing::info!("Rate limit auto-cleanup task started (interval: 15s)");
     }
 
-    /// 从主应用账号目录加载所有账号
+    /// 从账号存储加载所有账号
     pub async fn load_accounts(&self) -> Result<usize, String> {
-        let accounts_dir = self.data_dir.join("accounts");
-
-        if !accounts_dir.exists() {
-            return Err(format!("账号目录不存在: {:?}", accounts_dir));
+        // 旧版文件布局 (accounts.json + accounts/*.json) 首次加载时迁移到账号存储
+        if let Err(e) = crate::modules::account::migrate_legacy_layout_in(&self.data_dir) {
+            tracing::warn!("Legacy account layout migration failed: {}", e);
         }
 
-        // Reload should reflect current on-disk state (accounts can be added/removed/disabled).
+        let accounts = account_db::list_accounts(&self.data_dir)?;
+
+        // Reload should reflect current stored state (accounts can be added/removed/disabled).
         self.tokens.clear();
+        self.providers.clear();
         self.current_index.store(0, Ordering::SeqCst);
         {
             let mut last_used = self.last_used_account.lock().await;
             *last_used = None;
         }
 
-        let entries = std::fs::read_dir(&accounts_dir)
-            .map_err(|e| format!("读取账号目录失败: {}", e))?;
-
         let mut count = 0;
 
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
-            let path = entry.path();
-
-            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+        for account in accounts {
+            let account_id = account.id.clone();
+            if self.load_provider_account(&account) {
                 continue;
             }
+            let account = serde_json::to_value(&account).map_err(|e| e.to_string())?;
 
             // 尝试加载账号
-            match self.load_single_account(&path).await {
+            match self.load_single_account(account).await {
                 Ok(Some(token)) => {
-                    let account_id = token.account_id.clone();
                     self.tokens.insert(account_id, token);
                     count += 1;
                 }
@@ -151,7 +191,7 @@ impl TokenManager {
                     // 跳过无效账号
                 }
                 Err(e) => {
-                    tracing::debug!("加载账号失败 {:?}: {}", path, e);
+                    tracing::debug!("加载账号失败 {}: {}", account_id, e);
                 }
             }
         }
@@ -159,17 +199,446 @@ impl TokenManager {
         Ok(count)
     }
 
+    /// [NEW] 订阅账号存储的变更通知，增量同步内存中的账号池
+    ///
+    /// 取代原先基于全局队列的 reload/delete 信号；反代层自身的写入 (`ChangeOrigin::Proxy`) 会被忽略
+    pub async fn start_account_sync(self: &Arc<Self>) {
+        let mut rx = account_db::subscribe();
+        let cancel = self.cancel_token.child_token();
+        let manager = Arc::downgrade(self);
+
+        let handle = tokio::spawn(async move {
+            loop {
+                let change = tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    change = rx.recv() => change,
+                };
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                match change {
+                    Ok(change) => {
+                        if change.data_dir != manager.data_dir || change.origin == ChangeOrigin::Proxy {
+                            continue;
+                        }
+                        match change.kind {
+                            AccountChangeKind::Upserted => {
+                                if let Err(e) = manager.reload_account(&change.account_id).await {
+                                    tracing::warn!(
+                                        "[Proxy] Failed to sync account {}: {}",
+                                        change.account_id,
+                                        e
+                                    );
+                                }
+                            }
+                            AccountChangeKind::Deleted => manager.remove_account(&change.account_id),
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(
+                            "[Proxy] Account change feed lagged by {} event(s), reloading all accounts",
+                            skipped
+                        );
+                        if let Err(e) = manager.reload_all_accounts().await {
+                            tracing::warn!("[Proxy] Failed to reload accounts: {}", e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let mut guard = self.account_sync_handle.lock().await;
+        if let Some(old) = guard.replace(handle) {
+            old.abort();
+        }
+    }
+
+    /// [NEW] 定期巡检账号健康状态，状态转换写入 accounts.db
+    pub async fn start_health_monitor(self: &Arc<Self>) {
+        let cancel = self.cancel_token.child_token();
+        let manager = Arc::downgrade(self);
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                HEALTH_MONITOR_INTERVAL_SECS,
+            ));
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                if let Err(e) = manager.pool_health().await {
+                    tracing::warn!("[Health] Failed to refresh account health: {}", e);
+                }
+            }
+        });
+
+        let mut guard = self.health_monitor_handle.lock().await;
+        if let Some(old) = guard.replace(handle) {
+            old.abort();
+        }
+    }
+
+    /// [NEW] 后台 Token 预刷新：在过期前主动续期，临时失败按退避重试，invalid_grant 禁用账号
+    pub async fn start_token_refresher(self: &Arc<Self>) {
+        let cancel = self.cancel_token.child_token();
+        let manager = Arc::downgrade(self);
+
+        let handle = tokio::spawn(async move {
+            loop {
+                // 每轮重新读取间隔，配置热更新后下一轮生效
+                let interval = match manager.upgrade() {
+                    Some(manager) => manager.token_refresh_config.read().await.check_interval_secs,
+                    None => break,
+                };
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(
+                        interval.max(MIN_TOKEN_REFRESH_CHECK_SECS),
+                    )) => {}
+                }
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                let refreshed = manager.refresh_due_tokens().await;
+                if refreshed > 0 {
+                    tracing::info!("[TokenRefresh] Refreshed {} token(s) ahead of expiry", refreshed);
+                }
+            }
+        });
+
+        let mut guard = self.token_refresh_handle.lock().await;
+        if let Some(old) = guard.replace(handle) {
+            old.abort();
+        }
+    }
+
+    /// 刷新所有已到计划时间的 Token，返回成功数量
+    pub async fn refresh_due_tokens(&self) -> usize {
+        use futures::stream::{self, StreamExt};
+
+        let config = self.token_refresh_config.read().await.clone();
+        if !config.enabled {
+            return 0;
+        }
+        let now = chrono::Utc::now().timestamp();
+        let due: Vec<ProxyToken> = self
+            .tokens
+            .iter()
+            .filter(|entry| {
+                let token = entry.value();
+                let retry = self.refresh_retries.get(&token.account_id).map(|r| *r);
+                token_refresh::is_due(
+                    &token.account_id,
+                    token.timestamp,
+                    token.expires_in,
+                    retry.as_ref(),
+                    &config,
+                    now,
+                )
+            })
+            .map(|entry| entry.value().clone())
+            .collect();
+        if due.is_empty() {
+            return 0;
+        }
+
+        stream::iter(due)
+            .map(|token| self.refresh_token_ahead(token, &config))
+            .buffer_unordered(config.max_concurrency.max(1))
+            .filter(|refreshed| futures::future::ready(*refreshed))
+            .count()
+            .await
+    }
+
+    /// 预刷新单个账号的 Token，结果写入健康历史
+    async fn refresh_token_ahead(
+        &self,
+        token: ProxyToken,
+        config: &crate::models::TokenRefreshConfig,
+    ) -> bool {
+        let account_id = token.account_id.as_str();
+        let result =
+            crate::modules::oauth::refresh_access_token(&token.refresh_token, Some(account_id)).await;
+        let now = chrono::Utc::now().timestamp();
+
+        let outcome = match result {
+            Ok(token_response) => {
+                let expiry = now + token_response.expires_in;
+                // 刷新期间账号可能已被移除或更换了 refresh_token，此时丢弃结果
+                match self.tokens.get_mut(account_id) {
+                    Some(mut entry) if entry.refresh_token == token.refresh_token => {
+                        entry.access_token = token_response.access_token.clone();
+                        entry.expires_in = token_response.expires_in;
+                        entry.timestamp = expiry;
+                    }
+                    _ => return false,
+                }
+                self.refresh_retries.remove(account_id);
+                if let Err(e) = self.save_refreshed_token(account_id, &token_response).await {
+                    tracing::warn!("[TokenRefresh] Failed to save token for {}: {}", token.email, e);
+                }
+                self.record_refresh_event(
+                    account_id,
+                    format!("Token refreshed ahead of expiry, valid until {}", expiry),
+                )
+                .await;
+                return true;
+            }
+            Err(e) => e,
+        };
+
+        match token_refresh::classify_refresh_error(&outcome) {
+            RefreshFailure::InvalidGrant => {
+                tracing::error!(
+                    "[TokenRefresh] Disabling account {} due to invalid_grant: refresh_token revoked/expired",
+                    token.email
+                );
+                self.refresh_retries.remove(account_id);
+                if let Err(e) = self
+                    .disable_account(account_id, &format!("invalid_grant: {}", outcome))
+                    .await
+                {
+                    tracing::warn!("[TokenRefresh] Failed to disable {}: {}", token.email, e);
+                }
+                self.record_refresh_event(
+                    account_id,
+                    format!(
+                        "Token refresh failed with invalid_grant, account disabled: {}",
+                        truncate_reason(&outcome, 400)
+                    ),
+                )
+                .await;
+            }
+            RefreshFailure::Transient => {
+                let previous = self.refresh_retries.get(account_id).map(|r| *r);
+                let retry = RetryState::after_failure(previous, token.timestamp, config, now);
+                self.refresh_retries.insert(account_id.to_string(), retry);
+                let next_step = if retry.exhausted(config) {
+                    "giving up until the next request".to_string()
+                } else {
+                    format!("retrying at {}", retry.next_attempt_at)
+                };
+                tracing::warn!(
+                    "[TokenRefresh] Refresh failed for {} (attempt {}/{}), {}: {}",
+                    token.email,
+                    retry.attempts,
+                    config.max_attempts,
+                    next_step,
+                    outcome
+                );
+                self.record_refresh_event(
+                    account_id,
+                    format!(
+                        "Token refresh failed (attempt {}/{}), {}: {}",
+                        retry.attempts,
+                        config.max_attempts,
+                        next_step,
+                        truncate_reason(&outcome, 400)
+                    ),
+                )
+                .await;
+            }
+        }
+        false
+    }
+
+    /// 将 Token 刷新结果写入账号健康历史
+    async fn record_refresh_event(&self, account_id: &str, outcome: String) {
+        let enabled = self.circuit_breaker_config.read().await.enabled;
+        let signals = Self::collect_health_signals(
+            &self.rate_limit_tracker,
+            &self.health_scores,
+            account_id,
+            enabled,
+        );
+        let data_dir = self.data_dir.clone();
+        let id = account_id.to_string();
+        let result = tokio::task::spawn_blocking(move || {
+            account_health::record_refresh_event_in(
+                &data_dir,
+                &id,
+                signals,
+                &outcome,
+                chrono::Utc::now().timestamp(),
+            )
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+        .and_then(|r| r);
+        if let Err(e) = result {
+            tracing::warn!("[TokenRefresh] Failed to record refresh event for {}: {}", account_id, e);
+        }
+    }
+
+    /// 收集账号的运行时健康信号 (熔断关闭时忽略限流记录)
+    fn collect_health_signals(
+        tracker: &RateLimitTracker,
+        health_scores: &DashMap<String, f32>,
+        account_id: &str,
+        circuit_breaker_enabled: bool,
+    ) -> RuntimeSignals {
+        let mut signals = RuntimeSignals {
+            health_score: health_scores.get(account_id).map(|s| *s),
+            ..Default::default()
+        };
+        if circuit_breaker_enabled {
+            if let Some((until, reason)) = tracker.active_account_limit(account_id) {
+                signals.rate_limited_until = Some(until);
+                signals.rate_limit_reason = Some(reason.as_str().to_string());
+            }
+            signals.limited_models = tracker.limited_models(account_id);
+            signals.consecutive_failures = tracker.failure_count(account_id);
+        }
+        signals
+    }
+
+    /// 账号当前健康状态与转换历史
+    pub async fn account_health(
+        &self,
+        account_id: &str,
+        history_limit: usize,
+    ) -> Result<AccountHealthDetail, String> {
+        let enabled = self.circuit_breaker_config.read().await.enabled;
+        let signals = Self::collect_health_signals(
+            &self.rate_limit_tracker,
+            &self.health_scores,
+            account_id,
+            enabled,
+        );
+        let data_dir = self.data_dir.clone();
+        let account_id = account_id.to_string();
+        tokio::task::spawn_blocking(move || {
+            account_health::account_health_in(
+                &data_dir,
+                &account_id,
+                signals,
+                history_limit,
+                chrono::Utc::now().timestamp(),
+            )
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    /// 账号池健康汇总 (同时记录各账号的状态转换)
+    pub async fn pool_health(&self) -> Result<PoolHealth, String> {
+        let enabled = self.circuit_breaker_config.read().await.enabled;
+        let tracker = self.rate_limit_tracker.clone();
+        let health_scores = self.health_scores.clone();
+        let data_dir = self.data_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            account_health::pool_health_in(
+                &data_dir,
+                |id| Self::collect_health_signals(&tracker, &health_scores, id, enabled),
+                chrono::Utc::now().timestamp(),
+            )
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    /// 生成当前运行时状态快照 (会话绑定、限流记录、失败计数、签名缓存)
+    fn build_runtime_snapshot(
+        sessions: &DashMap<String, String>,
+        tracker: &RateLimitTracker,
+    ) -> RuntimeStateSnapshot {
+        let now = chrono::Utc::now().timestamp();
+        let (rate_limits, failure_counts) = tracker.export_state();
+        RuntimeStateSnapshot {
+            sessions: sessions
+                .iter()
+                .map(|e| SessionBindingRecord {
+                    session_id: e.key().clone(),
+                    account_id: e.value().clone(),
+                    updated_at: now,
+                })
+                .collect(),
+            rate_limits,
+            failure_counts,
+            signatures: SignatureCache::global().export_entries(),
+        }
+    }
+
+    async fn save_runtime_snapshot(snapshot: RuntimeStateSnapshot) -> Result<(), String> {
+        tokio::task::spawn_blocking(move || {
+            crate::modules::runtime_state_db::save_snapshot(&snapshot)
+        })
+        .await
+        .map_err(|e| format!("持久化任务失败: {}", e))?
+    }
+
+    /// 立即持久化运行时状态 (用于关闭前)
+    pub async fn persist_runtime_state(&self) -> Result<(), String> {
+        let snapshot = Self::build_runtime_snapshot(&self.session_accounts, &self.rate_limit_tracker);
+        Self::save_runtime_snapshot(snapshot).await
+    }
+
+    /// 从 SQLite 恢复上次运行的调度状态 (应在首次 load_accounts 之后调用)
+    ///
+    /// - 仅恢复指向当前已加载账号、且未超过 TTL 的会话绑定
+    /// - 已过期的限流记录与签名缓存会被丢弃
+    /// - 内存中已有的状态优先，不会被覆盖
+    pub async fn restore_runtime_state(&self) -> Result<(), String> {
+        let snapshot = tokio::task::spawn_blocking(crate::modules::runtime_state_db::load_snapshot)
+            .await
+            .map_err(|e| format!("恢复任务失败: {}", e))??;
+
+        let (sessions_restored, limits_restored, failures_restored, signatures_restored) =
+            self.apply_runtime_snapshot(snapshot, chrono::Utc::now().timestamp());
+
+        tracing::info!(
+            "Runtime state restored: {} session binding(s), {} rate limit(s), {} failure counter(s), {} signature(s)",
+            sessions_restored,
+            limits_restored,
+            failures_restored,
+            signatures_restored
+        );
+        Ok(())
+    }
+
+    /// 将快照应用到内存状态，返回恢复的 (会话绑定, 限流记录, 失败计数, 签名) 数量
+    fn apply_runtime_snapshot(
+        &self,
+        snapshot: RuntimeStateSnapshot,
+        now: i64,
+    ) -> (usize, usize, usize, usize) {
+        let mut sessions_restored = 0;
+        for record in snapshot.sessions {
+            if now - record.updated_at > SESSION_BINDING_TTL_SECS
+                || !self.tokens.contains_key(&record.account_id)
+                || self.session_accounts.contains_key(&record.session_id)
+            {
+                continue;
+            }
+            self.session_accounts.insert(record.session_id, record.account_id);
+            sessions_restored += 1;
+        }
+
+        let (limits_restored, failures_restored) = self
+            .rate_limit_tracker
+            .import_state(snapshot.rate_limits, snapshot.failure_counts);
+        let signatures_restored = SignatureCache::global().import_entries(snapshot.signatures);
+        (sessions_restored, limits_restored, failures_restored, signatures_restored)
+    }
+
     /// 重新加载指定账号（用于配额更新后的实时同步）
     pub async fn reload_account(&self, account_id: &str) -> Result<(), String> {
-        let path = self
-            .data_dir
-            .join("accounts")
-            .join(format!("{}.json", account_id));
-        if !path.exists() {
-            return Err(format!("账号文件不存在: {:?}", path));
+        let Some(account) = account_db::get_account(&self.data_dir, account_id)? else {
+            // 账号已从存储中删除
+            self.remove_account(account_id);
+            return Err(format!("账号不存在: {}", account_id));
+        };
+        if self.load_provider_account(&account) {
+            return Ok(());
         }
+        let account = serde_json::to_value(&account).map_err(|e| e.to_string())?;
 
-        match self.load_single_account(&path).await {
+        match self.load_single_account(account).await {
             Ok(Some(token)) => {
                 self.tokens.insert(account_id.to_string(), token);
                 // [NEW] 重新加载账号时自动清除该账号的限流记录
@@ -198,12 +667,13 @@ impl TokenManager {
     /// 从内存中彻底移除指定账号及其关联数据 (Issue #1477)
     pub fn remove_account(&self, account_id: &str) {
         // 1. 从 DashMap 中移除令牌
-        if self.tokens.remove(account_id).is_some() {
+        if self.tokens.remove(account_id).is_some() || self.providers.remove(account_id).is_some() {
             tracing::info!("[Proxy] Removed account {} from memory cache", account_id);
         }
 
-        // 2. 清理相关的健康分数
+        // 2. 清理相关的健康分数与预刷新重试状态
         self.health_scores.remove(account_id);
+        self.refresh_retries.remove(account_id);
 
         // 3. 清理该账号的所有限流记录
         self.clear_rate_limit(account_id);
@@ -220,85 +690,69 @@ impl TokenManager {
         }
     }
 
-    /// Check if an account has been disabled on disk.
+    /// Check if an account has been disabled in the account store.
     ///
     /// Safety net: avoids selecting a disabled account when the in-memory pool hasn't been
     /// reloaded yet (e.g. fixed account mode / sticky session).
     ///
-    /// Note: this is intentionally tolerant to transient read/parse failures (e.g. concurrent
-    /// writes). Failures are reported as `Unknown` so callers can skip without purging the in-memory
+    /// Note: this is intentionally tolerant to transient read failures (e.g. a locked database).
+    /// Failures are reported as `Unknown` so callers can skip without purging the in-memory
     /// token pool.
-    async fn get_account_state_on_disk(account_path: &std::path::PathBuf) -> OnDiskAccountState {
-        const MAX_RETRIES: usize = 2;
-        const RETRY_DELAY_MS: u64 = 5;
-
-        for attempt in 0..=MAX_RETRIES {
-            let content = match tokio::fs::read_to_string(account_path).await {
-                Ok(c) => c,
-                Err(e) => {
-                    // If the file is gone, the in-memory token is definitely stale.
-                    if e.kind() == std::io::ErrorKind::NotFound {
-                        return OnDiskAccountState::Disabled;
-                    }
-                    if attempt < MAX_RETRIES {
-                        tokio::time::sleep(std::time::Duration::from_millis(RETRY_DELAY_MS)).await;
-                        continue;
-                    }
-                    tracing::debug!(
-                        "Failed to read account file on disk {:?}: {}",
-                        account_path,
-                        e
-                    );
-                    return OnDiskAccountState::Unknown;
-                }
-            };
-
-            let account = match serde_json::from_str::<serde_json::Value>(&content) {
-                Ok(v) => v,
-                Err(e) => {
-                    if attempt < MAX_RETRIES {
-                        tokio::time::sleep(std::time::Duration::from_millis(RETRY_DELAY_MS)).await;
-                        continue;
-                    }
-                    tracing::debug!(
-                        "Failed to parse account JSON on disk {:?}: {}",
-                        account_path,
-                        e
-                    );
-                    return OnDiskAccountState::Unknown;
+    fn get_account_state_in_store(&self, account_id: &str) -> StoredAccountState {
+        match account_db::get_account(&self.data_dir, account_id) {
+            // If the account is gone, the in-memory token is definitely stale.
+            Ok(None) => StoredAccountState::Disabled,
+            Ok(Some(account)) => {
+                let disabled = account.disabled
+                    || account.proxy_disabled
+                    || account.quota.as_ref().is_some_and(|q| q.is_forbidden);
+                if disabled {
+                    StoredAccountState::Disabled
+                } else {
+                    StoredAccountState::Enabled
                 }
-            };
+            }
+            Err(e) => {
+                tracing::debug!("Failed to read account {} from store: {}", account_id, e);
+                StoredAccountState::Unknown
+            }
+        }
+    }
 
-            let disabled = account
-                .get("disabled")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false)
-                || account
-                    .get("proxy_disabled")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false)
-                || account
-                    .get("quota")
-                    .and_then(|q| q.get("is_forbidden"))
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false);
-
-            return if disabled {
-                OnDiskAccountState::Disabled
-            } else {
-                OnDiskAccountState::Enabled
-            };
+    /// [NEW] 同步 Provider 账号 (无 OAuth 凭据，单独维护)；非 Provider 账号返回 false
+    fn load_provider_account(&self, account: &crate::models::Account) -> bool {
+        if !account.is_provider() {
+            return false;
+        }
+        self.tokens.remove(&account.id);
+        match ProviderEndpoint::from_account(account) {
+            Some(endpoint) if !account.disabled && !account.proxy_disabled => {
+                self.providers.insert(account.id.clone(), endpoint);
+            }
+            _ => {
+                self.providers.remove(&account.id);
+            }
         }
+        true
+    }
 
-        OnDiskAccountState::Unknown
+    /// [NEW] 按字段修改存储中的账号 (反代层写入，不会回流触发自身的同步)
+    fn update_stored_account(
+        &self,
+        account_id: &str,
+        f: impl FnOnce(&mut serde_json::Value),
+    ) -> Result<(), String> {
+        account_db::update_account_json(&self.data_dir, account_id, ChangeOrigin::Proxy, f)
     }
 
     /// 加载单个账号
-    async fn load_single_account(&self, path: &PathBuf) -> Result<Option<ProxyToken>, String> {
-        let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
-
-        let mut account: serde_json::Value =
-            serde_json::from_str(&content).map_err(|e| format!("解析 JSON 失败: {}", e))?;
+    async fn load_single_account(
+        &self,
+        mut account: serde_json::Value,
+    ) -> Result<Option<ProxyToken>, String> {
+        let account_id = account["id"].as_str()
+            .ok_or("缺少 id 字段")?
+            .to_string();
 
         // [修复 #1344] 先检查账号是否被手动禁用(非配额保护原因)
         let is_proxy_disabled = account
@@ -314,8 +768,8 @@ impl TokenManager {
         if is_proxy_disabled && disabled_reason != "quota_protection" {
             // Account manually disabled
             tracing::debug!(
-                "Account skipped due to manual disable: {:?} (email={}, reason={})",
-                path,
+                "Account skipped due to manual disable: {} (email={}, reason={})",
+                account_id,
                 account
                     .get("email")
                     .and_then(|v| v.as_str())
@@ -341,8 +795,8 @@ impl TokenManager {
             if now < block_until {
                 // Still blocked
                 tracing::debug!(
-                    "Skipping validation-blocked account: {:?} (email={}, blocked until {})",
-                    path,
+                    "Skipping validation-blocked account: {} (email={}, blocked until {})",
+                    account_id,
                     account
                         .get("email")
                         .and_then(|v| v.as_str())
@@ -358,9 +812,11 @@ impl TokenManager {
                 account["validation_blocked_until"] = serde_json::json!(0);
                 account["validation_blocked_reason"] = serde_json::Value::Null;
 
-                let updated_json =
-                    serde_json::to_string_pretty(&account).map_err(|e| e.to_string())?;
-                std::fs::write(path, updated_json).map_err(|e| e.to_string())?;
+                self.update_stored_account(&account_id, |stored| {
+                    stored["validation_blocked"] = serde_json::json!(false);
+                    stored["validation_blocked_until"] = serde_json::json!(0);
+                    stored["validation_blocked_reason"] = serde_json::Value::Null;
+                })?;
                 tracing::info!(
                     "Validation block expired and cleared for account: {}",
                     account
@@ -378,8 +834,8 @@ impl TokenManager {
             .unwrap_or(false)
         {
             tracing::debug!(
-                "Skipping disabled account file: {:?} (email={})",
-                path,
+                "Skipping disabled account: {} (email={})",
+                account_id,
                 account
                     .get("email")
                     .and_then(|v| v.as_str())
@@ -388,18 +844,18 @@ impl TokenManager {
             return Ok(None);
         }
 
-        // Safety check: verify state on disk again to handle concurrent mid-parse writes
-        if Self::get_account_state_on_disk(path).await == OnDiskAccountState::Disabled {
-            tracing::debug!("Account file {:?} is disabled on disk, skipping.", path);
+        // Safety check: verify stored state again to handle concurrent writes since the read
+        if self.get_account_state_in_store(&account_id) == StoredAccountState::Disabled {
+            tracing::debug!("Account {} is disabled in store, skipping.", account_id);
             return Ok(None);
         }
 
         // 配额保护检查 - 只处理配额保护逻辑
         // 这样可以在加载时自动恢复配额已恢复的账号
-        if self.check_and_protect_quota(&mut account, path).await {
+        if self.check_and_protect_quota(&mut account).await {
             tracing::debug!(
-                "Account skipped due to quota protection: {:?} (email={})",
-                path,
+                "Account skipped due to quota protection: {} (email={})",
+                account_id,
                 account
                     .get("email")
                     .and_then(|v| v.as_str())
@@ -415,8 +871,8 @@ impl TokenManager {
             .unwrap_or(false)
         {
             tracing::debug!(
-                "Skipping proxy-disabled account file: {:?} (email={})",
-                path,
+                "Skipping proxy-disabled account: {} (email={})",
+                account_id,
                 account
                     .get("email")
                     .and_then(|v| v.as_str())
@@ -425,10 +881,6 @@ impl TokenManager {
             return Ok(None);
         }
 
-        let account_id = account["id"].as_str()
-            .ok_or("缺少 id 字段")?
-            .to_string();
-
         let email = account["email"].as_str()
             .ok_or("缺少 email 字段")?
             .to_string();
@@ -507,7 +959,6 @@ impl TokenManager {
             expires_in,
             timestamp,
             email,
-            account_path: path.clone(),
             project_id,
             subscription_tier,
             remaining_quota,
@@ -523,11 +974,7 @@ impl TokenManager {
 
     /// 检查账号是否应该被配额保护
     /// 如果配额低于阈值，自动禁用账号并返回 true
-    async fn check_and_protect_quota(
-        &self,
-        account_json: &mut serde_json::Value,
-        account_path: &PathBuf,
-    ) -> bool {
+    async fn check_and_protect_quota(&self, account_json: &mut serde_json::Value) -> bool {
         // 1. 加载配额保护配置
         let config = match crate::modules::config::load_app_config() {
             Ok(cfg) => cfg.quota_protection,
@@ -558,7 +1005,7 @@ impl TokenManager {
         if is_proxy_disabled && reason == "quota_protection" {
             // 如果是被旧版账号级保护禁用的,尝试恢复并转为模型级
             return self
-                .check_and_restore_quota(account_json, account_path, &quota, &config)
+                .check_and_restore_quota(account_json, &quota, &config)
                 .await;
         }
 
@@ -587,7 +1034,8 @@ impl TokenManager {
         }
 
         // 6. 遍历受监控的 Standard ID，根据组内“最差状态”执行锁定或恢复
-        let threshold = config.threshold_percentage as i32;
+        // [NEW] 阈值可能已被配额预测临时收紧
+        let threshold = crate::modules::quota_forecast::effective_threshold_percentage(&config) as i32;
         let account_id = account_json
             .get("id")
             .and_then(|v| v.as_str())
@@ -605,7 +1053,6 @@ impl TokenManager {
                     .trigger_quota_protection(
                         account_json,
                         &account_id,
-                        account_path,
                         min_pct,
                         threshold,
                         std_id,
@@ -627,12 +1074,7 @@ impl TokenManager {
 
                 if is_protected {
                     if self
-                        .restore_quota_protection(
-                            account_json,
-                            &account_id,
-                            account_path,
-                            std_id,
-                        )
+                        .restore_quota_protection(account_json, &account_id, std_id)
                         .await
                         .unwrap_or(false)
                     {
@@ -677,37 +1119,36 @@ impl TokenManager {
         }
     }
 
-    /// 从磁盘读取特定模型的 quota 百分比 [FIX] 排序使用目标模型的 quota 而非 max
+    /// 从账号存储读取特定模型的 quota 百分比 [FIX] 排序使用目标模型的 quota 而非 max
     ///
     /// # 参数
-    /// * `account_path` - 账号 JSON 文件路径
+    /// * `data_dir` - 账号存储所在的数据目录
+    /// * `account_id` - 账号 ID
     /// * `model_name` - 目标模型名称（已标准化）
     #[allow(dead_code)] // 预留给精确配额读取逻辑
-    fn get_model_quota_from_json(account_path: &PathBuf, model_name: &str) -> Option<i32> {
-        let content = std::fs::read_to_string(account_path).ok()?;
-        let account: serde_json::Value = serde_json::from_str(&content).ok()?;
-        let models = account.get("quota")?.get("models")?.as_array()?;
-
-        for model in models {
-            if let Some(name) = model.get("name").and_then(|v| v.as_str()) {
-                if crate::proxy::common::model_mapping::normalize_to_standard_id(name)
-                    .unwrap_or_else(|| name.to_string())
-                    == model_name
-                {
-                    return model
-                        .get("percentage")
-                        .and_then(|v| v.as_i64())
-                        .map(|p| p as i32);
-                }
-            }
-        }
-        None
+    fn get_model_quota_from_store(
+        data_dir: &std::path::Path,
+        account_id: &str,
+        model_name: &str,
+    ) -> Option<i32> {
+        let account = account_db::get_account(data_dir, account_id).ok()??;
+
+        account.quota?.models.into_iter().find_map(|model| {
+            (crate::proxy::common::model_mapping::normalize_to_standard_id(&model.name)
+                .unwrap_or_else(|| model.name.clone())
+                == model_name)
+                .then_some(model.percentage)
+        })
     }
 
-    /// 测试辅助函数：公开访问 get_model_quota_from_json
+    /// 测试辅助函数：公开访问 get_model_quota_from_store
     #[cfg(test)]
-    pub fn get_model_quota_from_json_for_test(account_path: &PathBuf, model_name: &str) -> Option<i32> {
-        Self::get_model_quota_from_json(account_path, model_name)
+    pub fn get_model_quota_from_store_for_test(
+        data_dir: &std::path::Path,
+        account_id: &str,
+        model_name: &str,
+    ) -> Option<i32> {
+        Self::get_model_quota_from_store(data_dir, account_id, model_name)
     }
 
     /// 触发配额保护，限制特定模型 (Issue #621)
@@ -716,7 +1157,6 @@ impl TokenManager {
         &self,
         account_json: &mut serde_json::Value,
         account_id: &str,
-        account_path: &PathBuf,
         current_val: i32,
         threshold: i32,
         model_name: &str,
@@ -743,12 +1183,17 @@ impl TokenManager {
                 threshold
             );
 
-            // 3. 写入磁盘
-            std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap())
-                .map_err(|e| format!("写入文件失败: {}", e))?;
-
-            // [FIX] 触发 TokenManager 的账号重新加载信号，确保内存中的 protected_models 同步
-            crate::proxy::server::trigger_account_reload(account_id);
+            // 3. 写入账号存储 (内存中的 protected_models 由调用方基于 account_json 构建)
+            self.update_stored_account(account_id, |stored| {
+                if !stored["protected_models"].is_array() {
+                    stored["protected_models"] = serde_json::Value::Array(Vec::new());
+                }
+                let models = stored["protected_models"].as_array_mut().unwrap();
+                if !models.iter().any(|m| m.as_str() == Some(model_name)) {
+                    models.push(serde_json::Value::String(model_name.to_string()));
+                }
+            })
+            .map_err(|e| format!("写入账号失败: {}", e))?;
 
             return Ok(true);
         }
@@ -760,7 +1205,6 @@ impl TokenManager {
     async fn check_and_restore_quota(
         &self,
         account_json: &mut serde_json::Value,
-        account_path: &PathBuf,
         quota: &serde_json::Value,
         config: &crate::models::QuotaProtectionConfig,
     ) -> bool {
@@ -793,9 +1237,16 @@ impl TokenManager {
             }
         }
 
-        account_json["protected_models"] = serde_json::Value::Array(protected_list);
+        account_json["protected_models"] = serde_json::Value::Array(protected_list.clone());
 
-        let _ = std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap());
+        if let Some(account_id) = account_json.get("id").and_then(|v| v.as_str()) {
+            let _ = self.update_stored_account(account_id, |stored| {
+                stored["proxy_disabled"] = serde_json::Value::Bool(false);
+                stored["proxy_disabled_reason"] = serde_json::Value::Null;
+                stored["proxy_disabled_at"] = serde_json::Value::Null;
+                stored["protected_models"] = serde_json::Value::Array(protected_list);
+            });
+        }
 
         false // 返回 false 表示现在已可以尝试加载该账号（模型级过滤会在 get_token 时发生）
     }
@@ -806,7 +1257,6 @@ impl TokenManager {
         &self,
         account_json: &mut serde_json::Value,
         account_id: &str,
-        account_path: &PathBuf,
         model_name: &str,
     ) -> Result<bool, String> {
         if let Some(arr) = account_json
@@ -822,11 +1272,15 @@ impl TokenManager {
                     account_id,
                     model_name
                 );
-                std::fs::write(
-                    account_path,
-                    serde_json::to_string_pretty(account_json).unwrap(),
-                )
-                .map_err(|e| format!("写入文件失败: {}", e))?;
+                self.update_stored_account(account_id, |stored| {
+                    if let Some(models) = stored
+                        .get_mut("protected_models")
+                        .and_then(|v| v.as_array_mut())
+                    {
+                        models.retain(|m| m.as_str() != Some(model_name));
+                    }
+                })
+                .map_err(|e| format!("写入账号失败: {}", e))?;
                 return Ok(true);
             }
         }
@@ -904,6 +1358,13 @@ impl TokenManager {
     pub async fn graceful_shutdown(&self, timeout: std::time::Duration) {
         tracing::info!("Initiating graceful shutdown of background tasks...");
 
+        // 关闭前保存运行时状态，便于重启后恢复会话亲和性与限流信息
+        match tokio::time::timeout(timeout, self.persist_runtime_state()).await {
+            Ok(Ok(())) => tracing::info!("Runtime state persisted before shutdown"),
+            Ok(Err(e)) => tracing::warn!("Failed to persist runtime state: {}", e),
+            Err(_) => tracing::warn!("Persisting runtime state timed out after {:?}", timeout),
+        }
+
         // 发送取消信号给所有后台任务
         self.cancel_token.cancel();
 
@@ -918,6 +1379,9 @@ impl TokenManager {
     /// abort() 仅设置取消标志，必须 await 确认清理完成
     pub async fn abort_background_tasks(&self) {
         Self::abort_task(&self.auto_cleanup_handle, "Auto-cleanup task").await;
+        Self::abort_task(&self.account_sync_handle, "Account sync task").await;
+        Self::abort_task(&self.health_monitor_handle, "Health monitor task").await;
+        Self::abort_task(&self.token_refresh_handle, "Token refresh task").await;
     }
 
     /// 中止单个后台任务并记录结果
@@ -953,29 +1417,6 @@ impl TokenManager {
         session_id: Option<&str>,
         target_model: &str,
     ) -> Result<(String, String, String, String, u64), String> {
-        // [FIX] 检查并处理待重新加载的账号（配额保护同步）
-        let pending_reload = crate::proxy::server::take_pending_reload_accounts();
-        for account_id in pending_reload {
-            if let Err(e) = self.reload_account(&account_id).await {
-                tracing::warn!("[Quota] Failed to reload account {}: {}", account_id, e);
-            } else {
-                tracing::info!(
-                    "[Quota] Reloaded account {} (protected_models synced)",
-                    account_id
-                );
-            }
-        }
-
-        // [FIX #1477] 检查并处理待删除的账号（彻底清理缓存）
-        let pending_delete = crate::proxy::server::take_pending_delete_accounts();
-        for account_id in pending_delete {
-            self.remove_account(&account_id);
-            tracing::info!(
-                "[Proxy] Purged deleted account {} from all caches",
-                account_id
-            );
-        }
-
         // 【优化 Issue #284】添加 5 秒超时，防止死锁
         let timeout_duration = std::time::Duration::from_secs(5);
         match tokio::time::timeout(
@@ -1006,6 +1447,16 @@ impl TokenManager {
             return Err("Token pool is empty".to_string());
         }
 
+        // [NEW] 用户令牌限定的账号池
+        let token_scope = crate::proxy::token_policy::current_account_scope();
+        if let Some(scope) = token_scope.as_ref().filter(|s| !s.account_pool.is_empty()) {
+            tokens_snapshot.retain(|t| scope.allows(&t.account_id, &t.email));
+            total = tokens_snapshot.len();
+            if total == 0 {
+                return Err("No accounts available in the account pool pinned to this API token".to_string());
+            }
+        }
+
         // [NEW] 1. 动态能力过滤 (Capability Filter)
         
         // 定义常量
@@ -1109,7 +1560,11 @@ impl TokenManager {
             .unwrap_or(false);
 
         // ===== [FIX #820] 固定账号模式：优先使用指定账号 =====
-        let preferred_id = self.preferred_account_id.read().await.clone();
+        // [NEW] 用户令牌的优先账号覆盖全局固定账号
+        let preferred_id = match token_scope.and_then(|s| s.preferred_account_id) {
+            Some(id) => Some(id),
+            None => self.preferred_account_id.read().await.clone(),
+        };
         if let Some(ref pref_id) = preferred_id {
             // 查找优先账号
             if let Some(preferred_token) = tokens_snapshot
@@ -1118,10 +1573,10 @@ impl TokenManager {
                 .cloned()
             {
                 // 检查账号是否可用（未限流、未被配额保护）
-                match Self::get_account_state_on_disk(&preferred_token.account_path).await {
-                    OnDiskAccountState::Disabled => {
+                match self.get_account_state_in_store(&preferred_token.account_id) {
+                    StoredAccountState::Disabled => {
                         tracing::warn!(
-                            "🔒 [FIX #820] Preferred account {} is disabled on disk, purging and falling back",
+                            "🔒 [FIX #820] Preferred account {} is disabled in store, purging and falling back",
                             preferred_token.email
                         );
                         self.remove_account(&preferred_token.account_id);
@@ -1139,9 +1594,9 @@ impl TokenManager {
                             return Err("Token pool is empty".to_string());
                         }
                     }
-                    OnDiskAccountState::Unknown => {
+                    StoredAccountState::Unknown => {
                         tracing::warn!(
-                            "🔒 [FIX #820] Preferred account {} state on disk is unavailable, falling back",
+                            "🔒 [FIX #820] Preferred account {} state in store is unavailable, falling back",
                             preferred_token.email
                         );
                         // Don't purge on transient read/parse failures; just skip this token for this request.
@@ -1151,7 +1606,7 @@ impl TokenManager {
                             return Err("Token pool is empty".to_string());
                         }
                     }
-                    OnDiskAccountState::Enabled => {
+                    StoredAccountState::Enabled => {
                         let normalized_target =
                             crate::proxy::common::model_mapping::normalize_to_standard_id(
                                 target_model,
@@ -1489,25 +1944,25 @@ impl TokenManager {
 
             // Safety net: avoid selecting an account that has been disabled on disk but still
             // exists in the in-memory snapshot (e.g. stale cache + sticky session binding).
-            match Self::get_account_state_on_disk(&token.account_path).await {
-                OnDiskAccountState::Disabled => {
+            match self.get_account_state_in_store(&token.account_id) {
+                StoredAccountState::Disabled => {
                     tracing::warn!(
-                        "Selected account {} is disabled on disk, purging and retrying",
+                        "Selected account {} is disabled in store, purging and retrying",
                         token.email
                     );
                     attempted.insert(token.account_id.clone());
                     self.remove_account(&token.account_id);
                     continue;
                 }
-                OnDiskAccountState::Unknown => {
+                StoredAccountState::Unknown => {
                     tracing::warn!(
-                        "Selected account {} state on disk is unavailable, skipping",
+                        "Selected account {} state in store is unavailable, skipping",
                         token.email
                     );
                     attempted.insert(token.account_id.clone());
                     continue;
                 }
-                OnDiskAccountState::Enabled => {}
+                StoredAccountState::Enabled => {}
             }
 
             // 3. 检查 token 是否过期（提前5分钟刷新）
@@ -1542,7 +1997,7 @@ impl TokenManager {
                     }
                     Err(e) => {
                         tracing::error!("Token 刷新失败 ({}): {}，尝试下一个账号", token.email, e);
-                        if e.contains("\"invalid_grant\"") || e.contains("invalid_grant") {
+                        if token_refresh::classify_refresh_error(&e) == RefreshFailure::InvalidGrant {
                             tracing::error!(
                                 "Disabling account due to invalid_grant ({}): refresh_token likely revoked/expired",
                                 token.email
@@ -1622,73 +2077,50 @@ impl TokenManager {
     }
 
     async fn disable_account(&self, account_id: &str, reason: &str) -> Result<(), String> {
-        let path = if let Some(entry) = self.tokens.get(account_id) {
-            entry.account_path.clone()
-        } else {
-            self.data_dir
-                .join("accounts")
-                .join(format!("{}.json", account_id))
-        };
-
-        let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?,
-        )
-        .map_err(|e| format!("解析 JSON 失败: {}", e))?;
-
         let now = chrono::Utc::now().timestamp();
-        content["disabled"] = serde_json::Value::Bool(true);
-        content["disabled_at"] = serde_json::Value::Number(now.into());
-        content["disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
-
-        std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        self.update_stored_account(account_id, |content| {
+            content["disabled"] = serde_json::Value::Bool(true);
+            content["disabled_at"] = serde_json::Value::Number(now.into());
+            content["disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
+        })
+        .map_err(|e| format!("写入账号失败: {}", e))?;
 
         // 【修复 Issue #3】从内存中移除禁用的账号，防止被60s锁定逻辑继续使用
         self.tokens.remove(account_id);
 
-        tracing::warn!("Account disabled: {} ({:?})", account_id, path);
+        tracing::warn!("Account disabled: {}", account_id);
         Ok(())
     }
 
-    /// 保存 project_id 到账号文件
+    /// 保存 project_id 到账号存储
     async fn save_project_id(&self, account_id: &str, project_id: &str) -> Result<(), String> {
-        let entry = self.tokens.get(account_id)
-            .ok_or("账号不存在")?;
-
-        let path = &entry.account_path;
-
-        let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
-        ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
-
-        content["token"]["project_id"] = serde_json::Value::String(project_id.to_string());
+        if !self.tokens.contains_key(account_id) {
+            return Err("账号不存在".to_string());
+        }
 
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        self.update_stored_account(account_id, |content| {
+            content["token"]["project_id"] = serde_json::Value::String(project_id.to_string());
+        })
+        .map_err(|e| format!("写入账号失败: {}", e))?;
 
         tracing::debug!("已保存 project_id 到账号 {}", account_id);
         Ok(())
     }
 
-    /// 保存刷新后的 token 到账号文件
+    /// 保存刷新后的 token 到账号存储
     async fn save_refreshed_token(&self, account_id: &str, token_response: &crate::modules::oauth::TokenResponse) -> Result<(), String> {
-        let entry = self.tokens.get(account_id)
-            .ok_or("账号不存在")?;
-
-        let path = &entry.account_path;
-
-        let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
-        ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
+        if !self.tokens.contains_key(account_id) {
+            return Err("账号不存在".to_string());
+        }
 
         let now = chrono::Utc::now().timestamp();
 
-        content["token"]["access_token"] = serde_json::Value::String(token_response.access_token.clone());
-        content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
-        content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
-
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        self.update_stored_account(account_id, |content| {
+            content["token"]["access_token"] = serde_json::Value::String(token_response.access_token.clone());
+            content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
+            content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
+        })
+        .map_err(|e| format!("写入账号失败: {}", e))?;
 
         tracing::debug!("已保存刷新后的 token 到账号 {}", account_id);
         Ok(())
@@ -1698,6 +2130,11 @@ impl TokenManager {
         self.tokens.len()
     }
 
+    /// [NEW] 已启用的 Provider 账号数
+    pub fn provider_count(&self) -> usize {
+        self.providers.len()
+    }
+
     /// 通过 email 获取指定账号的 Token（用于预热等需要指定账号的场景）
     /// 此方法会自动刷新过期的 token
     pub async fn get_token_by_email(
@@ -1715,7 +2152,6 @@ impl TokenManager {
                         token.access_token.clone(),
                         token.refresh_token.clone(),
                         token.timestamp,
-                        token.expires_in,
                         chrono::Utc::now().timestamp(),
                         token.project_id.clone(),
                     ));
@@ -1730,7 +2166,6 @@ impl TokenManager {
             current_access_token,
             refresh_token,
             timestamp,
-            expires_in,
             now,
             project_id_opt,
         ) = match token_info {
@@ -1742,8 +2177,8 @@ impl TokenManager {
             .filter(|s| !s.is_empty())
             .unwrap_or_else(|| "bamboo-precept-lgxtn".to_string());
 
-        // 检查是否过期 (提前5分钟)
-        if now < timestamp + expires_in - 300 {
+        // 检查是否过期 (提前5分钟，timestamp 为过期时间戳)
+        if now < timestamp - 300 {
             return Ok((current_access_token, project_id, email.to_string(), account_id, 0));
         }
 
@@ -1759,7 +2194,7 @@ impl TokenManager {
                 if let Some(mut entry) = self.tokens.get_mut(&account_id) {
                     entry.access_token = token_response.access_token.clone();
                     entry.expires_in = token_response.expires_in;
-                    entry.timestamp = new_now;
+                    entry.timestamp = new_now + token_response.expires_in;
                 }
 
                 // 保存到磁盘
@@ -1782,6 +2217,76 @@ impl TokenManager {
         }
     }
 
+    /// 为对冲请求挑选一个不同于主请求的账号
+    /// 只选择 token 未临近过期且已有 project_id 的账号，避免对冲路径引入额外的 OAuth 往返
+    /// 返回 (access_token, project_id, email, account_id)
+    pub async fn get_hedge_token(
+        &self,
+        target_model: &str,
+        exclude_account_id: &str,
+    ) -> Option<(String, String, String, String)> {
+        let normalized_target = crate::proxy::common::model_mapping::normalize_to_standard_id(target_model)
+            .unwrap_or_else(|| target_model.to_string());
+        let quota_protection_enabled = crate::modules::config::load_app_config()
+            .map(|cfg| cfg.quota_protection.enabled)
+            .unwrap_or(false);
+        let now = chrono::Utc::now().timestamp();
+
+        // [FIX] 令牌限定的账号池同样约束对冲请求；固定账号 (令牌优先账号或全局固定账号) 时不对冲
+        let token_scope = crate::proxy::token_policy::current_account_scope();
+        if token_scope
+            .as_ref()
+            .is_some_and(|s| s.preferred_account_id.is_some())
+            || self.preferred_account_id.read().await.is_some()
+        {
+            return None;
+        }
+
+        let snapshot: Vec<ProxyToken> = self.tokens.iter().map(|e| e.value().clone()).collect();
+        let mut candidates: Vec<ProxyToken> = Vec::new();
+        for t in snapshot {
+            if t.account_id == exclude_account_id
+                || token_scope
+                    .as_ref()
+                    .is_some_and(|scope| !scope.allows(&t.account_id, &t.email))
+                || !t.model_quotas.contains_key(&normalized_target)
+                || (t.validation_blocked && t.validation_blocked_until > now)
+                || now >= t.timestamp - 300
+                || t.project_id.as_deref().map_or(true, |pid| pid.is_empty())
+            {
+                continue;
+            }
+            if self.is_rate_limited(&t.account_id, Some(&normalized_target)).await {
+                continue;
+            }
+            if self.get_account_state_in_store(&t.account_id) != StoredAccountState::Enabled {
+                continue;
+            }
+            candidates.push(t);
+        }
+
+        // 与主路径一致：优先目标模型剩余配额更高的账号
+        candidates.sort_by(|a, b| {
+            let qa = a.model_quotas.get(&normalized_target).copied().unwrap_or(0);
+            let qb = b.model_quotas.get(&normalized_target).copied().unwrap_or(0);
+            qb.cmp(&qa)
+        });
+
+        let selected = self.select_with_p2c(
+            &candidates,
+            &HashSet::new(),
+            &normalized_target,
+            quota_protection_enabled,
+        )?;
+
+        Some((
+            selected.access_token.clone(),
+            selected.project_id.clone().unwrap_or_default(),
+            selected.email.clone(),
+            selected.account_id.clone(),
+        ))
+    }
+
     // ===== 限流管理方法 =====
 
     /// 标记账号限流(从外部调用,通常在 handler 中)
@@ -1863,6 +2368,24 @@ impl TokenManager {
         self.rate_limit_tracker.clear_all();
     }
 
+    /// 限流跟踪器 (供调度模拟器直接操控锁定状态)
+    pub(crate) fn rate_limit_tracker(&self) -> &RateLimitTracker {
+        &self.rate_limit_tracker
+    }
+
+    /// 更新内存中某账号的模型配额 (不落盘)
+    pub(crate) fn set_model_quota(&self, account_id: &str, model: &str, percentage: i32) {
+        if let Some(mut entry) = self.tokens.get_mut(account_id) {
+            entry.model_quotas.insert(model.to_string(), percentage);
+            entry.remaining_quota = entry.model_quotas.values().copied().max();
+        }
+    }
+
+    /// 清除 60s 全局锁定窗口记录的上一个账号
+    pub(crate) async fn clear_last_used_account(&self) {
+        *self.last_used_account.lock().await = None;
+    }
+
     /// 标记账号请求成功，重置连续失败计数
     ///
     /// 在请求成功完成后调用，将该账号的失败计数归零，
@@ -1937,31 +2460,23 @@ impl TokenManager {
         false
     }
 
-    /// 从账号文件获取配额刷新时间
+    /// 从账号存储获取配额刷新时间
     ///
     /// 返回该账号最近的配额刷新时间字符串（ISO 8601 格式）
     ///
     /// # 参数
-    /// - `account_id`: 账号 ID（用于查找账号文件）
+    /// - `account_id`: 账号 ID
     pub fn get_quota_reset_time(&self, account_id: &str) -> Option<String> {
-        // 直接用 account_id 查找账号文件（文件名是 {account_id}.json）
-        let account_path = self.data_dir.join("accounts").join(format!("{}.json", account_id));
-
-        let content = std::fs::read_to_string(&account_path).ok()?;
-        let account: serde_json::Value = serde_json::from_str(&content).ok()?;
+        let account = account_db::get_account(&self.data_dir, account_id).ok()??;
 
         // 获取 quota.models 中最早的 reset_time（最保守的锁定策略）
         account
-            .get("quota")
-            .and_then(|q| q.get("models"))
-            .and_then(|m| m.as_array())
-            .and_then(|models| {
-                models.iter()
-                    .filter_map(|m| m.get("reset_time").and_then(|r| r.as_str()))
-                    .filter(|s| !s.is_empty())
-                    .min()
-                    .map(|s| s.to_string())
-            })
+            .quota?
+            .models
+            .into_iter()
+            .map(|m| m.reset_time)
+            .filter(|s| !s.is_empty())
+            .min()
     }
 
     /// 使用配额刷新时间精确锁定账号
@@ -2188,6 +2703,12 @@ impl TokenManager {
         tracing::debug!("Circuit breaker configuration updated");
     }
 
+    /// [NEW] 更新 Token 预刷新配置
+    pub async fn update_token_refresh_config(&self, config: crate::models::TokenRefreshConfig) {
+        *self.token_refresh_config.write().await = config;
+        tracing::debug!("Token refresh configuration updated");
+    }
+
     /// [NEW] 获取熔断器配置
     pub async fn get_circuit_breaker_config(&self) -> crate::models::CircuitBreakerConfig {
         self.circuit_breaker_config.read().await.clone()
@@ -2287,6 +2808,109 @@ impl TokenManager {
         self.reload_all_accounts().await.map(|_| ())
     }
 
+    /// [NEW] 选出本次请求需要依次尝试的 Provider 账号 (可服务该模型、未限流、本请求内未尝试过)
+    pub async fn select_providers(
+        &self,
+        model: &str,
+        stage: ProviderStage,
+        attempted: &HashSet<String>,
+    ) -> Vec<ProviderEndpoint> {
+        if self.providers.is_empty() {
+            return Vec::new();
+        }
+        // 令牌级强制映射同样作用于 Provider 路由
+        let model = crate::proxy::token_policy::current_forced_model().unwrap_or_else(|| model.to_string());
+        let token_scope = crate::proxy::token_policy::current_account_scope();
+        let mut candidates = Vec::new();
+        let snapshot: Vec<ProviderEndpoint> = self.providers.iter().map(|e| e.value().clone()).collect();
+        for mut endpoint in snapshot {
+            if attempted.contains(&endpoint.account_id) || !endpoint.serves(&model) {
+                continue;
+            }
+            if token_scope
+                .as_ref()
+                .is_some_and(|scope| !scope.allows(&endpoint.account_id, &endpoint.name))
+            {
+                continue;
+            }
+            if self.is_rate_limited(&endpoint.account_id, None).await {
+                continue;
+            }
+            endpoint.health_score = self
+                .health_scores
+                .get(&endpoint.account_id)
+                .map(|v| *v)
+                .unwrap_or(1.0);
+            candidates.push(endpoint);
+        }
+        if candidates.is_empty() {
+            return candidates;
+        }
+
+        let google_accounts = self.len();
+        let google_available = google_accounts > 0
+            && (stage == ProviderStage::AfterPool
+                || self
+                    .has_available_account(
+                        "",
+                        &crate::proxy::common::model_mapping::normalize_to_standard_id(&model)
+                            .unwrap_or_else(|| model.clone()),
+                    )
+                    .await);
+        let rotation = self.provider_index.fetch_add(1, Ordering::Relaxed);
+        provider::plan_attempts(candidates, stage, google_accounts, google_available, rotation)
+    }
+
+    /// [NEW] Provider 账号声明的具体模型 (模型, 账号名)，按模型名排序去重
+    pub fn provider_models(&self) -> Vec<(String, String)> {
+        let mut models: Vec<(String, String)> = self
+            .providers
+            .iter()
+            .flat_map(|entry| {
+                let name = entry.name.clone();
+                entry
+                    .config
+                    .models
+                    .iter()
+                    .filter(|m| !m.contains('*'))
+                    .map(move |m| (m.clone(), name.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        models.sort();
+        models.dedup_by(|a, b| a.0 == b.0);
+        models
+    }
+
+    /// [NEW] Provider 请求成功
+    pub fn mark_provider_success(&self, account_id: &str) {
+        self.record_success(account_id);
+        self.mark_account_success(account_id);
+    }
+
+    /// [NEW] Provider 请求失败：降低健康分，429 / 5xx / 404 按熔断配置短时避让
+    pub async fn mark_provider_failure(
+        &self,
+        account_id: &str,
+        status: u16,
+        retry_after_header: Option<&str>,
+        error_body: &str,
+    ) {
+        self.record_failure(account_id);
+        let config = self.circuit_breaker_config.read().await.clone();
+        if !config.enabled {
+            return;
+        }
+        self.rate_limit_tracker.parse_from_error(
+            account_id,
+            status,
+            retry_after_header,
+            error_body,
+            None,
+            &config.backoff_steps,
+        );
+    }
+
     /// 记录请求成功，增加健康分
     pub fn record_success(&self, account_id: &str) {
         self.health_scores
@@ -2376,22 +3000,6 @@ impl TokenManager {
              token.validation_blocked_until = block_until;
         }
 
-        // 2. Persist to disk
-        let path = self.data_dir.join("accounts").join(format!("{}.json", account_id));
-        if !path.exists() {
-             return Err(format!("Account file not found: {:?}", path));
-        }
-
-        let content = std::fs::read_to_string(&path)
-             .map_err(|e| format!("Failed to read account file: {}", e))?;
-
-        let mut account: serde_json::Value = serde_json::from_str(&content)
-             .map_err(|e| format!("Failed to parse account JSON: {}", e))?;
-
-        account["validation_blocked"] = serde_json::Value::Bool(true);
-        account["validation_blocked_until"] = serde_json::Value::Number(serde_json::Number::from(block_until));
-        account["validation_blocked_reason"] = serde_json::Value::String(reason.to_string());
-
         // [NEW] 尝试从消息中提取验证链接 (#1522)
         let extracted_url = if let Ok(parsed_json) = serde_json::from_str::<serde_json::Value>(reason) {
              // 尝试从特定的 Google RPC error 结构中取
@@ -2418,21 +3026,25 @@ impl TokenManager {
              })
         };
         
-        if let Some(url) = extracted_url {
-             account["validation_url"] = serde_json::Value::String(url.clone());
+        if let Some(url) = extracted_url.as_ref() {
              if let Some(mut token) = self.tokens.get_mut(account_id) {
-                 token.validation_url = Some(url);
+                 token.validation_url = Some(url.clone());
              }
         }
 
         // Clear sticky session if blocked
         self.session_accounts.retain(|_, v| *v != account_id);
 
-        let json_str = serde_json::to_string_pretty(&account)
-             .map_err(|e| format!("Failed to serialize account JSON: {}", e))?;
-
-        std::fs::write(&path, json_str)
-             .map_err(|e| format!("Failed to write account file: {}", e))?;
+        // 2. Persist to account store
+        self.update_stored_account(account_id, |account| {
+             account["validation_blocked"] = serde_json::Value::Bool(true);
+             account["validation_blocked_until"] = serde_json::Value::Number(serde_json::Number::from(block_until));
+             account["validation_blocked_reason"] = serde_json::Value::String(reason.to_string());
+             if let Some(url) = extracted_url {
+                 account["validation_url"] = serde_json::Value::String(url);
+             }
+        })
+        .map_err(|e| format!("Failed to write account: {}", e))?;
 
         tracing::info!(
              "🚫 Account {} validation blocked until {} (reason: {})",
@@ -2451,41 +3063,27 @@ impl TokenManager {
 
     /// Set is_forbidden status for an account (called when proxy encounters 403)
     pub async fn set_forbidden(&self, account_id: &str, reason: &str) -> Result<(), String> {
-        // 1. Persist to disk - update quota.is_forbidden in account JSON
-        let path = self.data_dir.join("accounts").join(format!("{}.json", account_id));
-        if !path.exists() {
-            return Err(format!("Account file not found: {:?}", path));
-        }
-
-        let content = std::fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read account file: {}", e))?;
-
-        let mut account: serde_json::Value = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse account JSON: {}", e))?;
-
-        // Update quota.is_forbidden
-        if let Some(quota) = account.get_mut("quota") {
-            quota["is_forbidden"] = serde_json::Value::Bool(true);
-            quota["forbidden_reason"] = serde_json::Value::String(reason.to_string());
-        } else {
-            // Create quota object if not exists
-            account["quota"] = serde_json::json!({
-                "models": [],
-                "last_updated": chrono::Utc::now().timestamp(),
-                "is_forbidden": true,
-                "forbidden_reason": reason
-            });
-        }
+        // 1. Persist to account store - update quota.is_forbidden in account JSON
+        self.update_stored_account(account_id, |account| {
+            // Update quota.is_forbidden
+            if let Some(quota) = account.get_mut("quota").filter(|q| q.is_object()) {
+                quota["is_forbidden"] = serde_json::Value::Bool(true);
+                quota["forbidden_reason"] = serde_json::Value::String(reason.to_string());
+            } else {
+                // Create quota object if not exists
+                account["quota"] = serde_json::json!({
+                    "models": [],
+                    "last_updated": chrono::Utc::now().timestamp(),
+                    "is_forbidden": true,
+                    "forbidden_reason": reason
+                });
+            }
+        })
+        .map_err(|e| format!("Failed to write account: {}", e))?;
 
         // Clear sticky session if forbidden
         self.session_accounts.retain(|_, v| *v != account_id);
 
-        let json_str = serde_json::to_string_pretty(&account)
-            .map_err(|e| format!("Failed to serialize account JSON: {}", e))?;
-
-        std::fs::write(&path, json_str)
-            .map_err(|e| format!("Failed to write account file: {}", e))?;
-
         // [FIX] 从内存池中移除账号，避免重试时再次选中
         self.remove_account(account_id);
 
@@ -2520,19 +3118,23 @@ mod tests {
     use super::*;
     use std::cmp::Ordering;
 
+    /// 将测试账号 JSON 写入临时目录下的账号存储
+    fn seed_account(data_dir: &std::path::Path, json: &serde_json::Value) {
+        let account: crate::models::Account = serde_json::from_value(json.clone()).unwrap();
+        account_db::save_account(data_dir, &account, ChangeOrigin::App).unwrap();
+    }
+
     #[tokio::test]
     async fn test_reload_account_purges_cache_when_account_becomes_proxy_disabled() {
         let tmp_root = std::env::temp_dir().join(format!(
             "antigravity-token-manager-test-{}",
             uuid::Uuid::new_v4()
         ));
-        let accounts_dir = tmp_root.join("accounts");
-        std::fs::create_dir_all(&accounts_dir).unwrap();
+        std::fs::create_dir_all(&tmp_root).unwrap();
 
         let account_id = "acc1";
         let email = "a@test.com";
         let now = chrono::Utc::now().timestamp();
-        let account_path = accounts_dir.join(format!("{}.json", account_id));
 
         let account_json = serde_json::json!({
             "id": account_id,
@@ -2541,14 +3143,15 @@ mod tests {
                 "access_token": "atk",
                 "refresh_token": "rtk",
                 "expires_in": 3600,
-                "expiry_timestamp": now + 3600
+                "expiry_timestamp": now + 3600,
+                "token_type": "Bearer"
             },
             "disabled": false,
             "proxy_disabled": false,
             "created_at": now,
             "last_used": now
         });
-        std::fs::write(&account_path, serde_json::to_string_pretty(&account_json).unwrap()).unwrap();
+        seed_account(&tmp_root, &account_json);
 
         let manager = TokenManager::new(tmp_root.clone());
         manager.load_accounts().await.unwrap();
@@ -2563,12 +3166,12 @@ mod tests {
             *preferred = Some(account_id.to_string());
         }
 
-        // Mark account as proxy-disabled on disk (manual disable).
+        // Mark account as proxy-disabled in the store (manual disable).
         let mut disabled_json = account_json.clone();
         disabled_json["proxy_disabled"] = serde_json::Value::Bool(true);
         disabled_json["proxy_disabled_reason"] = serde_json::Value::String("manual".to_string());
         disabled_json["proxy_disabled_at"] = serde_json::Value::Number(now.into());
-        std::fs::write(&account_path, serde_json::to_string_pretty(&disabled_json).unwrap()).unwrap();
+        seed_account(&tmp_root, &disabled_json);
 
         manager.reload_account(account_id).await.unwrap();
 
@@ -2585,13 +3188,11 @@ mod tests {
             "antigravity-token-manager-test-fixed-mode-{}",
             uuid::Uuid::new_v4()
         ));
-        let accounts_dir = tmp_root.join("accounts");
-        std::fs::create_dir_all(&accounts_dir).unwrap();
+        std::fs::create_dir_all(&tmp_root).unwrap();
 
         let now = chrono::Utc::now().timestamp();
 
         let write_account = |id: &str, email: &str, proxy_disabled: bool| {
-            let account_path = accounts_dir.join(format!("{}.json", id));
             let json = serde_json::json!({
                 "id": id,
                 "email": email,
@@ -2600,6 +3201,7 @@ mod tests {
                     "refresh_token": format!("rtk-{}", id),
                     "expires_in": 3600,
                     "expiry_timestamp": now + 3600,
+                    "token_type": "Bearer",
                     "project_id": format!("pid-{}", id)
                 },
                 "disabled": false,
@@ -2608,7 +3210,7 @@ mod tests {
                 "created_at": now,
                 "last_used": now
             });
-            std::fs::write(&account_path, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+            seed_account(&tmp_root, &json);
         };
 
         // Two accounts in pool.
@@ -2621,7 +3223,7 @@ mod tests {
         // Enable fixed account mode for acc1.
         manager.set_preferred_account(Some("acc1".to_string())).await;
 
-        // Disable acc1 on disk WITHOUT reloading the in-memory pool (simulates stale cache).
+        // Disable acc1 in the store WITHOUT reloading the in-memory pool (simulates stale cache).
         write_account("acc1", "a@test.com", true);
 
         let (_token, _project_id, email, account_id, _wait_ms) = manager
@@ -2644,13 +3246,11 @@ mod tests {
             "antigravity-token-manager-test-sticky-disabled-{}",
             uuid::Uuid::new_v4()
         ));
-        let accounts_dir = tmp_root.join("accounts");
-        std::fs::create_dir_all(&accounts_dir).unwrap();
+        std::fs::create_dir_all(&tmp_root).unwrap();
 
         let now = chrono::Utc::now().timestamp();
 
         let write_account = |id: &str, email: &str, percentage: i64, proxy_disabled: bool| {
-            let account_path = accounts_dir.join(format!("{}.json", id));
             let json = serde_json::json!({
                 "id": id,
                 "email": email,
@@ -2659,12 +3259,14 @@ mod tests {
                     "refresh_token": format!("rtk-{}", id),
                     "expires_in": 3600,
                     "expiry_timestamp": now + 3600,
+                    "token_type": "Bearer",
                     "project_id": format!("pid-{}", id)
                 },
                 "quota": {
                     "models": [
-                        { "name": "gemini-1.5-flash", "percentage": percentage }
-                    ]
+                        { "name": "gemini-1.5-flash", "percentage": percentage, "reset_time": "" }
+                    ],
+                    "last_updated": now
                 },
                 "disabled": false,
                 "proxy_disabled": proxy_disabled,
@@ -2672,7 +3274,7 @@ mod tests {
                 "created_at": now,
                 "last_used": now
             });
-            std::fs::write(&account_path, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+            seed_account(&tmp_root, &json);
         };
 
         // Two accounts in pool. acc1 has higher quota -> should be selected and bound first.
@@ -2693,7 +3295,7 @@ mod tests {
             Some("acc1".to_string())
         );
 
-        // Disable acc1 on disk WITHOUT reloading the in-memory pool (simulates stale cache).
+        // Disable acc1 in the store WITHOUT reloading the in-memory pool (simulates stale cache).
         write_account("acc1", "a@test.com", 90, true);
 
         let (_token, _project_id, email, account_id, _wait_ms) = manager
@@ -2713,6 +3315,221 @@ mod tests {
         let _ = std::fs::remove_dir_all(&tmp_root);
     }
 
+    #[tokio::test]
+    async fn test_pinned_account_pool_applies_in_spawned_tasks() {
+        let tmp_root = std::env::temp_dir().join(format!(
+            "antigravity-token-manager-test-pinned-spawn-{}",
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::create_dir_all(&tmp_root).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        for (id, email, percentage) in [("acc1", "a@test.com", 90), ("acc2", "b@test.com", 10)] {
+            let json = serde_json::json!({
+                "id": id,
+                "email": email,
+                "token": {
+                    "access_token": format!("atk-{}", id),
+                    "refresh_token": format!("rtk-{}", id),
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600,
+                    "token_type": "Bearer",
+                    "project_id": format!("pid-{}", id)
+                },
+                "quota": {
+                    "models": [
+                        { "name": "gemini-3-pro-image", "percentage": percentage, "reset_time": "" }
+                    ],
+                    "last_updated": now
+                },
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            });
+            seed_account(&tmp_root, &json);
+        }
+
+        let manager = Arc::new(TokenManager::new(tmp_root.clone()));
+        manager.load_accounts().await.unwrap();
+
+        // 令牌只允许 acc2 (配额较低，不受限时不会被优先选中)
+        let policy = crate::modules::user_token_db::TokenModelPolicy {
+            account_pool: vec!["acc2".to_string()],
+            ..Default::default()
+        };
+        let picked = crate::proxy::token_policy::scope(policy, async {
+            let mut tasks = Vec::new();
+            for attempt in 0..3 {
+                let manager = manager.clone();
+                tasks.push(crate::proxy::token_policy::spawn_scoped(async move {
+                    manager
+                        .get_token("image_gen", attempt > 0, None, "gemini-3-pro-image")
+                        .await
+                        .map(|(_, _, _, account_id, _)| account_id)
+                }));
+            }
+            let mut picked = Vec::new();
+            for task in tasks {
+                picked.push(task.await.unwrap().unwrap());
+            }
+
+            // 直接 tokio::spawn 的任务看不到令牌策略
+            let leaked = tokio::spawn(async { crate::proxy::token_policy::current_account_scope() });
+            assert!(leaked.await.unwrap().is_none());
+            picked
+        })
+        .await;
+        assert_eq!(picked, vec!["acc2", "acc2", "acc2"]);
+
+        let _ = std::fs::remove_dir_all(&tmp_root);
+    }
+
+    #[tokio::test]
+    async fn test_hedge_token_respects_account_scope() {
+        let tmp_root = std::env::temp_dir().join(format!(
+            "antigravity-token-manager-test-hedge-scope-{}",
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::create_dir_all(&tmp_root).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        for (id, email, percentage) in [
+            ("acc1", "a@test.com", 50),
+            ("acc2", "b@test.com", 90),
+            ("acc3", "c@test.com", 10),
+        ] {
+            let json = serde_json::json!({
+                "id": id,
+                "email": email,
+                "token": {
+                    "access_token": format!("atk-{}", id),
+                    "refresh_token": format!("rtk-{}", id),
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600,
+                    "token_type": "Bearer",
+                    "project_id": format!("pid-{}", id)
+                },
+                "quota": {
+                    "models": [
+                        { "name": "claude-sonnet-4-5", "percentage": percentage, "reset_time": "" }
+                    ],
+                    "last_updated": now
+                },
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            });
+            seed_account(&tmp_root, &json);
+        }
+
+        let manager = TokenManager::new(tmp_root.clone());
+        manager.load_accounts().await.unwrap();
+
+        // 令牌只允许 acc1 / acc3：对冲不得选中池外的 acc2
+        let pooled = crate::modules::user_token_db::TokenModelPolicy {
+            account_pool: vec!["acc1".to_string(), "c@test.com".to_string()],
+            ..Default::default()
+        };
+        let hedge = crate::proxy::token_policy::scope(pooled, async {
+            manager.get_hedge_token("claude-sonnet-4-5", "acc1").await
+        })
+        .await;
+        assert_eq!(hedge.map(|(_, _, _, account_id)| account_id).as_deref(), Some("acc3"));
+
+        // 令牌固定了优先账号时不对冲
+        let pinned = crate::modules::user_token_db::TokenModelPolicy {
+            preferred_account_id: Some("acc1".to_string()),
+            ..Default::default()
+        };
+        let hedge = crate::proxy::token_policy::scope(pinned, async {
+            manager.get_hedge_token("claude-sonnet-4-5", "acc1").await
+        })
+        .await;
+        assert!(hedge.is_none());
+
+        let _ = std::fs::remove_dir_all(&tmp_root);
+    }
+
+    #[tokio::test]
+    async fn test_runtime_snapshot_restore_skips_expired_and_unknown() {
+        let tmp_root = std::env::temp_dir().join(format!(
+            "antigravity-token-manager-test-restore-{}",
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::create_dir_all(&tmp_root).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        seed_account(
+            &tmp_root,
+            &serde_json::json!({
+                "id": "acc1",
+                "email": "a@test.com",
+                "token": {
+                    "access_token": "atk",
+                    "refresh_token": "rtk",
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600,
+                    "token_type": "Bearer"
+                },
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            }),
+        );
+        let manager = TokenManager::new(tmp_root.clone());
+        manager.load_accounts().await.unwrap();
+        // 内存中已有的绑定优先于快照
+        manager
+            .session_accounts
+            .insert("sid-live".to_string(), "acc1".to_string());
+
+        let binding = |sid: &str, account_id: &str, updated_at: i64| SessionBindingRecord {
+            session_id: sid.to_string(),
+            account_id: account_id.to_string(),
+            updated_at,
+        };
+        let limit = |key: &str, reset_time: i64| crate::modules::runtime_state_db::RateLimitRecord {
+            key: key.to_string(),
+            reset_time,
+            retry_after_sec: 60,
+            detected_at: now - 60,
+            reason: "quota_exhausted".to_string(),
+            model: None,
+        };
+        let snapshot = RuntimeStateSnapshot {
+            sessions: vec![
+                binding("sid-fresh", "acc1", now - 60),
+                binding("sid-expired", "acc1", now - SESSION_BINDING_TTL_SECS - 1),
+                binding("sid-unknown", "deleted-account", now - 60),
+                binding("sid-live", "deleted-account", now - 60),
+            ],
+            rate_limits: vec![limit("acc1", now + 600), limit("acc1:gemini-3-flash", now - 1)],
+            ..Default::default()
+        };
+
+        let (sessions, limits, _, _) = manager.apply_runtime_snapshot(snapshot, now);
+        assert_eq!((sessions, limits), (1, 1));
+        assert_eq!(
+            manager.session_accounts.get("sid-fresh").map(|v| v.clone()),
+            Some("acc1".to_string())
+        );
+        assert!(manager.session_accounts.get("sid-expired").is_none());
+        assert!(manager.session_accounts.get("sid-unknown").is_none());
+        assert_eq!(
+            manager.session_accounts.get("sid-live").map(|v| v.clone()),
+            Some("acc1".to_string())
+        );
+
+        let (restored_limits, _) = manager.rate_limit_tracker.export_state();
+        assert_eq!(restored_limits.len(), 1);
+        assert_eq!(restored_limits[0].key, "acc1");
+
+        let _ = std::fs::remove_dir_all(&tmp_root);
+    }
+
     /// 创建测试用的 ProxyToken
     fn create_test_token(
         email: &str,
@@ -2728,7 +3545,6 @@ mod tests {
             expires_in: 3600,
             timestamp: chrono::Utc::now().timestamp() + 3600,
             email: email.to_string(),
-            account_path: PathBuf::from("/tmp/test"),
             project_id: None,
             subscription_tier: tier.map(|s| s.to_string()),
             remaining_quota,
@@ -2737,6 +3553,7 @@ mod tests {
             reset_time,
             validation_blocked: false,
             validation_blocked_until: 0,
+            validation_url: None,
             model_quotas: HashMap::new(),
         }
     }
@@ -2984,7 +3801,6 @@ mod tests {
             expires_in: 3600,
             timestamp: chrono::Utc::now().timestamp() + 3600,
             email: email.to_string(),
-            account_path: PathBuf::from("/tmp/test"),
             project_id: None,
             subscription_tier: Some("PRO".to_string()),
             remaining_quota,
@@ -2993,6 +3809,7 @@ mod tests {
             reset_time: None,
             validation_blocked: false,
             validation_blocked_until: 0,
+            validation_url: None,
             model_quotas: HashMap::new(),
         }
     }
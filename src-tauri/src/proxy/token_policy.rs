@@ -0,0 +1,221 @@
+// 用户令牌模型策略
+// 允许/拒绝模型模式在 monitor_middleware 中预检查 (同时检查请求模型与路由目标)，
+// 强制映射与账号池通过 task-local 作用域传递给 resolve_model_route 与 TokenManager。
+
+use axum::{body::Body, http::StatusCode, response::Response};
+use std::collections::HashMap;
+use std::future::Future;
+
+use crate::modules::user_token_db::TokenModelPolicy;
+use crate::proxy::common::model_mapping::{preview_model_route, wildcard_match};
+
+tokio::task_local! {
+    static CURRENT_POLICY: TokenModelPolicy;
+}
+
+/// 令牌限定的账号范围
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountScope {
+    /// 账号 ID 或邮箱，为空表示不限制
+    pub account_pool: Vec<String>,
+    pub preferred_account_id: Option<String>,
+}
+
+impl AccountScope {
+    /// 账号是否在令牌的账号池内
+    pub fn allows(&self, account_id: &str, email: &str) -> bool {
+        self.account_pool.is_empty()
+            || self
+                .account_pool
+                .iter()
+                .any(|a| a == account_id || a.eq_ignore_ascii_case(email))
+    }
+}
+
+/// 在令牌策略作用域内执行请求
+pub async fn scope<F: Future>(policy: TokenModelPolicy, fut: F) -> F::Output {
+    CURRENT_POLICY.scope(policy, fut).await
+}
+
+/// 在新任务中执行并延续当前令牌策略作用域 (task-local 不会自动传递给 `tokio::spawn` 的任务)
+pub fn spawn_scoped<F>(fut: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match CURRENT_POLICY.try_with(|p| p.clone()) {
+        Ok(policy) => tokio::spawn(CURRENT_POLICY.scope(policy, fut)),
+        Err(_) => tokio::spawn(fut),
+    }
+}
+
+/// 当前请求的令牌强制映射目标
+pub fn current_forced_model() -> Option<String> {
+    CURRENT_POLICY
+        .try_with(|p| p.forced_model.clone())
+        .ok()
+        .flatten()
+        .filter(|m| !m.is_empty())
+}
+
+/// 当前请求的令牌账号范围 (无策略或未限定时返回 None)
+pub fn current_account_scope() -> Option<AccountScope> {
+    CURRENT_POLICY
+        .try_with(|p| AccountScope {
+            account_pool: p.account_pool.clone(),
+            preferred_account_id: p.preferred_account_id.clone().filter(|id| !id.is_empty()),
+        })
+        .ok()
+        .filter(|s| !s.account_pool.is_empty() || s.preferred_account_id.is_some())
+}
+
+fn matches_any(patterns: &[String], model: &str) -> bool {
+    patterns.iter().any(|p| wildcard_match(p, model))
+}
+
+/// 检查单个模型名是否被策略允许
+fn is_model_allowed(policy: &TokenModelPolicy, model: &str) -> bool {
+    if matches_any(&policy.denied_models, model) {
+        return false;
+    }
+    policy.allowed_models.is_empty() || matches_any(&policy.allowed_models, model)
+}
+
+/// 策略是否包含允许/拒绝模式 (无法识别请求模型时需要拒绝)
+pub fn restricts_models(policy: &TokenModelPolicy) -> bool {
+    !policy.allowed_models.is_empty() || !policy.denied_models.is_empty()
+}
+
+/// 检查请求模型及其路由目标是否被允许，返回被拒绝的模型名
+/// 强制映射的令牌只检查请求模型 (路由目标由管理员指定)
+pub fn check_model(
+    policy: &TokenModelPolicy,
+    requested_model: &str,
+    custom_mapping: &HashMap<String, String>,
+) -> Result<(), String> {
+    if !is_model_allowed(policy, requested_model) {
+        return Err(requested_model.to_string());
+    }
+
+    if policy.forced_model.as_deref().unwrap_or("").is_empty() {
+        let target = preview_model_route(requested_model, custom_mapping);
+        if target != requested_model && !is_model_allowed(policy, &target) {
+            return Err(target);
+        }
+    }
+
+    Ok(())
+}
+
+/// 模型被拒绝时的 403 响应
+pub fn model_forbidden_response(blocked_model: &str) -> Response {
+    let body = serde_json::json!({
+        "error": {
+            "message": format!("Model '{}' is not allowed for this API token.", blocked_model),
+            "type": "permission_error",
+            "code": "model_not_allowed",
+            "model": blocked_model
+        }
+    });
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// 受模型限制的令牌无法识别请求模型时的 403 响应
+pub fn model_required_response() -> Response {
+    let body = serde_json::json!({
+        "error": {
+            "message": "This API token is restricted to specific models, but the request model could not be determined.",
+            "type": "permission_error",
+            "code": "model_not_determined"
+        }
+    });
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowed: &[&str], denied: &[&str]) -> TokenModelPolicy {
+        TokenModelPolicy {
+            allowed_models: allowed.iter().map(|s| s.to_string()).collect(),
+            denied_models: denied.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_model_deny_wins_over_allow() {
+        let mapping = HashMap::new();
+        let p = policy(&["claude-*", "gemini-*"], &["claude-opus-*"]);
+
+        assert!(check_model(&p, "claude-sonnet-4-5", &mapping).is_ok());
+        assert!(check_model(&p, "gemini-3-flash", &mapping).is_ok());
+        assert_eq!(
+            check_model(&p, "claude-opus-4-6-thinking", &mapping).unwrap_err(),
+            "claude-opus-4-6-thinking"
+        );
+        assert_eq!(check_model(&p, "gpt-4o", &mapping).unwrap_err(), "gpt-4o");
+    }
+
+    #[test]
+    fn test_check_model_blocks_mapped_target() {
+        let mut mapping = HashMap::new();
+        mapping.insert("gpt-4o".to_string(), "claude-opus-4-6-thinking".to_string());
+        let p = policy(&[], &["claude-opus-*"]);
+
+        // 别名映射到被拒绝的模型时，返回实际目标模型名
+        assert_eq!(
+            check_model(&p, "gpt-4o", &mapping).unwrap_err(),
+            "claude-opus-4-6-thinking"
+        );
+
+        // 强制映射的令牌不检查路由目标
+        let forced = TokenModelPolicy {
+            forced_model: Some("gemini-3-flash".to_string()),
+            ..p
+        };
+        assert!(check_model(&forced, "gpt-4o", &mapping).is_ok());
+    }
+
+    #[test]
+    fn test_restricts_models_only_with_patterns() {
+        assert!(restricts_models(&policy(&["claude-*"], &[])));
+        assert!(restricts_models(&policy(&[], &["claude-opus-*"])));
+
+        // 仅限定账号池或强制映射时不要求识别模型
+        let pinned = TokenModelPolicy {
+            account_pool: vec!["acc-1".to_string()],
+            forced_model: Some("gemini-3-flash".to_string()),
+            ..Default::default()
+        };
+        assert!(!restricts_models(&pinned));
+    }
+
+    #[tokio::test]
+    async fn test_scope_exposes_forced_model_and_accounts() {
+        assert!(current_forced_model().is_none());
+        assert!(current_account_scope().is_none());
+
+        let p = TokenModelPolicy {
+            forced_model: Some("gemini-3-flash".to_string()),
+            account_pool: vec!["intern@example.com".to_string()],
+            ..Default::default()
+        };
+        scope(p, async {
+            assert_eq!(current_forced_model().as_deref(), Some("gemini-3-flash"));
+            let accounts = current_account_scope().unwrap();
+            assert!(accounts.allows("acc-1", "Intern@example.com"));
+            assert!(!accounts.allows("acc-2", "other@example.com"));
+        })
+        .await;
+    }
+}
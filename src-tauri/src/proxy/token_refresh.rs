@@ -0,0 +1,214 @@
+// 后台 Token 预刷新调度
+// 在 access_token 过期前 (margin + 每账号稳定的抖动) 主动续期，避免过期后的首个请求承担 OAuth 往返；
+// 临时失败按指数退避重试，invalid_grant 视为 refresh_token 已失效。执行逻辑见 TokenManager::start_token_refresher。
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::models::TokenRefreshConfig;
+
+/// 刷新失败分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshFailure {
+    /// refresh_token 已被撤销或过期，需要禁用账号
+    InvalidGrant,
+    /// 网络错误、上游 5xx 等，可重试
+    Transient,
+}
+
+/// 根据 refresh_access_token 的错误信息分类 (`Refresh failed: {"error": "invalid_grant", ...}`)
+pub fn classify_refresh_error(error: &str) -> RefreshFailure {
+    let oauth_error = error
+        .find('{')
+        .and_then(|start| serde_json::from_str::<serde_json::Value>(&error[start..]).ok())
+        .and_then(|body| {
+            body.get("error")
+                .and_then(|e| e.as_str())
+                .map(str::to_string)
+        });
+    match oauth_error {
+        Some(code) if code == "invalid_grant" => RefreshFailure::InvalidGrant,
+        Some(_) => RefreshFailure::Transient,
+        None if error.contains("invalid_grant") => RefreshFailure::InvalidGrant,
+        None => RefreshFailure::Transient,
+    }
+}
+
+/// 计划刷新时间 = 过期时间 - margin - 抖动
+/// 抖动由账号 ID 与过期时间决定，同一 Token 在多次巡检间保持不变；总提前量不超过有效期的一半，
+/// 避免 margin 配置过大时刚刷新的 Token 立即再次到期
+pub fn refresh_due_at(
+    account_id: &str,
+    expiry: i64,
+    lifetime: i64,
+    config: &TokenRefreshConfig,
+) -> i64 {
+    let jitter = if config.jitter_secs == 0 {
+        0
+    } else {
+        let mut hasher = DefaultHasher::new();
+        (account_id, expiry).hash(&mut hasher);
+        hasher.finish() % (config.jitter_secs + 1)
+    };
+    let lead = config
+        .margin_secs
+        .saturating_add(jitter)
+        .min(i64::MAX as u64) as i64;
+    expiry - lead.min(lifetime.max(0) / 2)
+}
+
+/// 第 attempt 次失败 (从 1 开始) 后的重试延迟，每次翻倍
+pub fn retry_delay_secs(config: &TokenRefreshConfig, attempt: u32) -> u64 {
+    let shift = attempt.saturating_sub(1).min(16);
+    config.retry_backoff_secs.saturating_mul(1 << shift)
+}
+
+/// 单个账号的重试状态，绑定到具体 Token (过期时间变化后失效)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryState {
+    pub expiry: i64,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+}
+
+impl RetryState {
+    /// 记录一次临时失败
+    pub fn after_failure(
+        previous: Option<RetryState>,
+        expiry: i64,
+        config: &TokenRefreshConfig,
+        now: i64,
+    ) -> Self {
+        let attempts = previous
+            .filter(|r| r.expiry == expiry)
+            .map(|r| r.attempts)
+            .unwrap_or(0)
+            + 1;
+        Self {
+            expiry,
+            attempts,
+            next_attempt_at: now + retry_delay_secs(config, attempts) as i64,
+        }
+    }
+
+    /// 重试次数已用完，交由请求路径按需刷新
+    pub fn exhausted(&self, config: &TokenRefreshConfig) -> bool {
+        self.attempts >= config.max_attempts
+    }
+}
+
+/// Token 当前是否需要由调度器刷新
+pub fn is_due(
+    account_id: &str,
+    expiry: i64,
+    lifetime: i64,
+    retry: Option<&RetryState>,
+    config: &TokenRefreshConfig,
+    now: i64,
+) -> bool {
+    if now < refresh_due_at(account_id, expiry, lifetime, config) {
+        return false;
+    }
+    match retry.filter(|r| r.expiry == expiry) {
+        Some(r) => !r.exhausted(config) && now >= r.next_attempt_at,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TokenRefreshConfig {
+        TokenRefreshConfig {
+            margin_secs: 600,
+            jitter_secs: 120,
+            retry_backoff_secs: 30,
+            max_attempts: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_classify_refresh_error() {
+        let revoked = r#"Refresh failed: {"error": "invalid_grant", "error_description": "Token has been expired or revoked."}"#;
+        assert_eq!(
+            classify_refresh_error(revoked),
+            RefreshFailure::InvalidGrant
+        );
+        let server = r#"Refresh failed: {"error": "internal_failure"}"#;
+        assert_eq!(classify_refresh_error(server), RefreshFailure::Transient);
+        assert_eq!(
+            classify_refresh_error("Refresh request failed: operation timed out"),
+            RefreshFailure::Transient
+        );
+        assert_eq!(
+            classify_refresh_error("Refresh failed: invalid_grant"),
+            RefreshFailure::InvalidGrant
+        );
+    }
+
+    #[test]
+    fn test_due_time_and_jitter() {
+        let cfg = config();
+        let expiry = 10_000;
+        let due = refresh_due_at("acc-1", expiry, 3600, &cfg);
+        assert!((expiry - 720..=expiry - 600).contains(&due));
+        // 同一 Token 的抖动稳定
+        assert_eq!(due, refresh_due_at("acc-1", expiry, 3600, &cfg));
+
+        // 不同账号的刷新时间被打散
+        let spread: std::collections::HashSet<i64> = (0..20)
+            .map(|i| refresh_due_at(&format!("acc-{}", i), expiry, 3600, &cfg))
+            .collect();
+        assert!(spread.len() > 1);
+
+        // 提前量不超过有效期的一半
+        let huge = TokenRefreshConfig {
+            margin_secs: 7200,
+            ..cfg.clone()
+        };
+        assert_eq!(refresh_due_at("acc-1", expiry, 3600, &huge), expiry - 1800);
+
+        let no_jitter = TokenRefreshConfig {
+            jitter_secs: 0,
+            ..cfg
+        };
+        assert_eq!(
+            refresh_due_at("acc-1", expiry, 3600, &no_jitter),
+            expiry - 600
+        );
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let cfg = config();
+        let expiry = 10_000;
+        let now = expiry - 100;
+        assert!(is_due("a", expiry, 3600, None, &cfg, now));
+        assert!(!is_due("a", expiry, 3600, None, &cfg, expiry - 1000));
+
+        let first = RetryState::after_failure(None, expiry, &cfg, now);
+        assert_eq!((first.attempts, first.next_attempt_at), (1, now + 30));
+        assert!(!is_due("a", expiry, 3600, Some(&first), &cfg, now + 29));
+        assert!(is_due("a", expiry, 3600, Some(&first), &cfg, now + 30));
+
+        let second = RetryState::after_failure(Some(first), expiry, &cfg, now + 30);
+        assert_eq!((second.attempts, second.next_attempt_at), (2, now + 90));
+        let third = RetryState::after_failure(Some(second), expiry, &cfg, now + 90);
+        assert!(third.exhausted(&cfg));
+        assert!(!is_due("a", expiry, 3600, Some(&third), &cfg, now + 10_000));
+
+        // Token 更新后旧的重试状态不再生效
+        assert!(is_due(
+            "a",
+            expiry + 3600,
+            3600,
+            Some(&third),
+            &cfg,
+            expiry + 3600
+        ));
+        let fresh = RetryState::after_failure(Some(third), expiry + 3600, &cfg, now);
+        assert_eq!(fresh.attempts, 1);
+    }
+}
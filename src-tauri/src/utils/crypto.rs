@@ -1,16 +1,22 @@
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
 use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Deserializer, Serializer};
 use sha2::Digest;
 
+use crate::utils::vault;
+
+/// 旧版 (v1) 固定 nonce，仅用于解密历史数据
 const FIXED_NONCE: &[u8; 12] = b"antigravsalt";
-const ENCRYPTED_PREFIX: &str = "ag_enc_";
+pub const ENCRYPTED_PREFIX: &str = "ag_enc_";
+/// [NEW] v2 密文: `ag_enc_v2:<kid>:<base64(nonce || ciphertext)>`，kid 作为 AAD 参与认证
+const V2_PREFIX: &str = "ag_enc_v2:";
+const NONCE_LEN: usize = 12;
 
-/// 生成加密密钥 (基于设备 ID)
-fn get_encryption_key() -> [u8; 32] {
+/// 旧版密钥 (设备 ID 的 SHA-256)，仅用于解密历史数据
+fn get_legacy_encryption_key() -> [u8; 32] {
     // 使用设备唯一标识生成密钥
     let device_id = machine_uid::get().unwrap_or_else(|_| "default".to_string());
     let mut key = [0u8; 32];
@@ -43,9 +49,8 @@ where
 
     // [FIX #1738] 检查魔术前缀
     if raw.starts_with(ENCRYPTED_PREFIX) {
-        // 新版格式：去前缀后解密
-        let ciphertext = &raw[ENCRYPTED_PREFIX.len()..];
-        match decrypt_string_internal(ciphertext) {
+        // 新版格式 (v1 / v2)
+        match decrypt_string(&raw) {
             Ok(plaintext) => Ok(plaintext),
             Err(_) => {
                 // 解密失败（如密钥变更），返回原始密文以防止数据丢失
@@ -70,25 +75,61 @@ where
 }
 
 pub fn encrypt_string(password: &str) -> Result<String, String> {
-    let key = get_encryption_key();
-    let cipher = Aes256Gcm::new(&key.into());
-    // In production, we should use a random nonce and prepend it to the ciphertext
-    // For simplicity in this demo, we use a fixed nonce (NOT SECURE for repeats)
-    // improving security: use random nonce
-    let nonce = Nonce::from_slice(FIXED_NONCE);
+    let (kid, key) = vault::current_key()?;
+    encrypt_with_key(password, &kid, &key)
+}
 
+/// 使用指定密钥加密为 v2 格式 (每条密文使用随机 nonce)
+fn encrypt_with_key(plaintext: &str, kid: &str, key: &[u8; 32]) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce_bytes: [u8; NONCE_LEN] = rand::random();
     let ciphertext = cipher
-        .encrypt(nonce, password.as_bytes())
+        .encrypt(
+            Nonce::from_slice(&nonce_bytes),
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad: kid.as_bytes(),
+            },
+        )
         .map_err(|e| format!("Encryption failed: {}", e))?;
 
-    let base64_ciphertext = general_purpose::STANDARD.encode(ciphertext);
-    // [FIX #1738] 添加魔术前缀
-    Ok(format!("{}{}", ENCRYPTED_PREFIX, base64_ciphertext))
+    let mut blob = nonce_bytes.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(format!(
+        "{}{}:{}",
+        V2_PREFIX,
+        kid,
+        general_purpose::STANDARD.encode(blob)
+    ))
 }
 
-/// 内部解密函数 (输入必须是纯 Base64 密文，不含前缀)
+fn decrypt_v2(body: &str) -> Result<String, String> {
+    let (kid, encoded) = body
+        .split_once(':')
+        .ok_or_else(|| "Malformed vault ciphertext".to_string())?;
+    let key = vault::key_for(kid).ok_or_else(|| format!("Unknown vault key {}", kid))?;
+    let blob = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Base64 decode failed: {}", e))?;
+    if blob.len() <= NONCE_LEN {
+        return Err("Vault ciphertext too short".to_string());
+    }
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    let plaintext = Aes256Gcm::new(&key.into())
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: kid.as_bytes(),
+            },
+        )
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+    String::from_utf8(plaintext).map_err(|e| format!("UTF-8 conversion failed: {}", e))
+}
+
+/// 内部解密函数 (旧版 v1 格式: 纯 Base64 密文，不含前缀)
 fn decrypt_string_internal(encrypted_base64: &str) -> Result<String, String> {
-    let key = get_encryption_key();
+    let key = get_legacy_encryption_key();
     let cipher = Aes256Gcm::new(&key.into());
     let nonce = Nonce::from_slice(FIXED_NONCE);
 
@@ -104,49 +145,157 @@ fn decrypt_string_internal(encrypted_base64: &str) -> Result<String, String> {
 }
 
 pub fn decrypt_string(encrypted: &str) -> Result<String, String> {
-    if encrypted.starts_with(ENCRYPTED_PREFIX) {
-        decrypt_string_internal(&encrypted[ENCRYPTED_PREFIX.len()..])
+    if let Some(body) = encrypted.strip_prefix(V2_PREFIX) {
+        decrypt_v2(body)
+    } else if let Some(body) = encrypted.strip_prefix(ENCRYPTED_PREFIX) {
+        decrypt_string_internal(body)
     } else {
         decrypt_string_internal(encrypted)
     }
 }
 
-/// Encrypt file content for storage. On error, returns original plaintext (safe fallback).
-pub fn encrypt_file_content(plaintext: &str) -> String {
-    match encrypt_string(plaintext) {
-        Ok(encrypted) => encrypted,
+/// 生成旧版 (v1, 固定 nonce) 密文，仅供迁移测试
+#[cfg(test)]
+pub(crate) fn encrypt_legacy(plaintext: &str) -> String {
+    let cipher = Aes256Gcm::new(&get_legacy_encryption_key().into());
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(FIXED_NONCE), plaintext.as_bytes())
+        .expect("legacy encryption");
+    format!("{}{}", ENCRYPTED_PREFIX, general_purpose::STANDARD.encode(ciphertext))
+}
+
+/// 是否为当前密钥加密的 v2 密文 (密钥轮换 / 旧版迁移时跳过)
+pub fn is_current_ciphertext(value: &str) -> bool {
+    let Some(body) = value.strip_prefix(V2_PREFIX) else {
+        return false;
+    };
+    let kid = body.split(':').next().unwrap_or_default();
+    vault::current_kid().is_some_and(|current| current == kid)
+}
+
+/// Encrypt file content for storage.
+/// Fails instead of writing plaintext when the vault key is unavailable (e.g. missing passphrase).
+pub fn encrypt_file_content(plaintext: &str) -> Result<String, String> {
+    encrypt_string(plaintext).map_err(|e| {
+        tracing::error!("File encryption failed: {}", e);
+        e
+    })
+}
+
+/// Decrypt file content from storage. On error, returns original raw content (backward compat).
+pub fn decrypt_file_content(raw: &str) -> String {
+    match decrypt_file_content_strict(raw) {
+        Ok(plaintext) => plaintext,
         Err(e) => {
-            tracing::warn!("File encryption failed, saving as plaintext: {}", e);
-            plaintext.to_string()
+            tracing::warn!("File decryption failed, returning raw content: {}", e);
+            raw.to_string()
         }
     }
 }
 
-/// Decrypt file content from storage. On error, returns original raw content (backward compat).
-pub fn decrypt_file_content(raw: &str) -> String {
+/// Decrypt file content from storage. Plaintext (old files) passes through unchanged,
+/// but an encrypted envelope that cannot be decrypted is reported as an error.
+pub fn decrypt_file_content_strict(raw: &str) -> Result<String, String> {
     let to_decrypt = raw.trim();
-    if to_decrypt.is_empty() {
-        return raw.to_string();
-    }
     // Only attempt decryption if it looks like our encrypted format
     if to_decrypt.starts_with(ENCRYPTED_PREFIX) {
-        match decrypt_string(to_decrypt) {
-            Ok(plaintext) => plaintext,
-            Err(e) => {
-                tracing::warn!("File decryption failed, returning raw content: {}", e);
-                raw.to_string()
-            }
-        }
+        decrypt_string(to_decrypt)
     } else {
         // Not encrypted (old file) — return as-is for backward compatibility
-        raw.to_string()
+        Ok(raw.to_string())
     }
 }
 
+/// Whether stored file content is already wrapped in an encrypted envelope
+pub fn is_encrypted_content(raw: &str) -> bool {
+    raw.trim().starts_with(ENCRYPTED_PREFIX)
+}
+
+// ============================================================================
+// 管理员密码哈希 (Argon2id, 加盐 + 内存困难)
+// ============================================================================
+
+const PASSWORD_HASH_PREFIX: &str = "$argon2";
+
+/// 最近一次验证成功的 (哈希 -> 明文摘要)，避免每个管理请求都重新计算 Argon2
+static VERIFIED_PASSWORDS: once_cell::sync::Lazy<
+    parking_lot::Mutex<std::collections::HashMap<String, [u8; 32]>>,
+> = once_cell::sync::Lazy::new(|| parking_lot::Mutex::new(std::collections::HashMap::new()));
+
+/// 是否为 PHC 格式的 Argon2 哈希
+pub fn is_password_hash(value: &str) -> bool {
+    value.starts_with(PASSWORD_HASH_PREFIX)
+}
+
+/// 使用随机盐生成 Argon2id 哈希 (PHC 字符串)
+pub fn hash_password(password: &str) -> Result<String, String> {
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    argon2::Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| format!("Password hashing failed: {}", e))
+}
+
+/// 校验密码；stored 可为 Argon2 哈希或旧版明文
+pub fn verify_password(candidate: &str, stored: &str) -> bool {
+    if !is_password_hash(stored) {
+        return constant_time_eq(candidate.as_bytes(), stored.as_bytes());
+    }
+
+    let digest: [u8; 32] = sha2::Sha256::digest(candidate.as_bytes()).into();
+    if VERIFIED_PASSWORDS
+        .lock()
+        .get(stored)
+        .is_some_and(|cached| constant_time_eq(cached, &digest))
+    {
+        return true;
+    }
+
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+    let Ok(parsed) = PasswordHash::new(stored) else {
+        return false;
+    };
+    let ok = argon2::Argon2::default()
+        .verify_password(candidate.as_bytes(), &parsed)
+        .is_ok();
+    if ok {
+        let mut cache = VERIFIED_PASSWORDS.lock();
+        cache.clear();
+        cache.insert(stored.to_string(), digest);
+    }
+    ok
+}
+
+/// 常量时间比较，避免时序侧信道
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_password_hash_roundtrip() {
+        let hash = hash_password("admin123").unwrap();
+        assert!(is_password_hash(&hash));
+        assert_ne!(hash, hash_password("admin123").unwrap(), "salt must be random");
+
+        assert!(verify_password("admin123", &hash));
+        // 命中缓存
+        assert!(verify_password("admin123", &hash));
+        assert!(!verify_password("admin124", &hash));
+
+        // 旧版明文
+        assert!(verify_password("plain", "plain"));
+        assert!(!verify_password("plain", "plain2"));
+    }
+
     #[test]
     fn test_encrypt_decrypt_cycle() {
         let password = "my_secret_password";
@@ -163,7 +312,7 @@ mod tests {
     fn test_legacy_compatibility() {
         // 模拟旧版加密（手动调用内部逻辑生成无前缀密文）
         let password = "legacy_password";
-        let key = get_encryption_key();
+        let key = get_legacy_encryption_key();
         let cipher = Aes256Gcm::new(&key.into());
         let nonce = Nonce::from_slice(FIXED_NONCE);
         let ciphertext = cipher.encrypt(nonce, password.as_bytes()).unwrap();
@@ -174,5 +323,58 @@ mod tests {
         // 使用新版解密逻辑
         let decrypted = decrypt_string(&legacy_encrypted).unwrap();
         assert_eq!(password, decrypted);
+
+        // 带前缀的 v1 密文
+        let prefixed = format!("{}{}", ENCRYPTED_PREFIX, legacy_encrypted);
+        assert_eq!(decrypt_string(&prefixed).unwrap(), password);
+        assert!(!is_current_ciphertext(&prefixed));
+    }
+
+    #[test]
+    fn test_v2_random_nonce_and_key_binding() {
+        let key = [7u8; 32];
+        let a = encrypt_with_key("same secret", "ktest0001", &key).unwrap();
+        let b = encrypt_with_key("same secret", "ktest0001", &key).unwrap();
+        assert!(a.starts_with("ag_enc_v2:ktest0001:"));
+        assert_ne!(a, b, "nonce must be random per ciphertext");
+
+        // kid 参与认证: 篡改 kid 后无法用同一密钥解密
+        let cipher = Aes256Gcm::new(&key.into());
+        let blob = general_purpose::STANDARD
+            .decode(a.rsplit(':').next().unwrap())
+            .unwrap();
+        let (nonce, ct) = blob.split_at(NONCE_LEN);
+        let ok = cipher.decrypt(Nonce::from_slice(nonce), Payload { msg: ct, aad: b"ktest0001" });
+        assert_eq!(ok.unwrap(), b"same secret");
+        let tampered = cipher.decrypt(Nonce::from_slice(nonce), Payload { msg: ct, aad: b"kother001" });
+        assert!(tampered.is_err());
+
+        // 未知 kid
+        assert!(decrypt_string("ag_enc_v2:kmissing1:AAAA").is_err());
+    }
+
+    #[test]
+    fn test_new_ciphertexts_use_current_key() {
+        let encrypted = encrypt_string("rotate me").unwrap();
+        assert!(encrypted.starts_with(V2_PREFIX));
+        assert!(is_current_ciphertext(&encrypted));
+        assert_eq!(decrypt_file_content(&encrypted), "rotate me");
+        assert_eq!(encrypt_file_content("x").map(|c| decrypt_file_content(&c)).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_strict_file_decryption() {
+        let plain = r#"{"id":"a"}"#;
+        assert_eq!(decrypt_file_content_strict(plain).unwrap(), plain);
+        assert!(!is_encrypted_content(plain));
+
+        let encrypted = encrypt_file_content(plain).unwrap();
+        assert!(is_encrypted_content(&format!("{}\n", encrypted)));
+        assert_eq!(decrypt_file_content_strict(&encrypted).unwrap(), plain);
+
+        // 无法解密的密文必须报错，而不是原样交给 JSON 解析
+        let corrupted = format!("{}AAAA", &encrypted[..encrypted.len() - 4]);
+        assert!(decrypt_file_content_strict(&corrupted).is_err());
+        assert_eq!(decrypt_file_content(&corrupted), corrupted);
     }
 }
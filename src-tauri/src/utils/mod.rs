@@ -1,3 +1,4 @@
 pub mod http;
 pub mod protobuf;
 pub mod crypto;
+pub mod vault; // 密钥保险库 (密钥来源 / 轮换)
@@ -0,0 +1,611 @@
+//! 密钥保险库 (Vault)
+//! 管理加密密钥的来源、派生与轮换；密文格式见 `utils::crypto`。
+//!
+//! 密钥来源优先级: 用户口令 (环境变量) > 系统钥匙串中的随机密钥 > 设备 ID，
+//! 均经 Argon2id + 随机盐派生为 AES-256 密钥。密钥描述 (不含密钥本身) 保存在数据目录的 `vault.json`。
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use argon2::Argon2;
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub const VAULT_FILE: &str = "vault.json";
+/// 口令模式: 启动时从环境变量读取口令
+pub const PASSPHRASE_ENV: &str = "ABV_VAULT_PASSPHRASE";
+/// 口令模式: 从文件读取口令 (适用于 Docker secrets)
+pub const PASSPHRASE_FILE_ENV: &str = "ABV_VAULT_PASSPHRASE_FILE";
+/// 口令轮换未完成时，旧口令密钥 (retired) 的口令
+pub const OLD_PASSPHRASE_ENV: &str = "ABV_VAULT_OLD_PASSPHRASE";
+
+const VAULT_VERSION: u32 = 2;
+const KEYRING_SERVICE: &str = "antigravity-sw-vault";
+/// 校验值明文: 以派生密钥加密后写入 vault.json，用于识别错误口令 / 变化的设备 ID
+const KEY_CHECK_PLAINTEXT: &[u8] = b"antigravity-sw-vault-check";
+
+/// 密钥材料来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// 用户口令
+    Passphrase,
+    /// 系统钥匙串 (macOS Keychain / Windows Credential Manager / Secret Service)
+    Keyring,
+    /// 设备 ID (兜底)
+    Machine,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeySpec {
+    /// 密钥 ID，写入每条密文以便轮换期间选择解密密钥
+    kid: String,
+    source: KeySource,
+    /// KDF 盐 (base64)
+    salt: String,
+    created_at: i64,
+    /// 密钥校验值 (base64(nonce || 密文))，旧版文件缺失时在首次加载后补写
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    check: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    current: Option<KeySpec>,
+    /// 轮换未完成时保留的旧密钥 (完成后清除)
+    #[serde(default)]
+    retired: Vec<KeySpec>,
+}
+
+struct VaultState {
+    path: PathBuf,
+    file: VaultFile,
+    keys: HashMap<String, [u8; 32]>,
+    /// 本次初始化时新建了保险库 (需迁移旧版密文)
+    created: bool,
+}
+
+static STATE: Lazy<RwLock<Option<VaultState>>> = Lazy::new(|| RwLock::new(None));
+
+/// 保险库状态 (管理接口展示，不含密钥)
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStatus {
+    pub version: u32,
+    pub kid: String,
+    pub source: KeySource,
+    pub created_at: i64,
+    /// 尚未完成轮换的旧密钥数
+    pub retired_keys: usize,
+}
+
+fn vault_path() -> Result<PathBuf, String> {
+    Ok(crate::modules::account::get_data_dir()?.join(VAULT_FILE))
+}
+
+/// 启动时提供的口令 (环境变量优先于口令文件)
+fn env_passphrase() -> Option<String> {
+    if let Some(p) = std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty()) {
+        return Some(p);
+    }
+    let path = std::env::var(PASSPHRASE_FILE_ENV).ok()?;
+    std::fs::read_to_string(path.trim())
+        .ok()
+        .map(|s| s.trim_end_matches(['\r', '\n']).to_string())
+        .filter(|p| !p.is_empty())
+}
+
+fn old_env_passphrase() -> Option<String> {
+    std::env::var(OLD_PASSPHRASE_ENV).ok().filter(|p| !p.is_empty())
+}
+
+fn new_kid() -> String {
+    let bytes: [u8; 4] = rand::random();
+    format!("k{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+fn keyring_entry(kid: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, kid).map_err(|e| format!("Keyring unavailable: {}", e))
+}
+
+/// 读取 (或新建) 钥匙串中的随机密钥材料
+fn keyring_material(kid: &str, create: bool) -> Result<String, String> {
+    let entry = keyring_entry(kid)?;
+    match entry.get_password() {
+        Ok(secret) => Ok(secret),
+        Err(keyring::Error::NoEntry) if create => {
+            let secret = general_purpose::STANDARD.encode(rand::random::<[u8; 32]>());
+            entry
+                .set_password(&secret)
+                .map_err(|e| format!("Failed to store key in keyring: {}", e))?;
+            // 回读确认 (部分平台在无会话时静默失败)
+            match entry.get_password() {
+                Ok(stored) if stored == secret => Ok(secret),
+                _ => Err("Keyring did not persist the vault key".to_string()),
+            }
+        }
+        Err(e) => Err(format!("Failed to read key from keyring: {}", e)),
+    }
+}
+
+fn derive_key(
+    spec: &KeySpec,
+    passphrase: Option<&str>,
+    create: bool,
+) -> Result<[u8; 32], String> {
+    let material = match spec.source {
+        KeySource::Passphrase => passphrase
+            .map(str::to_string)
+            .ok_or_else(|| {
+                format!(
+                    "Vault is protected by a passphrase; set {} or {}",
+                    PASSPHRASE_ENV, PASSPHRASE_FILE_ENV
+                )
+            })?,
+        KeySource::Keyring => keyring_material(&spec.kid, create)?,
+        KeySource::Machine => machine_uid::get().unwrap_or_else(|_| "default".to_string()),
+    };
+    let salt = general_purpose::STANDARD
+        .decode(&spec.salt)
+        .map_err(|e| format!("Invalid vault salt: {}", e))?;
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(material.as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn new_spec(source: KeySource) -> KeySpec {
+    KeySpec {
+        kid: new_kid(),
+        source,
+        salt: general_purpose::STANDARD.encode(rand::random::<[u8; 16]>()),
+        created_at: chrono::Utc::now().timestamp(),
+        check: None,
+    }
+}
+
+/// 生成密钥校验值 (kid 作为 AAD)
+fn key_check(kid: &str, key: &[u8; 32]) -> Result<String, String> {
+    let nonce: [u8; 12] = rand::random();
+    let ciphertext = Aes256Gcm::new(key.into())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: KEY_CHECK_PLAINTEXT,
+                aad: kid.as_bytes(),
+            },
+        )
+        .map_err(|e| format!("Failed to create key check: {}", e))?;
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(general_purpose::STANDARD.encode(blob))
+}
+
+/// 校验派生密钥；无校验值 (旧版文件) 时视为通过
+fn key_matches(spec: &KeySpec, key: &[u8; 32]) -> bool {
+    let Some(check) = spec.check.as_deref() else {
+        return true;
+    };
+    let Ok(blob) = general_purpose::STANDARD.decode(check) else {
+        return false;
+    };
+    if blob.len() <= 12 {
+        return false;
+    }
+    let (nonce, ciphertext) = blob.split_at(12);
+    Aes256Gcm::new(key.into())
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: spec.kid.as_bytes(),
+            },
+        )
+        .map(|plain| plain == KEY_CHECK_PLAINTEXT)
+        .unwrap_or(false)
+}
+
+/// 解锁已有密钥: 依次尝试候选口令，派生结果须与校验值一致
+fn unlock_key(spec: &KeySpec, passphrases: &[Option<&str>]) -> Result<[u8; 32], String> {
+    if spec.source != KeySource::Passphrase {
+        let key = derive_key(spec, None, false)?;
+        if !key_matches(spec, &key) {
+            return Err(format!(
+                "Vault key {} ({:?}) does not match its check value",
+                spec.kid, spec.source
+            ));
+        }
+        return Ok(key);
+    }
+    let mut candidates = passphrases.iter().flatten().peekable();
+    if candidates.peek().is_none() {
+        return derive_key(spec, None, false);
+    }
+    for passphrase in candidates {
+        let key = derive_key(spec, Some(passphrase), false)?;
+        if key_matches(spec, &key) {
+            return Ok(key);
+        }
+    }
+    Err(format!("Wrong vault passphrase for key {}", spec.kid))
+}
+
+/// 新建密钥: 未指定来源时按 口令 > 钥匙串 > 设备 ID 选择可用来源
+fn create_key(
+    source: Option<KeySource>,
+    passphrase: Option<&str>,
+) -> Result<(KeySpec, [u8; 32]), String> {
+    let candidates = match source {
+        Some(source) => vec![source],
+        None if passphrase.is_some() => vec![KeySource::Passphrase],
+        None => vec![KeySource::Keyring, KeySource::Machine],
+    };
+    let mut last_err = String::new();
+    for source in candidates {
+        let mut spec = new_spec(source);
+        match derive_key(&spec, passphrase, true) {
+            Ok(key) => {
+                spec.check = Some(key_check(&spec.kid, &key)?);
+                return Ok((spec, key));
+            }
+            Err(e) => {
+                tracing::warn!("[Vault] Key source {:?} unavailable: {}", source, e);
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
+
+fn save_file(path: &PathBuf, file: &VaultFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(file).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write vault file: {}", e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to write vault file: {}", e))
+}
+
+fn load_state() -> Result<VaultState, String> {
+    load_state_from(vault_path()?, env_passphrase(), old_env_passphrase())
+}
+
+/// `passphrase` 解锁当前密钥；轮换未完成时 retired 口令密钥可能使用旧口令 (`old_passphrase`)
+fn load_state_from(
+    path: PathBuf,
+    passphrase: Option<String>,
+    old_passphrase: Option<String>,
+) -> Result<VaultState, String> {
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(content) => Some(
+            serde_json::from_str::<VaultFile>(&content)
+                .map_err(|e| format!("Corrupted vault file {}: {}", path.display(), e))?,
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("Failed to read vault file: {}", e)),
+    };
+
+    if let Some(mut file) = existing.filter(|f| f.current.is_some()) {
+        let mut keys = HashMap::new();
+        let mut backfilled = false;
+        let current = file.current.as_mut().expect("checked above");
+        let key = unlock_key(current, &[passphrase.as_deref()])?;
+        if current.check.is_none() {
+            current.check = Some(key_check(&current.kid, &key)?);
+            backfilled = true;
+        }
+        keys.insert(current.kid.clone(), key);
+        for spec in file.retired.iter_mut() {
+            match unlock_key(spec, &[passphrase.as_deref(), old_passphrase.as_deref()]) {
+                Ok(key) => {
+                    if spec.check.is_none() {
+                        spec.check = Some(key_check(&spec.kid, &key)?);
+                        backfilled = true;
+                    }
+                    keys.insert(spec.kid.clone(), key);
+                }
+                Err(e) => tracing::warn!("[Vault] Retired key {} unavailable: {}", spec.kid, e),
+            }
+        }
+        if backfilled {
+            save_file(&path, &file)?;
+        }
+        return Ok(VaultState {
+            path,
+            file,
+            keys,
+            created: false,
+        });
+    }
+
+    let (spec, key) = create_key(None, passphrase.as_deref())?;
+    tracing::info!("[Vault] Created vault key {} ({:?})", spec.kid, spec.source);
+    let file = VaultFile {
+        version: VAULT_VERSION,
+        current: Some(spec.clone()),
+        retired: Vec::new(),
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    save_file(&path, &file)?;
+    Ok(VaultState {
+        path,
+        file,
+        keys: HashMap::from([(spec.kid, key)]),
+        created: true,
+    })
+}
+
+/// 初始化保险库；返回 true 表示本次新建 (调用方应迁移旧版密文)
+pub fn init() -> Result<bool, String> {
+    let mut state = STATE.write();
+    if let Some(state) = state.as_ref() {
+        return Ok(state.created);
+    }
+    let loaded = load_state()?;
+    let created = loaded.created;
+    *state = Some(loaded);
+    Ok(created)
+}
+
+fn with_state<T>(f: impl FnOnce(&VaultState) -> T) -> Result<T, String> {
+    if STATE.read().is_none() {
+        init()?;
+    }
+    let state = STATE.read();
+    state
+        .as_ref()
+        .map(f)
+        .ok_or_else(|| "Vault is not initialized".to_string())
+}
+
+/// 当前加密密钥 (kid, key)
+pub fn current_key() -> Result<(String, [u8; 32]), String> {
+    with_state(|state| {
+        let kid = state.file.current.as_ref().map(|s| s.kid.clone())?;
+        state.keys.get(&kid).map(|key| (kid, *key))
+    })?
+    .ok_or_else(|| "Vault key is unavailable".to_string())
+}
+
+/// 按 kid 查找解密密钥 (当前或轮换中的旧密钥)
+pub fn key_for(kid: &str) -> Option<[u8; 32]> {
+    with_state(|state| state.keys.get(kid).copied()).ok().flatten()
+}
+
+pub fn current_kid() -> Option<String> {
+    with_state(|state| state.file.current.as_ref().map(|s| s.kid.clone()))
+        .ok()
+        .flatten()
+}
+
+/// 尚未丢弃的旧口令密钥 (轮换中断时重启需要通过 `OLD_PASSPHRASE_ENV` 提供其口令)
+pub fn retired_passphrase_kid() -> Option<String> {
+    with_state(|state| {
+        state
+            .file
+            .retired
+            .iter()
+            .find(|spec| spec.source == KeySource::Passphrase)
+            .map(|spec| spec.kid.clone())
+    })
+    .ok()
+    .flatten()
+}
+
+pub fn status() -> Result<VaultStatus, String> {
+    with_state(|state| {
+        state.file.current.as_ref().map(|spec| VaultStatus {
+            version: state.file.version,
+            kid: spec.kid.clone(),
+            source: spec.source,
+            created_at: spec.created_at,
+            retired_keys: state.file.retired.len(),
+        })
+    })?
+    .ok_or_else(|| "Vault is not initialized".to_string())
+}
+
+/// 开始轮换: 生成新密钥并设为当前密钥，旧密钥保留为 retired 直至 `finish_rotation`
+/// 口令模式下 `passphrase` 为新口令；旧密钥已在启动时以旧口令解锁，保留在内存中用于重新加密
+pub fn begin_rotation(source: KeySource, passphrase: Option<&str>) -> Result<String, String> {
+    with_state(|_| ())?;
+    let mut guard = STATE.write();
+    let state = guard.as_mut().ok_or("Vault is not initialized")?;
+    rotate_state(state, source, passphrase)
+}
+
+fn rotate_state(
+    state: &mut VaultState,
+    source: KeySource,
+    passphrase: Option<&str>,
+) -> Result<String, String> {
+    if source == KeySource::Passphrase && passphrase.unwrap_or("").is_empty() {
+        return Err("Passphrase is required for passphrase mode".to_string());
+    }
+    let (spec, key) = create_key(Some(source), passphrase)?;
+
+    let mut file = state.file.clone();
+    if let Some(previous) = file.current.take() {
+        file.retired.push(previous);
+    }
+    file.current = Some(spec.clone());
+    file.version = VAULT_VERSION;
+    save_file(&state.path, &file)?;
+
+    state.file = file;
+    state.keys.insert(spec.kid.clone(), key);
+    tracing::info!("[Vault] Rotated to key {} ({:?})", spec.kid, source);
+    Ok(spec.kid)
+}
+
+/// 完成轮换: 所有数据已使用新密钥重新加密，丢弃旧密钥
+/// 钥匙串中的旧密钥材料不删除，旧备份 (含旧 vault.json) 恢复后仍可解密
+pub fn finish_rotation() -> Result<(), String> {
+    let mut guard = STATE.write();
+    let state = guard.as_mut().ok_or("Vault is not initialized")?;
+    finish_state(state)
+}
+
+fn finish_state(state: &mut VaultState) -> Result<(), String> {
+    let retired = std::mem::take(&mut state.file.retired);
+    if retired.is_empty() {
+        return Ok(());
+    }
+    save_file(&state.path, &state.file)?;
+    for spec in retired {
+        state.keys.remove(&spec.kid);
+        if spec.source == KeySource::Keyring {
+            tracing::info!(
+                "[Vault] Retired key {} kept in keyring service {} for recovery",
+                spec.kid,
+                KEYRING_SERVICE
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_key_is_deterministic_per_salt() {
+        let spec = new_spec(KeySource::Passphrase);
+        let a = derive_key(&spec, Some("correct horse"), false).unwrap();
+        let b = derive_key(&spec, Some("correct horse"), false).unwrap();
+        let c = derive_key(&spec, Some("battery staple"), false).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let other_salt = new_spec(KeySource::Passphrase);
+        assert_ne!(a, derive_key(&other_salt, Some("correct horse"), false).unwrap());
+        assert!(derive_key(&spec, None, false).is_err());
+    }
+
+    #[test]
+    fn test_machine_key_source() {
+        let spec = new_spec(KeySource::Machine);
+        assert_eq!(
+            derive_key(&spec, None, false).unwrap(),
+            derive_key(&spec, None, false).unwrap()
+        );
+        assert!(spec.kid.starts_with('k') && spec.kid.len() == 9);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let dir = std::env::temp_dir().join(format!("abv-vault-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(VAULT_FILE);
+
+        let created =
+            load_state_from(path.clone(), Some("correct horse".to_string()), None).unwrap();
+        assert!(created.file.current.as_ref().unwrap().check.is_some());
+
+        let err = load_state_from(path.clone(), Some("battery staple".to_string()), None)
+            .err()
+            .unwrap();
+        assert!(err.contains("Wrong vault passphrase"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_passphrase_to_passphrase_rotation() {
+        let dir = std::env::temp_dir().join(format!("abv-vault-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(VAULT_FILE);
+
+        // 以旧口令启动，轮换到新口令
+        let mut state =
+            load_state_from(path.clone(), Some("old passphrase".to_string()), None).unwrap();
+        let old_kid = state.file.current.clone().unwrap().kid;
+        let old_key = state.keys[&old_kid];
+        let new_kid =
+            rotate_state(&mut state, KeySource::Passphrase, Some("new passphrase")).unwrap();
+        let new_key = state.keys[&new_kid];
+        assert_ne!(old_key, new_key);
+
+        // 重新加密中断后重启: 新口令解锁当前密钥，旧口令密钥需单独提供
+        let restarted =
+            load_state_from(path.clone(), Some("new passphrase".to_string()), None).unwrap();
+        assert_eq!(restarted.keys.get(&new_kid), Some(&new_key));
+        assert!(!restarted.keys.contains_key(&old_kid));
+        let restarted = load_state_from(
+            path.clone(),
+            Some("new passphrase".to_string()),
+            Some("old passphrase".to_string()),
+        )
+        .unwrap();
+        assert_eq!(restarted.keys.get(&old_kid), Some(&old_key));
+
+        finish_state(&mut state).unwrap();
+        let finished =
+            load_state_from(path.clone(), Some("new passphrase".to_string()), None).unwrap();
+        assert!(finished.file.retired.is_empty());
+        assert_eq!(finished.keys.get(&new_kid), Some(&new_key));
+        assert!(load_state_from(path.clone(), Some("old passphrase".to_string()), None).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_passphrase_vault_needs_passphrase_on_reload() {
+        let dir = std::env::temp_dir().join(format!("abv-vault-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(VAULT_FILE);
+
+        let created =
+            load_state_from(path.clone(), Some("correct horse".to_string()), None).unwrap();
+        assert!(created.created);
+        let spec = created.file.current.clone().unwrap();
+        assert_eq!(spec.source, KeySource::Passphrase);
+
+        let reloaded =
+            load_state_from(path.clone(), Some("correct horse".to_string()), None).unwrap();
+        assert!(!reloaded.created);
+        assert_eq!(reloaded.keys.get(&spec.kid), created.keys.get(&spec.kid));
+
+        // 口令未提供时拒绝启动，而不是悄悄新建密钥覆盖
+        assert!(load_state_from(path.clone(), None, None).is_err());
+        let on_disk: VaultFile =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(on_disk.current.unwrap().kid, spec.kid);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unavailable_retired_key_does_not_block_startup() {
+        let dir = std::env::temp_dir().join(format!("abv-vault-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(VAULT_FILE);
+
+        // 轮换中断: 当前为设备密钥，旧口令密钥仍在 retired 中
+        let current = new_spec(KeySource::Machine);
+        let retired = new_spec(KeySource::Passphrase);
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            current: Some(current.clone()),
+            retired: vec![retired.clone()],
+        };
+        save_file(&path, &file).unwrap();
+
+        let without = load_state_from(path.clone(), None, None).unwrap();
+        assert!(without.keys.contains_key(&current.kid));
+        assert!(!without.keys.contains_key(&retired.kid));
+        assert_eq!(without.file.retired.len(), 1);
+
+        let with = load_state_from(path.clone(), Some("correct horse".to_string()), None).unwrap();
+        assert!(with.keys.contains_key(&retired.kid));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -11,11 +11,21 @@ interface IpRanking {
     is_blocked: boolean;
 }
 
+interface GeoRanking {
+    key: string;
+    label?: string | null;
+    request_count: number;
+    unique_ips: number;
+    blocked_count: number;
+}
+
 interface IpStatsResponse {
     total_requests: number;
     unique_ips: number;
     blocked_requests: number;
     top_ips: IpRanking[];
+    top_countries?: GeoRanking[];
+    top_asns?: GeoRanking[];
 }
 
 interface IpTokenStats {
@@ -78,6 +88,38 @@ export const IpStatistics: React.FC<Props> = ({ refreshKey }) => {
 
     const maxReqCount = Math.max(...tokenStats.map(ip => ip.request_count), 1);
 
+    const renderGeoTable = (title: string, rows: GeoRanking[]) => (
+        <div className="bg-white dark:bg-base-200 rounded-xl shadow-sm border border-gray-100 dark:border-base-300 overflow-hidden">
+            <div className="p-4 border-b border-gray-100 dark:border-base-300 flex items-center gap-2">
+                <Globe size={18} className="text-blue-500" />
+                <h3 className="font-bold">{title}</h3>
+            </div>
+            <table className="table table-sm w-full">
+                <tbody>
+                    {rows.map((row, index) => (
+                        <tr key={row.key} className="hover:bg-gray-50 dark:hover:bg-base-300">
+                            <td className="w-10 font-bold text-gray-400">#{index + 1}</td>
+                            <td className="font-mono font-medium">
+                                {row.key}
+                                {row.label && <span className="ml-2 text-xs text-gray-500 font-sans">{row.label}</span>}
+                            </td>
+                            <td className="text-right font-mono">{formatCompactNumber(row.request_count)}</td>
+                            <td className="text-right font-mono text-xs text-gray-500">{formatCompactNumber(row.unique_ips)} IPs</td>
+                            <td className="text-right font-mono text-xs text-red-500">{row.blocked_count > 0 ? formatCompactNumber(row.blocked_count) : '-'}</td>
+                        </tr>
+                    ))}
+                    {rows.length === 0 && (
+                        <tr>
+                            <td colSpan={5} className="text-center py-6 text-gray-500">
+                                {t('security.stats.no_geo_data', 'No GeoIP data (configure a .mmdb database)')}
+                            </td>
+                        </tr>
+                    )}
+                </tbody>
+            </table>
+        </div>
+    );
+
     return (
         <div className="h-full flex flex-col overflow-hidden">
             <div className="flex-1 overflow-y-scroll p-6 space-y-6">
@@ -112,6 +154,12 @@ export const IpStatistics: React.FC<Props> = ({ refreshKey }) => {
                     </div>
                 </div>
 
+                {/* Top Countries / ASNs (GeoIP) */}
+                <div className="grid grid-cols-1 md:grid-cols-2 gap-6">
+                    {renderGeoTable(t('security.stats.top_countries', 'Top Countries (24h)'), stats.top_countries || [])}
+                    {renderGeoTable(t('security.stats.top_asns', 'Top ASNs (24h)'), stats.top_asns || [])}
+                </div>
+
                 <div className="w-full">
                     {/* Combined IP Stats */}
                     <div className="bg-white dark:bg-base-200 rounded-xl shadow-sm border border-gray-100 dark:border-base-300 overflow-hidden">
@@ -178,6 +178,7 @@ export default function ApiProxy() {
 
     const [isEditingAdminPassword, setIsEditingAdminPassword] = useState(false);
     const [tempAdminPassword, setTempAdminPassword] = useState('');
+    const [adminPasswordDirty, setAdminPasswordDirty] = useState(false);
 
     // Preset selection state
     const [selectedPreset, setSelectedPreset] = useState<string>('default');
@@ -928,24 +929,33 @@ export default function ApiProxy() {
     };
 
     // Admin Password editing functions
+    // 配置中保存的是 Argon2 哈希，只允许设置新密码，不回显也不回传
     const handleEditAdminPassword = () => {
-        setTempAdminPassword(appConfig?.proxy.admin_password || '');
+        setTempAdminPassword('');
+        setAdminPasswordDirty(false);
         setIsEditingAdminPassword(true);
     };
 
     const handleSaveAdminPassword = () => {
+        if (!adminPasswordDirty) {
+            setIsEditingAdminPassword(false);
+            return;
+        }
         // Validation: can be empty (meaning fallback to api_key) or at least 4 chars
         if (tempAdminPassword && tempAdminPassword.length < 4) {
             showToast(t('proxy.config.admin_password_short', { defaultValue: 'Password is too short (min 4 chars)' }), 'error');
             return;
         }
         updateProxyConfig({ admin_password: tempAdminPassword || undefined });
+        setTempAdminPassword('');
+        setAdminPasswordDirty(false);
         setIsEditingAdminPassword(false);
         showToast(t('proxy.config.admin_password_updated', { defaultValue: 'Web UI password updated' }), 'success');
     };
 
     const handleCancelEditAdminPassword = () => {
         setTempAdminPassword('');
+        setAdminPasswordDirty(false);
         setIsEditingAdminPassword(false);
     };
 
@@ -1395,9 +1405,18 @@ print(response.choices[0].message.content)`;
                                 </label>
                                 <div className="flex gap-2">
                                     <input
-                                        type="text"
-                                        value={isEditingAdminPassword ? tempAdminPassword : (appConfig.proxy.admin_password || t('proxy.config.admin_password_default', { defaultValue: '(Same as API Key)' }))}
-                                        onChange={(e) => isEditingAdminPassword && setTempAdminPassword(e.target.value)}
+                                        type={isEditingAdminPassword ? 'password' : 'text'}
+                                        value={isEditingAdminPassword
+                                            ? tempAdminPassword
+                                            : (appConfig.proxy.admin_password
+                                                ? t('proxy.config.admin_password_set', { defaultValue: '•••••••• (Set)' })
+                                                : t('proxy.config.admin_password_default', { defaultValue: '(Same as API Key)' }))}
+                                        onChange={(e) => {
+                                            if (!isEditingAdminPassword) return;
+                                            setTempAdminPassword(e.target.value);
+                                            setAdminPasswordDirty(true);
+                                        }}
+                                        autoComplete="new-password"
                                         readOnly={!isEditingAdminPassword}
                                         placeholder={t('proxy.config.admin_password_placeholder', { defaultValue: 'Enter new password or leave empty to use API Key' })}
                                         className={`flex-1 px-2.5 py-1.5 border border-gray-300 dark:border-base-200 rounded-lg text-xs font-mono ${isEditingAdminPassword
@@ -1431,17 +1450,6 @@ print(response.choices[0].message.content)`;
                                             >
                                                 <Edit2 size={14} />
                                             </button>
-                                            <button
-                                                onClick={() => copyToClipboardHandler(appConfig.proxy.admin_password || appConfig.proxy.api_key, 'admin_password')}
-                                                className="px-2.5 py-1.5 border border-gray-300 dark:border-base-200 rounded-lg bg-white dark:bg-base-200 hover:bg-gray-50 dark:hover:bg-base-300 transition-colors"
-                                                title={t('proxy.config.btn_copy')}
-                                            >
-                                                {copied === 'admin_password' ? (
-                                                    <CheckCircle size={14} className="text-green-500" />
-                                                ) : (
-                                                    <Copy size={14} />
-                                                )}
-                                            </button>
                                         </>
                                     )}
                                 </div>
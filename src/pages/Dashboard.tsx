@@ -131,9 +131,14 @@ function Dashboard() {
                 return;
             }
 
+            // 导出文件包含明文 refresh_token，需用户显式确认
+            if (!confirm(t('dashboard.export_plaintext_confirm', 'The export file will contain plaintext refresh tokens. Anyone with this file can use these accounts. Continue?'))) {
+                return;
+            }
+
             // Get export data from API (contains refresh_token)
             const accountIds = accountsToExport.map(acc => acc.id);
-            const response = await exportAccounts(accountIds);
+            const response = await exportAccounts(accountIds, true);
 
             if (!response.accounts || response.accounts.length === 0) {
                 showToast(t('dashboard.toast.export_no_accounts'), 'warning');
@@ -1,5 +1,19 @@
 import i18n from '../i18n';
-import { Account, DeviceProfile, DeviceProfileVersion, QuotaData } from '../types/account';
+import {
+    Account,
+    AccountEventPage,
+    AccountEventQuery,
+    AccountPage,
+    AccountQuery,
+    AccountTagCount,
+    BulkAccountReport,
+    BulkAccountRequest,
+    DeviceProfile,
+    DeviceProfileVersion,
+    ProviderAccountRequest,
+    ProviderConfigInput,
+    QuotaData,
+} from '../types/account';
 import { request as invoke } from '../utils/request';
 
 // 检查环境 (可选)
@@ -186,8 +200,8 @@ export interface ExportAccountsResponse {
     accounts: ExportAccountItem[];
 }
 
-export async function exportAccounts(accountIds: string[]): Promise<ExportAccountsResponse> {
-    return await invoke('export_accounts', { accountIds });
+export async function exportAccounts(accountIds: string[], confirmPlaintext = false): Promise<ExportAccountsResponse> {
+    return await invoke('export_accounts', { accountIds, confirmPlaintext });
 }
 
 // 自定义标签相关
@@ -195,3 +209,34 @@ export async function updateAccountLabel(accountId: string, label: string): Prom
     return await invoke('update_account_label', { accountId, label });
 }
 
+
+// [NEW] 账号标签、筛选查询与批量操作
+export async function updateAccountTags(accountId: string, tags: string[]): Promise<string[]> {
+    return await invoke('update_account_tags', { accountId, tags });
+}
+
+export async function listAccountTags(): Promise<AccountTagCount[]> {
+    return await invoke('list_account_tags');
+}
+
+export async function queryAccounts(query: AccountQuery): Promise<AccountPage> {
+    return await invoke('query_accounts', { ...query });
+}
+
+export async function bulkAccountAction(request: BulkAccountRequest): Promise<BulkAccountReport> {
+    return await invoke('bulk_account_action', { request });
+}
+
+// [NEW] 账号生命周期事件 (不传 accountId 时返回全局事件流)
+export async function listAccountEvents(query: AccountEventQuery = {}): Promise<AccountEventPage> {
+    return await invoke('list_account_events', { ...query });
+}
+
+// [NEW] 通用上游 Provider 账号 (OpenAI / Anthropic 兼容端点、自建 vLLM 等)
+export async function addProviderAccount(request: ProviderAccountRequest): Promise<Account> {
+    return await invoke('add_provider_account', { request });
+}
+
+export async function updateProviderAccount(accountId: string, request: ProviderConfigInput): Promise<Account> {
+    return await invoke('update_provider_account', { accountId, request });
+}
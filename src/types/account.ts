@@ -14,9 +14,11 @@ export interface Account {
     proxy_disabled_at?: number;
     protected_models?: string[];
     custom_label?: string;  // 用户自定义标签
+    tags?: string[];  // [NEW] 任意标签，如 owner:alice / region:eu
     account_type?: string;  // e.g. "anthropic"
     anthropic_auth_token?: string;  // only for anthropic accounts
     anthropic_base_url?: string;    // only for anthropic accounts
+    provider?: ProviderConfig;  // [NEW] only for account_type "provider"
     validation_blocked?: boolean;
     validation_blocked_until?: number;
     validation_blocked_reason?: string;
@@ -63,3 +65,123 @@ export interface DeviceProfileVersion {
     is_current?: boolean;
 }
 
+
+// [NEW] 账号筛选查询 (filter 语法见 account_query.rs，如 "tag:region:eu health:healthy quota:claude-*<20")
+export interface AccountQuery {
+    filter?: string;
+    sort?: string;  // email / last_used / created_at / tier / health / quota:<model>，前缀 - 表示降序
+    page?: number;
+    pageSize?: number;
+}
+
+export interface AccountPage {
+    accounts: Account[];
+    total: number;
+    page?: number;
+    page_size?: number;
+}
+
+export interface AccountTagCount {
+    tag: string;
+    count: number;
+}
+
+export type BulkAccountAction =
+    | 'disable'
+    | 'enable'
+    | 'warmup'
+    | 'bind_proxy'
+    | 'unbind_proxy'
+    | 'delete'
+    | 'add_tags'
+    | 'remove_tags';
+
+export interface BulkAccountRequest {
+    action: BulkAccountAction;
+    filter?: string;
+    accountIds?: string[];
+    proxyId?: string;
+    tags?: string[];
+    reason?: string;
+    dryRun?: boolean;
+}
+
+export interface BulkAccountReport {
+    action: BulkAccountAction;
+    dry_run: boolean;
+    matched: number;
+    succeeded: number;
+    failed: number;
+    results: { account_id: string; email: string; error?: string }[];
+}
+
+// [NEW] 账号生命周期事件
+export type AccountEventKind =
+    | 'created'
+    | 'deleted'
+    | 'switched'
+    | 'disabled'
+    | 'enabled'
+    | 'proxy_disabled'
+    | 'proxy_enabled'
+    | 'forbidden'
+    | 'forbidden_cleared'
+    | 'validation_blocked'
+    | 'validation_cleared'
+    | 'device_profile_changed'
+    | 'proxy_bound'
+    | 'proxy_unbound'
+    | 'label_changed'
+    | 'tags_changed'
+    | 'provider_changed';
+
+export interface AccountEvent {
+    id: number;
+    account_id: string;
+    email?: string;
+    kind: AccountEventKind;
+    detail: string;
+    actor: string;  // 管理 API 调用者 / proxy / local
+    created_at: number;
+}
+
+export interface AccountEventQuery {
+    accountId?: string;
+    kind?: string;  // 逗号分隔
+    actor?: string;
+    since?: number;
+    until?: number;
+    before?: number;  // 上一页的 next_before
+    limit?: number;
+}
+
+export interface AccountEventPage {
+    events: AccountEvent[];
+    next_before?: number;
+}
+
+// [NEW] 通用上游 Provider 账号 (OpenAI / Anthropic 兼容端点)
+export type ProviderProtocol = 'openai' | 'anthropic';
+export type ProviderRouting = 'primary' | 'pooled' | 'fallback';
+
+export interface ProviderConfig {
+    base_url: string;
+    api_key: string;
+    protocol: ProviderProtocol;
+    models: string[];  // 支持通配符
+    model_mapping?: Record<string, string>;
+    routing: ProviderRouting;
+}
+
+export interface ProviderConfigInput {
+    baseUrl: string;
+    apiKey?: string;  // 更新时留空沿用原值
+    protocol?: ProviderProtocol;
+    models: string[];
+    modelMapping?: Record<string, string>;
+    routing?: ProviderRouting;
+}
+
+export interface ProviderAccountRequest extends ProviderConfigInput {
+    name: string;
+}
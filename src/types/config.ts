@@ -131,6 +131,38 @@ export interface CircuitBreakerConfig {
     backoff_steps: number[];
 }
 
+export type BackupComponent =
+    | 'accounts'
+    | 'proxy_logs'
+    | 'token_stats'
+    | 'security'
+    | 'user_tokens'
+    | 'admin_auth'
+    | 'runtime_state'
+    | 'warmup'
+    | 'config'
+    | 'vault'
+    | 'mitm_ca';
+
+export interface BackupConfig {
+    enabled: boolean;
+    interval_hours: number;
+    keep: number;
+    directory?: string;
+    components: BackupComponent[]; // 为空表示全部组件
+    encrypt: boolean; // 使用 ABV_BACKUP_PASSPHRASE 加密定时备份
+}
+
+export interface TokenRefreshConfig {
+    enabled: boolean;
+    margin_secs: number; // 过期前多少秒刷新
+    jitter_secs: number; // 额外随机提前量，分散刷新时间
+    check_interval_secs: number;
+    max_concurrency: number;
+    max_attempts: number; // 超过后交由请求时按需刷新
+    retry_backoff_secs: number; // 首次重试延迟，之后每次翻倍
+}
+
 export interface AppConfig {
     language: string;
     theme: string;
@@ -151,6 +183,8 @@ export interface AppConfig {
     quota_protection: QuotaProtectionConfig; // [NEW] 配额保护配置
     pinned_quota_models: PinnedQuotaModelsConfig; // [NEW] 配额关注列表
     circuit_breaker: CircuitBreakerConfig; // [NEW] 熔断器配置
+    backup?: BackupConfig; // [NEW] 定时数据备份配置
+    token_refresh?: TokenRefreshConfig; // [NEW] 后台 Token 预刷新配置
     proxy: ProxyConfig;
     cloudflared: CloudflaredConfig; // [NEW] Cloudflared 配置
 }
@@ -19,7 +19,19 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'warm_up_all_accounts': { url: '/api/accounts/warmup', method: 'POST' },
   'warm_up_account': { url: '/api/accounts/:accountId/warmup', method: 'POST' },
   'update_account_label': { url: '/api/accounts/:accountId/label', method: 'POST' },
+  'query_accounts': { url: '/api/accounts', method: 'GET' },
+  'update_account_tags': { url: '/api/accounts/:accountId/tags', method: 'POST' },
+  'list_account_tags': { url: '/api/accounts/tags', method: 'GET' },
+  'bulk_account_action': { url: '/api/accounts/bulk', method: 'POST' },
   'export_accounts': { url: '/api/accounts/export', method: 'POST' },
+  'import_accounts_legacy_layout': { url: '/api/accounts/legacy/import', method: 'POST' },
+  'export_accounts_legacy_layout': { url: '/api/accounts/legacy/export', method: 'POST' },
+  'get_account_health': { url: '/api/accounts/:accountId/health', method: 'GET' },
+  'get_pool_health': { url: '/api/accounts/health', method: 'GET' },
+  'list_account_events': { url: '/api/accounts/events', method: 'GET' },
+  'add_provider_account': { url: '/api/accounts/providers', method: 'POST' },
+  'update_provider_account': { url: '/api/accounts/:accountId/provider', method: 'POST' },
+  'bulk_import_accounts': { url: '/api/accounts/import/bulk', method: 'POST' },
   'bind_device_profile': { url: '/api/accounts/:accountId/bind-device', method: 'POST' },
   'get_device_profiles': { url: '/api/accounts/:accountId/device-profiles', method: 'GET' },
   'list_device_versions': { url: '/api/accounts/:accountId/device-versions', method: 'GET' },
@@ -89,6 +101,9 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
 
   // System
   'get_data_dir_path': { url: '/api/system/data-dir', method: 'GET' },
+  'list_backups': { url: '/api/system/backups', method: 'GET' },
+  'create_backup': { url: '/api/system/backups', method: 'POST' },
+  'restore_backup': { url: '/api/system/backups/restore', method: 'POST' },
   'get_update_settings': { url: '/api/system/updates/settings', method: 'GET' },
   'save_update_settings': { url: '/api/system/updates/save', method: 'POST' },
   'is_auto_launch_enabled': { url: '/api/system/autostart/status', method: 'GET' },